@@ -0,0 +1,312 @@
+use std::path::{Component, Path, PathBuf};
+
+#[cfg(feature = "walk")]
+use crate::token::{self, TokenTree as _};
+#[cfg(feature = "walk")]
+use crate::walk::{PruneCause, TreeIterator, Walk, WalkBehavior, WalkItem};
+use crate::{CandidatePath, Glob, MatchedText, Pattern as _};
+
+/// [`Glob`] that only matches paths beneath a directory.
+///
+/// `Anchored` interprets relative paths against its directory and never matches
+/// paths that are not beneath that directory, including paths that escape it
+/// via `..` components. Paths are resolved lexically, so symbolic links are not
+/// considered. If the [`Glob`] is unrooted, then it is matched against paths
+/// relative to the directory. If the [`Glob`] is [rooted][`Glob::has_root`],
+/// then it is matched against complete paths that are beneath the directory.
+///
+/// The directory should be absolute when matching absolute paths. Absolute
+/// paths never match an `Anchored` with a relative directory.
+///
+/// See [`Glob::anchored_at`].
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::Glob;
+///
+/// let glob = Glob::new("**/*.txt").unwrap().anchored_at("/srv/tenant");
+///
+/// assert!(glob.is_match("notes/todo.txt"));
+/// assert!(glob.is_match("/srv/tenant/notes/todo.txt"));
+///
+/// assert!(!glob.is_match("/srv/other/notes/todo.txt"));
+/// assert!(!glob.is_match("notes/../../other/todo.txt"));
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::anchored_at`]: crate::Glob::anchored_at
+/// [`Glob::has_root`]: crate::Glob::has_root
+#[derive(Clone, Debug)]
+pub struct Anchored<'t> {
+    glob: Glob<'t>,
+    directory: PathBuf,
+}
+
+impl<'t> Anchored<'t> {
+    pub(crate) fn new(glob: Glob<'t>, directory: impl AsRef<Path>) -> Self {
+        Anchored {
+            glob,
+            directory: normalize(directory.as_ref()),
+        }
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Anchored<'static> {
+        let Anchored { glob, directory } = self;
+        Anchored {
+            glob: glob.into_owned(),
+            directory,
+        }
+    }
+
+    /// Gets the [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn glob(&self) -> &Glob<'t> {
+        &self.glob
+    }
+
+    /// Gets the directory.
+    ///
+    /// The directory is normalized lexically, so it contains no `.` components
+    /// and no `..` components beyond those at the beginning of a relative
+    /// path.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Resolves a path against the directory.
+    ///
+    /// Relative paths are joined to the directory and the resolved path is
+    /// normalized lexically. Returns `None` if the resolved path is not
+    /// beneath the directory.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Option<PathBuf> {
+        let path = normalize(&self.directory.join(path));
+        path.starts_with(&self.directory).then(|| path)
+    }
+
+    /// Returns `true` if a path is beneath the directory and matches the
+    /// [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_match(&self, path: impl AsRef<Path>) -> bool {
+        self.resolve(path).map_or(false, |path| {
+            self.glob.is_match(CandidatePath::from(self.target(&path)))
+        })
+    }
+
+    /// Gets [matched text][`MatchedText`] in a path.
+    ///
+    /// The [`Glob`] is matched against the resolved path (see
+    /// [`Anchored::resolve`]), so the [matched text][`MatchedText`] may differ
+    /// from the given path. Returns `None` if the path is not beneath the
+    /// directory or does not match the [`Glob`].
+    ///
+    /// [`Anchored::resolve`]: crate::Anchored::resolve
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched(&self, path: impl AsRef<Path>) -> Option<MatchedText<'static>> {
+        let path = self.resolve(path)?;
+        let path = CandidatePath::from(self.target(&path));
+        self.glob.matched(&path).map(MatchedText::into_owned)
+    }
+
+    /// Gets an iterator over matching files beneath the directory.
+    ///
+    /// If the [`Glob`] is unrooted, then the directory tree is traversed as
+    /// with [`Glob::walk`]. If the [`Glob`] is rooted, then the traversal begins
+    /// at its invariant prefix if that prefix is beneath the directory and
+    /// otherwise reads the entire directory tree beneath the directory. No
+    /// files are read if the traversal would begin outside of the directory.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::walk`]: crate::Glob::walk
+    #[cfg(feature = "walk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+    pub fn walk(&self) -> AnchoredWalk<'_> {
+        self.walk_with_behavior(WalkBehavior::default())
+    }
+
+    /// Gets an iterator over matching files beneath the directory.
+    ///
+    /// This function is the same as [`Anchored::walk`], but it additionally
+    /// accepts a [`WalkBehavior`].
+    ///
+    /// [`Anchored::walk`]: crate::Anchored::walk
+    /// [`WalkBehavior`]: crate::WalkBehavior
+    #[cfg(feature = "walk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+    pub fn walk_with_behavior(&self, behavior: impl Into<WalkBehavior>) -> AnchoredWalk<'_> {
+        let prefix: PathBuf = token::invariant_text_prefix(self.glob.tree.as_ref().tokens()).into();
+        let root = normalize(&self.directory.join(prefix));
+        let (walk, filter) = if root.starts_with(&self.directory) {
+            (
+                Some(self.glob.walk_with_behavior(&self.directory, behavior)),
+                false,
+            )
+        }
+        else if self.glob.has_root() && self.directory.starts_with(&root) {
+            // The traversal cannot begin at the invariant prefix, so the
+            // entire directory tree is read and files are matched against
+            // their complete paths.
+            let walk = Glob::new("**")
+                .expect("failed to build tree glob")
+                .walk_with_behavior(&self.directory, behavior)
+                .into_owned();
+            (Some(walk), true)
+        }
+        else {
+            (None, false)
+        };
+        AnchoredWalk {
+            walk,
+            anchored: filter.then(|| self),
+        }
+    }
+
+    // Gets the path against which the glob is matched from a resolved path.
+    fn target<'p>(&self, path: &'p Path) -> &'p Path {
+        if self.glob.has_root() {
+            path
+        }
+        else {
+            path.strip_prefix(&self.directory)
+                .expect("resolved path is not beneath directory")
+        }
+    }
+}
+
+/// Iterator over files matching an [`Anchored`] glob beneath its directory.
+///
+/// `AnchoredWalk` is a `TreeIterator` and supports
+/// [`FileIterator::filter_tree`].
+///
+/// [`Anchored`]: crate::Anchored
+/// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+#[cfg(feature = "walk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+#[must_use]
+pub struct AnchoredWalk<'a> {
+    walk: Option<Walk<'a>>,
+    // The anchored glob against which entries are matched, if the traversal
+    // is not already confined to matching files.
+    anchored: Option<&'a Anchored<'a>>,
+}
+
+#[cfg(feature = "walk")]
+impl Iterator for AnchoredWalk<'_> {
+    type Item = WalkItem<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let walk = self.walk.as_mut()?;
+        loop {
+            match walk.next()? {
+                Ok(entry) => {
+                    if self
+                        .anchored
+                        .map_or(true, |anchored| anchored.is_match(entry.path()))
+                    {
+                        return Some(Ok(entry));
+                    }
+                },
+                error => {
+                    return Some(error);
+                },
+            }
+        }
+    }
+}
+
+#[cfg(feature = "walk")]
+impl TreeIterator for AnchoredWalk<'_> {
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        if let Some(walk) = self.walk.as_mut() {
+            walk.skip_tree_with(cause);
+        }
+    }
+}
+
+// Normalizes a path lexically by removing `.` components and resolving `..`
+// components against preceding components. `..` components that cannot be
+// resolved are retained in relative paths and discarded in rooted paths.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                },
+                Some(Component::RootDir | Component::Prefix(_)) => {},
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::anchor;
+    use crate::Glob;
+
+    #[test]
+    fn normalize_path() {
+        assert_eq!(anchor::normalize(Path::new("a/./b/../c")), Path::new("a/c"));
+        assert_eq!(anchor::normalize(Path::new("../a/..")), Path::new(".."));
+        assert_eq!(anchor::normalize(Path::new("/../a")), Path::new("/a"));
+    }
+
+    #[test]
+    fn match_anchored_glob() {
+        let glob = Glob::new("src/**/*.rs").unwrap().anchored_at("/srv/a");
+
+        assert!(glob.is_match("src/lib.rs"));
+        assert!(glob.is_match("./src/../src/lib.rs"));
+        assert!(glob.is_match("/srv/a/src/lib.rs"));
+        assert!(glob.is_match("/srv/b/../a/src/lib.rs"));
+
+        assert!(!glob.is_match("/srv/b/src/lib.rs"));
+        assert!(!glob.is_match("../b/src/lib.rs"));
+        assert!(!glob.is_match("/srv/ab/src/lib.rs"));
+        assert!(!glob.is_match("/src/lib.rs"));
+    }
+
+    #[test]
+    fn match_anchored_glob_with_root() {
+        let glob = Glob::new("/srv/*/src/*.rs").unwrap().anchored_at("/srv/a");
+
+        assert!(glob.is_match("src/lib.rs"));
+        assert!(glob.is_match("/srv/a/src/lib.rs"));
+
+        assert!(!glob.is_match("/srv/b/src/lib.rs"));
+        assert!(!glob.is_match("../b/src/lib.rs"));
+    }
+
+    #[test]
+    fn match_anchored_glob_with_relative_directory() {
+        let glob = Glob::new("*.rs").unwrap().anchored_at("./src");
+
+        assert!(glob.is_match("lib.rs"));
+        assert!(glob.is_match("../src/lib.rs"));
+
+        assert!(!glob.is_match("src/lib.rs"));
+        assert!(!glob.is_match("/src/lib.rs"));
+    }
+
+    #[test]
+    fn matched_anchored_glob() {
+        let glob = Glob::new("src/{*}.rs").unwrap().anchored_at("/srv/a");
+
+        let matched = glob.matched("/srv/a/src/lib.rs").unwrap();
+        assert_eq!(matched.complete(), "src/lib.rs");
+        assert_eq!(matched.get(1), Some("lib"));
+        assert!(glob.matched("../b/src/lib.rs").is_none());
+    }
+}
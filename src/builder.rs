@@ -0,0 +1,151 @@
+use crate::expand::{self, Expansions};
+use crate::{BuildError, CaseFolding, Glob};
+
+/// Configures and builds [`Glob`]s from glob expressions.
+///
+/// A `GlobBuilder` enables optional behaviors that are not part of glob
+/// expression syntax, such as expanding a leading `~` into a home directory or
+/// references to environment variables into their values or folding the case
+/// of literals per the conventions of a locale. These behaviors are applied to
+/// the glob expression as it is parsed, so errors refer to the glob expression
+/// as it was given rather than as it was expanded.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::{GlobBuilder, Pattern};
+///
+/// let glob = GlobBuilder::new()
+///     .expand_home(true)
+///     .build("~/.config/**/*.toml")
+///     .unwrap();
+/// assert!(glob.has_root());
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[derive(Clone, Debug, Default)]
+pub struct GlobBuilder {
+    expansions: Expansions,
+    folding: CaseFolding,
+}
+
+impl GlobBuilder {
+    /// Constructs a `GlobBuilder` with expansions disabled and simple case
+    /// folding.
+    pub fn new() -> Self {
+        GlobBuilder::default()
+    }
+
+    /// Expands a leading `~` into the home directory.
+    ///
+    /// When enabled, a glob expression that begins with `~` followed by a
+    /// separator or the end of the expression is expanded into the escaped
+    /// path of the home directory of the current user. On Unix, a glob
+    /// expression that begins with `~user` is expanded into the home directory
+    /// of `user`. A `~` elsewhere in a glob expression is never expanded and a
+    /// leading `~` can be matched literally with a character class, as in
+    /// `[~]/`.
+    ///
+    /// The home directory is matched literally and is an invariant rooted
+    /// prefix, so it is part of the [invariant prefix][`Glob::partition`] when
+    /// [walking a directory tree][`Glob::walk`]. On Unix, the home directory
+    /// is read from `$HOME`. On Windows, it is read from `%USERPROFILE%`.
+    ///
+    /// This is disabled by default.
+    ///
+    /// [`Glob::partition`]: crate::Glob::partition
+    /// [`Glob::walk`]: crate::Glob::walk
+    pub fn expand_home(mut self, is_home_expanded: bool) -> Self {
+        self.expansions.is_home_expanded = is_home_expanded;
+        self
+    }
+
+    /// Expands references to environment variables into their values.
+    ///
+    /// When enabled, references of the form `${NAME}` in a glob expression are
+    /// expanded into the escaped values of the environment variables `NAME`,
+    /// so values are always matched literally. Names consist of ASCII
+    /// alphanumeric characters and `_` and must not begin with a digit. Any
+    /// separators in values are replaced by `/` and values that are not valid
+    /// UTF-8 are converted lossily in the same way as [`CandidatePath`].
+    ///
+    /// `\$` is a literal `$` that never begins a reference. Other text that
+    /// begins with `$` but is not a reference, such as `${a,b}`, is not
+    /// expanded and is interpreted as glob expression text.
+    ///
+    /// This is disabled by default.
+    ///
+    /// [`CandidatePath`]: crate::CandidatePath
+    pub fn expand_variables(mut self, is_variable_expanded: bool) -> Self {
+        self.expansions.is_variable_expanded = is_variable_expanded;
+        self
+    }
+
+    /// Sets the case folding of case-insensitive literals.
+    ///
+    /// Case folding only applies to literals that are matched
+    /// case-insensitively, such as literals following the `(?i)` flag or all
+    /// literals on platforms where paths are case-insensitive. With
+    /// [`CaseFolding::Turkic`], the dotted `i` and `İ` and the dotless `ı` and
+    /// `I` are distinct pairs, so `(?i)dir` matches `DİR` but not `DIR`.
+    ///
+    /// Case folding is not part of the glob expression, so it is not preserved
+    /// by [`Display`] nor serialization. It is preserved by
+    /// [`Glob::partition`] and [`Glob::join`].
+    ///
+    /// This is [`CaseFolding::Simple`] by default.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{CaseFolding, GlobBuilder, Pattern};
+    ///
+    /// let glob = GlobBuilder::new()
+    ///     .case_folding(CaseFolding::Turkic)
+    ///     .build("(?i)dir/*.txt")
+    ///     .unwrap();
+    /// assert!(glob.is_match("DİR/a.txt"));
+    /// assert!(!glob.is_match("DIR/a.txt"));
+    /// ```
+    ///
+    /// [`CaseFolding::Simple`]: crate::CaseFolding::Simple
+    /// [`CaseFolding::Turkic`]: crate::CaseFolding::Turkic
+    /// [`Display`]: std::fmt::Display
+    /// [`Glob::join`]: crate::Glob::join
+    /// [`Glob::partition`]: crate::Glob::partition
+    pub fn case_folding(mut self, folding: CaseFolding) -> Self {
+        self.folding = folding;
+        self
+    }
+
+    /// Builds a [`Glob`] from a glob expression.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the glob expression cannot be expanded, such as if
+    /// the home directory cannot be resolved or an environment variable is not
+    /// set, or if it fails to build. The [locations][`BuildError::locations`]
+    /// of the error refer to `expression` and not the expanded glob
+    /// expression.
+    ///
+    /// [`BuildError::locations`]: crate::BuildError::locations
+    /// [`Glob`]: crate::Glob
+    pub fn build<'t>(&self, expression: &'t str) -> Result<Glob<'t>, BuildError> {
+        let expanded = expand::expand(expression, self.expansions)?;
+        if expanded.is_expanded() {
+            self.build_expanded(expanded.expression())
+                .map(Glob::into_owned)
+                .map_err(|error| {
+                    error.map_spans(expression, &|span| expanded.original_span(span))
+                })
+        }
+        else {
+            self.build_expanded(expression)
+        }
+    }
+
+    fn build_expanded<'t>(&self, expression: &'t str) -> Result<Glob<'t>, BuildError> {
+        let tree = crate::parse_and_check_with_folding(expression, self.folding)?;
+        Ok(Glob::from_tree(tree)?)
+    }
+}
@@ -0,0 +1,217 @@
+//! Caching of compiled [`Glob`]s.
+//!
+//! [`Glob`]: crate::Glob
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+use crate::{BuildError, Glob};
+
+#[derive(Debug, Default)]
+struct State {
+    globs: HashMap<Arc<str>, (Arc<Glob<'static>>, u64)>,
+    // Expressions ordered by their most recent use. The first expression is
+    // the least recently used.
+    uses: BTreeMap<u64, Arc<str>>,
+    clock: u64,
+}
+
+impl State {
+    fn get(&mut self, expression: &str) -> Option<Arc<Glob<'static>>> {
+        let clock = self.tick();
+        let (glob, used) = self.globs.get_mut(expression)?;
+        let expression = self
+            .uses
+            .remove(used)
+            .expect("cached glob has no recorded use");
+        *used = clock;
+        self.uses.insert(clock, expression);
+        Some(glob.clone())
+    }
+
+    fn insert(&mut self, expression: &str, glob: Arc<Glob<'static>>) {
+        let clock = self.tick();
+        let expression: Arc<str> = expression.into();
+        if let Some((_, used)) = self.globs.insert(expression.clone(), (glob, clock)) {
+            self.uses.remove(&used);
+        }
+        self.uses.insert(clock, expression);
+    }
+
+    fn evict(&mut self, capacity: usize) {
+        while self.globs.len() > capacity {
+            let expression = self
+                .uses
+                .values()
+                .next()
+                .cloned()
+                .expect("cached glob has no recorded use");
+            let (_, used) = self
+                .globs
+                .remove(&expression)
+                .expect("recorded use has no cached glob");
+            self.uses.remove(&used);
+        }
+    }
+
+    fn tick(&mut self) -> u64 {
+        self.clock += 1;
+        self.clock
+    }
+}
+
+/// Size-bounded cache of compiled [`Glob`]s keyed by their glob expressions.
+///
+/// `GlobCache` maps glob expressions to shared compiled [`Glob`]s, so that
+/// expressions that are received repeatedly, such as patterns in requests or
+/// configuration that is reloaded, are only compiled once. When the cache
+/// exceeds its capacity, the least recently used [`Glob`] is evicted.
+///
+/// `GlobCache` is synchronized and can be shared between threads. Expressions
+/// are compiled without blocking other threads, so an expression may be
+/// compiled more than once if it is requested concurrently. Expressions that
+/// fail to build are not cached.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::sync::Arc;
+/// use wax::cache::GlobCache;
+/// use wax::Pattern;
+///
+/// let cache = GlobCache::new(64);
+/// let glob = cache.get("src/**/*.rs").unwrap();
+/// assert!(glob.is_match("src/lib.rs"));
+/// assert!(Arc::ptr_eq(&glob, &cache.get("src/**/*.rs").unwrap()));
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[derive(Debug)]
+pub struct GlobCache {
+    capacity: usize,
+    state: Mutex<State>,
+}
+
+impl GlobCache {
+    /// The capacity of a `GlobCache` constructed via [`Default`].
+    ///
+    /// [`Default`]: std::default::Default
+    pub const DEFAULT_CAPACITY: usize = 256;
+
+    /// Constructs a `GlobCache` that holds at most `capacity` [`Glob`]s.
+    ///
+    /// A `GlobCache` with no capacity never caches [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn new(capacity: usize) -> Self {
+        GlobCache {
+            capacity,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Gets the compiled [`Glob`] of a glob expression.
+    ///
+    /// If the expression is not in the cache, then it is compiled and
+    /// inserted into the cache.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the expression is not in the cache and fails to
+    /// build. See [`Glob::new`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::new`]: crate::Glob::new
+    pub fn get(&self, expression: &str) -> Result<Arc<Glob<'static>>, BuildError> {
+        if let Some(glob) = self.lock().get(expression) {
+            return Ok(glob);
+        }
+        // Compile the glob without holding the lock.
+        let glob = Arc::new(Glob::new(expression)?.into_owned());
+        if self.capacity > 0 {
+            let mut state = self.lock();
+            state.insert(expression, glob.clone());
+            state.evict(self.capacity);
+        }
+        Ok(glob)
+    }
+
+    /// Removes all [`Glob`]s from the cache.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn clear(&self) {
+        *self.lock() = State::default();
+    }
+
+    /// Gets the maximum number of [`Glob`]s in the cache.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gets the number of [`Glob`]s in the cache.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn len(&self) -> usize {
+        self.lock().globs.len()
+    }
+
+    /// Returns `true` if the cache has no [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        // The state is consistent between each operation, so it is not
+        // necessary to propagate poisoning.
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl Default for GlobCache {
+    fn default() -> Self {
+        GlobCache::new(GlobCache::DEFAULT_CAPACITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use crate::cache::GlobCache;
+
+    #[test]
+    fn cache_shares_globs() {
+        let cache = GlobCache::new(2);
+        let glob = cache.get("**/*.rs").unwrap();
+        assert!(Arc::ptr_eq(&glob, &cache.get("**/*.rs").unwrap()));
+        assert_eq!(cache.len(), 1);
+
+        assert!(cache.get("a/{b").is_err());
+        assert_eq!(cache.len(), 1);
+
+        cache.clear();
+        assert!(cache.is_empty());
+        assert!(!Arc::ptr_eq(&glob, &cache.get("**/*.rs").unwrap()));
+    }
+
+    #[test]
+    fn cache_evicts_least_recently_used_globs() {
+        let cache = GlobCache::new(2);
+        let a = cache.get("a/*").unwrap();
+        let b = cache.get("b/*").unwrap();
+        // Use `a` so that `b` is the least recently used glob.
+        cache.get("a/*").unwrap();
+        cache.get("c/*").unwrap();
+        assert_eq!(cache.len(), 2);
+        assert!(Arc::ptr_eq(&a, &cache.get("a/*").unwrap()));
+        assert!(!Arc::ptr_eq(&b, &cache.get("b/*").unwrap()));
+
+        let cache = GlobCache::new(0);
+        cache.get("a/*").unwrap();
+        assert!(cache.is_empty());
+    }
+}
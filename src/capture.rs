@@ -1,6 +1,7 @@
 use regex::Captures as BorrowedText;
 use std::str;
 
+use crate::diagnostics::Span;
 use crate::CandidatePath;
 
 #[derive(Clone, Debug)]
@@ -20,6 +21,17 @@ impl OwnedText {
                 .and_then(|range| range.map(|range| &self.matched[range.0..range.1]))
         }
     }
+
+    pub fn span(&self, index: usize) -> Option<Span> {
+        if index == 0 {
+            Some((0, self.matched.len()))
+        }
+        else {
+            self.ranges
+                .get(index - 1)
+                .and_then(|range| range.map(|(start, end)| (start, end - start)))
+        }
+    }
 }
 
 impl<'t> From<BorrowedText<'t>> for OwnedText {
@@ -40,9 +52,21 @@ impl<'m, 't> From<&'m BorrowedText<'t>> for OwnedText {
     }
 }
 
+impl From<&'_ str> for OwnedText {
+    fn from(matched: &str) -> Self {
+        OwnedText {
+            matched: matched.into(),
+            ranges: vec![],
+        }
+    }
+}
+
 #[derive(Debug)]
 enum MaybeOwnedText<'t> {
     Borrowed(BorrowedText<'t>),
+    // Text matched by a pattern without captures, such as a literal `Glob`.
+    // Such text is not matched by a regular expression.
+    Complete(&'t str),
     Owned(OwnedText),
 }
 
@@ -50,6 +74,7 @@ impl<'t> MaybeOwnedText<'t> {
     fn into_owned(self) -> MaybeOwnedText<'static> {
         match self {
             MaybeOwnedText::Borrowed(borrowed) => OwnedText::from(borrowed).into(),
+            MaybeOwnedText::Complete(complete) => OwnedText::from(complete).into(),
             MaybeOwnedText::Owned(owned) => owned.into(),
         }
     }
@@ -59,6 +84,7 @@ impl<'t> MaybeOwnedText<'t> {
     fn to_owned(&self) -> MaybeOwnedText<'static> {
         match self {
             MaybeOwnedText::Borrowed(ref borrowed) => OwnedText::from(borrowed).into(),
+            MaybeOwnedText::Complete(complete) => OwnedText::from(*complete).into(),
             MaybeOwnedText::Owned(ref owned) => owned.clone().into(),
         }
     }
@@ -115,6 +141,12 @@ pub struct MatchedText<'t> {
 }
 
 impl<'t> MatchedText<'t> {
+    pub(crate) fn from_complete(text: &'t str) -> Self {
+        MatchedText {
+            inner: MaybeOwnedText::Complete(text),
+        }
+    }
+
     /// Clones any borrowed data into an owning instance.
     pub fn into_owned(self) -> MatchedText<'static> {
         let MatchedText { inner } = self;
@@ -172,13 +204,76 @@ impl<'t> MatchedText<'t> {
             MaybeOwnedText::Borrowed(ref captures) => {
                 captures.get(index).map(|capture| capture.as_str())
             },
+            MaybeOwnedText::Complete(complete) => (index == 0).then(|| complete),
             MaybeOwnedText::Owned(ref captures) => captures.get(index),
         }
     }
 
+    /// Gets the [`Span`] of a capture at the given index in the complete text.
+    ///
+    /// Captures are indexed as in [`get`], so the [`Span`] at index zero covers
+    /// the complete text. The [`Span`] locates the text of the capture in the
+    /// complete text, such that `&complete[start..][..n]` is the same as the
+    /// output of [`get`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{CandidatePath, Glob, Pattern};
+    ///
+    /// let glob = Glob::new("src/**/*.rs").unwrap();
+    /// let candidate = CandidatePath::from("src/token/parse.rs");
+    /// let matched = glob.matched(&candidate).unwrap();
+    ///
+    /// assert_eq!(matched.span(2), Some((10, 5)));
+    /// ```
+    ///
+    /// [`get`]: crate::MatchedText::get
+    /// [`Span`]: crate::Span
+    pub fn span(&self, index: usize) -> Option<Span> {
+        match self.inner {
+            MaybeOwnedText::Borrowed(ref captures) => {
+                let start = captures.get(0).map_or(0, |capture| capture.start());
+                captures
+                    .get(index)
+                    .map(|capture| (capture.start() - start, capture.as_str().len()))
+            },
+            MaybeOwnedText::Complete(complete) => (index == 0).then(|| (0, complete.len())),
+            MaybeOwnedText::Owned(ref captures) => captures.span(index),
+        }
+    }
+
     pub fn to_candidate_path(&self) -> CandidatePath {
         CandidatePath::from(self.complete())
     }
+
+    // Gets matched text in which the complete text is replaced by `text` and
+    // captures are translated by `offset`. The complete text must appear in
+    // `text` at `offset`.
+    #[cfg(feature = "walk")]
+    pub(crate) fn to_translated(&self, text: &str, offset: usize) -> MatchedText<'static> {
+        let ranges = (1..)
+            .map(|index| self.span(index))
+            .take(self.len().saturating_sub(1))
+            .map(|span| span.map(|(start, n)| (offset + start, offset + start + n)))
+            .collect();
+        OwnedText {
+            matched: text.into(),
+            ranges,
+        }
+        .into()
+    }
+
+    // Gets the number of captures, including the implicit capture of the
+    // complete text.
+    #[cfg(feature = "walk")]
+    fn len(&self) -> usize {
+        match self.inner {
+            MaybeOwnedText::Borrowed(ref captures) => captures.len(),
+            MaybeOwnedText::Complete(_) => 1,
+            MaybeOwnedText::Owned(ref captures) => captures.ranges.len() + 1,
+        }
+    }
 }
 
 // TODO: This probably shouldn't be part of the public API.
@@ -0,0 +1,107 @@
+use std::path;
+
+/// Component of a glob expression constructed by [`Glob::from_components`].
+///
+/// [`Glob::from_components`]: crate::Glob::from_components
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum GlobComponent<'a> {
+    /// Literal text, such as the name of a directory.
+    ///
+    /// The text is escaped and so matches itself exactly. Separators in the
+    /// text delimit components, so `Literal("src/token")` is equivalent to
+    /// `Literal("src")` followed by `Literal("token")`. A leading separator in
+    /// the first component roots the glob.
+    Literal(&'a str),
+    /// Matches any single component (`*`).
+    AnyComponent,
+    /// Matches zero or more components (`**`).
+    Tree,
+    /// Matches any file name with the given extension (`*.ext`).
+    ///
+    /// The extension is escaped and a single leading `.` is ignored, so
+    /// `Extension("rs")` and `Extension(".rs")` are equivalent. As with
+    /// literals, separators in the extension delimit components.
+    Extension(&'a str),
+}
+
+// Constructs a glob expression from components. Components are delimited by
+// separators and the text of literals and extensions is escaped.
+pub fn expression<'a, I>(components: I) -> String
+where
+    I: IntoIterator<Item = GlobComponent<'a>>,
+{
+    let mut expression = String::new();
+    let mut is_empty = true;
+    let mut push = |expression: &mut String, text: &str| {
+        if !is_empty {
+            expression.push('/');
+        }
+        is_empty = false;
+        expression.push_str(text);
+    };
+    for (index, component) in components.into_iter().enumerate() {
+        match component {
+            GlobComponent::Literal(text) => {
+                if index == 0 && text.starts_with(path::is_separator) {
+                    expression.push('/');
+                }
+                for text in text
+                    .split(path::is_separator)
+                    .filter(|text| !text.is_empty())
+                {
+                    push(&mut expression, &crate::escape(text));
+                }
+            },
+            GlobComponent::AnyComponent => push(&mut expression, "*"),
+            GlobComponent::Tree => push(&mut expression, "**"),
+            GlobComponent::Extension(extension) => {
+                let extension = extension.strip_prefix('.').unwrap_or(extension);
+                let mut texts = extension.split(path::is_separator);
+                push(
+                    &mut expression,
+                    &format!("*.{}", crate::escape(texts.next().unwrap_or(""))),
+                );
+                for text in texts.filter(|text| !text.is_empty()) {
+                    push(&mut expression, &crate::escape(text));
+                }
+            },
+        }
+    }
+    expression
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{self, GlobComponent};
+    use crate::{Glob, Pattern};
+
+    #[test]
+    fn expression_from_components() {
+        use GlobComponent::{AnyComponent, Extension, Literal, Tree};
+
+        assert_eq!(
+            component::expression([Literal("src"), Tree, Extension("rs")]),
+            "src/**/*.rs",
+        );
+        assert_eq!(
+            component::expression([Literal("/usr//local/"), AnyComponent, Extension(".so")]),
+            "/usr/local/*/*.so",
+        );
+        assert_eq!(
+            component::expression([Literal("{a,b}"), Literal("(c)")]),
+            "\\{a\\,b\\}/\\(c\\)",
+        );
+        assert_eq!(component::expression([Tree, Literal("/a")]), "**/a");
+        assert_eq!(component::expression([]), "");
+    }
+
+    #[test]
+    fn build_glob_from_components() {
+        use GlobComponent::{Extension, Literal, Tree};
+
+        let glob = Glob::from_components([Literal("a[1]"), Tree, Extension("{x}")]).unwrap();
+        assert!(glob.is_match("a[1]/b/c.{x}"));
+        assert!(!glob.is_match("a1/b/c.x"));
+        assert!(Glob::from_components([Tree, Tree]).is_err());
+    }
+}
@@ -1,12 +1,110 @@
 #![cfg(feature = "miette")]
 
-use miette::{Diagnostic, SourceSpan};
+use miette::{Diagnostic, LabeledSpan, Severity, SourceCode, SourceSpan};
+#[cfg(feature = "serde")]
+use serde::{Serialize, Serializer};
 use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
 use tardar::BoxedDiagnostic;
 use thiserror::Error;
 
 use crate::diagnostics::SpanExt as _;
-use crate::token::{self, TokenKind, TokenTree, Tokenized};
+use crate::token::{self, Archetype, Class, TokenKind, TokenTree, Tokenized};
+use crate::{Glob, StrExt as _};
+
+// Estimated cost at which glob expressions are diagnosed as expensive. See
+// `token::cost`.
+const EXPENSIVE_COST: usize = 10_000;
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::invalid_character),
+    severity(warning),
+    help("this literal cannot match any file on {platform}")
+)]
+#[error("`{}` is not allowed in file names on {platform}", .character.escape_debug())]
+pub struct InvalidCharacterWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    character: char,
+    platform: &'static str,
+    #[label("here")]
+    span: SourceSpan,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::trailing_character),
+    severity(warning),
+    help("trailing characters are removed from file names on Windows")
+)]
+#[error("file names cannot end with `{}` on Windows", .character.escape_debug())]
+pub struct TrailingCharacterWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    character: char,
+    #[label("here")]
+    span: SourceSpan,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::nonportable_case),
+    severity(warning),
+    help("the case sensitivity of this literal is set by a flag")
+)]
+#[error("`{literal}` is partitioned differently on Unix and Windows")]
+pub struct NonPortableCaseWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    literal: Cow<'t, str>,
+    #[label("here")]
+    span: SourceSpan,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::nonportable_separator),
+    severity(warning),
+    help("`\\` is a separator on Windows and is never matched by character classes")
+)]
+#[error("character class matches `\\` on Unix but not on Windows")]
+pub struct NonPortableSeparatorWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    #[label("here")]
+    span: SourceSpan,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::nonportable_drive),
+    severity(warning),
+    help("this is a literal component on Unix")
+)]
+#[error("`{prefix}` resembles a drive prefix on Windows")]
+pub struct NonPortableDriveWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    prefix: String,
+    #[label("here")]
+    span: SourceSpan,
+}
+
+#[derive(Clone, Debug, Diagnostic, Error)]
+#[diagnostic(
+    code(wax::glob::expensive_pattern),
+    severity(warning),
+    help("bounded repetitions and their alternatives are expanded when compiled")
+)]
+#[error("glob expression has an estimated cost of {cost}")]
+pub struct ExpensivePatternWarning<'t> {
+    #[source_code]
+    expression: Cow<'t, str>,
+    cost: usize,
+    #[label("most expensive here")]
+    span: SourceSpan,
+}
 
 #[derive(Clone, Debug, Diagnostic, Error)]
 #[diagnostic(code(wax::glob::semantic_literal), severity(warning))]
@@ -29,6 +127,282 @@ pub struct TerminatingSeparatorWarning<'t> {
     span: SourceSpan,
 }
 
+/// Owned record of a diagnostic of a glob expression.
+///
+/// `DiagnosticRecord`s are the related diagnostics of a [`DiagnosticReport`]
+/// and describe an error or warning with its code, message, help, and labeled
+/// spans. The source code of a record is always the glob expression of its
+/// report.
+///
+/// When the `serde` feature is enabled, records are serialized as a map with
+/// the following entries:
+///
+/// | Key        | Value                                                    |
+/// |------------|----------------------------------------------------------|
+/// | `code`     | Code of the diagnostic, such as `wax::glob::parse`.      |
+/// | `severity` | `"error"`, `"warning"`, or `"advice"`.                   |
+/// | `message`  | Description of the diagnostic.                           |
+/// | `help`     | Suggestion for resolving the diagnostic or `null`.       |
+/// | `labels`   | Sequence of labeled spans (see below).                   |
+/// | `related`  | Sequence of related `DiagnosticRecord`s.                 |
+///
+/// Labeled spans are serialized as a map with a `label` (or `null`), an
+/// `offset`, and a `length`, where the offset and length are measured in bytes
+/// as with [`Span`].
+///
+/// [`DiagnosticReport`]: crate::DiagnosticReport
+/// [`Span`]: crate::Span
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DiagnosticRecord {
+    code: Option<String>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_severity"))]
+    severity: Option<Severity>,
+    message: String,
+    help: Option<String>,
+    #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_labels"))]
+    labels: Vec<LabeledSpan>,
+    related: Vec<DiagnosticRecord>,
+}
+
+impl DiagnosticRecord {
+    fn from_diagnostic(diagnostic: &dyn Diagnostic) -> Self {
+        DiagnosticRecord {
+            code: diagnostic.code().map(|code| code.to_string()),
+            severity: diagnostic.severity(),
+            message: diagnostic.to_string(),
+            help: diagnostic.help().map(|help| help.to_string()),
+            labels: diagnostic
+                .labels()
+                .map(Iterator::collect)
+                .unwrap_or_default(),
+            related: diagnostic
+                .related()
+                .map(|related| related.map(DiagnosticRecord::from_diagnostic).collect())
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Returns `true` if the diagnostic is an error.
+    ///
+    /// Diagnostics with no severity are errors.
+    pub fn is_error(&self) -> bool {
+        matches!(self.severity, Some(Severity::Error) | None)
+    }
+}
+
+impl Diagnostic for DiagnosticRecord {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        self.code
+            .as_ref()
+            .map(|code| Box::new(code) as Box<dyn Display>)
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+
+    fn help<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        self.help
+            .as_ref()
+            .map(|help| Box::new(help) as Box<dyn Display>)
+    }
+
+    fn labels(&self) -> Option<Box<dyn '_ + Iterator<Item = LabeledSpan>>> {
+        (!self.labels.is_empty()).then(|| Box::new(self.labels.iter().cloned()) as Box<_>)
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn 'a + Iterator<Item = &'a dyn Diagnostic>>> {
+        (!self.related.is_empty()).then(|| {
+            Box::new(
+                self.related
+                    .iter()
+                    .map(|related| related as &dyn Diagnostic),
+            ) as Box<_>
+        })
+    }
+}
+
+impl Display for DiagnosticRecord {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for DiagnosticRecord {}
+
+/// Diagnostic that aggregates the errors and warnings of a glob expression.
+///
+/// A `DiagnosticReport` is an owned [`Diagnostic`] with the glob expression as
+/// its source code and a [`DiagnosticRecord`] for each error and warning as
+/// its related diagnostics, so all diagnostics for a glob expression can be
+/// rendered as one block. The report is an error if any of its records are
+/// errors and is otherwise a warning.
+///
+/// When the `serde` feature is enabled, reports are serialized as a map with
+/// an `expression` and a sequence of `diagnostics` (see [`DiagnosticRecord`]).
+/// This can be used to integrate the validation of glob expressions with code
+/// review and continuous integration tools without parsing rendered
+/// diagnostics.
+///
+/// See [`Glob::reported`].
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::DiagnosticReport;
+///
+/// let report = DiagnosticReport::new("../src/**/**/*.rs");
+/// assert!(report.is_error());
+/// for record in report.records() {
+///     eprintln!("{}", record);
+/// }
+/// ```
+///
+/// [`Diagnostic`]: miette::Diagnostic
+/// [`DiagnosticRecord`]: crate::DiagnosticRecord
+/// [`Glob::reported`]: crate::Glob::reported
+#[derive(Clone, Debug)]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+#[cfg_attr(feature = "serde", derive(Serialize))]
+pub struct DiagnosticReport {
+    expression: String,
+    #[cfg_attr(feature = "serde", serde(rename = "diagnostics"))]
+    records: Vec<DiagnosticRecord>,
+}
+
+impl DiagnosticReport {
+    /// Builds a glob expression and reports all of its errors and warnings.
+    pub fn new(expression: &str) -> Self {
+        match Glob::diagnosed(expression) {
+            Ok((_, diagnostics)) => DiagnosticReport::from_diagnostics(expression, &diagnostics),
+            Err(diagnostics) => DiagnosticReport::from_diagnostics(expression, &diagnostics),
+        }
+    }
+
+    pub(crate) fn from_diagnostics<'i, 'd>(
+        expression: &str,
+        diagnostics: impl IntoIterator<Item = &'i BoxedDiagnostic<'d>>,
+    ) -> Self
+    where
+        'd: 'i,
+    {
+        DiagnosticReport {
+            expression: expression.into(),
+            records: diagnostics
+                .into_iter()
+                .map(|diagnostic| DiagnosticRecord::from_diagnostic(diagnostic.as_ref()))
+                .collect(),
+        }
+    }
+
+    /// Gets the glob expression.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Gets the [`DiagnosticRecord`]s of the errors and warnings of the glob
+    /// expression.
+    ///
+    /// [`DiagnosticRecord`]: crate::DiagnosticRecord
+    pub fn records(&self) -> &[DiagnosticRecord] {
+        &self.records
+    }
+
+    /// Returns `true` if the glob expression fails to build.
+    pub fn is_error(&self) -> bool {
+        self.errors() > 0
+    }
+
+    fn errors(&self) -> usize {
+        self.records
+            .iter()
+            .filter(|record| record.is_error())
+            .count()
+    }
+}
+
+impl Diagnostic for DiagnosticReport {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        Some(Box::new("wax::glob::report"))
+    }
+
+    fn severity(&self) -> Option<Severity> {
+        Some(if self.is_error() {
+            Severity::Error
+        }
+        else {
+            Severity::Warning
+        })
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.expression)
+    }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn 'a + Iterator<Item = &'a dyn Diagnostic>>> {
+        Some(Box::new(
+            self.records.iter().map(|record| record as &dyn Diagnostic),
+        ))
+    }
+}
+
+impl Display for DiagnosticReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        fn count(n: usize, noun: &str) -> String {
+            format!("{} {}{}", n, noun, if n == 1 { "" } else { "s" })
+        }
+
+        let errors = self.errors();
+        let warnings = self.records.len() - errors;
+        write!(f, "glob expression `{}` has ", self.expression)?;
+        match (errors, warnings) {
+            (0, _) => write!(f, "{}", count(warnings, "warning")),
+            (_, 0) => write!(f, "{}", count(errors, "error")),
+            _ => write!(
+                f,
+                "{} and {}",
+                count(errors, "error"),
+                count(warnings, "warning"),
+            ),
+        }
+    }
+}
+
+impl std::error::Error for DiagnosticReport {}
+
+#[cfg(feature = "serde")]
+fn serialize_severity<S>(severity: &Option<Severity>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(match severity {
+        Some(Severity::Error) | None => "error",
+        Some(Severity::Warning) => "warning",
+        Some(Severity::Advice) => "advice",
+    })
+}
+
+#[cfg(feature = "serde")]
+fn serialize_labels<S>(labels: &[LabeledSpan], serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    #[derive(Serialize)]
+    struct Label<'a> {
+        label: Option<&'a str>,
+        offset: usize,
+        length: usize,
+    }
+
+    serializer.collect_seq(labels.iter().map(|label| Label {
+        label: label.label(),
+        offset: label.offset(),
+        length: label.len(),
+    }))
+}
+
 pub fn diagnose<'i, 't>(
     tokenized: &'i Tokenized<'t>,
 ) -> impl 'i + Iterator<Item = BoxedDiagnostic<'t>> {
@@ -50,6 +424,47 @@ pub fn diagnose<'i, 't>(
                     }) as BoxedDiagnostic
                 }),
         )
+        .chain(tokenized.walk().filter_map(|(_, token)| {
+            // Report only the first invalid character in each literal.
+            let character = match token.kind() {
+                TokenKind::Literal(ref literal) => literal
+                    .text()
+                    .chars()
+                    .find(|&x| invalid_platform(x).is_some())?,
+                _ => {
+                    return None;
+                },
+            };
+            Some(Box::new(InvalidCharacterWarning {
+                expression: tokenized.expression().clone(),
+                character,
+                platform: invalid_platform(character).expect("no platform for character"),
+                span: (*token.annotation()).into(),
+            }) as BoxedDiagnostic)
+        }))
+        .chain(
+            token::components(tokenized.tokens()).filter_map(|component| {
+                let token = component.tokens().last()?;
+                let character = match token.kind() {
+                    TokenKind::Literal(ref literal) => literal.text().chars().last()?,
+                    _ => {
+                        return None;
+                    },
+                };
+                // `.` and `..` are diagnosed as semantic literals.
+                let is_semantic = component
+                    .literal()
+                    .map_or(false, |literal| literal.is_semantic_literal());
+                (matches!(character, '.' | ' ') && !is_semantic).then(|| {
+                    Box::new(TrailingCharacterWarning {
+                        expression: tokenized.expression().clone(),
+                        character,
+                        span: (*token.annotation()).into(),
+                    }) as BoxedDiagnostic
+                })
+            }),
+        )
+        .chain(diagnose_nonportable(tokenized))
         .chain(tokenized.tokens().last().into_iter().filter_map(|token| {
             matches!(token.kind(), TokenKind::Separator(_)).then(|| {
                 Box::new(TerminatingSeparatorWarning {
@@ -58,17 +473,144 @@ pub fn diagnose<'i, 't>(
                 }) as BoxedDiagnostic
             })
         }))
+        .chain(diagnose_cost(tokenized))
+}
+
+// Diagnoses glob expressions with an estimated cost that exceeds a threshold.
+// The most expensive top-level token is labeled.
+fn diagnose_cost<'t>(tokenized: &Tokenized<'t>) -> Option<BoxedDiagnostic<'t>> {
+    let cost = token::cost(tokenized.tokens());
+    if cost < EXPENSIVE_COST {
+        return None;
+    }
+    let token = tokenized
+        .tokens()
+        .iter()
+        .max_by_key(|token| token::cost(Some(*token)))?;
+    Some(Box::new(ExpensivePatternWarning {
+        expression: tokenized.expression().clone(),
+        cost,
+        span: (*token.annotation()).into(),
+    }))
+}
+
+// Diagnoses constructs that are interpreted differently on Unix and Windows.
+fn diagnose_nonportable<'i, 't>(
+    tokenized: &'i Tokenized<'t>,
+) -> impl 'i + Iterator<Item = BoxedDiagnostic<'t>> {
+    nonportable_case(tokenized)
+        .into_iter()
+        .chain(
+            tokenized
+                .walk()
+                .filter_map(|(_, token)| match token.kind() {
+                    TokenKind::Class(ref class) if is_nonportable_class(class) => {
+                        Some(Box::new(NonPortableSeparatorWarning {
+                            expression: tokenized.expression().clone(),
+                            span: (*token.annotation()).into(),
+                        }) as BoxedDiagnostic)
+                    },
+                    _ => None,
+                }),
+        )
+        .chain(tokenized.tokens().first().and_then(|token| {
+            let prefix = match token.kind() {
+                TokenKind::Literal(ref literal) => drive_prefix(literal.text())?,
+                _ => {
+                    return None;
+                },
+            };
+            Some(Box::new(NonPortableDriveWarning {
+                expression: tokenized.expression().clone(),
+                prefix,
+                span: (*token.annotation()).into(),
+            }) as BoxedDiagnostic)
+        }))
+}
+
+// Literals with casing are invariant only if their case sensitivity agrees with
+// the platform, so literals with an explicit case sensitivity may be included
+// in the invariant prefix on one platform but not the other.
+fn nonportable_case<'t>(tokenized: &Tokenized<'t>) -> Vec<BoxedDiagnostic<'t>> {
+    tokenized
+        .tokens()
+        .iter()
+        .take_while(|token| {
+            matches!(
+                token.kind(),
+                TokenKind::Literal(_) | TokenKind::Separator(_)
+            )
+        })
+        .filter_map(|token| match token.kind() {
+            TokenKind::Literal(ref literal)
+                if literal.is_case_explicit() && literal.text().has_casing() =>
+            {
+                Some(Box::new(NonPortableCaseWarning {
+                    expression: tokenized.expression().clone(),
+                    literal: literal.text().to_owned().into(),
+                    span: (*token.annotation()).into(),
+                }) as BoxedDiagnostic)
+            },
+            _ => None,
+        })
+        .collect()
+}
+
+// Returns `true` if a character class matches `\`, which is a separator on
+// Windows.
+fn is_nonportable_class(class: &Class) -> bool {
+    !class.is_negated()
+        && class.archetypes().iter().any(|archetype| match archetype {
+            Archetype::Character(x) => *x == '\\',
+            Archetype::Range(left, right) => (*left..=*right).contains(&'\\'),
+        })
+}
+
+// Gets the drive prefix that begins a literal, if any.
+fn drive_prefix(text: &str) -> Option<String> {
+    let mut characters = text.chars();
+    match (characters.next(), characters.next()) {
+        (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => Some(format!("{}:", drive)),
+        _ => None,
+    }
+}
+
+// Gets the name of a platform on which the given character is not allowed in
+// file names, if any. `NUL` is not allowed on any supported platform.
+fn invalid_platform(x: char) -> Option<&'static str> {
+    match x {
+        '\0' => Some("any platform"),
+        '<' | '>' | ':' | '"' | '|' | '?' | '*' | '\u{1}'..='\u{1F}' => Some("Windows"),
+        _ => None,
+    }
 }
 
 // These tests exercise `Glob` APIs, which wrap functions in this module.
 #[cfg(test)]
 mod tests {
-    use crate::Glob;
+    use miette::{Report, Severity};
+    use std::path::Path;
+
+    use crate::{DiagnosticReport, Glob};
 
     // It is non-trivial to downcast `&dyn Diagnostic`, so diagnostics are
     // identified in tests by their code.
     const CODE_SEMANTIC_LITERAL: &str = "wax::glob::semantic_literal";
     const CODE_TERMINATING_SEPARATOR: &str = "wax::glob::terminating_separator";
+    const CODE_INVALID_CHARACTER: &str = "wax::glob::invalid_character";
+    const CODE_TRAILING_CHARACTER: &str = "wax::glob::trailing_character";
+    const CODE_NONPORTABLE_CASE: &str = "wax::glob::nonportable_case";
+    const CODE_NONPORTABLE_SEPARATOR: &str = "wax::glob::nonportable_separator";
+    const CODE_NONPORTABLE_DRIVE: &str = "wax::glob::nonportable_drive";
+    const CODE_EXPENSIVE_PATTERN: &str = "wax::glob::expensive_pattern";
+
+    fn codes(expression: &str) -> Vec<String> {
+        Glob::new(expression)
+            .unwrap()
+            .diagnose()
+            .filter_map(|diagnostic| diagnostic.code().map(|code| code.to_string()))
+            .collect()
+    }
 
     #[cfg(any(unix, windows))]
     #[test]
@@ -90,4 +632,123 @@ mod tests {
             .code()
             .map_or(false, |code| code.to_string() == CODE_TERMINATING_SEPARATOR)));
     }
+
+    #[test]
+    fn diagnose_glob_invalid_character_warning() {
+        assert_eq!(codes("reports/q1:q2/*.csv"), [CODE_INVALID_CHARACTER]);
+        assert_eq!(codes("**/a\\?b"), [CODE_INVALID_CHARACTER]);
+        assert_eq!(codes("{a|b,c}/*"), [CODE_INVALID_CHARACTER]);
+        assert!(codes("reports/*.csv").is_empty());
+    }
+
+    #[test]
+    fn diagnose_glob_trailing_character_warning() {
+        assert_eq!(codes("notes./*.txt"), [CODE_TRAILING_CHARACTER]);
+        assert_eq!(codes("*/notes "), [CODE_TRAILING_CHARACTER]);
+        assert!(!codes("../*.txt").contains(&CODE_TRAILING_CHARACTER.to_owned()));
+        assert!(codes("*.txt/a").is_empty());
+    }
+
+    #[test]
+    fn diagnose_glob_nonportable_case_warning() {
+        assert_eq!(codes("(?i)src/*.rs"), [CODE_NONPORTABLE_CASE]);
+        assert_eq!(codes("(?-i)src/lib/*.rs"), [CODE_NONPORTABLE_CASE; 2]);
+        assert!(codes("src/*.rs").is_empty());
+        assert!(codes("*.(?i){jpg,jpeg}").is_empty());
+        assert!(codes("(?i)_/*.rs").is_empty());
+    }
+
+    #[test]
+    fn diagnose_glob_nonportable_separator_warning() {
+        assert!(codes("[A-z]").contains(&CODE_NONPORTABLE_SEPARATOR.to_owned()));
+        assert!(!codes("[!A-z]").contains(&CODE_NONPORTABLE_SEPARATOR.to_owned()));
+        assert!(!codes("[a-z]").contains(&CODE_NONPORTABLE_SEPARATOR.to_owned()));
+
+        // An escaped `\` in a class is invariant text, which is converted into
+        // a native path in which `\` is a separator on Windows. Literals cannot
+        // contain `\`, even if it is escaped.
+        let (prefix, _) = Glob::new("a[\\u{5C}]b/*.txt").unwrap().partition();
+        assert_eq!(prefix, Path::new("a\\b"));
+        assert_eq!(codes("a[\\u{5C}]b/*.txt"), [CODE_NONPORTABLE_SEPARATOR]);
+        assert!(Glob::new("a\\u{5C}b/*.txt").is_err());
+    }
+
+    #[test]
+    fn diagnose_glob_expensive_pattern_warning() {
+        assert_eq!(
+            codes("a/<{a,b,c,d}<x:0,100>:0,100>"),
+            [CODE_EXPENSIVE_PATTERN]
+        );
+        assert!(codes("a/<{a,b,c,d}<x:0,10>:0,10>").is_empty());
+    }
+
+    #[test]
+    fn report_glob_diagnostics() {
+        let codes = |report: &Report| -> Vec<_> {
+            report
+                .related()
+                .into_iter()
+                .flatten()
+                .filter_map(|diagnostic| diagnostic.code().map(|code| code.to_string()))
+                .collect()
+        };
+
+        assert!(Glob::reported("src/**/*.rs").unwrap().1.is_none());
+
+        let report = Glob::reported("(?i)notes./*.txt").unwrap().1.unwrap();
+        assert_eq!(report.severity(), Some(Severity::Warning));
+        assert_eq!(
+            report.to_string(),
+            "glob expression `(?i)notes./*.txt` has 2 warnings",
+        );
+        assert_eq!(
+            codes(&report),
+            [CODE_TRAILING_CHARACTER, CODE_NONPORTABLE_CASE]
+        );
+        assert!(report
+            .related()
+            .unwrap()
+            .all(|diagnostic| diagnostic.labels().is_some()));
+
+        let report = Glob::reported("a/**/**").unwrap_err();
+        assert_eq!(report.severity(), Some(Severity::Error));
+        assert!(report.to_string().ends_with("has 1 error"));
+    }
+
+    #[test]
+    fn record_glob_diagnostics() {
+        let report = DiagnosticReport::new("(?i)notes./*.txt");
+        assert!(!report.is_error());
+        assert_eq!(report.records().len(), 2);
+
+        let report = DiagnosticReport::new("a/**/**");
+        assert!(report.is_error());
+        assert!(report.records()[0].is_error());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_diagnostic_report() {
+        let report = DiagnosticReport::new("../*.txt");
+        assert_eq!(
+            serde_json::to_value(&report).unwrap(),
+            serde_json::json!({
+                "expression": "../*.txt",
+                "diagnostics": [{
+                    "code": CODE_SEMANTIC_LITERAL,
+                    "severity": "warning",
+                    "message": "`..` has been interpreted as a literal with no semantics",
+                    "help": null,
+                    "labels": [{ "label": "here", "offset": 0, "length": 2 }],
+                    "related": [],
+                }],
+            }),
+        );
+    }
+
+    #[test]
+    fn diagnose_glob_nonportable_drive_warning() {
+        assert!(codes("C:/Users/**").contains(&CODE_NONPORTABLE_DRIVE.to_owned()));
+        assert!(!codes("users/c:/**").contains(&CODE_NONPORTABLE_DRIVE.to_owned()));
+    }
 }
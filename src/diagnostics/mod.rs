@@ -6,7 +6,7 @@ use std::cmp;
 use std::fmt::{self, Display, Formatter};
 
 #[cfg(feature = "miette")]
-pub use crate::diagnostics::miette::diagnose;
+pub use crate::diagnostics::miette::{diagnose, DiagnosticRecord, DiagnosticReport};
 
 /// Location and length of a token within a glob expression.
 ///
@@ -80,6 +80,23 @@ impl CompositeSpan {
         }
     }
 
+    // Maps the spans of this composite span, such as from an expanded glob
+    // expression to the expression from which it was expanded.
+    pub fn map_spans(self, f: &impl Fn(Span) -> Span) -> Self {
+        CompositeSpan {
+            label: self.label,
+            kind: match self.kind {
+                CompositeSpanKind::Span(span) => CompositeSpanKind::Span(f(span)),
+                CompositeSpanKind::Correlated { span, correlated } => {
+                    CompositeSpanKind::Correlated {
+                        span: f(span),
+                        correlated: correlated.map_spans(f),
+                    }
+                },
+            },
+        }
+    }
+
     #[cfg(feature = "miette")]
     pub fn labels(&self) -> Vec<LabeledSpan> {
         let label = Some(self.label.to_string());
@@ -138,6 +155,13 @@ impl CorrelatedSpan {
         }
     }
 
+    pub fn map_spans(self, f: &impl Fn(Span) -> Span) -> Self {
+        match self {
+            CorrelatedSpan::Contiguous(span) => CorrelatedSpan::Contiguous(f(span)),
+            CorrelatedSpan::Split(left, right) => CorrelatedSpan::Split(f(left), f(right)),
+        }
+    }
+
     #[cfg(feature = "miette")]
     pub fn labels(&self) -> Vec<LabeledSpan> {
         let label = Some("here".to_string());
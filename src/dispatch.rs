@@ -0,0 +1,237 @@
+use regex::{Captures, Regex, RegexSet};
+use std::collections::BTreeMap;
+use std::mem;
+use std::path;
+
+use crate::encode::{self, CompileError};
+use crate::token::{self, Token, TokenKind};
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: BTreeMap<char, Node>,
+    program: Option<Regex>,
+}
+
+// Compiled programs keyed by the literal text that begins all text matched by
+// the program.
+//
+// The branches of an alternative are grouped by their literal prefix and each
+// group is compiled into a separate program. Programs are stored in a trie of
+// their prefixes, so that matching text only runs the programs with a prefix
+// that begins the text. This is much less expensive than running a single
+// program when there are many branches with distinct prefixes, such as `any`
+// combinators of many globs rooted in different directories.
+//
+// Separators in prefixes are always `/` and match any separator in text.
+//
+// Branches may have exclusions, in which case text only matches if it matches a
+// branch that does not also match the exclusion of that branch.
+#[derive(Clone, Debug, Default)]
+pub struct PrefixDispatch {
+    root: Node,
+    exclusions: Option<Exclusions>,
+}
+
+// Programs of the branches of an alternative and of their exclusions.
+#[derive(Clone, Debug)]
+struct Exclusions {
+    branches: RegexSet,
+    excluded: Vec<Option<PrefixDispatch>>,
+}
+
+impl PrefixDispatch {
+    // Compiles an alternative with an exclusion for each of its branches.
+    // Exclusions correspond to the branches with the same index.
+    pub fn compile_with_exclusions<'t, A>(
+        token: &Token<'t, A>,
+        exclusions: &[Option<Token<'t, ()>>],
+    ) -> Result<Self, CompileError>
+    where
+        A: Clone,
+    {
+        let mut dispatch = PrefixDispatch::compile(token)?;
+        if exclusions.iter().any(Option::is_some) {
+            let branches = match token.kind() {
+                TokenKind::Alternative(ref alternative) => alternative.branches(),
+                _ => unreachable!("excluded token is not an alternative"),
+            };
+            dispatch.exclusions = Some(Exclusions {
+                branches: encode::compile_set(
+                    branches
+                        .iter()
+                        .map(encode::compile)
+                        .collect::<Result<Vec<_>, _>>()?
+                        .iter(),
+                )?,
+                excluded: exclusions
+                    .iter()
+                    .map(|exclusion| exclusion.as_ref().map(PrefixDispatch::compile).transpose())
+                    .collect::<Result<_, _>>()?,
+            });
+        }
+        Ok(dispatch)
+    }
+
+    pub fn compile<'t, A>(token: &Token<'t, A>) -> Result<Self, CompileError>
+    where
+        A: Clone,
+    {
+        let mut groups: BTreeMap<String, Vec<&[Token<'t, A>]>> = BTreeMap::new();
+        match token.kind() {
+            TokenKind::Alternative(ref alternative) => {
+                for branch in alternative.branches() {
+                    groups
+                        .entry(token::literal_text_prefix(branch))
+                        .or_default()
+                        .push(branch);
+                }
+            },
+            _ => {
+                groups.insert(
+                    token::literal_text_prefix(Some(token)),
+                    vec![std::slice::from_ref(token)],
+                );
+            },
+        }
+        let mut dispatch = PrefixDispatch::default();
+        for (prefix, branches) in groups {
+            // Compile each group as an alternative, so that the capture groups
+            // of every program are the same as those of the complete
+            // alternative.
+            let program = encode::compile([token::any(
+                branches.into_iter().map(|branch| branch.iter().cloned()),
+            )])?;
+            let node = prefix.chars().fold(&mut dispatch.root, |node, x| {
+                node.children.entry(x).or_default()
+            });
+            node.program = Some(program);
+        }
+        Ok(dispatch)
+    }
+
+    // Estimates the number of bytes allocated on the heap by the programs and
+    // the trie.
+    pub fn heap_usage(&self) -> usize {
+        fn heap_usage(node: &Node) -> usize {
+            node.program.as_ref().map_or(0, encode::program_heap_usage)
+                + node
+                    .children
+                    .values()
+                    .map(|child| mem::size_of::<(char, Node)>() + heap_usage(child))
+                    .sum::<usize>()
+        }
+
+        heap_usage(&self.root)
+            + self.exclusions.as_ref().map_or(0, |exclusions| {
+                encode::program_set_heap_usage(&exclusions.branches)
+                    + exclusions
+                        .excluded
+                        .iter()
+                        .flatten()
+                        .map(PrefixDispatch::heap_usage)
+                        .sum::<usize>()
+            })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        self.programs(text).any(|program| program.is_match(text)) && !self.is_excluded(text)
+    }
+
+    pub fn captures<'p>(&self, text: &'p str) -> Option<Captures<'p>> {
+        if self.is_excluded(text) {
+            None
+        }
+        else {
+            self.programs(text)
+                .find_map(|program| program.captures(text))
+        }
+    }
+
+    // Returns `true` if the branch with the given index has an exclusion that
+    // matches the text.
+    pub fn is_excluded_by(&self, index: usize, text: &str) -> bool {
+        self.exclusions
+            .as_ref()
+            .and_then(|exclusions| exclusions.excluded.get(index))
+            .and_then(Option::as_ref)
+            .map_or(false, |excluded| excluded.is_match(text))
+    }
+
+    // Returns `true` if every branch that matches the text is excluded. Note
+    // that this is vacuously `true` if no branches match the text.
+    fn is_excluded(&self, text: &str) -> bool {
+        self.exclusions.as_ref().map_or(false, |exclusions| {
+            exclusions
+                .branches
+                .matches(text)
+                .into_iter()
+                .all(|index| self.is_excluded_by(index, text))
+        })
+    }
+
+    // Gets the programs with a prefix that begins the given text.
+    fn programs<'d>(&'d self, text: &'d str) -> impl 'd + Iterator<Item = &'d Regex> {
+        let mut chars = text.chars();
+        let mut node = Some(&self.root);
+        std::iter::from_fn(move || loop {
+            let current = node.take()?;
+            node = chars.next().and_then(|x| {
+                current
+                    .children
+                    .get(&if path::is_separator(x) { '/' } else { x })
+            });
+            if let Some(program) = current.program.as_ref() {
+                return Some(program);
+            }
+        })
+    }
+}
+
+impl From<Regex> for PrefixDispatch {
+    fn from(program: Regex) -> Self {
+        PrefixDispatch {
+            root: Node {
+                children: BTreeMap::new(),
+                program: Some(program),
+            },
+            exclusions: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dispatch::PrefixDispatch;
+    use crate::token::{self, TokenTree as _};
+
+    fn dispatch(expression: &str) -> PrefixDispatch {
+        let tokenized = token::parse(expression).unwrap();
+        let token = token::any([tokenized.into_tokens()]);
+        PrefixDispatch::compile(&token).unwrap()
+    }
+
+    #[test]
+    fn dispatch_programs_by_prefix() {
+        let dispatch = PrefixDispatch::compile(&token::any(
+            ["src/**/*.rs", "src/*.toml", "tests/*.rs", "**/*.md"]
+                .iter()
+                .map(|expression| token::parse(expression).unwrap().into_tokens()),
+        ))
+        .unwrap();
+        assert_eq!(dispatch.programs("src/lib.rs").count(), 3);
+        assert_eq!(dispatch.programs("tests/walk.rs").count(), 2);
+        assert_eq!(dispatch.programs("doc/README.md").count(), 1);
+        assert!(dispatch.is_match("src/lib.rs"));
+        assert!(dispatch.is_match("src/Cargo.toml"));
+        assert!(dispatch.is_match("tests/walk.rs"));
+        assert!(dispatch.is_match("doc/README.md"));
+        assert!(!dispatch.is_match("tests/data/walk.rs"));
+    }
+
+    #[test]
+    fn dispatch_programs_with_caseless_prefix() {
+        let dispatch = dispatch("(?i)src/*.rs");
+        assert!(dispatch.is_match("src/lib.rs"));
+        assert!(dispatch.is_match("SRC/lib.rs"));
+    }
+}
@@ -2,19 +2,23 @@ use const_format::formatcp;
 use itertools::{Itertools as _, Position};
 #[cfg(feature = "miette")]
 use miette::Diagnostic;
-use regex::{Error as RegexError, Regex};
+use regex::{Error as RegexError, Regex, RegexSet};
 use std::borrow::{Borrow, Cow};
 #[cfg(feature = "miette")]
 use std::fmt::Display;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
 use thiserror::Error;
 
-use crate::token::Token;
-use crate::PositionExt as _;
+use crate::token::{CaseFolding, Token};
+use crate::{PathKind, PositionExt as _};
+
+const UNIX_SEPARATOR_CLASS_EXPRESSION: &str = "/";
+const WINDOWS_SEPARATOR_CLASS_EXPRESSION: &str = "/\\\\";
 
 #[cfg(windows)]
-const SEPARATOR_CLASS_EXPRESSION: &str = "/\\\\";
+const SEPARATOR_CLASS_EXPRESSION: &str = WINDOWS_SEPARATOR_CLASS_EXPRESSION;
 #[cfg(unix)]
-const SEPARATOR_CLASS_EXPRESSION: &str = "/";
+const SEPARATOR_CLASS_EXPRESSION: &str = UNIX_SEPARATOR_CLASS_EXPRESSION;
 
 // This only encodes the platform's main separator, so any additional separators
 // will be missed. It may be better to have explicit platform support and invoke
@@ -43,18 +47,49 @@ const fn main_separator_class_expression() -> &'static str {
     formatcp!("{0}{1}", escape(MAIN_SEPARATOR), MAIN_SEPARATOR)
 }
 
+// Separator expressions of native paths are constant, so only those of foreign
+// paths are formatted when encoding.
 macro_rules! sepexpr {
-    ($fmt:expr) => {
-        formatcp!($fmt, formatcp!("[{0}]", SEPARATOR_CLASS_EXPRESSION))
+    ($kind:expr, $fmt:literal) => {
+        match $kind {
+            PathKind::Native => Cow::Borrowed(formatcp!(
+                $fmt,
+                formatcp!("[{0}]", SEPARATOR_CLASS_EXPRESSION)
+            )),
+            kind => Cow::<'static, str>::Owned(format!(
+                $fmt,
+                format!("[{0}]", separator_class_expression(kind))
+            )),
+        }
     };
 }
 
 macro_rules! nsepexpr {
-    ($fmt:expr) => {
-        formatcp!($fmt, formatcp!("[^{0}]", SEPARATOR_CLASS_EXPRESSION))
+    ($kind:expr, $fmt:literal) => {
+        match $kind {
+            PathKind::Native => Cow::Borrowed(formatcp!(
+                $fmt,
+                formatcp!("[^{0}]", SEPARATOR_CLASS_EXPRESSION)
+            )),
+            kind => Cow::<'static, str>::Owned(format!(
+                $fmt,
+                format!("[^{0}]", separator_class_expression(kind))
+            )),
+        }
     };
 }
 
+// Gets the separator character class expression used to match paths of the
+// given kind. Separators are encoded when a glob is compiled, so matching paths
+// of a foreign kind requires a distinct program.
+fn separator_class_expression(kind: PathKind) -> &'static str {
+    match kind {
+        PathKind::Native => SEPARATOR_CLASS_EXPRESSION,
+        PathKind::Unix => UNIX_SEPARATOR_CLASS_EXPRESSION,
+        PathKind::Windows => WINDOWS_SEPARATOR_CLASS_EXPRESSION,
+    }
+}
+
 /// Describes errors that occur when compiling a glob expression.
 ///
 /// **This error only occurs when the size of the compiled program is too
@@ -99,6 +134,25 @@ impl Escaped for str {
     }
 }
 
+// Encodes case-insensitive literal text with the given folding. The encoding
+// must follow the `i` flag.
+fn encode_folded_text(folding: CaseFolding, text: &str, pattern: &mut String) {
+    match folding {
+        CaseFolding::Turkic => {
+            // Simple case folding is disabled for dotted and dotless `i`, which
+            // are instead matched by classes of their Turkic case variants.
+            for x in text.chars() {
+                match x {
+                    'i' | 'İ' => pattern.push_str("(?-i:[iİ])"),
+                    'I' | 'ı' => pattern.push_str("(?-i:[Iı])"),
+                    _ => pattern.push_str(&x.escaped()),
+                }
+            }
+        },
+        CaseFolding::Simple => pattern.push_str(&text.escaped()),
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 enum Grouping {
     Capture,
@@ -134,14 +188,57 @@ pub fn case_folded_eq(left: &str, right: &str) -> bool {
     }
 }
 
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", skip_all, err))]
 pub fn compile<'t, A, T>(tokens: impl IntoIterator<Item = T>) -> Result<Regex, CompileError>
+where
+    T: Borrow<Token<'t, A>>,
+{
+    compile_with_kind(PathKind::Native, tokens)
+}
+
+// Compiles tokens into a program that matches paths of the given kind. Only
+// separators are determined by the kind: the case sensitivity of literals is
+// determined when parsing.
+pub fn compile_with_kind<'t, A, T>(
+    kind: PathKind,
+    tokens: impl IntoIterator<Item = T>,
+) -> Result<Regex, CompileError>
+where
+    T: Borrow<Token<'t, A>>,
+{
+    compile_with_prefix(kind, "", tokens)
+}
+
+// Compiles tokens into a program that matches any suffix of the components of
+// a path. Leading components are matched by a non-capturing prefix, so the
+// capture groups of the program are the same as those compiled by `compile`.
+pub fn compile_suffix<'t, A, T>(tokens: impl IntoIterator<Item = T>) -> Result<Regex, CompileError>
+where
+    T: Borrow<Token<'t, A>>,
+{
+    // The prefix is lazy, so the tokens match the longest possible suffix.
+    compile_with_prefix(
+        PathKind::Native,
+        &sepexpr!(PathKind::Native, "(?:.*?{0})??"),
+        tokens,
+    )
+}
+
+fn compile_with_prefix<'t, A, T>(
+    kind: PathKind,
+    prefix: &str,
+    tokens: impl IntoIterator<Item = T>,
+) -> Result<Regex, CompileError>
 where
     T: Borrow<Token<'t, A>>,
 {
     let mut pattern = String::new();
     pattern.push('^');
-    encode(Grouping::Capture, None, &mut pattern, tokens);
+    pattern.push_str(prefix);
+    encode(Grouping::Capture, None, kind, &mut pattern, tokens);
     pattern.push('$');
+    #[cfg(feature = "tracing")]
+    tracing::trace!(pattern = %pattern, "compiling glob pattern");
     Regex::new(&pattern).map_err(|error| match error {
         RegexError::CompiledTooBig(_) => CompileError {
             kind: CompileErrorKind::OversizedProgram,
@@ -150,12 +247,79 @@ where
     })
 }
 
-// TODO: Some versions of `const_format` in `^0.2.0` fail this lint in `formatcp`. See
-//       https://github.com/rodrimati1992/const_format_crates/issues/38
-#[allow(clippy::double_parens)]
+// The assumed number of bytes in a compiled program per byte of its pattern.
+// This is a rough heuristic rather than a measurement: `regex` does not expose
+// the size of compiled programs, which varies with the engines that it selects
+// and can be much larger for patterns with Unicode classes. It is intended only
+// to scale estimates with the size of patterns.
+const PROGRAM_BYTES_PER_PATTERN_BYTE: usize = 64;
+
+// Estimates the number of bytes allocated on the heap by a compiled program.
+// `Regex` does not report its memory usage, so this is derived from the size of
+// its pattern. This does not include caches that grow while matching.
+pub fn program_heap_usage(program: &Regex) -> usize {
+    program.as_str().len() * PROGRAM_BYTES_PER_PATTERN_BYTE
+}
+
+// Estimates the number of bytes allocated on the heap by a compiled program set.
+// See `program_heap_usage`.
+pub fn program_set_heap_usage(programs: &RegexSet) -> usize {
+    programs.patterns().iter().map(String::len).sum::<usize>() * PROGRAM_BYTES_PER_PATTERN_BYTE
+}
+
+// Compiles the patterns of programs into a set that matches all of the programs
+// in a single pass.
+pub fn compile_set<'r>(
+    programs: impl IntoIterator<Item = &'r Regex>,
+) -> Result<RegexSet, CompileError> {
+    RegexSet::new(programs.into_iter().map(Regex::as_str)).map_err(|error| match error {
+        RegexError::CompiledTooBig(_) => CompileError {
+            kind: CompileErrorKind::OversizedProgram,
+        },
+        _ => panic!("failed to compile glob set"),
+    })
+}
+
+// Program or program set that is compiled when it is first used. This is used
+// for queries that are rarely needed and would otherwise increase the cost of
+// building patterns.
+#[derive(Debug)]
+pub struct LazyProgram<T>(Mutex<Option<Arc<T>>>);
+
+pub type LazyProgramSet = LazyProgram<RegexSet>;
+
+impl<T> LazyProgram<T> {
+    pub fn get_or_compile(&self, f: impl FnOnce() -> T) -> Arc<T> {
+        self.lock().get_or_insert_with(|| Arc::new(f())).clone()
+    }
+
+    // Discards any compiled program so that it is compiled again when it is
+    // next used.
+    pub fn invalidate(&mut self) {
+        *self.0.get_mut().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Option<Arc<T>>> {
+        self.0.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+impl<T> Clone for LazyProgram<T> {
+    fn clone(&self) -> Self {
+        LazyProgram(Mutex::new(self.lock().clone()))
+    }
+}
+
+impl<T> Default for LazyProgram<T> {
+    fn default() -> Self {
+        LazyProgram(Mutex::new(None))
+    }
+}
+
 fn encode<'t, A, T>(
     grouping: Grouping,
     superposition: Option<Position<()>>,
+    kind: PathKind,
     pattern: &mut String,
     tokens: impl IntoIterator<Item = T>,
 ) where
@@ -169,11 +333,27 @@ fn encode<'t, A, T>(
     use crate::token::Wildcard::{One, Tree, ZeroOrMore};
 
     // This assumes that `NUL` is not allowed in paths and matches nothing.
-    const NULL_CHARACTER_CLASS: &str = nsepexpr!("[\\x00&&{0}]");
+    let null_character_class = nsepexpr!(kind, "[\\x00&&{0}]");
+    // Matches exactly one extended grapheme cluster that contains no
+    // separators. This approximates the boundary rules of UAX #29 and, unlike
+    // a segmenter, may backtrack into a cluster when followed by other
+    // variant tokens (e.g., `??`).
+    let grapheme_cluster = format!(
+        concat!(
+            r"(?:\r\n|[\p{{gcb=CR}}\p{{gcb=LF}}\p{{gcb=Control}}]|\p{{gcb=Prepend}}*",
+            r"(?:\p{{gcb=L}}*(?:\p{{gcb=V}}+|\p{{gcb=LV}}\p{{gcb=V}}*|\p{{gcb=LVT}})\p{{gcb=T}}*",
+            r"|\p{{gcb=L}}+|\p{{gcb=T}}+|\p{{gcb=RI}}\p{{gcb=RI}}",
+            r"|\p{{Extended_Pictographic}}",
+            r"(?:\p{{gcb=Extend}}*\p{{gcb=ZWJ}}\p{{Extended_Pictographic}})*",
+            r"|[^{0}\p{{gcb=CR}}\p{{gcb=LF}}\p{{gcb=Control}}])",
+            r"[\p{{gcb=Extend}}\p{{gcb=ZWJ}}\p{{gcb=SpacingMark}}]*)",
+        ),
+        separator_class_expression(kind),
+    );
 
-    fn encode_intermediate_tree(grouping: Grouping, pattern: &mut String) {
-        pattern.push_str(sepexpr!("(?:{0}|{0}"));
-        grouping.push_str(pattern, sepexpr!(".*{0}"));
+    fn encode_intermediate_tree(grouping: Grouping, kind: PathKind, pattern: &mut String) {
+        pattern.push_str(&sepexpr!(kind, "(?:{0}|{0}"));
+        grouping.push_str(pattern, &sepexpr!(kind, ".*{0}"));
         pattern.push(')');
     }
 
@@ -186,13 +366,14 @@ fn encode<'t, A, T>(
                 // TODO: Should Unicode support also be toggled by casing flags?
                 if literal.is_case_insensitive() {
                     pattern.push_str("(?i)");
+                    encode_folded_text(literal.folding(), literal.text(), pattern);
                 }
                 else {
                     pattern.push_str("(?-i)");
+                    pattern.push_str(&literal.text().escaped());
                 }
-                pattern.push_str(&literal.text().escaped());
             },
-            (_, Separator(_)) => pattern.push_str(sepexpr!("{0}")),
+            (_, Separator(_)) => pattern.push_str(&sepexpr!(kind, "{0}")),
             (position, Alternative(alternative)) => {
                 let encodings: Vec<_> = alternative
                     .branches()
@@ -203,6 +384,7 @@ fn encode<'t, A, T>(
                         encode(
                             Grouping::NonCapture,
                             superposition.or(Some(position)),
+                            kind,
                             &mut pattern,
                             tokens.iter(),
                         );
@@ -220,6 +402,7 @@ fn encode<'t, A, T>(
                     encode(
                         Grouping::NonCapture,
                         superposition.or(Some(position)),
+                        kind,
                         &mut pattern,
                         repetition.tokens().iter(),
                     );
@@ -229,6 +412,9 @@ fn encode<'t, A, T>(
                     else {
                         format!("){{{},}}", lower)
                     });
+                    if let Lazy = repetition.evaluation() {
+                        pattern.push('?');
+                    }
                     pattern
                 };
                 grouping.push_str(pattern, &encoding);
@@ -250,7 +436,7 @@ fn encode<'t, A, T>(
                             },
                         }
                     }
-                    pattern.push_str(nsepexpr!("&&{0}]"));
+                    pattern.push_str(&nsepexpr!(kind, "&&{0}]"));
                     // Compile the character class sub-expression. This may fail
                     // if the subtraction of the separator pattern yields an
                     // empty character class (meaning that the glob expression
@@ -262,35 +448,66 @@ fn encode<'t, A, T>(
                         pattern.into()
                     }
                     else {
-                        NULL_CHARACTER_CLASS.into()
+                        (&*null_character_class).into()
                     }
                 });
             },
-            (_, Wildcard(One)) => grouping.push_str(pattern, nsepexpr!("{0}")),
-            (_, Wildcard(ZeroOrMore(Eager))) => grouping.push_str(pattern, nsepexpr!("{0}*")),
-            (_, Wildcard(ZeroOrMore(Lazy))) => grouping.push_str(pattern, nsepexpr!("{0}*?")),
+            (_, Wildcard(One { is_grapheme: false })) => {
+                grouping.push_str(pattern, &nsepexpr!(kind, "{0}"))
+            },
+            (_, Wildcard(One { is_grapheme: true })) => {
+                grouping.push_str(pattern, &grapheme_cluster)
+            },
+            (
+                _,
+                Wildcard(ZeroOrMore {
+                    evaluation: Eager,
+                    is_separator_crossing,
+                }),
+            ) => {
+                if *is_separator_crossing {
+                    grouping.push_str(pattern, ".*")
+                }
+                else {
+                    grouping.push_str(pattern, &nsepexpr!(kind, "{0}*"))
+                }
+            },
+            (
+                _,
+                Wildcard(ZeroOrMore {
+                    evaluation: Lazy,
+                    is_separator_crossing,
+                }),
+            ) => {
+                if *is_separator_crossing {
+                    grouping.push_str(pattern, ".*?")
+                }
+                else {
+                    grouping.push_str(pattern, &nsepexpr!(kind, "{0}*?"))
+                }
+            },
             (First(_), Wildcard(Tree { has_root })) => {
                 if let Some(Middle(_) | Last(_)) = superposition {
-                    encode_intermediate_tree(grouping, pattern);
+                    encode_intermediate_tree(grouping, kind, pattern);
                 }
                 else if *has_root {
-                    grouping.push_str(pattern, sepexpr!("{0}.*{0}?"));
+                    grouping.push_str(pattern, &sepexpr!(kind, "{0}.*{0}?"));
                 }
                 else {
-                    pattern.push_str(sepexpr!("(?:{0}?|"));
-                    grouping.push_str(pattern, sepexpr!(".*{0}"));
+                    pattern.push_str(&sepexpr!(kind, "(?:{0}?|"));
+                    grouping.push_str(pattern, &sepexpr!(kind, ".*{0}"));
                     pattern.push(')');
                 }
             },
             (Middle(_), Wildcard(Tree { .. })) => {
-                encode_intermediate_tree(grouping, pattern);
+                encode_intermediate_tree(grouping, kind, pattern);
             },
             (Last(_), Wildcard(Tree { .. })) => {
                 if let Some(First(_) | Middle(_)) = superposition {
-                    encode_intermediate_tree(grouping, pattern);
+                    encode_intermediate_tree(grouping, kind, pattern);
                 }
                 else {
-                    pattern.push_str(sepexpr!("(?:{0}?|{0}"));
+                    pattern.push_str(&sepexpr!(kind, "(?:{0}?|{0}"));
                     grouping.push_str(pattern, ".*");
                     pattern.push(')');
                 }
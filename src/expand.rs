@@ -0,0 +1,391 @@
+//! Expansion of glob expression text before parsing.
+//!
+//! Expansions replace text in a glob expression with escaped text, such as a
+//! leading `~` with the path of a home directory or `${NAME}` with the value
+//! of an environment variable. The expanded expression is
+//! built as usual, but errors refer to the original expression: spans are
+//! mapped from the expanded expression back to the expression from which it
+//! was expanded.
+
+#[cfg(feature = "miette")]
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+use std::borrow::Cow;
+#[cfg(feature = "miette")]
+use std::fmt::Display;
+use std::path::PathBuf;
+use thiserror::Error;
+
+use crate::diagnostics::{LocatedError, Span};
+
+/// Describes errors that occur when expanding a glob expression.
+///
+/// Expansions are enabled by a [`GlobBuilder`]. For example, this error occurs
+/// if a glob expression begins with `~` but the home directory cannot be
+/// resolved or if a glob expression references an environment variable that is
+/// not set.
+///
+/// [`GlobBuilder`]: crate::GlobBuilder
+#[derive(Clone, Debug, Error)]
+#[error("failed to expand glob expression: {kind}")]
+pub struct ExpansionError {
+    expression: String,
+    kind: ExpansionErrorKind,
+    span: Span,
+}
+
+impl ExpansionError {
+    fn new(expression: &str, kind: ExpansionErrorKind, span: Span) -> Self {
+        ExpansionError {
+            expression: expression.to_owned(),
+            kind,
+            span,
+        }
+    }
+
+    /// Gets the glob expression that failed to expand.
+    pub fn expression(&self) -> &str {
+        self.expression.as_ref()
+    }
+}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+impl Diagnostic for ExpansionError {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        Some(Box::new(match self.kind {
+            ExpansionErrorKind::UnresolvedHome | ExpansionErrorKind::UnresolvedUserHome(_) => {
+                "wax::glob::unresolved_home"
+            },
+            ExpansionErrorKind::UnsetVariable(_) => "wax::glob::unset_variable",
+        }))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.expression)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(Some(LabeledSpan::new_with_span(
+            Some(format!("{}", self.kind)),
+            self.span,
+        ))
+        .into_iter()))
+    }
+}
+
+impl LocatedError for ExpansionError {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+enum ExpansionErrorKind {
+    #[error("home directory not found")]
+    UnresolvedHome,
+    #[error("home directory of user `{0}` not found")]
+    UnresolvedUserHome(String),
+    #[error("environment variable `{0}` is not set")]
+    UnsetVariable(String),
+}
+
+// Expanded text in a glob expression. The span is the location of the text in
+// the original expression and `len` is the length of the text that replaces it
+// in the expanded expression.
+#[derive(Clone, Copy, Debug)]
+struct Substitution {
+    span: Span,
+    len: usize,
+}
+
+/// Glob expression with expanded text.
+#[derive(Clone, Debug)]
+pub struct Expanded<'t> {
+    expression: Cow<'t, str>,
+    substitutions: Vec<Substitution>,
+}
+
+impl<'t> Expanded<'t> {
+    pub fn expression(&self) -> &str {
+        self.expression.as_ref()
+    }
+
+    pub fn is_expanded(&self) -> bool {
+        !self.substitutions.is_empty()
+    }
+
+    // Maps a span in the expanded expression to the original expression. Spans
+    // that begin or end within expanded text are widened to the text that it
+    // replaced.
+    pub fn original_span(&self, (start, n): Span) -> Span {
+        let end = self.original_offset(start + n, true);
+        let start = self.original_offset(start, false);
+        (start, end.saturating_sub(start))
+    }
+
+    fn original_offset(&self, offset: usize, is_end: bool) -> usize {
+        // The difference between offsets in the original and expanded
+        // expressions, which changes after each substitution.
+        let mut delta = 0isize;
+        for substitution in self.substitutions.iter() {
+            let (original, n) = substitution.span;
+            let start = original as isize - delta;
+            let end = start + substitution.len as isize;
+            let offset = offset as isize;
+            if offset < start || (is_end && offset == start) {
+                break;
+            }
+            if offset < end {
+                return if is_end { original + n } else { original };
+            }
+            delta += n as isize - substitution.len as isize;
+        }
+        (offset as isize + delta) as usize
+    }
+}
+
+/// Expansions that are applied to a glob expression.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct Expansions {
+    pub is_home_expanded: bool,
+    pub is_variable_expanded: bool,
+}
+
+// Builds an expanded glob expression from an original glob expression and the
+// substitutions applied to it. Substitutions must be applied in order.
+struct Expander<'t> {
+    original: &'t str,
+    expression: String,
+    // The offset in the original expression up to which text has been copied
+    // into the expanded expression.
+    end: usize,
+    substitutions: Vec<Substitution>,
+}
+
+impl<'t> Expander<'t> {
+    fn new(original: &'t str) -> Self {
+        Expander {
+            original,
+            expression: String::new(),
+            end: 0,
+            substitutions: vec![],
+        }
+    }
+
+    fn substitute(&mut self, span: Span, text: &str) {
+        let (start, n) = span;
+        self.expression.push_str(&self.original[self.end..start]);
+        self.expression.push_str(text);
+        self.end = start + n;
+        self.substitutions.push(Substitution {
+            span,
+            len: text.len(),
+        });
+    }
+
+    fn finish(self) -> Expanded<'t> {
+        let Expander {
+            original,
+            mut expression,
+            end,
+            substitutions,
+        } = self;
+        if substitutions.is_empty() {
+            Expanded {
+                expression: original.into(),
+                substitutions,
+            }
+        }
+        else {
+            expression.push_str(&original[end..]);
+            Expanded {
+                expression: expression.into(),
+                substitutions,
+            }
+        }
+    }
+}
+
+/// Applies expansions to a glob expression.
+///
+/// When enabled, a leading `~` that is followed by a separator or the end of
+/// the expression is expanded into the escaped path of the home directory. On
+/// Unix, a leading `~user` is expanded into the home directory of `user`.
+/// Similarly, references to environment variables of the form `${NAME}` are
+/// expanded into their escaped values and `\$` is expanded into `$`.
+pub fn expand(expression: &str, expansions: Expansions) -> Result<Expanded<'_>, ExpansionError> {
+    let mut expander = Expander::new(expression);
+    if expansions.is_home_expanded {
+        expand_home(&mut expander)?;
+    }
+    if expansions.is_variable_expanded {
+        expand_variables(&mut expander)?;
+    }
+    Ok(expander.finish())
+}
+
+fn expand_home(expander: &mut Expander<'_>) -> Result<(), ExpansionError> {
+    let expression = expander.original;
+    let text = match expression.strip_prefix('~') {
+        Some(text) => text,
+        _ => return Ok(()),
+    };
+    let end = text.find('/').unwrap_or(text.len());
+    let (user, rest) = text.split_at(end);
+    if !user.chars().all(is_user_name_character) {
+        return Ok(());
+    }
+    let span = (0, end + 1);
+    let (home, kind) = if user.is_empty() {
+        (home_dir(), ExpansionErrorKind::UnresolvedHome)
+    }
+    else {
+        (
+            user_home_dir(user),
+            ExpansionErrorKind::UnresolvedUserHome(user.to_owned()),
+        )
+    };
+    // Home directories must be rooted so that the expanded glob is rooted.
+    let home = home
+        .filter(|home| home.has_root())
+        .ok_or_else(|| ExpansionError::new(expression, kind, span))?;
+    let home = crate::escape_path(&home);
+    // Avoid repeated separators if the home directory is the root by also
+    // substituting the separator that follows `~`.
+    let span = if home.ends_with('/') && rest.starts_with('/') {
+        (0, end + 2)
+    }
+    else {
+        span
+    };
+    expander.substitute(span, &home);
+    Ok(())
+}
+
+fn expand_variables(expander: &mut Expander<'_>) -> Result<(), ExpansionError> {
+    let expression = expander.original;
+    let mut start = expander.end;
+    while let Some(offset) = expression[start..].find(['\\', '$']) {
+        let offset = start + offset;
+        let text = &expression[offset..];
+        if text.starts_with("\\$") {
+            expander.substitute((offset, 2), "$");
+            start = offset + 2;
+        }
+        else if let Some(escaped) = text.strip_prefix('\\') {
+            // Skip any escaped character.
+            start = offset + 1 + escaped.chars().next().map_or(0, char::len_utf8);
+        }
+        else if let Some(name) = variable_name(text) {
+            let span = (offset, name.len() + 3);
+            let value = std::env::var_os(name).ok_or_else(|| {
+                ExpansionError::new(
+                    expression,
+                    ExpansionErrorKind::UnsetVariable(name.to_owned()),
+                    span,
+                )
+            })?;
+            expander.substitute(span, &crate::escape_os_str(&value));
+            start = offset + span.1;
+        }
+        else {
+            start = offset + 1;
+        }
+    }
+    Ok(())
+}
+
+// Gets the name of a variable in a reference of the form `${NAME}` at the
+// beginning of the given text.
+fn variable_name(text: &str) -> Option<&str> {
+    let text = text.strip_prefix("${")?;
+    let (name, _) = text.split_at(text.find('}')?);
+    let mut chars = name.chars();
+    (chars
+        .next()
+        .map_or(false, |x| x.is_ascii_alphabetic() || x == '_')
+        && chars.all(|x| x.is_ascii_alphanumeric() || x == '_'))
+    .then(|| name)
+}
+
+fn is_user_name_character(x: char) -> bool {
+    x.is_ascii_alphanumeric() || matches!(x, '_' | '-' | '.')
+}
+
+fn home_dir() -> Option<PathBuf> {
+    #[cfg(unix)]
+    const HOME: &str = "HOME";
+    #[cfg(not(unix))]
+    const HOME: &str = "USERPROFILE";
+
+    std::env::var_os(HOME)
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(unix)]
+fn user_home_dir(user: &str) -> Option<PathBuf> {
+    use std::ffi::{CStr, CString, OsStr};
+    use std::mem::MaybeUninit;
+    use std::os::unix::ffi::OsStrExt as _;
+
+    let user = CString::new(user).ok()?;
+    let mut buffer = vec![0u8; 1024];
+    loop {
+        let mut entry = MaybeUninit::<libc::passwd>::uninit();
+        let mut result = std::ptr::null_mut();
+        // SAFETY: `user` is a nul-terminated string and the other pointers
+        //         refer to live and correctly sized storage. `getpwnam_r` is
+        //         reentrant: it writes the entry into `entry` and `buffer`
+        //         rather than static storage and sets `result` to a pointer to
+        //         `entry` if the user is found.
+        let code = unsafe {
+            libc::getpwnam_r(
+                user.as_ptr(),
+                entry.as_mut_ptr(),
+                buffer.as_mut_ptr().cast(),
+                buffer.len(),
+                &mut result,
+            )
+        };
+        if code == libc::ERANGE && buffer.len() < (1 << 20) {
+            buffer.resize(buffer.len() * 2, 0);
+            continue;
+        }
+        if code != 0 || result.is_null() {
+            return None;
+        }
+        // SAFETY: `result` is non-null, so `entry` has been initialized and
+        //         `pw_dir` is a nul-terminated string in `buffer`.
+        let home = unsafe { CStr::from_ptr(entry.assume_init().pw_dir) };
+        return Some(PathBuf::from(OsStr::from_bytes(home.to_bytes())));
+    }
+}
+
+#[cfg(not(unix))]
+fn user_home_dir(_: &str) -> Option<PathBuf> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::expand::{Expanded, Substitution};
+
+    #[test]
+    fn map_expanded_span_to_original_span() {
+        // `~/{a,b}` expanded to `/home/user/{a,b}`.
+        let expanded = Expanded {
+            expression: "/home/user/{a,b}".into(),
+            substitutions: vec![Substitution {
+                span: (0, 1),
+                len: 10,
+            }],
+        };
+        assert_eq!(expanded.original_span((11, 5)), (2, 5));
+        assert_eq!(expanded.original_span((0, 16)), (0, 7));
+        assert_eq!(expanded.original_span((4, 2)), (0, 1));
+        assert_eq!(expanded.original_span((6, 8)), (0, 5));
+        assert_eq!(expanded.original_span((10, 1)), (1, 1));
+    }
+}
@@ -0,0 +1,573 @@
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+#[cfg(feature = "walk")]
+use std::collections::HashSet;
+use std::convert::Infallible;
+#[cfg(feature = "walk")]
+use std::path::{Path, PathBuf};
+
+use crate::dispatch::PrefixDispatch;
+use crate::rule::Checked;
+use crate::token::{self, Composition, InvariantText};
+#[cfg(feature = "walk")]
+use crate::walk::{FilterTarget, Negation, PruneCause, TreeIterator, Walk, WalkBehavior, WalkItem};
+use crate::{BuildError, CandidatePath, Compose, Glob, MatchedText, Pattern, Variance};
+
+/// Selection of paths described by included and excluded [`Glob`]s.
+///
+/// A `Filter` matches a path if the path matches any of its included [`Glob`]s
+/// and **none** of its excluded [`Glob`]s, so exclusions always override
+/// inclusions. If a `Filter` has no included [`Glob`]s, then it includes any
+/// and all paths (as if it included the glob expression `**`).
+///
+/// `Filter` implements [`Pattern`] and can be composed with other patterns,
+/// such as in [`any`] and [`Walk::not`]. When composed, the excluded
+/// [`Glob`]s only apply to the included [`Glob`]s of the same `Filter`.
+///
+/// When the `serde` feature is enabled, `Filter` can be deserialized from a
+/// map with optional `include` and `exclude` sequences of glob expressions.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Filter, Glob};
+///
+/// let filter = Filter::new(
+///     [Glob::new("src/**/*.rs").unwrap()],
+///     [Glob::new("src/**/generated/**").unwrap()],
+/// )
+/// .unwrap();
+/// assert!(filter.is_match("src/lib.rs"));
+/// assert!(!filter.is_match("src/token/generated/table.rs"));
+/// ```
+///
+/// A `Filter` can be read from configuration and used to walk a directory
+/// tree.
+///
+/// ```rust,no_run
+/// # #[cfg(all(feature = "serde", feature = "walk"))]
+/// # {
+/// use serde::Deserialize;
+/// use wax::Filter;
+///
+/// #[derive(Deserialize)]
+/// struct Config {
+///     sources: Filter<'static>,
+/// }
+///
+/// let config: Config = serde_json::from_str(
+///     r#"{ "sources": { "include": ["**/*.md"], "exclude": ["target/**"] } }"#,
+/// )
+/// .unwrap();
+/// for entry in config.sources.walk(".") {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// # }
+/// ```
+///
+/// [`any`]: crate::any
+/// [`Glob`]: crate::Glob
+/// [`Pattern`]: crate::Pattern
+/// [`Walk::not`]: crate::Walk::not
+#[derive(Clone, Debug)]
+pub struct Filter<'t> {
+    pub(crate) tree: Checked<Composition<'t>>,
+    include: Vec<Glob<'t>>,
+    exclude: Vec<Glob<'t>>,
+    included: Option<PrefixDispatch>,
+    excluded: Option<PrefixDispatch>,
+    #[cfg(feature = "walk")]
+    negation: Negation,
+}
+
+impl<'t> Filter<'t> {
+    /// Constructs a `Filter` from included and excluded [`Glob`]s.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn new<I, E>(include: I, exclude: E) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = Glob<'t>>,
+        E: IntoIterator<Item = Glob<'t>>,
+    {
+        fn any<'t>(globs: &[Glob<'t>]) -> Result<Option<PrefixDispatch>, BuildError> {
+            if globs.is_empty() {
+                Ok(None)
+            }
+            else {
+                crate::any(globs.iter().cloned()).map(|any| Some(any.pattern))
+            }
+        }
+
+        let include: Vec<_> = include.into_iter().collect();
+        let exclude: Vec<_> = exclude.into_iter().collect();
+        let tree = Checked::any_except(
+            if include.is_empty() {
+                vec![Glob::new("**").expect("failed to build tree glob").tree]
+            }
+            else {
+                include.iter().cloned().map(|glob| glob.tree).collect()
+            },
+            exclude.iter().cloned().map(|glob| glob.tree),
+        );
+        Ok(Filter {
+            tree,
+            included: any(&include)?,
+            excluded: any(&exclude)?,
+            #[cfg(feature = "walk")]
+            negation: Negation::any(exclude.iter().cloned())?,
+            include,
+            exclude,
+        })
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Filter<'static> {
+        let Filter {
+            tree,
+            include,
+            exclude,
+            included,
+            excluded,
+            #[cfg(feature = "walk")]
+            negation,
+        } = self;
+        Filter {
+            tree: tree.into_owned(),
+            include: include.into_iter().map(Glob::into_owned).collect(),
+            exclude: exclude.into_iter().map(Glob::into_owned).collect(),
+            included,
+            excluded,
+            #[cfg(feature = "walk")]
+            negation,
+        }
+    }
+
+    /// Includes paths that match another [`Glob`].
+    ///
+    /// If the `Filter` has no included [`Glob`]s, then it already includes any
+    /// and all paths and the [`Glob`] is discarded.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn or(self, glob: Glob<'t>) -> Result<Self, BuildError> {
+        if self.include.is_empty() {
+            Ok(self)
+        }
+        else {
+            let Filter {
+                mut include,
+                exclude,
+                ..
+            } = self;
+            include.push(glob);
+            Filter::new(include, exclude)
+        }
+    }
+
+    /// Excludes paths that match another [`Glob`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn and_not(self, glob: Glob<'t>) -> Result<Self, BuildError> {
+        let Filter {
+            include,
+            mut exclude,
+            ..
+        } = self;
+        exclude.push(glob);
+        Filter::new(include, exclude)
+    }
+
+    /// Returns `true` if a path is included and not excluded by the `Filter`.
+    pub fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
+        let path = path.into();
+        self.included
+            .as_ref()
+            .map_or(true, |included| included.is_match(path.as_ref()))
+            && !self
+                .excluded
+                .as_ref()
+                .map_or(false, |excluded| excluded.is_match(path.as_ref()))
+    }
+
+    /// Gets [matched text][`MatchedText`] in a [`CandidatePath`].
+    ///
+    /// Returns `None` if the [`CandidatePath`] is not included or is excluded
+    /// by the `Filter`. As with [`Any`], the [matched text][`MatchedText`] only
+    /// exposes the complete text of a match.
+    ///
+    /// [`Any`]: crate::Any
+    /// [`CandidatePath`]: crate::CandidatePath
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched<'p>(&self, path: &'p CandidatePath<'_>) -> Option<MatchedText<'p>> {
+        if self
+            .excluded
+            .as_ref()
+            .map_or(false, |excluded| excluded.is_match(path.as_ref()))
+        {
+            return None;
+        }
+        match self.included {
+            Some(ref included) => included.captures(path.as_ref()).map(From::from),
+            None => Some(MatchedText::from_complete(path.as_ref())),
+        }
+    }
+
+    /// Gets the included [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn include(&self) -> &[Glob<'t>] {
+        &self.include
+    }
+
+    /// Gets the excluded [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn exclude(&self) -> &[Glob<'t>] {
+        &self.exclude
+    }
+
+    /// Gets an iterator over matching files in a directory tree.
+    ///
+    /// Each included [`Glob`] is matched against the directory tree as with
+    /// [`Glob::walk`] and excluded [`Glob`]s are applied as with [`Walk::not`],
+    /// so directory trees matched by [exhaustive][`Pattern::is_exhaustive`]
+    /// exclusions are not read. Files that are matched by more than one
+    /// included [`Glob`] are only yielded once.
+    ///
+    /// If the `Filter` has no included [`Glob`]s, then the entire directory
+    /// tree is traversed.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::walk`]: crate::Glob::walk
+    /// [`Pattern::is_exhaustive`]: crate::Pattern::is_exhaustive
+    /// [`Walk::not`]: crate::Walk::not
+    #[cfg(feature = "walk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+    pub fn walk(&self, directory: impl AsRef<Path>) -> FilterWalk<'_> {
+        self.walk_with_behavior(directory, WalkBehavior::default())
+    }
+
+    /// Gets an iterator over matching files in a directory tree.
+    ///
+    /// This function is the same as [`Filter::walk`], but it additionally
+    /// accepts a [`WalkBehavior`].
+    ///
+    /// [`Filter::walk`]: crate::Filter::walk
+    /// [`WalkBehavior`]: crate::WalkBehavior
+    #[cfg(feature = "walk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+    pub fn walk_with_behavior(
+        &self,
+        directory: impl AsRef<Path>,
+        behavior: impl Into<WalkBehavior>,
+    ) -> FilterWalk<'_> {
+        let directory = directory.as_ref();
+        let behavior = behavior.into();
+        let walks = if self.include.is_empty() {
+            vec![Glob::new("**")
+                .expect("failed to build tree glob")
+                .walk_with_behavior(directory, behavior)
+                .into_owned()]
+        }
+        else {
+            self.include
+                .iter()
+                .map(|glob| glob.walk_with_behavior(directory, behavior.clone()))
+                .collect()
+        };
+        FilterWalk {
+            negation: &self.negation,
+            // Tracking yielded paths is only necessary when traversals may
+            // overlap.
+            visited: (walks.len() > 1).then(HashSet::new),
+            walks: walks.into_iter().rev().collect(),
+        }
+    }
+}
+
+impl<'t> Pattern<'t> for Filter<'t> {
+    fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
+        Filter::is_match(self, path)
+    }
+
+    fn matched<'p>(&self, path: &'p CandidatePath<'_>) -> Option<MatchedText<'p>> {
+        Filter::matched(self, path)
+    }
+
+    fn variance(&self) -> Variance {
+        self.tree
+            .as_ref()
+            .token()
+            .variance::<InvariantText>()
+            .into()
+    }
+
+    fn is_exhaustive(&self) -> bool {
+        self.exclude.is_empty() && token::is_exhaustive(Some(self.tree.as_ref().token()))
+    }
+}
+
+impl<'t> Compose<'t> for Filter<'t> {
+    type Tokens = Composition<'t>;
+    type Error = Infallible;
+}
+
+#[cfg(feature = "serde")]
+#[derive(Deserialize, Serialize)]
+#[serde(bound(deserialize = "G: Deserialize<'de>"))]
+struct FilterExpressions<G> {
+    #[serde(default)]
+    include: Vec<G>,
+    #[serde(default)]
+    exclude: Vec<G>,
+}
+
+/// Deserializes a [`Filter`] from a map of `include` and `exclude` glob
+/// expressions.
+///
+/// Both fields are optional. Deserialization fails if any glob expression fails
+/// to build. See [`BuildError`].
+///
+/// [`BuildError`]: crate::BuildError
+/// [`Filter`]: crate::Filter
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for Filter<'static> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let FilterExpressions { include, exclude } = FilterExpressions::deserialize(deserializer)?;
+        Filter::new(include, exclude).map_err(de::Error::custom)
+    }
+}
+
+/// Serializes a [`Filter`] as a map of `include` and `exclude` glob
+/// expressions.
+///
+/// [`Filter`]: crate::Filter
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Filter<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        FilterExpressions {
+            include: self.include.iter().collect(),
+            exclude: self.exclude.iter().collect(),
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Iterator over files matching a [`Filter`] in a directory tree.
+///
+/// `FilterWalk` is a `TreeIterator` and supports [`FileIterator::filter_tree`].
+///
+/// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+/// [`Filter`]: crate::Filter
+#[cfg(feature = "walk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+#[must_use]
+pub struct FilterWalk<'f> {
+    walks: Vec<Walk<'f>>,
+    negation: &'f Negation,
+    visited: Option<HashSet<PathBuf>>,
+}
+
+#[cfg(feature = "walk")]
+impl Iterator for FilterWalk<'_> {
+    type Item = WalkItem<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(walk) = self.walks.last_mut() {
+            let entry = match walk.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(error)) => {
+                    return Some(Err(error));
+                },
+                None => {
+                    self.walks.pop();
+                    continue;
+                },
+            };
+            match self.negation.target(&entry) {
+                Some(FilterTarget::Tree) => {
+                    if entry.file_type().is_dir() {
+                        walk.skip_tree_with(PruneCause::Negation);
+                    }
+                    continue;
+                },
+                Some(FilterTarget::File) => {
+                    continue;
+                },
+                None => {},
+            }
+            if self
+                .visited
+                .as_mut()
+                .map_or(true, |visited| visited.insert(entry.path().to_path_buf()))
+            {
+                return Some(Ok(entry));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(feature = "walk")]
+impl TreeIterator for FilterWalk<'_> {
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        if let Some(walk) = self.walks.last_mut() {
+            walk.skip_tree_with(cause);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CandidatePath, Filter, Glob, Pattern as _};
+
+    #[test]
+    fn match_filter_with_include_and_exclude() {
+        let filter = Filter::new(
+            [
+                Glob::new("src/**/*.rs").unwrap(),
+                Glob::new("doc/**").unwrap(),
+            ],
+            [
+                Glob::new("**/generated/**").unwrap(),
+                Glob::new("doc/*.tmp").unwrap(),
+            ],
+        )
+        .unwrap();
+
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(filter.is_match("doc/wax.svg"));
+
+        assert!(!filter.is_match("src/generated/table.rs"));
+        assert!(!filter.is_match("doc/draft.tmp"));
+        assert!(!filter.is_match("tests/walk.rs"));
+    }
+
+    #[test]
+    fn match_filter_with_only_exclude() {
+        let filter = Filter::new([], [Glob::new("target/**").unwrap()]).unwrap();
+
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(filter.is_match("README.md"));
+
+        assert!(!filter.is_match("target/debug/wax"));
+    }
+
+    #[test]
+    fn match_filter_with_nothing() {
+        let filter = Filter::new([], []).unwrap();
+
+        assert!(filter.is_match(""));
+        assert!(filter.is_match("src/lib.rs"));
+    }
+
+    #[test]
+    fn match_filter_from_combinators() {
+        let filter = Glob::new("src/**/*.rs")
+            .unwrap()
+            .or(Glob::new("tests/*.rs").unwrap())
+            .unwrap()
+            .and_not(Glob::new("**/generated/**").unwrap())
+            .unwrap();
+        assert_eq!(filter.include().len(), 2);
+        assert_eq!(filter.exclude().len(), 1);
+
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(filter.is_match("tests/walk.rs"));
+        assert!(!filter.is_match("src/generated/table.rs"));
+        assert!(!filter.is_match("doc/guide.md"));
+
+        let path = CandidatePath::from("tests/walk.rs");
+        assert_eq!(filter.matched(&path).unwrap().complete(), "tests/walk.rs");
+        let path = CandidatePath::from("src/generated/table.rs");
+        assert!(filter.matched(&path).is_none());
+
+        // Filters with no inclusions include all paths.
+        let filter = Filter::new([], [])
+            .unwrap()
+            .or(Glob::new("*.md").unwrap())
+            .unwrap();
+        assert!(filter.is_match("src/lib.rs"));
+    }
+
+    #[test]
+    fn match_any_with_filter() {
+        let filter = Filter::new(
+            [Glob::new("src/**/*.rs").unwrap()],
+            [Glob::new("**/generated/**").unwrap()],
+        )
+        .unwrap();
+        let generated = Filter::new([Glob::new("**/generated/*.md").unwrap()], []).unwrap();
+        let any = crate::any([&filter, &generated]).unwrap();
+
+        assert!(any.is_match("src/lib.rs"));
+        assert!(any.is_match("src/generated/README.md"));
+
+        assert!(!any.is_match("src/generated/table.rs"));
+        assert!(!any.is_match("tests/walk.rs"));
+
+        assert_eq!(any.position("src/lib.rs"), Some(0));
+        assert_eq!(any.position("src/generated/README.md"), Some(1));
+        assert!(any.positions("src/generated/table.rs").is_empty());
+        assert!(!any.is_exhaustive());
+
+        let any = crate::any([&filter]).unwrap();
+        assert!(any.is_match("src/lib.rs"));
+        assert!(!any.is_match("src/generated/table.rs"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_filter_from_expressions() {
+        let filter: Filter<'static> =
+            serde_json::from_str(r#"{ "include": ["**/*.md"], "exclude": ["target/**"] }"#)
+                .unwrap();
+        assert!(filter.is_match("doc/guide.md"));
+        assert!(!filter.is_match("target/doc/guide.md"));
+        assert!(!filter.is_match("src/lib.rs"));
+
+        let filter: Filter<'static> = serde_json::from_str(r#"{ "exclude": ["*.tmp"] }"#).unwrap();
+        assert!(filter.is_match("src/lib.rs"));
+        assert!(!filter.is_match("draft.tmp"));
+
+        assert!(serde_json::from_str::<Filter<'static>>(r#"{ "include": ["***"] }"#).is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_filter_as_expressions() {
+        let filter = Filter::new(
+            [Glob::new("**/*.md").unwrap()],
+            [Glob::new("target/**").unwrap()],
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::to_string(&filter).unwrap(),
+            r#"{"include":["**/*.md"],"exclude":["target/**"]}"#,
+        );
+    }
+}
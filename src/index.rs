@@ -0,0 +1,359 @@
+use regex::Regex;
+use std::collections::btree_map::{self, BTreeMap};
+use std::iter::FromIterator;
+use std::path::{Component, Path, PathBuf};
+
+use crate::capture::MatchedText;
+use crate::token::{self, Token, TokenTree};
+use crate::{CandidatePath, Glob};
+
+#[derive(Clone, Debug, Default)]
+struct Node {
+    children: BTreeMap<String, Node>,
+    // The number of normal components (excluding roots and prefixes) in the
+    // path of the node.
+    depth: usize,
+    is_path: bool,
+}
+
+/// In-memory index of paths that can be queried by [`Glob`]s.
+///
+/// `PathIndex` ingests a set of paths once and stores their components in a
+/// trie. Queries use the invariant prefix of a glob to find the relevant
+/// subtree and then only visit nodes that match the leading components and
+/// depth of the glob. This is useful when a set of paths is already known,
+/// such as file lists from version control, and many globs are matched against
+/// it without reading the file system.
+///
+/// Paths are indexed by their components, so `a/b`, `a//b`, and `./a/b` are
+/// the same path in a `PathIndex`.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::PathBuf;
+/// use wax::{Glob, PathIndex};
+///
+/// let index: PathIndex = ["src/lib.rs", "src/token/parse.rs", "README.md"]
+///     .into_iter()
+///     .collect();
+/// let glob = Glob::new("src/**/*.rs").unwrap();
+/// let paths: Vec<_> = index.matched(&glob).map(|(path, _)| path).collect();
+/// assert_eq!(
+///     paths,
+///     [PathBuf::from("src/lib.rs"), PathBuf::from("src/token/parse.rs")],
+/// );
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[derive(Clone, Debug, Default)]
+pub struct PathIndex {
+    root: Node,
+    len: usize,
+}
+
+impl PathIndex {
+    /// Constructs an empty `PathIndex`.
+    pub fn new() -> Self {
+        PathIndex::default()
+    }
+
+    /// Inserts a path into the index.
+    ///
+    /// Returns `true` if the path was not already in the index.
+    pub fn insert(&mut self, path: impl AsRef<Path>) -> bool {
+        let mut node = &mut self.root;
+        for component in keys(path.as_ref()) {
+            let depth = node.depth + usize::from(component.is_normal);
+            node = node.children.entry(component.text).or_insert_with(|| Node {
+                depth,
+                ..Node::default()
+            });
+        }
+        if node.is_path {
+            false
+        }
+        else {
+            node.is_path = true;
+            self.len += 1;
+            true
+        }
+    }
+
+    /// Returns `true` if the path is in the index.
+    pub fn contains(&self, path: impl AsRef<Path>) -> bool {
+        self.get(keys(path.as_ref()).map(|component| component.text))
+            .map_or(false, |node| node.is_path)
+    }
+
+    /// Gets the number of paths in the index.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the index has no paths.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Gets an iterator over the paths in the index that match a [`Glob`].
+    ///
+    /// Paths are yielded in lexicographical order of their components along
+    /// with their [matched text][`MatchedText`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched<'i, 'g>(&'i self, glob: &'g Glob<'_>) -> IndexMatches<'i, 'g> {
+        let tokens = glob.tree.as_ref().tokens();
+        let components = compile(tokens);
+        // The depth of matching paths is bounded unless the glob has unbounded
+        // depth, such as with tree wildcards.
+        let depth = token::component_upper_bound(tokens);
+        // Begin the query at the node of the invariant prefix of the glob, if
+        // any. The prefix is always matched by the leading components.
+        let prefix = PathBuf::from(token::invariant_text_prefix(tokens));
+        let mut text = String::new();
+        let node = self.get(keys(&prefix).map(|component| {
+            push(&mut text, &component.text);
+            component.text
+        }));
+        IndexMatches {
+            pattern: &glob.pattern,
+            components,
+            depth,
+            root: node,
+            stack: node
+                .map(|node| vec![(node.children.iter(), text.len())])
+                .unwrap_or_default(),
+            text,
+        }
+    }
+
+    fn get<I>(&self, keys: I) -> Option<&Node>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        keys.into_iter()
+            .try_fold(&self.root, |node, key| node.children.get(&key))
+    }
+}
+
+impl<P> Extend<P> for PathIndex
+where
+    P: AsRef<Path>,
+{
+    fn extend<I>(&mut self, paths: I)
+    where
+        I: IntoIterator<Item = P>,
+    {
+        for path in paths {
+            self.insert(path);
+        }
+    }
+}
+
+impl<P> FromIterator<P> for PathIndex
+where
+    P: AsRef<Path>,
+{
+    fn from_iter<I>(paths: I) -> Self
+    where
+        I: IntoIterator<Item = P>,
+    {
+        let mut index = PathIndex::new();
+        index.extend(paths);
+        index
+    }
+}
+
+/// Iterator over the paths in a [`PathIndex`] that match a [`Glob`].
+///
+/// This iterator is constructed by [`PathIndex::matched`].
+///
+/// [`Glob`]: crate::Glob
+/// [`PathIndex`]: crate::PathIndex
+/// [`PathIndex::matched`]: crate::PathIndex::matched
+#[derive(Clone, Debug)]
+pub struct IndexMatches<'i, 'g> {
+    pattern: &'g Regex,
+    components: Vec<Regex>,
+    depth: Option<usize>,
+    root: Option<&'i Node>,
+    stack: Vec<(btree_map::Iter<'i, String, Node>, usize)>,
+    text: String,
+}
+
+impl<'i, 'g> IndexMatches<'i, 'g> {
+    fn matched(&self) -> Option<(PathBuf, MatchedText<'static>)> {
+        self.pattern.captures(&self.text).map(|captures| {
+            (
+                PathBuf::from(&self.text),
+                MatchedText::from(captures).into_owned(),
+            )
+        })
+    }
+}
+
+impl<'i, 'g> Iterator for IndexMatches<'i, 'g> {
+    type Item = (PathBuf, MatchedText<'static>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // The node of the invariant prefix may itself be a matching path.
+        if let Some(node) = self.root.take() {
+            if node.is_path {
+                if let Some(matched) = self.matched() {
+                    return Some(matched);
+                }
+            }
+        }
+        loop {
+            let (children, len) = self.stack.last_mut()?;
+            let len = *len;
+            let (key, node) = match children.next() {
+                Some(child) => child,
+                None => {
+                    self.stack.pop();
+                    continue;
+                },
+            };
+            self.text.truncate(len);
+            if let Some(pattern) = node
+                .depth
+                .checked_sub(1)
+                .and_then(|index| self.components.get(index))
+            {
+                if !pattern.is_match(key) {
+                    // Do not descend into nodes that do not match the
+                    // corresponding component pattern.
+                    continue;
+                }
+            }
+            push(&mut self.text, key);
+            if self.depth.map_or(true, |depth| node.depth < depth) {
+                self.stack.push((node.children.iter(), self.text.len()));
+            }
+            if node.is_path {
+                if let Some(matched) = self.matched() {
+                    return Some(matched);
+                }
+            }
+        }
+    }
+}
+
+struct Key {
+    text: String,
+    is_normal: bool,
+}
+
+// Gets the keys of the components of a path in a `PathIndex`. Current directory
+// components are discarded and the text of components is encoded as it is in a
+// `CandidatePath`.
+fn keys(path: &Path) -> impl '_ + Iterator<Item = Key> {
+    path.components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .map(|component| Key {
+            text: CandidatePath::from(component.as_os_str())
+                .as_ref()
+                .to_owned(),
+            is_normal: !matches!(component, Component::Prefix(_) | Component::RootDir),
+        })
+}
+
+// Pushes the text of a component onto a path. Separators are not pushed
+// following a root component, which is itself a separator.
+fn push(text: &mut String, key: &str) {
+    if !(text.is_empty() || text.ends_with('/')) {
+        text.push('/');
+    }
+    text.push_str(key);
+}
+
+fn compile(tokens: &[Token<'_>]) -> Vec<Regex> {
+    let mut regexes = vec![];
+    for component in token::components(tokens) {
+        if component
+            .tokens()
+            .iter()
+            .any(|token| token.has_component_boundary())
+        {
+            // Stop at component boundaries, such as tree wildcards or any
+            // boundary within a group token.
+            break;
+        }
+        regexes.push(
+            Glob::compile(component.tokens().iter().copied())
+                .expect("failed to compile glob sub-expressions"),
+        );
+    }
+    regexes
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{Glob, PathIndex};
+
+    fn index() -> PathIndex {
+        [
+            "README.md",
+            "./src/lib.rs",
+            "src/token/mod.rs",
+            "src/token/parse.rs",
+            "tests/walk.rs",
+            "/etc/hosts",
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    fn paths(index: &PathIndex, expression: &str) -> Vec<PathBuf> {
+        let glob = Glob::new(expression).unwrap();
+        index.matched(&glob).map(|(path, _)| path).collect()
+    }
+
+    #[test]
+    fn insert_paths_into_index() {
+        let mut index = index();
+        assert_eq!(index.len(), 6);
+        assert!(index.contains("src/lib.rs"));
+        assert!(index.contains("./src//token/mod.rs"));
+        assert!(!index.contains("src"));
+        assert!(!index.insert("src/lib.rs"));
+        assert!(index.insert("src"));
+        assert!(index.contains("src"));
+        assert_eq!(index.len(), 7);
+    }
+
+    #[test]
+    fn match_index_with_glob() {
+        let index = index();
+        assert_eq!(
+            paths(&index, "src/**/*.rs"),
+            [
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/token/mod.rs"),
+                PathBuf::from("src/token/parse.rs"),
+            ],
+        );
+        assert_eq!(
+            paths(&index, "*/*.rs"),
+            [PathBuf::from("src/lib.rs"), PathBuf::from("tests/walk.rs")],
+        );
+        assert_eq!(paths(&index, "README.md"), [PathBuf::from("README.md")]);
+        assert_eq!(paths(&index, "/etc/*"), [PathBuf::from("/etc/hosts")]);
+        assert!(paths(&index, "doc/**").is_empty());
+    }
+
+    #[test]
+    fn match_index_with_glob_captures() {
+        let index = index();
+        let glob = Glob::new("src/**/{*}.rs").unwrap();
+        let names: Vec<_> = index
+            .matched(&glob)
+            .map(|(_, matched)| matched.get(2).unwrap().to_owned())
+            .collect();
+        assert_eq!(names, ["lib", "mod", "parse"]);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use crate::token::TokenKind;
+use crate::Glob;
+
+/// Shared storage for the literal text of [`Glob`]s.
+///
+/// Globs retain their token trees for diagnostics and analysis, and owned
+/// [`Glob`]s allocate the text of each of their literals. When many [`Glob`]s
+/// share common literals, such as `node_modules`, `target`, or file
+/// extensions, an `Interner` can store each distinct literal once. [`Glob`]s
+/// are interned in two steps: first the literals of each [`Glob`] are inserted
+/// into an `Interner` and then each [`Glob`] is rebound to borrow its literals
+/// from the `Interner` via [`Glob::intern`].
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, Interner};
+///
+/// let globs: Vec<_> = ["**/node_modules/**", "node_modules/*.js", "target/**"]
+///     .into_iter()
+///     .map(|expression| Glob::new(expression).unwrap().into_owned())
+///     .collect();
+///
+/// let mut interner = Interner::new();
+/// interner.extend(globs.iter());
+/// assert_eq!(interner.len(), 3);
+///
+/// let globs: Vec<_> = globs
+///     .into_iter()
+///     .map(|glob| glob.intern(&interner))
+///     .collect();
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::intern`]: crate::Glob::intern
+#[derive(Clone, Debug, Default)]
+pub struct Interner {
+    literals: HashSet<Box<str>>,
+}
+
+impl Interner {
+    /// Constructs an empty `Interner`.
+    pub fn new() -> Self {
+        Interner::default()
+    }
+
+    /// Inserts the literal text of a [`Glob`] into the interner.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn insert(&mut self, glob: &Glob<'_>) {
+        for (_, token) in glob.tree.as_ref().walk() {
+            if let TokenKind::Literal(ref literal) = token.kind() {
+                if !self.literals.contains(literal.text()) {
+                    self.literals.insert(literal.text().into());
+                }
+            }
+        }
+    }
+
+    /// Gets the number of distinct literals in the interner.
+    pub fn len(&self) -> usize {
+        self.literals.len()
+    }
+
+    /// Returns `true` if the interner has no literals.
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    pub(crate) fn get(&self, text: &str) -> Option<&str> {
+        self.literals.get(text).map(AsRef::as_ref)
+    }
+}
+
+impl<'g, 't> Extend<&'g Glob<'t>> for Interner
+where
+    't: 'g,
+{
+    fn extend<I>(&mut self, globs: I)
+    where
+        I: IntoIterator<Item = &'g Glob<'t>>,
+    {
+        for glob in globs {
+            self.insert(glob);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, Interner, Pattern};
+
+    #[test]
+    fn intern_glob_literals() {
+        let expressions = ["a/b/**/*.rs", "b/{a,c}/*.txt", "<a/:1,>b"];
+        let globs: Vec<_> = expressions
+            .iter()
+            .map(|expression| Glob::new(expression).unwrap().into_owned())
+            .collect();
+        let mut interner = Interner::new();
+        interner.extend(globs.iter());
+        assert_eq!(interner.len(), 5);
+
+        let globs: Vec<_> = globs
+            .into_iter()
+            .map(|glob| glob.intern(&interner))
+            .collect();
+        for (glob, expression) in globs.iter().zip(expressions) {
+            assert_eq!(glob.to_string(), expression);
+        }
+        assert!(globs[0].is_match("a/b/c/lib.rs"));
+        assert!(globs[1].is_match("b/c/notes.txt"));
+        assert!(globs[2].is_match("a/a/b"));
+    }
+}
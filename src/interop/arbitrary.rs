@@ -0,0 +1,169 @@
+use ::arbitrary::{Arbitrary, Error, Result, Unstructured};
+
+use crate::Glob;
+
+// Groups are nested no deeper than this to bound the size of expressions.
+const MAX_GROUP_DEPTH: usize = 2;
+
+const LITERAL_CHARACTERS: &[char] = &[
+    'a', 'b', 'c', 'x', 'y', 'z', 'A', 'B', 'Z', '0', '1', '9', '.', '-', '_', ' ',
+];
+const CLASS_CHARACTERS: &[char] = &['a', 'b', 'f', 'x', 'z', 'A', 'Z', '0', '9', '.'];
+const META_CHARACTERS: &[char] = &[
+    '?', '*', '$', ':', '<', '>', '(', ')', '[', ']', '{', '}', ',',
+];
+
+/// Generates a `Glob` from unstructured data.
+///
+/// Glob expressions are generated from a grammar that produces only valid
+/// expressions, including rooted paths, tree wildcards, character classes,
+/// alternatives, repetitions, and escaped literals. This can be used to fuzz
+/// code that consumes [`Glob`]s and the expression of a generated [`Glob`] can
+/// be recovered via its `Display` implementation (for example, for
+/// differential fuzzing against another matcher).
+///
+/// [`Glob`]: crate::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "arbitrary")))]
+impl<'a> Arbitrary<'a> for Glob<'static> {
+    fn arbitrary(u: &mut Unstructured<'a>) -> Result<Self> {
+        let expression = expression(u)?;
+        // The grammar only generates valid expressions, so this should never
+        // fail. Nevertheless, reject rather than panic if it does.
+        Glob::new(&expression)
+            .map(Glob::into_owned)
+            .map_err(|_| Error::IncorrectFormat)
+    }
+}
+
+fn expression(u: &mut Unstructured) -> Result<String> {
+    let mut expression = String::new();
+    let mut is_tree = false;
+    for n in 0..u.int_in_range(1..=4)? {
+        // The first component may be rooted.
+        if n > 0 || u.ratio(1, 4)? {
+            expression.push('/');
+        }
+        // Tree wildcards are never adjacent, as this is a rule violation.
+        if !is_tree && u.ratio(1, 4)? {
+            expression.push_str("**");
+            is_tree = true;
+        }
+        else {
+            sequence(u, &mut expression, 0)?;
+            is_tree = false;
+        }
+    }
+    Ok(expression)
+}
+
+// Pushes a sequence of tokens within a component. Zero-or-more wildcards are
+// never adjacent and never begin nor end a sequence within a group, so they
+// cannot be adjacent across group boundaries nor singular within a group.
+fn sequence(u: &mut Unstructured, expression: &mut String, depth: usize) -> Result<()> {
+    let n = u.int_in_range(1..=3)?;
+    let is_group = depth > 0;
+    let mut is_zom = is_group;
+    for m in 0..n {
+        let is_last = m + 1 == n;
+        match u.int_in_range(0..=5)? {
+            0 if !(is_zom || (is_group && is_last)) => {
+                expression.push(*u.choose(&['*', '$'])?);
+                is_zom = true;
+                continue;
+            },
+            1 => expression.push('?'),
+            2 => class(u, expression)?,
+            3 if depth < MAX_GROUP_DEPTH => alternative(u, expression, depth + 1)?,
+            4 if depth < MAX_GROUP_DEPTH => repetition(u, expression, depth + 1)?,
+            _ => literal(u, expression)?,
+        }
+        is_zom = false;
+    }
+    Ok(())
+}
+
+fn literal(u: &mut Unstructured, expression: &mut String) -> Result<()> {
+    for _ in 0..u.int_in_range(1..=4)? {
+        if u.ratio(1, 8)? {
+            expression.push('\\');
+            expression.push(*u.choose(META_CHARACTERS)?);
+        }
+        else {
+            expression.push(*u.choose(LITERAL_CHARACTERS)?);
+        }
+    }
+    Ok(())
+}
+
+fn class(u: &mut Unstructured, expression: &mut String) -> Result<()> {
+    expression.push('[');
+    if u.arbitrary()? {
+        expression.push('!');
+    }
+    for _ in 0..u.int_in_range(1..=2)? {
+        let left = *u.choose(CLASS_CHARACTERS)?;
+        expression.push(left);
+        if u.arbitrary()? {
+            let right = *u.choose(CLASS_CHARACTERS)?;
+            expression.push('-');
+            expression.push(left.max(right));
+        }
+    }
+    expression.push(']');
+    Ok(())
+}
+
+fn alternative(u: &mut Unstructured, expression: &mut String, depth: usize) -> Result<()> {
+    expression.push('{');
+    for n in 0..u.int_in_range(2..=3)? {
+        if n > 0 {
+            expression.push(',');
+        }
+        sequence(u, expression, depth)?;
+    }
+    expression.push('}');
+    Ok(())
+}
+
+fn repetition(u: &mut Unstructured, expression: &mut String, depth: usize) -> Result<()> {
+    expression.push('<');
+    sequence(u, expression, depth)?;
+    match u.int_in_range(0..=2)? {
+        0 => {},
+        1 => expression.push_str(&format!(":{}", u.int_in_range(1..=3usize)?)),
+        _ => {
+            let lower = u.int_in_range(0..=2usize)?;
+            let upper = u.int_in_range(lower.max(1)..=3)?;
+            expression.push_str(&format!(":{},{}", lower, upper));
+        },
+    }
+    expression.push('>');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use arbitrary::{Arbitrary, Unstructured};
+
+    use crate::Glob;
+
+    #[test]
+    fn build_arbitrary_glob() {
+        // Generate data with a simple linear congruential generator.
+        let mut state = 0x2545_f491_u32;
+        let data: Vec<u8> = (0..1 << 14)
+            .map(|_| {
+                state = state.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                (state >> 16) as u8
+            })
+            .collect();
+        let mut u = Unstructured::new(&data);
+        let mut n = 0;
+        while !u.is_empty() {
+            let glob = Glob::arbitrary(&mut u).unwrap();
+            Glob::new(glob.to_string().as_str()).unwrap();
+            n += 1;
+        }
+        assert!(n > 10);
+    }
+}
@@ -0,0 +1,46 @@
+use regex::Regex;
+use std::path::{Component, Path, PathBuf};
+
+use crate::capture::MatchedText;
+use crate::{CandidatePath, Glob};
+
+/// Matches the paths of archive entries against a [`Glob`].
+///
+/// Entries are first compared against the invariant prefix of the glob, which
+/// cheaply discards entries in unrelated directories before the pattern is
+/// matched.
+///
+/// [`Glob`]: crate::Glob
+#[derive(Clone, Debug)]
+pub struct EntryFilter {
+    pattern: Regex,
+    prefix: String,
+}
+
+impl EntryFilter {
+    pub fn new(glob: &Glob<'_>) -> Self {
+        let (prefix, _) = glob.clone().partition();
+        let prefix = CandidatePath::from(prefix.as_path()).as_ref().to_owned();
+        EntryFilter {
+            pattern: glob.pattern.clone(),
+            prefix,
+        }
+    }
+
+    pub fn matched(&self, path: &Path) -> Option<MatchedText<'static>> {
+        // Archive paths are often written with a leading current directory
+        // component (`./`) and a trailing separator for directories. These are
+        // discarded, so that entries are matched as relative paths.
+        let path: PathBuf = path
+            .components()
+            .filter(|component| !matches!(component, Component::CurDir))
+            .collect();
+        let path = CandidatePath::from(path.as_path());
+        if !path.as_ref().starts_with(&self.prefix) {
+            return None;
+        }
+        self.pattern
+            .captures(path.as_ref())
+            .map(|captures| MatchedText::from(captures).into_owned())
+    }
+}
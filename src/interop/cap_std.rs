@@ -0,0 +1,248 @@
+use ::cap_std::fs::{Dir, Metadata as DirMetadata};
+use std::ffi::OsStr;
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError};
+use std::time::SystemTime;
+
+use crate::walk::{FileType, Filesystem, Metadata};
+
+type Handle = (PathBuf, Arc<Dir>);
+
+impl FileType for ::cap_std::fs::FileType {
+    fn is_dir(&self) -> bool {
+        ::cap_std::fs::FileType::is_dir(self)
+    }
+
+    fn is_symlink(&self) -> bool {
+        ::cap_std::fs::FileType::is_symlink(self)
+    }
+}
+
+impl Metadata for DirMetadata {
+    type FileType = ::cap_std::fs::FileType;
+
+    fn file_type(&self) -> Self::FileType {
+        DirMetadata::file_type(self)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.len())
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        DirMetadata::modified(self)
+            .ok()
+            .map(::cap_std::time::SystemTime::into_std)
+    }
+}
+
+/// [`Filesystem`] that reads a directory tree from an open [`Dir`].
+///
+/// `DirFilesystem` reads directory trees from a [`cap_std`] directory handle
+/// rather than from paths, so programs can match [`Glob`]s against directory
+/// trees without ambient authority. Files outside of the directory cannot be
+/// read, even via `..` components or symbolic links.
+///
+/// Paths are relative to the directory and the empty path refers to the
+/// directory itself, so the directory given to functions like
+/// [`Glob::walk_with_filesystem`] is typically empty. Directories are read by
+/// opening each subdirectory relative to the open handle of its parent, so
+/// paths are not resolved again as a traversal descends into a directory tree.
+/// Handles are retained only for the directories that are being read.
+///
+/// A handle can be constructed from a native file descriptor or handle via
+/// functions like [`Dir::from_std_file`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use cap_std::ambient_authority;
+/// use cap_std::fs::Dir;
+/// use wax::{DirFilesystem, Glob, WalkBehavior};
+///
+/// let dir = Dir::open_ambient_dir("./Pictures", ambient_authority()).unwrap();
+/// let glob = Glob::new("**/*.(?i){jpg,jpeg}").unwrap();
+/// let filesystem = DirFilesystem::new(dir);
+/// for entry in glob.walk_with_filesystem(filesystem, "", WalkBehavior::default()) {
+///     let entry = entry.unwrap();
+///     println!("JPEG: {:?}", entry.path());
+/// }
+/// ```
+///
+/// [`cap_std`]: cap_std
+/// [`Dir`]: cap_std::fs::Dir
+/// [`Dir::from_std_file`]: cap_std::fs::Dir::from_std_file
+/// [`Filesystem`]: crate::Filesystem
+/// [`Glob`]: crate::Glob
+/// [`Glob::walk_with_filesystem`]: crate::Glob::walk_with_filesystem
+#[cfg_attr(docsrs, doc(cfg(feature = "cap-std")))]
+#[derive(Clone)]
+pub struct DirFilesystem {
+    root: Arc<Dir>,
+    // Handles of the directories that are being read, ordered by depth. This
+    // is the path from the root to the most recently read directory.
+    handles: Arc<Mutex<Vec<Handle>>>,
+}
+
+impl DirFilesystem {
+    /// Constructs a `DirFilesystem` that reads directory trees from the given
+    /// [`Dir`].
+    ///
+    /// [`Dir`]: cap_std::fs::Dir
+    pub fn new(root: Dir) -> Self {
+        DirFilesystem {
+            root: Arc::new(root),
+            handles: Default::default(),
+        }
+    }
+
+    /// Gets the root directory.
+    pub fn root(&self) -> &Dir {
+        &self.root
+    }
+
+    // Gets the handle of the directory that contains the given path and the
+    // name of the file in that directory. If the handle of the parent
+    // directory is not open, then the root and the entire path are used.
+    fn parent<'p>(&self, path: &'p Path) -> (Arc<Dir>, &'p Path) {
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+            if parent.as_os_str().is_empty() {
+                return (self.root.clone(), Path::new(name));
+            }
+            let handles = self.handles.lock().unwrap_or_else(PoisonError::into_inner);
+            if let Some((_, handle)) = handles.iter().rev().find(|(other, _)| other == parent) {
+                return (handle.clone(), Path::new(name));
+            }
+        }
+        (self.root.clone(), path)
+    }
+
+    fn open_dir(&self, path: &Path) -> io::Result<Arc<Dir>> {
+        if is_root(path) {
+            Ok(self.root.clone())
+        }
+        else {
+            let (parent, path) = self.parent(path);
+            parent.open_dir(path).map(Arc::new)
+        }
+    }
+}
+
+impl Debug for DirFilesystem {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("DirFilesystem")
+            .field("root", &self.root)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Filesystem for DirFilesystem {
+    type FileType = ::cap_std::fs::FileType;
+    type Metadata = DirMetadata;
+    type ReadDir = DirEntries;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+        let handle = self.open_dir(path)?;
+        let entries = handle.entries()?;
+        let mut handles = self.handles.lock().unwrap_or_else(PoisonError::into_inner);
+        // Directories are read depth-first, so only the ancestors of the
+        // directory remain open.
+        handles.retain(|(ancestor, _)| path.starts_with(ancestor) && ancestor != path);
+        handles.push((path.to_path_buf(), handle));
+        Ok(DirEntries {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        if is_root(path) {
+            self.root.dir_metadata()
+        }
+        else {
+            let (parent, path) = self.parent(path);
+            parent.metadata(path)
+        }
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        if is_root(path) {
+            self.root.dir_metadata()
+        }
+        else {
+            let (parent, path) = self.parent(path);
+            parent.symlink_metadata(path)
+        }
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        if is_root(path) {
+            Ok(PathBuf::new())
+        }
+        else {
+            self.root.canonicalize(path)
+        }
+    }
+}
+
+/// Iterator over the entries in a directory of a [`DirFilesystem`].
+///
+/// [`DirFilesystem`]: crate::DirFilesystem
+#[cfg_attr(docsrs, doc(cfg(feature = "cap-std")))]
+#[derive(Debug)]
+pub struct DirEntries {
+    path: PathBuf,
+    entries: ::cap_std::fs::ReadDir,
+}
+
+impl Iterator for DirEntries {
+    type Item = io::Result<(PathBuf, ::cap_std::fs::FileType, Option<DirMetadata>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.entries.next().map(|entry| {
+            let entry = entry?;
+            Ok((self.path.join(entry.file_name()), entry.file_type()?, None))
+        })
+    }
+}
+
+fn is_root(path: &Path) -> bool {
+    path.as_os_str().is_empty() || path.as_os_str() == OsStr::new(".")
+}
+
+#[cfg(test)]
+mod tests {
+    use ::cap_std::ambient_authority;
+    use ::cap_std::fs::Dir;
+    use std::collections::HashSet;
+    use std::path::PathBuf;
+
+    use crate::{DirFilesystem, Glob, WalkBehavior};
+
+    #[test]
+    fn walk_dir_filesystem() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = Dir::open_ambient_dir(root.path(), ambient_authority()).unwrap();
+        dir.create_dir_all("src/walk").unwrap();
+        dir.create_dir("doc").unwrap();
+        for path in ["src/lib.rs", "src/walk/mod.rs", "doc/guide.md"] {
+            dir.write(path, b"").unwrap();
+        }
+
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let paths: HashSet<_> = glob
+            .walk_with_filesystem(DirFilesystem::new(dir), "", WalkBehavior::default())
+            .map(|entry| entry.unwrap().into_path())
+            .collect();
+        assert_eq!(
+            paths,
+            ["src/lib.rs", "src/walk/mod.rs"]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use ::clap::builder::{TypedValueParser, ValueParserFactory};
+use ::clap::error::ErrorKind;
+use ::clap::{Arg, Command, Error};
+use std::ffi::OsStr;
+use std::fmt::Write as _;
+
+use crate::{BuildError, Glob};
+
+/// [`clap`] value parser for [`Glob`]s.
+///
+/// This parser builds a [`Glob`] from an argument. If the glob expression
+/// fails to build, then the error includes the expression with the locations
+/// of any errors annotated beneath it. This parser is also used by
+/// `clap::value_parser!(Glob<'static>)`.
+///
+/// # Examples
+///
+/// ```rust
+/// use clap::{Arg, Command};
+/// use wax::{Glob, GlobValueParser, Pattern};
+///
+/// let command = Command::new("find").arg(Arg::new("pattern").value_parser(GlobValueParser));
+/// let matches = command.get_matches_from(["find", "**/*.rs"]);
+/// let glob = matches.get_one::<Glob<'static>>("pattern").unwrap();
+/// assert!(glob.is_match("src/lib.rs"));
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "clap")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct GlobValueParser;
+
+impl TypedValueParser for GlobValueParser {
+    type Value = Glob<'static>;
+
+    fn parse_ref(
+        &self,
+        command: &Command,
+        arg: Option<&Arg>,
+        value: &OsStr,
+    ) -> Result<Self::Value, Error> {
+        let expression = value
+            .to_str()
+            .ok_or_else(|| Error::new(ErrorKind::InvalidUtf8).with_cmd(command))?;
+        Glob::new(expression)
+            .map(Glob::into_owned)
+            .map_err(|error| {
+                let arg = arg.map_or_else(|| String::from("..."), ToString::to_string);
+                Error::raw(
+                    ErrorKind::ValueValidation,
+                    format!(
+                        "invalid glob expression `{}` for `{}`: {}{}\n",
+                        expression,
+                        arg,
+                        error,
+                        annotate(expression, &error),
+                    ),
+                )
+                .with_cmd(command)
+            })
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "clap")))]
+impl ValueParserFactory for Glob<'static> {
+    type Parser = GlobValueParser;
+
+    fn value_parser() -> Self::Parser {
+        GlobValueParser
+    }
+}
+
+// Renders the expression with the spans of located errors underlined. Spans are
+// measured in bytes, but are rendered in code points.
+fn annotate(expression: &str, error: &BuildError) -> String {
+    let mut annotation = String::new();
+    for location in error.locations() {
+        let (start, n) = location.span();
+        let indent = expression[..start].chars().count();
+        let width = expression[start..][..n].chars().count().max(1);
+        let _ = write!(
+            annotation,
+            "\n\n    {}\n    {}{} {}",
+            expression,
+            " ".repeat(indent),
+            "^".repeat(width),
+            location,
+        );
+    }
+    annotation
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::error::ErrorKind;
+    use clap::{value_parser, Arg, Command};
+
+    use crate::{Glob, GlobValueParser, Pattern};
+
+    fn command() -> Command {
+        Command::new("wax").arg(Arg::new("glob").value_parser(GlobValueParser))
+    }
+
+    #[test]
+    fn parse_glob_argument() {
+        let matches = command().try_get_matches_from(["wax", "**/*.rs"]).unwrap();
+        let glob = matches.get_one::<Glob<'static>>("glob").unwrap();
+        assert!(glob.is_match("src/lib.rs"));
+
+        let matches = Command::new("wax")
+            .arg(Arg::new("glob").value_parser(value_parser!(Glob<'static>)))
+            .try_get_matches_from(["wax", "*.md"])
+            .unwrap();
+        assert!(matches
+            .get_one::<Glob<'static>>("glob")
+            .unwrap()
+            .is_match("README.md"));
+    }
+
+    #[test]
+    fn reject_invalid_glob_argument() {
+        let error = command()
+            .try_get_matches_from(["wax", "**/{foo,**/bar}"])
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::ValueValidation);
+        let message = error.to_string();
+        assert!(message.contains("**/{foo,**/bar}"));
+        assert!(message.contains('^'));
+    }
+}
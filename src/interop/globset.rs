@@ -0,0 +1,414 @@
+#[cfg(feature = "miette")]
+use miette::Diagnostic;
+use std::convert::TryFrom;
+use std::iter::Peekable;
+use std::str::Chars;
+use thiserror::Error;
+
+use crate::token::{Archetype, Class, Token, TokenKind, TokenTree, Wildcard};
+use crate::{BuildError, Glob, StrExt as _};
+
+/// Error that occurs when converting a [`Glob`] into a [`globset::Glob`].
+///
+/// [`Glob`]: crate::Glob
+/// [`globset::Glob`]: globset::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "globset")))]
+#[cfg_attr(feature = "miette", derive(Diagnostic))]
+#[derive(Debug, Error)]
+#[non_exhaustive]
+pub enum GlobsetError {
+    /// The glob expression cannot be expressed in the `globset` dialect.
+    ///
+    /// This occurs if a glob expression contains repetitions or mixes case
+    /// sensitivity, for example.
+    #[error("glob expression has no `globset` equivalent: {0}")]
+    #[cfg_attr(feature = "miette", diagnostic(code = "wax::globset::unsupported"))]
+    Unsupported(&'static str),
+    /// The `globset` expression failed to build.
+    #[error(transparent)]
+    #[cfg_attr(feature = "miette", diagnostic(code = "wax::globset::build"))]
+    Build(::globset::Error),
+}
+
+/// Converts a [`globset::Glob`] into a [`Glob`].
+///
+/// The `globset` expression is translated into an equivalent glob expression.
+/// Options given to a [`globset::GlobBuilder`] are not available to this
+/// conversion, so the defaults of [`globset::Glob::new`] are assumed. Note that
+/// `globset` wildcards match path separators by default, but wildcards in
+/// [`Glob`]s never do.
+///
+/// # Errors
+///
+/// Returns an error if the translated glob expression fails to build. This
+/// occurs if the `globset` expression uses features that are rejected by Wax,
+/// such as literal backslashes or adjacent wildcards.
+///
+/// [`Glob`]: crate::Glob
+/// [`globset::Glob`]: globset::Glob
+/// [`globset::Glob::new`]: globset::Glob::new
+/// [`globset::GlobBuilder`]: globset::GlobBuilder
+#[cfg_attr(docsrs, doc(cfg(feature = "globset")))]
+impl TryFrom<&'_ ::globset::Glob> for Glob<'static> {
+    type Error = BuildError;
+
+    fn try_from(glob: &::globset::Glob) -> Result<Self, Self::Error> {
+        let expression = translate(glob.glob());
+        Glob::new(&expression).map(Glob::into_owned)
+    }
+}
+
+/// Converts a [`Glob`] into a [`globset::Glob`].
+///
+/// The glob expression is translated into an equivalent `globset` expression
+/// that is built with literal separators, so that wildcards do not match path
+/// separators as in Wax. Note that the semantics of tree wildcards `**` differ
+/// slightly: `globset` does not match the parent of a trailing tree wildcard.
+/// For example, `a/**` matches `a` in Wax, but not in `globset`.
+///
+/// # Errors
+///
+/// Returns an error if the glob expression has no equivalent in `globset`,
+/// such as expressions with repetitions or literals with differing case
+/// sensitivity.
+///
+/// [`Glob`]: crate::Glob
+/// [`globset::Glob`]: globset::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "globset")))]
+impl TryFrom<&'_ Glob<'_>> for ::globset::Glob {
+    type Error = GlobsetError;
+
+    fn try_from(glob: &Glob<'_>) -> Result<Self, Self::Error> {
+        let mut expression = String::new();
+        let mut is_case_insensitive = None;
+        encode(
+            glob.tree.as_ref().tokens(),
+            &mut expression,
+            &mut is_case_insensitive,
+        )?;
+        ::globset::GlobBuilder::new(&expression)
+            .literal_separator(true)
+            .backslash_escape(true)
+            .case_insensitive(is_case_insensitive.unwrap_or(false))
+            .build()
+            .map_err(GlobsetError::Build)
+    }
+}
+
+fn translate(expression: &str) -> String {
+    fn push_literal(translated: &mut String, x: char) {
+        if crate::is_meta_character(x) {
+            translated.push('\\');
+        }
+        translated.push(x);
+    }
+
+    fn translate_class(chars: &mut Peekable<Chars>, translated: &mut String) {
+        translated.push('[');
+        if let Some('!' | '^') = chars.peek() {
+            chars.next();
+            translated.push('!');
+        }
+        // Read the class as `globset` does: a leading `]` or leading or
+        // trailing `-` is a literal and there are no escapes.
+        let mut ranges: Vec<(char, char)> = vec![];
+        let mut is_range = false;
+        for x in chars.by_ref() {
+            match x {
+                ']' if !ranges.is_empty() => {
+                    break;
+                },
+                '-' if !ranges.is_empty() && !is_range => {
+                    is_range = true;
+                    continue;
+                },
+                x if is_range => {
+                    if let Some(range) = ranges.last_mut() {
+                        range.1 = x;
+                    }
+                },
+                x => ranges.push((x, x)),
+            }
+            is_range = false;
+        }
+        if is_range {
+            ranges.push(('-', '-'));
+        }
+        for (left, right) in ranges {
+            for (n, x) in [left, right].into_iter().enumerate() {
+                if n > 0 {
+                    if left == right {
+                        break;
+                    }
+                    translated.push('-');
+                }
+                if matches!(x, '[' | ']' | '-') {
+                    translated.push('\\');
+                }
+                translated.push(x);
+            }
+        }
+        translated.push(']');
+    }
+
+    let mut translated = String::with_capacity(expression.len());
+    let mut depth = 0usize;
+    let mut previous = None;
+    let mut chars = expression.chars().peekable();
+    while let Some(x) = chars.next() {
+        match x {
+            // `globset` interprets backslashes as separators on Windows and as
+            // escapes on other platforms by default.
+            '\\' if cfg!(windows) => translated.push('/'),
+            '\\' => {
+                if let Some(x) = chars.next() {
+                    push_literal(&mut translated, x);
+                    previous = Some(x);
+                    continue;
+                }
+            },
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `globset` interprets `**` as `*` unless it is delimited by
+                // component boundaries. Wax rejects such expressions.
+                let is_boundary = |x: Option<char>, delimiters: [char; 2]| {
+                    x.map_or(true, |x| x == '/' || (depth > 0 && delimiters.contains(&x)))
+                };
+                if is_boundary(previous, ['{', ','])
+                    && is_boundary(chars.peek().copied(), [',', '}'])
+                {
+                    translated.push_str("**");
+                }
+                else {
+                    translated.push('*');
+                }
+            },
+            '*' | '?' => translated.push(x),
+            '[' => translate_class(&mut chars, &mut translated),
+            '{' => {
+                depth += 1;
+                translated.push(x);
+            },
+            '}' | ',' if depth > 0 => {
+                if x == '}' {
+                    depth -= 1;
+                }
+                translated.push(x);
+            },
+            x => push_literal(&mut translated, x),
+        }
+        previous = Some(x);
+    }
+    translated
+}
+
+fn encode<A>(
+    tokens: &[Token<'_, A>],
+    expression: &mut String,
+    is_case_insensitive: &mut Option<bool>,
+) -> Result<(), GlobsetError> {
+    for (n, token) in tokens.iter().enumerate() {
+        match token.kind() {
+            TokenKind::Alternative(alternative) => {
+                expression.push('{');
+                for (n, branch) in alternative.branches().iter().enumerate() {
+                    if n > 0 {
+                        expression.push(',');
+                    }
+                    encode(branch, expression, is_case_insensitive)?;
+                }
+                expression.push('}');
+            },
+            TokenKind::Class(class) => encode_class(class, expression)?,
+            TokenKind::Literal(literal) => {
+                if literal.text().has_casing() {
+                    // `globset` can only configure case sensitivity for an
+                    // entire expression.
+                    let casing = literal.is_case_insensitive();
+                    if *is_case_insensitive.get_or_insert(casing) != casing {
+                        return Err(GlobsetError::Unsupported("mixed case sensitivity"));
+                    }
+                }
+                for x in literal.text().chars() {
+                    if matches!(x, '?' | '*' | '[' | ']' | '{' | '}' | ',' | '\\') {
+                        expression.push('\\');
+                    }
+                    expression.push(x);
+                }
+            },
+            TokenKind::Repetition(_) => {
+                return Err(GlobsetError::Unsupported("repetition"));
+            },
+            TokenKind::Separator(_) => expression.push('/'),
+            TokenKind::Wildcard(Wildcard::One { is_grapheme: false }) => expression.push('?'),
+            TokenKind::Wildcard(Wildcard::One { is_grapheme: true }) => {
+                return Err(GlobsetError::Unsupported("grapheme cluster wildcard"));
+            },
+            TokenKind::Wildcard(Wildcard::ZeroOrMore {
+                is_separator_crossing: false,
+                ..
+            }) => expression.push('*'),
+            TokenKind::Wildcard(Wildcard::ZeroOrMore {
+                is_separator_crossing: true,
+                ..
+            }) => {
+                return Err(GlobsetError::Unsupported("separator-crossing wildcard"));
+            },
+            TokenKind::Wildcard(Wildcard::Tree { has_root }) => {
+                // Tree tokens include their delimiting separators, which are
+                // implied by their position.
+                if *has_root {
+                    expression.push('/');
+                }
+                expression.push_str("**");
+                if n + 1 < tokens.len() {
+                    expression.push('/');
+                }
+            },
+        }
+    }
+    Ok(())
+}
+
+fn encode_class(class: &Class, expression: &mut String) -> Result<(), GlobsetError> {
+    const UNSUPPORTED: GlobsetError = GlobsetError::Unsupported("character class");
+
+    // `globset` has no escapes in character classes, so `]` must appear first
+    // and `-` must appear last. `!` and `^` cannot appear first unless the
+    // class is negated.
+    let mut has_bracket = false;
+    let mut has_hyphen = false;
+    let mut archetypes = vec![];
+    for archetype in class.archetypes() {
+        match archetype {
+            Archetype::Character(']') => has_bracket = true,
+            Archetype::Character('-') => has_hyphen = true,
+            Archetype::Range(left, right)
+                if [left, right].into_iter().any(|x| matches!(x, ']' | '-')) =>
+            {
+                return Err(UNSUPPORTED);
+            },
+            _ => archetypes.push(archetype),
+        }
+    }
+    let is_negation_character = |archetype: &&Archetype| match archetype {
+        Archetype::Character(left) | Archetype::Range(left, _) => matches!(left, '!' | '^'),
+    };
+    let mut leading_hyphen = false;
+    if !class.is_negated()
+        && !has_bracket
+        && archetypes.first().map_or(false, is_negation_character)
+    {
+        if let Some(n) = archetypes.iter().position(|archetype| !is_negation_character(archetype)) {
+            archetypes.swap(0, n);
+        }
+        else if has_hyphen {
+            leading_hyphen = true;
+        }
+        else {
+            return Err(UNSUPPORTED);
+        }
+    }
+
+    expression.push('[');
+    if class.is_negated() {
+        expression.push('!');
+    }
+    if has_bracket {
+        expression.push(']');
+    }
+    if leading_hyphen {
+        expression.push('-');
+    }
+    for archetype in archetypes {
+        match archetype {
+            Archetype::Character(x) => expression.push(*x),
+            Archetype::Range(left, right) => {
+                expression.push(*left);
+                expression.push('-');
+                expression.push(*right);
+            },
+        }
+    }
+    if has_hyphen && !leading_hyphen {
+        expression.push('-');
+    }
+    expression.push(']');
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use crate::interop::GlobsetError;
+    use crate::{Glob, Pattern};
+
+    fn from_globset(expression: &str) -> Glob<'static> {
+        Glob::try_from(&globset::Glob::new(expression).unwrap()).unwrap()
+    }
+
+    fn into_globset(expression: &str) -> Result<globset::Glob, GlobsetError> {
+        globset::Glob::try_from(&Glob::new(expression).unwrap())
+    }
+
+    #[test]
+    fn convert_from_globset_glob() {
+        assert!(from_globset("**/*.rs").is_match("src/lib.rs"));
+        assert!(from_globset("src/{lib,main}.rs").is_match("src/main.rs"));
+        assert!(from_globset("[!a-c]x").is_match("dx"));
+        assert!(from_globset("[]-]").is_match("-"));
+        assert!(from_globset("a,b").is_match("a,b"));
+        assert!(from_globset("(a):b$").is_match("(a):b$"));
+        assert!(from_globset("foo**").is_match("foobar"));
+
+        assert!(!from_globset("[!a-c]x").is_match("ax"));
+        assert!(!from_globset("foo**").is_match("foo/bar"));
+    }
+
+    #[cfg(not(windows))]
+    #[test]
+    fn convert_from_globset_glob_with_escapes() {
+        assert!(from_globset(r"\*.txt").is_match("*.txt"));
+        assert!(from_globset(r"\{a\}").is_match("{a}"));
+
+        assert!(!from_globset(r"\*.txt").is_match("a.txt"));
+    }
+
+    #[test]
+    fn convert_into_globset_glob() {
+        for (expression, converted) in [
+            ("**/*.rs", "**/*.rs"),
+            ("/**/*.rs", "/**/*.rs"),
+            ("src/**/lib.rs", "src/**/lib.rs"),
+            ("src/**", "src/**"),
+            ("{a,b/**/c}.txt", "{a,b/**/c}.txt"),
+            ("[!a-c]?", "[!a-c]?"),
+            ("[\\-a\\]]", "[]a-]"),
+            ("[!a]", "[!a]"),
+            ("[^a]", "[a^]"),
+            ("a\\{b\\}", "a\\{b\\}"),
+            ("a\\,b", "a\\,b"),
+        ] {
+            assert_eq!(into_globset(expression).unwrap().glob(), converted);
+        }
+
+        let matcher = into_globset("src/**/*.rs").unwrap().compile_matcher();
+        assert!(matcher.is_match("src/glob/lib.rs"));
+        assert!(!matcher.is_match("tests/lib.rs"));
+        let matcher = into_globset("*.rs").unwrap().compile_matcher();
+        assert!(!matcher.is_match("src/lib.rs"));
+    }
+
+    #[test]
+    fn reject_into_globset_glob_with_unsupported_syntax() {
+        assert!(matches!(
+            into_globset("<a:1,>"),
+            Err(GlobsetError::Unsupported(_))
+        ));
+        assert!(matches!(
+            into_globset("(?i)a(?-i)b"),
+            Err(GlobsetError::Unsupported(_))
+        ));
+    }
+}
@@ -0,0 +1,194 @@
+use ::ignore::DirEntry;
+use regex::Regex;
+use std::path::Path;
+
+use crate::dispatch::PrefixDispatch;
+use crate::token::{self, TokenTree};
+use crate::{Any, CandidatePath, Glob};
+
+/// Filters directory entries read by [`ignore`] walkers against a [`Glob`] or
+/// [`Any`].
+///
+/// `IgnoreFilter` can be used with [`WalkBuilder::filter_entry`], so that both
+/// [`Walk`] and [`WalkParallel`] apply the stack of ignore files and wax
+/// patterns in a single traversal. Patterns are matched against paths relative
+/// to the root directories of the traversal.
+///
+/// Files are accepted if they match the pattern. Directories are accepted if
+/// they may contain matching files, which allows the traversal to descend into
+/// them. For a [`Glob`], directories are discarded (not read from the file
+/// system) if they do not match the leading components of the glob expression.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use ignore::{WalkBuilder, WalkState};
+/// use wax::{Glob, IgnoreFilter};
+///
+/// let glob = Glob::new("src/**/*.rs").unwrap();
+/// let filter = IgnoreFilter::from(&glob);
+/// WalkBuilder::new(".")
+///     .filter_entry(move |entry| filter.is_match(entry))
+///     .build_parallel()
+///     .run(|| {
+///         Box::new(|entry| {
+///             if let Ok(entry) = entry {
+///                 if entry.file_type().map_or(false, |file_type| file_type.is_file()) {
+///                     println!("{:?}", entry.path());
+///                 }
+///             }
+///             WalkState::Continue
+///         })
+///     });
+/// ```
+///
+/// [`Any`]: crate::Any
+/// [`Glob`]: crate::Glob
+/// [`Walk`]: ignore::Walk
+/// [`WalkBuilder::filter_entry`]: ignore::WalkBuilder::filter_entry
+/// [`WalkParallel`]: ignore::WalkParallel
+#[cfg_attr(docsrs, doc(cfg(feature = "ignore")))]
+#[derive(Clone, Debug)]
+pub struct IgnoreFilter {
+    pattern: PrefixDispatch,
+    components: Vec<Regex>,
+}
+
+impl IgnoreFilter {
+    /// Returns `true` if the directory entry is accepted by the filter.
+    ///
+    /// The root directories of the traversal are always accepted.
+    pub fn is_match(&self, entry: &DirEntry) -> bool {
+        let depth = entry.depth();
+        if depth == 0 {
+            return true;
+        }
+        // Strip the root directory from the path, which may be any of the
+        // paths given to the walker.
+        let mut path = entry.path();
+        for _ in 0..depth {
+            path = path.parent().unwrap_or_else(|| Path::new(""));
+        }
+        let path = entry
+            .path()
+            .strip_prefix(path)
+            .expect("path is not in tree");
+        if entry
+            .file_type()
+            .map_or(false, |file_type| file_type.is_dir())
+        {
+            path.iter()
+                .zip(self.components.iter())
+                .all(|(component, pattern)| {
+                    pattern.is_match(CandidatePath::from(component).as_ref())
+                })
+        }
+        else {
+            self.pattern.is_match(CandidatePath::from(path).as_ref())
+        }
+    }
+}
+
+impl<'t> From<&'_ Any<'t>> for IgnoreFilter {
+    fn from(any: &Any<'t>) -> Self {
+        IgnoreFilter {
+            pattern: any.pattern.clone(),
+            components: vec![],
+        }
+    }
+}
+
+impl<'t> From<&'_ Glob<'t>> for IgnoreFilter {
+    fn from(glob: &Glob<'t>) -> Self {
+        let mut components = vec![];
+        for component in token::components(glob.tree.as_ref().tokens()) {
+            if component
+                .tokens()
+                .iter()
+                .any(|token| token.has_component_boundary())
+            {
+                // Stop at component boundaries, such as tree wildcards or any
+                // boundary within a group token.
+                break;
+            }
+            components.push(
+                Glob::compile(component.tokens().iter().copied())
+                    .expect("failed to compile glob sub-expressions"),
+            );
+        }
+        IgnoreFilter {
+            pattern: glob.pattern.clone().into(),
+            components,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use build_fs_tree::{dir, file, Build, FileSystemTree};
+    use ignore::WalkBuilder;
+    use std::path::{Path, PathBuf};
+    use tempfile::TempDir;
+
+    use crate::{Glob, IgnoreFilter};
+
+    fn temptree() -> (TempDir, PathBuf) {
+        let tree: FileSystemTree<&str, &str> = dir! {
+            ".ignore" => file!("ignored.rs\n"),
+            "README.md" => file!(""),
+            "src" => dir! {
+                "lib.rs" => file!(""),
+                "ignored.rs" => file!(""),
+                "token" => dir! {
+                    "parse.rs" => file!(""),
+                },
+            },
+            "tests" => dir! {
+                "walk.rs" => file!(""),
+            },
+        };
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("project");
+        tree.build(&path).unwrap();
+        (root, path)
+    }
+
+    fn paths(path: &Path, filter: IgnoreFilter) -> Vec<PathBuf> {
+        let mut paths: Vec<_> = WalkBuilder::new(path)
+            .filter_entry(move |entry| filter.is_match(entry))
+            .build()
+            .map(|entry| entry.unwrap())
+            .filter(|entry| {
+                entry
+                    .file_type()
+                    .map_or(false, |file_type| file_type.is_file())
+            })
+            .map(|entry| entry.path().strip_prefix(path).unwrap().to_path_buf())
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn filter_ignore_walk_with_glob() {
+        let (_root, path) = temptree();
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        assert_eq!(
+            paths(&path, IgnoreFilter::from(&glob)),
+            [
+                PathBuf::from("src/lib.rs"),
+                PathBuf::from("src/token/parse.rs")
+            ],
+        );
+    }
+
+    #[test]
+    fn filter_ignore_walk_with_any() {
+        let (_root, path) = temptree();
+        let any = crate::any(["**/*.md", "tests/**"]).unwrap();
+        assert_eq!(
+            paths(&path, IgnoreFilter::from(&any)),
+            [PathBuf::from("README.md"), PathBuf::from("tests/walk.rs")],
+        );
+    }
+}
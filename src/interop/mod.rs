@@ -0,0 +1,45 @@
+//! Conversions between glob types and those of other crates.
+
+#[cfg(any(feature = "tar", feature = "zip"))]
+mod archive;
+#[cfg(feature = "arbitrary")]
+mod arbitrary;
+#[cfg(feature = "cap-std")]
+mod cap_std;
+#[cfg(feature = "clap")]
+mod clap;
+#[cfg(feature = "globset")]
+mod globset;
+#[cfg(feature = "ignore")]
+mod ignore;
+#[cfg(feature = "notify")]
+mod notify;
+#[cfg(feature = "proptest")]
+mod proptest;
+#[cfg(feature = "tar")]
+mod tar;
+#[cfg(feature = "typed-path")]
+mod typed_path;
+#[cfg(feature = "url")]
+mod url;
+#[cfg(feature = "zip")]
+mod zip;
+
+#[cfg(feature = "cap-std")]
+pub use crate::interop::cap_std::{DirEntries, DirFilesystem};
+#[cfg(feature = "clap")]
+pub use crate::interop::clap::GlobValueParser;
+#[cfg(feature = "globset")]
+pub use crate::interop::globset::GlobsetError;
+#[cfg(feature = "ignore")]
+pub use crate::interop::ignore::IgnoreFilter;
+#[cfg(feature = "notify")]
+pub use crate::interop::notify::{GlobWatcher, WatchError, WatchEvent, WatchItem};
+#[cfg(feature = "proptest")]
+pub use crate::interop::proptest::glob_with_paths;
+#[cfg(feature = "tar")]
+pub use crate::interop::tar::TarMatches;
+#[cfg(feature = "url")]
+pub use crate::interop::url::FileUrlError;
+#[cfg(feature = "zip")]
+pub use crate::interop::zip::ZipMatches;
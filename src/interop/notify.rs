@@ -0,0 +1,404 @@
+use ::notify::event::CreateKind;
+use ::notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher as _};
+#[cfg(feature = "miette")]
+use miette::Diagnostic;
+use regex::Regex;
+use std::borrow::Cow;
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::{Duration, Instant};
+use thiserror::Error;
+
+use crate::capture::MatchedText;
+use crate::token::{Token, TokenTree};
+use crate::{CandidatePath, Glob};
+
+pub type WatchItem = Result<WatchEvent, WatchError>;
+
+/// Describes errors that occur when watching a directory tree for changes to
+/// files that match a [`Glob`].
+///
+/// [`Glob`]: crate::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "notify")))]
+#[cfg_attr(feature = "miette", derive(Diagnostic))]
+#[cfg_attr(feature = "miette", diagnostic(code = "wax::notify::watch"))]
+#[derive(Debug, Error)]
+#[error("failed to watch directory tree: {error}")]
+pub struct WatchError {
+    error: ::notify::Error,
+}
+
+impl WatchError {
+    /// Gets the paths related to the error, if any.
+    pub fn paths(&self) -> &[PathBuf] {
+        &self.error.paths
+    }
+}
+
+impl From<::notify::Error> for WatchError {
+    fn from(error: ::notify::Error) -> Self {
+        WatchError { error }
+    }
+}
+
+/// Describes a change to a file that matches a [`Glob`].
+///
+/// [`Glob`]: crate::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "notify")))]
+#[derive(Debug)]
+pub struct WatchEvent {
+    kind: EventKind,
+    path: PathBuf,
+    matched: MatchedText<'static>,
+}
+
+impl WatchEvent {
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// Gets the path of the changed file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Converts the event to the relative [`CandidatePath`].
+    ///
+    /// **This differs from [`path`] and [`into_path`], which are natively
+    /// encoded and absolute.** The [`CandidatePath`] is relative to the
+    /// directory given to [`GlobWatcher::new`].
+    ///
+    /// [`CandidatePath`]: crate::CandidatePath
+    /// [`GlobWatcher::new`]: crate::GlobWatcher::new
+    /// [`into_path`]: crate::WatchEvent::into_path
+    /// [`path`]: crate::WatchEvent::path
+    pub fn to_candidate_path(&self) -> CandidatePath<'_> {
+        self.matched.to_candidate_path()
+    }
+
+    /// Gets the kind of change reported by [`notify`].
+    pub fn kind(&self) -> &EventKind {
+        &self.kind
+    }
+
+    /// Gets the matched text in the path of the changed file.
+    pub fn matched(&self) -> &MatchedText<'static> {
+        &self.matched
+    }
+}
+
+/// Watches a directory tree for changes to files that match a [`Glob`].
+///
+/// `GlobWatcher` registers [`notify`] watches on only the directories beneath
+/// which a [`Glob`] can match: the directory given to [`GlobWatcher::new`]
+/// joined with any [invariant prefix][`Glob::partition`] of the [`Glob`]. The
+/// watch is recursive unless the [`Glob`] can only match files directly within
+/// that directory. If the directory does not yet exist, then its nearest
+/// existing ancestor is watched instead, so that files in directories that are
+/// created later are matched too.
+///
+/// Events are filtered through the [`Glob`] and each path that matches is
+/// yielded as a [`WatchEvent`] with its [matched text][`MatchedText`]. When a
+/// directory is created, any matching files that are created within it before
+/// it is watched are also yielded, so changes to some files may be reported
+/// more than once.
+/// `GlobWatcher` is an [`Iterator`] that blocks until such an event occurs.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::{Glob, GlobWatcher};
+///
+/// let glob = Glob::new("src/**/*.rs").unwrap();
+/// for event in GlobWatcher::new(&glob, ".").unwrap() {
+///     let event = event.unwrap();
+///     println!("{:?}: {:?}", event.kind(), event.path());
+/// }
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::partition`]: crate::Glob::partition
+/// [`GlobWatcher::new`]: crate::GlobWatcher::new
+/// [`Iterator`]: std::iter::Iterator
+/// [`MatchedText`]: crate::MatchedText
+/// [`WatchEvent`]: crate::WatchEvent
+#[cfg_attr(docsrs, doc(cfg(feature = "notify")))]
+#[derive(Debug)]
+pub struct GlobWatcher<'g> {
+    pattern: Cow<'g, Regex>,
+    root: PathBuf,
+    prefix: PathBuf,
+    is_recursive: bool,
+    // The watcher is never read, but must be kept alive to receive events.
+    #[allow(dead_code)]
+    watcher: RecommendedWatcher,
+    receiver: Receiver<::notify::Result<Event>>,
+    pending: VecDeque<WatchItem>,
+}
+
+impl<'g> GlobWatcher<'g> {
+    /// Watches a directory tree for changes to files that match a [`Glob`].
+    ///
+    /// As with [`Glob::walk`], the [`Glob`] is matched against paths relative
+    /// to the given directory.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the directory cannot be read or watched.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::walk`]: crate::Glob::walk
+    pub fn new(glob: &'g Glob<'_>, directory: impl AsRef<Path>) -> Result<Self, WatchError> {
+        let directory = directory.as_ref();
+        // Paths in events are canonical on some platforms, so the directory is
+        // canonicalized to strip it from these paths.
+        let directory = directory
+            .canonicalize()
+            .map_err(|error| ::notify::Error::io(error).add_path(directory.into()))?;
+        let (invariant, variant) = glob.clone().partition();
+        let root = directory.join(&invariant);
+        let prefix = if invariant.is_absolute() {
+            // Absolute paths replace paths with which they are joined, in which
+            // case there is no prefix.
+            PathBuf::new()
+        }
+        else {
+            directory.clone()
+        };
+        let target = root
+            .ancestors()
+            .find(|path| path.is_dir())
+            .unwrap_or(&directory)
+            .to_path_buf();
+        let is_flat = !variant
+            .tree
+            .as_ref()
+            .tokens()
+            .iter()
+            .any(Token::has_component_boundary);
+        let mode = if is_flat && target == root {
+            RecursiveMode::NonRecursive
+        }
+        else {
+            RecursiveMode::Recursive
+        };
+
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = ::notify::recommended_watcher(sender)?;
+        watcher.watch(&target, mode)?;
+        Ok(GlobWatcher {
+            pattern: Cow::Borrowed(&glob.pattern),
+            root,
+            prefix,
+            is_recursive: matches!(mode, RecursiveMode::Recursive),
+            watcher,
+            receiver,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> GlobWatcher<'static> {
+        let GlobWatcher {
+            pattern,
+            root,
+            prefix,
+            is_recursive,
+            watcher,
+            receiver,
+            pending,
+        } = self;
+        GlobWatcher {
+            pattern: Cow::Owned(pattern.into_owned()),
+            root,
+            prefix,
+            is_recursive,
+            watcher,
+            receiver,
+            pending,
+        }
+    }
+
+    /// Gets the root directory of the watched tree.
+    ///
+    /// As with [`Walk::root`], this is the directory given to
+    /// [`GlobWatcher::new`] joined with any invariant prefix of the [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`GlobWatcher::new`]: crate::GlobWatcher::new
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Blocks until a file that matches the [`Glob`] changes.
+    ///
+    /// Returns `None` if the underlying watcher has stopped.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn recv(&mut self) -> Option<WatchItem> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let event = self.receiver.recv().ok()?;
+            self.push(event);
+        }
+    }
+
+    /// Blocks until a file that matches the [`Glob`] changes or the timeout
+    /// elapses.
+    ///
+    /// Returns `None` if the timeout elapses or the underlying watcher has
+    /// stopped.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn recv_timeout(&mut self, timeout: Duration) -> Option<WatchItem> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let timeout = deadline.checked_duration_since(Instant::now())?;
+            let event = self.receiver.recv_timeout(timeout).ok()?;
+            self.push(event);
+        }
+    }
+
+    /// Gets a change to a file that matches the [`Glob`] without blocking.
+    ///
+    /// Returns `None` if no such change has occurred.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn try_recv(&mut self) -> Option<WatchItem> {
+        loop {
+            if let Some(item) = self.pending.pop_front() {
+                return Some(item);
+            }
+            let event = self.receiver.try_recv().ok()?;
+            self.push(event);
+        }
+    }
+
+    fn push(&mut self, event: ::notify::Result<Event>) {
+        match event {
+            Ok(Event { kind, paths, .. }) => {
+                for path in paths {
+                    if self.is_recursive && matches!(kind, EventKind::Create(_)) && path.is_dir() {
+                        // Files may be created in a new directory before it is
+                        // watched, so read any such files.
+                        self.scan(&path);
+                    }
+                    if let Some(matched) = self.matched(&path) {
+                        self.pending.push_back(Ok(WatchEvent {
+                            kind,
+                            path,
+                            matched,
+                        }));
+                    }
+                }
+            },
+            Err(error) => self.pending.push_back(Err(error.into())),
+        }
+    }
+
+    fn scan(&mut self, directory: &Path) {
+        let entries = match fs::read_dir(directory) {
+            Ok(entries) => entries,
+            Err(_) => return,
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if entry
+                .file_type()
+                .map_or(false, |file_type| file_type.is_dir())
+            {
+                self.scan(&path);
+            }
+            if let Some(matched) = self.matched(&path) {
+                self.pending.push_back(Ok(WatchEvent {
+                    kind: EventKind::Create(CreateKind::Any),
+                    path,
+                    matched,
+                }));
+            }
+        }
+    }
+
+    fn matched(&self, path: &Path) -> Option<MatchedText<'static>> {
+        let path = CandidatePath::from(path.strip_prefix(&self.prefix).ok()?);
+        self.pattern
+            .captures(path.as_ref())
+            .map(|captures| MatchedText::from(captures).into_owned())
+    }
+}
+
+impl Iterator for GlobWatcher<'_> {
+    type Item = WatchItem;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.recv()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    use crate::{Glob, GlobWatcher, WatchEvent};
+
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    fn recv_matched(watcher: &mut GlobWatcher, name: &str) -> WatchEvent {
+        loop {
+            let event = watcher
+                .recv_timeout(TIMEOUT)
+                .expect("timed out waiting for event")
+                .unwrap();
+            if event.path().file_name().map_or(false, |file| file == name) {
+                return event;
+            }
+        }
+    }
+
+    #[test]
+    fn watch_matched_files_in_new_directories() {
+        let temp = TempDir::new().unwrap();
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let mut watcher = GlobWatcher::new(&glob, temp.path()).unwrap();
+
+        fs::create_dir_all(temp.path().join("src/token")).unwrap();
+        fs::write(temp.path().join("src/token/parse.md"), "").unwrap();
+        fs::write(temp.path().join("src/token/parse.rs"), "").unwrap();
+
+        let event = recv_matched(&mut watcher, "parse.rs");
+        assert_eq!(event.to_candidate_path().as_ref(), "src/token/parse.rs");
+        assert_eq!(event.matched().get(1), Some("token/"));
+        assert_eq!(event.matched().get(2), Some("parse"));
+        while let Some(event) = watcher.try_recv() {
+            assert!(!event.unwrap().path().ends_with("parse.md"));
+        }
+    }
+
+    #[test]
+    fn watch_matched_files_in_flat_directory() {
+        let temp = TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("nested")).unwrap();
+        let glob = Glob::new("*.txt").unwrap();
+        let mut watcher = GlobWatcher::new(&glob, temp.path()).unwrap();
+        assert_eq!(watcher.root(), temp.path().canonicalize().unwrap());
+
+        fs::write(temp.path().join("nested/b.txt"), "").unwrap();
+        fs::write(temp.path().join("a.txt"), "").unwrap();
+
+        let event = recv_matched(&mut watcher, "a.txt");
+        assert_eq!(event.to_candidate_path().as_ref(), "a.txt");
+        while let Some(event) = watcher.try_recv() {
+            assert!(!event.unwrap().path().ends_with("nested/b.txt"));
+        }
+    }
+}
@@ -0,0 +1,332 @@
+use ::proptest::arbitrary::{any, Arbitrary};
+use ::proptest::collection::vec;
+use ::proptest::sample::select;
+use ::proptest::strategy::{BoxedStrategy, Just, Strategy, Union};
+use std::path::PathBuf;
+
+use crate::Glob;
+
+const CHARACTERS: &[char] = &['a', 'b', 'c', 'x', 'y', 'z', '0', '1', '.', '-', '_'];
+// Unmatched paths end with this character, which is never otherwise generated.
+const UNMATCHED_CHARACTER: char = '#';
+
+#[derive(Clone, Debug)]
+enum Node {
+    Literal(String),
+    One,
+    ZeroOrMore(char),
+    Class {
+        is_negated: bool,
+        ranges: Vec<(char, char)>,
+    },
+    Alternative(Vec<Vec<Node>>),
+    Repetition {
+        nodes: Vec<Node>,
+        lower: usize,
+        upper: usize,
+    },
+}
+
+impl Node {
+    fn is_zero_or_more(&self) -> bool {
+        matches!(self, Node::ZeroOrMore(_))
+    }
+
+    fn encode(&self, expression: &mut String) {
+        match self {
+            Node::Literal(text) => expression.push_str(text),
+            Node::One => expression.push('?'),
+            Node::ZeroOrMore(wildcard) => expression.push(*wildcard),
+            Node::Class { is_negated, ranges } => {
+                expression.push('[');
+                if *is_negated {
+                    expression.push('!');
+                }
+                for (left, right) in ranges {
+                    for (n, x) in [*left, *right].into_iter().enumerate() {
+                        if n > 0 {
+                            if left == right {
+                                break;
+                            }
+                            expression.push('-');
+                        }
+                        if x == '-' {
+                            expression.push('\\');
+                        }
+                        expression.push(x);
+                    }
+                }
+                expression.push(']');
+            },
+            Node::Alternative(branches) => {
+                expression.push('{');
+                for (n, branch) in branches.iter().enumerate() {
+                    if n > 0 {
+                        expression.push(',');
+                    }
+                    encode(branch, expression);
+                }
+                expression.push('}');
+            },
+            Node::Repetition {
+                nodes,
+                lower,
+                upper,
+            } => {
+                expression.push('<');
+                encode(nodes, expression);
+                expression.push_str(&format!(":{},{}>", lower, upper));
+            },
+        }
+    }
+
+    // Every node matches at least one character in generated paths, so that
+    // components are never empty.
+    fn sample(&self) -> BoxedStrategy<String> {
+        match self {
+            Node::Literal(text) => Just(text.clone()).boxed(),
+            Node::One => text(1..2).boxed(),
+            Node::ZeroOrMore(_) => text(1..4).boxed(),
+            Node::Class { is_negated, ranges } => {
+                let characters: Vec<_> = if *is_negated {
+                    CHARACTERS
+                        .iter()
+                        .copied()
+                        .filter(|x| {
+                            !ranges
+                                .iter()
+                                .any(|(left, right)| (left..=right).contains(&x))
+                        })
+                        .collect()
+                }
+                else {
+                    ranges.iter().map(|(left, _)| *left).collect()
+                };
+                select(characters).prop_map(String::from).boxed()
+            },
+            Node::Alternative(branches) => {
+                Union::new(branches.iter().map(|branch| sample(branch))).boxed()
+            },
+            Node::Repetition {
+                nodes,
+                lower,
+                upper,
+            } => {
+                let nodes = nodes.clone();
+                ((*lower).max(1)..=*upper)
+                    .prop_flat_map(move |n| {
+                        vec(sample(&nodes), n..=n).prop_map(|texts| texts.concat())
+                    })
+                    .boxed()
+            },
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Component {
+    Tree,
+    Nodes(Vec<Node>),
+}
+
+fn encode(nodes: &[Node], expression: &mut String) {
+    for node in nodes {
+        node.encode(expression);
+    }
+}
+
+fn sample(nodes: &[Node]) -> BoxedStrategy<String> {
+    nodes
+        .iter()
+        .map(Node::sample)
+        .collect::<Vec<_>>()
+        .prop_map(|texts| texts.concat())
+        .boxed()
+}
+
+fn text(n: std::ops::Range<usize>) -> impl Strategy<Value = String> {
+    vec(select(CHARACTERS), n).prop_map(|text| text.into_iter().collect())
+}
+
+// Removes zero-or-more wildcards that would violate rules: adjacent wildcards
+// and wildcards that begin or end a group.
+fn normalize(nodes: Vec<Node>, is_group: bool) -> Vec<Node> {
+    let mut normalized: Vec<Node> = vec![];
+    for node in nodes {
+        if node.is_zero_or_more() && normalized.last().map_or(is_group, Node::is_zero_or_more) {
+            continue;
+        }
+        normalized.push(node);
+    }
+    if is_group {
+        while normalized.last().map_or(false, Node::is_zero_or_more) {
+            normalized.pop();
+        }
+    }
+    if normalized.is_empty() {
+        normalized.push(Node::One);
+    }
+    normalized
+}
+
+fn nodes(is_group: bool) -> BoxedStrategy<Vec<Node>> {
+    let leaf = ::proptest::prop_oneof![
+        3 => text(1..4).prop_map(Node::Literal),
+        1 => Just(Node::One),
+        2 => select(&['*', '$'][..]).prop_map(Node::ZeroOrMore),
+        1 => (
+            any::<bool>(),
+            vec(
+                (select(CHARACTERS), select(CHARACTERS))
+                    .prop_map(|(left, right)| (left.min(right), left.max(right))),
+                1..3,
+            ),
+        )
+            .prop_map(|(is_negated, ranges)| {
+                // Negated classes must match at least one character.
+                let is_negated = is_negated
+                    && CHARACTERS.iter().any(|x| {
+                        !ranges.iter().any(|(left, right)| (left..=right).contains(&x))
+                    });
+                Node::Class { is_negated, ranges }
+            }),
+    ];
+    let node = leaf.prop_recursive(2, 16, 3, |node| {
+        let group = vec(node, 1..4).prop_map(|nodes| normalize(nodes, true));
+        ::proptest::prop_oneof![
+            vec(group.clone(), 2..4).prop_map(Node::Alternative),
+            (group, 0..3usize, 1..4usize).prop_map(|(nodes, lower, upper)| {
+                Node::Repetition {
+                    nodes,
+                    lower,
+                    upper: upper.max(lower),
+                }
+            }),
+        ]
+    });
+    vec(node, 1..4)
+        .prop_map(move |nodes| normalize(nodes, is_group))
+        .boxed()
+}
+
+/// Gets a strategy that generates [`Glob`]s with a path that each matches and a
+/// path that each does not match.
+///
+/// The strategy generates triples of a [`Glob`], a path that matches the
+/// [`Glob`], and a path that does not match the [`Glob`]. Glob expressions
+/// include tree wildcards, character classes, alternatives, and repetitions.
+/// This can be used to property test code that routes files by pattern.
+///
+/// # Examples
+///
+/// ```rust
+/// use proptest::test_runner::TestRunner;
+/// use wax::Pattern;
+///
+/// let mut runner = TestRunner::default();
+/// runner
+///     .run(&wax::glob_with_paths(), |(glob, matched, unmatched)| {
+///         assert!(glob.is_match(matched.as_path()));
+///         assert!(!glob.is_match(unmatched.as_path()));
+///         Ok(())
+///     })
+///     .unwrap();
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+pub fn glob_with_paths() -> impl Strategy<Value = (Glob<'static>, PathBuf, PathBuf)> {
+    (
+        ::proptest::bool::weighted(0.25),
+        vec(
+            ::proptest::prop_oneof![
+                1 => Just(Component::Tree),
+                3 => nodes(false).prop_map(Component::Nodes),
+            ],
+            0..3,
+        ),
+        nodes(false),
+        text(1..3),
+    )
+        .prop_flat_map(|(is_rooted, mut components, mut nodes, suffix)| {
+            // The expression always ends with a literal, so that paths that
+            // end with another character are never matched.
+            nodes.push(Node::Literal(suffix));
+            components.push(Component::Nodes(nodes));
+            components.dedup_by(|right, left| {
+                matches!((left, right), (Component::Tree, Component::Tree))
+            });
+
+            let mut expression = String::new();
+            if is_rooted {
+                expression.push('/');
+            }
+            for (n, component) in components.iter().enumerate() {
+                if n > 0 {
+                    expression.push('/');
+                }
+                match component {
+                    Component::Tree => expression.push_str("**"),
+                    Component::Nodes(nodes) => encode(nodes, &mut expression),
+                }
+            }
+            let glob = Glob::new(&expression)
+                .expect("failed to build generated glob")
+                .into_owned();
+
+            let path = components
+                .iter()
+                .map(|component| match component {
+                    Component::Tree => vec(text(1..3), 0..3).boxed(),
+                    Component::Nodes(nodes) => sample(nodes).prop_map(|text| vec![text]).boxed(),
+                })
+                .collect::<Vec<_>>()
+                .prop_map(move |components| {
+                    let path = components.concat().join("/");
+                    if is_rooted {
+                        format!("/{}", path)
+                    }
+                    else {
+                        path
+                    }
+                });
+            (Just(glob), path)
+        })
+        .prop_map(|(glob, matched)| {
+            let mut unmatched = matched.clone();
+            unmatched.pop();
+            unmatched.push(UNMATCHED_CHARACTER);
+            (glob, matched.into(), unmatched.into())
+        })
+}
+
+/// Generates arbitrary `Glob`s.
+///
+/// See [`glob_with_paths`].
+///
+/// [`glob_with_paths`]: crate::glob_with_paths
+#[cfg_attr(docsrs, doc(cfg(feature = "proptest")))]
+impl Arbitrary for Glob<'static> {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        glob_with_paths().prop_map(|(glob, _, _)| glob).boxed()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proptest::proptest;
+
+    use crate::Pattern;
+
+    proptest! {
+        #[test]
+        fn match_generated_paths(triple in crate::glob_with_paths()) {
+            let (glob, matched, unmatched) = triple;
+            assert!(glob.is_match(matched.as_path()), "{} !~ {:?}", glob, matched);
+            assert!(!glob.is_match(unmatched.as_path()), "{} ~ {:?}", glob, unmatched);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use ::tar::{Archive, Entries, Entry};
+use std::io::{self, Read};
+
+use crate::capture::MatchedText;
+use crate::interop::archive::EntryFilter;
+use crate::Glob;
+
+/// Iterator over the entries in a [`tar`] archive that match a [`Glob`].
+///
+/// `TarMatches` yields each matching [`Entry`] along with its [matched
+/// text][`MatchedText`]. Entry paths are matched as relative paths, so any
+/// leading `./` component is ignored. Entries that are not within the
+/// invariant prefix of the glob are skipped without matching the pattern.
+///
+/// Entries in a [`tar`] archive are read in order from a stream, so a matching
+/// [`Entry`] must be read before the next item is requested.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use std::io::Read;
+/// use tar::Archive;
+/// use wax::{Glob, TarMatches};
+///
+/// let glob = Glob::new("src/**/*.rs").unwrap();
+/// let mut archive = Archive::new(File::open("crate.tar").unwrap());
+/// for item in TarMatches::new(&glob, &mut archive).unwrap() {
+///     let (mut entry, matched) = item.unwrap();
+///     let mut text = String::new();
+///     entry.read_to_string(&mut text).unwrap();
+///     println!("{}: {} bytes", matched.complete(), text.len());
+/// }
+/// ```
+///
+/// [`Entry`]: tar::Entry
+/// [`Glob`]: crate::Glob
+/// [`MatchedText`]: crate::MatchedText
+#[cfg_attr(docsrs, doc(cfg(feature = "tar")))]
+pub struct TarMatches<'a, R>
+where
+    R: 'a + Read,
+{
+    filter: EntryFilter,
+    entries: Entries<'a, R>,
+}
+
+impl<'a, R> TarMatches<'a, R>
+where
+    R: 'a + Read,
+{
+    /// Constructs an iterator over the entries in an [`Archive`] that match a
+    /// [`Glob`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the entries of the archive cannot be read, such as
+    /// when entries have already been read from the archive.
+    ///
+    /// [`Archive`]: tar::Archive
+    /// [`Glob`]: crate::Glob
+    pub fn new(glob: &Glob<'_>, archive: &'a mut Archive<R>) -> io::Result<Self> {
+        Ok(TarMatches {
+            filter: EntryFilter::new(glob),
+            entries: archive.entries()?,
+        })
+    }
+}
+
+impl<'a, R> Iterator for TarMatches<'a, R>
+where
+    R: 'a + Read,
+{
+    type Item = io::Result<(Entry<'a, R>, MatchedText<'static>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.entries.next()? {
+                Ok(entry) => entry,
+                Err(error) => {
+                    return Some(Err(error));
+                },
+            };
+            let matched = match entry.path() {
+                Ok(path) => self.filter.matched(&path),
+                Err(error) => {
+                    return Some(Err(error));
+                },
+            };
+            if let Some(matched) = matched {
+                return Some(Ok((entry, matched)));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+    use tar::{Archive, Builder, Header};
+
+    use crate::{Glob, TarMatches};
+
+    fn archive() -> Archive<Cursor<Vec<u8>>> {
+        let mut builder = Builder::new(vec![]);
+        for (path, data) in [
+            ("./README.md", "readme"),
+            ("src/lib.rs", "lib"),
+            ("src/token/parse.rs", "parse"),
+            ("tests/walk.rs", "walk"),
+        ] {
+            let mut header = Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder
+                .append_data(&mut header, path, data.as_bytes())
+                .unwrap();
+        }
+        Archive::new(Cursor::new(builder.into_inner().unwrap()))
+    }
+
+    #[test]
+    fn match_tar_entries_with_glob() {
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let mut archive = archive();
+        let entries: Vec<_> = TarMatches::new(&glob, &mut archive)
+            .unwrap()
+            .map(|item| {
+                let (mut entry, matched) = item.unwrap();
+                let mut data = String::new();
+                entry.read_to_string(&mut data).unwrap();
+                (matched.complete().to_owned(), data)
+            })
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                ("src/lib.rs".to_owned(), "lib".to_owned()),
+                ("src/token/parse.rs".to_owned(), "parse".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn match_tar_entries_with_current_directory_component() {
+        let glob = Glob::new("*.md").unwrap();
+        let mut archive = archive();
+        let paths: Vec<_> = TarMatches::new(&glob, &mut archive)
+            .unwrap()
+            .map(|item| item.unwrap().1.complete().to_owned())
+            .collect();
+        assert_eq!(paths, ["README.md"]);
+    }
+}
@@ -0,0 +1,127 @@
+use ::typed_path::{
+    TypedPath, UnixPath, Utf8TypedPath, Utf8UnixPath, Utf8WindowsPath, WindowsPath,
+};
+
+use crate::{CandidatePath, PathKind};
+
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<&'b UnixPath> for CandidatePath<'b> {
+    fn from(path: &'b UnixPath) -> Self {
+        CandidatePath {
+            text: path.to_string_lossy(),
+            kind: PathKind::Unix,
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<&'b Utf8UnixPath> for CandidatePath<'b> {
+    fn from(path: &'b Utf8UnixPath) -> Self {
+        CandidatePath::unix(path.as_str())
+    }
+}
+
+/// Converts a [`WindowsPath`] into a `CandidatePath`.
+///
+/// Backslash separators are normalized regardless of the host platform, so
+/// Windows paths can be matched on Unix. The `CandidatePath` is a
+/// [Windows path][`PathKind::Windows`], so its names are matched
+/// case-insensitively by default.
+///
+/// [`PathKind::Windows`]: crate::PathKind::Windows
+/// [`WindowsPath`]: typed_path::WindowsPath
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<&'b WindowsPath> for CandidatePath<'b> {
+    fn from(path: &'b WindowsPath) -> Self {
+        CandidatePath::from_windows_text(path.to_string_lossy())
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<&'b Utf8WindowsPath> for CandidatePath<'b> {
+    fn from(path: &'b Utf8WindowsPath) -> Self {
+        CandidatePath::from_windows_text(path.as_str().into())
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<TypedPath<'b>> for CandidatePath<'b> {
+    fn from(path: TypedPath<'b>) -> Self {
+        match path {
+            TypedPath::Unix(path) => path.into(),
+            TypedPath::Windows(path) => path.into(),
+        }
+    }
+}
+
+#[cfg_attr(docsrs, doc(cfg(feature = "typed-path")))]
+impl<'b> From<Utf8TypedPath<'b>> for CandidatePath<'b> {
+    fn from(path: Utf8TypedPath<'b>) -> Self {
+        match path {
+            Utf8TypedPath::Unix(path) => path.into(),
+            Utf8TypedPath::Windows(path) => path.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use typed_path::{TypedPath, UnixPath, Utf8TypedPath, Utf8WindowsPath, WindowsPath};
+
+    use crate::{CandidatePath, Glob, Pattern};
+
+    #[test]
+    fn match_windows_path_candidate() {
+        let glob = Glob::new("src/**/*.rs").unwrap();
+
+        assert!(glob.is_match(WindowsPath::new(r"src\glob\lib.rs")));
+        assert!(glob.is_match(WindowsPath::new("src/glob/lib.rs")));
+        assert!(glob.is_match(Utf8WindowsPath::new(r"src\lib.rs")));
+        assert_eq!(
+            CandidatePath::from(WindowsPath::new(r"src\lib.rs")).as_ref(),
+            "src/lib.rs",
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn match_unix_path_candidate() {
+        let glob = Glob::new("src/*.rs").unwrap();
+
+        assert!(glob.is_match(UnixPath::new("src/lib.rs")));
+
+        assert!(!glob.is_match(UnixPath::new(r"src\glob/lib.rs")));
+    }
+
+    #[test]
+    fn match_typed_path_candidate() {
+        let glob = Glob::new("src/*.rs").unwrap();
+
+        assert!(glob.is_match(TypedPath::windows(r"src\lib.rs")));
+        assert!(glob.is_match(TypedPath::unix("src/lib.rs")));
+        assert!(glob.is_match(Utf8TypedPath::windows(r"src\lib.rs")));
+        assert!(glob.is_match(Utf8TypedPath::unix("src/lib.rs")));
+    }
+
+    #[test]
+    fn match_typed_path_candidate_with_case_of_kind() {
+        let glob = Glob::new("src/*.rs").unwrap();
+
+        // Windows paths are matched case-insensitively and Unix paths are
+        // matched case-sensitively regardless of the target platform.
+        assert!(glob.is_match(WindowsPath::new(r"SRC\Lib.rs")));
+        assert!(glob.is_match(Utf8WindowsPath::new(r"Src\LIB.RS")));
+        assert!(glob.is_match(TypedPath::windows(r"SRC\Lib.rs")));
+        assert!(glob.is_match(Utf8TypedPath::windows(r"SRC\Lib.rs")));
+
+        assert!(!glob.is_match(UnixPath::new("SRC/Lib.rs")));
+        assert!(!glob.is_match(TypedPath::unix("SRC/Lib.rs")));
+        assert!(!glob.is_match(Utf8TypedPath::unix("SRC/Lib.rs")));
+
+        // Flags take precedence over the kind of a path.
+        let glob = Glob::new("(?-i)src/*.rs").unwrap();
+        assert!(!glob.is_match(WindowsPath::new(r"SRC\Lib.rs")));
+        let glob = Glob::new("(?i)src/*.rs").unwrap();
+        assert!(glob.is_match(UnixPath::new("SRC/Lib.rs")));
+    }
+}
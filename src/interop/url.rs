@@ -0,0 +1,138 @@
+use ::url::{Host, Url};
+#[cfg(feature = "miette")]
+use miette::Diagnostic;
+use percent_encoding::percent_decode_str;
+use std::convert::TryFrom;
+use thiserror::Error;
+
+use crate::{CandidatePath, PathKind};
+
+/// Describes errors that occur when converting a URL into a [`CandidatePath`].
+///
+/// Only URLs with the `file` scheme can be converted into [`CandidatePath`]s.
+///
+/// [`CandidatePath`]: crate::CandidatePath
+#[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+#[cfg_attr(feature = "miette", derive(Diagnostic))]
+#[cfg_attr(feature = "miette", diagnostic(code = "wax::url::scheme"))]
+#[derive(Clone, Debug, Error)]
+#[error("URL has scheme `{scheme}` rather than `file`")]
+pub struct FileUrlError {
+    scheme: String,
+}
+
+impl FileUrlError {
+    /// Gets the scheme of the URL that failed to convert.
+    pub fn scheme(&self) -> &str {
+        &self.scheme
+    }
+}
+
+/// Converts a `file` URL into a `CandidatePath`.
+///
+/// The path of the URL is percent-decoded and any bytes that are not valid
+/// UTF-8 are replaced as described by [`CandidatePath`]. Paths beginning with
+/// a drive letter, such as `file:///C:/Windows`, are converted into Windows
+/// paths like `C:/Windows` and URLs with a host other than `localhost`, such
+/// as `file://server/share`, are converted into UNC paths like
+/// `//server/share`. These conversions do not depend on the host platform.
+///
+/// # Errors
+///
+/// Returns an error if the URL does not have a `file` scheme.
+///
+/// [`CandidatePath`]: crate::CandidatePath
+#[cfg_attr(docsrs, doc(cfg(feature = "url")))]
+impl TryFrom<&'_ Url> for CandidatePath<'static> {
+    type Error = FileUrlError;
+
+    fn try_from(url: &Url) -> Result<Self, Self::Error> {
+        // Drive letters may be delimited by `|` in legacy URLs.
+        fn drive_letter(path: &str) -> Option<char> {
+            match path.as_bytes() {
+                [b'/', letter, b':' | b'|'] | [b'/', letter, b':' | b'|', b'/', ..]
+                    if letter.is_ascii_alphabetic() =>
+                {
+                    Some(char::from(*letter))
+                },
+                _ => None,
+            }
+        }
+
+        if url.scheme() != "file" {
+            return Err(FileUrlError {
+                scheme: url.scheme().into(),
+            });
+        }
+        let path = percent_decode_str(url.path()).decode_utf8_lossy();
+        let text = match url.host() {
+            Some(Host::Domain("localhost")) | None => {
+                if let Some(letter) = drive_letter(&path) {
+                    format!("{}:{}", letter, &path[3..])
+                }
+                else {
+                    path.into_owned()
+                }
+            },
+            Some(host) => format!("//{}{}", host, path),
+        };
+        Ok(CandidatePath {
+            text: text.into(),
+            kind: PathKind::Native,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+    use url::Url;
+
+    use crate::{CandidatePath, Glob, Pattern};
+
+    fn candidate(url: &str) -> CandidatePath<'static> {
+        CandidatePath::try_from(&Url::parse(url).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn convert_file_url_into_candidate_path() {
+        assert_eq!(
+            candidate("file:///tmp/src/lib.rs").as_ref(),
+            "/tmp/src/lib.rs"
+        );
+        assert_eq!(
+            candidate("file://localhost/tmp/lib.rs").as_ref(),
+            "/tmp/lib.rs"
+        );
+        assert_eq!(
+            candidate("file:///tmp/a%20b%23.txt").as_ref(),
+            "/tmp/a b#.txt"
+        );
+        assert_eq!(
+            candidate("file:///C:/Users/lib.rs").as_ref(),
+            "C:/Users/lib.rs"
+        );
+        assert_eq!(candidate("file:///c|/Users").as_ref(), "c:/Users");
+        assert_eq!(
+            candidate("file://server/share/lib.rs").as_ref(),
+            "//server/share/lib.rs",
+        );
+    }
+
+    #[test]
+    fn match_file_url_candidate() {
+        let glob = Glob::new("/tmp/**/*.rs").unwrap();
+
+        assert!(glob.is_match(candidate("file:///tmp/src/lib.rs")));
+        assert!(glob.is_match(candidate("file:///tmp/src%20code/lib.rs")));
+
+        assert!(!glob.is_match(candidate("file:///tmp/src/lib.md")));
+    }
+
+    #[test]
+    fn reject_non_file_url() {
+        let error = CandidatePath::try_from(&Url::parse("https://example.com/lib.rs").unwrap())
+            .unwrap_err();
+        assert_eq!(error.scheme(), "https");
+    }
+}
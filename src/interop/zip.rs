@@ -0,0 +1,155 @@
+use ::zip::read::ZipFile;
+use ::zip::result::ZipResult;
+use ::zip::ZipArchive;
+use std::io::{Read, Seek};
+use std::path::Path;
+use std::vec;
+
+use crate::capture::MatchedText;
+use crate::interop::archive::EntryFilter;
+use crate::Glob;
+
+/// Entries in a [`zip`] archive that match a [`Glob`].
+///
+/// `ZipMatches` reads the entries of a [`ZipArchive`] that match a [`Glob`]
+/// along with their [matched text][`MatchedText`]. Entry names are matched
+/// against the glob via the central directory of the archive, so entries that
+/// do not match are never read. Entries that are not within the invariant
+/// prefix of the glob are skipped without matching the pattern.
+///
+/// Matching entries are read in order by name. Because a [`ZipFile`] borrows
+/// its [`ZipArchive`], `ZipMatches` is not an [`Iterator`]; entries are read
+/// with [`ZipMatches::next_entry`] instead. Use [`ZipMatches::names`] to get
+/// the names of the matching entries without reading them.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::fs::File;
+/// use wax::{Glob, ZipMatches};
+/// use zip::ZipArchive;
+///
+/// let glob = Glob::new("src/**/*.rs").unwrap();
+/// let mut archive = ZipArchive::new(File::open("crate.zip").unwrap()).unwrap();
+/// let mut matches = ZipMatches::new(&glob, &mut archive);
+/// while let Some(item) = matches.next_entry() {
+///     let (entry, matched) = item.unwrap();
+///     println!("{}: {} bytes", matched.complete(), entry.size());
+/// }
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Iterator`]: std::iter::Iterator
+/// [`MatchedText`]: crate::MatchedText
+/// [`ZipArchive`]: zip::ZipArchive
+/// [`ZipFile`]: zip::read::ZipFile
+/// [`ZipMatches::names`]: crate::ZipMatches::names
+/// [`ZipMatches::next_entry`]: crate::ZipMatches::next_entry
+#[cfg_attr(docsrs, doc(cfg(feature = "zip")))]
+pub struct ZipMatches<'a, R>
+where
+    R: Read + Seek,
+{
+    archive: &'a mut ZipArchive<R>,
+    names: vec::IntoIter<(String, MatchedText<'static>)>,
+}
+
+impl<'a, R> ZipMatches<'a, R>
+where
+    R: Read + Seek,
+{
+    /// Matches the entries in a [`ZipArchive`] against a [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`ZipArchive`]: zip::ZipArchive
+    pub fn new(glob: &Glob<'_>, archive: &'a mut ZipArchive<R>) -> Self {
+        let filter = EntryFilter::new(glob);
+        let mut names: Vec<_> = archive
+            .file_names()
+            .filter_map(|name| {
+                filter
+                    .matched(Path::new(name))
+                    .map(|matched| (name.to_owned(), matched))
+            })
+            .collect();
+        names.sort_by(|(left, _), (right, _)| left.cmp(right));
+        ZipMatches {
+            archive,
+            names: names.into_iter(),
+        }
+    }
+
+    /// Reads the next matching entry.
+    ///
+    /// Returns `None` if there are no more matching entries.
+    pub fn next_entry(&mut self) -> Option<ZipResult<(ZipFile<'_>, MatchedText<'static>)>> {
+        let (name, matched) = self.names.next()?;
+        Some(self.archive.by_name(&name).map(|entry| (entry, matched)))
+    }
+
+    /// Gets the names and [matched text][`MatchedText`] of the remaining
+    /// matching entries.
+    ///
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn names(&self) -> impl '_ + Iterator<Item = (&str, &MatchedText<'static>)> {
+        self.names
+            .as_slice()
+            .iter()
+            .map(|(name, matched)| (name.as_str(), matched))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read, Write};
+    use zip::write::FileOptions;
+    use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+    use crate::{Glob, ZipMatches};
+
+    fn archive() -> ZipArchive<Cursor<Vec<u8>>> {
+        let options = FileOptions::default().compression_method(CompressionMethod::Stored);
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer.add_directory("src/", options).unwrap();
+        for (name, data) in [
+            ("README.md", "readme"),
+            ("src/token/parse.rs", "parse"),
+            ("src/lib.rs", "lib"),
+            ("tests/walk.rs", "walk"),
+        ] {
+            writer.start_file(name, options).unwrap();
+            writer.write_all(data.as_bytes()).unwrap();
+        }
+        ZipArchive::new(writer.finish().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn match_zip_entries_with_glob() {
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let mut archive = archive();
+        let mut matches = ZipMatches::new(&glob, &mut archive);
+        let mut entries = vec![];
+        while let Some(item) = matches.next_entry() {
+            let (mut entry, matched) = item.unwrap();
+            let mut data = String::new();
+            entry.read_to_string(&mut data).unwrap();
+            entries.push((matched.complete().to_owned(), data));
+        }
+        assert_eq!(
+            entries,
+            [
+                ("src/lib.rs".to_owned(), "lib".to_owned()),
+                ("src/token/parse.rs".to_owned(), "parse".to_owned()),
+            ],
+        );
+    }
+
+    #[test]
+    fn match_zip_directory_entries_with_glob() {
+        let glob = Glob::new("src").unwrap();
+        let mut archive = archive();
+        let matches = ZipMatches::new(&glob, &mut archive);
+        let names: Vec<_> = matches.names().map(|(name, _)| name).collect();
+        assert_eq!(names, ["src/"]);
+    }
+}
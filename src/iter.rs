@@ -0,0 +1,112 @@
+use std::path::Path;
+
+use crate::capture::MatchedText;
+use crate::{CandidatePath, Glob, Pattern as _};
+
+/// Extension trait for matching the paths of an [`Iterator`] against a
+/// [`Glob`].
+///
+/// This trait is implemented for all [`Iterator`]s with items that can be
+/// viewed as a [`Path`], such as the output of `git ls-files`, paths read from
+/// a database, or the entries of an archive.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, IteratorExt as _};
+///
+/// let glob = Glob::new("src/**/{*}.rs").unwrap();
+/// let stems: Vec<_> = ["src/lib.rs", "README.md", "src/token/parse.rs"]
+///     .into_iter()
+///     .filter_glob(&glob)
+///     .map(|(_, matched)| matched.get(2).unwrap().to_owned())
+///     .collect();
+/// assert_eq!(stems, ["lib", "parse"]);
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Iterator`]: std::iter::Iterator
+/// [`Path`]: std::path::Path
+pub trait IteratorExt: Iterator + Sized
+where
+    Self::Item: AsRef<Path>,
+{
+    /// Filters items with paths that match a [`Glob`].
+    ///
+    /// The adaptor yields each matching item together with its [matched
+    /// text][`MatchedText`]. The compiled program of the [`Glob`] is reused for
+    /// all items.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    fn filter_glob<'g, 't>(self, glob: &'g Glob<'t>) -> FilterGlob<'g, 't, Self> {
+        FilterGlob { input: self, glob }
+    }
+}
+
+impl<I> IteratorExt for I
+where
+    I: Iterator,
+    I::Item: AsRef<Path>,
+{
+}
+
+/// Iterator adaptor that filters items with paths that match a [`Glob`].
+///
+/// See [`IteratorExt::filter_glob`].
+///
+/// [`Glob`]: crate::Glob
+/// [`IteratorExt::filter_glob`]: crate::IteratorExt::filter_glob
+#[derive(Clone, Debug)]
+#[must_use]
+pub struct FilterGlob<'g, 't, I> {
+    input: I,
+    glob: &'g Glob<'t>,
+}
+
+impl<I> Iterator for FilterGlob<'_, '_, I>
+where
+    I: Iterator,
+    I::Item: AsRef<Path>,
+{
+    type Item = (I::Item, MatchedText<'static>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let glob = self.glob;
+        self.input.find_map(|item| {
+            let matched = glob
+                .matched(&CandidatePath::from(item.as_ref()))
+                .map(MatchedText::into_owned)?;
+            Some((item, matched))
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.input.size_hint().1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use crate::{Glob, IteratorExt as _};
+
+    #[test]
+    fn filter_iterator_with_glob() {
+        let glob = Glob::new("**/*.{md,txt}").unwrap();
+        let paths: Vec<_> = ["doc/guide.md", "src/lib.rs", "notes.txt"]
+            .into_iter()
+            .map(PathBuf::from)
+            .filter_glob(&glob)
+            .map(|(path, matched)| (path, matched.complete().to_owned()))
+            .collect();
+        assert_eq!(
+            paths,
+            [
+                (PathBuf::from("doc/guide.md"), String::from("doc/guide.md")),
+                (PathBuf::from("notes.txt"), String::from("notes.txt")),
+            ],
+        );
+    }
+}
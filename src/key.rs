@@ -0,0 +1,207 @@
+use regex::Regex;
+use std::borrow::Cow;
+
+use crate::capture::MatchedText;
+use crate::token::{self, TokenTree};
+use crate::Glob;
+
+/// Filters object keys against a [`Glob`].
+///
+/// `KeyFilter` matches text keys with `/`-separated components, such as the
+/// keys of objects in S3 or GCS buckets, against a [`Glob`] without any
+/// interaction with a file system. Keys are matched as given: unlike native
+/// paths, they are not interpreted by the target platform. A single trailing
+/// separator, which is often used by keys that represent directories, is
+/// ignored.
+///
+/// [`KeyFilter::prefix`] derives the longest literal text that begins all
+/// matching keys, which can be given to list requests to limit the keys that
+/// are read from a store.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, KeyFilter};
+///
+/// let glob = Glob::new("logs/2023-*/**/*.json").unwrap();
+/// let filter = KeyFilter::new(&glob);
+/// assert_eq!(filter.prefix(), "logs/2023-");
+///
+/// let keys = ["logs/2023-01/a.json", "logs/2023-01/a.txt", "logs/2024-01/b.json"];
+/// let matched: Vec<_> = filter.filter(keys).map(|(key, _)| key).collect();
+/// assert_eq!(matched, ["logs/2023-01/a.json"]);
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`KeyFilter::prefix`]: crate::KeyFilter::prefix
+#[derive(Clone, Debug)]
+pub struct KeyFilter<'g> {
+    pattern: Cow<'g, Regex>,
+    prefix: String,
+}
+
+impl<'g> KeyFilter<'g> {
+    /// Constructs a `KeyFilter` that matches keys against the given [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn new(glob: &'g Glob<'_>) -> Self {
+        KeyFilter {
+            pattern: Cow::Borrowed(&glob.pattern),
+            prefix: token::literal_text_prefix(glob.tree.as_ref().tokens()),
+        }
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> KeyFilter<'static> {
+        let KeyFilter { pattern, prefix } = self;
+        KeyFilter {
+            pattern: Cow::Owned(pattern.into_owned()),
+            prefix,
+        }
+    }
+
+    /// Gets the literal prefix of matching keys.
+    ///
+    /// All keys that match the [`Glob`] begin with this prefix, so it can be
+    /// used as the `prefix` parameter of list requests. The prefix is not
+    /// restricted to whole components. For example, the prefix of
+    /// `logs/2023-*/*.json` is `logs/2023-`. The prefix may be empty.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Gets [matched text][`MatchedText`] in a key.
+    ///
+    /// Returns `None` if the key does not match the [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched<'k>(&self, key: &'k str) -> Option<MatchedText<'k>> {
+        let key = match key.strip_suffix('/') {
+            Some(key) if !key.is_empty() => key,
+            _ => key,
+        };
+        if !key.starts_with(&self.prefix) {
+            return None;
+        }
+        self.pattern.captures(key).map(MatchedText::from)
+    }
+
+    /// Returns `true` if the key matches the [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_match(&self, key: &str) -> bool {
+        self.matched(key).is_some()
+    }
+
+    /// Filters keys against the [`Glob`].
+    ///
+    /// The returned iterator yields matching keys along with their [matched
+    /// text][`MatchedText`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn filter<I>(&self, keys: I) -> FilterKeys<'_, 'g, I::IntoIter>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        FilterKeys {
+            filter: self,
+            keys: keys.into_iter(),
+        }
+    }
+}
+
+/// Iterator over keys that match a [`Glob`].
+///
+/// This iterator is constructed by [`KeyFilter::filter`].
+///
+/// [`Glob`]: crate::Glob
+/// [`KeyFilter::filter`]: crate::KeyFilter::filter
+#[derive(Clone, Debug)]
+pub struct FilterKeys<'f, 'g, I> {
+    filter: &'f KeyFilter<'g>,
+    keys: I,
+}
+
+impl<'f, 'g, I> Iterator for FilterKeys<'f, 'g, I>
+where
+    I: Iterator,
+    I::Item: AsRef<str>,
+{
+    type Item = (I::Item, MatchedText<'static>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for key in self.keys.by_ref() {
+            if let Some(matched) = self
+                .filter
+                .matched(key.as_ref())
+                .map(MatchedText::into_owned)
+            {
+                return Some((key, matched));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, KeyFilter};
+
+    #[test]
+    fn key_filter_prefix() {
+        fn prefix(expression: &str) -> String {
+            KeyFilter::new(&Glob::new(expression).unwrap())
+                .prefix()
+                .to_owned()
+        }
+
+        assert_eq!(prefix("logs/**/*.json"), "logs");
+        assert_eq!(prefix("logs/2023-*/*.json"), "logs/2023-");
+        assert_eq!(prefix("logs/{a,b}/*.json"), "logs/");
+        assert_eq!(prefix("a/b/c.txt"), "a/b/c.txt");
+        assert_eq!(prefix("(?i)logs/*"), "");
+        assert_eq!(prefix("(?i)2023/*"), "2023/");
+        assert_eq!(prefix("**/*.json"), "");
+        assert_eq!(prefix("/**/*.json"), "/");
+    }
+
+    #[test]
+    fn key_filter_match() {
+        let glob = Glob::new("data/{*}/**/*.parquet").unwrap();
+        let filter = KeyFilter::new(&glob);
+        assert!(filter.is_match("data/x/part-0.parquet"));
+        assert!(filter.is_match("data/x/y/z/part-0.parquet"));
+        assert!(!filter.is_match("data/part-0.parquet"));
+        assert!(!filter.is_match("other/x/part-0.parquet"));
+        assert_eq!(
+            filter
+                .matched("data/x/part-0.parquet")
+                .unwrap()
+                .get(1)
+                .unwrap(),
+            "x",
+        );
+    }
+
+    #[test]
+    fn key_filter_directory_key() {
+        let glob = Glob::new("data/*").unwrap();
+        let filter = KeyFilter::new(&glob);
+        assert!(filter.is_match("data/x/"));
+        assert!(!filter.is_match("data/x/y/"));
+        let keys: Vec<_> = filter
+            .filter(vec![
+                "data/".to_owned(),
+                "data/x/".to_owned(),
+                "data/y".to_owned(),
+            ])
+            .map(|(key, _)| key)
+            .collect();
+        assert_eq!(keys, ["data/x/", "data/y"]);
+    }
+}
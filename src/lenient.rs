@@ -0,0 +1,271 @@
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+
+use crate::diagnostics::{LocatedError, Span};
+
+/// Auto-correction of a common mistake in a glob expression.
+///
+/// Corrections are recorded by [`Glob::lenient`], which accepts glob
+/// expressions with common mistakes and gives them defined semantics. The
+/// [`Span`] of a correction is a location in the **original** glob expression.
+///
+/// [`Glob::lenient`]: crate::Glob::lenient
+/// [`Span`]: crate::Span
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Correction {
+    kind: CorrectionKind,
+    span: Span,
+}
+
+impl Correction {
+    /// Gets the kind of the correction.
+    pub fn kind(&self) -> CorrectionKind {
+        self.kind
+    }
+}
+
+impl Display for Correction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            CorrectionKind::LiteralParenthesis => {
+                write!(f, "unescaped parenthesis `(` or `)` matched literally")
+            },
+            CorrectionKind::LiteralBracket => {
+                write!(f, "unescaped closing bracket `]` matched literally")
+            },
+            CorrectionKind::CollapsedTree => {
+                write!(
+                    f,
+                    "repeated zero-or-more wildcards `***` collapsed into `**`"
+                )
+            },
+        }
+    }
+}
+
+impl LocatedError for Correction {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+/// Kind of a [`Correction`].
+///
+/// [`Correction`]: crate::Correction
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CorrectionKind {
+    /// An unescaped parenthesis `(` or `)` that does not delimit flags, a
+    /// scoped flag group, or an optional group is escaped and matched
+    /// literally.
+    LiteralParenthesis,
+    /// An unescaped closing bracket `]` outside of a class is escaped and
+    /// matched literally.
+    LiteralBracket,
+    /// A run of three or more `*` is collapsed into a tree wildcard `**`.
+    CollapsedTree,
+}
+
+// Rewrites common mistakes in a glob expression. The corrected expression is
+// borrowed if there are no corrections.
+pub fn correct(expression: &str) -> (Cow<'_, str>, Vec<Correction>) {
+    let mut corrected = String::with_capacity(expression.len());
+    let mut corrections = vec![];
+
+    let (mut is_escaped, mut is_class, mut is_flags) = (false, false, false);
+    // Open parentheses, which are either scoped flag groups (`None`), such as
+    // `(?i:...)`, or are matched literally (`Some`). Closing parentheses are
+    // paired with the innermost open parenthesis. Parentheses that are matched
+    // literally record the location of their escape and correction, which are
+    // removed if the parentheses delimit an optional group, such as `(...)?`.
+    let mut scopes: Vec<Option<(usize, usize)>> = vec![];
+    let mut chars = expression.char_indices().peekable();
+    while let Some((start, x)) = chars.next() {
+        if is_escaped {
+            is_escaped = false;
+            corrected.push(x);
+            continue;
+        }
+        match x {
+            '\\' => {
+                is_escaped = true;
+                corrected.push(x);
+            },
+            ']' if is_class => {
+                is_class = false;
+                corrected.push(x);
+            },
+            ')' if is_flags => {
+                is_flags = false;
+                corrected.push(x);
+            },
+            ':' if is_flags => {
+                is_flags = false;
+                scopes.push(None);
+                corrected.push(x);
+            },
+            '[' if is_class && matches!(chars.peek(), Some((_, '=' | '.'))) => {
+                // Equivalence classes and collating symbols, such as `[=e=]`,
+                // are copied as is, including their closing brackets.
+                let (_, delimiter) = chars.next().expect("no delimiter");
+                corrected.push(x);
+                corrected.push(delimiter);
+                while let Some((_, y)) = chars.next() {
+                    corrected.push(y);
+                    if y == delimiter && chars.next_if(|(_, x)| *x == ']').is_some() {
+                        corrected.push(']');
+                        break;
+                    }
+                }
+            },
+            _ if is_class || is_flags => {
+                corrected.push(x);
+            },
+            '[' => {
+                is_class = true;
+                corrected.push(x);
+            },
+            '(' if chars.peek().map_or(false, |(_, x)| *x == '?') => {
+                is_flags = true;
+                corrected.push(x);
+            },
+            ')' if matches!(scopes.last(), Some(None)) => {
+                scopes.pop();
+                corrected.push(x);
+            },
+            ')' if matches!(scopes.last(), Some(Some(_)))
+                && matches!(chars.peek(), Some((_, '?'))) =>
+            {
+                // The parentheses delimit an optional group, so the opening
+                // parenthesis is restored.
+                if let Some(Some((index, correction))) = scopes.pop() {
+                    corrected.remove(index);
+                    corrections.remove(correction);
+                }
+                corrected.push(x);
+            },
+            '(' | ')' | ']' => {
+                if x == '(' {
+                    scopes.push(Some((corrected.len(), corrections.len())));
+                }
+                else if x == ')' {
+                    scopes.pop();
+                }
+                corrected.push('\\');
+                corrected.push(x);
+                corrections.push(Correction {
+                    kind: if x == ']' {
+                        CorrectionKind::LiteralBracket
+                    }
+                    else {
+                        CorrectionKind::LiteralParenthesis
+                    },
+                    span: (start, 1),
+                });
+            },
+            '*' => {
+                let mut n = 1;
+                while chars.next_if(|(_, x)| *x == '*').is_some() {
+                    n += 1;
+                }
+                if n > 2 {
+                    corrected.push_str("**");
+                    corrections.push(Correction {
+                        kind: CorrectionKind::CollapsedTree,
+                        span: (start, n),
+                    });
+                }
+                else {
+                    corrected.push_str(&expression[start..][..n]);
+                }
+            },
+            _ => {
+                corrected.push(x);
+            },
+        }
+    }
+    if corrections.is_empty() {
+        (expression.into(), corrections)
+    }
+    else {
+        (corrected.into(), corrections)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::lenient::{self, CorrectionKind};
+    use crate::{Glob, LocatedError as _, Pattern as _};
+
+    #[test]
+    fn correct_expression() {
+        let correct = |expression| {
+            let (corrected, corrections) = lenient::correct(expression);
+            (
+                corrected.into_owned(),
+                corrections
+                    .into_iter()
+                    .map(|correction| (correction.kind(), correction.span()))
+                    .collect::<Vec<_>>(),
+            )
+        };
+
+        assert_eq!(
+            correct("src/**/*.rs"),
+            (String::from("src/**/*.rs"), vec![])
+        );
+        assert_eq!(
+            correct("a/***/b"),
+            (
+                String::from("a/**/b"),
+                vec![(CorrectionKind::CollapsedTree, (2, 3))],
+            ),
+        );
+        assert_eq!(
+            correct("(?i)notes (draft)].txt"),
+            (
+                String::from("(?i)notes \\(draft\\)\\].txt"),
+                vec![
+                    (CorrectionKind::LiteralParenthesis, (10, 1)),
+                    (CorrectionKind::LiteralParenthesis, (16, 1)),
+                    (CorrectionKind::LiteralBracket, (17, 1)),
+                ],
+            ),
+        );
+        assert_eq!(
+            correct("(?i:notes (draft)).txt"),
+            (
+                String::from("(?i:notes \\(draft\\)).txt"),
+                vec![
+                    (CorrectionKind::LiteralParenthesis, (10, 1)),
+                    (CorrectionKind::LiteralParenthesis, (16, 1)),
+                ],
+            ),
+        );
+        // Optional groups are not corrected.
+        assert_eq!(
+            correct("app(-v2)? (1).log"),
+            (
+                String::from("app(-v2)? \\(1\\).log"),
+                vec![
+                    (CorrectionKind::LiteralParenthesis, (10, 1)),
+                    (CorrectionKind::LiteralParenthesis, (12, 1)),
+                ],
+            ),
+        );
+        // Escaped characters and classes are not corrected.
+        assert_eq!(correct("\\(a\\)[(]"), (String::from("\\(a\\)[(]"), vec![]));
+        assert_eq!(correct("[[=e=][.].]]"), (String::from("[[=e=][.].]]"), vec![]));
+    }
+
+    #[test]
+    fn build_lenient_glob() {
+        let (glob, corrections) = Glob::lenient("logs/***/app (1).log").unwrap();
+        assert_eq!(corrections.len(), 3);
+        assert_eq!(glob.to_string(), "logs/**/app \\(1\\).log");
+        assert!(glob.is_match("logs/2023/app (1).log"));
+
+        assert!(Glob::new("logs/***/app (1).log").is_err());
+        assert!(Glob::lenient("{a,b").is_err());
+    }
+}
@@ -31,38 +31,129 @@
     clippy::unused_self
 )]
 
+mod anchor;
+mod builder;
+pub mod cache;
 mod capture;
+mod component;
 mod diagnostics;
+mod dispatch;
 mod encode;
+mod expand;
+mod filter;
+mod index;
+mod intern;
+mod interop;
+mod iter;
+mod key;
+mod lenient;
+mod lines;
+mod overrides;
+mod portability;
+mod router;
 mod rule;
+mod safe;
+mod set;
+mod substitute;
+mod swap;
+mod template;
 mod token;
+mod unanchor;
+pub mod variance;
 mod walk;
 
 use itertools::Position;
 #[cfg(feature = "miette")]
-use miette::Diagnostic;
-use regex::Regex;
+use miette::{Diagnostic, Report};
+use regex::{Regex, RegexSet};
+#[cfg(feature = "serde")]
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use std::borrow::{Borrow, Cow};
 use std::convert::Infallible;
 use std::ffi::OsStr;
 use std::fmt::{self, Debug, Display, Formatter};
-use std::path::{Path, PathBuf};
+use std::io::BufRead;
+use std::path::{self, Path, PathBuf};
+use std::rc::Rc;
 use std::str::{self, FromStr};
+use std::string::FromUtf16Error;
+use std::sync::Arc;
 #[cfg(feature = "miette")]
-use tardar::{DiagnosticResult, DiagnosticResultExt as _, IteratorExt as _, ResultExt as _};
+use tardar::{
+    BoxedDiagnostic, DiagnosticResult, DiagnosticResultExt as _, IteratorExt as _, ResultExt as _,
+};
 use thiserror::Error;
 
-use crate::encode::CompileError;
-use crate::rule::{Checked, RuleError};
-use crate::token::{InvariantText, ParseError, Token, TokenTree, Tokenized};
+use crate::dispatch::PrefixDispatch;
+use crate::encode::{CompileError, LazyProgram, LazyProgramSet};
+use crate::rule::{AsChecked, Checked, RuleError};
+use crate::template::TemplateError;
+use crate::token::{
+    Composition, InvariantText, ParseError, Token, TokenKind, TokenTree, Tokenized,
+};
 
+pub use crate::anchor::Anchored;
+#[cfg(feature = "walk")]
+pub use crate::anchor::AnchoredWalk;
+pub use crate::builder::GlobBuilder;
 pub use crate::capture::MatchedText;
+pub use crate::component::GlobComponent;
+#[cfg(feature = "miette")]
+pub use crate::diagnostics::{DiagnosticRecord, DiagnosticReport};
 pub use crate::diagnostics::{LocatedError, Span};
+pub use crate::expand::ExpansionError;
+pub use crate::filter::Filter;
+#[cfg(feature = "walk")]
+pub use crate::filter::FilterWalk;
+pub use crate::index::{IndexMatches, PathIndex};
+pub use crate::intern::Interner;
+#[cfg(feature = "clap")]
+pub use crate::interop::GlobValueParser;
+#[cfg(feature = "globset")]
+pub use crate::interop::GlobsetError;
+#[cfg(feature = "ignore")]
+pub use crate::interop::IgnoreFilter;
+#[cfg(feature = "notify")]
+pub use crate::interop::{GlobWatcher, WatchError, WatchEvent, WatchItem};
+#[cfg(feature = "proptest")]
+pub use crate::interop::glob_with_paths;
+#[cfg(feature = "tar")]
+pub use crate::interop::TarMatches;
+#[cfg(feature = "url")]
+pub use crate::interop::FileUrlError;
+#[cfg(feature = "zip")]
+pub use crate::interop::ZipMatches;
+#[cfg(feature = "cap-std")]
+pub use crate::interop::{DirEntries, DirFilesystem};
+pub use crate::iter::{FilterGlob, IteratorExt};
+pub use crate::key::{FilterKeys, KeyFilter};
+pub use crate::lenient::{Correction, CorrectionKind};
+pub use crate::lines::FilterLines;
+pub use crate::overrides::{Decision, Overrides, Polarity};
+pub use crate::portability::Divergence;
+pub use crate::router::{GlobRouter, Routed};
+pub use crate::safe::EscapeError;
+pub use crate::set::{GlobId, GlobSet};
+pub use crate::substitute::SubstitutionError;
+#[cfg(feature = "swap")]
+pub use crate::swap::{GlobSnapshot, SwappableGlobs};
+pub use crate::template::GlobTemplate;
+pub use crate::token::{Boundedness, CaseFolding};
+pub use crate::unanchor::Unanchored;
 #[cfg(feature = "walk")]
 pub use crate::walk::{
-    FileIterator, FilterTarget, FilterTree, LinkBehavior, Negation, Walk, WalkBehavior, WalkEntry,
-    WalkError,
+    CaseSensitivity, Change, ChangeCache, Confinement, DescriptorBudget, DirectoryOrder,
+    FileIterator, FileType, Filesystem, FilterTarget, FilterTree, IncrementalWalk, LinkBehavior,
+    Metadata, Negation, ProgressCallback, PruneCallback, PruneCause, PrunedTree, RetryPolicy,
+    SortCallback, SortEntry, StdEntries, StdFilesystem, TakeMatches, Walk, WalkBackend,
+    WalkBehavior, WalkEntry, WalkError, WalkErrors, WalkOperation, WalkProgress,
 };
+#[cfg(all(feature = "walk", target_os = "linux"))]
+pub use crate::walk::{DentsEntries, LinuxFileType, LinuxFilesystem, LinuxMetadata};
+#[cfg(all(feature = "walk", unix))]
+pub use crate::walk::{FilterPermissions, UnixFileIterator};
+#[cfg(all(feature = "walk", windows))]
+pub use crate::walk::{FindEntries, WindowsFileType, WindowsFilesystem, WindowsMetadata};
 
 #[cfg(windows)]
 const PATHS_ARE_CASE_INSENSITIVE: bool = true;
@@ -83,12 +174,31 @@ impl CharExt for char {
 trait StrExt {
     /// Returns `true` if any characters in the string have casing.
     fn has_casing(&self) -> bool;
+
+    /// Swaps the case of each character in the string that has casing.
+    fn swap_case(&self) -> String;
 }
 
 impl StrExt for str {
     fn has_casing(&self) -> bool {
         self.chars().any(CharExt::has_casing)
     }
+
+    fn swap_case(&self) -> String {
+        let mut swapped = String::with_capacity(self.len());
+        for x in self.chars() {
+            if x.is_lowercase() {
+                swapped.extend(x.to_uppercase());
+            }
+            else if x.is_uppercase() {
+                swapped.extend(x.to_lowercase());
+            }
+            else {
+                swapped.push(x);
+            }
+        }
+        swapped
+    }
 }
 
 trait PositionExt<T> {
@@ -270,9 +380,17 @@ impl From<token::Variance<InvariantText<'_>>> for Variance {
 /// associated functions. See [`Glob::partition`] for more about globs and path
 /// operations.
 ///
+/// `Pattern` is also implemented for references, [`Arc`]s, [`Rc`]s, and
+/// [`Cow`]s of `Pattern` types, so shared and borrowed patterns can be matched
+/// and composed without cloning or wrapping them. Composing such a pattern
+/// clones at most its token tree.
+///
+/// [`Arc`]: std::sync::Arc
+/// [`Cow`]: std::borrow::Cow
 /// [`Glob::partition`]: crate::Glob::partition
 /// [`Path`]: std::path::Path
 /// [`PathBuf`]: std::path::PathBuf
+/// [`Rc`]: std::rc::Rc
 pub trait Pattern<'t>: Compose<'t, Error = Infallible> {
     /// Returns `true` if a path matches the pattern.
     ///
@@ -455,13 +573,24 @@ impl BuildError {
                 .collect(),
             BuildErrorKind::Rule(ref error) => error
                 .locations()
-                .iter()
                 .map(|location| location as &dyn LocatedError)
                 .collect(),
+            BuildErrorKind::Expansion(ref error) => vec![error as &dyn LocatedError],
             _ => vec![],
         };
         locations.into_iter()
     }
+
+    // Replaces the glob expression of the error and maps the spans of its
+    // locations into it. This is used when building expanded glob expressions.
+    fn map_spans(self, expression: &str, f: &impl Fn(Span) -> Span) -> Self {
+        let kind = match self.kind {
+            BuildErrorKind::Parse(error) => BuildErrorKind::Parse(error.map_spans(expression, f)),
+            BuildErrorKind::Rule(error) => BuildErrorKind::Rule(error.map_spans(expression, f)),
+            kind => kind,
+        };
+        BuildError { kind }
+    }
 }
 
 impl From<BuildErrorKind> for BuildError {
@@ -478,6 +607,22 @@ impl From<CompileError> for BuildError {
     }
 }
 
+impl From<ExpansionError> for BuildError {
+    fn from(error: ExpansionError) -> Self {
+        BuildError {
+            kind: BuildErrorKind::Expansion(error),
+        }
+    }
+}
+
+impl From<TemplateError> for BuildError {
+    fn from(error: TemplateError) -> Self {
+        BuildError {
+            kind: BuildErrorKind::Template(error),
+        }
+    }
+}
+
 impl From<Infallible> for BuildError {
     fn from(_: Infallible) -> Self {
         unreachable!()
@@ -509,10 +654,63 @@ enum BuildErrorKind {
     Compile(CompileError),
     #[error(transparent)]
     #[cfg_attr(feature = "miette", diagnostic(transparent))]
+    Expansion(ExpansionError),
+    #[error(transparent)]
+    #[cfg_attr(feature = "miette", diagnostic(transparent))]
     Parse(ParseError<'static>),
     #[error(transparent)]
     #[cfg_attr(feature = "miette", diagnostic(transparent))]
     Rule(RuleError<'static>),
+    #[error(transparent)]
+    #[cfg_attr(feature = "miette", diagnostic(transparent))]
+    Template(TemplateError),
+}
+
+/// Flavor of a [`CandidatePath`].
+///
+/// The kind of a path determines how its separators and the case of its text
+/// are treated when matched against a [`Glob`]. By default, paths are
+/// [native][`PathKind::Native`] and follow the conventions of the target
+/// platform. Paths that originate from another platform, such as Windows paths
+/// in logs processed on Linux, can be constructed with a foreign kind via
+/// [`CandidatePath::windows`] and [`CandidatePath::unix`].
+///
+/// Literals in a glob expression that are not affected by flags are matched
+/// using the default case sensitivity of the kind of a path: case-insensitive
+/// for Windows paths and case-sensitive for Unix paths. Flags in a glob
+/// expression always take precedence. Other patterns, such as [`Any`], match
+/// paths using the defaults of the target platform regardless of their kind.
+///
+/// [`Any`]: crate::Any
+/// [`CandidatePath`]: crate::CandidatePath
+/// [`CandidatePath::unix`]: crate::CandidatePath::unix
+/// [`CandidatePath::windows`]: crate::CandidatePath::windows
+/// [`Glob`]: crate::Glob
+/// [`PathKind::Native`]: crate::PathKind::Native
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum PathKind {
+    /// A path that follows the conventions of the target platform.
+    Native,
+    /// A Unix path with case-sensitive names and `/` separators.
+    Unix,
+    /// A Windows path with case-insensitive names and `\` or `/` separators.
+    Windows,
+}
+
+impl PathKind {
+    fn is_case_insensitive(&self) -> bool {
+        match self {
+            PathKind::Native => PATHS_ARE_CASE_INSENSITIVE,
+            PathKind::Unix => false,
+            PathKind::Windows => true,
+        }
+    }
+}
+
+impl Default for PathKind {
+    fn default() -> Self {
+        PathKind::Native
+    }
 }
 
 /// Path that can be matched against a [`Pattern`].
@@ -523,17 +721,184 @@ enum BuildErrorKind {
 /// means that some byte sequences cannot be matched, though this is uncommon in
 /// practice.
 ///
+/// On Windows, verbatim and device prefixes are removed from paths with a
+/// drive letter or UNC server, so paths such as those returned by
+/// [`fs::canonicalize`] are matched like regular paths. For example,
+/// `\\?\C:\a` is matched like `C:\a`. See [`CandidatePath::windows`].
+///
+/// The encoded text of a path is retained by a `CandidatePath`. When matching a
+/// path against many [`Pattern`]s, construct a `CandidatePath` once and match
+/// it by reference, which borrows its text rather than encoding the path again.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::Path;
+/// use wax::{CandidatePath, Glob, Pattern};
+///
+/// let globs = [Glob::new("**/*.rs").unwrap(), Glob::new("src/**").unwrap()];
+/// let candidate = CandidatePath::from(Path::new("src/lib.rs"));
+/// assert!(globs.iter().all(|glob| glob.is_match(&candidate)));
+/// ```
+///
+/// [`CandidatePath::windows`]: crate::CandidatePath::windows
+/// [`fs::canonicalize`]: std::fs::canonicalize
 /// [`Pattern`]: crate::Pattern
 #[derive(Clone)]
 pub struct CandidatePath<'b> {
     text: Cow<'b, str>,
+    kind: PathKind,
 }
 
 impl<'b> CandidatePath<'b> {
+    fn from_windows_text(text: Cow<'b, str>) -> Self {
+        let text = strip_verbatim_prefix(text);
+        CandidatePath {
+            text: if text.contains('\\') {
+                text.replace('\\', "/").into()
+            }
+            else {
+                text
+            },
+            kind: PathKind::Windows,
+        }
+    }
+
+    /// Constructs a `CandidatePath` from the text of a Unix path.
+    ///
+    /// Names in the path are matched case-sensitively by default regardless
+    /// of the target platform. See [`PathKind`].
+    ///
+    /// Only `/` is interpreted as a separator on Unix, but the target platform
+    /// determines which characters are separators when matching, so
+    /// backslashes in the names of Unix paths are interpreted as separators on
+    /// Windows.
+    ///
+    /// [`PathKind`]: crate::PathKind
+    pub fn unix(text: &'b str) -> Self {
+        CandidatePath {
+            text: text.into(),
+            kind: PathKind::Unix,
+        }
+    }
+
+    /// Constructs a `CandidatePath` from the text of a Windows path.
+    ///
+    /// Backslash separators are normalized to `/` regardless of the target
+    /// platform and names in the path are matched case-insensitively by
+    /// default. See [`PathKind`].
+    ///
+    /// Verbatim and device prefixes are removed from paths with a drive letter
+    /// or UNC server, such that `\\?\C:\a` and `\\.\C:\a` are matched like
+    /// `C:\a` and `\\?\UNC\server\share` is matched like `\\server\share`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{CandidatePath, Glob, Pattern};
+    ///
+    /// let glob = Glob::new("logs/*.txt").unwrap();
+    /// assert!(glob.is_match(CandidatePath::windows(r"Logs\Server.TXT")));
+    /// ```
+    ///
+    /// [`PathKind`]: crate::PathKind
+    pub fn windows(text: &'b str) -> Self {
+        CandidatePath::from_windows_text(text.into())
+    }
+
+    /// Gets the [kind][`PathKind`] of the path.
+    ///
+    /// [`PathKind`]: crate::PathKind
+    pub fn kind(&self) -> PathKind {
+        self.kind
+    }
+
+    /// Constructs a `CandidatePath` from bytes, replacing any invalid UTF-8.
+    ///
+    /// Byte sequences that are not valid UTF-8 are replaced with Unicode
+    /// replacement codepoints `�`. The bytes are borrowed if they are valid
+    /// UTF-8. On Unix, this is equivalent to constructing a `CandidatePath`
+    /// from an [`OsStr`] with the given bytes (see [`OsStrExt::from_bytes`]).
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{CandidatePath, Glob, Pattern};
+    ///
+    /// let candidate = CandidatePath::from_bytes_lossy(b"photos/\xFF.jpg");
+    /// assert_eq!(candidate.as_ref(), "photos/\u{FFFD}.jpg");
+    /// assert!(Glob::new("photos/*.jpg").unwrap().is_match(candidate));
+    /// ```
+    ///
+    /// [`OsStr`]: std::ffi::OsStr
+    /// [`OsStrExt::from_bytes`]: https://doc.rust-lang.org/std/os/unix/ffi/trait.OsStrExt.html#tymethod.from_bytes
+    pub fn from_bytes_lossy(bytes: &'b [u8]) -> Self {
+        CandidatePath {
+            text: String::from_utf8_lossy(bytes),
+            kind: PathKind::Native,
+        }
+    }
+
+    /// Constructs a `CandidatePath` from bytes that must be valid UTF-8.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the bytes are not valid UTF-8. Use
+    /// [`from_bytes_lossy`] to replace invalid byte sequences instead.
+    ///
+    /// [`from_bytes_lossy`]: crate::CandidatePath::from_bytes_lossy
+    pub fn from_bytes(bytes: &'b [u8]) -> Result<Self, str::Utf8Error> {
+        str::from_utf8(bytes).map(CandidatePath::from)
+    }
+
+    /// Constructs a `CandidatePath` from UTF-16, replacing any invalid code
+    /// units.
+    ///
+    /// Unpaired surrogates are replaced with Unicode replacement codepoints
+    /// `�`. On Windows, this is equivalent to constructing a `CandidatePath`
+    /// from an [`OsString`] with the given wide string (see
+    /// [`OsStringExt::from_wide`]). Any terminating nul in the wide string is
+    /// **not** removed and should be excluded from the slice.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{CandidatePath, Glob, Pattern};
+    ///
+    /// let wide: Vec<u16> = "src/lib.rs".encode_utf16().collect();
+    /// let candidate = CandidatePath::from_utf16_lossy(&wide);
+    /// assert!(Glob::new("src/*.rs").unwrap().is_match(candidate));
+    /// ```
+    ///
+    /// [`OsString`]: std::ffi::OsString
+    /// [`OsStringExt::from_wide`]: https://doc.rust-lang.org/std/os/windows/ffi/trait.OsStringExt.html#tymethod.from_wide
+    pub fn from_utf16_lossy(wide: &[u16]) -> CandidatePath<'static> {
+        CandidatePath {
+            text: String::from_utf16_lossy(wide).into(),
+            kind: PathKind::Native,
+        }
+    }
+
+    /// Constructs a `CandidatePath` from UTF-16 that must be valid.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the wide string contains unpaired surrogates. Use
+    /// [`from_utf16_lossy`] to replace invalid code units instead.
+    ///
+    /// [`from_utf16_lossy`]: crate::CandidatePath::from_utf16_lossy
+    pub fn from_utf16(wide: &[u16]) -> Result<CandidatePath<'static>, FromUtf16Error> {
+        String::from_utf16(wide).map(|text| CandidatePath {
+            text: text.into(),
+            kind: PathKind::Native,
+        })
+    }
+
     /// Clones any borrowed data into an owning instance.
     pub fn into_owned(self) -> CandidatePath<'static> {
         CandidatePath {
             text: self.text.into_owned().into(),
+            kind: self.kind,
         }
     }
 }
@@ -558,8 +923,15 @@ impl Display for CandidatePath<'_> {
 
 impl<'b> From<&'b OsStr> for CandidatePath<'b> {
     fn from(text: &'b OsStr) -> Self {
+        let text = text.to_string_lossy();
         CandidatePath {
-            text: text.to_string_lossy(),
+            text: if cfg!(windows) {
+                strip_verbatim_prefix(text)
+            }
+            else {
+                text
+            },
+            kind: PathKind::Native,
         }
     }
 }
@@ -570,9 +942,51 @@ impl<'b> From<&'b Path> for CandidatePath<'b> {
     }
 }
 
+impl<'b> From<&'b CandidatePath<'_>> for CandidatePath<'b> {
+    fn from(candidate: &'b CandidatePath<'_>) -> Self {
+        CandidatePath {
+            text: Cow::Borrowed(candidate.text.as_ref()),
+            kind: candidate.kind,
+        }
+    }
+}
+
 impl<'b> From<&'b str> for CandidatePath<'b> {
     fn from(text: &'b str) -> Self {
-        CandidatePath { text: text.into() }
+        CandidatePath {
+            text: text.into(),
+            kind: PathKind::Native,
+        }
+    }
+}
+
+// Removes verbatim (`\\?\`) and device (`\\.\`) prefixes from Windows paths
+// with a drive letter or UNC server. Other verbatim and device paths, such as
+// `\\.\PIPE\name`, cannot be expressed as regular paths and are unchanged.
+fn strip_verbatim_prefix(text: Cow<str>) -> Cow<str> {
+    fn is_disk(text: &str) -> bool {
+        let mut chars = text.chars();
+        matches!(
+            (chars.next(), chars.next(), chars.next()),
+            (Some(letter), Some(':'), None | Some('\\')) if letter.is_ascii_alphabetic()
+        )
+    }
+
+    if let Some(unc) = text.strip_prefix(r"\\?\UNC\") {
+        return format!(r"\\{}", unc).into();
+    }
+    let n = match [r"\\?\", r"\\.\"]
+        .iter()
+        .find_map(|prefix| text.strip_prefix(prefix))
+    {
+        Some(disk) if is_disk(disk) => text.len() - disk.len(),
+        _ => {
+            return text;
+        },
+    };
+    match text {
+        Cow::Borrowed(text) => Cow::Borrowed(&text[n..]),
+        Cow::Owned(text) => Cow::Owned(text[n..].into()),
     }
 }
 
@@ -625,6 +1039,14 @@ impl<'b> From<&'b str> for CandidatePath<'b> {
 pub struct Glob<'t> {
     tree: Checked<Tokenized<'t>>,
     pattern: Regex,
+    // The text of the glob if it consists only of case-sensitive literals and
+    // separators. Such globs are matched by comparing text rather than via the
+    // regular expression.
+    literal: Option<String>,
+    // The program with the opposite default case sensitivity. This is used to
+    // match paths of a kind with a different case sensitivity than the target
+    // platform.
+    recased: LazyProgram<Regex>,
 }
 
 impl<'t> Glob<'t> {
@@ -635,6 +1057,44 @@ impl<'t> Glob<'t> {
         encode::compile(tokens)
     }
 
+    fn from_tree(tree: Checked<Tokenized<'t>>) -> Result<Self, CompileError> {
+        let pattern = Glob::compile(tree.as_ref().tokens())?;
+        let literal = token::literal_text(tree.as_ref().tokens());
+        Ok(Glob {
+            tree,
+            pattern,
+            literal,
+            recased: LazyProgram::default(),
+        })
+    }
+
+    // Gets the program with the opposite default case sensitivity if the kind
+    // of the path differs from the target platform.
+    fn recased(&self, path: &CandidatePath<'_>) -> Option<Arc<Regex>> {
+        (path.kind.is_case_insensitive() != PATHS_ARE_CASE_INSENSITIVE).then(|| {
+            self.recased.get_or_compile(|| {
+                let tokenized = self.tree.as_ref().clone().recase(!PATHS_ARE_CASE_INSENSITIVE);
+                Glob::compile(tokenized.tokens()).expect("failed to compile glob expression")
+            })
+        })
+    }
+
+    // Returns `true` if the text matches the literal text of the glob. Unlike
+    // the regular expression, this does not allocate.
+    fn is_literal_match(literal: &str, text: &str) -> bool {
+        let mut text = text.chars();
+        literal.chars().all(|x| {
+            text.next().map_or(false, |y| {
+                if x == '/' {
+                    path::is_separator(y)
+                }
+                else {
+                    x == y
+                }
+            })
+        }) && text.next().is_none()
+    }
+
     // TODO: Document pattern syntax in the crate documentation and refer to it
     //       here.
     /// Constructs a [`Glob`] from a glob expression.
@@ -652,8 +1112,7 @@ impl<'t> Glob<'t> {
     /// [`BuildError`]: crate::BuildError
     pub fn new(expression: &'t str) -> Result<Self, BuildError> {
         let tree = parse_and_check(expression)?;
-        let pattern = Glob::compile(tree.as_ref().tokens())?;
-        Ok(Glob { tree, pattern })
+        Ok(Glob::from_tree(tree)?)
     }
 
     /// Constructs a [`Glob`] from a glob expression with diagnostics.
@@ -683,12 +1142,169 @@ impl<'t> Glob<'t> {
     #[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
     pub fn diagnosed(expression: &'t str) -> DiagnosticResult<'t, Self> {
         parse_and_diagnose(expression).and_then_diagnose(|tree| {
-            Glob::compile(tree.as_ref().tokens())
-                .into_error_diagnostic()
-                .map_output(|pattern| Glob { tree, pattern })
+            Glob::from_tree(tree).into_error_diagnostic()
         })
     }
 
+    /// Constructs a [`Glob`] from a glob expression with an aggregated
+    /// diagnostic report.
+    ///
+    /// This function is the same as [`Glob::diagnosed`], but aggregates all
+    /// diagnostics into a single [`Report`]. Each error and warning is a
+    /// related diagnostic of the report with its own code and labeled spans, so
+    /// all diagnostics for a glob expression can be rendered as one block. The
+    /// report is `None` if there are no warnings. The [`Report`] wraps a
+    /// [`DiagnosticReport`].
+    ///
+    /// # Errors
+    ///
+    /// Returns a [`Report`] with all errors and warnings if the glob
+    /// expression fails to build.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let (glob, report) = Glob::reported("../src/**/*.rs").unwrap();
+    /// if let Some(report) = report {
+    ///     eprintln!("{:?}", report);
+    /// }
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`DiagnosticReport`]: crate::DiagnosticReport
+    /// [`Glob::diagnosed`]: crate::Glob::diagnosed
+    /// [`Report`]: miette::Report
+    #[cfg(feature = "miette")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+    pub fn reported(expression: &'t str) -> Result<(Self, Option<Report>), Report> {
+        let report = |diagnostics: &[BoxedDiagnostic<'_>]| {
+            Report::new(DiagnosticReport::from_diagnostics(expression, diagnostics))
+        };
+        match Glob::diagnosed(expression) {
+            Ok((glob, diagnostics)) => Ok((
+                glob,
+                (!diagnostics.is_empty()).then(|| report(&diagnostics)),
+            )),
+            Err(diagnostics) => Err(report(&diagnostics)),
+        }
+    }
+
+    /// Constructs a [`Glob`] from a glob expression, correcting common
+    /// mistakes.
+    ///
+    /// This function is the same as [`Glob::new`], but accepts glob
+    /// expressions with common mistakes and gives them defined semantics. This
+    /// is useful in interactive applications, where users may type imperfect
+    /// glob expressions. The following mistakes are corrected:
+    ///
+    /// - Unescaped parentheses `(` and `)` that do not delimit flags are
+    ///   matched literally.
+    /// - Unescaped closing brackets `]` outside of classes are matched
+    ///   literally.
+    /// - Runs of three or more `*` are collapsed into a tree wildcard `**`.
+    ///
+    /// The [`Correction`]s that have been applied are returned with the
+    /// [`Glob`]. The [`Glob`] is built from the corrected glob expression,
+    /// which is given by its [`Display`] implementation.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the corrected glob expression fails to build. The
+    /// [locations][`BuildError::locations`] of the error refer to the corrected
+    /// glob expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{Glob, Pattern};
+    ///
+    /// let (glob, corrections) = Glob::lenient("photos/***/IMG (1).jpg").unwrap();
+    /// for correction in corrections {
+    ///     eprintln!("{}", correction);
+    /// }
+    /// assert!(glob.is_match("photos/2023/IMG (1).jpg"));
+    /// ```
+    ///
+    /// [`BuildError::locations`]: crate::BuildError::locations
+    /// [`Correction`]: crate::Correction
+    /// [`Display`]: std::fmt::Display
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::new`]: crate::Glob::new
+    pub fn lenient(expression: &'t str) -> Result<(Self, Vec<Correction>), BuildError> {
+        let (corrected, corrections) = lenient::correct(expression);
+        let glob = match corrected {
+            Cow::Borrowed(expression) => Glob::new(expression)?,
+            Cow::Owned(expression) => Glob::new(&expression)?.into_owned(),
+        };
+        Ok((glob, corrections))
+    }
+
+    /// Constructs a [`Glob`] from [`GlobComponent`]s.
+    ///
+    /// Components are delimited by separators and the text of literals and
+    /// extensions is escaped, so text from untrusted sources, such as the
+    /// names of directories given by users, is always matched literally. See
+    /// [`GlobComponent`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the constructed glob expression fails to build.
+    /// For example, adjacent [`Tree`] components violate rules and some
+    /// characters, such as `\`, cannot be matched literally.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{Glob, GlobComponent, Pattern};
+    ///
+    /// let directory = "assets[v2]";
+    /// let glob = Glob::from_components([
+    ///     GlobComponent::Literal(directory),
+    ///     GlobComponent::Tree,
+    ///     GlobComponent::Extension("png"),
+    /// ])
+    /// .unwrap();
+    /// assert!(glob.is_match("assets[v2]/icons/close.png"));
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`GlobComponent`]: crate::GlobComponent
+    /// [`Tree`]: crate::GlobComponent::Tree
+    pub fn from_components<'a, I>(components: I) -> Result<Glob<'static>, BuildError>
+    where
+        I: IntoIterator<Item = GlobComponent<'a>>,
+    {
+        Glob::new(&component::expression(components)).map(Glob::into_owned)
+    }
+
+    /// Constructs a [`Glob`] that matches exactly the given path.
+    ///
+    /// The path is escaped by [`escape_path`], so any meta-characters in the
+    /// path, such as `[`, `{`, or `(`, are matched literally.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the escaped path fails to build. Some characters,
+    /// such as `\` in file names on Unix, cannot be matched literally.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{Glob, Pattern};
+    ///
+    /// let glob = Glob::literal("photos/{2022}/[raw](1).cr2").unwrap();
+    /// assert!(glob.is_match("photos/{2022}/[raw](1).cr2"));
+    /// assert!(!glob.is_match("photos/2022/r(1).cr2"));
+    /// ```
+    ///
+    /// [`escape_path`]: crate::escape_path
+    /// [`Glob`]: crate::Glob
+    pub fn literal(path: impl AsRef<Path>) -> Result<Glob<'static>, BuildError> {
+        Glob::new(&escape_path(path.as_ref())).map(Glob::into_owned)
+    }
+
     /// Partitions a [`Glob`] into an invariant [`PathBuf`] prefix and variant
     /// [`Glob`] postfix.
     ///
@@ -752,47 +1368,727 @@ impl<'t> Glob<'t> {
     pub fn partition(self) -> (PathBuf, Self) {
         let Glob { tree, .. } = self;
         let (prefix, tree) = tree.partition();
-        let pattern =
-            Glob::compile(tree.as_ref().tokens()).expect("failed to compile partitioned glob");
-        (prefix, Glob { tree, pattern })
+        (
+            prefix,
+            Glob::from_tree(tree).expect("failed to compile partitioned glob"),
+        )
     }
 
-    /// Clones any borrowed data into an owning instance.
+    /// Partitions a [`Glob`] into an invariant [`PathBuf`] prefix and variant
+    /// [`Glob`] postfix without consuming it.
     ///
-    /// # Examples
+    /// This function is the same as [`Glob::partition`], but borrows the
+    /// [`Glob`] so that it can be used after it has been partitioned. The
+    /// variant [`Glob`] postfix borrows the same glob expression text as the
+    /// [`Glob`]. See [`Glob::invariant_prefix_span`] to locate the invariant
+    /// prefix in the glob expression.
     ///
-    /// `Glob`s borrow data in the corresponding glob expression. To move a
-    /// `Glob` beyond the scope of a glob expression, clone the data with this
-    /// function.
+    /// # Examples
     ///
     /// ```rust
-    /// use wax::{BuildError, Glob};
+    /// use std::path::Path;
+    /// use wax::Glob;
     ///
-    /// fn local() -> Result<Glob<'static>, BuildError> {
-    ///     let expression = String::from("**/*.txt");
-    ///     Glob::new(&expression).map(Glob::into_owned)
-    /// }
+    /// let glob = Glob::new("src/**/*.rs").unwrap();
+    /// let (prefix, postfix) = glob.partitioned();
+    ///
+    /// assert_eq!(prefix, Path::new("src"));
+    /// assert_eq!(postfix.to_string(), "**/*.rs");
+    /// assert_eq!(glob.to_string(), "src/**/*.rs");
     /// ```
-    pub fn into_owned(self) -> Glob<'static> {
-        let Glob { tree, pattern } = self;
-        Glob {
-            tree: tree.into_owned(),
-            pattern,
-        }
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::invariant_prefix_span`]: crate::Glob::invariant_prefix_span
+    /// [`Glob::partition`]: crate::Glob::partition
+    /// [`PathBuf`]: std::path::PathBuf
+    pub fn partitioned(&self) -> (PathBuf, Glob<'t>) {
+        let (prefix, tree) = self.tree.clone().partition();
+        (
+            prefix,
+            Glob::from_tree(tree).expect("failed to compile partitioned glob"),
+        )
     }
 
-    /// Gets an iterator over matching files in a directory tree.
+    /// Gets the [`Span`] of the invariant prefix in the glob expression.
     ///
-    /// This function matches a [`Glob`] against a directory tree, returning
-    /// each matching file as a [`WalkEntry`]. [`Glob`]s are the only patterns
-    /// that support this semantic operation; it is not possible to match
-    /// combinators over directory trees.
+    /// The [`Span`] covers the text that is discarded when the [`Glob`] is
+    /// [partitioned][`Glob::partition`], including any separator that
+    /// delimits the invariant prefix. This can be used to highlight the part
+    /// of a glob expression that establishes the [root of a
+    /// traversal][`Walk::root`]. Returns `None` if the [`Glob`] has no
+    /// invariant prefix.
     ///
-    /// As with [`Path::join`] and [`PathBuf::push`], the base directory can be
-    /// escaped or overridden by rooted [`Glob`]s. In many cases, the current
-    /// working directory `.` is an appropriate base directory and will be
-    /// intuitively ignored if the [`Glob`] is rooted, such as in
-    /// `/mnt/media/**/*.mp4`. The [`has_root`] function can be used to check if
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("/var/log/**/*.log").unwrap();
+    /// let (start, n) = glob.invariant_prefix_span().unwrap();
+    /// assert_eq!(&glob.to_string()[start..][..n], "/var/log/");
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::partition`]: crate::Glob::partition
+    /// [`Span`]: crate::Span
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn invariant_prefix_span(&self) -> Option<Span> {
+        self.tree.as_ref().invariant_prefix_span()
+    }
+
+    /// Gets the native path that is equivalent to the [`Glob`] if it is
+    /// invariant.
+    ///
+    /// An invariant [`Glob`] matches exactly one path with respect to the
+    /// platform's file system APIs, so it is not necessary to walk a directory
+    /// tree to find a match. Instead, the path can be queried directly.
+    ///
+    /// Returns `None` if the [`Glob`] is variant. See [`Variance`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("config/app.toml").unwrap();
+    /// if let Some(path) = glob.as_literal_path() {
+    ///     if path.exists() {
+    ///         // ...
+    ///     }
+    /// }
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Variance`]: crate::Variance
+    pub fn as_literal_path(&self) -> Option<PathBuf> {
+        match self.variance() {
+            Variance::Invariant(path) => Some(path),
+            Variance::Variant => None,
+        }
+    }
+
+    /// Gets an example path that matches the [`Glob`].
+    ///
+    /// The example uses minimal expansions of patterns: the first branch of
+    /// alternatives, the fewest repetitions, and the fewest characters and
+    /// components for wildcards. This is useful for documentation and previews
+    /// of glob expressions.
+    ///
+    /// Returns `None` if the [`Glob`] matches no paths, such as when a
+    /// character class matches only separators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::path::Path;
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("src/**/*.{rs,toml}").unwrap();
+    /// assert_eq!(glob.example().unwrap(), Path::new("src/.rs"));
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn example(&self) -> Option<PathBuf> {
+        token::examples(self.tree.as_ref().tokens())
+            .next()
+            .map(PathBuf::from)
+    }
+
+    /// Gets up to `n` distinct example paths that match the [`Glob`].
+    ///
+    /// The first example is the same as [`Glob::example`]. Subsequent examples
+    /// vary the expansions of patterns, such as choosing other branches of
+    /// alternatives and expanding wildcards. Fewer than `n` examples are
+    /// returned if the [`Glob`] matches fewer distinct paths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{Glob, Pattern};
+    ///
+    /// let glob = Glob::new("**/*.{rs,toml}").unwrap();
+    /// for path in glob.examples(4) {
+    ///     assert!(glob.is_match(path.as_path()));
+    /// }
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::example`]: crate::Glob::example
+    pub fn examples(&self, n: usize) -> Vec<PathBuf> {
+        token::examples(self.tree.as_ref().tokens())
+            .take(n)
+            .map(PathBuf::from)
+            .collect()
+    }
+
+    /// Filters newline-delimited paths read from a [`BufRead`] against the
+    /// [`Glob`].
+    ///
+    /// Paths are delimited by `\n` and any `\r` that precedes the delimiter is
+    /// discarded, so both LF and CRLF line endings are supported. This reads
+    /// path lists as produced by tools like `git ls-files` and `find`. The
+    /// returned iterator yields the [matched text][`MatchedText`] of matching
+    /// paths and any I/O errors. See [`FilterLines`].
+    ///
+    /// Paths may contain newlines, so delimiting paths by `NUL` is more
+    /// robust when the producer supports it. See
+    /// [`Glob::filter_nul_delimited`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::io;
+    /// use wax::Glob;
+    ///
+    /// // $ git ls-files | my-program
+    /// let glob = Glob::new("src/**/*.rs").unwrap();
+    /// for matched in glob.filter_lines(io::stdin().lock()) {
+    ///     println!("{}", matched.unwrap().complete());
+    /// }
+    /// ```
+    ///
+    /// [`BufRead`]: std::io::BufRead
+    /// [`FilterLines`]: crate::FilterLines
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::filter_nul_delimited`]: crate::Glob::filter_nul_delimited
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn filter_lines<R>(&self, reader: R) -> FilterLines<'_, 't, R>
+    where
+        R: BufRead,
+    {
+        FilterLines::new(reader, self, b'\n')
+    }
+
+    /// Filters `NUL`-delimited paths read from a [`BufRead`] against the
+    /// [`Glob`].
+    ///
+    /// This function is the same as [`Glob::filter_lines`], but paths are
+    /// delimited by `NUL` (`\0`) bytes, as produced by tools like
+    /// `git ls-files -z` and `find -print0`. Unlike newlines, `NUL` cannot
+    /// appear in paths.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("**/{*}.md").unwrap();
+    /// let input = b"doc/guide.md\0src/lib.rs\0README.md\0";
+    /// let names: Vec<_> = glob
+    ///     .filter_nul_delimited(&input[..])
+    ///     .map(|matched| matched.unwrap().get(2).unwrap().to_owned())
+    ///     .collect();
+    /// assert_eq!(names, ["guide", "README"]);
+    /// ```
+    ///
+    /// [`BufRead`]: std::io::BufRead
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::filter_lines`]: crate::Glob::filter_lines
+    pub fn filter_nul_delimited<R>(&self, reader: R) -> FilterLines<'_, 't, R>
+    where
+        R: BufRead,
+    {
+        FilterLines::new(reader, self, b'\0')
+    }
+
+    /// Constructs a path by substituting values for the captures of the
+    /// [`Glob`].
+    ///
+    /// Values are given as pairs of capture indices and text. As with
+    /// [`MatchedText::get`], capturing tokens are indexed from one in the order
+    /// in which they appear in the glob expression. Each value must match its
+    /// capturing token: for example, a value for `*` cannot contain
+    /// separators and a value for `[0-9]` must be a single digit. Values for
+    /// tree wildcards (`**`) may contain any number of components and any
+    /// leading or trailing separators are ignored. Tree wildcards with no value
+    /// match no components and capturing tokens with invariant text, such as
+    /// `{src}`, need no value. Indices that do not
+    /// refer to a capturing token are ignored and if an index is given more
+    /// than once, then the last value is used.
+    ///
+    /// Glob expressions do not name their captures, so values are always
+    /// given by index. To build globs from named parts, see [`GlobTemplate`].
+    ///
+    /// This allows a [`Glob`] to both match paths and generate them. Text
+    /// matched by a [`Glob`] can be substituted to reconstruct the matched
+    /// path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::path::Path;
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("outputs/*/strings.json").unwrap();
+    /// let path = glob.substitute([(1, "de")]).unwrap();
+    /// assert_eq!(path, Path::new("outputs/de/strings.json"));
+    /// assert!(glob.substitute([(1, "de/at")]).is_err());
+    /// ```
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a capturing token with variant text other than a
+    /// tree wildcard has no value or if a value does not match its capturing
+    /// token.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`GlobTemplate`]: crate::GlobTemplate
+    /// [`MatchedText::get`]: crate::MatchedText::get
+    pub fn substitute<I, V>(&self, values: I) -> Result<PathBuf, SubstitutionError>
+    where
+        I: IntoIterator<Item = (usize, V)>,
+        V: AsRef<str>,
+    {
+        let values: Vec<_> = values.into_iter().collect();
+        substitute::substitute(
+            self.tree.as_ref().expression(),
+            self.tree.as_ref().tokens(),
+            &values,
+        )
+    }
+
+    /// Gets the root of the [`Glob`] beneath a directory.
+    ///
+    /// The [invariant prefix][`Glob::partition`] of the [`Glob`] is joined to
+    /// the directory and normalized lexically. This is the directory from
+    /// which a traversal like [`Glob::walk`] begins, which only reads paths
+    /// beneath this root. Glob expressions from untrusted sources, such as
+    /// requests to a service, should be verified with this function before
+    /// they are used to match or read paths: `../` in an invariant prefix
+    /// reaches parent directories and a rooted glob expression ignores the
+    /// directory entirely.
+    ///
+    /// Paths are resolved lexically, so symbolic links are not considered. See
+    /// [`Confinement`] to also confine traversals with respect to symbolic
+    /// links.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Glob`] is [rooted][`Glob::has_root`] or if its
+    /// invariant prefix resolves outside of the directory. The error locates
+    /// the offending text in the glob expression.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::path::Path;
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("uploads/../images/**/*.png").unwrap();
+    /// let root = glob.safe_root("/srv/tenant").unwrap();
+    /// assert_eq!(root, Path::new("/srv/tenant/images"));
+    ///
+    /// let glob = Glob::new("../../etc/**").unwrap();
+    /// assert!(glob.safe_root("/srv/tenant").is_err());
+    /// ```
+    ///
+    /// [`Confinement`]: crate::Confinement
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::has_root`]: crate::Glob::has_root
+    /// [`Glob::partition`]: crate::Glob::partition
+    /// [`Glob::walk`]: crate::Glob::walk
+    pub fn safe_root(&self, directory: impl AsRef<Path>) -> Result<PathBuf, EscapeError> {
+        safe::safe_root(self.tree.as_ref(), self.has_root(), directory.as_ref())
+    }
+
+    /// Gets a hash of the [`Glob`] that is stable across releases of Wax.
+    ///
+    /// The stable hash is computed by a documented algorithm over the tokens of
+    /// the glob rather than its expression, so it is suitable as a persistent
+    /// key, such as for caches of compiled programs on disk or for detecting
+    /// changes to configuration. Unlike hashes computed via [`Hash`] and
+    /// [`DefaultHasher`], it does not change between releases of Wax or Rust.
+    /// Expressions that differ only in ways that do not change tokens, such as
+    /// redundant flags, have the same hash.
+    ///
+    /// Flags and other options are reflected in the properties of tokens. Note
+    /// that the default case sensitivity of literals depends on the target
+    /// platform, so globs with literals may have different hashes on different
+    /// platforms.
+    ///
+    /// # Algorithm
+    ///
+    /// The hash is the 64-bit [FNV-1a] hash of an encoding of the tokens of the
+    /// glob. Before encoding, adjacent literals with the same case sensitivity
+    /// and [folding][`CaseFolding`] are concatenated into one literal. The
+    /// encoding uses the following primitives:
+    ///
+    /// - Booleans are a byte that is `0x00` for `false` and `0x01` for `true`.
+    /// - Integers are 64-bit little-endian unsigned integers.
+    /// - Characters are 32-bit little-endian Unicode scalar values.
+    /// - Text is its length in bytes as an integer followed by its UTF-8 bytes.
+    ///
+    /// A sequence of tokens is the encoding of each token followed by the byte
+    /// `0x00`. The glob is encoded as a sequence. Each token is encoded as a
+    /// tag byte followed by fields:
+    ///
+    /// | Token                | Tag    | Fields                                                  |
+    /// |----------------------|--------|---------------------------------------------------------|
+    /// | Literal              | `0x01` | case-insensitive (boolean), text                        |
+    /// | Separator            | `0x02` |                                                         |
+    /// | `?` wildcard         | `0x03` | grapheme (boolean)                                      |
+    /// | `*` or `$` wildcard  | `0x04` | lazy (boolean), separator-crossing (boolean)            |
+    /// | `**` wildcard        | `0x05` | rooted (boolean)                                        |
+    /// | Class                | `0x06` | negated (boolean), number of members (integer), members |
+    /// | Alternative          | `0x07` | number of branches (integer), branches (sequences)      |
+    /// | Repetition           | `0x08` | bounds, lazy (boolean), tokens (sequence)               |
+    /// | Turkic literal       | `0x09` | text                                                    |
+    ///
+    /// The members of a class are either `0x00` followed by a character or
+    /// `0x01` followed by the first and last characters of a range. The bounds
+    /// of a repetition are its lower bound as an integer followed by its upper
+    /// bound, which is either `0x00` if it is unbounded or `0x01` followed by
+    /// an integer. Case-insensitive literals with [`CaseFolding::Turkic`] are
+    /// encoded as Turkic literals and all other literals are encoded as
+    /// literals.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("**/*.{md,txt}").unwrap();
+    /// assert_eq!(glob.stable_hash(), Glob::new("**/*.{md,txt}").unwrap().stable_hash());
+    /// assert_ne!(glob.stable_hash(), Glob::new("**/*.{txt,md}").unwrap().stable_hash());
+    /// ```
+    ///
+    /// [`CaseFolding`]: crate::CaseFolding
+    /// [`CaseFolding::Turkic`]: crate::CaseFolding::Turkic
+    /// [`DefaultHasher`]: std::collections::hash_map::DefaultHasher
+    /// [`Glob`]: crate::Glob
+    /// [`Hash`]: std::hash::Hash
+    /// [FNV-1a]: https://datatracker.ietf.org/doc/html/draft-eastlake-fnv
+    pub fn stable_hash(&self) -> u64 {
+        token::stable_hash(self.tree.as_ref().tokens())
+    }
+
+    /// Matches the [`Glob`] against a sequence of components.
+    ///
+    /// The components are matched as if they were joined by separators into a
+    /// path, but are not interpreted by the target platform. This can be used
+    /// to apply the wildcard semantics of globs to hierarchical data that is
+    /// not a path, such as routing segments or topic names.
+    ///
+    /// Returns `None` if the components do not match the [`Glob`] or if any
+    /// component is empty or contains a separator, because such components
+    /// cannot be distinguished from a sequence of components.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("sensors/{*}/**/temperature").unwrap();
+    /// let matched = glob
+    ///     .match_components(&["sensors", "north", "a", "temperature"])
+    ///     .unwrap();
+    /// assert_eq!(matched.get(1), Some("north"));
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn match_components<I>(&self, components: I) -> Option<MatchedText<'static>>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        let mut text = String::new();
+        for (n, component) in components.into_iter().enumerate() {
+            let component = component.as_ref();
+            if component.is_empty() || component.chars().any(path::is_separator) {
+                return None;
+            }
+            if n > 0 {
+                text.push('/');
+            }
+            text.push_str(component);
+        }
+        self.pattern
+            .captures(&text)
+            .map(|captures| MatchedText::from(captures).into_owned())
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    ///
+    /// # Examples
+    ///
+    /// `Glob`s borrow data in the corresponding glob expression. To move a
+    /// `Glob` beyond the scope of a glob expression, clone the data with this
+    /// function.
+    ///
+    /// ```rust
+    /// use wax::{BuildError, Glob};
+    ///
+    /// fn local() -> Result<Glob<'static>, BuildError> {
+    ///     let expression = String::from("**/*.txt");
+    ///     Glob::new(&expression).map(Glob::into_owned)
+    /// }
+    /// ```
+    pub fn into_owned(self) -> Glob<'static> {
+        let Glob {
+            tree,
+            pattern,
+            literal,
+            recased,
+        } = self;
+        Glob {
+            tree: tree.into_owned(),
+            pattern,
+            literal,
+            recased,
+        }
+    }
+
+    /// Borrows the text of literals from an [`Interner`].
+    ///
+    /// Literals that have not been inserted into the [`Interner`] are
+    /// unchanged. See [`Interner`].
+    ///
+    /// [`Interner`]: crate::Interner
+    pub fn intern<'i>(self, interner: &'i Interner) -> Glob<'i>
+    where
+        't: 'i,
+    {
+        let Glob {
+            tree,
+            pattern,
+            literal,
+            recased,
+        } = self;
+        Glob {
+            tree: tree.intern(interner),
+            pattern,
+            literal,
+            recased,
+        }
+    }
+
+    /// Joins the [`Glob`] with another [`Glob`] delimited by a separator.
+    ///
+    /// The output [`Glob`] matches paths with leading components that match
+    /// the [`Glob`] and trailing components that match the other [`Glob`].
+    /// Flags in the [`Glob`] do not apply to the other [`Glob`]. The joined
+    /// glob expression is rebuilt and checked, so joining cannot construct a
+    /// [`Glob`] that could not otherwise be built. An empty [`Glob`] is joined
+    /// as if it were absent. If either [`Glob`] uses [`CaseFolding::Turkic`],
+    /// then the output [`Glob`] uses it for all case-insensitive literals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the joined glob expression fails to build. Notably,
+    /// the other [`Glob`] must not be rooted and the [`Glob`]s must not
+    /// terminate and begin with adjacent component boundaries, such as in
+    /// `a/**` and `**/b`.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::{Glob, Pattern};
+    ///
+    /// let base = Glob::new("(?i)src").unwrap();
+    /// let glob = base.join(&Glob::new("**/*.rs").unwrap()).unwrap();
+    /// assert!(glob.is_match("SRC/lib.rs"));
+    ///
+    /// assert!(base.join(&Glob::new("/**/*.rs").unwrap()).is_err());
+    /// ```
+    ///
+    /// [`CaseFolding::Turkic`]: crate::CaseFolding::Turkic
+    /// [`Glob`]: crate::Glob
+    pub fn join(&self, other: &Glob<'_>) -> Result<Glob<'static>, BuildError> {
+        let left = self.tree.as_ref().expression();
+        let right = other.tree.as_ref().expression();
+        let folding = match self.tree.as_ref().folding() {
+            CaseFolding::Simple => other.tree.as_ref().folding(),
+            folding => folding,
+        };
+        let build = |expression: &str| {
+            let tree = parse_and_check_with_folding(expression, folding)?;
+            Ok(Glob::from_tree(tree)?.into_owned())
+        };
+        if left.is_empty() || right.is_empty() {
+            let expression = if left.is_empty() { right } else { left };
+            return build(expression);
+        }
+        // Flags persist until they are toggled, so scope the expression if it
+        // may contain flags. Closing the scope restores all default flags
+        // before the other expression. The scope toggles grapheme clusters
+        // off, which is also the default. Parentheses are always escaped
+        // elsewhere.
+        let is_scoped = left.contains('(');
+        let mut expression = String::with_capacity(left.len() + right.len() + 7);
+        if is_scoped {
+            expression.push_str("(?-g:");
+        }
+        expression.push_str(left);
+        if !matches!(
+            self.tree.as_ref().tokens().last().map(Token::kind),
+            Some(TokenKind::Separator(_)),
+        ) {
+            expression.push('/');
+        }
+        if is_scoped {
+            expression.push(')');
+        }
+        expression.push_str(right);
+        build(&expression)
+    }
+
+    /// Joins a literal directory and the [`Glob`].
+    ///
+    /// The directory is escaped as with [`Glob::literal`] and is joined with
+    /// the [`Glob`] as with [`Glob::join`], so the output [`Glob`] matches
+    /// paths beneath the directory and the directory is included in its
+    /// [invariant prefix][`Glob::partition`]. The output [`Glob`] is rooted if
+    /// the directory is an absolute path.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Glob`] is already [rooted][`Glob::has_root`]
+    /// or if the joined glob expression fails to build.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::path::Path;
+    /// use wax::{Glob, Pattern};
+    ///
+    /// let glob = Glob::new("**/*.md").unwrap().into_rooted("/srv/docs[v1]").unwrap();
+    /// assert!(glob.has_root());
+    /// assert!(glob.is_match("/srv/docs[v1]/guide/intro.md"));
+    ///
+    /// let (prefix, _) = glob.partition();
+    /// assert_eq!(prefix, Path::new("/srv/docs[v1]"));
+    /// ```
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::has_root`]: crate::Glob::has_root
+    /// [`Glob::join`]: crate::Glob::join
+    /// [`Glob::literal`]: crate::Glob::literal
+    /// [`Glob::partition`]: crate::Glob::partition
+    pub fn into_rooted(self, directory: impl AsRef<Path>) -> Result<Glob<'static>, BuildError> {
+        Glob::literal(directory)?.join(&self)
+    }
+
+    /// Composes the [`Glob`] with another [`Glob`] into a [`Filter`] that
+    /// matches paths that match either [`Glob`].
+    ///
+    /// The [`Filter`] can be further composed via [`Filter::or`] and
+    /// [`Filter::and_not`] and can be matched against paths and directory
+    /// trees.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let filter = Glob::new("**/*.rs")
+    ///     .unwrap()
+    ///     .or(Glob::new("**/*.toml").unwrap())
+    ///     .unwrap()
+    ///     .and_not(Glob::new("target/**").unwrap())
+    ///     .unwrap();
+    /// assert!(filter.is_match("src/lib.rs"));
+    /// assert!(filter.is_match("Cargo.toml"));
+    /// assert!(!filter.is_match("target/debug/build.rs"));
+    /// ```
+    ///
+    /// [`Filter`]: crate::Filter
+    /// [`Filter::and_not`]: crate::Filter::and_not
+    /// [`Filter::or`]: crate::Filter::or
+    /// [`Glob`]: crate::Glob
+    pub fn or(self, other: Glob<'t>) -> Result<Filter<'t>, BuildError> {
+        Filter::new([self, other], None)
+    }
+
+    /// Composes the [`Glob`] with an excluded [`Glob`] into a [`Filter`] that
+    /// matches paths that match the [`Glob`] but **not** the excluded
+    /// [`Glob`].
+    ///
+    /// See [`Glob::or`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// [`Filter`]: crate::Filter
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::or`]: crate::Glob::or
+    pub fn and_not(self, exclude: Glob<'t>) -> Result<Filter<'t>, BuildError> {
+        Filter::new([self], [exclude])
+    }
+
+    /// Anchors the [`Glob`] at a directory.
+    ///
+    /// The [`Anchored`] glob interprets relative paths against the directory
+    /// and never matches paths that are not beneath it, even if those paths
+    /// escape the directory via `..` components. This is useful for confining
+    /// matches and traversals to a particular directory tree, such as the
+    /// files of a particular user.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("**/*.txt").unwrap().anchored_at("/srv/tenant");
+    /// assert!(glob.is_match("notes/todo.txt"));
+    /// assert!(!glob.is_match("../other/notes/todo.txt"));
+    /// ```
+    ///
+    /// [`Anchored`]: crate::Anchored
+    /// [`Glob`]: crate::Glob
+    pub fn anchored_at(self, directory: impl AsRef<Path>) -> Anchored<'t> {
+        Anchored::new(self, directory)
+    }
+
+    /// Unanchors the [`Glob`] so that it matches any suffix of a path.
+    ///
+    /// The [`Unanchored`] glob matches paths in which any trailing components
+    /// match the [`Glob`], like patterns in ignore files that match at any
+    /// level of a directory tree. Unlike prepending `**/` to the glob
+    /// expression, leading components are not captured and so the indices of
+    /// [`MatchedText`] are unchanged. Rooted [`Glob`]s are not unanchored.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the unanchored program cannot be compiled. See
+    /// [`BuildError`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("target/**").unwrap().unanchored().unwrap();
+    /// assert!(glob.is_match("target/debug/wax"));
+    /// assert!(glob.is_match("crates/wax/target/debug/wax"));
+    /// ```
+    ///
+    /// [`BuildError`]: crate::BuildError
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    /// [`Unanchored`]: crate::Unanchored
+    pub fn unanchored(self) -> Result<Unanchored<'t>, BuildError> {
+        Ok(Unanchored::new(self)?)
+    }
+
+    /// Gets an iterator over matching files in a directory tree.
+    ///
+    /// This function matches a [`Glob`] against a directory tree, returning
+    /// each matching file as a [`WalkEntry`]. [`Glob`]s are the only patterns
+    /// that support this semantic operation; it is not possible to match
+    /// combinators over directory trees.
+    ///
+    /// As with [`Path::join`] and [`PathBuf::push`], the base directory can be
+    /// escaped or overridden by rooted [`Glob`]s. In many cases, the current
+    /// working directory `.` is an appropriate base directory and will be
+    /// intuitively ignored if the [`Glob`] is rooted, such as in
+    /// `/mnt/media/**/*.mp4`. The [`has_root`] function can be used to check if
     /// a [`Glob`] is rooted and the [`Walk::root`] function can be used to get
     /// the resulting root directory of the traversal.
     ///
@@ -912,7 +2208,51 @@ impl<'t> Glob<'t> {
         directory: impl AsRef<Path>,
         behavior: impl Into<WalkBehavior>,
     ) -> Walk {
-        walk::walk(self, directory, behavior)
+        walk::walk(self, StdFilesystem, directory, behavior)
+    }
+
+    /// Gets an iterator over matching files in a directory tree of a
+    /// [`Filesystem`].
+    ///
+    /// This function is the same as [`Glob::walk_with_behavior`], but it
+    /// additionally accepts a [`Filesystem`] from which the directory tree is
+    /// read rather than the native file system. This can be used to match a
+    /// [`Glob`] against in-memory trees, remote listings, test fixtures, etc.
+    ///
+    /// See [`Glob::walk`] for more information.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::{Glob, StdFilesystem, WalkBehavior};
+    ///
+    /// let glob = Glob::new("**/*.(?i){jpg,jpeg}").unwrap();
+    /// for entry in glob.walk_with_filesystem(
+    ///     StdFilesystem,
+    ///     "./Pictures",
+    ///     WalkBehavior::default(),
+    /// ) {
+    ///     let entry = entry.unwrap();
+    ///     println!("JPEG: {:?}", entry.path());
+    /// }
+    /// ```
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::walk`]: crate::Glob::walk
+    /// [`Glob::walk_with_behavior`]: crate::Glob::walk_with_behavior
+    #[cfg(feature = "walk")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+    pub fn walk_with_filesystem<S>(
+        &self,
+        filesystem: S,
+        directory: impl AsRef<Path>,
+        behavior: impl Into<WalkBehavior>,
+    ) -> Walk<'_, S>
+    where
+        S: Filesystem,
+    {
+        walk::walk(self, filesystem, directory, behavior)
     }
 
     /// Gets **non-error** [`Diagnostic`]s.
@@ -968,6 +2308,43 @@ impl<'t> Glob<'t> {
             .map_or(false, Token::has_root)
     }
 
+    /// Gets the breadth of the glob.
+    ///
+    /// The breadth of a glob is [open][`Boundedness::Open`] if it can match a
+    /// single component of unbounded length and
+    /// [closed][`Boundedness::Closed`] otherwise. For example, the breadth of
+    /// `*/fixed.txt` and `<a>` is open, but the breadth of `literal/fixed.txt`
+    /// and `?/<a:0,8>` is closed. Tree wildcards `**` and flagged zero-or-more
+    /// wildcards that cross separators match components of any length.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// assert!(Glob::new("*/fixed.txt").unwrap().breadth().is_open());
+    /// assert!(Glob::new("literal/fixed.txt").unwrap().breadth().is_closed());
+    /// ```
+    ///
+    /// [`Boundedness::Closed`]: crate::Boundedness::Closed
+    /// [`Boundedness::Open`]: crate::Boundedness::Open
+    pub fn breadth(&self) -> Boundedness {
+        self.tree.as_ref().breadth()
+    }
+
+    /// Gets the depth of the glob.
+    ///
+    /// The depth of a glob is [open][`Boundedness::Open`] if it can match an
+    /// unbounded number of components and [closed][`Boundedness::Closed`]
+    /// otherwise. For example, the depth of `**/*.rs` and `<*/>*.rs` is open,
+    /// but the depth of `src/*/*.rs` is closed.
+    ///
+    /// [`Boundedness::Closed`]: crate::Boundedness::Closed
+    /// [`Boundedness::Open`]: crate::Boundedness::Open
+    pub fn depth(&self) -> Boundedness {
+        self.tree.as_ref().depth()
+    }
+
     /// Returns `true` if the glob has literals that have non-nominal semantics
     /// on the target platform.
     ///
@@ -984,6 +2361,74 @@ impl<'t> Glob<'t> {
         token::literals(self.tree.as_ref().tokens())
             .any(|(_, literal)| literal.is_semantic_literal())
     }
+
+    /// Checks that the glob matches the same paths on Unix and Windows.
+    ///
+    /// The glob expression is compiled with the separators and default case
+    /// sensitivity of each platform and the programs are compared by matching
+    /// paths derived from examples of the glob. Paths with names that contain
+    /// backslashes are not considered, as these names are not valid on
+    /// Windows. Flags in a glob expression take precedence over the defaults
+    /// of each platform, so globs that explicitly set the case sensitivity of
+    /// their literals are portable.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if paths may be matched differently on Unix and
+    /// Windows. The error includes a [witness][`Divergence::witness`] path that
+    /// is matched on only one platform if such a path is found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("docs/README.md").unwrap();
+    /// let divergence = glob.check_portability().unwrap_err();
+    /// assert_eq!(divergence.witness(), Some("docs/readme.md"));
+    ///
+    /// assert!(Glob::new("(?i)docs/README.md").unwrap().check_portability().is_ok());
+    /// ```
+    ///
+    /// [`Divergence::witness`]: crate::Divergence::witness
+    pub fn check_portability(&self) -> Result<(), Divergence> {
+        portability::check(self.tree.as_ref())
+    }
+
+    /// Estimates the cost of compiling and matching the glob.
+    ///
+    /// The cost is roughly proportional to the size of the compiled program of
+    /// the glob. It is dominated by bounded repetitions, which are expanded
+    /// when compiled, such that nested repetitions and repetitions of large
+    /// alternatives may be legal but pathologically expensive. For example,
+    /// `<{a,b,c,d}<x:0,100>:0,100>` has a cost of more than ten thousand.
+    ///
+    /// The estimate is computed from the token tree and can be used to reject
+    /// glob expressions from untrusted sources. When the `miette` feature is
+    /// enabled, glob expressions with a large estimated cost are diagnosed
+    /// with a warning.
+    pub fn estimated_cost(&self) -> usize {
+        token::cost(self.tree.as_ref().tokens())
+    }
+
+    /// Estimates the number of bytes allocated on the heap by the glob.
+    ///
+    /// This includes the token tree and compiled program of the glob, but not
+    /// the size of the `Glob` itself. The size of the token tree is exact, but
+    /// the size of the compiled program is a rough heuristic derived from the
+    /// length of its pattern and may differ greatly from the memory actually
+    /// used. It does not include caches that grow while matching. Text
+    /// borrowed from a glob expression or an [`Interner`] is not included.
+    ///
+    /// This can be used to meter and limit the memory used by many globs, such
+    /// as globs that are read from untrusted sources.
+    ///
+    /// [`Interner`]: crate::Interner
+    pub fn heap_usage(&self) -> usize {
+        self.tree.as_ref().heap_usage()
+            + encode::program_heap_usage(&self.pattern)
+            + self.literal.as_ref().map_or(0, String::capacity)
+    }
 }
 
 impl Display for Glob<'_> {
@@ -1000,14 +2445,62 @@ impl FromStr for Glob<'static> {
     }
 }
 
+/// Deserializes a [`Glob`] from a glob expression.
+///
+/// Deserialization fails if the glob expression fails to build. See
+/// [`BuildError`].
+///
+/// [`BuildError`]: crate::BuildError
+/// [`Glob`]: crate::Glob
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl<'de> Deserialize<'de> for Glob<'static> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let expression = Cow::<'de, str>::deserialize(deserializer)?;
+        Glob::new(expression.as_ref())
+            .map(Glob::into_owned)
+            .map_err(de::Error::custom)
+    }
+}
+
+/// Serializes a [`Glob`] as its glob expression.
+///
+/// [`Glob`]: crate::Glob
+#[cfg(feature = "serde")]
+#[cfg_attr(docsrs, doc(cfg(feature = "serde")))]
+impl Serialize for Glob<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.tree.as_ref().expression())
+    }
+}
+
 impl<'t> Pattern<'t> for Glob<'t> {
     fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
         let path = path.into();
-        self.pattern.is_match(path.as_ref())
+        if let Some(pattern) = self.recased(&path) {
+            return pattern.is_match(path.as_ref());
+        }
+        match self.literal {
+            Some(ref literal) => Glob::is_literal_match(literal, path.as_ref()),
+            None => self.pattern.is_match(path.as_ref()),
+        }
     }
 
     fn matched<'p>(&self, path: &'p CandidatePath<'_>) -> Option<MatchedText<'p>> {
-        self.pattern.captures(path.as_ref()).map(From::from)
+        if let Some(pattern) = self.recased(path) {
+            return pattern.captures(path.as_ref()).map(From::from);
+        }
+        match self.literal {
+            Some(ref literal) => Glob::is_literal_match(literal, path.as_ref())
+                .then(|| MatchedText::from_complete(path.as_ref())),
+            None => self.pattern.captures(path.as_ref()).map(From::from),
+        }
     }
 
     fn variance(&self) -> Variance {
@@ -1037,17 +2530,206 @@ impl<'t> Compose<'t> for Glob<'t> {
 /// An instance of `Any` is constructed using the [`any`] function, which
 /// composes multiple [`Pattern`]s for more ergonomic and efficient matching.
 ///
+/// Component patterns are grouped by the literal text that begins them, such
+/// as `src/` in `src/**/*.rs`, and candidate paths are only matched against the
+/// patterns with a literal prefix that begins the path. This scales well to
+/// many patterns, especially patterns rooted in distinct directories.
+///
+/// Nested combinators are flattened, so the component patterns of an `Any`
+/// are always [`Glob`]s or glob expressions. These can be queried via
+/// [`Any::expressions`], [`Any::position`], and [`Any::positions`].
+///
 /// [`any`]: crate::any
+/// [`Any::expressions`]: crate::Any::expressions
+/// [`Any::position`]: crate::Any::position
+/// [`Any::positions`]: crate::Any::positions
+/// [`Glob`]: crate::Glob
 /// [`Pattern`]: crate::Pattern
 #[derive(Clone, Debug)]
 pub struct Any<'t> {
-    tree: Checked<Token<'t, ()>>,
-    pattern: Regex,
+    tree: Checked<Composition<'t>>,
+    pattern: PrefixDispatch,
+    members: LazyProgramSet,
 }
 
-impl<'t> Any<'t> {
-    fn compile(token: &Token<'t, ()>) -> Result<Regex, CompileError> {
-        encode::compile([token])
+impl<'t> Any<'t> {
+    fn compile(tree: &Composition<'t>) -> Result<PrefixDispatch, CompileError> {
+        PrefixDispatch::compile_with_exclusions(tree.token(), tree.exclusions())
+    }
+
+    /// Constructs an `Any` that owns its glob expressions.
+    ///
+    /// See [`any_owned`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the glob expressions fail to build.
+    ///
+    /// [`any_owned`]: crate::any_owned
+    pub fn from_strings<I>(expressions: I) -> Result<Any<'static>, BuildError>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+    {
+        any_owned(expressions)
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Any<'static> {
+        let Any {
+            tree,
+            pattern,
+            members,
+        } = self;
+        Any {
+            tree: tree.into_owned(),
+            pattern,
+            members,
+        }
+    }
+
+    /// Gets the glob expressions of the component patterns of the combinator.
+    ///
+    /// Nested combinators are flattened, so the expressions are those of the
+    /// [`Glob`]s and `str` slices given to [`any`] and any nested [`Any`]s in
+    /// order. The expressions of composed [`Filter`]s are those of their
+    /// included [`Glob`]s; excluded [`Glob`]s are not exposed.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let any = wax::any([wax::any(["**/*.rs", "**/*.toml"]).unwrap()]).unwrap();
+    /// let any = wax::any([any]).unwrap();
+    /// assert_eq!(
+    ///     any.expressions().collect::<Vec<_>>(),
+    ///     ["**/*.rs", "**/*.toml"],
+    /// );
+    /// ```
+    ///
+    /// [`any`]: crate::any
+    /// [`Any`]: crate::Any
+    /// [`Filter`]: crate::Filter
+    /// [`Glob`]: crate::Glob
+    pub fn expressions(&self) -> impl '_ + Clone + ExactSizeIterator<Item = &str> {
+        self.tree
+            .as_ref()
+            .members()
+            .iter()
+            .map(|tree| tree.expression().as_ref())
+    }
+
+    /// Gets [`Glob`]s of the component patterns of the combinator.
+    ///
+    /// The [`Glob`]s are compiled from the token trees of the component
+    /// patterns, which are cloned from the combinator. The [`Glob`]s
+    /// correspond to the [expressions][`Any::expressions`] of the combinator.
+    ///
+    /// [`Any::expressions`]: crate::Any::expressions
+    /// [`Glob`]: crate::Glob
+    pub fn globs(&self) -> impl '_ + Iterator<Item = Glob<'t>> {
+        self.tree.members().map(|tree| {
+            // The program of a component pattern is a branch of the program
+            // of the combinator.
+            Glob::from_tree(tree).expect("failed to compile component glob")
+        })
+    }
+
+    /// Gets the index of the first component pattern that matches a path.
+    ///
+    /// Returns `None` if the path does not match the combinator. The index
+    /// corresponds to the [expressions][`Any::expressions`] of the combinator.
+    /// This can be used to determine which pattern is responsible for a
+    /// match, such as when debugging rules.
+    ///
+    /// The programs of the individual component patterns are compiled when
+    /// this function is first called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let any = wax::any(["**/*.md", "src/**", "**/*.rs"]).unwrap();
+    /// assert_eq!(any.position("src/lib.rs"), Some(1));
+    /// assert_eq!(any.position("tests/walk.rs"), Some(2));
+    /// assert_eq!(any.position("Cargo.toml"), None);
+    /// ```
+    ///
+    /// [`Any::expressions`]: crate::Any::expressions
+    pub fn position<'p>(&self, path: impl Into<CandidatePath<'p>>) -> Option<usize> {
+        let path = path.into();
+        if !self.pattern.is_match(path.as_ref()) {
+            return None;
+        }
+        self.members()
+            .matches(path.as_ref())
+            .into_iter()
+            .find(|&index| !self.pattern.is_excluded_by(index, path.as_ref()))
+    }
+
+    /// Gets the indices of all component patterns that match a path.
+    ///
+    /// Indices are in increasing order and correspond to the
+    /// [expressions][`Any::expressions`] of the combinator. The component
+    /// patterns are matched together in a single pass over the path rather
+    /// than one after another, so this is far less expensive than matching
+    /// each [`Glob`] when there are many component patterns, such as long
+    /// lists of excluded patterns.
+    ///
+    /// As with [`Any::position`], the programs of the individual component
+    /// patterns are compiled when this function is first called.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let any = wax::any(["**/*.md", "src/**", "**/*.rs"]).unwrap();
+    /// assert_eq!(any.positions("src/lib.rs"), [1, 2]);
+    /// assert_eq!(any.positions("README.md"), [0]);
+    /// assert!(any.positions("Cargo.toml").is_empty());
+    /// ```
+    ///
+    /// [`Any::expressions`]: crate::Any::expressions
+    /// [`Any::position`]: crate::Any::position
+    /// [`Glob`]: crate::Glob
+    pub fn positions<'p>(&self, path: impl Into<CandidatePath<'p>>) -> Vec<usize> {
+        let path = path.into();
+        if !self.pattern.is_match(path.as_ref()) {
+            return vec![];
+        }
+        self.members()
+            .matches(path.as_ref())
+            .into_iter()
+            .filter(|&index| !self.pattern.is_excluded_by(index, path.as_ref()))
+            .collect()
+    }
+
+    // Gets the set of the programs of the component patterns, compiling it if
+    // necessary.
+    fn members(&self) -> Arc<RegexSet> {
+        self.members.get_or_compile(|| {
+            let branches = match self.tree.as_ref().token().kind() {
+                TokenKind::Alternative(ref alternative) => alternative.branches(),
+                _ => unreachable!("composition is not an alternative"),
+            };
+            encode::compile_set(
+                branches
+                    .iter()
+                    .map(encode::compile)
+                    .collect::<Result<Vec<_>, _>>()
+                    // Component programs are smaller than the compiled
+                    // programs of the combinator.
+                    .expect("failed to compile component glob")
+                    .iter(),
+            )
+            .expect("failed to compile component globs")
+        })
+    }
+
+    /// Estimates the number of bytes allocated on the heap by the combinator.
+    ///
+    /// See [`Glob::heap_usage`].
+    ///
+    /// [`Glob::heap_usage`]: crate::Glob::heap_usage
+    pub fn heap_usage(&self) -> usize {
+        self.tree.as_ref().heap_usage() + self.pattern.heap_usage()
     }
 }
 
@@ -1062,19 +2744,68 @@ impl<'t> Pattern<'t> for Any<'t> {
     }
 
     fn variance(&self) -> Variance {
-        self.tree.as_ref().variance::<InvariantText>().into()
+        self.tree
+            .as_ref()
+            .token()
+            .variance::<InvariantText>()
+            .into()
     }
 
     fn is_exhaustive(&self) -> bool {
-        token::is_exhaustive(Some(self.tree.as_ref()))
+        // Patterns with exclusions may not match some paths in a directory
+        // tree, so they are never exhaustive.
+        let tree = self.tree.as_ref();
+        !tree.has_exclusions() && token::is_exhaustive(Some(tree.token()))
     }
 }
 
 impl<'t> Compose<'t> for Any<'t> {
-    type Tokens = Token<'t, ()>;
+    type Tokens = Composition<'t>;
     type Error = Infallible;
 }
 
+// Implements `Compose` and `Pattern` for a pointer type that dereferences to a
+// `Pattern`, such that shared and borrowed patterns can be used directly in
+// APIs that accept patterns, like `any` and `FileIterator::not`.
+macro_rules! impl_pattern_for_pointer {
+    (<$($l:lifetime),*> $pointer:ty $(where $($bound:tt)+)?) => {
+        impl<$($l,)* 't, P> Compose<'t> for $pointer
+        where
+            P: AsChecked<Tokens = <P as Compose<'t>>::Tokens> + Pattern<'t>,
+            $($($bound)+)?
+        {
+            type Tokens = <P as Compose<'t>>::Tokens;
+            type Error = Infallible;
+        }
+
+        impl<$($l,)* 't, P> Pattern<'t> for $pointer
+        where
+            P: AsChecked<Tokens = <P as Compose<'t>>::Tokens> + Pattern<'t>,
+            $($($bound)+)?
+        {
+            fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
+                P::is_match(self, path)
+            }
+
+            fn matched<'p>(&self, path: &'p CandidatePath<'_>) -> Option<MatchedText<'p>> {
+                P::matched(self, path)
+            }
+
+            fn variance(&self) -> Variance {
+                P::variance(self)
+            }
+
+            fn is_exhaustive(&self) -> bool {
+                P::is_exhaustive(self)
+            }
+        }
+    };
+}
+impl_pattern_for_pointer!(<'r> &'r P);
+impl_pattern_for_pointer!(<> Arc<P>);
+impl_pattern_for_pointer!(<> Rc<P>);
+impl_pattern_for_pointer!(<'c> Cow<'c, P> where P: Clone);
+
 // TODO: It may be useful to use dynamic dispatch via trait objects instead.
 //       This would allow for a variety of types to be composed in an `any` call
 //       and would be especially useful if additional combinators are
@@ -1145,7 +2876,157 @@ where
             .map_err(Into::into)?,
     );
     let pattern = Any::compile(tree.as_ref())?;
-    Ok(Any { tree, pattern })
+    Ok(Any {
+        tree,
+        pattern,
+        members: LazyProgramSet::default(),
+    })
+}
+
+/// Composes glob expressions into an [`Any`] that owns its glob expressions.
+///
+/// This function is like [`any`], but accepts glob expressions of any string
+/// type and constructs an [`Any`] that does not borrow them. This is useful
+/// when glob expressions are read at runtime, such as from configuration
+/// files.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Any, Pattern};
+///
+/// fn read_patterns() -> Vec<String> {
+///     vec!["**/*.rs".to_owned(), "**/*.toml".to_owned()]
+/// }
+///
+/// let any: Any<'static> = wax::any_owned(read_patterns()).unwrap();
+/// assert!(any.is_match("Cargo.toml"));
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if any of the glob expressions fail to build.
+///
+/// [`any`]: crate::any
+/// [`Any`]: crate::Any
+pub fn any_owned<I>(expressions: I) -> Result<Any<'static>, BuildError>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let globs = expressions
+        .into_iter()
+        .map(|expression| Glob::new(expression.as_ref()).map(Glob::into_owned))
+        .collect::<Result<Vec<_>, _>>()?;
+    any(globs)
+}
+
+/// Checks a glob expression against the rules of [`Glob::new`] without
+/// compiling it.
+///
+/// Glob expressions are parsed and their token trees are checked against the
+/// same rules as [`Glob::new`], such as rules for adjacent tree wildcards and
+/// repetition bounds. This can be used to validate the output of code that
+/// constructs glob expressions, such as translators from other glob syntaxes
+/// (e.g., `.gitignore` files) or code that rewrites glob expressions, without
+/// the cost of compiling a [`Glob`]. Text from untrusted sources can be
+/// embedded in such expressions with [`escape`].
+///
+/// # Examples
+///
+/// ```rust
+/// // Translate a `.gitignore` pattern that matches files at any depth.
+/// let expression = format!("**/{}", wax::escape("build(1).log"));
+/// assert!(wax::check(&expression).is_ok());
+///
+/// assert!(wax::check("**/**").is_err());
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the glob expression fails to parse or violates a rule.
+/// Unlike [`Glob::new`], this function never returns an error because a
+/// compiled program is too large.
+///
+/// [`escape`]: crate::escape
+/// [`Glob`]: crate::Glob
+/// [`Glob::new`]: crate::Glob::new
+pub fn check(expression: &str) -> Result<(), BuildError> {
+    parse_and_check(expression).map(|_| ())
+}
+
+/// Matches a glob expression against a directory tree in the working
+/// directory.
+///
+/// This function builds a [`Glob`] from the given expression,
+/// [partitions][`Glob::partition`] it, and walks the directory tree beneath its
+/// invariant prefix. Paths of [`WalkEntry`]s begin with this prefix, so they
+/// resemble the glob expression. If there is no invariant prefix, then paths
+/// begin with the working directory `.`.
+///
+/// To walk a directory tree beneath another directory, use [`walk_in`]. To
+/// configure the traversal, build a [`Glob`] and use functions like
+/// [`Glob::walk_with_behavior`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// for entry in wax::walk("src/**/*.rs").unwrap() {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the glob expression fails to build. Errors that occur
+/// while reading the directory tree are yielded by the iterator.
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::partition`]: crate::Glob::partition
+/// [`Glob::walk_with_behavior`]: crate::Glob::walk_with_behavior
+/// [`walk_in`]: crate::walk_in
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg(feature = "walk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub fn walk(expression: &str) -> Result<Walk<'static>, BuildError> {
+    walk_in("", expression)
+}
+
+/// Matches a glob expression against a directory tree in the given directory.
+///
+/// This function is like [`walk`], but joins the given directory with the
+/// invariant prefix of the glob expression. Paths of [`WalkEntry`]s begin with
+/// the given directory.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// for entry in wax::walk_in("/var/log", "**/*.log").unwrap() {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the glob expression fails to build. Errors that occur
+/// while reading the directory tree are yielded by the iterator.
+///
+/// [`walk`]: crate::walk()
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg(feature = "walk")]
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub fn walk_in(directory: impl AsRef<Path>, expression: &str) -> Result<Walk<'static>, BuildError> {
+    let (prefix, glob) = Glob::new(expression)?.partition();
+    let root = directory.as_ref().join(prefix);
+    let root = if root.as_os_str().is_empty() {
+        PathBuf::from(".")
+    }
+    else {
+        root
+    };
+    Ok(glob.walk(root).into_owned())
 }
 
 /// Escapes text as a literal glob expression.
@@ -1204,6 +3085,76 @@ pub fn escape(unescaped: &str) -> Cow<str> {
     }
 }
 
+/// Escapes native text as a literal glob expression.
+///
+/// This function is like [`escape`], but accepts native text. Text that is not
+/// valid UTF-8 is converted lossily in the same way as [`CandidatePath`] and
+/// native separators are replaced by `/`, so that the expression matches the
+/// text on the target platform.
+///
+/// [`CandidatePath`]: crate::CandidatePath
+/// [`escape`]: crate::escape
+#[must_use]
+pub fn escape_os_str(unescaped: &OsStr) -> Cow<str> {
+    let unescaped = unescaped.to_string_lossy();
+    if unescaped.chars().any(path::is_separator) {
+        unescaped
+            .split(path::is_separator)
+            .map(escape)
+            .collect::<Vec<_>>()
+            .join("/")
+            .into()
+    }
+    else {
+        match unescaped {
+            Cow::Borrowed(text) => escape(text),
+            Cow::Owned(text) => escape(&text).into_owned().into(),
+        }
+    }
+}
+
+/// Escapes a native path as a literal glob expression.
+///
+/// The components of the path are escaped and delimited by `/`. As with
+/// [`Path::components`], repeated separators and `.` components that do not
+/// begin the path are ignored. A root is preserved, but prefixes, such as `C:`
+/// on Windows, are not supported by globs and are escaped as literal text.
+///
+/// # Examples
+///
+/// ```rust
+/// use std::path::Path;
+///
+/// let expression = wax::escape_path(Path::new("/var/log/app[1]"));
+/// assert_eq!(expression, "/var/log/app\\[1\\]");
+/// ```
+///
+/// [`Path::components`]: std::path::Path::components
+#[must_use]
+pub fn escape_path(unescaped: &Path) -> Cow<str> {
+    use std::path::Component;
+
+    let mut expression = String::new();
+    for component in unescaped.components() {
+        match component {
+            Component::RootDir => {
+                expression.push('/');
+                continue;
+            },
+            Component::Prefix(prefix) => {
+                expression.push_str(&escape_os_str(prefix.as_os_str()));
+                continue;
+            },
+            _ => {},
+        }
+        if !(expression.is_empty() || expression.ends_with('/')) {
+            expression.push('/');
+        }
+        expression.push_str(&escape_os_str(component.as_os_str()));
+    }
+    expression.into()
+}
+
 // TODO: Is it possible for `:` and `,` to be contextual meta-characters?
 /// Returns `true` if the given character is a meta-character.
 ///
@@ -1238,6 +3189,15 @@ fn parse_and_check(expression: &str) -> Result<Checked<Tokenized>, BuildError> {
     Ok(checked)
 }
 
+fn parse_and_check_with_folding(
+    expression: &str,
+    folding: CaseFolding,
+) -> Result<Checked<Tokenized<'_>>, BuildError> {
+    let tokenized = token::parse_with_folding(expression, folding)?;
+    let checked = rule::check(tokenized)?;
+    Ok(checked)
+}
+
 #[cfg(feature = "miette")]
 fn parse_and_diagnose(expression: &str) -> DiagnosticResult<Checked<Tokenized>> {
     token::parse(expression)
@@ -1258,7 +3218,17 @@ fn parse_and_diagnose(expression: &str) -> DiagnosticResult<Checked<Tokenized>>
 mod tests {
     use std::path::Path;
 
-    use crate::{BuildError, BuildErrorKind, CandidatePath, Glob, Pattern};
+    use crate::{
+        Any, BuildError, BuildErrorKind, CandidatePath, CaseFolding, Glob, GlobBuilder,
+        LocatedError, Pattern, StrExt as _,
+    };
+
+    #[test]
+    fn swap_case() {
+        assert_eq!("Read.Me".swap_case(), "rEAD.mE");
+        assert_eq!("a_1".swap_case(), "A_1");
+        assert_eq!("_1".swap_case(), "_1");
+    }
 
     #[test]
     fn escape() {
@@ -1280,6 +3250,32 @@ mod tests {
         assert_eq!(crate::escape("*中*"), "\\*中\\*");
     }
 
+    #[test]
+    fn escape_path() {
+        assert_eq!(crate::escape_path(Path::new("")), "");
+        assert_eq!(crate::escape_path(Path::new("/")), "/");
+        assert_eq!(
+            crate::escape_path(Path::new("/usr//local/./lib[64]/")),
+            "/usr/local/lib\\[64\\]",
+        );
+        assert_eq!(
+            crate::escape_path(Path::new("./{a,b}/(c)")),
+            "./\\{a\\,b\\}/\\(c\\)",
+        );
+        assert_eq!(crate::escape_os_str("a/*.txt".as_ref()), "a/\\*.txt");
+    }
+
+    #[test]
+    fn build_glob_from_literal_path() {
+        let glob = Glob::literal("logs/[2022]/{a,b}(1).log").unwrap();
+        assert!(glob.is_match("logs/[2022]/{a,b}(1).log"));
+        assert!(!glob.is_match("logs/2/a(1).log"));
+
+        let glob = Glob::literal("/var//log/").unwrap();
+        assert!(glob.is_match(Path::new("/var/log")));
+        assert!(glob.has_root());
+    }
+
     #[test]
     fn build_glob_with_eager_zom_tokens() {
         Glob::new("*").unwrap();
@@ -1344,6 +3340,10 @@ mod tests {
         Glob::new("a/[!xy]").unwrap();
         Glob::new("a/[!x-z]").unwrap();
         Glob::new("a/[xy]b/c").unwrap();
+        Glob::new("a/[[=e=]]").unwrap();
+        Glob::new("a/[![=e=]x]").unwrap();
+        Glob::new("a/[[.-.]]").unwrap();
+        Glob::new("a/[[.a.]-[.z.]]").unwrap();
     }
 
     #[test]
@@ -1353,6 +3353,9 @@ mod tests {
         Glob::new("a/{???,x$y,frob}b*").unwrap();
         Glob::new("a/{???,{x*z,y$}}b*").unwrap();
         Glob::new("a{/**/b/,/b/**/}ca{t,b/**}").unwrap();
+        Glob::new("{foo,}").unwrap();
+        Glob::new("src{/generated,}/**").unwrap();
+        Glob::new("a/{,b/,c/}d").unwrap();
     }
 
     #[test]
@@ -1363,6 +3366,12 @@ mod tests {
         Glob::new("<a:>").unwrap();
         Glob::new("<a>").unwrap();
         Glob::new("<a<b:0,>:0,>").unwrap();
+        Glob::new("<a:?>").unwrap();
+        Glob::new("<a:0,?>").unwrap();
+        Glob::new("<a:1,3?>").unwrap();
+        Glob::new("a(b)?").unwrap();
+        Glob::new("a(b{c,d}(e)?)?/f").unwrap();
+        Glob::new("(?i:a(b)?)c").unwrap();
         // Rooted repetitions are accepted if the lower bound is one or greater.
         Glob::new("</root:1,>").unwrap();
         Glob::new("<[!.]*/:0,>[!.]*").unwrap();
@@ -1396,6 +3405,14 @@ mod tests {
         Glob::new("a/{x,y[,],z}/c").unwrap();
     }
 
+    #[test]
+    fn build_glob_with_escaped_code_points() {
+        Glob::new("a\\tb").unwrap();
+        Glob::new("a/\\u{1F600}.txt").unwrap();
+        Glob::new("{\\x{2C},b}").unwrap();
+        Glob::new("[\\n\\x{41}-\\x{5A}]").unwrap();
+    }
+
     #[test]
     fn build_glob_with_literal_escaped_class_tokens() {
         Glob::new("a/\\[a-z\\]/c").unwrap();
@@ -1411,6 +3428,89 @@ mod tests {
         Glob::new("a/(?-i)b/c").unwrap();
         Glob::new("a/b/(?-i)c").unwrap();
         Glob::new("(?i)a/(?-i)b/(?i)c").unwrap();
+        Glob::new("(?g)a/?/c").unwrap();
+        Glob::new("(?ig)a/(?-g)?/c").unwrap();
+        Glob::new("(?s)a*/c").unwrap();
+        Glob::new("a/(?s:*.txt)").unwrap();
+        Glob::new("a/(?i:b)/c").unwrap();
+        Glob::new("(?i:a/(?-i:b)/c)").unwrap();
+        Glob::new("a/(?i:**)/c").unwrap();
+        Glob::new("{(?i:a*),b}").unwrap();
+    }
+
+    #[test]
+    fn join_globs() {
+        fn join(left: &str, right: &str) -> Result<String, BuildError> {
+            Glob::new(left)
+                .unwrap()
+                .join(&Glob::new(right).unwrap())
+                .map(|glob| glob.to_string())
+        }
+
+        assert_eq!(join("a", "b/*").unwrap(), "a/b/*");
+        assert_eq!(join("a/**", "*.rs").unwrap(), "a/**/*.rs");
+        assert_eq!(join("/", "a").unwrap(), "/a");
+        assert_eq!(join("", "a").unwrap(), "a");
+        assert_eq!(join("a", "").unwrap(), "a");
+        assert!(join("a", "/b").is_err());
+        assert!(join("a/**", "**/b").is_err());
+        assert!(join("a*", "*b").is_ok());
+
+        let glob = Glob::new("(?i)a")
+            .unwrap()
+            .join(&Glob::new("(?-i)b/(?i)c").unwrap())
+            .unwrap();
+        assert!(glob.is_match("A/b/C"));
+        assert!(!glob.is_match("A/B/C"));
+
+        let glob = Glob::new("(?i)a")
+            .unwrap()
+            .join(&Glob::new("b").unwrap())
+            .unwrap();
+        assert!(glob.is_match(CandidatePath::unix("A/b")));
+        assert!(!glob.is_match(CandidatePath::unix("A/B")));
+
+        let glob = Glob::new("(?g)?")
+            .unwrap()
+            .join(&Glob::new("?").unwrap())
+            .unwrap();
+        assert!(glob.is_match("e\u{301}/e"));
+        assert!(!glob.is_match("e/e\u{301}"));
+
+        let glob = Glob::new("(?s)a*")
+            .unwrap()
+            .join(&Glob::new("b*.rs").unwrap())
+            .unwrap();
+        assert!(glob.is_match("ax/y/b.rs"));
+        assert!(!glob.is_match("a/bx/y.rs"));
+
+        // The case sensitivity of the other glob is not explicit.
+        let glob = Glob::new("(?-i)a")
+            .unwrap()
+            .join(&Glob::new("b").unwrap())
+            .unwrap();
+        assert_eq!(glob.to_string(), "(?-g:(?-i)a/)b");
+        assert!(glob.is_match(CandidatePath::windows(r"a\B")));
+        assert!(!glob.is_match(CandidatePath::windows(r"A\b")));
+    }
+
+    #[test]
+    fn root_glob() {
+        let glob = Glob::new("**/*.rs")
+            .unwrap()
+            .into_rooted("/src/{a}")
+            .unwrap();
+        assert!(glob.has_root());
+        assert!(glob.is_match("/src/{a}/lib.rs"));
+        assert!(!glob.is_match("/src/a/lib.rs"));
+        assert!(glob.variance().is_variant());
+        assert_eq!(glob.partition().0, Path::new("/src/{a}"));
+
+        let glob = Glob::new("*.rs").unwrap().into_rooted("src").unwrap();
+        assert!(!glob.has_root());
+        assert!(glob.is_match("src/lib.rs"));
+
+        assert!(Glob::new("/**").unwrap().into_rooted("/src").is_err());
     }
 
     #[test]
@@ -1433,6 +3533,71 @@ mod tests {
         .unwrap();
     }
 
+    #[test]
+    fn flatten_any_nested_combinator() {
+        let any = crate::any([
+            crate::any(["a/b", "c/d"]).unwrap(),
+            crate::any(["{e,f,g}", "{h,i}"]).unwrap(),
+        ])
+        .unwrap();
+        let any = crate::any([any, crate::any(["j/**"]).unwrap()]).unwrap();
+        assert_eq!(
+            any.expressions().collect::<Vec<_>>(),
+            ["a/b", "c/d", "{e,f,g}", "{h,i}", "j/**"],
+        );
+        assert_eq!(
+            any.globs().map(|glob| glob.to_string()).collect::<Vec<_>>(),
+            ["a/b", "c/d", "{e,f,g}", "{h,i}", "j/**"],
+        );
+        assert_eq!(any.position("c/d"), Some(1));
+        assert_eq!(any.position("i"), Some(3));
+        assert_eq!(any.position("j/k/l"), Some(4));
+        assert_eq!(any.position("k"), None);
+        assert_eq!(any.clone().into_owned().position("a/b"), Some(0));
+    }
+
+    #[test]
+    fn query_any_combinator_globs() {
+        let turkic = GlobBuilder::new()
+            .case_folding(CaseFolding::Turkic)
+            .build("(?i)dir")
+            .unwrap();
+        let (_, partitioned) = Glob::new("src/(?-i:lib/)*.rs").unwrap().partition();
+        let any = crate::any([turkic, partitioned]).unwrap();
+        let globs: Vec<_> = any.globs().collect();
+        assert_eq!(globs[0].to_string(), "(?i)dir");
+        assert!(globs[0].is_match("DİR"));
+        assert!(!globs[0].is_match("DIR"));
+        assert_eq!(globs[1].to_string(), "*.rs");
+        assert!(globs[1].is_match("main.rs"));
+    }
+
+    #[test]
+    fn query_any_combinator_positions() {
+        let any = crate::any(["**/*.rs", "src/**", "{src,tests}/*.rs", "**/*.md"]).unwrap();
+        assert_eq!(any.positions("src/lib.rs"), [0, 1, 2]);
+        assert_eq!(any.positions("tests/walk.rs"), [0, 2]);
+        assert_eq!(any.positions("src/README.md"), [1, 3]);
+        assert!(any.positions("Cargo.toml").is_empty());
+        assert_eq!(any.position("src/README.md"), Some(1));
+    }
+
+    #[test]
+    fn build_any_owned_combinator() {
+        fn any(expressions: Vec<String>) -> Any<'static> {
+            crate::any_owned(expressions).unwrap()
+        }
+
+        let any = any(vec!["src/**/*.rs".to_owned(), "doc/**/*.md".to_owned()]);
+        assert!(any.is_match("src/lib.rs"));
+        assert!(Any::from_strings(["a/{b"]).is_err());
+
+        let expression = String::from("pkg/**/PKGBUILD");
+        let any = crate::any([expression.as_str()]).unwrap().into_owned();
+        drop(expression);
+        assert!(any.is_match("pkg/arch/PKGBUILD"));
+    }
+
     #[test]
     fn reject_glob_with_invalid_separator_tokens() {
         assert!(Glob::new("//a").is_err());
@@ -1474,6 +3639,16 @@ mod tests {
         assert!(Glob::new("**/**?/**").is_err());
     }
 
+    #[test]
+    fn reject_glob_with_invalid_escaped_code_points() {
+        assert!(Glob::new("a\\x{}").is_err());
+        assert!(Glob::new("a\\x{110000}").is_err());
+        assert!(Glob::new("a\\u{D800}").is_err());
+        assert!(Glob::new("a\\x{2F}b").is_err());
+        assert!(Glob::new("a\\x{5C}b").is_err());
+        assert!(Glob::new("a\\q").is_err());
+    }
+
     #[test]
     fn reject_glob_with_unescaped_meta_characters_in_class_tokens() {
         assert!(Glob::new("a/[a-z-]/c").is_err());
@@ -1485,6 +3660,9 @@ mod tests {
         assert!(Glob::new("a/[---]/c").is_err());
         assert!(Glob::new("a/[[]/c").is_err());
         assert!(Glob::new("a/[]]/c").is_err());
+        assert!(Glob::new("a/[[=ae=]]/c").is_err());
+        assert!(Glob::new("a/[[.ch.]]/c").is_err());
+        assert!(Glob::new("a/[[=e]]/c").is_err());
     }
 
     #[test]
@@ -1525,9 +3703,31 @@ mod tests {
         assert!(Glob::new("{okay,/**/error}").is_err());
     }
 
+    #[test]
+    fn reject_glob_with_empty_alternative_tokens() {
+        assert!(Glob::new("{}").is_err());
+        assert!(Glob::new("{,}").is_err());
+        assert!(Glob::new("{okay,}/rooted").is_err());
+        assert!(Glob::new("slash/{okay,}/slash").is_err());
+        assert!(Glob::new("**/{okay,}/slash").is_err());
+    }
+
+    #[test]
+    fn reject_glob_with_invalid_optional_tokens() {
+        assert!(Glob::new("a(b)").is_err());
+        assert!(Glob::new("a()?").is_err());
+        assert!(Glob::new("a(?b)?").is_err());
+        assert!(Glob::new("a(b?").is_err());
+        assert!(Glob::new("(?i:a(b)?").is_err());
+        assert!(Glob::new("(/root)?").is_err());
+        assert!(Glob::new("a/(b/)?/c").is_err());
+    }
+
     #[test]
     fn reject_glob_with_invalid_repetition_bounds_tokens() {
         assert!(Glob::new("<a/:0,0>").is_err());
+        assert!(Glob::new("<a:??>").is_err());
+        assert!(Glob::new("<a:?1>").is_err());
     }
 
     #[test]
@@ -1592,11 +3792,57 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn reject_glob_with_parse_error_locations() {
+        let spans = |expression| {
+            Glob::new(expression)
+                .unwrap_err()
+                .locations()
+                .map(LocatedError::span)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(spans("a/{b,c/<d:1,x>}"), [(12, 1), (7, 1), (2, 1)]);
+        assert_eq!(spans("{a,(b/[c-]d)?}"), [(8, 1), (6, 1), (3, 1), (0, 1)]);
+        assert_eq!(spans("src/***"), [(4, 2)]);
+        assert_eq!(spans("{a,b"), [(4, 0), (0, 1)]);
+        assert_eq!(spans("(?i:a"), [(0, 2)]);
+        assert_eq!(spans("a(?x)b"), [(3, 1)]);
+        assert_eq!(spans("a\\qb"), [(1, 2)]);
+        assert_eq!(spans("<a:99999999999999999999999>"), [(3, 23), (0, 1)]);
+    }
+
+    #[test]
+    fn reject_glob_with_all_rule_violations() {
+        let spans = |expression| {
+            Glob::new(expression)
+                .unwrap_err()
+                .locations()
+                .map(LocatedError::span)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(spans("a//b/{c,**}/<d:2,1>"), [(1, 2), (12, 7), (5, 6)]);
+        assert_eq!(spans("a///b"), [(1, 3)]);
+        assert_eq!(spans("a//b//c"), [(1, 2), (4, 2)]);
+
+        let error = Glob::new("{**}/</:1,>").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "malformed glob expression: singular tree wildcard `**` in group (and 1 other \
+             violation)",
+        );
+    }
+
     #[test]
     fn reject_glob_with_invalid_flags() {
         assert!(Glob::new("(?)a").is_err());
         assert!(Glob::new("(?-)a").is_err());
         assert!(Glob::new("()a").is_err());
+        assert!(Glob::new("(?:a)").is_err());
+        assert!(Glob::new("(?i:a").is_err());
+        assert!(Glob::new("a)").is_err());
+        assert!(Glob::new("{(?i:a,b)}").is_err());
     }
 
     #[test]
@@ -1607,6 +3853,8 @@ mod tests {
         assert!(Glob::new("**(?i)?").is_err());
         assert!(Glob::new("a(?i)**").is_err());
         assert!(Glob::new("**(?i)a").is_err());
+        assert!(Glob::new("(?i:a*)*").is_err());
+        assert!(Glob::new("(?i:a/**)b").is_err());
     }
 
     #[test]
@@ -1626,31 +3874,187 @@ mod tests {
     }
 
     #[test]
-    fn match_glob_with_empty_expression() {
-        let glob = Glob::new("").unwrap();
+    fn match_glob_with_empty_expression() {
+        let glob = Glob::new("").unwrap();
+
+        assert!(glob.is_match(Path::new("")));
+
+        assert!(!glob.is_match(Path::new("abc")));
+    }
+
+    #[test]
+    fn match_glob_with_only_invariant_tokens() {
+        let glob = Glob::new("a/b").unwrap();
+
+        assert!(glob.is_match(Path::new("a/b")));
+
+        assert!(!glob.is_match(Path::new("aa/b")));
+        assert!(!glob.is_match(Path::new("a/bb")));
+        assert!(!glob.is_match(Path::new("a/b/c")));
+
+        // There are no variant tokens with which to capture, but the matched
+        // text should always be available.
+        assert_eq!(
+            "a/b",
+            glob.matched(&CandidatePath::from(Path::new("a/b")))
+                .unwrap()
+                .complete(),
+        );
+    }
+
+    #[test]
+    fn match_glob_with_literal_text() {
+        for (expression, is_literal) in [
+            ("", true),
+            ("a/b", true),
+            ("/a/b.txt", true),
+            ("(?i)1/2", true),
+            ("(?i)a/b", false),
+            ("a/*", false),
+        ] {
+            let glob = Glob::new(expression).unwrap();
+            assert_eq!(glob.literal.is_some(), is_literal);
+            for path in ["", "a", "a/b", "a/B", "/a/b.txt", "a/b/c", "1/2"] {
+                assert_eq!(
+                    glob.is_match(path),
+                    glob.pattern.is_match(path),
+                    "{:?} ~ {:?}",
+                    expression,
+                    path,
+                );
+            }
+        }
+
+        let glob = Glob::new("a/b").unwrap();
+        let candidate = CandidatePath::from("a/b");
+        let matched = glob.matched(&candidate).unwrap();
+        assert_eq!(matched.get(1), None);
+        assert_eq!(matched.into_owned().complete(), "a/b");
+    }
+
+    #[test]
+    fn match_glob_with_encoded_candidate() {
+        let glob = Glob::new("src/*.rs").unwrap();
+
+        assert!(glob.is_match(CandidatePath::from_bytes(b"src/lib.rs").unwrap()));
+        assert!(CandidatePath::from_bytes(b"src/\xFF.rs").is_err());
+        let candidate = CandidatePath::from_bytes_lossy(b"src/\xFF.rs");
+        assert_eq!(candidate.as_ref(), "src/\u{FFFD}.rs");
+        assert!(glob.is_match(candidate));
+
+        let mut wide: Vec<u16> = "src/lib.rs".encode_utf16().collect();
+        assert!(glob.is_match(CandidatePath::from_utf16(&wide).unwrap()));
+        // Insert an unpaired surrogate.
+        wide.insert(4, 0xD800);
+        assert!(CandidatePath::from_utf16(&wide).is_err());
+        let candidate = CandidatePath::from_utf16_lossy(&wide);
+        assert_eq!(candidate.as_ref(), "src/\u{FFFD}lib.rs");
+        assert!(glob.is_match(candidate));
+    }
+
+    #[test]
+    fn match_glob_with_path_kind() {
+        let glob = Glob::new("logs/*.txt").unwrap();
+        assert!(glob.is_match(CandidatePath::windows(r"Logs\Server.TXT")));
+        assert!(glob.is_match(CandidatePath::unix("logs/server.txt")));
+        assert!(!glob.is_match(CandidatePath::unix("Logs/server.txt")));
+        assert_eq!(
+            glob.matched(&CandidatePath::windows(r"LOGS\a.txt"))
+                .unwrap()
+                .get(1)
+                .unwrap(),
+            "a",
+        );
+
+        // Literal globs are also matched case-insensitively.
+        let glob = Glob::new("src/lib.rs").unwrap();
+        assert!(glob.is_match(CandidatePath::windows(r"SRC\Lib.rs")));
+        assert!(!glob.is_match(CandidatePath::unix("SRC/Lib.rs")));
+
+        // Flags take precedence over the kind of a path.
+        let glob = Glob::new("(?-i)logs/*.txt").unwrap();
+        assert!(!glob.is_match(CandidatePath::windows(r"Logs\server.txt")));
+        let glob = Glob::new("(?i)logs/*.txt").unwrap();
+        assert!(glob.is_match(CandidatePath::unix("Logs/server.TXT")));
+
+        // Flags also take precedence when they have no effect on the target
+        // platform and when the glob is partitioned.
+        let glob = Glob::new("(?-i)foo/bar*.TXT").unwrap();
+        assert!(!glob.is_match(CandidatePath::windows(r"foo\BARa.txt")));
+        let (_, glob) = glob.partition();
+        assert!(!glob.is_match(CandidatePath::windows("BARa.txt")));
+        assert!(glob.is_match(CandidatePath::windows("bara.TXT")));
+        let expression = glob.to_string();
+        assert_eq!(expression, "(?-i)bar*.TXT");
+        let glob = Glob::new(&expression).unwrap();
+        assert!(!glob.is_match(CandidatePath::windows("BARa.txt")));
+    }
+
+    #[test]
+    fn strip_verbatim_prefix_from_candidate() {
+        assert_eq!(
+            CandidatePath::windows(r"\\?\C:\src\lib.rs").as_ref(),
+            "C:/src/lib.rs",
+        );
+        assert_eq!(CandidatePath::windows(r"\\.\C:").as_ref(), "C:");
+        assert_eq!(
+            CandidatePath::windows(r"\\?\UNC\server\share\a.txt").as_ref(),
+            "//server/share/a.txt",
+        );
+        assert_eq!(
+            CandidatePath::windows(r"\\.\PIPE\name").as_ref(),
+            "//./PIPE/name",
+        );
+        assert_eq!(
+            CandidatePath::windows(r"\\?\Volume{0}\a").as_ref(),
+            "//?/Volume{0}/a",
+        );
 
-        assert!(glob.is_match(Path::new("")));
+        let glob = Glob::new("C\\:/src/*.rs").unwrap();
+        assert!(glob.is_match(CandidatePath::windows(r"\\?\C:\src\lib.rs")));
+        assert!(glob.is_match(CandidatePath::windows(r"C:\src\lib.rs")));
+    }
 
-        assert!(!glob.is_match(Path::new("abc")));
+    #[test]
+    fn match_glob_with_borrowed_candidate() {
+        let candidate = CandidatePath::from(Path::new("src/lib.rs")).into_owned();
+        let borrowed = CandidatePath::from(&candidate);
+        assert_eq!(borrowed.as_ref().as_ptr(), candidate.as_ref().as_ptr());
+
+        let glob = Glob::new("src/{*}.rs").unwrap();
+        assert!(glob.is_match(&candidate));
+        assert_eq!(glob.matched(&candidate).unwrap().get(1), Some("lib"));
     }
 
     #[test]
-    fn match_glob_with_only_invariant_tokens() {
-        let glob = Glob::new("a/b").unwrap();
+    fn glob_heap_usage() {
+        let glob = Glob::new("src/**/{*.rs,*.toml}").unwrap();
+        assert!(glob.heap_usage() > 0);
+        assert!(Glob::new("src/**/{*.rs,*.toml,*.md}").unwrap().heap_usage() > glob.heap_usage());
 
-        assert!(glob.is_match(Path::new("a/b")));
+        let any = crate::any(["src/**/*.rs", "tests/**/*.rs"]).unwrap();
+        assert!(any.heap_usage() > 0);
+    }
 
-        assert!(!glob.is_match(Path::new("aa/b")));
-        assert!(!glob.is_match(Path::new("a/bb")));
-        assert!(!glob.is_match(Path::new("a/b/c")));
+    #[test]
+    fn check_expression() {
+        assert!(crate::check("src/**/*.{rs,toml}").is_ok());
+        assert!(crate::check("<a*:0,>").is_ok());
+
+        assert!(crate::check("src/**/**").is_err());
+        assert!(crate::check("{a,**}/b").is_err());
+        assert!(crate::check("<a:2,1>").is_err());
+        assert!(crate::check("src/{").is_err());
+    }
 
-        // There are no variant tokens with which to capture, but the matched
-        // text should always be available.
-        assert_eq!(
-            "a/b",
-            glob.matched(&CandidatePath::from(Path::new("a/b")))
+    #[test]
+    fn glob_estimated_cost() {
+        assert!(Glob::new("src/**/*.rs").unwrap().estimated_cost() < 100);
+        assert!(
+            Glob::new("<{a,b,c,d}<x:0,100>:0,100>")
                 .unwrap()
-                .complete(),
+                .estimated_cost()
+                > 10_000
         );
     }
 
@@ -1688,6 +4092,27 @@ mod tests {
         assert_eq!("file", matched.get(2).unwrap());
     }
 
+    #[test]
+    fn query_matched_text_spans() {
+        let glob = Glob::new("src/**/*.{md,txt}").unwrap();
+        let path = CandidatePath::from("src/a/b/file.txt");
+        let matched = glob.matched(&path).unwrap();
+        let owned = matched.to_owned();
+        for matched in [&matched, &owned] {
+            assert_eq!(matched.span(0), Some((0, 16)));
+            assert_eq!(matched.span(1), Some((4, 4)));
+            assert_eq!(matched.span(2), Some((8, 4)));
+            assert_eq!(matched.span(3), Some((13, 3)));
+            assert_eq!(matched.span(4), None);
+        }
+
+        let glob = Glob::new("src/lib.rs").unwrap();
+        let path = CandidatePath::from("src/lib.rs");
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!(matched.span(0), Some((0, 10)));
+        assert_eq!(matched.span(1), None);
+    }
+
     #[test]
     fn match_glob_with_eager_and_lazy_zom_tokens() {
         let glob = Glob::new("$-*.*").unwrap();
@@ -1731,6 +4156,34 @@ mod tests {
         assert_eq!("金", matched.get(1).unwrap());
     }
 
+    #[test]
+    fn match_glob_with_equivalence_class_tokens() {
+        let glob = Glob::new("caf[[=e=]].txt").unwrap();
+
+        assert!(glob.is_match(Path::new("cafe.txt")));
+        assert!(glob.is_match(Path::new("caf\u{E9}.txt")));
+        assert!(glob.is_match(Path::new("caf\u{EA}.txt")));
+
+        assert!(!glob.is_match(Path::new("cafE.txt")));
+        assert!(!glob.is_match(Path::new("cafa.txt")));
+
+        let glob = Glob::new("[![=o=]]").unwrap();
+
+        assert!(glob.is_match(Path::new("a")));
+
+        assert!(!glob.is_match(Path::new("\u{F6}")));
+    }
+
+    #[test]
+    fn match_glob_with_collating_symbol_class_tokens() {
+        let glob = Glob::new("a/[[.-.][.].]]").unwrap();
+
+        assert!(glob.is_match(Path::new("a/-")));
+        assert!(glob.is_match(Path::new("a/]")));
+
+        assert!(!glob.is_match(Path::new("a/.")));
+    }
+
     #[test]
     fn match_glob_with_literal_escaped_class_tokens() {
         let glob = Glob::new("a/[\\[\\]\\-]/**").unwrap();
@@ -1773,6 +4226,65 @@ mod tests {
         assert_eq!("xyz", matched.get(1).unwrap());
     }
 
+    #[test]
+    fn match_glob_with_empty_alternative_tokens() {
+        let glob = Glob::new("src{/generated,}/*.rs").unwrap();
+
+        assert!(glob.is_match(Path::new("src/lib.rs")));
+        assert!(glob.is_match(Path::new("src/generated/lib.rs")));
+
+        assert!(!glob.is_match(Path::new("src/other/lib.rs")));
+        assert!(!glob.is_match(Path::new("srcgenerated/lib.rs")));
+
+        let glob = Glob::new("file{.tar,}.gz").unwrap();
+
+        let path = CandidatePath::from(Path::new("file.gz"));
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!("", matched.get(1).unwrap());
+    }
+
+    #[test]
+    fn match_glob_with_lazy_repetition_tokens() {
+        let eager = Glob::new("v<[0-9].:1,>{*}").unwrap();
+        let lazy = Glob::new("v<[0-9].:1,?>{*}").unwrap();
+
+        let path = CandidatePath::from(Path::new("v1.2.3"));
+        let matched = eager.matched(&path).unwrap();
+        assert_eq!("1.2.", matched.get(1).unwrap());
+        assert_eq!("3", matched.get(2).unwrap());
+        let matched = lazy.matched(&path).unwrap();
+        assert_eq!("1.", matched.get(1).unwrap());
+        assert_eq!("2.3", matched.get(2).unwrap());
+
+        assert!(lazy.is_match(Path::new("v1.2")));
+        assert!(!lazy.is_match(Path::new("v1")));
+    }
+
+    #[test]
+    fn match_glob_with_optional_tokens() {
+        let glob = Glob::new("app(-v{[0-9]})?.tar.gz").unwrap();
+
+        assert!(glob.is_match(Path::new("app.tar.gz")));
+        assert!(glob.is_match(Path::new("app-v2.tar.gz")));
+
+        assert!(!glob.is_match(Path::new("app-v.tar.gz")));
+        assert!(!glob.is_match(Path::new("app-v2-v3.tar.gz")));
+
+        let path = CandidatePath::from(Path::new("app-v2.tar.gz"));
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!("-v2", matched.get(1).unwrap());
+
+        let path = CandidatePath::from(Path::new("app.tar.gz"));
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!("", matched.get(1).unwrap());
+
+        // Flags apply within and after optional groups as usual.
+        let glob = Glob::new("(?i:a(b)?)c").unwrap();
+
+        assert!(glob.is_match(Path::new("ABc")));
+        assert!(!glob.is_match(Path::new("aC")));
+    }
+
     #[test]
     fn match_glob_with_nested_alternative_tokens() {
         let glob = Glob::new("a/{y$,{x?z,?z}}b/*").unwrap();
@@ -1888,6 +4400,86 @@ mod tests {
         assert!(!glob.is_match(Path::new("Photos/flower.jpeg")));
     }
 
+    #[test]
+    fn match_glob_with_scoped_flags() {
+        let glob = Glob::new("(?-i)src/(?i:readme).md").unwrap();
+
+        assert!(glob.is_match(Path::new("src/README.md")));
+        assert!(glob.is_match(Path::new("src/ReadMe.md")));
+
+        assert!(!glob.is_match(Path::new("SRC/README.md")));
+        assert!(!glob.is_match(Path::new("src/README.MD")));
+
+        let glob = Glob::new("(?-i)(?i:a(?-i:b)c)d").unwrap();
+
+        assert!(glob.is_match(Path::new("AbCd")));
+
+        assert!(!glob.is_match(Path::new("ABCd")));
+        assert!(!glob.is_match(Path::new("AbCD")));
+    }
+
+    #[test]
+    fn match_glob_with_grapheme_flag() {
+        let glob = Glob::new("(?g)caf?.txt").unwrap();
+
+        assert!(glob.is_match(Path::new("cafe.txt")));
+        assert!(glob.is_match(Path::new("cafe\u{301}.txt")));
+        assert!(!glob.is_match(Path::new("caf.txt")));
+        assert!(!glob.is_match(Path::new("caf/.txt")));
+
+        let glob = Glob::new("(?g)?.png").unwrap();
+
+        assert!(glob.is_match(Path::new("\u{1F469}\u{200D}\u{1F4BB}.png")));
+        assert!(glob.is_match(Path::new("\u{1F1E9}\u{1F1EA}.png")));
+        assert!(glob.is_match(Path::new("\u{D55C}.png")));
+        assert!(!glob.is_match(Path::new("ab.png")));
+
+        // Without the flag, `?` matches exactly one code point.
+        let glob = Glob::new("caf?.txt").unwrap();
+
+        assert!(!glob.is_match(Path::new("cafe\u{301}.txt")));
+    }
+
+    #[test]
+    fn match_glob_with_separator_crossing_flag() {
+        let glob = Glob::new("src/(?s:*)/mod.rs").unwrap();
+
+        assert!(glob.is_match(Path::new("src/a/mod.rs")));
+        assert!(glob.is_match(Path::new("src/a/b/mod.rs")));
+
+        assert!(!glob.is_match(Path::new("src/mod.rs")));
+        assert!(!glob.is_match(Path::new("lib/a/b/mod.rs")));
+
+        let glob = Glob::new("(?s)a*z/(?-s)*.txt").unwrap();
+
+        assert!(glob.is_match(Path::new("a/b/z/c.txt")));
+        assert!(glob.is_match(Path::new("az/c.txt")));
+
+        assert!(!glob.is_match(Path::new("a/b/z/c/d.txt")));
+
+        // Without the flag, `*` does not match separators.
+        let glob = Glob::new("a*z").unwrap();
+
+        assert!(!glob.is_match(Path::new("a/b/z")));
+    }
+
+    #[test]
+    fn match_glob_with_escaped_code_points() {
+        let glob = Glob::new("a\\tb/\\u{1F600}\\x{2A}.txt").unwrap();
+
+        assert!(glob.is_match(Path::new("a\tb/\u{1F600}*.txt")));
+
+        assert!(!glob.is_match(Path::new("atb/\u{1F600}*.txt")));
+        assert!(!glob.is_match(Path::new("a\tb/\u{1F600}x.txt")));
+
+        let glob = Glob::new("[\\t\\x{41}-\\x{43}]").unwrap();
+
+        assert!(glob.is_match(Path::new("\t")));
+        assert!(glob.is_match(Path::new("B")));
+
+        assert!(!glob.is_match(Path::new("D")));
+    }
+
     #[test]
     fn match_glob_with_escaped_flags() {
         let glob = Glob::new("a\\(b\\)").unwrap();
@@ -1908,6 +4500,171 @@ mod tests {
         assert!(!any.is_match("pkg/lib.rs"));
     }
 
+    #[test]
+    fn match_pointer_patterns() {
+        use std::borrow::Cow;
+        use std::rc::Rc;
+        use std::sync::Arc;
+
+        fn is_match<'t>(pattern: impl Pattern<'t>, path: &str) -> bool {
+            pattern.is_match(path)
+        }
+
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        assert!(is_match(&glob, "src/lib.rs"));
+        assert!(is_match(Arc::new(glob.clone()), "src/token/mod.rs"));
+        assert!(is_match(Rc::new(glob.clone()), "src/walk/mod.rs"));
+        assert!(is_match(Cow::Borrowed(&glob), "src/rule.rs"));
+        assert!(!is_match(Cow::<Glob>::Owned(glob.clone()), "README.md"));
+
+        let shared = Arc::new(Glob::new("doc/**/*.md").unwrap());
+        let any = crate::any([&glob, &*shared]).unwrap();
+        assert!(any.is_match("src/lib.rs"));
+        assert!(any.is_match("doc/guide.md"));
+        let any = crate::any([Arc::clone(&shared), Arc::new(glob.clone())]).unwrap();
+        assert!(any.is_match("doc/guide.md"));
+        assert_eq!(Arc::strong_count(&shared), 1);
+
+        let any = crate::any([&any, &crate::any(["*.toml"]).unwrap()]).unwrap();
+        assert!(any.is_match("src/lib.rs"));
+        assert!(any.is_match("Cargo.toml"));
+        assert!(is_match(Rc::new(any), "doc/guide.md"));
+    }
+
+    // The home directory is read from the environment, so all home expansion
+    // cases are tested in a single test.
+    #[cfg(unix)]
+    #[test]
+    fn build_glob_with_expanded_home() {
+        std::env::set_var("HOME", "/home/user [1]");
+
+        let build = |expression| GlobBuilder::new().expand_home(true).build(expression);
+
+        let glob = build("~/src/**/*.rs").unwrap();
+        assert!(glob.has_root());
+        assert!(glob.is_match("/home/user [1]/src/lib.rs"));
+        assert!(!glob.is_match("~/src/lib.rs"));
+        let (prefix, _) = glob.partition();
+        assert_eq!(prefix, Path::new("/home/user [1]/src"));
+
+        assert!(build("~").unwrap().is_match("/home/user [1]"));
+        assert!(build("~root/.profile").unwrap().has_root());
+
+        // Only a leading `~` followed by a separator or user name is expanded.
+        assert!(build("a/~/b").unwrap().is_match("a/~/b"));
+        assert!(build("~*").unwrap().is_match("~x"));
+        assert!(build("[~]/a").unwrap().is_match("~/a"));
+        assert!(GlobBuilder::new().build("~/a").unwrap().is_match("~/a"));
+
+        // Locations refer to the unexpanded glob expression.
+        let spans = |expression| {
+            build(expression)
+                .unwrap_err()
+                .locations()
+                .map(LocatedError::span)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spans("~/a//b"), [(3, 2)]);
+        assert_eq!(spans("~/{a,**}"), [(2, 6)]);
+        assert_eq!(spans("~no-such-user/a"), [(0, 13)]);
+        assert!(matches!(
+            build("~no-such-user/a"),
+            Err(BuildError {
+                kind: BuildErrorKind::Expansion(_),
+                ..
+            }),
+        ));
+
+        std::env::set_var("HOME", "/");
+        assert!(build("~/a").unwrap().is_match("/a"));
+
+        std::env::remove_var("HOME");
+        assert_eq!(spans("~/a"), [(0, 1)]);
+    }
+
+    #[test]
+    fn build_glob_with_expanded_variables() {
+        std::env::set_var("WAX_TEST_DIR", "assets/img[1]");
+        std::env::set_var("WAX_TEST_EXT", "png");
+
+        let build = |expression| GlobBuilder::new().expand_variables(true).build(expression);
+
+        let glob = build("${WAX_TEST_DIR}/**/*.${WAX_TEST_EXT}").unwrap();
+        assert!(glob.is_match("assets/img[1]/a/b.png"));
+        assert!(!glob.is_match("assets/img1/a/b.png"));
+        let (prefix, _) = glob.partition();
+        assert_eq!(prefix, Path::new("assets/img[1]"));
+
+        // Escaped `$` and text that is not a reference are not expanded.
+        assert!(build("\\${WAX_TEST_EXT}").unwrap().is_match("$WAX_TEST_EXT"));
+        assert!(build("${a,b}").unwrap().is_match("$b"));
+        assert!(build("$WAX_TEST_EXT").unwrap().is_match("$WAX_TEST_EXT"));
+        assert!(build("\\*${WAX_TEST_EXT}").unwrap().is_match("*png"));
+        assert!(GlobBuilder::new()
+            .build("${WAX_TEST_EXT}")
+            .unwrap()
+            .is_match("$WAX_TEST_EXT"));
+
+        // Locations refer to the unexpanded glob expression.
+        let spans = |expression| {
+            build(expression)
+                .unwrap_err()
+                .locations()
+                .map(LocatedError::span)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(spans("${WAX_TEST_EXT}//a"), [(15, 2)]);
+        assert_eq!(spans("a/${WAX_TEST_UNSET}"), [(2, 17)]);
+        assert!(matches!(
+            build("${WAX_TEST_UNSET}"),
+            Err(BuildError {
+                kind: BuildErrorKind::Expansion(_),
+                ..
+            }),
+        ));
+    }
+
+    #[test]
+    fn build_glob_with_turkic_case_folding() {
+        let build = |expression| {
+            GlobBuilder::new()
+                .case_folding(CaseFolding::Turkic)
+                .build(expression)
+                .unwrap()
+        };
+
+        let glob = build("(?i)dir/*.txt");
+        assert!(glob.is_match("dir/a.txt"));
+        assert!(glob.is_match("DİR/a.txt"));
+        assert!(!glob.is_match("DIR/a.txt"));
+        assert!(!glob.is_match("dır/a.txt"));
+        assert!(build("(?i)ı").is_match("I"));
+        assert!(build("(?i)dosya").is_match("DOSYA"));
+        assert!(build("(?-i)i").is_match("i"));
+        assert!(!build("(?-i)i").is_match("İ"));
+
+        let glob = GlobBuilder::new().build("(?i)dir").unwrap();
+        assert!(glob.is_match("DIR"));
+        assert!(!glob.is_match("DİR"));
+
+        // Folding is preserved by partitioning and joining.
+        let (prefix, glob) = build("(?-i)src/(?i)dir*").partition();
+        assert_eq!(prefix, Path::new("src"));
+        assert!(glob.is_match("DİRx"));
+        assert!(!glob.is_match("DIRx"));
+        let glob = Glob::new("(?-i)src")
+            .unwrap()
+            .join(&build("(?i)dir"))
+            .unwrap();
+        assert!(glob.is_match("src/DİR"));
+        assert!(!glob.is_match("src/DIR"));
+
+        // Only case-insensitive literals with Turkic folding change hashes.
+        let hash = |expression| Glob::new(expression).unwrap().stable_hash();
+        assert_eq!(build("(?-i)dir*").stable_hash(), hash("(?-i)dir*"));
+        assert_ne!(build("(?i)dir*").stable_hash(), hash("(?i)dir*"));
+    }
+
     #[test]
     fn partition_glob_with_variant_and_invariant_parts() {
         let (prefix, glob) = Glob::new("a/b/x?z/*.ext").unwrap().partition();
@@ -2037,6 +4794,58 @@ mod tests {
         assert_eq!(format!("{}", glob), "**");
     }
 
+    #[test]
+    fn partition_glob_without_consuming() {
+        let glob = Glob::new("/root/**/file.ext").unwrap();
+        let (prefix, postfix) = glob.partitioned();
+        assert_eq!(prefix, Path::new("/root"));
+        assert_eq!(format!("{}", postfix), "**/file.ext");
+        assert!(glob.is_match(Path::new("/root/a/file.ext")));
+    }
+
+    #[test]
+    fn partition_glob_with_flags() {
+        for (expression, expected, postfix) in [
+            ("src/(?-i:lib/)*.rs", "src/lib", "*.rs"),
+            ("(?-i:src/)*.rs", "src", "*.rs"),
+            ("abc(?-i:/)x*", "abc", "x*"),
+            ("foo(?s)/**/*.rs", "foo", "(?s)**/*.rs"),
+            ("(?s:foo/*)", "foo", "(?s:*)"),
+            ("(?-s:foo/(?s)bar/*)", "foo/bar", "(?s:*)"),
+            ("(?s:a/(?-s:b/(?g)c/*)d*)", "a/b/c", "(?s:(?g-s:*)d*)"),
+            ("(?s:a(?-s)/**/*.rs)", "a", "(?-g:**/*.rs)"),
+        ] {
+            let (prefix, glob) = Glob::new(expression).unwrap().partition();
+            assert_eq!(prefix, Path::new(expected));
+            assert_eq!(glob.to_string(), postfix);
+            assert_eq!(
+                Glob::new(&glob.to_string()).unwrap().to_string(),
+                glob.to_string(),
+            );
+        }
+
+        let (_, glob) = Glob::new("(?s:foo/*)").unwrap().partition();
+        assert!(glob.is_match(Path::new("a/b")));
+        assert!(Glob::new(&glob.to_string()).unwrap().is_match(Path::new("a/b")));
+    }
+
+    #[test]
+    fn invariant_prefix_span_of_glob() {
+        fn span(expression: &str) -> Option<&str> {
+            Glob::new(expression)
+                .unwrap()
+                .invariant_prefix_span()
+                .map(|(start, n)| &expression[start..][..n])
+        }
+
+        assert_eq!(span("a/b/*.ext"), Some("a/b/"));
+        assert_eq!(span("/root/**/file.ext"), Some("/root/"));
+        assert_eq!(span("/**/file.ext"), Some("/"));
+        assert_eq!(span("/root/file.ext"), Some("/root/file.ext"));
+        assert_eq!(span("**/file.ext"), None);
+        assert_eq!(span("*.ext"), None);
+    }
+
     #[test]
     fn repartition_glob_with_variant_tokens() {
         let (prefix, glob) = Glob::new("/root/**/file.ext").unwrap().partition();
@@ -2048,6 +4857,62 @@ mod tests {
         assert_eq!(format!("{}", glob), "**/file.ext");
     }
 
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_glob_as_expression() {
+        let glob = Glob::new("src/**/*.{rs,toml}").unwrap();
+        assert_eq!(
+            serde_json::to_string(&glob).unwrap(),
+            "\"src/**/*.{rs,toml}\"",
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn deserialize_glob_from_expression() {
+        let glob: Glob<'static> = serde_json::from_str("\"src/**/*.rs\"").unwrap();
+        assert!(glob.is_match("src/token/mod.rs"));
+
+        let globs: Vec<Glob<'static>> = serde_json::from_str("[\"*.md\", \"doc/**\"]").unwrap();
+        assert!(globs[0].is_match("README.md"));
+        assert!(globs[1].is_match("doc/wax.svg"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serialize_partitioned_glob_with_flags() {
+        let (_, glob) = Glob::new("src/(?-i:lib/)(?s:*.rs)").unwrap().partition();
+        let json = serde_json::to_string(&glob).unwrap();
+        assert_eq!(json, "\"(?s:*.rs)\"");
+
+        let glob: Glob<'static> = serde_json::from_str(&json).unwrap();
+        assert!(glob.is_match("a/b.rs"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn reject_deserialized_glob_with_invalid_expression() {
+        assert!(serde_json::from_str::<Glob<'static>>("\"src/***\"").is_err());
+        assert!(serde_json::from_str::<Glob<'static>>("\"{a,**}\"").is_err());
+        assert!(serde_json::from_str::<Glob<'static>>("0").is_err());
+    }
+
+    #[test]
+    fn query_glob_literal_path() {
+        assert_eq!(
+            Glob::new("a/b/file.ext").unwrap().as_literal_path(),
+            Some(Path::new("a/b/file.ext").to_path_buf()),
+        );
+        assert_eq!(
+            Glob::new("<a:3>/{b}").unwrap().as_literal_path(),
+            Some(Path::new("aaa/b").to_path_buf()),
+        );
+
+        assert_eq!(Glob::new("a/*.ext").unwrap().as_literal_path(), None);
+        assert_eq!(Glob::new("**/file.ext").unwrap().as_literal_path(), None);
+        assert_eq!(Glob::new("a/{b,c}").unwrap().as_literal_path(), None);
+    }
+
     #[test]
     fn query_glob_has_root() {
         assert!(Glob::new("/root").unwrap().has_root());
@@ -2139,4 +5004,245 @@ mod tests {
             .variance()
             .is_variant());
     }
+
+    #[test]
+    fn query_glob_breadth_and_depth() {
+        let breadth = |expression| Glob::new(expression).unwrap().breadth();
+        let depth = |expression| Glob::new(expression).unwrap().depth();
+
+        assert!(breadth("literal/fixed.txt").is_closed());
+        assert!(breadth("?/[a-z]/{a,b}.txt").is_closed());
+        assert!(breadth("<a:0,8>/<b/>").is_closed());
+        assert!(breadth("*/fixed.txt").is_open());
+        assert!(breadth("a/**/b").is_open());
+        assert!(breadth("<a>").is_open());
+        assert!(breadth("<{a,b/}:1,>").is_open());
+        assert!(breadth("(?g)?").is_open());
+
+        assert!(depth("src/*/*.rs").is_closed());
+        assert!(depth("<*/:0,4>*.rs").is_closed());
+        assert!(depth("**/*.rs").is_open());
+        assert!(depth("<*/>*.rs").is_open());
+        assert!(depth("(?s)*.rs").is_open());
+    }
+
+    #[test]
+    fn combine_variance() {
+        use crate::variance::{
+            Boundedness, ConjunctiveVariance as _, DisjunctiveVariance as _, IntoInvariantText as _,
+            InvariantSize, InvariantText, Variance,
+        };
+
+        let text = |text: &'static str| Variance::Invariant(text.into_nominal_text());
+        let size = |n| Variance::Invariant(InvariantSize::new(n));
+
+        assert_eq!(
+            [text("a/"), text("b")].into_iter().conjunctive_variance(),
+            text("a/b"),
+        );
+        assert_eq!(
+            [text("a"), Variance::Variant(Boundedness::Open)]
+                .into_iter()
+                .conjunctive_variance()
+                .boundedness(),
+            Boundedness::Closed,
+        );
+        assert_eq!(
+            Variance::<InvariantSize>::Variant(Boundedness::Open)
+                + Variance::Variant(Boundedness::Open),
+            Variance::Variant(Boundedness::Open),
+        );
+        assert_eq!(
+            [text("a"), text("a")].into_iter().disjunctive_variance(),
+            text("a"),
+        );
+        assert_eq!(
+            [text("a"), text("b")].into_iter().disjunctive_variance(),
+            Variance::<InvariantText>::Variant(Boundedness::Closed),
+        );
+        assert_eq!(
+            Vec::<Variance<InvariantText>>::new()
+                .into_iter()
+                .conjunctive_variance(),
+            Variance::Invariant(InvariantText::new()),
+        );
+        assert_eq!(
+            (InvariantText::new() + "ab".into_nominal_text())
+                .repeat(3)
+                .to_string(),
+            "ababab",
+        );
+        assert_eq!(size(2) + size(3), size(5));
+        assert_eq!(InvariantSize::new(2) * 3, InvariantSize::new(6));
+    }
+
+    #[test]
+    fn query_glob_examples() {
+        for expression in [
+            "a",
+            "*",
+            "**",
+            "/**",
+            "a/**",
+            "/**/a",
+            "**/*.rs",
+            "src/*/lib.rs",
+            "a*b$c",
+            "?[a-c][!a-z][!0-9]",
+            "{a,b/**/c}.txt",
+            "(?i){readme,license}.md",
+            "<a*:2,>/<b/:0,2>c",
+            "a/<[xyz]:1,5>/{?,[!x]*}",
+        ] {
+            let glob = Glob::new(expression).unwrap();
+            let examples = glob.examples(16);
+            assert!(!examples.is_empty());
+            assert_eq!(glob.example().as_ref(), examples.first());
+            for example in examples.iter() {
+                assert!(
+                    glob.is_match(example.as_path()),
+                    "{} !~ {:?}",
+                    expression,
+                    example,
+                );
+            }
+            for (n, example) in examples.iter().enumerate() {
+                assert!(!examples[..n].contains(example));
+            }
+        }
+
+        assert_eq!(Glob::new("a/*/b").unwrap().example().unwrap(), Path::new("a/a/b"));
+        assert_eq!(Glob::new("a/**/b").unwrap().example().unwrap(), Path::new("a/b"));
+        assert_eq!(Glob::new("{a,b}.txt").unwrap().examples(3).len(), 2);
+        assert!(Glob::new("[/]").unwrap().example().is_none());
+    }
+
+    #[test]
+    fn substitute_glob() {
+        let substitute = |expression, values: &[(usize, &str)]| {
+            Glob::new(expression)
+                .unwrap()
+                .substitute(values.iter().copied())
+        };
+
+        assert_eq!(
+            substitute("src/**/*.rs", &[(1, "a/b"), (2, "lib")]).unwrap(),
+            Path::new("src/a/b/lib.rs"),
+        );
+        assert_eq!(
+            substitute("src/**/*.rs", &[(1, ""), (2, "lib")]).unwrap(),
+            Path::new("src/lib.rs"),
+        );
+        assert_eq!(
+            substitute("/**/a", &[(1, "x/")]).unwrap(),
+            Path::new("/x/a"),
+        );
+        assert_eq!(
+            substitute("{a}/<b:2>/[xy]?", &[(2, "bb"), (3, "x"), (4, "z")]).unwrap(),
+            Path::new("a/bb/xz"),
+        );
+        assert_eq!(
+            substitute("*.{md,txt}", &[(1, "a"), (2, "md"), (1, "b")]).unwrap(),
+            Path::new("b.md"),
+        );
+
+        let error = substitute("src/*.rs", &[]).unwrap_err();
+        assert_eq!(error.index(), 1);
+        assert_eq!(error.span(), (4, 1));
+        let error = substitute("src/*.{md,txt}", &[(1, "a"), (2, "rs")]).unwrap_err();
+        assert_eq!(error.index(), 2);
+        assert!(substitute("*", &[(1, "a/b")]).is_err());
+        assert!(substitute("[0-9]", &[(1, "a")]).is_err());
+        assert!(substitute("<[0-9]:2>", &[(1, "123")]).is_err());
+    }
+
+    #[test]
+    fn substitute_glob_with_matched_text() {
+        for (expression, path) in [
+            ("src/**/*.rs", "src/a/b/lib.rs"),
+            ("src/**/*.rs", "src/lib.rs"),
+            ("a/**", "a/b/c"),
+            ("/**/a", "/x/a"),
+            ("{a,b}/<[0-9]:1,>-*.log", "b/2023-01.log"),
+        ] {
+            let glob = Glob::new(expression).unwrap();
+            let candidate = CandidatePath::from(path);
+            let matched = glob.matched(&candidate).unwrap();
+            let values: Vec<_> = (1..8)
+                .filter_map(|index| Some((index, matched.get(index)?)))
+                .collect();
+            assert_eq!(
+                glob.substitute(values).unwrap(),
+                Path::new(path),
+            );
+        }
+    }
+
+    #[test]
+    fn query_glob_safe_root() {
+        let safe_root = |expression| Glob::new(expression).unwrap().safe_root("srv/tenant");
+
+        assert_eq!(safe_root("**/*.txt").unwrap(), Path::new("srv/tenant"));
+        assert_eq!(
+            safe_root("a/./b/../c/*.txt").unwrap(),
+            Path::new("srv/tenant/a/c"),
+        );
+        assert_eq!(safe_root("a/../{b}/..").unwrap(), Path::new("srv/tenant"));
+
+        let error = safe_root("../../etc/**").unwrap_err();
+        assert!(!error.is_rooted());
+        assert_eq!(error.directory(), Path::new("srv/tenant"));
+        assert_eq!(error.span(), (0, 10));
+        assert!(safe_root("a/../../other/*").is_err());
+        assert!(safe_root("a/../../tenant/*").is_ok());
+        assert!(safe_root("..").is_err());
+
+        let error = safe_root("/etc/**").unwrap_err();
+        assert!(error.is_rooted());
+        assert_eq!(error.span(), (0, 1));
+        assert!(safe_root("/**/passwd").unwrap_err().is_rooted());
+        assert!(Glob::new("/srv/**")
+            .unwrap()
+            .safe_root("/srv")
+            .unwrap_err()
+            .is_rooted());
+    }
+
+    #[test]
+    fn query_glob_stable_hash() {
+        let hash = |expression| Glob::new(expression).unwrap().stable_hash();
+
+        // These hashes must not change between releases.
+        assert_eq!(hash(""), 12_638_153_115_695_167_455);
+        assert_eq!(hash("**/*"), 639_432_106_211_771_492);
+        assert_eq!(hash("(?-i)src/**/*.{rs,toml}"), 7_912_163_729_619_261_703);
+        assert_eq!(hash("(?-i)<[!a-z]:1,>?"), 6_867_074_598_614_921_447);
+
+        assert_eq!(hash("(?-i)a(?i)(?-i)b"), hash("(?-i)ab"));
+        assert_eq!(hash("(?-i)a\\*"), hash("(?-i)a(?-i)\\*"));
+        assert_ne!(hash("(?-i)ab"), hash("(?i)ab"));
+        assert_ne!(hash("(?-i)a{b}"), hash("(?-i)ab"));
+        assert_ne!(hash("{a,b}"), hash("{b,a}"));
+        assert_ne!(hash("<a>"), hash("<a:1>"));
+        assert_ne!(hash("*"), hash("$"));
+    }
+
+    #[test]
+    fn match_glob_components() {
+        let glob = Glob::new("a/**/{*}.txt").unwrap();
+        assert!(glob.match_components(["a", "b.txt"]).is_some());
+        assert!(glob.match_components(["a", "b", "c", "d.txt"]).is_some());
+        assert!(glob.match_components(["b", "c.txt"]).is_none());
+        assert!(glob.match_components(["a", "b/c.txt"]).is_none());
+        assert!(glob.match_components(["a", "", "c.txt"]).is_none());
+        assert_eq!(
+            glob.match_components(vec![String::from("a"), String::from("b.txt")])
+                .unwrap()
+                .get(2),
+            Some("b"),
+        );
+
+        let glob = Glob::new("").unwrap();
+        assert!(glob.match_components(Vec::<&str>::new()).is_some());
+    }
 }
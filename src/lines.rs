@@ -0,0 +1,132 @@
+use std::io::{self, BufRead};
+
+use crate::capture::MatchedText;
+use crate::{CandidatePath, Glob, Pattern as _};
+
+/// Iterator over the delimited paths read from a [`BufRead`] that match a
+/// [`Glob`].
+///
+/// This iterator is constructed by [`Glob::filter_lines`] and
+/// [`Glob::filter_nul_delimited`]. Each record is read into a buffer that is
+/// reused for all records, so paths that do not match are never allocated.
+/// Matching paths are yielded as [matched text][`MatchedText`], which owns its
+/// captures and the complete path at index zero.
+///
+/// Records that are not UTF-8 are matched with any invalid sequences replaced
+/// by `U+FFFD`. Empty records are ignored.
+///
+/// [`BufRead`]: std::io::BufRead
+/// [`Glob`]: crate::Glob
+/// [`Glob::filter_lines`]: crate::Glob::filter_lines
+/// [`Glob::filter_nul_delimited`]: crate::Glob::filter_nul_delimited
+/// [`MatchedText`]: crate::MatchedText
+#[derive(Debug)]
+#[must_use]
+pub struct FilterLines<'g, 't, R> {
+    reader: R,
+    glob: &'g Glob<'t>,
+    delimiter: u8,
+    buffer: Vec<u8>,
+}
+
+impl<'g, 't, R> FilterLines<'g, 't, R>
+where
+    R: BufRead,
+{
+    pub(crate) fn new(reader: R, glob: &'g Glob<'t>, delimiter: u8) -> Self {
+        FilterLines {
+            reader,
+            glob,
+            delimiter,
+            buffer: vec![],
+        }
+    }
+
+    /// Converts into the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+}
+
+impl<'g, 't, R> Iterator for FilterLines<'g, 't, R>
+where
+    R: BufRead,
+{
+    type Item = io::Result<MatchedText<'static>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buffer.clear();
+            match self.reader.read_until(self.delimiter, &mut self.buffer) {
+                Ok(0) => return None,
+                Ok(_) => {},
+                Err(error) => return Some(Err(error)),
+            }
+            let mut record = self.buffer.as_slice();
+            if let Some(rest) = record.strip_suffix(&[self.delimiter]) {
+                record = rest;
+            }
+            // Newline-delimited text may use CRLF line endings.
+            if self.delimiter == b'\n' {
+                if let Some(rest) = record.strip_suffix(b"\r") {
+                    record = rest;
+                }
+            }
+            if record.is_empty() {
+                continue;
+            }
+            let text = String::from_utf8_lossy(record);
+            if let Some(matched) = self
+                .glob
+                .matched(&CandidatePath::from(&*text))
+                .map(MatchedText::into_owned)
+            {
+                return Some(Ok(matched));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, BufReader, Read};
+
+    use crate::{Glob, MatchedText};
+
+    fn paths(lines: impl Iterator<Item = io::Result<MatchedText<'static>>>) -> Vec<String> {
+        lines
+            .map(|matched| matched.unwrap().complete().to_owned())
+            .collect()
+    }
+
+    #[test]
+    fn filter_newline_delimited_paths() {
+        let glob = Glob::new("src/**/{*}.rs").unwrap();
+        let input = "src/lib.rs\r\nREADME.md\n\nsrc/token/parse.rs";
+        let stems: Vec<_> = glob
+            .filter_lines(input.as_bytes())
+            .map(|matched| matched.unwrap().get(2).unwrap().to_owned())
+            .collect();
+        assert_eq!(stems, ["lib", "parse"]);
+    }
+
+    #[test]
+    fn filter_nul_delimited_paths() {
+        let glob = Glob::new("**/*.txt").unwrap();
+        let input = b"a\nb.txt\0c.md\0\xffd.txt\0";
+        let matched = paths(glob.filter_nul_delimited(&input[..]));
+        assert_eq!(matched, ["a\nb.txt", "\u{fffd}d.txt"]);
+    }
+
+    #[test]
+    fn filter_paths_with_small_buffer() {
+        let glob = Glob::new("*/b").unwrap();
+        let input = "a/b\nab\nc/b\n";
+        let reader = BufReader::with_capacity(1, input.as_bytes());
+        let mut lines = glob.filter_lines(reader);
+        assert_eq!(lines.next().unwrap().unwrap().complete(), "a/b");
+        assert_eq!(lines.next().unwrap().unwrap().complete(), "c/b");
+        assert!(lines.next().is_none());
+        assert!(lines.into_inner().bytes().next().is_none());
+    }
+}
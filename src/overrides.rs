@@ -0,0 +1,220 @@
+use regex::RegexSet;
+
+use crate::encode;
+use crate::{BuildError, CandidatePath, Glob};
+
+/// Polarity of a [`Glob`] in [`Overrides`].
+///
+/// [`Glob`]: crate::Glob
+/// [`Overrides`]: crate::Overrides
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Polarity {
+    /// The [`Glob`] selects matching paths.
+    ///
+    /// [`Glob`]: crate::Glob
+    Positive,
+    /// The [`Glob`] deselects matching paths, like negated (`!`) patterns in
+    /// ignore files.
+    ///
+    /// [`Glob`]: crate::Glob
+    Negative,
+}
+
+impl Polarity {
+    pub fn is_positive(&self) -> bool {
+        matches!(self, Polarity::Positive)
+    }
+
+    pub fn is_negative(&self) -> bool {
+        matches!(self, Polarity::Negative)
+    }
+}
+
+/// Ordered [`Glob`]s in which later [`Glob`]s override earlier [`Glob`]s.
+///
+/// `Overrides` implements the precedence of ignore files and similar override
+/// lists: each [`Glob`] has a [`Polarity`] and the **last** [`Glob`] that
+/// matches a path decides that path. A negative [`Glob`] can therefore
+/// re-include paths that are selected by an earlier positive [`Glob`] and vice
+/// versa. All [`Glob`]s are matched in a single pass.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, Overrides, Polarity};
+///
+/// let overrides = Overrides::new([
+///     (Glob::new("**/*.log").unwrap(), Polarity::Positive),
+///     (Glob::new("**/keep.log").unwrap(), Polarity::Negative),
+/// ])
+/// .unwrap();
+///
+/// let decision = overrides.decide("logs/keep.log").unwrap();
+/// assert_eq!(decision.index(), 1);
+/// assert!(decision.polarity().is_negative());
+/// assert!(overrides.decide("src/lib.rs").is_none());
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Polarity`]: crate::Polarity
+#[derive(Clone, Debug)]
+pub struct Overrides<'t> {
+    rules: Vec<(Glob<'t>, Polarity)>,
+    set: RegexSet,
+}
+
+impl<'t> Overrides<'t> {
+    /// Constructs `Overrides` from [`Glob`]s and their [`Polarity`] in order of
+    /// increasing precedence.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the combined [`Glob`]s fail to build. This only
+    /// occurs if the compiled program is too large.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Polarity`]: crate::Polarity
+    pub fn new<I>(rules: I) -> Result<Self, BuildError>
+    where
+        I: IntoIterator<Item = (Glob<'t>, Polarity)>,
+    {
+        let rules: Vec<_> = rules.into_iter().collect();
+        let set = encode::compile_set(rules.iter().map(|(glob, _)| &glob.pattern))?;
+        Ok(Overrides { rules, set })
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Overrides<'static> {
+        let Overrides { rules, set } = self;
+        Overrides {
+            rules: rules
+                .into_iter()
+                .map(|(glob, polarity)| (glob.into_owned(), polarity))
+                .collect(),
+            set,
+        }
+    }
+
+    /// Gets the [`Decision`] of the [`Glob`] with the highest precedence that
+    /// matches a path.
+    ///
+    /// Returns `None` if no [`Glob`] matches the path.
+    ///
+    /// [`Decision`]: crate::Decision
+    /// [`Glob`]: crate::Glob
+    pub fn decide<'p>(&self, path: impl Into<CandidatePath<'p>>) -> Option<Decision<'_, 't>> {
+        let path = path.into();
+        self.set
+            .matches(path.as_ref())
+            .into_iter()
+            .next_back()
+            .map(|index| Decision {
+                index,
+                rule: &self.rules[index],
+            })
+    }
+
+    /// Gets the [`Glob`]s and their [`Polarity`] in order of increasing
+    /// precedence.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Polarity`]: crate::Polarity
+    pub fn rules(&self) -> &[(Glob<'t>, Polarity)] {
+        &self.rules
+    }
+
+    /// Gets the number of [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Returns `true` if there are no [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+/// The [`Glob`] in [`Overrides`] that decides a path.
+///
+/// See [`Overrides::decide`].
+///
+/// [`Glob`]: crate::Glob
+/// [`Overrides`]: crate::Overrides
+/// [`Overrides::decide`]: crate::Overrides::decide
+#[derive(Clone, Copy, Debug)]
+pub struct Decision<'o, 't> {
+    index: usize,
+    rule: &'o (Glob<'t>, Polarity),
+}
+
+impl<'o, 't> Decision<'o, 't> {
+    /// Gets the index of the deciding [`Glob`] in its [`Overrides`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Overrides`]: crate::Overrides
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Gets the deciding [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn glob(&self) -> &'o Glob<'t> {
+        &self.rule.0
+    }
+
+    /// Gets the [`Polarity`] of the deciding [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Polarity`]: crate::Polarity
+    pub fn polarity(&self) -> Polarity {
+        self.rule.1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, Overrides, Polarity};
+
+    fn overrides(rules: &[(&'static str, Polarity)]) -> Overrides<'static> {
+        Overrides::new(
+            rules
+                .iter()
+                .map(|(expression, polarity)| (Glob::new(expression).unwrap(), *polarity)),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn decide_with_last_matching_glob() {
+        let overrides = overrides(&[
+            ("target/**", Polarity::Positive),
+            ("**/*.rs", Polarity::Negative),
+            ("target/debug/build/**", Polarity::Positive),
+        ]);
+        let decide = |path| {
+            overrides
+                .decide(path)
+                .map(|decision| (decision.index(), decision.polarity()))
+        };
+        assert_eq!(decide("target/debug/a.o"), Some((0, Polarity::Positive)));
+        assert_eq!(decide("target/debug/a.rs"), Some((1, Polarity::Negative)));
+        assert_eq!(
+            decide("target/debug/build/a.rs"),
+            Some((2, Polarity::Positive)),
+        );
+        assert_eq!(decide("src/lib.rs"), Some((1, Polarity::Negative)));
+        assert_eq!(decide("README.md"), None);
+    }
+
+    #[test]
+    fn decide_with_empty_overrides() {
+        let overrides = overrides(&[]);
+        assert!(overrides.is_empty());
+        assert!(overrides.decide("src/lib.rs").is_none());
+    }
+}
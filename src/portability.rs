@@ -0,0 +1,167 @@
+use regex::Regex;
+use std::error::Error;
+use std::fmt::{self, Display, Formatter};
+
+use crate::encode;
+use crate::token::{self, Token, TokenKind, TokenTree as _, Tokenized};
+use crate::{PathKind, StrExt as _};
+
+// Bounds the number of example paths from which witnesses are derived.
+const MAX_EXAMPLES: usize = 64;
+
+/// Describes a glob that may match paths differently on Unix and Windows.
+///
+/// A glob is compared on Unix and Windows by matching the same paths with the
+/// semantics of each platform: separators are `/` on Unix and `\` or `/` on
+/// Windows and literals use the default case sensitivity of each platform
+/// unless set by a flag. Only paths with names that are valid on both
+/// platforms are compared, so names that contain backslashes are never
+/// considered.
+///
+/// See [`Glob::check_portability`].
+///
+/// [`Glob::check_portability`]: crate::Glob::check_portability
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Divergence {
+    witness: Option<(String, PathKind)>,
+}
+
+impl Divergence {
+    /// Gets a path that is matched by the glob on only one platform, if any.
+    ///
+    /// The path uses `/` separators. If no such path is found, then the glob
+    /// has literals with a case sensitivity that differs between platforms,
+    /// but it is not known if any paths are matched differently.
+    pub fn witness(&self) -> Option<&str> {
+        self.witness.as_ref().map(|(path, _)| path.as_str())
+    }
+
+    /// Gets the platform on which the [witness][`Divergence::witness`] is
+    /// matched, if any.
+    ///
+    /// This is either [`PathKind::Unix`] or [`PathKind::Windows`].
+    ///
+    /// [`Divergence::witness`]: crate::Divergence::witness
+    /// [`PathKind::Unix`]: crate::PathKind::Unix
+    /// [`PathKind::Windows`]: crate::PathKind::Windows
+    pub fn matching_platform(&self) -> Option<PathKind> {
+        self.witness.as_ref().map(|(_, kind)| *kind)
+    }
+}
+
+impl Display for Divergence {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self.witness {
+            Some((ref path, PathKind::Windows)) => {
+                write!(f, "glob matches `{}` on Windows but not on Unix", path)
+            },
+            Some((ref path, _)) => write!(f, "glob matches `{}` on Unix but not on Windows", path),
+            _ => write!(f, "glob may match differently on Unix and Windows"),
+        }
+    }
+}
+
+impl Error for Divergence {}
+
+// Compiles the tokens of a glob with the semantics of Unix and Windows and
+// searches for a path that is matched on only one platform. Separators are
+// encoded differently, but only the case sensitivity of literals can cause
+// paths with portable names to match differently.
+pub fn check(tokenized: &Tokenized<'_>) -> Result<(), Divergence> {
+    let recase = |kind: PathKind| tokenized.clone().recase(kind.is_case_insensitive());
+    let (unix, windows) = (recase(PathKind::Unix), recase(PathKind::Windows));
+    let is_recased = unix
+        .walk()
+        .zip(windows.walk())
+        .any(|((_, left), (_, right))| is_recased(left, right));
+    if !is_recased {
+        return Ok(());
+    }
+
+    let compile = |kind: PathKind, tokenized: &Tokenized<'_>| {
+        encode::compile_with_kind(kind, tokenized.tokens()).ok()
+    };
+    let programs = match (
+        compile(PathKind::Unix, &unix),
+        compile(PathKind::Windows, &windows),
+    ) {
+        (Some(unix), Some(windows)) => (unix, windows),
+        // The glob diverges, but no path can be found without programs.
+        _ => return Err(Divergence { witness: None }),
+    };
+    let witness = token::examples(unix.tokens())
+        .chain(token::examples(windows.tokens()))
+        .take(MAX_EXAMPLES)
+        .flat_map(|example| {
+            [
+                example.to_lowercase(),
+                example.to_uppercase(),
+                example.swap_case(),
+                example,
+            ]
+        })
+        .find_map(|path| diverge(&programs, path));
+    Err(Divergence { witness })
+}
+
+// Returns `true` if the tokens are literals with casing that are matched with a
+// different case sensitivity.
+fn is_recased<A>(left: &Token<'_, A>, right: &Token<'_, A>) -> bool {
+    match (left.kind(), right.kind()) {
+        (TokenKind::Literal(ref left), TokenKind::Literal(ref right)) => {
+            left.is_case_insensitive() != right.is_case_insensitive() && left.text().has_casing()
+        },
+        _ => false,
+    }
+}
+
+// Gets the platform on which a path is matched if it is matched on only one
+// platform.
+fn diverge((unix, windows): &(Regex, Regex), path: String) -> Option<(String, PathKind)> {
+    let is_windows_match = windows.is_match(&path.replace('/', "\\"));
+    match (unix.is_match(&path), is_windows_match) {
+        (true, false) => Some((path, PathKind::Unix)),
+        (false, true) => Some((path, PathKind::Windows)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, PathKind};
+
+    #[test]
+    fn check_portable_glob() {
+        let is_portable = |expression| Glob::new(expression).unwrap().check_portability().is_ok();
+
+        assert!(is_portable("**/*.[0-9]"));
+        assert!(is_portable("(?i)README.md"));
+        assert!(is_portable("(?-i)src/README.md"));
+        assert!(is_portable("{0,1}/[0-9]/*"));
+
+        // Flags are retained when a glob is partitioned.
+        let (_, glob) = Glob::new("src/(?-i:lib/)(?i)*.RS").unwrap().partition();
+        assert!(glob.check_portability().is_ok());
+    }
+
+    #[test]
+    fn check_nonportable_glob() {
+        let divergence = |expression| {
+            Glob::new(expression)
+                .unwrap()
+                .check_portability()
+                .unwrap_err()
+        };
+
+        let error = divergence("README.md");
+        assert_eq!(error.witness(), Some("readme.md"));
+        assert_eq!(error.matching_platform(), Some(PathKind::Windows));
+
+        let error = divergence("docs/**/*.{md,txt}");
+        assert_eq!(error.witness(), Some("DOCS/.MD"));
+
+        // Both platforms match the same paths, but this is not detected.
+        let error = divergence("{a,A}");
+        assert!(error.witness().is_none());
+    }
+}
@@ -0,0 +1,331 @@
+use regex::RegexSet;
+use std::cmp::Reverse;
+
+use crate::capture::MatchedText;
+use crate::encode::{self, LazyProgram};
+use crate::token::{self, TokenTree};
+use crate::{CandidatePath, Glob};
+
+#[derive(Clone, Debug)]
+struct Route<'t, T> {
+    glob: Glob<'t>,
+    value: T,
+    // The number of characters in the invariant prefix of the glob.
+    specificity: usize,
+    priority: i32,
+    order: u64,
+}
+
+impl<'t, T> Route<'t, T> {
+    // Gets the key by which routes are ordered. Routes that are ordered first
+    // take precedence.
+    fn key(&self) -> (Reverse<usize>, Reverse<i32>, u64) {
+        (
+            Reverse(self.specificity),
+            Reverse(self.priority),
+            self.order,
+        )
+    }
+}
+
+/// Map from [`Glob`]s to values that routes paths to the most specific match.
+///
+/// `GlobRouter` associates each [`Glob`] with a value and resolves a path to the
+/// value of the most specific [`Glob`] that matches it, along with the
+/// [matched text][`MatchedText`] of that [`Glob`]. This is the lookup behind
+/// path-based routing in servers, asset pipelines, and configuration that
+/// applies settings by path.
+///
+/// When more than one [`Glob`] matches a path, the [`Glob`] with the longest
+/// invariant prefix is the most specific. For example, `src/lib.rs` is more
+/// specific than `src/*.rs`, which is more specific than `**/*.rs`. Ties are
+/// broken by priority, which is zero unless given via
+/// [`GlobRouter::insert_with_priority`], and then by insertion: the [`Glob`]
+/// with the greatest priority and then the [`Glob`] inserted first is chosen.
+/// See [`Glob::partition`] for more about invariant prefixes.
+///
+/// All [`Glob`]s are matched in a single pass. The [`Glob`]s in a router are
+/// compiled together when a path is first routed after an insertion, so
+/// building a router is cheap but the first call to [`GlobRouter::route`]
+/// after any insertion is not.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, GlobRouter};
+///
+/// let mut router = GlobRouter::new();
+/// router.insert(Glob::new("**/*.{md,txt}").unwrap(), "text");
+/// router.insert(Glob::new("doc/{*}.md").unwrap(), "doc");
+///
+/// let routed = router.route("doc/guide.md").unwrap();
+/// assert_eq!(*routed.value(), "doc");
+/// assert_eq!(routed.matched().get(1), Some("guide"));
+/// assert_eq!(*router.route("README.md").unwrap().value(), "text");
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::partition`]: crate::Glob::partition
+/// [`GlobRouter::insert_with_priority`]: crate::GlobRouter::insert_with_priority
+/// [`GlobRouter::route`]: crate::GlobRouter::route
+/// [`MatchedText`]: crate::MatchedText
+#[derive(Clone, Debug)]
+pub struct GlobRouter<'t, T> {
+    // Routes in order of precedence.
+    routes: Vec<Route<'t, T>>,
+    // Program set of the routes in order of precedence. This is `None` if the
+    // set is too large to compile, in which case routes are matched one by one.
+    set: LazyProgram<Option<RegexSet>>,
+    next: u64,
+}
+
+impl<'t, T> GlobRouter<'t, T> {
+    /// Constructs an empty `GlobRouter`.
+    pub fn new() -> Self {
+        GlobRouter {
+            routes: vec![],
+            set: LazyProgram::default(),
+            next: 0,
+        }
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> GlobRouter<'static, T> {
+        let GlobRouter { routes, set, next } = self;
+        GlobRouter {
+            routes: routes
+                .into_iter()
+                .map(
+                    |Route {
+                         glob,
+                         value,
+                         specificity,
+                         priority,
+                         order,
+                     }| Route {
+                        glob: glob.into_owned(),
+                        value,
+                        specificity,
+                        priority,
+                        order,
+                    },
+                )
+                .collect(),
+            set,
+            next,
+        }
+    }
+
+    /// Inserts a [`Glob`] and its value into the router.
+    ///
+    /// This function is the same as [`GlobRouter::insert_with_priority`] with
+    /// a priority of zero.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`GlobRouter::insert_with_priority`]: crate::GlobRouter::insert_with_priority
+    pub fn insert(&mut self, glob: Glob<'t>, value: T) {
+        self.insert_with_priority(glob, value, 0)
+    }
+
+    /// Inserts a [`Glob`] and its value into the router with the given
+    /// priority.
+    ///
+    /// Priority only breaks ties between [`Glob`]s with invariant prefixes of
+    /// the same length: among these, the [`Glob`] with the greatest priority
+    /// is chosen. Priority does not override specificity.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn insert_with_priority(&mut self, glob: Glob<'t>, value: T, priority: i32) {
+        let route = Route {
+            specificity: token::invariant_text_prefix(glob.tree.as_ref().tokens())
+                .chars()
+                .count(),
+            glob,
+            value,
+            priority,
+            order: self.next,
+        };
+        let index = self
+            .routes
+            .partition_point(|other| other.key() < route.key());
+        self.routes.insert(index, route);
+        self.set.invalidate();
+        self.next += 1;
+    }
+
+    /// Routes a path to the most specific [`Glob`] that matches it.
+    ///
+    /// Returns `None` if no [`Glob`] in the router matches the path.
+    ///
+    /// The [`Glob`]s in the router are compiled if any have been inserted
+    /// since the last call to this function.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn route<'p>(&self, path: impl Into<CandidatePath<'p>>) -> Option<Routed<'_, 't, T>> {
+        let path = path.into();
+        let set = self.set.get_or_compile(|| {
+            encode::compile_set(self.routes.iter().map(|route| &route.glob.pattern)).ok()
+        });
+        let route = match set.as_ref() {
+            Some(set) => &self.routes[set.matches(path.as_ref()).into_iter().next()?],
+            // The set is too large to compile, so match routes in order of
+            // precedence instead.
+            None => self
+                .routes
+                .iter()
+                .find(|route| route.glob.pattern.is_match(path.as_ref()))?,
+        };
+        let matched = route
+            .glob
+            .pattern
+            .captures(path.as_ref())
+            .map(MatchedText::from)
+            .expect("routed glob does not match path")
+            .into_owned();
+        Some(Routed {
+            glob: &route.glob,
+            value: &route.value,
+            matched,
+        })
+    }
+
+    /// Gets an iterator over the [`Glob`]s and their values in order of
+    /// precedence.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn iter(&self) -> impl '_ + Iterator<Item = (&Glob<'t>, &T)> {
+        self.routes.iter().map(|route| (&route.glob, &route.value))
+    }
+
+    /// Gets the number of [`Glob`]s in the router.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Returns `true` if the router has no [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+}
+
+impl<'t, T> Default for GlobRouter<'t, T> {
+    fn default() -> Self {
+        GlobRouter::new()
+    }
+}
+
+/// The [`Glob`] and value in a [`GlobRouter`] to which a path is routed.
+///
+/// See [`GlobRouter::route`].
+///
+/// [`Glob`]: crate::Glob
+/// [`GlobRouter`]: crate::GlobRouter
+/// [`GlobRouter::route`]: crate::GlobRouter::route
+#[derive(Debug)]
+pub struct Routed<'r, 't, T> {
+    glob: &'r Glob<'t>,
+    value: &'r T,
+    matched: MatchedText<'static>,
+}
+
+impl<'r, 't, T> Routed<'r, 't, T> {
+    /// Gets the [`Glob`] that matched the path.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn glob(&self) -> &'r Glob<'t> {
+        self.glob
+    }
+
+    /// Gets the value of the [`Glob`] that matched the path.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn value(&self) -> &'r T {
+        self.value
+    }
+
+    /// Gets the [matched text][`MatchedText`] of the path.
+    ///
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched(&self) -> &MatchedText<'static> {
+        &self.matched
+    }
+
+    /// Converts into the [matched text][`MatchedText`] of the path.
+    ///
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn into_matched(self) -> MatchedText<'static> {
+        self.matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, GlobRouter};
+
+    fn router(routes: &[(&'static str, i32)]) -> GlobRouter<'static, usize> {
+        let mut router = GlobRouter::new();
+        for (n, (expression, priority)) in routes.iter().enumerate() {
+            router.insert_with_priority(Glob::new(expression).unwrap(), n, *priority);
+        }
+        router
+    }
+
+    fn route(router: &GlobRouter<'_, usize>, path: &str) -> Option<usize> {
+        router.route(path).map(|routed| *routed.value())
+    }
+
+    #[test]
+    fn route_to_longest_invariant_prefix() {
+        let router = router(&[
+            ("**/*.rs", 0),
+            ("src/**/*.rs", 0),
+            ("src/lib.rs", 0),
+            ("src/token/*.rs", 0),
+        ]);
+        assert_eq!(route(&router, "src/lib.rs"), Some(2));
+        assert_eq!(route(&router, "src/token/parse.rs"), Some(3));
+        assert_eq!(route(&router, "src/walk/mod.rs"), Some(1));
+        assert_eq!(route(&router, "tests/walk.rs"), Some(0));
+        assert_eq!(route(&router, "README.md"), None);
+    }
+
+    #[test]
+    fn route_ties_by_priority_then_insertion() {
+        let router = router(&[("src/*.rs", 0), ("src/lib.*", 0), ("src/*", 1)]);
+        assert_eq!(route(&router, "src/lib.rs"), Some(2));
+        assert_eq!(route(&router, "src/main.rs"), Some(2));
+
+        let mut router = GlobRouter::default();
+        router.insert(Glob::new("src/*.rs").unwrap(), 0);
+        router.insert(Glob::new("src/lib.*").unwrap(), 1);
+        assert_eq!(route(&router, "src/lib.rs"), Some(0));
+        assert_eq!(route(&router, "src/lib.toml"), Some(1));
+        assert_eq!(
+            router.iter().map(|(_, value)| *value).collect::<Vec<_>>(),
+            [0, 1],
+        );
+    }
+
+    #[test]
+    fn route_after_insert_into_routed_router() {
+        let mut router = router(&[("**/*.rs", 0)]);
+        assert_eq!(route(&router, "src/lib.rs"), Some(0));
+        router.insert(Glob::new("src/*.rs").unwrap(), 1);
+        assert_eq!(route(&router, "src/lib.rs"), Some(1));
+        assert_eq!(route(&router, "tests/walk.rs"), Some(0));
+    }
+
+    #[test]
+    fn route_with_captures() {
+        let router = router(&[("**/{*}.md", 0), ("doc/<*/>{*}.md", 0)]);
+        let routed = router.route("doc/guide/intro.md").unwrap();
+        assert_eq!(*routed.value(), 1);
+        assert_eq!(routed.glob().to_string(), "doc/<*/>{*}.md");
+        assert_eq!(routed.matched().complete(), "doc/guide/intro.md");
+        assert_eq!(routed.matched().get(2), Some("intro"));
+    }
+}
@@ -6,13 +6,12 @@
 //! detect and reject token sequences that produce anomalous, meaningless, or
 //! unexpected globs (regular expressions) when compiled.
 //!
+//! Rules are checked exhaustively: all violations in a token sequence are
+//! reported in a single error rather than only the first.
+//!
 //! Most rules concern alternatives, which have complex interactions with
 //! neighboring tokens.
 
-// TODO: The `check` function fails fast and either report no errors or exactly
-//       one error. To better support diagnostics, `check` should probably
-//       perform an exhaustive analysis and report zero or more errors.
-
 use itertools::Itertools as _;
 #[cfg(feature = "miette")]
 use miette::{Diagnostic, LabeledSpan, SourceCode};
@@ -22,12 +21,13 @@ use std::convert::Infallible;
 use std::fmt::Display;
 use std::iter::Fuse;
 use std::path::PathBuf;
-use std::slice;
+use std::rc::Rc;
+use std::sync::Arc;
 use thiserror::Error;
 
-use crate::diagnostics::{CompositeSpan, CorrelatedSpan, SpanExt as _};
-use crate::token::{self, InvariantSize, Token, TokenKind, TokenTree, Tokenized};
-use crate::{Any, BuildError, Compose, Glob};
+use crate::diagnostics::{CompositeSpan, CorrelatedSpan, Span, SpanExt as _};
+use crate::token::{self, Composition, InvariantSize, Token, TokenKind, TokenTree, Tokenized};
+use crate::{Any, BuildError, Compose, Filter, Glob, Interner};
 
 /// Maximum invariant size.
 ///
@@ -178,12 +178,23 @@ impl<T> Terminals<T> {
 /// These rules are designed to avoid nonsense glob expressions and ambiguity.
 /// If a glob expression parses but violates these rules or is otherwise
 /// malformed, then this error is returned by some APIs.
+///
+/// A `RuleError` describes **all** of the rules that are violated by a glob
+/// expression. The first violation is described by the error itself and any
+/// other violations are [related diagnostics][`Diagnostic::related`] when the
+/// `miette` feature is enabled.
+///
+/// [`Diagnostic::related`]: miette::Diagnostic::related
 #[derive(Debug, Error)]
-#[error("malformed glob expression: {kind}")]
+#[error("malformed glob expression: {kind}{}", others(.related))]
 pub struct RuleError<'t> {
     expression: Cow<'t, str>,
     kind: RuleErrorKind,
     location: CompositeSpan,
+    // Violations other than the first. Each related error has exactly one
+    // location and no related errors of its own. This is boxed to limit the
+    // size of `BuildError`.
+    related: Box<[RuleError<'t>]>,
 }
 
 impl<'t> RuleError<'t> {
@@ -192,6 +203,17 @@ impl<'t> RuleError<'t> {
             expression,
             kind,
             location,
+            related: Box::new([]),
+        }
+    }
+
+    fn with_related<I>(self, related: I) -> Self
+    where
+        I: IntoIterator<Item = RuleError<'t>>,
+    {
+        RuleError {
+            related: self.related.into_vec().into_iter().chain(related).collect(),
+            ..self
         }
     }
 
@@ -201,16 +223,49 @@ impl<'t> RuleError<'t> {
             expression,
             kind,
             location,
+            related,
         } = self;
         RuleError {
             expression: expression.into_owned().into(),
             kind,
             location,
+            related: related
+                .into_vec()
+                .into_iter()
+                .map(RuleError::into_owned)
+                .collect(),
         }
     }
 
-    pub fn locations(&self) -> &[CompositeSpan] {
-        slice::from_ref(&self.location)
+    // Replaces the glob expression and maps the spans of locations into it.
+    pub(crate) fn map_spans(
+        self,
+        expression: &str,
+        f: &impl Fn(Span) -> Span,
+    ) -> RuleError<'static> {
+        let RuleError {
+            kind,
+            location,
+            related,
+            ..
+        } = self;
+        RuleError {
+            expression: expression.to_owned().into(),
+            kind,
+            location: location.map_spans(f),
+            related: related
+                .into_vec()
+                .into_iter()
+                .map(|error| error.map_spans(expression, f))
+                .collect(),
+        }
+    }
+
+    /// Gets the locations of all rule violations in the glob expression.
+    pub fn locations(&self) -> impl '_ + Iterator<Item = &CompositeSpan> {
+        Some(&self.location)
+            .into_iter()
+            .chain(self.related.iter().map(|error| &error.location))
     }
 
     /// Gets the glob expression that violated pattern rules.
@@ -251,6 +306,11 @@ impl Diagnostic for RuleError<'_> {
     fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan>>> {
         Some(Box::new(self.location.labels().into_iter()))
     }
+
+    fn related<'a>(&'a self) -> Option<Box<dyn 'a + Iterator<Item = &'a dyn Diagnostic>>> {
+        (!self.related.is_empty())
+            .then(|| Box::new(self.related.iter().map(|error| error as &dyn Diagnostic)) as Box<_>)
+    }
 }
 
 #[derive(Clone, Debug, Error)]
@@ -272,6 +332,15 @@ enum RuleErrorKind {
     IncompatibleBounds,
 }
 
+// Describes violations beyond the first in the display of a `RuleError`.
+fn others(related: &[RuleError<'_>]) -> String {
+    match related.len() {
+        0 => String::new(),
+        1 => String::from(" (and 1 other violation)"),
+        n => format!(" (and {} other violations)", n),
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub struct Checked<T> {
     inner: T,
@@ -297,31 +366,71 @@ where
     type Error = Infallible;
 }
 
-impl<'t> Checked<Token<'t, ()>> {
+impl<'t> Checked<Composition<'t>> {
+    // Gets the token trees of the composed patterns. Only checked trees are
+    // composed, so these trees are also checked.
+    pub fn members(&self) -> impl '_ + ExactSizeIterator<Item = Checked<Tokenized<'t>>> {
+        self.inner
+            .members()
+            .iter()
+            .cloned()
+            .map(|inner| Checked { inner })
+    }
+
     pub fn any<T, I>(tokens: I) -> Self
     where
         T: TokenTree<'t>,
         I: IntoIterator<Item = Checked<T>>,
     {
         Checked {
-            // `token::any` composes the input tokens into an alternative. The
-            // alternative is not checked, but the `any` combinator is
+            // `token::compose` composes the input tokens into an alternative.
+            // The alternative is not checked, but the `any` combinator is
             // explicitly allowed to ignore the subset of rules that may be
             // violated by this construction. In particular, branches may or may
             // not have roots such that the alternative can match overlapping
-            // trees.
-            inner: token::any(
+            // trees. Composed inputs are flattened, so each branch is a single
+            // glob expression.
+            inner: token::compose(
                 tokens
                     .into_iter()
                     .map(Checked::release)
-                    .map(TokenTree::into_tokens),
+                    .flat_map(TokenTree::into_members),
             ),
         }
     }
-}
 
-impl<'t, A> Checked<Token<'t, A>> {
-    pub fn into_owned(self) -> Checked<Token<'static, A>> {
+    // Composes trees into an alternative in which each branch excludes any text
+    // that is matched by the excluded trees.
+    pub fn any_except<I, E>(include: I, exclude: E) -> Self
+    where
+        I: IntoIterator<Item = Checked<Tokenized<'t>>>,
+        E: IntoIterator<Item = Checked<Tokenized<'t>>>,
+    {
+        let exclude: Vec<_> = exclude
+            .into_iter()
+            .map(|tree| tree.release().into_tokens())
+            .collect();
+        let exclusion = if exclude.is_empty() {
+            None
+        }
+        else {
+            Some(token::any(exclude))
+        };
+        Checked {
+            // As with `Checked::any`, the alternative is not checked. Exclusions
+            // are not composed into the alternative, so they cannot violate
+            // rules.
+            inner: token::compose(
+                include
+                    .into_iter()
+                    .map(Checked::release)
+                    .flat_map(TokenTree::into_members)
+                    .map(|(tree, _)| (tree, exclusion.clone())),
+            ),
+        }
+    }
+
+    pub fn into_owned(self) -> Checked<Composition<'static>> {
         Checked {
             inner: self.release().into_owned(),
         }
@@ -343,15 +452,30 @@ impl<'t, A> Checked<Tokenized<'t, A>> {
             inner: self.release().into_owned(),
         }
     }
+
+    pub fn intern<'i>(self, interner: &'i Interner) -> Checked<Tokenized<'i, A>>
+    where
+        't: 'i,
+    {
+        Checked {
+            inner: self.release().intern(interner),
+        }
+    }
 }
 
-impl<'t> From<Any<'t>> for Checked<Token<'t, ()>> {
+impl<'t> From<Any<'t>> for Checked<Composition<'t>> {
     fn from(any: Any<'t>) -> Self {
         let Any { tree, .. } = any;
         tree
     }
 }
 
+impl<'t> From<Filter<'t>> for Checked<Composition<'t>> {
+    fn from(filter: Filter<'t>) -> Self {
+        filter.tree
+    }
+}
+
 impl<'t> From<Glob<'t>> for Checked<Tokenized<'t>> {
     fn from(glob: Glob<'t>) -> Self {
         let Glob { tree, .. } = glob;
@@ -359,6 +483,98 @@ impl<'t> From<Glob<'t>> for Checked<Tokenized<'t>> {
     }
 }
 
+// Borrows the checked token tree of a compiled pattern. This allows shared and
+// borrowed patterns like `&Glob` and `Arc<Any>` to be composed by cloning only
+// their token trees.
+pub trait AsChecked: Sized {
+    type Tokens: Clone;
+
+    fn as_checked(&self) -> &Checked<Self::Tokens>;
+
+    fn into_checked(self) -> Checked<Self::Tokens>;
+}
+
+impl<'t> AsChecked for Any<'t> {
+    type Tokens = Composition<'t>;
+
+    fn as_checked(&self) -> &Checked<Self::Tokens> {
+        &self.tree
+    }
+
+    fn into_checked(self) -> Checked<Self::Tokens> {
+        self.tree
+    }
+}
+
+impl<'t> AsChecked for Filter<'t> {
+    type Tokens = Composition<'t>;
+
+    fn as_checked(&self) -> &Checked<Self::Tokens> {
+        &self.tree
+    }
+
+    fn into_checked(self) -> Checked<Self::Tokens> {
+        self.tree
+    }
+}
+
+impl<'t> AsChecked for Glob<'t> {
+    type Tokens = Tokenized<'t>;
+
+    fn as_checked(&self) -> &Checked<Self::Tokens> {
+        &self.tree
+    }
+
+    fn into_checked(self) -> Checked<Self::Tokens> {
+        self.tree
+    }
+}
+
+impl<'p, P> From<&'p P> for Checked<P::Tokens>
+where
+    P: AsChecked,
+{
+    fn from(pattern: &'p P) -> Self {
+        pattern.as_checked().clone()
+    }
+}
+
+impl<P> From<Arc<P>> for Checked<P::Tokens>
+where
+    P: AsChecked,
+{
+    fn from(pattern: Arc<P>) -> Self {
+        Arc::try_unwrap(pattern).map_or_else(
+            |pattern| pattern.as_checked().clone(),
+            AsChecked::into_checked,
+        )
+    }
+}
+
+impl<P> From<Rc<P>> for Checked<P::Tokens>
+where
+    P: AsChecked,
+{
+    fn from(pattern: Rc<P>) -> Self {
+        Rc::try_unwrap(pattern).map_or_else(
+            |pattern| pattern.as_checked().clone(),
+            AsChecked::into_checked,
+        )
+    }
+}
+
+impl<'p, P> From<Cow<'p, P>> for Checked<P::Tokens>
+where
+    P: AsChecked + Clone,
+{
+    fn from(pattern: Cow<'p, P>) -> Self {
+        match pattern {
+            Cow::Borrowed(pattern) => pattern.as_checked().clone(),
+            Cow::Owned(pattern) => pattern.into_checked(),
+        }
+    }
+}
+
 impl<'t> TryFrom<&'t str> for Checked<Tokenized<'t>> {
     type Error = BuildError;
 
@@ -367,16 +583,29 @@ impl<'t> TryFrom<&'t str> for Checked<Tokenized<'t>> {
     }
 }
 
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        level = "trace",
+        skip_all,
+        fields(expression = %tokenized.expression()),
+        err,
+    )
+)]
 pub fn check(tokenized: Tokenized) -> Result<Checked<Tokenized>, RuleError> {
-    boundary(&tokenized)?;
-    bounds(&tokenized)?;
-    group(&tokenized)?;
-    size(&tokenized)?;
-    Ok(Checked { inner: tokenized })
+    let mut errors = boundary(&tokenized)
+        .into_iter()
+        .chain(bounds(&tokenized))
+        .chain(group(&tokenized))
+        .chain(size(&tokenized));
+    match errors.next() {
+        Some(error) => Err(error.with_related(errors)),
+        _ => Ok(Checked { inner: tokenized }),
+    }
 }
 
-fn boundary<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
-    if let Some((left, right)) = tokenized
+fn boundary<'t>(tokenized: &Tokenized<'t>) -> Vec<RuleError<'t>> {
+    tokenized
         .walk()
         .group_by(|(position, _)| *position)
         .into_iter()
@@ -387,22 +616,28 @@ fn boundary<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
                 .filter(|(left, right)| {
                     left.is_component_boundary() && right.is_component_boundary()
                 })
-                .map(|(left, right)| (*left.annotation(), *right.annotation()))
+                .map(|(left, right)| left.annotation().union(right.annotation()))
+                // Runs of adjacent boundaries are reported as one violation.
+                .coalesce(|left, right| {
+                    if left.0 + left.1 > right.0 {
+                        Ok(left.union(&right))
+                    }
+                    else {
+                        Err((left, right))
+                    }
+                })
         })
-        .next()
-    {
-        Err(RuleError::new(
-            tokenized.expression().clone(),
-            RuleErrorKind::AdjacentBoundary,
-            CompositeSpan::spanned("here", left.union(&right)),
-        ))
-    }
-    else {
-        Ok(())
-    }
+        .map(|span| {
+            RuleError::new(
+                tokenized.expression().clone(),
+                RuleErrorKind::AdjacentBoundary,
+                CompositeSpan::spanned("here", span),
+            )
+        })
+        .collect()
 }
 
-fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
+fn group<'t>(tokenized: &Tokenized<'t>) -> Vec<RuleError<'t>> {
     use crate::token::TokenKind::{Separator, Wildcard};
     use crate::token::Wildcard::{Tree, ZeroOrMore};
     use Terminals::{Only, StartEnd};
@@ -462,7 +697,7 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
             token
                 .walk()
                 .starting()
-                .any(|(_, token)| matches!(token.kind(), Wildcard(ZeroOrMore(_))))
+                .any(|(_, token)| matches!(token.kind(), Wildcard(ZeroOrMore { .. })))
         })
     }
 
@@ -471,7 +706,7 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
             token
                 .walk()
                 .ending()
-                .any(|(_, token)| matches!(token.kind(), Wildcard(ZeroOrMore(_))))
+                .any(|(_, token)| matches!(token.kind(), Wildcard(ZeroOrMore { .. })))
         })
     }
 
@@ -500,8 +735,8 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
         #[allow(clippy::ptr_arg)] expression: &Cow<'t, str>,
         tokens: I,
         outer: Outer<'i, 't>,
-    ) -> Result<(), RuleError<'t>>
-    where
+        errors: &mut Vec<RuleError<'t>>,
+    ) where
         I: IntoIterator<Item = &'i Token<'t>>,
         't: 'i,
     {
@@ -512,10 +747,22 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
                     let diagnose = diagnose(expression, token, "in this alternative");
                     for tokens in alternative.branches() {
                         if let Some(terminals) = tokens.terminals() {
-                            check_group(terminals, outer).map_err(diagnose)?;
-                            check_group_alternative(terminals, outer).map_err(diagnose)?;
+                            // At most one violation is reported for each branch.
+                            errors.extend(
+                                check_group(terminals, outer)
+                                    .and_then(|_| check_group_alternative(terminals, outer))
+                                    .err()
+                                    .map(diagnose),
+                            );
                         }
-                        recurse(expression, tokens.iter(), outer)?;
+                        else {
+                            errors.extend(
+                                check_empty_alternative_branch(token, outer)
+                                    .err()
+                                    .map(diagnose),
+                            );
+                        }
+                        recurse(expression, tokens.iter(), outer, errors);
                     }
                 },
                 TokenKind::Repetition(ref repetition) => {
@@ -523,16 +770,20 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
                     let diagnose = diagnose(expression, token, "in this repetition");
                     let tokens = repetition.tokens();
                     if let Some(terminals) = tokens.terminals() {
-                        check_group(terminals, outer).map_err(diagnose)?;
-                        check_group_repetition(terminals, outer, repetition.bounds())
-                            .map_err(diagnose)?;
+                        errors.extend(
+                            check_group(terminals, outer)
+                                .and_then(|_| {
+                                    check_group_repetition(terminals, outer, repetition.bounds())
+                                })
+                                .err()
+                                .map(diagnose),
+                        );
                     }
-                    recurse(expression, tokens.iter(), outer)?;
+                    recurse(expression, tokens.iter(), outer, errors);
                 },
                 _ => {},
             }
         }
-        Ok(())
     }
 
     fn check_group<'t>(
@@ -603,8 +854,8 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
             // zero-or-more tokens.
             //
             // For example, `foo*{bar,*,baz}`.
-            Only((inner, Wildcard(ZeroOrMore(_))))
-            | StartEnd((inner, Wildcard(ZeroOrMore(_))), _)
+            Only((inner, Wildcard(ZeroOrMore { .. })))
+            | StartEnd((inner, Wildcard(ZeroOrMore { .. })), _)
                 if has_ending_zom_token(left) =>
             {
                 Err(CorrelatedError::new(
@@ -617,8 +868,8 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
             // zero-or-more tokens.
             //
             // For example, `{foo,*,bar}*baz`.
-            Only((inner, Wildcard(ZeroOrMore(_))))
-            | StartEnd(_, (inner, Wildcard(ZeroOrMore(_))))
+            Only((inner, Wildcard(ZeroOrMore { .. })))
+            | StartEnd(_, (inner, Wildcard(ZeroOrMore { .. })))
                 if has_starting_zom_token(right) =>
             {
                 Err(CorrelatedError::new(
@@ -666,6 +917,39 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
         }
     }
 
+    fn check_empty_alternative_branch<'t>(
+        alternative: &Token,
+        outer: Outer<'t, 't>,
+    ) -> Result<(), CorrelatedError> {
+        let Outer { left, right } = outer;
+        // The alternative is preceded by a termination and followed by
+        // component boundaries; disallow empty branches, which match rooted
+        // paths.
+        //
+        // For example, `{foo,}/bar`.
+        if left.is_none() && has_starting_component_boundary(right) {
+            Err(CorrelatedError::new(
+                RuleErrorKind::RootedSubGlob,
+                right,
+                alternative,
+            ))
+        }
+        // The alternative is preceded and followed by component boundaries;
+        // disallow empty branches, which match adjacent boundaries.
+        //
+        // For example, `foo/{bar,}/baz`.
+        else if has_ending_component_boundary(left) && has_starting_component_boundary(right) {
+            Err(CorrelatedError::new(
+                RuleErrorKind::AdjacentBoundary,
+                left,
+                alternative,
+            ))
+        }
+        else {
+            Ok(())
+        }
+    }
+
     fn check_group_repetition<'t>(
         terminals: Terminals<&Token>,
         outer: Outer<'t, 't>,
@@ -724,7 +1008,7 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
             // The repetition is a singular zero-or-more wildcard.
             //
             // For example, `<*:1,>`.
-            Only((token, Wildcard(ZeroOrMore(_)))) => Err(CorrelatedError::new(
+            Only((token, Wildcard(ZeroOrMore { .. }))) => Err(CorrelatedError::new(
                 RuleErrorKind::SingularZeroOrMore,
                 None,
                 token,
@@ -733,51 +1017,57 @@ fn group<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
         }
     }
 
-    recurse(tokenized.expression(), tokenized.tokens(), Outer::default())
+    let mut errors = vec![];
+    recurse(
+        tokenized.expression(),
+        tokenized.tokens(),
+        Outer::default(),
+        &mut errors,
+    );
+    errors
 }
 
-fn bounds<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
-    if let Some((_, token)) = tokenized.walk().find(|(_, token)| match token.kind() {
-        TokenKind::Repetition(ref repetition) => {
-            let (lower, upper) = repetition.bounds();
-            upper.map_or(false, |upper| upper < lower || upper == 0)
-        },
-        _ => false,
-    }) {
-        Err(RuleError::new(
-            tokenized.expression().clone(),
-            RuleErrorKind::IncompatibleBounds,
-            CompositeSpan::spanned("here", *token.annotation()),
-        ))
-    }
-    else {
-        Ok(())
-    }
+fn bounds<'t>(tokenized: &Tokenized<'t>) -> Vec<RuleError<'t>> {
+    tokenized
+        .walk()
+        .filter(|(_, token)| match token.kind() {
+            TokenKind::Repetition(ref repetition) => {
+                let (lower, upper) = repetition.bounds();
+                upper.map_or(false, |upper| upper < lower || upper == 0)
+            },
+            _ => false,
+        })
+        .map(|(_, token)| {
+            RuleError::new(
+                tokenized.expression().clone(),
+                RuleErrorKind::IncompatibleBounds,
+                CompositeSpan::spanned("here", *token.annotation()),
+            )
+        })
+        .collect()
 }
 
-fn size<'t>(tokenized: &Tokenized<'t>) -> Result<(), RuleError<'t>> {
-    if let Some((_, token)) = tokenized
+fn size<'t>(tokenized: &Tokenized<'t>) -> Vec<RuleError<'t>> {
+    tokenized
         .walk()
         // TODO: This is expensive. For each token tree encountered, the
         //       tree is traversed to determine its variance. If variant,
         //       the tree is traversed and queried again, revisiting the
         //       same tokens to recompute their local variance.
-        .find(|(_, token)| {
+        .filter(|(_, token)| {
             token
                 .variance::<InvariantSize>()
                 .as_invariance()
                 .map_or(false, |size| *size >= MAX_INVARIANT_SIZE)
         })
-    {
-        Err(RuleError::new(
-            tokenized.expression().clone(),
-            RuleErrorKind::OversizedInvariant,
-            CompositeSpan::spanned("here", *token.annotation()),
-        ))
-    }
-    else {
-        Ok(())
-    }
+        .map(|(_, token)| {
+            RuleError::new(
+                tokenized.expression().clone(),
+                RuleErrorKind::OversizedInvariant,
+                CompositeSpan::spanned("here", *token.annotation()),
+            )
+        })
+        .collect()
 }
 
 #[cfg(test)]
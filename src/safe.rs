@@ -0,0 +1,134 @@
+#[cfg(feature = "miette")]
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+#[cfg(feature = "miette")]
+use std::fmt::Display;
+use std::path::{Component, Path, PathBuf};
+use thiserror::Error;
+
+use crate::anchor;
+use crate::diagnostics::{LocatedError, Span};
+use crate::token::{self, TokenTree as _, Tokenized};
+
+/// Describes errors that occur when the invariant prefix of a [`Glob`] escapes
+/// a directory.
+///
+/// This error occurs if a [`Glob`] is rooted or if its invariant prefix
+/// resolves outside of a directory via `..` components, such as in `/etc/**`
+/// or `../../etc/**`. See [`Glob::safe_root`].
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::safe_root`]: crate::Glob::safe_root
+#[derive(Clone, Debug, Error)]
+#[error("glob escapes directory `{}`: {kind}", directory.display())]
+pub struct EscapeError {
+    expression: String,
+    directory: PathBuf,
+    kind: EscapeErrorKind,
+    span: Span,
+}
+
+impl EscapeError {
+    fn new(expression: &str, directory: &Path, kind: EscapeErrorKind, span: Span) -> Self {
+        EscapeError {
+            expression: expression.to_owned(),
+            directory: directory.to_path_buf(),
+            kind,
+            span,
+        }
+    }
+
+    /// Gets the glob expression that escapes the directory.
+    pub fn expression(&self) -> &str {
+        self.expression.as_ref()
+    }
+
+    /// Gets the directory that the glob expression escapes.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// Returns `true` if the glob expression escapes the directory because it
+    /// is rooted.
+    pub fn is_rooted(&self) -> bool {
+        matches!(self.kind, EscapeErrorKind::Rooted)
+    }
+}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+impl Diagnostic for EscapeError {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        Some(Box::new(match self.kind {
+            EscapeErrorKind::Rooted => "wax::glob::rooted_prefix",
+            EscapeErrorKind::Parent => "wax::glob::escaping_prefix",
+        }))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.expression)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(
+            Some(LabeledSpan::new_with_span(
+                Some(format!("{}", self.kind)),
+                self.span,
+            ))
+            .into_iter(),
+        ))
+    }
+}
+
+impl LocatedError for EscapeError {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+enum EscapeErrorKind {
+    #[error("rooted glob is not relative to the directory")]
+    Rooted,
+    #[error("invariant prefix resolves outside of the directory")]
+    Parent,
+}
+
+// Joins the invariant prefix of a glob to a directory. Fails if the glob is
+// rooted or if the joined path is not beneath the directory when normalized
+// lexically.
+pub fn safe_root<'t>(
+    tree: &Tokenized<'t>,
+    is_rooted: bool,
+    directory: &Path,
+) -> Result<PathBuf, EscapeError> {
+    let expression = tree.expression();
+    let tokens = tree.tokens();
+    let prefix = PathBuf::from(token::invariant_text_prefix(tokens));
+    if is_rooted || prefix.has_root() {
+        return Err(EscapeError::new(
+            expression,
+            directory,
+            EscapeErrorKind::Rooted,
+            tokens.first().map_or((0, 0), |token| *token.annotation()),
+        ));
+    }
+    let directory = anchor::normalize(directory);
+    let root = anchor::normalize(&directory.join(prefix));
+    let is_beneath = root.strip_prefix(&directory).map_or(false, |path| {
+        path.components()
+            .all(|component| component != Component::ParentDir)
+    });
+    if is_beneath {
+        Ok(root)
+    }
+    else {
+        Err(EscapeError::new(
+            expression,
+            &directory,
+            EscapeErrorKind::Parent,
+            tree.invariant_prefix_span()
+                .expect("no invariant prefix in escaping glob"),
+        ))
+    }
+}
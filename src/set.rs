@@ -0,0 +1,270 @@
+use regex::RegexSet;
+use std::collections::{BTreeMap, HashMap};
+use std::path;
+
+use crate::encode;
+use crate::token::{self, TokenTree};
+use crate::{BuildError, CandidatePath, Glob};
+
+/// Identifier of a [`Glob`] in a [`GlobSet`].
+///
+/// Identifiers are assigned by [`GlobSet::insert`] and are never reused by the
+/// same [`GlobSet`].
+///
+/// [`Glob`]: crate::Glob
+/// [`GlobSet`]: crate::GlobSet
+/// [`GlobSet::insert`]: crate::GlobSet::insert
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct GlobId(u64);
+
+#[derive(Clone, Debug)]
+struct Shard<'t> {
+    globs: Vec<(GlobId, Glob<'t>)>,
+    set: RegexSet,
+}
+
+impl<'t> Shard<'t> {
+    fn compile(globs: Vec<(GlobId, Glob<'t>)>) -> Result<Self, BuildError> {
+        let set = encode::compile_set(globs.iter().map(|(_, glob)| &glob.pattern))?;
+        Ok(Shard { globs, set })
+    }
+}
+
+/// Set of [`Glob`]s that can be modified after it is built.
+///
+/// `GlobSet` matches paths against many [`Glob`]s like [`Any`], but [`Glob`]s
+/// can be inserted and removed at any time. [`Glob`]s are partitioned into
+/// shards by the literal text that begins any matching path and each shard is
+/// compiled separately. Inserting or removing a [`Glob`] only recompiles its
+/// shard, so modifying a large set is inexpensive when its [`Glob`]s are
+/// rooted in different directories. This is useful for long-running services
+/// with rules that change over time.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, GlobSet};
+///
+/// let mut set = GlobSet::new();
+/// let rs = set.insert(Glob::new("src/**/*.rs").unwrap()).unwrap();
+/// let md = set.insert(Glob::new("doc/*.md").unwrap()).unwrap();
+/// assert_eq!(set.matches("src/lib.rs"), [rs]);
+///
+/// set.remove(rs);
+/// assert!(!set.is_match("src/lib.rs"));
+/// assert_eq!(set.matches("doc/README.md"), [md]);
+/// ```
+///
+/// [`Any`]: crate::Any
+/// [`Glob`]: crate::Glob
+#[derive(Clone, Debug, Default)]
+pub struct GlobSet<'t> {
+    shards: BTreeMap<String, Shard<'t>>,
+    prefixes: HashMap<GlobId, String>,
+    next: u64,
+}
+
+impl<'t> GlobSet<'t> {
+    /// Constructs an empty `GlobSet`.
+    pub fn new() -> Self {
+        GlobSet::default()
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> GlobSet<'static> {
+        let GlobSet {
+            shards,
+            prefixes,
+            next,
+        } = self;
+        GlobSet {
+            shards: shards
+                .into_iter()
+                .map(|(prefix, Shard { globs, set })| {
+                    let globs = globs
+                        .into_iter()
+                        .map(|(id, glob)| (id, glob.into_owned()))
+                        .collect();
+                    (prefix, Shard { globs, set })
+                })
+                .collect(),
+            prefixes,
+            next,
+        }
+    }
+
+    /// Inserts a [`Glob`] into the set.
+    ///
+    /// Returns the identifier of the [`Glob`], which is used to remove it from
+    /// the set and is reported by [`GlobSet::matches`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the [`Glob`]s in the shard of the inserted [`Glob`]
+    /// fail to build. This only occurs if the compiled program is too large.
+    /// The set is not modified if an error occurs.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`GlobSet::matches`]: crate::GlobSet::matches
+    pub fn insert(&mut self, glob: Glob<'t>) -> Result<GlobId, BuildError> {
+        let id = GlobId(self.next);
+        let prefix = token::literal_text_prefix(glob.tree.as_ref().tokens());
+        let mut globs = self
+            .shards
+            .get(&prefix)
+            .map(|shard| shard.globs.clone())
+            .unwrap_or_default();
+        globs.push((id, glob));
+        // The shard is replaced only if it builds, so the set is not modified
+        // if an error occurs.
+        let shard = Shard::compile(globs)?;
+        self.shards.insert(prefix.clone(), shard);
+        self.prefixes.insert(id, prefix);
+        self.next += 1;
+        Ok(id)
+    }
+
+    /// Removes a [`Glob`] from the set.
+    ///
+    /// Returns the removed [`Glob`] or `None` if there is no [`Glob`] with the
+    /// given identifier in the set.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn remove(&mut self, id: GlobId) -> Option<Glob<'t>> {
+        let prefix = self.prefixes.remove(&id)?;
+        let mut globs = self
+            .shards
+            .remove(&prefix)
+            .expect("glob set has no shard for prefix")
+            .globs;
+        let index = globs
+            .iter()
+            .position(|(other, _)| *other == id)
+            .expect("glob set shard has no glob for identifier");
+        let (_, glob) = globs.remove(index);
+        if !globs.is_empty() {
+            // Removing a `Glob` cannot grow the compiled program of a shard.
+            let shard = Shard::compile(globs).expect("failed to recompile glob set shard");
+            self.shards.insert(prefix, shard);
+        }
+        Some(glob)
+    }
+
+    /// Gets the [`Glob`] with the given identifier, if any.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn get(&self, id: GlobId) -> Option<&Glob<'t>> {
+        let prefix = self.prefixes.get(&id)?;
+        self.shards[prefix]
+            .globs
+            .iter()
+            .find(|(other, _)| *other == id)
+            .map(|(_, glob)| glob)
+    }
+
+    /// Returns `true` if a path matches any [`Glob`] in the set.
+    pub fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
+        let path = path.into();
+        // The iterator borrows the path and so must be dropped before it.
+        let mut shards = self.shards(path.as_ref());
+        shards.any(|shard| shard.set.is_match(path.as_ref()))
+    }
+
+    /// Gets the identifiers of the [`Glob`]s in the set that match a path.
+    ///
+    /// Identifiers are ordered by insertion.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn matches<'p>(&self, path: impl Into<CandidatePath<'p>>) -> Vec<GlobId> {
+        let path = path.into();
+        let mut ids: Vec<_> = self
+            .shards(path.as_ref())
+            .flat_map(|shard| {
+                shard
+                    .set
+                    .matches(path.as_ref())
+                    .into_iter()
+                    .map(move |index| shard.globs[index].0)
+            })
+            .collect();
+        ids.sort_unstable();
+        ids
+    }
+
+    /// Gets the number of [`Glob`]s in the set.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn len(&self) -> usize {
+        self.prefixes.len()
+    }
+
+    /// Returns `true` if the set has no [`Glob`]s.
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_empty(&self) -> bool {
+        self.prefixes.is_empty()
+    }
+
+    // Gets the shards with a prefix that begins the given text. As with
+    // `PrefixDispatch`, separators in prefixes are always `/` and match any
+    // separator in text.
+    fn shards<'s>(&'s self, text: &'s str) -> impl 's + Iterator<Item = &'s Shard<'t>> {
+        let mut prefix = String::new();
+        let mut chars = text.chars();
+        let mut is_done = false;
+        std::iter::from_fn(move || {
+            while !is_done {
+                let shard = self.shards.get(&prefix);
+                match chars.next() {
+                    Some(x) => prefix.push(if path::is_separator(x) { '/' } else { x }),
+                    None => {
+                        is_done = true;
+                    },
+                }
+                if shard.is_some() {
+                    return shard;
+                }
+            }
+            None
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, GlobSet};
+
+    #[test]
+    fn insert_and_remove_globs() {
+        let mut set = GlobSet::new();
+        let a = set.insert(Glob::new("src/**/*.rs").unwrap()).unwrap();
+        let b = set.insert(Glob::new("src/*.toml").unwrap()).unwrap();
+        let c = set.insert(Glob::new("**/*.rs").unwrap()).unwrap();
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.matches("src/lib.rs"), [a, c]);
+        assert_eq!(set.matches("src/Cargo.toml"), [b]);
+        assert_eq!(set.matches("tests/walk.rs"), [c]);
+
+        assert_eq!(set.remove(a).unwrap().to_string(), "src/**/*.rs");
+        assert!(set.remove(a).is_none());
+        assert!(set.get(a).is_none());
+        assert_eq!(set.matches("src/lib.rs"), [c]);
+
+        set.remove(c);
+        assert!(!set.is_match("src/lib.rs"));
+        assert!(set.is_match("src/Cargo.toml"));
+        set.remove(b);
+        assert!(set.is_empty());
+        assert!(set.shards.is_empty());
+    }
+
+    #[test]
+    fn insert_glob_with_unique_identifier() {
+        let mut set = GlobSet::new();
+        let a = set.insert(Glob::new("a/*").unwrap()).unwrap();
+        set.remove(a);
+        let b = set.insert(Glob::new("a/*").unwrap()).unwrap();
+        assert_ne!(a, b);
+        assert_eq!(set.matches("a/b"), [b]);
+    }
+}
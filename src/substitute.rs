@@ -0,0 +1,164 @@
+#[cfg(feature = "miette")]
+use miette::{Diagnostic, LabeledSpan, SourceCode};
+#[cfg(feature = "miette")]
+use std::fmt::Display;
+use std::path::{self, PathBuf};
+use thiserror::Error;
+
+use crate::diagnostics::{LocatedError, Span};
+use crate::encode;
+use crate::token::{InvariantText, Token, TokenKind, Wildcard};
+
+/// Describes errors that occur when substituting values into a [`Glob`].
+///
+/// This error occurs if a capturing token with variant text has no value or if
+/// a value does not match its capturing token. See
+/// [`Glob::substitute`].
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::substitute`]: crate::Glob::substitute
+#[derive(Clone, Debug, Error)]
+#[error("failed to substitute glob: {kind}")]
+pub struct SubstitutionError {
+    expression: String,
+    kind: SubstitutionErrorKind,
+    span: Span,
+}
+
+impl SubstitutionError {
+    fn new(expression: &str, kind: SubstitutionErrorKind, span: Span) -> Self {
+        SubstitutionError {
+            expression: expression.to_owned(),
+            kind,
+            span,
+        }
+    }
+
+    /// Gets the glob expression into which values were substituted.
+    pub fn expression(&self) -> &str {
+        self.expression.as_ref()
+    }
+
+    /// Gets the index of the capture with a missing or mismatched value.
+    ///
+    /// Captures are indexed from one, as in [`MatchedText::get`].
+    ///
+    /// [`MatchedText::get`]: crate::MatchedText::get
+    pub fn index(&self) -> usize {
+        match self.kind {
+            SubstitutionErrorKind::Missing(index)
+            | SubstitutionErrorKind::Unsatisfied { index, .. } => index,
+        }
+    }
+}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+impl Diagnostic for SubstitutionError {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        Some(Box::new(match self.kind {
+            SubstitutionErrorKind::Missing(_) => "wax::glob::missing_value",
+            SubstitutionErrorKind::Unsatisfied { .. } => "wax::glob::unsatisfied_value",
+        }))
+    }
+
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        Some(&self.expression)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        Some(Box::new(
+            Some(LabeledSpan::new_with_span(
+                Some(format!("{}", self.kind)),
+                self.span,
+            ))
+            .into_iter(),
+        ))
+    }
+}
+
+impl LocatedError for SubstitutionError {
+    fn span(&self) -> Span {
+        self.span
+    }
+}
+
+#[derive(Clone, Debug, Error)]
+#[non_exhaustive]
+enum SubstitutionErrorKind {
+    #[error("no value for capture {0}")]
+    Missing(usize),
+    #[error("value `{value}` does not match capture {index}")]
+    Unsatisfied { index: usize, value: String },
+}
+
+// Builds a path from tokens by replacing capturing tokens with the values at
+// their capture indices. Capturing tokens with invariant text and tree
+// wildcards need no value.
+pub fn substitute<'t, V>(
+    expression: &str,
+    tokens: &[Token<'t>],
+    values: &[(usize, V)],
+) -> Result<PathBuf, SubstitutionError>
+where
+    V: AsRef<str>,
+{
+    let mut text = String::new();
+    let mut index = 0;
+    for (n, token) in tokens.iter().enumerate() {
+        match token.kind() {
+            TokenKind::Literal(ref literal) => text.push_str(literal.text()),
+            TokenKind::Separator(_) => text.push('/'),
+            kind => {
+                index += 1;
+                let value = values
+                    .iter()
+                    .rev()
+                    .find(|(other, _)| *other == index)
+                    .map(|(_, value)| value.as_ref());
+                let is_tree = matches!(kind, TokenKind::Wildcard(Wildcard::Tree { .. }));
+                let invariant = kind.variance::<InvariantText>();
+                let value = match (value, invariant.as_invariance()) {
+                    (Some(value), _) => value.to_owned(),
+                    (None, Some(invariant)) => invariant.to_string().into_owned(),
+                    // Tree wildcards match no components when they have no
+                    // value, as they have no capture in such matched text.
+                    (None, None) if is_tree => String::new(),
+                    (None, None) => {
+                        return Err(SubstitutionError::new(
+                            expression,
+                            SubstitutionErrorKind::Missing(index),
+                            *token.annotation(),
+                        ));
+                    },
+                };
+                if let TokenKind::Wildcard(Wildcard::Tree { has_root }) = kind {
+                    // Tree wildcards absorb their surrounding separators, so
+                    // these are pushed here rather than taken from the value.
+                    let value = value.trim_matches(path::is_separator);
+                    let is_followed = n + 1 < tokens.len();
+                    if *has_root && (!value.is_empty() || is_followed || text.is_empty()) {
+                        text.push('/');
+                    }
+                    text.push_str(value);
+                    if !value.is_empty() && is_followed {
+                        text.push('/');
+                    }
+                }
+                else {
+                    let program =
+                        encode::compile([token]).expect("failed to compile glob sub-expression");
+                    if !program.is_match(&value) {
+                        return Err(SubstitutionError::new(
+                            expression,
+                            SubstitutionErrorKind::Unsatisfied { index, value },
+                            *token.annotation(),
+                        ));
+                    }
+                    text.push_str(&value);
+                }
+            },
+        }
+    }
+    Ok(text.into())
+}
@@ -0,0 +1,171 @@
+#![cfg(feature = "swap")]
+
+use arc_swap::ArcSwap;
+use std::ops::Deref;
+use std::sync::{Arc, Mutex, PoisonError};
+
+/// Patterns loaded from [`SwappableGlobs`] along with their generation.
+///
+/// `GlobSnapshot` dereferences to its patterns. The patterns of a snapshot
+/// never change, even if the patterns of its [`SwappableGlobs`] are swapped
+/// while the snapshot is in use.
+///
+/// [`SwappableGlobs`]: crate::SwappableGlobs
+#[cfg_attr(docsrs, doc(cfg(feature = "swap")))]
+#[derive(Debug)]
+pub struct GlobSnapshot<P> {
+    generation: u64,
+    patterns: P,
+}
+
+impl<P> GlobSnapshot<P> {
+    /// Gets the generation of the patterns.
+    ///
+    /// The initial patterns of a [`SwappableGlobs`] are generation zero and
+    /// each swap increments the generation.
+    ///
+    /// [`SwappableGlobs`]: crate::SwappableGlobs
+    pub fn generation(&self) -> u64 {
+        self.generation
+    }
+
+    pub fn patterns(&self) -> &P {
+        &self.patterns
+    }
+}
+
+impl<P> AsRef<P> for GlobSnapshot<P> {
+    fn as_ref(&self) -> &P {
+        &self.patterns
+    }
+}
+
+impl<P> Deref for GlobSnapshot<P> {
+    type Target = P;
+
+    fn deref(&self) -> &Self::Target {
+        &self.patterns
+    }
+}
+
+/// Patterns that can be atomically replaced while they are in use.
+///
+/// `SwappableGlobs` holds patterns, such as an [`Any`], [`Filter`], or
+/// [`Overrides`], that can be replaced by one thread while other threads match
+/// against them. Readers [load][`SwappableGlobs::load`] a [`GlobSnapshot`]
+/// without locking and are never blocked by a swap. Each swap increments a
+/// generation, so that the ruleset that made a decision can be identified,
+/// such as when configuration is reloaded.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, Pattern, SwappableGlobs};
+///
+/// let globs = SwappableGlobs::new(Glob::new("**/*.rs").unwrap());
+///
+/// let snapshot = globs.load();
+/// assert!(snapshot.is_match("src/lib.rs"));
+/// assert_eq!(snapshot.generation(), 0);
+///
+/// globs.swap(Glob::new("**/*.md").unwrap());
+/// assert!(!globs.load().is_match("src/lib.rs"));
+/// assert_eq!(globs.generation(), 1);
+/// // Loaded snapshots are not affected by swaps.
+/// assert!(snapshot.is_match("src/lib.rs"));
+/// ```
+///
+/// [`Any`]: crate::Any
+/// [`Filter`]: crate::Filter
+/// [`GlobSnapshot`]: crate::GlobSnapshot
+/// [`Overrides`]: crate::Overrides
+/// [`SwappableGlobs::load`]: crate::SwappableGlobs::load
+#[cfg_attr(docsrs, doc(cfg(feature = "swap")))]
+#[derive(Debug)]
+pub struct SwappableGlobs<P> {
+    current: ArcSwap<GlobSnapshot<P>>,
+    // Serializes swaps so that generations are stored in order. Loads never
+    // acquire this lock.
+    swap: Mutex<()>,
+}
+
+impl<P> SwappableGlobs<P> {
+    /// Constructs a `SwappableGlobs` with the given initial patterns, which are
+    /// generation zero.
+    pub fn new(patterns: P) -> Self {
+        SwappableGlobs {
+            current: ArcSwap::from_pointee(GlobSnapshot {
+                generation: 0,
+                patterns,
+            }),
+            swap: Mutex::new(()),
+        }
+    }
+
+    /// Loads a [`GlobSnapshot`] of the current patterns.
+    ///
+    /// This does not block and is never blocked by
+    /// [swaps][`SwappableGlobs::swap`].
+    ///
+    /// [`GlobSnapshot`]: crate::GlobSnapshot
+    /// [`SwappableGlobs::swap`]: crate::SwappableGlobs::swap
+    pub fn load(&self) -> Arc<GlobSnapshot<P>> {
+        self.current.load_full()
+    }
+
+    /// Replaces the current patterns and increments the generation.
+    ///
+    /// Returns a [`GlobSnapshot`] of the replaced patterns. Snapshots that have
+    /// already been [loaded][`SwappableGlobs::load`] continue to use the
+    /// replaced patterns.
+    ///
+    /// [`GlobSnapshot`]: crate::GlobSnapshot
+    /// [`SwappableGlobs::load`]: crate::SwappableGlobs::load
+    pub fn swap(&self, patterns: P) -> Arc<GlobSnapshot<P>> {
+        let _guard = self.swap.lock().unwrap_or_else(PoisonError::into_inner);
+        let generation = self.current.load().generation + 1;
+        self.current.swap(Arc::new(GlobSnapshot {
+            generation,
+            patterns,
+        }))
+    }
+
+    /// Gets the generation of the current patterns.
+    pub fn generation(&self) -> u64 {
+        self.current.load().generation
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+
+    use crate::{Glob, Pattern, SwappableGlobs};
+
+    #[test]
+    fn swap_globs_between_threads() {
+        let globs = Arc::new(SwappableGlobs::new(Glob::new("0/*").unwrap()));
+        let reader = {
+            let globs = globs.clone();
+            thread::spawn(move || {
+                let mut generation = 0;
+                while generation < 8 {
+                    let snapshot = globs.load();
+                    // Generations never decrease and each generation matches
+                    // only its own directory.
+                    assert!(snapshot.generation() >= generation);
+                    generation = snapshot.generation();
+                    assert!(snapshot.is_match(format!("{}/a", generation).as_str()));
+                }
+            })
+        };
+        for generation in 1..=8 {
+            let expression = format!("{}/*", generation);
+            let replaced = globs.swap(Glob::new(&expression).unwrap().into_owned());
+            assert_eq!(replaced.generation(), generation - 1);
+        }
+        reader.join().unwrap();
+        assert_eq!(globs.generation(), 8);
+    }
+}
@@ -0,0 +1,611 @@
+#[cfg(feature = "miette")]
+use miette::Diagnostic;
+use std::borrow::Cow;
+#[cfg(feature = "miette")]
+use std::fmt::Display;
+use std::fmt::Write as _;
+use std::ops::Range;
+use thiserror::Error;
+
+use crate::token::{
+    self, Archetype, Evaluation, Token, TokenKind, TokenTree as _, Tokenized, Wildcard,
+};
+use crate::{
+    is_meta_character, parse_and_check_with_folding, BuildError, CaseFolding, Glob,
+    PATHS_ARE_CASE_INSENSITIVE,
+};
+
+// Code points that replace placeholders when a template is parsed. This is the
+// Supplementary Private Use Area-B, which is unlikely to appear in glob
+// expressions.
+const SENTINELS: Range<u32> = 0x10_0000..0x10_FFFE;
+
+/// Describes errors that occur when substituting [`Glob`]s into a
+/// [`GlobTemplate`].
+///
+/// [`Glob`]: crate::Glob
+/// [`GlobTemplate`]: crate::GlobTemplate
+#[derive(Clone, Debug, Error)]
+#[error("placeholder `{name}` {kind}")]
+pub struct TemplateError {
+    name: String,
+    kind: TemplateErrorKind,
+}
+
+impl TemplateError {
+    fn new(name: &str, kind: TemplateErrorKind) -> Self {
+        TemplateError {
+            name: name.to_owned(),
+            kind,
+        }
+    }
+
+    /// Gets the name of the placeholder with no substitution or that cannot be
+    /// substituted.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+#[cfg(feature = "miette")]
+#[cfg_attr(docsrs, doc(cfg(feature = "miette")))]
+impl Diagnostic for TemplateError {
+    fn code<'a>(&'a self) -> Option<Box<dyn 'a + Display>> {
+        Some(Box::new(match self.kind {
+            TemplateErrorKind::Missing => "wax::glob::missing_substitution",
+            TemplateErrorKind::Misplaced => "wax::glob::misplaced_placeholder",
+        }))
+    }
+}
+
+#[derive(Clone, Copy, Debug, Error)]
+#[non_exhaustive]
+enum TemplateErrorKind {
+    #[error("has no substitution")]
+    Missing,
+    #[error("is in a character class")]
+    Misplaced,
+}
+
+#[derive(Clone, Debug)]
+enum Segment {
+    Text(Range<usize>),
+    Placeholder(Range<usize>),
+}
+
+/// Glob expression with named placeholders that are substituted by [`Glob`]s.
+///
+/// Placeholders are delimited by double braces, such as `{{ext}}` in
+/// `assets/**/*.{{ext}}`, and are named by ASCII alphanumeric characters,
+/// `_`, and `-`. Text that begins with double braces but is not a placeholder
+/// is interpreted as glob expression text. Placeholders may appear wherever a
+/// literal may appear, except in character classes.
+///
+/// Placeholders are substituted by the tokens of [`Glob`]s rather than text,
+/// so the substituted patterns are not reinterpreted by their surroundings.
+/// Commas and colons in a [`Glob`] are always literal, for example, even if the
+/// placeholder is in an alternative or repetition. Similarly, flags in a
+/// [`Glob`] only apply to that [`Glob`] and flags in the template do not apply
+/// to substituted [`Glob`]s. The substituted expression is built and checked
+/// as a whole.
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{Glob, GlobTemplate, Pattern};
+///
+/// let template = GlobTemplate::new("assets/{{kind}}/**/*.{{ext}}");
+/// let glob = template
+///     .build([
+///         ("kind", &Glob::new("{images,icons}").unwrap()),
+///         ("ext", &Glob::new("{png,svg}").unwrap()),
+///     ])
+///     .unwrap();
+/// assert!(glob.is_match("assets/icons/ui/close.svg"));
+/// ```
+///
+/// [`Glob`]: crate::Glob
+#[derive(Clone, Debug)]
+pub struct GlobTemplate<'t> {
+    expression: Cow<'t, str>,
+    segments: Vec<Segment>,
+}
+
+impl<'t> GlobTemplate<'t> {
+    /// Constructs a `GlobTemplate` from a glob expression with placeholders.
+    ///
+    /// The expression is not checked until the template is built.
+    pub fn new(expression: &'t str) -> Self {
+        GlobTemplate {
+            segments: segments(expression),
+            expression: expression.into(),
+        }
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> GlobTemplate<'static> {
+        let GlobTemplate {
+            expression,
+            segments,
+        } = self;
+        GlobTemplate {
+            expression: expression.into_owned().into(),
+            segments,
+        }
+    }
+
+    /// Gets the names of the placeholders in the template.
+    ///
+    /// Names are yielded in the order in which they appear in the template and
+    /// may be repeated.
+    pub fn placeholders(&self) -> impl '_ + Iterator<Item = &str> {
+        self.segments
+            .iter()
+            .filter_map(move |segment| match segment {
+                Segment::Placeholder(ref range) => Some(&self.expression[range.clone()]),
+                _ => None,
+            })
+    }
+
+    /// Builds a [`Glob`] by substituting placeholders with [`Glob`]s.
+    ///
+    /// Substitutions are pairs of placeholder names and [`Glob`]s. Names that
+    /// do not appear in the template are ignored. If a name is given more than
+    /// once, then the last [`Glob`] is used. If any substituted [`Glob`] uses
+    /// [`CaseFolding::Turkic`], then the output [`Glob`] uses it for all
+    /// case-insensitive literals.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if a placeholder has no substitution or is in a
+    /// character class or if the substituted glob expression fails to build.
+    ///
+    /// [`CaseFolding::Turkic`]: crate::CaseFolding::Turkic
+    /// [`Glob`]: crate::Glob
+    pub fn build<'s, I, N>(&self, substitutions: I) -> Result<Glob<'static>, BuildError>
+    where
+        I: IntoIterator<Item = (N, &'s Glob<'s>)>,
+        N: AsRef<str>,
+    {
+        let substitutions: Vec<_> = substitutions.into_iter().collect();
+        let globs = self
+            .placeholders()
+            .map(|name| {
+                substitutions
+                    .iter()
+                    .rev()
+                    .find(|(other, _)| other.as_ref() == name)
+                    .map(|(_, glob)| *glob)
+                    .ok_or_else(|| TemplateError::new(name, TemplateErrorKind::Missing))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let (sentinels, tokenized) = self.parse()?;
+        let placeholders: Vec<_> = sentinels
+            .into_iter()
+            .zip(self.placeholders())
+            .zip(globs)
+            .map(|((sentinel, name), glob)| Placeholder {
+                sentinel,
+                name,
+                glob,
+            })
+            .collect();
+        let mut encoder = Encoder::default();
+        encoder.encode(tokenized.tokens(), &placeholders)?;
+        let folding = placeholders
+            .iter()
+            .map(|placeholder| placeholder.glob.tree.as_ref().folding())
+            .find(|folding| matches!(folding, CaseFolding::Turkic))
+            .unwrap_or_default();
+        let tree = parse_and_check_with_folding(&encoder.expression, folding)?;
+        Ok(Glob::from_tree(tree)?.into_owned())
+    }
+
+    // Parses the template with each placeholder replaced by a distinct
+    // sentinel character, which then appears in the text of a literal or in a
+    // class. If the template itself contains a sentinel, such as via an escaped
+    // code point, then it is parsed again with sentinels that do not appear in
+    // its tokens.
+    fn parse(&self) -> Result<(Vec<char>, Tokenized<'static>), BuildError> {
+        let mut excluded = vec![];
+        loop {
+            let sentinels: Vec<_> = SENTINELS
+                .filter_map(char::from_u32)
+                .filter(|x| !(self.expression.contains(*x) || excluded.contains(x)))
+                .take(self.placeholders().count())
+                .collect();
+            let mut expression = String::with_capacity(self.expression.len());
+            let mut placeholders = sentinels.iter();
+            for segment in self.segments.iter() {
+                match segment {
+                    Segment::Text(ref range) => {
+                        expression.push_str(&self.expression[range.clone()]);
+                    },
+                    Segment::Placeholder(_) => {
+                        expression.push(*placeholders.next().expect("no sentinel for placeholder"));
+                    },
+                }
+            }
+            let tokenized = token::parse(&expression)?.into_owned();
+            let characters = characters(&tokenized);
+            if sentinels
+                .iter()
+                .all(|x| characters.iter().filter(|y| *y == x).count() == 1)
+            {
+                return Ok((sentinels, tokenized));
+            }
+            excluded = characters;
+        }
+    }
+}
+
+// A placeholder in a parsed template and the `Glob` that substitutes it.
+struct Placeholder<'p, 's> {
+    sentinel: char,
+    name: &'p str,
+    glob: &'s Glob<'s>,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Flags {
+    is_case_insensitive: bool,
+    is_grapheme: bool,
+    is_separator_crossing: bool,
+}
+
+impl Default for Flags {
+    fn default() -> Self {
+        Flags {
+            is_case_insensitive: PATHS_ARE_CASE_INSENSITIVE,
+            is_grapheme: false,
+            is_separator_crossing: false,
+        }
+    }
+}
+
+// Encodes tokens as a glob expression, substituting the tokens of `Glob`s for
+// placeholders. Every character is escaped as needed by its token and flags
+// are toggled before each token that they affect, so substituted tokens parse
+// the same regardless of their surroundings. Like the parser, flags are
+// tracked in the order in which tokens appear in the expression.
+#[derive(Debug, Default)]
+struct Encoder {
+    expression: String,
+    flags: Flags,
+}
+
+impl Encoder {
+    fn encode<A>(
+        &mut self,
+        tokens: &[Token<'_, A>],
+        placeholders: &[Placeholder<'_, '_>],
+    ) -> Result<(), TemplateError> {
+        for (n, token) in tokens.iter().enumerate() {
+            match token.kind() {
+                TokenKind::Alternative(alternative) => {
+                    self.expression.push('{');
+                    for (n, branch) in alternative.branches().iter().enumerate() {
+                        if n > 0 {
+                            self.expression.push(',');
+                        }
+                        self.encode(branch, placeholders)?;
+                    }
+                    self.expression.push('}');
+                },
+                TokenKind::Class(class) => {
+                    if let Some(placeholder) = placeholders.iter().find(|placeholder| {
+                        class.archetypes().iter().any(|archetype| match *archetype {
+                            Archetype::Character(x) => x == placeholder.sentinel,
+                            Archetype::Range(x, y) => {
+                                x == placeholder.sentinel || y == placeholder.sentinel
+                            },
+                        })
+                    }) {
+                        return Err(TemplateError::new(
+                            placeholder.name,
+                            TemplateErrorKind::Misplaced,
+                        ));
+                    }
+                    self.expression.push('[');
+                    if class.is_negated() {
+                        self.expression.push('!');
+                    }
+                    for archetype in class.archetypes() {
+                        match *archetype {
+                            Archetype::Character(x) => self.class_character(x),
+                            Archetype::Range(x, y) => {
+                                self.class_character(x);
+                                self.expression.push('-');
+                                self.class_character(y);
+                            },
+                        }
+                    }
+                    self.expression.push(']');
+                },
+                TokenKind::Literal(literal) => {
+                    let is_case_insensitive = literal.is_case_insensitive();
+                    let mut text = literal.text();
+                    while let Some((index, placeholder)) =
+                        text.char_indices().find_map(|(index, x)| {
+                            placeholders
+                                .iter()
+                                .find(|placeholder| placeholder.sentinel == x)
+                                .map(|placeholder| (index, placeholder))
+                        })
+                    {
+                        self.literal(&text[..index], is_case_insensitive);
+                        // Substituted tokens contain no placeholders.
+                        self.encode(placeholder.glob.tree.as_ref().tokens(), &[])?;
+                        text = &text[index + placeholder.sentinel.len_utf8()..];
+                    }
+                    self.literal(text, is_case_insensitive);
+                },
+                TokenKind::Repetition(repetition) => {
+                    self.expression.push('<');
+                    self.encode(repetition.tokens(), placeholders)?;
+                    let evaluation = repetition.evaluation();
+                    let is_lazy = matches!(evaluation, Evaluation::Lazy);
+                    match repetition.bounds() {
+                        (0, None) if !is_lazy => {},
+                        (lower, upper) => {
+                            write!(self.expression, ":{}", lower).unwrap();
+                            match upper {
+                                Some(upper) if upper == lower => {},
+                                Some(upper) => write!(self.expression, ",{}", upper).unwrap(),
+                                None => self.expression.push(','),
+                            }
+                        },
+                    }
+                    if is_lazy {
+                        self.expression.push('?');
+                    }
+                    self.expression.push('>');
+                },
+                TokenKind::Separator(_) => self.expression.push('/'),
+                TokenKind::Wildcard(Wildcard::One { is_grapheme }) => {
+                    self.toggle(Flags {
+                        is_grapheme: *is_grapheme,
+                        ..self.flags
+                    });
+                    self.expression.push('?');
+                },
+                TokenKind::Wildcard(Wildcard::ZeroOrMore {
+                    evaluation,
+                    is_separator_crossing,
+                }) => {
+                    self.toggle(Flags {
+                        is_separator_crossing: *is_separator_crossing,
+                        ..self.flags
+                    });
+                    self.expression.push(match evaluation {
+                        Evaluation::Eager => '*',
+                        Evaluation::Lazy => '$',
+                    });
+                },
+                // Tree wildcards consume the separators that surround them, so
+                // these are restored. The separator that follows a tree
+                // wildcard is only consumed if another token follows in the
+                // same sub-glob.
+                TokenKind::Wildcard(Wildcard::Tree { has_root }) => {
+                    if *has_root {
+                        self.expression.push('/');
+                    }
+                    self.expression.push_str("**");
+                    if n + 1 < tokens.len() {
+                        self.expression.push('/');
+                    }
+                },
+            }
+        }
+        Ok(())
+    }
+
+    fn literal(&mut self, text: &str, is_case_insensitive: bool) {
+        if text.is_empty() {
+            return;
+        }
+        self.toggle(Flags {
+            is_case_insensitive,
+            ..self.flags
+        });
+        for x in text.chars() {
+            if is_meta_character(x) {
+                self.expression.push('\\');
+            }
+            self.expression.push(x);
+        }
+    }
+
+    fn class_character(&mut self, x: char) {
+        match x {
+            '[' | ']' | '-' => {
+                self.expression.push('\\');
+                self.expression.push(x);
+            },
+            // Back slashes cannot be escaped and `!` negates a class if it is
+            // the first character, so these are escaped as code points.
+            '\\' | '!' => write!(self.expression, "\\u{{{:x}}}", u32::from(x)).unwrap(),
+            _ => self.expression.push(x),
+        }
+    }
+
+    // Toggles the flags that differ from the given flags, if any, via a flag
+    // group.
+    fn toggle(&mut self, flags: Flags) {
+        if flags == self.flags {
+            return;
+        }
+        self.expression.push_str("(?");
+        for (is_enabled, was_enabled, flag) in [
+            (
+                flags.is_case_insensitive,
+                self.flags.is_case_insensitive,
+                'i',
+            ),
+            (flags.is_grapheme, self.flags.is_grapheme, 'g'),
+            (
+                flags.is_separator_crossing,
+                self.flags.is_separator_crossing,
+                's',
+            ),
+        ] {
+            if is_enabled != was_enabled {
+                if !is_enabled {
+                    self.expression.push('-');
+                }
+                self.expression.push(flag);
+            }
+        }
+        self.expression.push(')');
+        self.flags = flags;
+    }
+}
+
+// Splits a template expression into text and placeholders. The ranges of
+// placeholders exclude their delimiters.
+fn segments(expression: &str) -> Vec<Segment> {
+    let mut segments = vec![];
+    let mut start = 0;
+    let mut chars = expression.char_indices().peekable();
+    while let Some((index, x)) = chars.next() {
+        match x {
+            '\\' => {
+                chars.next();
+            },
+            '{' if expression[index..].starts_with("{{") => {
+                let name = &expression[index + 2..];
+                let n = name
+                    .find(|x: char| !(x.is_ascii_alphanumeric() || x == '_' || x == '-'))
+                    .unwrap_or(name.len());
+                if n > 0 && name[n..].starts_with("}}") {
+                    if start < index {
+                        segments.push(Segment::Text(start..index));
+                    }
+                    segments.push(Segment::Placeholder(index + 2..index + 2 + n));
+                    start = index + n + 4;
+                    while chars.peek().map_or(false, |(index, _)| *index < start) {
+                        chars.next();
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+    if start < expression.len() {
+        segments.push(Segment::Text(start..expression.len()));
+    }
+    segments
+}
+
+// Gets the characters in the literals and classes of a token tree.
+fn characters(tokenized: &Tokenized<'_>) -> Vec<char> {
+    let mut characters = vec![];
+    for (_, token) in tokenized.walk() {
+        match token.kind() {
+            TokenKind::Class(class) => {
+                for archetype in class.archetypes() {
+                    match *archetype {
+                        Archetype::Character(x) => characters.push(x),
+                        Archetype::Range(x, y) => characters.extend([x, y]),
+                    }
+                }
+            },
+            TokenKind::Literal(literal) => characters.extend(literal.text().chars()),
+            _ => {},
+        }
+    }
+    characters
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Glob, GlobTemplate, Pattern};
+
+    fn build(template: &str, substitutions: &[(&str, &str)]) -> Glob<'static> {
+        let globs: Vec<_> = substitutions
+            .iter()
+            .map(|(name, expression)| (*name, Glob::new(expression).unwrap()))
+            .collect();
+        GlobTemplate::new(template)
+            .build(globs.iter().map(|(name, glob)| (*name, glob)))
+            .unwrap()
+    }
+
+    #[test]
+    fn template_placeholders() {
+        let template = GlobTemplate::new("{{a}}/{b}/{{c-d}}/\\{{e}}/{{}}/{{a}}");
+        assert_eq!(
+            template.placeholders().collect::<Vec<_>>(),
+            ["a", "c-d", "a"]
+        );
+        assert!(template.build([("a", &Glob::new("x").unwrap())]).is_err());
+    }
+
+    #[test]
+    fn build_glob_from_template() {
+        let glob = build(
+            "src/{{dir}}/*.{{ext}}",
+            &[("dir", "**"), ("ext", "{rs,toml}")],
+        );
+        assert_eq!(glob.to_string(), "src/**/*.{rs,toml}");
+        assert!(glob.is_match("src/a/b/lib.rs"));
+
+        // Commas and colons in substituted globs are literals.
+        let glob = build("{a,{{b}}}", &[("b", "b,c")]);
+        assert!(glob.is_match("b,c"));
+        assert!(!glob.is_match("c"));
+        let glob = build("<{{a}}:2>", &[("a", "a:")]);
+        assert!(glob.is_match("a:a:"));
+
+        // Flags do not apply across substitutions.
+        let glob = build("(?i){{a}}/b", &[("a", "(?-i)a/(?i)x")]);
+        assert!(glob.is_match("a/X/B"));
+        assert!(!glob.is_match("A/x/b"));
+        let glob = build("{x,(?i){{a}},y}z", &[("a", "(?-i)a")]);
+        assert!(glob.is_match("az"));
+        assert!(glob.is_match("YZ"));
+        assert!(!glob.is_match("Az"));
+
+        // Tree wildcards are substituted with their surrounding separators.
+        let glob = build("a/{{b}}/c", &[("b", "**")]);
+        assert_eq!(glob.to_string(), "a/**/c");
+        let glob = build("{{a}}/c", &[("a", "x/**")]);
+        assert!(glob.is_match("x/y/z/c"));
+    }
+
+    #[test]
+    fn build_glob_from_template_with_structural_substitutions() {
+        // Substituted globs cannot close or extend the groups of the template.
+        let glob = build("{a,{{b}}}", &[("b", "<x:2>")]);
+        assert_eq!(glob.to_string(), "{a,<x:2>}");
+        assert!(glob.is_match("xx"));
+        assert!(!glob.is_match("x"));
+
+        // Meta-characters in substituted literals are escaped and flags for
+        // wildcards are toggled per token.
+        let glob = build("{{a}}*", &[("a", "\\[(?s)*\\]")]);
+        assert!(glob.is_match("[a/b]c"));
+        assert!(!glob.is_match("[a]c/d"));
+
+        // Classes are encoded with their negation and any escapes.
+        let glob = build("{{a}}.rs", &[("a", "[!\\-a-c]")]);
+        assert!(glob.is_match("d.rs"));
+        assert!(!glob.is_match("-.rs"));
+        assert!(!glob.is_match("b.rs"));
+
+        // Substitutions that would not otherwise parse fail to build.
+        let globs = [("a", Glob::new("**").unwrap())];
+        let globs = globs.iter().map(|(name, glob)| (*name, glob));
+        assert!(GlobTemplate::new("{{a}}.rs").build(globs).is_err());
+
+        // Placeholders cannot appear in classes.
+        let error = GlobTemplate::new("[{{a}}]")
+            .build([("a", &Glob::new("x").unwrap())])
+            .unwrap_err();
+        assert!(error.to_string().contains("placeholder `a`"));
+
+        // Escaped code points in the template are not placeholders.
+        let glob = build("\\u{100000}{{a}}", &[("a", "b")]);
+        assert!(glob.is_match("\u{100000}b"));
+    }
+}
@@ -0,0 +1,73 @@
+// Equivalence classes of characters that are the same except for diacritical
+// marks. Each class consists of a base character followed by the precomposed
+// characters that canonically decompose into that base character and one or
+// more combining marks. Characters like `ø` and `ł` have no such decomposition
+// and are not included.
+//
+// This is independent of locale and only includes Latin characters in the
+// Latin-1 Supplement and Latin Extended-A blocks. Decomposed characters, such
+// as `e` followed by a combining acute accent, consist of more than one code
+// point and are never matched by a character class.
+const EQUIVALENCE_CLASSES: &[&str] = &[
+    "aàáâãäåāăą",
+    "AÀÁÂÃÄÅĀĂĄ",
+    "cçćĉċč",
+    "CÇĆĈĊČ",
+    "dď",
+    "DĎ",
+    "eèéêëēĕėęě",
+    "EÈÉÊËĒĔĖĘĚ",
+    "gĝğġģ",
+    "GĜĞĠĢ",
+    "hĥ",
+    "HĤ",
+    "iìíîïĩīĭį",
+    "IÌÍÎÏĨĪĬĮİ",
+    "jĵ",
+    "JĴ",
+    "kķ",
+    "KĶ",
+    "lĺļľ",
+    "LĹĻĽ",
+    "nñńņň",
+    "NÑŃŅŇ",
+    "oòóôõöōŏő",
+    "OÒÓÔÕÖŌŎŐ",
+    "rŕŗř",
+    "RŔŖŘ",
+    "sśŝşš",
+    "SŚŜŞŠ",
+    "tţť",
+    "TŢŤ",
+    "uùúûüũūŭůűų",
+    "UÙÚÛÜŨŪŬŮŰŲ",
+    "wŵ",
+    "WŴ",
+    "yýÿŷ",
+    "YÝŸŶ",
+    "zźżž",
+    "ZŹŻŽ",
+];
+
+// Gets the characters that are equivalent to the given character, including
+// the character itself. Characters that are not in any equivalence class are
+// only equivalent to themselves.
+pub fn equivalents(x: char) -> Vec<char> {
+    EQUIVALENCE_CLASSES
+        .iter()
+        .find(|class| class.contains(x))
+        .map_or_else(|| vec![x], |class| class.chars().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::token::collate;
+
+    #[test]
+    fn equivalents_of_character() {
+        assert_eq!(collate::equivalents('e'), collate::equivalents('é'));
+        assert!(collate::equivalents('e').contains(&'ë'));
+        assert!(!collate::equivalents('e').contains(&'E'));
+        assert_eq!(collate::equivalents('q'), ['q']);
+    }
+}
@@ -0,0 +1,181 @@
+use std::collections::HashSet;
+use std::path;
+
+use crate::token::{Archetype, Class, Token, TokenKind, Wildcard};
+
+// Characters used to expand wildcards and negated classes. The first choice is
+// used in minimal expansions.
+const CHARACTERS: &[char] = &['a', 'b', 'c', 'x', 'y', 'z', '0', '1', '_', '-'];
+// Bounds the number of choices at any one choice point, such as the number of
+// expansions of a zero-or-more wildcard or the depth of a tree wildcard.
+const MAX_CHOICES: usize = 3;
+// Examples are enumerated until this many consecutive choices yield no new
+// examples.
+const MAX_MISSES: usize = 64;
+
+// Enumerates combinations of choices as a mixed radix number, where each choice
+// point is a digit. Earlier choice points vary fastest and the zero index
+// selects the first (minimal) choice everywhere.
+struct Choices {
+    index: usize,
+}
+
+impl Choices {
+    fn choose(&mut self, n: usize) -> usize {
+        if n <= 1 {
+            0
+        }
+        else {
+            let choice = self.index % n;
+            self.index /= n;
+            choice
+        }
+    }
+}
+
+pub struct Examples<'i, 't, A> {
+    tokens: &'i [Token<'t, A>],
+    index: usize,
+    misses: usize,
+    examples: HashSet<String>,
+}
+
+impl<'i, 't, A> Iterator for Examples<'i, 't, A> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.misses < MAX_MISSES {
+            let mut choices = Choices { index: self.index };
+            self.index += 1;
+            let mut text = String::new();
+            match render(self.tokens, &mut choices, &mut text, 0) {
+                Some(_) if self.examples.insert(text.clone()) => {
+                    self.misses = 0;
+                    return Some(text);
+                },
+                _ => {
+                    self.misses += 1;
+                },
+            }
+        }
+        None
+    }
+}
+
+// Gets distinct example paths that match the given tokens. The first example
+// uses minimal expansions: the first branch of alternatives, the lower bound of
+// repetitions, no components for tree wildcards, and no characters for
+// zero-or-more wildcards unless they are alone in a component.
+pub fn examples<'i, 't, A>(tokens: &'i [Token<'t, A>]) -> Examples<'i, 't, A> {
+    Examples {
+        tokens,
+        index: 0,
+        misses: 0,
+        examples: HashSet::new(),
+    }
+}
+
+fn render<A>(
+    tokens: &[Token<'_, A>],
+    choices: &mut Choices,
+    text: &mut String,
+    depth: usize,
+) -> Option<()> {
+    let is_boundary = |n: Option<usize>| {
+        n.and_then(|n| tokens.get(n))
+            .map_or(true, |token| token.is_component_boundary())
+    };
+    for (n, token) in tokens.iter().enumerate() {
+        match token.kind() {
+            TokenKind::Alternative(ref alternative) => {
+                let branches = alternative.branches();
+                let branch = &branches[choices.choose(branches.len())];
+                render(branch, choices, text, depth + 1)?;
+            },
+            TokenKind::Class(ref class) => {
+                let characters = class_characters(class);
+                if characters.is_empty() {
+                    // The class matches no characters and so the tokens match
+                    // no paths.
+                    return None;
+                }
+                text.push(characters[choices.choose(characters.len())]);
+            },
+            TokenKind::Literal(ref literal) => text.push_str(literal.text()),
+            TokenKind::Repetition(ref repetition) => {
+                let (lower, upper) = repetition.bounds();
+                let n = upper.map_or(MAX_CHOICES, |upper| upper - lower + 1);
+                for _ in 0..(lower + choices.choose(n.min(MAX_CHOICES))) {
+                    render(repetition.tokens(), choices, text, depth + 1)?;
+                }
+            },
+            TokenKind::Separator(_) => text.push('/'),
+            TokenKind::Wildcard(Wildcard::One { .. }) => {
+                text.push(CHARACTERS[choices.choose(MAX_CHOICES)]);
+            },
+            TokenKind::Wildcard(Wildcard::ZeroOrMore { .. }) => {
+                // Avoid empty components, which are unusual in paths.
+                let is_singular = is_boundary(n.checked_sub(1)) && is_boundary(Some(n + 1));
+                let n = choices.choose(MAX_CHOICES) + usize::from(is_singular);
+                text.extend(&CHARACTERS[..n]);
+            },
+            TokenKind::Wildcard(Wildcard::Tree { has_root }) => {
+                let is_followed = n + 1 < tokens.len();
+                // Avoid empty paths, which are unusual as examples.
+                let is_singular = depth == 0 && tokens.len() == 1 && !has_root;
+                let n = choices.choose(MAX_CHOICES) + usize::from(is_singular);
+                // Tree wildcards absorb their surrounding separators, which are
+                // only needed when joining components.
+                if *has_root && (n > 0 || is_followed || (depth == 0 && text.is_empty())) {
+                    text.push('/');
+                }
+                for (m, x) in CHARACTERS[..n].iter().enumerate() {
+                    if m > 0 {
+                        text.push('/');
+                    }
+                    text.push(*x);
+                }
+                if n > 0 && is_followed {
+                    text.push('/');
+                }
+            },
+        }
+    }
+    // Discard choices that exceed the choice points of the tokens, as these
+    // only repeat earlier combinations.
+    if depth == 0 && choices.index != 0 {
+        None
+    }
+    else {
+        Some(())
+    }
+}
+
+fn class_characters(class: &Class) -> Vec<char> {
+    let is_member = |x: char| {
+        class.archetypes().iter().any(|archetype| match *archetype {
+            Archetype::Character(y) => x == y,
+            Archetype::Range(left, right) => (left..=right).contains(&x),
+        })
+    };
+    if class.is_negated() {
+        CHARACTERS
+            .iter()
+            .copied()
+            .filter(|x| !is_member(*x) && !is_member(x.to_ascii_uppercase()))
+            .take(MAX_CHOICES)
+            .collect()
+    }
+    else {
+        class
+            .archetypes()
+            .iter()
+            .flat_map(|archetype| match *archetype {
+                Archetype::Character(x) => x..=x,
+                Archetype::Range(left, right) => left..=right,
+            })
+            .filter(|x| !path::is_separator(*x))
+            .take(MAX_CHOICES)
+            .collect()
+    }
+}
@@ -0,0 +1,173 @@
+use crate::token::{Archetype, CaseFolding, Evaluation, Literal, Token, TokenKind, Wildcard};
+
+// Parameters of the 64-bit FNV-1a hash function.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// Tags that begin the encoding of each token. These values and the encoding of
+// tokens are part of the public API of `Glob::stable_hash` and must not change.
+const TAG_END: u8 = 0x00;
+const TAG_LITERAL: u8 = 0x01;
+const TAG_SEPARATOR: u8 = 0x02;
+const TAG_WILDCARD_ONE: u8 = 0x03;
+const TAG_WILDCARD_ZERO_OR_MORE: u8 = 0x04;
+const TAG_WILDCARD_TREE: u8 = 0x05;
+const TAG_CLASS: u8 = 0x06;
+const TAG_ALTERNATIVE: u8 = 0x07;
+const TAG_REPETITION: u8 = 0x08;
+const TAG_LITERAL_TURKIC: u8 = 0x09;
+
+// Case sensitivity and folding of a literal. Folding only applies to
+// case-insensitive literals, so case-sensitive literals have no folding.
+type Casing = (bool, Option<CaseFolding>);
+
+fn casing(literal: &Literal<'_>) -> Casing {
+    let is_case_insensitive = literal.is_case_insensitive();
+    (
+        is_case_insensitive,
+        is_case_insensitive.then(|| literal.folding()),
+    )
+}
+
+struct Encoder {
+    state: u64,
+    // Text and casing of adjacent literals that have not yet been encoded.
+    // Adjacent literals are encoded as one literal.
+    literal: Option<(String, Casing)>,
+}
+
+impl Encoder {
+    fn new() -> Self {
+        Encoder {
+            state: FNV_OFFSET_BASIS,
+            literal: None,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= u64::from(*byte);
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    fn write_bool(&mut self, value: bool) {
+        self.write(&[u8::from(value)]);
+    }
+
+    fn write_char(&mut self, value: char) {
+        self.write(&u32::from(value).to_le_bytes());
+    }
+
+    fn write_usize(&mut self, value: usize) {
+        self.write(&(value as u64).to_le_bytes());
+    }
+
+    fn write_text(&mut self, text: &str) {
+        self.write_usize(text.len());
+        self.write(text.as_bytes());
+    }
+
+    fn flush(&mut self) {
+        match self.literal.take() {
+            Some((text, (_, Some(CaseFolding::Turkic)))) => {
+                self.write(&[TAG_LITERAL_TURKIC]);
+                self.write_text(&text);
+            },
+            Some((text, (is_case_insensitive, _))) => {
+                self.write(&[TAG_LITERAL]);
+                self.write_bool(is_case_insensitive);
+                self.write_text(&text);
+            },
+            None => {},
+        }
+    }
+
+    // Encodes a sequence of tokens followed by `TAG_END`.
+    fn sequence<A>(&mut self, tokens: &[Token<'_, A>]) {
+        for token in tokens {
+            if let TokenKind::Literal(ref literal) = token.kind() {
+                match self.literal {
+                    Some((ref mut text, casing)) if casing == self::casing(literal) => {
+                        text.push_str(literal.text());
+                    },
+                    _ => {
+                        self.flush();
+                        self.literal = Some((literal.text().to_owned(), self::casing(literal)));
+                    },
+                }
+                continue;
+            }
+            self.flush();
+            match token.kind() {
+                TokenKind::Alternative(ref alternative) => {
+                    self.write(&[TAG_ALTERNATIVE]);
+                    self.write_usize(alternative.branches().len());
+                    for branch in alternative.branches() {
+                        self.sequence(branch);
+                    }
+                },
+                TokenKind::Class(ref class) => {
+                    self.write(&[TAG_CLASS]);
+                    self.write_bool(class.is_negated());
+                    self.write_usize(class.archetypes().len());
+                    for archetype in class.archetypes() {
+                        match *archetype {
+                            Archetype::Character(x) => {
+                                self.write(&[0x00]);
+                                self.write_char(x);
+                            },
+                            Archetype::Range(left, right) => {
+                                self.write(&[0x01]);
+                                self.write_char(left);
+                                self.write_char(right);
+                            },
+                        }
+                    }
+                },
+                TokenKind::Literal(_) => unreachable!(),
+                TokenKind::Repetition(ref repetition) => {
+                    let (lower, upper) = repetition.bounds();
+                    self.write(&[TAG_REPETITION]);
+                    self.write_usize(lower);
+                    match upper {
+                        Some(upper) => {
+                            self.write(&[0x01]);
+                            self.write_usize(upper);
+                        },
+                        None => self.write(&[0x00]),
+                    }
+                    self.write_bool(matches!(repetition.evaluation(), Evaluation::Lazy));
+                    self.sequence(repetition.tokens());
+                },
+                TokenKind::Separator(_) => self.write(&[TAG_SEPARATOR]),
+                TokenKind::Wildcard(Wildcard::One { is_grapheme }) => {
+                    self.write(&[TAG_WILDCARD_ONE]);
+                    self.write_bool(*is_grapheme);
+                },
+                TokenKind::Wildcard(Wildcard::ZeroOrMore {
+                    evaluation,
+                    is_separator_crossing,
+                }) => {
+                    self.write(&[TAG_WILDCARD_ZERO_OR_MORE]);
+                    self.write_bool(matches!(evaluation, Evaluation::Lazy));
+                    self.write_bool(*is_separator_crossing);
+                },
+                TokenKind::Wildcard(Wildcard::Tree { has_root }) => {
+                    self.write(&[TAG_WILDCARD_TREE]);
+                    self.write_bool(*has_root);
+                },
+            }
+        }
+        self.flush();
+        self.write(&[TAG_END]);
+    }
+}
+
+// Computes a hash of tokens that is stable across releases. See
+// `Glob::stable_hash` for the encoding of tokens.
+pub fn stable_hash<A>(tokens: &[Token<'_, A>]) -> u64 {
+    let mut encoder = Encoder::new();
+    encoder.sequence(tokens);
+    encoder.state
+}
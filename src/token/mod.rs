@@ -1,47 +1,220 @@
+mod collate;
+mod example;
+mod hash;
 mod parse;
 mod variance;
 
 use itertools::Itertools as _;
+use smallvec::{smallvec, SmallVec};
 use std::borrow::Cow;
-use std::cmp;
 use std::collections::VecDeque;
+use std::iter::Peekable;
 use std::mem;
 use std::ops::Deref;
 use std::path::{PathBuf, MAIN_SEPARATOR};
 use std::slice;
 use std::str;
 
-use crate::token::variance::{
-    CompositeBreadth, CompositeDepth, ConjunctiveVariance, DisjunctiveVariance, IntoInvariantText,
-    Invariance, UnitBreadth, UnitDepth, UnitVariance,
-};
-use crate::{StrExt as _, PATHS_ARE_CASE_INSENSITIVE};
+use crate::diagnostics::{Span, SpanExt as _};
+use crate::token::variance::{CompositeBreadth, CompositeDepth, UnitBreadth, UnitDepth};
+use crate::{Interner, StrExt as _, PATHS_ARE_CASE_INSENSITIVE};
 
-pub use crate::token::parse::{parse, Annotation, ParseError, ROOT_SEPARATOR_EXPRESSION};
+pub use crate::token::example::examples;
+pub use crate::token::hash::stable_hash;
+pub use crate::token::parse::{
+    parse, parse_with_folding, Annotation, ParseError, ROOT_SEPARATOR_EXPRESSION,
+};
 pub use crate::token::variance::{
-    invariant_text_prefix, is_exhaustive, Boundedness, InvariantSize, InvariantText, Variance,
+    component_upper_bound, invariant_text_prefix, is_exhaustive, Boundedness, ConjunctiveVariance,
+    DisjunctiveVariance, IntoInvariantText, Invariance, InvariantSize, InvariantText,
+    UnitVariance, Variance,
 };
 
+// Token tree and exclusion of a pattern in a token tree. The exclusion is an
+// alternative of tokens that the pattern must not match, such as the excluded
+// globs of a `Filter`.
+pub type Member<'t> = (Tokenized<'t>, Option<Token<'t, ()>>);
+
 pub trait TokenTree<'t>: Sized {
     type Annotation;
 
     fn into_tokens(self) -> Vec<Token<'t, Self::Annotation>>;
 
+    // Gets the token trees of the patterns in the tree. Composed patterns are
+    // flattened into their members.
+    fn into_members(self) -> Vec<Member<'t>>;
+
     fn tokens(&self) -> &[Token<'t, Self::Annotation>];
+
+    // Returns `true` if any pattern in the tree has an exclusion. Such trees
+    // match less than their tokens.
+    fn has_exclusions(&self) -> bool {
+        false
+    }
+}
+
+// Token tree of patterns composed by a combinator, such as `Any`. The branches
+// of the alternative token are the tokens of the composed patterns and
+// correspond to the member trees and exclusions with the same index.
+#[derive(Clone, Debug)]
+pub struct Composition<'t> {
+    members: Vec<Tokenized<'t>>,
+    exclusions: Vec<Option<Token<'t, ()>>>,
+    token: Token<'t, ()>,
+}
+
+impl<'t> Composition<'t> {
+    pub fn into_owned(self) -> Composition<'static> {
+        let Composition {
+            members,
+            exclusions,
+            token,
+        } = self;
+        Composition {
+            members: members.into_iter().map(Tokenized::into_owned).collect(),
+            exclusions: exclusions
+                .into_iter()
+                .map(|exclusion| exclusion.map(Token::into_owned))
+                .collect(),
+            token: token.into_owned(),
+        }
+    }
+
+    pub fn heap_usage(&self) -> usize {
+        (self.members.capacity() * mem::size_of::<Tokenized<'t>>())
+            + self
+                .members
+                .iter()
+                .map(Tokenized::heap_usage)
+                .sum::<usize>()
+            + (self.exclusions.capacity() * mem::size_of::<Option<Token<'t, ()>>>())
+            + self
+                .exclusions
+                .iter()
+                .flatten()
+                .map(Token::heap_usage)
+                .sum::<usize>()
+            + self.token.heap_usage()
+    }
+
+    pub fn members(&self) -> &[Tokenized<'t>] {
+        &self.members
+    }
+
+    pub fn exclusions(&self) -> &[Option<Token<'t, ()>>] {
+        &self.exclusions
+    }
+
+    pub fn token(&self) -> &Token<'t, ()> {
+        &self.token
+    }
+}
+
+impl<'t> TokenTree<'t> for Composition<'t> {
+    type Annotation = ();
+
+    fn into_tokens(self) -> Vec<Token<'t, Self::Annotation>> {
+        vec![self.token]
+    }
+
+    fn into_members(self) -> Vec<Member<'t>> {
+        let Composition {
+            members,
+            exclusions,
+            ..
+        } = self;
+        members.into_iter().zip(exclusions).collect()
+    }
+
+    fn tokens(&self) -> &[Token<'t, Self::Annotation>] {
+        slice::from_ref(&self.token)
+    }
+
+    fn has_exclusions(&self) -> bool {
+        self.exclusions.iter().any(Option::is_some)
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct Tokenized<'t, A = Annotation> {
     expression: Cow<'t, str>,
     tokens: Vec<Token<'t, A>>,
+    // The case folding with which the expression was parsed. This is not part
+    // of the expression, so it is retained to parse the expression again.
+    folding: CaseFolding,
 }
 
 impl<'t, A> Tokenized<'t, A> {
     pub fn into_owned(self) -> Tokenized<'static, A> {
-        let Tokenized { expression, tokens } = self;
+        let Tokenized {
+            expression,
+            tokens,
+            folding,
+        } = self;
         Tokenized {
             expression: expression.into_owned().into(),
             tokens: tokens.into_iter().map(Token::into_owned).collect(),
+            folding,
+        }
+    }
+
+    pub fn intern<'i>(self, interner: &'i Interner) -> Tokenized<'i, A>
+    where
+        't: 'i,
+    {
+        let Tokenized {
+            expression,
+            tokens,
+            folding,
+        } = self;
+        Tokenized {
+            expression,
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.intern(interner))
+                .collect(),
+            folding,
+        }
+    }
+
+    // Maps the annotations of all tokens in the tree. Tokens are visited in
+    // depth-first order, so the annotation of a token is mapped before the
+    // annotations of its child tokens.
+    pub fn map_annotations<F, B>(self, mut f: F) -> Tokenized<'t, B>
+    where
+        F: FnMut(A) -> B,
+    {
+        let Tokenized {
+            expression,
+            tokens,
+            folding,
+        } = self;
+        Tokenized {
+            expression,
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.map_annotations(&mut f))
+                .collect(),
+            folding,
+        }
+    }
+
+    // Sets the case sensitivity of literals that are not affected by flags.
+    // This is the token tree that is parsed from the expression with the given
+    // default case sensitivity.
+    pub fn recase(self, is_case_insensitive: bool) -> Self {
+        let Tokenized {
+            expression,
+            tokens,
+            folding,
+        } = self;
+        Tokenized {
+            expression,
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.recase(is_case_insensitive))
+                .collect(),
+            folding,
         }
     }
 
@@ -49,12 +222,39 @@ impl<'t, A> Tokenized<'t, A> {
         &self.expression
     }
 
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
+    // Gets the number of bytes allocated on the heap by the expression and
+    // tokens.
+    pub fn heap_usage(&self) -> usize {
+        let expression = match self.expression {
+            Cow::Borrowed(_) => 0,
+            Cow::Owned(ref expression) => expression.capacity(),
+        };
+        expression
+            + (self.tokens.capacity() * mem::size_of::<Token<'t, A>>())
+            + self
+                .walk()
+                .map(|(_, token)| token.kind().allocation_size())
+                .sum::<usize>()
+    }
+
     pub fn variance<T>(&self) -> Variance<T>
     where
         T: Invariance,
         for<'i> &'i Token<'t, A>: UnitVariance<T>,
     {
-        self.tokens().iter().conjunctive_variance()
+        self.tokens.iter().conjunctive_variance()
+    }
+
+    pub fn breadth(&self) -> Boundedness {
+        self.tokens.iter().composite_breadth()
+    }
+
+    pub fn depth(&self) -> Boundedness {
+        self.tokens.iter().composite_depth()
     }
 
     pub fn walk(&self) -> Walk<'_, 't, A> {
@@ -64,21 +264,16 @@ impl<'t, A> Tokenized<'t, A> {
 
 impl<'t> Tokenized<'t, Annotation> {
     pub fn partition(self) -> (PathBuf, Self) {
-        fn pop_expression_bytes(expression: &str, n: usize) -> &str {
-            let n = cmp::min(expression.len(), n);
-            str::from_utf8(&expression.as_bytes()[n..])
-                .expect("span offset split UTF-8 byte sequence")
-        }
-
         let Tokenized {
             expression,
             mut tokens,
+            folding,
         } = self;
 
         // Get the invariant prefix and its upper bound for the token sequence.
         let prefix = variance::invariant_text_prefix(tokens.iter()).into();
         let n = variance::invariant_text_prefix_upper_bound(&tokens);
-        let mut offset: usize = tokens
+        let offset: usize = tokens
             .iter()
             .take(n)
             .map(|token| token.annotation().1)
@@ -86,41 +281,94 @@ impl<'t> Tokenized<'t, Annotation> {
 
         // Drain invariant tokens from the beginning of the token sequence and
         // unroot any tokens at the beginning of the variant sequence (tree
-        // wildcards). Finally, translate spans and discard the corresponding
-        // invariant bytes in the expression.
+        // wildcards).
         tokens.drain(0..n);
-        if tokens.first_mut().map_or(false, Token::unroot) {
-            // TODO: The relationship between roots, the unrooting operation,
-            //       and the span in an expression that represents such a root
-            //       (if any) is not captured by these APIs very well. Perhaps
-            //       `unroot` should do more here?
-            // Pop additional bytes for the root separator expression if the
-            // initial token has lost a root.
-            offset += ROOT_SEPARATOR_EXPRESSION.len();
+        let is_unrooted = tokens.first_mut().map_or(false, Token::unroot);
+        // TODO: The relationship between roots, the unrooting operation, and
+        //       the span in an expression that represents such a root (if any)
+        //       is not captured by these APIs very well. Perhaps `unroot`
+        //       should do more here?
+        // Split the expression after the invariant tokens, including the root
+        // separator of the initial token if it has lost a root. Flag groups
+        // are never split: any flags in effect at the split, including the
+        // scoped flag groups that are closed after it, begin the remaining
+        // expression.
+        let (offset, flags) = parse::split(expression.as_ref(), n, is_unrooted)
+            .unwrap_or_else(|| (offset, String::new()));
+        let expression = if tokens.is_empty() {
+            Cow::Borrowed("")
         }
-        for token in tokens.iter_mut() {
-            let start = token.annotation().0.saturating_sub(offset);
-            token.annotation.0 = start;
+        else if flags.is_empty() {
+            match expression {
+                Cow::Borrowed(expression) => expression[offset..].into(),
+                Cow::Owned(expression) => String::from(&expression[offset..]).into(),
+            }
         }
-        let expression = match expression {
-            Cow::Borrowed(expression) => pop_expression_bytes(expression, offset).into(),
-            Cow::Owned(expression) => {
-                String::from(pop_expression_bytes(&expression, offset)).into()
-            },
+        else {
+            (flags.clone() + &expression[offset..]).into()
         };
 
-        (prefix, Tokenized { expression, tokens })
+        // Translate spans into the remaining expression. The initial token
+        // begins the expression, including any flag groups that precede it.
+        for (n, token) in tokens.iter_mut().enumerate() {
+            let (start, len) = *token.annotation();
+            token.annotation = if n == 0 {
+                (0, (start + len + flags.len()).saturating_sub(offset))
+            }
+            else {
+                ((start + flags.len()).saturating_sub(offset), len)
+            };
+        }
+
+        (
+            prefix,
+            Tokenized {
+                expression,
+                tokens,
+                folding,
+            },
+        )
+    }
+
+    // Gets the span of the invariant prefix in the expression, including any
+    // separator that delimits the prefix. This is the text that is discarded
+    // by `partition`.
+    pub fn invariant_prefix_span(&self) -> Option<Span> {
+        let n = variance::invariant_text_prefix_upper_bound(&self.tokens);
+        let span = self
+            .tokens
+            .iter()
+            .take(n)
+            .map(|token| *token.annotation())
+            .reduce(|left, right| left.union(&right));
+        match self.tokens.get(n) {
+            Some(token)
+                if matches!(
+                    token.kind(),
+                    TokenKind::Wildcard(Wildcard::Tree { has_root: true }),
+                ) =>
+            {
+                // The root separator of a tree wildcard is also discarded.
+                let root = (token.annotation().0, ROOT_SEPARATOR_EXPRESSION.len());
+                Some(span.map_or(root, |span| span.union(&root)))
+            },
+            _ => span,
+        }
     }
 }
 
-impl<'t, A> TokenTree<'t> for Tokenized<'t, A> {
-    type Annotation = A;
+impl<'t> TokenTree<'t> for Tokenized<'t> {
+    type Annotation = Annotation;
 
     fn into_tokens(self) -> Vec<Token<'t, Self::Annotation>> {
         let Tokenized { tokens, .. } = self;
         tokens
     }
 
+    fn into_members(self) -> Vec<Member<'t>> {
+        vec![(self, None)]
+    }
+
     fn tokens(&self) -> &[Token<'t, Self::Annotation>] {
         &self.tokens
     }
@@ -145,11 +393,38 @@ impl<'t, A> Token<'t, A> {
         }
     }
 
+    pub fn intern<'i>(self, interner: &'i Interner) -> Token<'i, A>
+    where
+        't: 'i,
+    {
+        let Token { kind, annotation } = self;
+        Token {
+            kind: kind.intern(interner),
+            annotation,
+        }
+    }
+
+    pub fn map_annotations<F, B>(self, f: &mut F) -> Token<'t, B>
+    where
+        F: FnMut(A) -> B,
+    {
+        let Token { kind, annotation } = self;
+        let annotation = f(annotation);
+        Token {
+            kind: kind.map_annotations(f),
+            annotation,
+        }
+    }
+
     pub fn unannotate(self) -> Token<'t, ()> {
-        let Token { kind, .. } = self;
+        self.map_annotations(&mut |_| ())
+    }
+
+    pub fn recase(self, is_case_insensitive: bool) -> Self {
+        let Token { kind, annotation } = self;
         Token {
-            kind: kind.unannotate(),
-            annotation: (),
+            kind: kind.recase(is_case_insensitive),
+            annotation,
         }
     }
 
@@ -178,8 +453,20 @@ impl<'t, A> Token<'t, A> {
         })
     }
 
+    // Zero-or-more wildcards that cross separators are not component
+    // boundaries, but may match any number of components and so are treated
+    // like boundaries when matching components.
     pub fn has_component_boundary(&self) -> bool {
-        self.walk().any(|(_, token)| token.is_component_boundary())
+        self.walk()
+            .any(|(_, token)| token.is_component_boundary() || token.is_separator_crossing())
+    }
+
+    // Gets the number of bytes allocated on the heap by the token and its
+    // sub-tokens.
+    pub fn heap_usage(&self) -> usize {
+        self.walk()
+            .map(|(_, token)| token.kind().allocation_size())
+            .sum()
     }
 }
 
@@ -212,18 +499,6 @@ impl<'t> From<TokenKind<'t, ()>> for Token<'t, ()> {
     }
 }
 
-impl<'t, A> TokenTree<'t> for Token<'t, A> {
-    type Annotation = A;
-
-    fn into_tokens(self) -> Vec<Token<'t, Self::Annotation>> {
-        vec![self]
-    }
-
-    fn tokens(&self) -> &[Token<'t, Self::Annotation>] {
-        slice::from_ref(self)
-    }
-}
-
 impl<'i, 't, A> UnitBreadth for &'i Token<'t, A> {
     fn unit_breadth(self) -> Boundedness {
         self.kind.unit_breadth()
@@ -264,9 +539,13 @@ impl<'t, A> TokenKind<'t, A> {
             TokenKind::Literal(Literal {
                 text,
                 is_case_insensitive,
+                is_case_explicit,
+                folding,
             }) => TokenKind::Literal(Literal {
                 text: text.into_owned().into(),
                 is_case_insensitive,
+                is_case_explicit,
+                folding,
             }),
             TokenKind::Repetition(repetition) => repetition.into_owned().into(),
             TokenKind::Separator(_) => TokenKind::Separator(Separator),
@@ -274,17 +553,65 @@ impl<'t, A> TokenKind<'t, A> {
         }
     }
 
-    pub fn unannotate(self) -> TokenKind<'t, ()> {
+    pub fn intern<'i>(self, interner: &'i Interner) -> TokenKind<'i, A>
+    where
+        't: 'i,
+    {
         match self {
-            TokenKind::Alternative(alternative) => TokenKind::Alternative(alternative.unannotate()),
+            TokenKind::Alternative(alternative) => alternative.intern(interner).into(),
+            TokenKind::Literal(Literal {
+                text,
+                is_case_insensitive,
+                is_case_explicit,
+                folding,
+            }) => TokenKind::Literal(Literal {
+                text: interner.get(&text).map_or(text, Cow::Borrowed),
+                is_case_insensitive,
+                is_case_explicit,
+                folding,
+            }),
+            TokenKind::Repetition(repetition) => repetition.intern(interner).into(),
+            TokenKind::Class(class) => TokenKind::Class(class),
+            TokenKind::Separator(_) => TokenKind::Separator(Separator),
+            TokenKind::Wildcard(wildcard) => TokenKind::Wildcard(wildcard),
+        }
+    }
+
+    pub fn map_annotations<F, B>(self, f: &mut F) -> TokenKind<'t, B>
+    where
+        F: FnMut(A) -> B,
+    {
+        match self {
+            TokenKind::Alternative(alternative) => {
+                TokenKind::Alternative(alternative.map_annotations(f))
+            },
             TokenKind::Class(class) => TokenKind::Class(class),
             TokenKind::Literal(literal) => TokenKind::Literal(literal),
-            TokenKind::Repetition(repetition) => TokenKind::Repetition(repetition.unannotate()),
+            TokenKind::Repetition(repetition) => {
+                TokenKind::Repetition(repetition.map_annotations(f))
+            },
             TokenKind::Separator(_) => TokenKind::Separator(Separator),
             TokenKind::Wildcard(wildcard) => TokenKind::Wildcard(wildcard),
         }
     }
 
+    pub fn unannotate(self) -> TokenKind<'t, ()> {
+        self.map_annotations(&mut |_| ())
+    }
+
+    pub fn recase(self, is_case_insensitive: bool) -> Self {
+        match self {
+            TokenKind::Alternative(alternative) => {
+                TokenKind::Alternative(alternative.recase(is_case_insensitive))
+            },
+            TokenKind::Literal(literal) => TokenKind::Literal(literal.recase(is_case_insensitive)),
+            TokenKind::Repetition(repetition) => {
+                TokenKind::Repetition(repetition.recase(is_case_insensitive))
+            },
+            _ => self,
+        }
+    }
+
     pub fn unroot(&mut self) -> bool {
         match self {
             TokenKind::Wildcard(Wildcard::Tree { ref mut has_root }) => {
@@ -302,6 +629,32 @@ impl<'t, A> TokenKind<'t, A> {
         self.unit_variance()
     }
 
+    // Gets the number of bytes allocated on the heap directly by the token,
+    // excluding the allocations of any sub-tokens.
+    fn allocation_size(&self) -> usize {
+        match self {
+            TokenKind::Alternative(ref alternative) => {
+                (alternative.0.capacity() * mem::size_of::<Vec<Token<'t, A>>>())
+                    + alternative
+                        .0
+                        .iter()
+                        .map(|tokens| tokens.capacity() * mem::size_of::<Token<'t, A>>())
+                        .sum::<usize>()
+            },
+            TokenKind::Class(ref class) => {
+                class.archetypes.capacity() * mem::size_of::<Archetype>()
+            },
+            TokenKind::Literal(Literal { ref text, .. }) => match text {
+                Cow::Borrowed(_) => 0,
+                Cow::Owned(ref text) => text.capacity(),
+            },
+            TokenKind::Repetition(ref repetition) => {
+                repetition.tokens.capacity() * mem::size_of::<Token<'t, A>>()
+            },
+            TokenKind::Separator(_) | TokenKind::Wildcard(_) => 0,
+        }
+    }
+
     pub fn has_sub_tokens(&self) -> bool {
         // It is not necessary to detect empty branches or sub-expressions.
         matches!(self, TokenKind::Alternative(_) | TokenKind::Repetition(_))
@@ -314,6 +667,16 @@ impl<'t, A> TokenKind<'t, A> {
         )
     }
 
+    pub fn is_separator_crossing(&self) -> bool {
+        matches!(
+            self,
+            TokenKind::Wildcard(Wildcard::ZeroOrMore {
+                is_separator_crossing: true,
+                ..
+            })
+        )
+    }
+
     pub fn is_capturing(&self) -> bool {
         use TokenKind::{Alternative, Class, Repetition, Wildcard};
 
@@ -406,12 +769,56 @@ impl<'t, A> Alternative<'t, A> {
         )
     }
 
+    pub fn intern<'i>(self, interner: &'i Interner) -> Alternative<'i, A>
+    where
+        't: 'i,
+    {
+        Alternative(
+            self.0
+                .into_iter()
+                .map(|tokens| {
+                    tokens
+                        .into_iter()
+                        .map(|token| token.intern(interner))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
+    pub fn map_annotations<F, B>(self, f: &mut F) -> Alternative<'t, B>
+    where
+        F: FnMut(A) -> B,
+    {
+        let Alternative(branches) = self;
+        Alternative(
+            branches
+                .into_iter()
+                .map(|branch| {
+                    branch
+                        .into_iter()
+                        .map(|token| token.map_annotations(f))
+                        .collect()
+                })
+                .collect(),
+        )
+    }
+
     pub fn unannotate(self) -> Alternative<'t, ()> {
+        self.map_annotations(&mut |_| ())
+    }
+
+    pub fn recase(self, is_case_insensitive: bool) -> Self {
         let Alternative(branches) = self;
         Alternative(
             branches
                 .into_iter()
-                .map(|branch| branch.into_iter().map(Token::unannotate).collect())
+                .map(|branch| {
+                    branch
+                        .into_iter()
+                        .map(|token| token.recase(is_case_insensitive))
+                        .collect()
+                })
                 .collect(),
         )
     }
@@ -511,6 +918,8 @@ impl<'i> UnitVariance<InvariantSize> for &'i Archetype {
         // This is pessimistic and assumes that the code point will require four
         // bytes when encoded as UTF-8. This is technically possible, but most
         // commonly only one or two bytes will be required.
+        // Unlike exactly-one wildcards, classes always match a single code
+        // point regardless of the grapheme flag, so this bound holds.
         self.domain_variance().map_invariance(|_| 4.into())
     }
 }
@@ -560,10 +969,70 @@ pub enum Evaluation {
     Lazy,
 }
 
+/// Rules by which case-insensitive literals are folded when matched.
+///
+/// Case-insensitive literals match text that is the same when both are folded
+/// into a common case. Most languages agree on the folding of most characters,
+/// but some do not: in Turkish and Azerbaijani, the lowercase of `I` is the
+/// dotless `ı` and the uppercase of `i` is the dotted `İ`. Under simple
+/// folding, `(?i)file` matches `FILE` but not `FİLE`, which is the uppercase
+/// of `file` in these languages.
+///
+/// Case folding only applies to case-insensitive literals, such as those that
+/// follow the `(?i)` flag. It is not part of glob expression syntax and is
+/// configured via [`GlobBuilder::case_folding`].
+///
+/// [`GlobBuilder::case_folding`]: crate::GlobBuilder::case_folding
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum CaseFolding {
+    /// Unicode simple case folding.
+    ///
+    /// This folding is independent of language. For example, `i` and `I` fold
+    /// together but `İ` and `ı` fold only to themselves.
+    Simple,
+    /// Unicode simple case folding with the Turkic folding of dotted and
+    /// dotless `i`.
+    ///
+    /// This folding is appropriate for text in Turkish and Azerbaijani: `i`
+    /// and `İ` fold together and `I` and `ı` fold together, but `i` and `I` do
+    /// not. All other characters are folded as with
+    /// [`CaseFolding::Simple`].
+    ///
+    /// No file system folds paths in this way, so case-insensitive literals
+    /// that contain any of these characters are never part of an [invariant
+    /// prefix][`Glob::partition`].
+    ///
+    /// [`CaseFolding::Simple`]: crate::CaseFolding::Simple
+    /// [`Glob::partition`]: crate::Glob::partition
+    Turkic,
+}
+
+impl CaseFolding {
+    // Returns `true` if text contains characters that are folded differently
+    // than with simple case folding.
+    fn is_divergent(&self, text: &str) -> bool {
+        match self {
+            CaseFolding::Simple => false,
+            CaseFolding::Turkic => text.chars().any(|x| matches!(x, 'i' | 'I' | 'İ' | 'ı')),
+        }
+    }
+}
+
+impl Default for CaseFolding {
+    fn default() -> Self {
+        CaseFolding::Simple
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Literal<'t> {
     text: Cow<'t, str>,
     is_case_insensitive: bool,
+    // Whether the case sensitivity is set by a flag rather than the default.
+    is_case_explicit: bool,
+    // The folding of the text if it is case-insensitive.
+    folding: CaseFolding,
 }
 
 impl<'t> Literal<'t> {
@@ -580,15 +1049,38 @@ impl<'t> Literal<'t> {
         }
     }
 
+    fn recase(self, is_case_insensitive: bool) -> Self {
+        if self.is_case_explicit {
+            self
+        }
+        else {
+            Literal {
+                is_case_insensitive,
+                ..self
+            }
+        }
+    }
+
     pub fn is_case_insensitive(&self) -> bool {
         self.is_case_insensitive
     }
 
+    pub fn is_case_explicit(&self) -> bool {
+        self.is_case_explicit
+    }
+
+    pub fn folding(&self) -> CaseFolding {
+        self.folding
+    }
+
     pub fn has_variant_casing(&self) -> bool {
         // If path case sensitivity agrees with the literal case sensitivity,
         // then the literal is not variant. Otherwise, the literal is variant if
-        // it contains characters with casing.
-        (PATHS_ARE_CASE_INSENSITIVE != self.is_case_insensitive) && self.text.has_casing()
+        // it contains characters with casing. Paths are never folded with
+        // language-specific rules, so case-insensitive literals that contain
+        // characters with such rules are always variant.
+        ((PATHS_ARE_CASE_INSENSITIVE != self.is_case_insensitive) && self.text.has_casing())
+            || (self.is_case_insensitive && self.folding.is_divergent(&self.text))
     }
 }
 
@@ -621,6 +1113,7 @@ pub struct Repetition<'t, A = ()> {
     // that errors in expressions can be deferred and presented more clearly.
     // Failures in the parser are difficult to describe.
     upper: Option<usize>,
+    evaluation: Evaluation,
 }
 
 impl<'t, A> Repetition<'t, A> {
@@ -629,24 +1122,77 @@ impl<'t, A> Repetition<'t, A> {
             tokens,
             lower,
             upper,
+            evaluation,
         } = self;
         Repetition {
             tokens: tokens.into_iter().map(Token::into_owned).collect(),
             lower,
             upper,
+            evaluation,
+        }
+    }
+
+    pub fn intern<'i>(self, interner: &'i Interner) -> Repetition<'i, A>
+    where
+        't: 'i,
+    {
+        let Repetition {
+            tokens,
+            lower,
+            upper,
+            evaluation,
+        } = self;
+        Repetition {
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.intern(interner))
+                .collect(),
+            lower,
+            upper,
+            evaluation,
+        }
+    }
+
+    pub fn map_annotations<F, B>(self, f: &mut F) -> Repetition<'t, B>
+    where
+        F: FnMut(A) -> B,
+    {
+        let Repetition {
+            tokens,
+            lower,
+            upper,
+            evaluation,
+        } = self;
+        Repetition {
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.map_annotations(f))
+                .collect(),
+            lower,
+            upper,
+            evaluation,
         }
     }
 
     pub fn unannotate(self) -> Repetition<'t, ()> {
+        self.map_annotations(&mut |_| ())
+    }
+
+    pub fn recase(self, is_case_insensitive: bool) -> Self {
         let Repetition {
             tokens,
             lower,
             upper,
+            evaluation,
         } = self;
         Repetition {
-            tokens: tokens.into_iter().map(Token::unannotate).collect(),
+            tokens: tokens
+                .into_iter()
+                .map(|token| token.recase(is_case_insensitive))
+                .collect(),
             lower,
             upper,
+            evaluation,
         }
     }
 
@@ -662,6 +1208,10 @@ impl<'t, A> Repetition<'t, A> {
         self.upper.map_or(false, |upper| self.lower == upper)
     }
 
+    pub fn evaluation(&self) -> Evaluation {
+        self.evaluation
+    }
+
     fn walk(&self) -> Walk<'_, 't, A> {
         Walk::from(&self.tokens)
     }
@@ -669,14 +1219,31 @@ impl<'t, A> Repetition<'t, A> {
 
 impl<'i, 't, A> UnitBreadth for &'i Repetition<'t, A> {
     fn unit_breadth(self) -> Boundedness {
-        self.tokens().iter().composite_breadth()
+        let (_, upper) = self.bounds();
+        // Repetitions with no upper bound match unbounded components unless
+        // each repetition is delimited by a component boundary.
+        if upper.is_none()
+            && !self
+                .tokens()
+                .iter()
+                .any(|token| token.kind().is_component_boundary())
+        {
+            Boundedness::Open
+        }
+        else {
+            self.tokens().iter().composite_breadth()
+        }
     }
 }
 
 impl<'i, 't, A> UnitDepth for &'i Repetition<'t, A> {
     fn unit_depth(self) -> Boundedness {
         let (_, upper) = self.bounds();
-        if upper.is_none() && self.walk().any(|(_, token)| token.is_component_boundary()) {
+        if upper.is_none()
+            && self.walk().any(|(_, token)| {
+                token.is_component_boundary() || token.is_separator_crossing()
+            })
+        {
             Boundedness::Open
         }
         else {
@@ -714,13 +1281,14 @@ where
             })
             .conjunctive_variance();
         match self.upper {
-            // Repeating invariance can cause overflows, very large allocations,
-            // and very inefficient comparisons (e.g., comparing very large
-            // strings). This is detected by both `encode::compile` and
-            // `rule::check` (in distinct but similar ways). Querying token
-            // trees for their invariance must be done with care (after using
-            // these functions) to avoid expanding pathological invariant
-            // expressions like `<long:9999999999999>`.
+            // Repeating invariance can cause overflows. Invariant text is
+            // repeated lazily, but converting it into a string expands the
+            // repetition and can cause very large allocations. This is detected
+            // by both `encode::compile` and `rule::check` (in distinct but
+            // similar ways). Querying token trees for their invariant text must
+            // be done with care (after using these functions) to avoid
+            // expanding pathological invariant expressions like
+            // `<long:9999999999999>`.
             Some(_) if self.is_converged() => {
                 variance.map_invariance(|invariance| invariance * self.lower)
             },
@@ -756,15 +1324,27 @@ impl<'i> UnitVariance<InvariantSize> for &'i Separator {
 
 #[derive(Clone, Debug)]
 pub enum Wildcard {
-    One,
-    ZeroOrMore(Evaluation),
+    One { is_grapheme: bool },
+    ZeroOrMore {
+        evaluation: Evaluation,
+        is_separator_crossing: bool,
+    },
     Tree { has_root: bool },
 }
 
 impl<'i> UnitBreadth for &'i Wildcard {
     fn unit_breadth(self) -> Boundedness {
         match self {
-            Wildcard::One => Boundedness::Closed,
+            // An extended grapheme cluster may be composed of any number of
+            // code points.
+            Wildcard::One { is_grapheme } => {
+                if *is_grapheme {
+                    Boundedness::Open
+                }
+                else {
+                    Boundedness::Closed
+                }
+            },
             _ => Boundedness::Open,
         }
     }
@@ -773,7 +1353,11 @@ impl<'i> UnitBreadth for &'i Wildcard {
 impl<'i> UnitDepth for &'i Wildcard {
     fn unit_depth(self) -> Boundedness {
         match self {
-            Wildcard::Tree { .. } => Boundedness::Open,
+            Wildcard::Tree { .. }
+            | Wildcard::ZeroOrMore {
+                is_separator_crossing: true,
+                ..
+            } => Boundedness::Open,
             _ => Boundedness::Closed,
         }
     }
@@ -918,7 +1502,7 @@ where
 }
 
 #[derive(Clone, Debug)]
-pub struct LiteralSequence<'i, 't>(Vec<&'i Literal<'t>>);
+pub struct LiteralSequence<'i, 't>(SmallVec<[&'i Literal<'t>; 4]>);
 
 impl<'i, 't> LiteralSequence<'i, 't> {
     pub fn literals(&self) -> &[&'i Literal<'t>] {
@@ -950,7 +1534,7 @@ impl<'i, 't> LiteralSequence<'i, 't> {
 }
 
 #[derive(Debug)]
-pub struct Component<'i, 't, A = ()>(Vec<&'i Token<'t, A>>);
+pub struct Component<'i, 't, A = ()>(SmallVec<[&'i Token<'t, A>; 4]>);
 
 impl<'i, 't, A> Component<'i, 't, A> {
     pub fn tokens(&self) -> &[&'i Token<'t, A>] {
@@ -1015,68 +1599,291 @@ where
     }
 }
 
-pub fn components<'i, 't, A, I>(tokens: I) -> impl Iterator<Item = Component<'i, 't, A>>
+pub fn compose<'t, I>(members: I) -> Composition<'t>
+where
+    I: IntoIterator<Item = Member<'t>>,
+{
+    let (members, exclusions): (Vec<_>, Vec<_>) = members.into_iter().unzip();
+    let token = any(members.iter().map(|tree| tree.tokens().to_vec()));
+    Composition {
+        members,
+        exclusions,
+        token,
+    }
+}
+
+pub fn components<'i, 't, A, I>(tokens: I) -> Components<'i, 't, A, I::IntoIter>
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    Components {
+        tokens: tokens.into_iter().peekable(),
+    }
+}
+
+// Expands alternatives that have component boundaries into a sequence of tokens
+// for each of their branches, such that `{a/b,c}/d` is expanded into `a/b/d` and
+// `c/d`. Other tokens are not expanded. Returns `None` if there are more than
+// `limit` sequences.
+#[cfg(feature = "walk")]
+pub fn expand_alternatives<'i, 't, A, I>(
+    tokens: I,
+    limit: usize,
+) -> Option<Vec<Vec<&'i Token<'t, A>>>>
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    let mut sequences = vec![vec![]];
+    for token in tokens {
+        match token.kind() {
+            TokenKind::Alternative(ref alternative) if token.has_component_boundary() => {
+                let mut branches = vec![];
+                for branch in alternative.branches() {
+                    branches.extend(expand_alternatives(branch, limit)?);
+                }
+                if sequences.len() * branches.len() > limit {
+                    return None;
+                }
+                sequences = sequences
+                    .into_iter()
+                    .cartesian_product(branches)
+                    .map(|(mut sequence, branch)| {
+                        sequence.extend(branch);
+                        sequence
+                    })
+                    .collect();
+            },
+            _ => {
+                for sequence in sequences.iter_mut() {
+                    sequence.push(token);
+                }
+            },
+        }
+    }
+    Some(sequences)
+}
+
+pub fn literals<'i, 't, A, I>(tokens: I) -> Literals<'i, 't, A, I::IntoIter>
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    Literals {
+        components: components(tokens),
+        stack: SmallVec::new(),
+    }
+}
+
+// Gets the longest text that begins any text matched by the tokens. Literals are
+// only included if they are matched with case sensitivity and separators are
+// always `/`. Unlike invariant text prefixes, this text is not limited to whole
+// components.
+pub fn literal_text_prefix<'i, 't, A, I>(tokens: I) -> String
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    let mut prefix = String::new();
+    for (n, token) in tokens.into_iter().enumerate() {
+        match token.kind() {
+            TokenKind::Literal(ref literal)
+                if !(literal.is_case_insensitive() && literal.text().has_casing()) =>
+            {
+                prefix.push_str(literal.text());
+            },
+            TokenKind::Separator(_) => {
+                prefix.push('/');
+            },
+            TokenKind::Wildcard(Wildcard::Tree { has_root: true }) if n == 0 => {
+                prefix.push('/');
+                break;
+            },
+            _ => {
+                break;
+            },
+        }
+    }
+    prefix
+}
+
+// Gets the text of the tokens if they consist only of literals that are matched
+// with case sensitivity and separators, which are always `/`. Such tokens match
+// exactly this text (modulo separators).
+pub fn literal_text<'i, 't, A, I>(tokens: I) -> Option<String>
 where
     't: 'i,
     A: 't,
     I: IntoIterator<Item = &'i Token<'t, A>>,
 {
-    tokens.into_iter().peekable().batching(|tokens| {
-        let mut first = tokens.next();
+    tokens
+        .into_iter()
+        .map(|token| match token.kind() {
+            TokenKind::Literal(ref literal)
+                if !(literal.is_case_insensitive() && literal.text().has_casing()) =>
+            {
+                Some(literal.text())
+            },
+            TokenKind::Separator(_) => Some("/"),
+            _ => None,
+        })
+        .collect()
+}
+
+// Estimates the cost of compiling and matching the tokens. The cost is roughly
+// proportional to the number of states in the compiled program: literals cost
+// their number of code points, other leaf tokens cost one, the branches of
+// alternatives are summed, and repetitions multiply their sub-tokens by their
+// upper bound (or lower bound plus one if unbounded), as bounded repetitions
+// are expanded when compiled. Arithmetic saturates, so nested repetitions
+// cannot overflow.
+pub fn cost<'i, 't, A, I>(tokens: I) -> usize
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    tokens
+        .into_iter()
+        .map(|token| match token.kind() {
+            TokenKind::Alternative(ref alternative) => alternative
+                .branches()
+                .iter()
+                .map(cost)
+                .fold(0, usize::saturating_add),
+            TokenKind::Literal(ref literal) => literal.text().chars().count(),
+            TokenKind::Repetition(ref repetition) => {
+                let (lower, upper) = repetition.bounds();
+                let n = upper.unwrap_or_else(|| lower.saturating_add(1));
+                cost(repetition.tokens()).saturating_mul(n.max(1))
+            },
+            TokenKind::Class(_) | TokenKind::Separator(_) | TokenKind::Wildcard(_) => 1,
+        })
+        .fold(0, usize::saturating_add)
+}
+
+#[derive(Clone, Debug)]
+pub struct Components<'i, 't, A, I>
+where
+    't: 'i,
+    A: 't,
+    I: Iterator<Item = &'i Token<'t, A>>,
+{
+    tokens: Peekable<I>,
+}
+
+impl<'i, 't, A, I> Iterator for Components<'i, 't, A, I>
+where
+    't: 'i,
+    A: 't,
+    I: Iterator<Item = &'i Token<'t, A>>,
+{
+    type Item = Component<'i, 't, A>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut first = self.tokens.next();
         while matches!(first.map(Token::kind), Some(TokenKind::Separator(_))) {
-            first = tokens.next();
+            first = self.tokens.next();
         }
         first.map(|first| match first.kind() {
-            TokenKind::Wildcard(Wildcard::Tree { .. }) => Component(vec![first]),
+            TokenKind::Wildcard(Wildcard::Tree { .. }) => Component(smallvec![first]),
             _ => Component(
                 Some(first)
                     .into_iter()
-                    .chain(tokens.peeking_take_while(|token| !token.is_component_boundary()))
+                    .chain(
+                        self.tokens
+                            .peeking_take_while(|token| !token.is_component_boundary()),
+                    )
                     .collect(),
             ),
         })
-    })
+    }
 }
 
-// TODO: This implementation allocates many `Vec`s.
-pub fn literals<'i, 't, A, I>(
-    tokens: I,
-) -> impl Iterator<Item = (Component<'i, 't, A>, LiteralSequence<'i, 't>)>
+// Iterator over the literal components in a token sequence, including the
+// literal components of sub-tokens in alternatives and repetitions.
+//
+// Sub-tokens are traversed depth-first via a stack, so literals are yielded in
+// the order in which they appear in the token sequence without collecting any
+// intermediate literals.
+pub struct Literals<'i, 't, A, I>
 where
     't: 'i,
     A: 't,
-    I: IntoIterator<Item = &'i Token<'t, A>>,
+    I: Iterator<Item = &'i Token<'t, A>>,
 {
-    components(tokens).flat_map(|component| {
-        if let Some(literal) = component.literal() {
-            vec![(component, literal)]
+    components: Components<'i, 't, A, I>,
+    stack: SmallVec<[Components<'i, 't, A, slice::Iter<'i, Token<'t, A>>>; 4]>,
+}
+
+impl<'i, 't, A, I> Literals<'i, 't, A, I>
+where
+    't: 'i,
+    A: 't,
+    I: Iterator<Item = &'i Token<'t, A>>,
+{
+    fn next_component(&mut self) -> Option<Component<'i, 't, A>> {
+        while let Some(components) = self.stack.last_mut() {
+            match components.next() {
+                Some(component) => {
+                    return Some(component);
+                },
+                None => {
+                    self.stack.pop();
+                },
+            }
         }
-        else {
-            component
-                .tokens()
-                .iter()
-                .filter_map(|token| match token.kind() {
-                    TokenKind::Alternative(ref alternative) => Some(
-                        alternative
-                            .branches()
-                            .iter()
-                            .flat_map(literals)
-                            .collect::<Vec<_>>(),
-                    ),
+        self.components.next()
+    }
+}
+
+impl<'i, 't, A, I> Iterator for Literals<'i, 't, A, I>
+where
+    't: 'i,
+    A: 't,
+    I: Iterator<Item = &'i Token<'t, A>>,
+{
+    type Item = (Component<'i, 't, A>, LiteralSequence<'i, 't>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let component = self.next_component()?;
+            if let Some(literal) = component.literal() {
+                return Some((component, literal));
+            }
+            // Push the sub-token sequences of the component onto the stack in
+            // reverse order, so that they are popped in the order in which they
+            // appear.
+            for token in component.tokens().iter().rev() {
+                match token.kind() {
+                    TokenKind::Alternative(ref alternative) => {
+                        self.stack.extend(
+                            alternative
+                                .branches()
+                                .iter()
+                                .rev()
+                                .map(|tokens| components(tokens.iter())),
+                        );
+                    },
                     TokenKind::Repetition(ref repetition) => {
-                        Some(literals(repetition.tokens()).collect::<Vec<_>>())
+                        self.stack.push(components(repetition.tokens().iter()));
                     },
-                    _ => None,
-                })
-                .flatten()
-                .collect::<Vec<_>>()
+                    _ => {},
+                }
+            }
         }
-    })
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::token::{self, TokenKind, TokenTree};
+    use crate::PATHS_ARE_CASE_INSENSITIVE;
 
     #[test]
     fn literal_case_insensitivity() {
@@ -1096,4 +1903,93 @@ mod tests {
         assert!(!literals[3].is_case_insensitive); // `baz`
         assert!(literals[4].is_case_insensitive); // `qux`
     }
+
+    #[test]
+    fn recase_literals_without_flags() {
+        let tokenized = token::parse("foo/(?i:bar)/{baz,<qux/>}/(?-i)*.txt")
+            .unwrap()
+            .recase(!PATHS_ARE_CASE_INSENSITIVE);
+        let is_case_insensitive = |text| {
+            tokenized
+                .walk()
+                .find_map(|(_, token)| match token.kind {
+                    TokenKind::Literal(ref literal) if literal.text() == text => {
+                        Some(literal.is_case_insensitive)
+                    },
+                    _ => None,
+                })
+                .unwrap()
+        };
+
+        assert_eq!(is_case_insensitive("foo"), !PATHS_ARE_CASE_INSENSITIVE);
+        assert!(is_case_insensitive("bar"));
+        assert_eq!(is_case_insensitive("baz"), !PATHS_ARE_CASE_INSENSITIVE);
+        assert_eq!(is_case_insensitive("qux"), !PATHS_ARE_CASE_INSENSITIVE);
+        assert!(!is_case_insensitive(".txt"));
+    }
+
+    #[test]
+    fn literals_in_order() {
+        let tokenized = token::parse("a/{b/*,c/{d,e*}}/<f/:1,2>*/g").unwrap();
+        let literals: Vec<_> = token::literals(tokenized.tokens())
+            .map(|(_, literal)| literal.text().into_owned())
+            .collect();
+        assert_eq!(literals, ["a", "b", "c", "d", "f", "g"]);
+    }
+
+    #[cfg(feature = "walk")]
+    #[test]
+    fn expand_alternatives_with_component_boundaries() {
+        let tokenized = token::parse("a/{b/*,c/{d,e/f}}/{g,h}").unwrap();
+        let sequences: Vec<_> = token::expand_alternatives(tokenized.tokens(), 8)
+            .unwrap()
+            .into_iter()
+            .map(|sequence| {
+                token::literals(sequence)
+                    .map(|(_, literal)| literal.text().into_owned())
+                    .collect::<Vec<_>>()
+            })
+            .collect();
+        // Alternatives without component boundaries, like `{g,h}`, are not
+        // expanded.
+        assert_eq!(
+            sequences,
+            [
+                vec!["a", "b", "g", "h"],
+                vec!["a", "c", "d", "g", "h"],
+                vec!["a", "c", "e", "f", "g", "h"],
+            ],
+        );
+
+        assert!(token::expand_alternatives(tokenized.tokens(), 2).is_none());
+    }
+
+    #[test]
+    fn map_annotations_in_depth_first_order() {
+        let tokenized = token::parse("a{b,c}/<d:2>").unwrap();
+        let mut spans = vec![];
+        let tokenized = tokenized.map_annotations(|span| {
+            spans.push(span);
+            spans.len() - 1
+        });
+        let ids: Vec<_> = tokenized
+            .walk()
+            .filter(|(position, _)| position.depth() == 0)
+            .map(|(_, token)| *token.annotation())
+            .collect();
+
+        assert_eq!(spans, [(0, 1), (1, 5), (2, 1), (4, 1), (6, 1), (7, 5), (8, 1)]);
+        assert_eq!(ids, [0, 1, 4, 5]);
+    }
+
+    #[test]
+    fn cost_of_tokens() {
+        let cost = |expression| token::cost(token::parse(expression).unwrap().tokens());
+
+        assert_eq!(cost("a/*.rs"), 6);
+        assert_eq!(cost("{a,bc}"), 3);
+        assert_eq!(cost("<ab:2,>"), 6);
+        assert_eq!(cost("<{a,b,c,d}<x:0,100>:0,100>"), 10_400);
+        assert_eq!(cost("<<<a:1000>:1000>:1000>"), 1_000_000_000);
+    }
 }
@@ -1,91 +1,99 @@
 #[cfg(feature = "miette")]
 use miette::{self, Diagnostic, LabeledSpan, SourceCode};
-use nom::{InputTakeAtPosition, FindToken};
-use nom::character::complete::none_of;
-use nom::error::{VerboseError as NomError, VerboseErrorKind as NomErrorKind, ErrorKind};
-use pori::{Located, Location, Stateful};
 use std::borrow::Cow;
 use std::fmt::{self, Display, Formatter};
-use std::str::FromStr;
+use std::mem;
 use thiserror::Error;
 
 use crate::diagnostics::{LocatedError, Span};
+use crate::token::collate;
 use crate::token::{
-    Alternative, Archetype, Class, Evaluation, Literal, Repetition, Separator, Token, TokenKind,
-    Tokenized, Wildcard,
+    Alternative, Archetype, CaseFolding, Class, Evaluation, Literal, Repetition, Separator, Token,
+    TokenKind, Tokenized, Wildcard,
 };
 use crate::PATHS_ARE_CASE_INSENSITIVE;
 
 pub type Annotation = Span;
 
-type Expression<'i> = Located<'i, str>;
-type Input<'i> = Stateful<Expression<'i>, ParserState>;
-type ErrorStack<'i> = NomError<Input<'i>>;
-type ErrorMode<'i> = nom::Err<ErrorStack<'i>>;
+type ParseResult<T> = Result<T, Vec<ErrorEntry>>;
 
 pub const ROOT_SEPARATOR_EXPRESSION: &str = "/";
 
-#[derive(Clone, Debug)]
-pub struct ErrorEntry<'t> {
-    fragment: Cow<'t, str>,
-    location: usize,
-    kind: NomErrorKind,
+// Characters that are never literals unless escaped.
+const LITERAL_EXCLUSIONS: &str = "/?*$<>()[]{}\\";
+
+#[derive(Clone, Copy, Debug)]
+enum ErrorKind {
+    // The group that encloses an error, such as an alternative.
+    Context(&'static str),
+    Expected {
+        expected: &'static str,
+        found: Option<char>,
+    },
+    AdjacentZeroOrMore,
+    EmptyAlternative,
+    InvalidEscape,
+    NestedFlagScopes,
+    RepetitionBoundOverflow,
+    UnbalancedFlagScopes,
+    UnclosedFlagScope,
 }
 
-impl<'t> ErrorEntry<'t> {
-    pub fn into_owned(self) -> ErrorEntry<'static> {
-        let ErrorEntry {
-            fragment,
-            location,
-            kind,
-        } = self;
-        ErrorEntry {
-            fragment: fragment.into_owned().into(),
-            location,
-            kind,
+impl Display for ErrorKind {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            ErrorKind::Context(context) => write!(f, "in {}", context),
+            ErrorKind::Expected {
+                expected,
+                found: Some(found),
+            } => write!(f, "expected {}, got `{}`", expected, found),
+            ErrorKind::Expected {
+                expected,
+                found: None,
+            } => write!(f, "expected {}, got end of input", expected),
+            ErrorKind::AdjacentZeroOrMore => write!(f, "adjacent zero-or-more wildcards"),
+            ErrorKind::EmptyAlternative => write!(f, "alternative has no non-empty branches"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            ErrorKind::NestedFlagScopes => write!(f, "scoped flag groups nested too deeply"),
+            ErrorKind::RepetitionBoundOverflow => write!(f, "repetition bound is too large"),
+            ErrorKind::UnbalancedFlagScopes => write!(
+                f,
+                "scoped flag groups must be closed in the sub-glob in which they are opened",
+            ),
+            ErrorKind::UnclosedFlagScope => write!(f, "unclosed scoped flag group"),
         }
     }
 }
 
-impl<'t> From<(Input<'t>, NomErrorKind)> for ErrorEntry<'t> {
-    fn from((input, kind): (Input<'t>, NomErrorKind)) -> Self {
-        let location = input.location();
-        ErrorEntry {
-            fragment: input.into_data().into(),
-            location,
-            kind,
-        }
+#[derive(Clone, Debug)]
+pub struct ErrorEntry {
+    span: Span,
+    kind: ErrorKind,
+}
+
+impl ErrorEntry {
+    fn new(span: Span, kind: ErrorKind) -> Self {
+        ErrorEntry { span, kind }
     }
 }
 
 #[cfg(feature = "miette")]
-impl From<ErrorEntry<'_>> for LabeledSpan {
-    fn from(error: ErrorEntry<'_>) -> Self {
+impl From<ErrorEntry> for LabeledSpan {
+    fn from(error: ErrorEntry) -> Self {
         let span = error.span();
         LabeledSpan::new_with_span(Some(format!("{}", error)), span)
     }
 }
 
-impl Display for ErrorEntry<'_> {
+impl Display for ErrorEntry {
     fn fmt(&self, f: &mut Formatter) -> fmt::Result {
-        match self.kind {
-            NomErrorKind::Char(expected) => {
-                if let Some(got) = self.fragment.chars().next() {
-                    write!(f, "expected `{}`, got `{}`", expected, got)
-                }
-                else {
-                    write!(f, "expected `{}`, got end of input", expected)
-                }
-            },
-            NomErrorKind::Context(context) => write!(f, "in context `{}`", context),
-            NomErrorKind::Nom(parser) => write!(f, "in sub-parser `{:?}`", parser),
-        }
+        write!(f, "{}", self.kind)
     }
 }
 
-impl LocatedError for ErrorEntry<'_> {
+impl LocatedError for ErrorEntry {
     fn span(&self) -> Span {
-        (self.location, 1)
+        self.span
     }
 }
 
@@ -98,19 +106,14 @@ impl LocatedError for ErrorEntry<'_> {
 #[error("failed to parse glob expression")]
 pub struct ParseError<'t> {
     expression: Cow<'t, str>,
-    locations: Vec<ErrorEntry<'t>>,
+    locations: Vec<ErrorEntry>,
 }
 
 impl<'t> ParseError<'t> {
-    fn new(expression: &'t str, error: ErrorMode<'t>) -> Self {
-        match error {
-            ErrorMode::Incomplete(_) => {
-                panic!("unexpected parse error: incomplete input")
-            },
-            ErrorMode::Error(stack) | ErrorMode::Failure(stack) => ParseError {
-                expression: expression.into(),
-                locations: stack.errors.into_iter().map(From::from).collect(),
-            },
+    fn new(expression: &'t str, locations: Vec<ErrorEntry>) -> Self {
+        ParseError {
+            expression: expression.into(),
+            locations,
         }
     }
 
@@ -122,11 +125,27 @@ impl<'t> ParseError<'t> {
         } = self;
         ParseError {
             expression: expression.into_owned().into(),
-            locations: locations.into_iter().map(ErrorEntry::into_owned).collect(),
+            locations,
         }
     }
 
-    pub fn locations(&self) -> &[ErrorEntry<'t>] {
+    // Replaces the glob expression and maps the spans of locations into it.
+    pub(crate) fn map_spans(
+        self,
+        expression: &str,
+        f: &impl Fn(Span) -> Span,
+    ) -> ParseError<'static> {
+        ParseError {
+            expression: expression.to_owned().into(),
+            locations: self
+                .locations
+                .into_iter()
+                .map(|entry| ErrorEntry::new(f(entry.span), entry.kind))
+                .collect(),
+        }
+    }
+
+    pub fn locations(&self) -> &[ErrorEntry] {
         &self.locations
     }
 
@@ -152,413 +171,804 @@ impl Diagnostic for ParseError<'_> {
     }
 }
 
+/// Maximum depth of nested scoped flag groups, such as `(?i:...)`.
+const MAX_FLAG_SCOPE_DEPTH: usize = 16;
+
 #[derive(Clone, Copy, Debug, Default)]
 struct ParserState {
     flags: FlagState,
-    subexpression: usize,
+    scopes: FlagScopes,
+    // The depth of scoped flag groups when the innermost optional group was
+    // opened. Scoped flag groups that are opened outside of an optional group
+    // cannot be closed within it, so `)` closes the optional group instead.
+    floor: usize,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 struct FlagState {
     is_case_insensitive: bool,
+    // Whether case sensitivity is set by a flag rather than the default.
+    is_case_explicit: bool,
+    is_grapheme: bool,
+    is_separator_crossing: bool,
 }
 
 impl Default for FlagState {
     fn default() -> Self {
         FlagState {
             is_case_insensitive: PATHS_ARE_CASE_INSENSITIVE,
+            is_case_explicit: false,
+            is_grapheme: false,
+            is_separator_crossing: false,
         }
     }
 }
 
+impl FlagState {
+    // Gets the toggles in a flag group that change these flags into the given
+    // flags, such as `i-s`. This is empty if the flags are the same.
+    fn toggles(&self, flags: &FlagState) -> String {
+        // Case sensitivity is toggled if it becomes explicit, even if it does
+        // not change.
+        let is_case_toggled = flags.is_case_explicit
+            && (!self.is_case_explicit || self.is_case_insensitive != flags.is_case_insensitive);
+        [
+            ('i', is_case_toggled, flags.is_case_insensitive),
+            (
+                'g',
+                self.is_grapheme != flags.is_grapheme,
+                flags.is_grapheme,
+            ),
+            (
+                's',
+                self.is_separator_crossing != flags.is_separator_crossing,
+                flags.is_separator_crossing,
+            ),
+        ]
+        .iter()
+        .filter(|(_, is_toggled, _)| *is_toggled)
+        .map(|(flag, _, is_enabled)| {
+            if *is_enabled {
+                flag.to_string()
+            }
+            else {
+                format!("-{}", flag)
+            }
+        })
+        .collect()
+    }
+}
+
+// Flags that are restored when scoped flag groups are closed. This is a stack
+// with a fixed capacity so that the parser is `Copy` and can be cheaply
+// restored when backtracking.
+#[derive(Clone, Copy, Debug, Default)]
+struct FlagScopes {
+    depth: usize,
+    flags: [FlagState; MAX_FLAG_SCOPE_DEPTH],
+    // The offsets of the scoped flag groups in the expression.
+    offsets: [usize; MAX_FLAG_SCOPE_DEPTH],
+}
+
+impl ParserState {
+    // Gets flag groups that establish these flags and open these scopes when
+    // they begin an expression. Scopes are opened but never closed, so the
+    // flag groups must be followed by the text that closes them.
+    fn expression(&self) -> String {
+        let mut expression = String::new();
+        let mut flags = FlagState::default();
+        let targets = self.scopes.flags[..self.scopes.depth]
+            .iter()
+            .chain(Some(&self.flags));
+        for (n, target) in targets.enumerate() {
+            let mut toggles = flags.toggles(target);
+            if n == 0 {
+                if !toggles.is_empty() {
+                    expression.push_str(&format!("(?{})", toggles));
+                }
+            }
+            else {
+                if toggles.is_empty() {
+                    // Scoped flag groups must toggle at least one flag, so
+                    // toggle a flag to its current state.
+                    toggles.push_str(if target.is_grapheme { "g" } else { "-g" });
+                }
+                expression.push_str(&format!("(?{}:", toggles));
+            }
+            flags = *target;
+        }
+        expression
+    }
+}
+
+impl FlagScopes {
+    fn push(&mut self, flags: FlagState, offset: usize) -> bool {
+        if self.depth < MAX_FLAG_SCOPE_DEPTH {
+            self.flags[self.depth] = flags;
+            self.offsets[self.depth] = offset;
+            self.depth += 1;
+            true
+        }
+        else {
+            false
+        }
+    }
+
+    fn pop(&mut self) -> Option<FlagState> {
+        self.depth = self.depth.checked_sub(1)?;
+        Some(self.flags[self.depth])
+    }
+}
+
+// Text that terminates a sub-glob. Sub-globs in groups are terminated by the
+// delimiters of the group, which are not consumed by the sub-glob.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Terminator {
+    End,
+    Alternative,
+    Optional,
+    Repetition,
+}
+
+impl Terminator {
+    fn is_terminated(self, text: &str) -> bool {
+        match self {
+            Terminator::End => text.is_empty(),
+            Terminator::Alternative => text.starts_with([',', '}']),
+            Terminator::Optional => text.starts_with(')'),
+            Terminator::Repetition => text.starts_with([':', '>']),
+        }
+    }
+
+    fn is_literal(self, x: char) -> bool {
+        !LITERAL_EXCLUSIONS.contains(x)
+            && match self {
+                Terminator::Alternative => x != ',',
+                Terminator::Repetition => x != ':',
+                _ => true,
+            }
+    }
+
+    fn expectation(self) -> &'static str {
+        match self {
+            Terminator::End => "end of expression",
+            Terminator::Alternative => "`,` or `}`",
+            Terminator::Optional => "`)`",
+            Terminator::Repetition => "`:` or `>`",
+        }
+    }
+}
+
+// Recursive descent parser over a glob expression.
+//
+// The parser is `Copy`, so it backtracks by restoring a copy of itself. This
+// only occurs for short and bounded look-ahead, such as determining whether
+// `**` is a tree wildcard. Otherwise, any error is fatal and is returned with
+// the groups that enclose it.
 #[derive(Clone, Copy, Debug)]
-enum FlagToggle {
-    CaseInsensitive(bool),
+struct Parser<'t> {
+    expression: &'t str,
+    offset: usize,
+    folding: CaseFolding,
+    state: ParserState,
 }
 
-pub fn parse(expression: &str) -> Result<Tokenized, ParseError> {
-    use nom::bytes::complete as bytes;
-    use nom::character::complete as character;
-    use nom::error;
-    use nom::{branch, combinator, multi, sequence, IResult, Parser};
+impl<'t> Parser<'t> {
+    fn new(expression: &'t str, folding: CaseFolding) -> Self {
+        Parser {
+            expression,
+            offset: 0,
+            folding,
+            state: ParserState::default(),
+        }
+    }
 
-    use crate::token::parse::FlagToggle::CaseInsensitive;
+    fn rest(&self) -> &'t str {
+        &self.expression[self.offset..]
+    }
 
-    type ParseResult<'i, O> = IResult<Input<'i>, O, ErrorStack<'i>>;
+    fn peek(&self) -> Option<char> {
+        self.rest().chars().next()
+    }
 
-    fn boe(input: Input) -> ParseResult<Input> {
-        if input.state.subexpression == input.location() {
-            Ok((input, input))
+    fn eat(&mut self, text: &str) -> bool {
+        if self.rest().starts_with(text) {
+            self.offset += text.len();
+            true
         }
         else {
-            Err(ErrorMode::Error(ErrorStack {
-                errors: vec![(input, NomErrorKind::Context("beginning of expression"))],
-            }))
-        }
-    }
-
-    fn flags<'i, F>(
-        mut toggle: impl FnMut(FlagToggle) -> F,
-    ) -> impl FnMut(Input<'i>) -> ParseResult<'i, ()>
-    where
-        F: Parser<Input<'i>, (), ErrorStack<'i>>,
-    {
-        move |input| {
-            let (input, _) = multi::many0(sequence::delimited(
-                bytes::tag("(?"),
-                multi::many1(branch::alt((
-                    sequence::tuple((bytes::tag("i"), toggle(CaseInsensitive(true)))),
-                    sequence::tuple((bytes::tag("-i"), toggle(CaseInsensitive(false)))),
-                ))),
-                bytes::tag(")"),
-            ))(input)?;
-            Ok((input, ()))
-        }
-    }
-
-    // Explicit lifetimes prevent inference errors.
-    #[allow(clippy::needless_lifetimes)]
-    fn flags_with_state<'i>(input: Input<'i>) -> ParseResult<'i, ()> {
-        flags(move |toggle| {
-            move |mut input: Input<'i>| {
-                match toggle {
-                    CaseInsensitive(toggle) => {
-                        input.state.flags.is_case_insensitive = toggle;
-                    },
-                }
-                Ok((input, ()))
+            false
+        }
+    }
+
+    fn eat_char(&mut self) -> Option<char> {
+        let x = self.peek()?;
+        self.offset += x.len_utf8();
+        Some(x)
+    }
+
+    fn eat_while(&mut self, f: impl Fn(char) -> bool) -> &'t str {
+        let rest = self.rest();
+        let n = rest.find(|x| !f(x)).unwrap_or(rest.len());
+        self.offset += n;
+        &rest[..n]
+    }
+
+    fn expected(&self, expected: &'static str) -> Vec<ErrorEntry> {
+        let found = self.peek();
+        vec![ErrorEntry::new(
+            (self.offset, found.map_or(0, char::len_utf8)),
+            ErrorKind::Expected { expected, found },
+        )]
+    }
+
+    // Returns `true` if the terminator follows any closing scoped flag groups.
+    fn is_terminated(&self, terminator: Terminator) -> bool {
+        let mut parser = *self;
+        while parser.close_scope() {}
+        terminator.is_terminated(parser.rest())
+    }
+
+    fn glob(&mut self, terminator: Terminator) -> ParseResult<Vec<Token<'t, Annotation>>> {
+        let start = self.offset;
+        // Scoped flag groups must be closed in the same sub-glob in which they
+        // are opened.
+        let depth = self.state.scopes.depth;
+        let mut tokens = vec![];
+        loop {
+            let checkpoint = *self;
+            self.flags();
+            // Flags that begin a sub-glob also begin its initial token, such
+            // as in `(?i)**/a`.
+            let initial = if tokens.is_empty() { self.offset } else { start };
+            match self.token(initial, terminator)? {
+                Some(kind) => {
+                    let span = (checkpoint.offset, self.offset - checkpoint.offset);
+                    tokens.push(Token::new(kind, span));
+                },
+                None => {
+                    if self.rest().starts_with("(?") {
+                        return Err(self.flags_error());
+                    }
+                    else if tokens.is_empty() {
+                        return Err(self.expected("a glob expression"));
+                    }
+                    *self = checkpoint;
+                    break;
+                },
             }
-        })(input)
+        }
+        while self.close_scope() {}
+        match self.state.scopes.depth {
+            n if n > depth => Err(vec![ErrorEntry::new(
+                (self.state.scopes.offsets[n - 1], 2),
+                ErrorKind::UnclosedFlagScope,
+            )]),
+            n if n < depth => Err(vec![ErrorEntry::new(
+                (start, self.offset - start),
+                ErrorKind::UnbalancedFlagScopes,
+            )]),
+            _ if !terminator.is_terminated(self.rest()) => {
+                Err(self.expected(terminator.expectation()))
+            },
+            _ => Ok(tokens),
+        }
     }
 
-    // Explicit lifetimes prevent inference errors.
-    #[allow(clippy::needless_lifetimes)]
-    fn flags_without_state<'i>(input: Input<'i>) -> ParseResult<'i, ()> {
-        flags(move |_| move |input: Input<'i>| Ok((input, ())))(input)
+    fn token(
+        &mut self,
+        start: usize,
+        terminator: Terminator,
+    ) -> ParseResult<Option<TokenKind<'t, Annotation>>> {
+        let x = match self.peek() {
+            Some(x) => x,
+            _ => return Ok(None),
+        };
+        match x {
+            '<' => self.repetition().map(Some),
+            '{' => self.alternative().map(Some),
+            '(' if !self.rest().starts_with("(?") => self.optional().map(Some),
+            '?' => {
+                self.offset += 1;
+                let is_grapheme = self.state.flags.is_grapheme;
+                Ok(Some(Wildcard::One { is_grapheme }.into()))
+            },
+            '*' | '$' | '/' => self.wildcard(start, terminator).map(Some),
+            '[' => self.class().map(Some),
+            '\\' => self.literal(terminator).map(Some),
+            x if terminator.is_literal(x) => self.literal(terminator).map(Some),
+            _ => Ok(None),
+        }
     }
 
+    fn flags(&mut self) {
+        while self.flag_group() || self.close_scope() {}
+    }
 
-    fn literal<'i>(
-        cant_contain: &'i str,
-    ) -> impl FnMut(Input<'i>) -> ParseResult<'i, TokenKind<'i, Annotation>>
-    {
-        fn is_not_both<'i>(
-            arr1: &'i str,
-            arr2: &'i str,
-        ) -> impl Fn(Input<'i>) -> ParseResult<'i,Input<'i>>
-        {
-            move |i: Input| {
-                let e: ErrorKind = ErrorKind::IsNot;
-                i.split_at_position1_complete(|c| arr1.find_token(c) || arr2.find_token(c), e)
+    // Parses a flag group, such as `(?i)` or `(?-s:`. Flags are saved before
+    // they are toggled. Scoped flags, such as `(?i:...)`, restore these flags
+    // when the group is closed. Otherwise, the saved flags are discarded.
+    fn flag_group(&mut self) -> bool {
+        if !self.rest().starts_with("(?") {
+            return false;
+        }
+        let checkpoint = *self;
+        if !self.state.scopes.push(self.state.flags, self.offset) {
+            return false;
+        }
+        self.offset += 2;
+        let mut is_toggled = false;
+        loop {
+            let (is_enabled, rest) = match self.rest().strip_prefix('-') {
+                Some(rest) => (false, rest),
+                _ => (true, self.rest()),
+            };
+            let flag = match rest.chars().next() {
+                Some('i') => &mut self.state.flags.is_case_insensitive,
+                Some('g') => &mut self.state.flags.is_grapheme,
+                Some('s') => &mut self.state.flags.is_separator_crossing,
+                _ => break,
+            };
+            *flag = is_enabled;
+            if rest.starts_with('i') {
+                self.state.flags.is_case_explicit = true;
             }
+            self.offset += if is_enabled { 1 } else { 2 };
+            is_toggled = true;
         }
+        if is_toggled && self.eat(":") {
+            true
+        }
+        else if is_toggled && self.eat(")") {
+            self.state.scopes.pop();
+            true
+        }
+        else {
+            *self = checkpoint;
+            false
+        }
+    }
 
-        move |input: Input| {
-            combinator::map(
-                combinator::verify(
-                    bytes::escaped_transform(
-                        is_not_both("/?*$<>()[]{}\\", cant_contain),
-                        '\\',
-                        branch::alt((
-                            combinator::value("?", bytes::tag("?")),
-                            combinator::value("*", bytes::tag("*")),
-                            combinator::value("$", bytes::tag("$")),
-                            combinator::value("<", bytes::tag("<")),
-                            combinator::value(">", bytes::tag(">")),
-                            combinator::value("(", bytes::tag("(")),
-                            combinator::value(")", bytes::tag(")")),
-                            combinator::value("[", bytes::tag("[")),
-                            combinator::value("]", bytes::tag("]")),
-                            combinator::value("{", bytes::tag("{")),
-                            combinator::value("}", bytes::tag("}")),
-                            combinator::value(",", bytes::tag(",")),
-                            combinator::value(":", bytes::tag(":")),
-                        )),
-                    ),
-                    |text: &str| !text.is_empty(),
-                ),
-                move |text| {
-                    TokenKind::Literal(Literal {
-                        text: text.into(),
-                        is_case_insensitive: input.state.flags.is_case_insensitive,
-                    })
-                },
-            )(input)
+    // Closes a scoped flag group, restoring the flags from before the group
+    // was opened.
+    fn close_scope(&mut self) -> bool {
+        if self.state.scopes.depth <= self.state.floor || !self.eat(")") {
+            return false;
+        }
+        if let Some(flags) = self.state.scopes.pop() {
+            self.state.flags = flags;
+        }
+        true
+    }
+
+    // Describes an invalid flag group at the current offset.
+    fn flags_error(&self) -> Vec<ErrorEntry> {
+        if self.state.scopes.depth >= MAX_FLAG_SCOPE_DEPTH {
+            return vec![ErrorEntry::new(
+                (self.offset, 2),
+                ErrorKind::NestedFlagScopes,
+            )];
         }
+        let mut parser = *self;
+        parser.offset += 2;
+        let mut is_toggled = false;
+        loop {
+            let mut next = parser;
+            let is_negated = next.eat("-");
+            if matches!(next.peek(), Some('i' | 'g' | 's')) {
+                next.offset += 1;
+                parser = next;
+                is_toggled = true;
+            }
+            else if is_negated {
+                return next.expected("a flag");
+            }
+            else {
+                break;
+            }
+        }
+        parser.expected(if is_toggled {
+            "a flag, `:`, or `)`"
+        }
+        else {
+            "a flag"
+        })
     }
 
-    fn separator(input: Input) -> ParseResult<TokenKind<Annotation>> {
-        combinator::value(TokenKind::Separator(Separator), bytes::tag("/"))(input)
+    fn literal(&mut self, terminator: Terminator) -> ParseResult<TokenKind<'t, Annotation>> {
+        let FlagState {
+            is_case_insensitive,
+            is_case_explicit,
+            ..
+        } = self.state.flags;
+        let start = self.offset;
+        // Literals are borrowed from the expression unless they contain escape
+        // sequences.
+        let mut text: Option<String> = None;
+        let mut run = start;
+        loop {
+            self.eat_while(|x| terminator.is_literal(x));
+            let escape = self.offset;
+            if !self.eat("\\") {
+                break;
+            }
+            let text = text.get_or_insert_with(String::new);
+            text.push_str(&self.expression[run..escape]);
+            text.push(self.escaped_literal().ok_or_else(|| {
+                let n = 1 + self.expression[escape + 1..]
+                    .chars()
+                    .next()
+                    .map_or(0, char::len_utf8);
+                vec![ErrorEntry::new((escape, n), ErrorKind::InvalidEscape)]
+            })?);
+            run = self.offset;
+        }
+        let text = match text {
+            Some(mut text) => {
+                text.push_str(&self.expression[run..self.offset]);
+                text.into()
+            },
+            _ => self.expression[start..self.offset].into(),
+        };
+        Ok(TokenKind::Literal(Literal {
+            text,
+            is_case_insensitive,
+            is_case_explicit,
+            folding: self.folding,
+        }))
     }
 
-    fn wildcard<'i>(
-        terminator: impl Clone + Parser<Input<'i>, Input<'i>, ErrorStack<'i>>,
-    ) -> impl FnMut(Input<'i>) -> ParseResult<'i, TokenKind<'i, Annotation>> {
-        branch::alt((
-            error::context(
-                "exactly-one",
-                combinator::map(bytes::tag("?"), |_| TokenKind::from(Wildcard::One)),
-            ),
-            error::context(
-                "tree",
-                combinator::map(
-                    sequence::tuple((
-                        error::context(
-                            "prefix",
-                            combinator::map(
-                                branch::alt((
-                                    sequence::tuple((
-                                        combinator::value(true, bytes::tag("/")),
-                                        flags_with_state,
-                                    )),
-                                    sequence::tuple((
-                                        combinator::value(false, boe),
-                                        flags_with_state,
-                                    )),
-                                )),
-                                |(prefix, _)| prefix,
-                            ),
-                        ),
-                        sequence::terminated(
-                            bytes::tag("**"),
-                            error::context(
-                                "postfix",
-                                branch::alt((
-                                    combinator::map(
-                                        sequence::tuple((flags_with_state, bytes::tag("/"))),
-                                        |(_, postfix)| postfix,
-                                    ),
-                                    terminator.clone(),
-                                )),
-                            ),
-                        ),
-                    )),
-                    |(has_root, _)| Wildcard::Tree { has_root }.into(),
-                ),
-            ),
-            error::context(
-                "zero-or-more",
-                combinator::map(
-                    sequence::terminated(
-                        bytes::tag("*"),
-                        branch::alt((
-                            combinator::map(
-                                combinator::peek(sequence::tuple((
-                                    flags_without_state,
-                                    error::context("no terminating wildcard", bytes::is_not("*$")),
-                                ))),
-                                |(_, right)| right,
-                            ),
-                            terminator.clone(),
-                        )),
-                    ),
-                    |_| Wildcard::ZeroOrMore(Evaluation::Eager).into(),
-                ),
-            ),
-            error::context(
-                "zero-or-more",
-                combinator::map(
-                    sequence::terminated(
-                        bytes::tag("$"),
-                        branch::alt((
-                            combinator::map(
-                                combinator::peek(sequence::tuple((
-                                    flags_without_state,
-                                    error::context("no terminating wildcard", bytes::is_not("*$")),
-                                ))),
-                                |(_, right)| right,
-                            ),
-                            terminator,
-                        )),
-                    ),
-                    |_| Wildcard::ZeroOrMore(Evaluation::Lazy).into(),
-                ),
-            ),
-        ))
-    }
-
-    fn repetition(input: Input) -> ParseResult<TokenKind<Annotation>> {
-        fn bounds(input: Input) -> ParseResult<(usize, Option<usize>)> {
-            type BoundResult<T> = Result<T, <usize as FromStr>::Err>;
-
-            branch::alt((
-                sequence::preceded(
-                    bytes::tag(":"),
-                    branch::alt((
-                        error::context(
-                            "range",
-                            combinator::map_res(
-                                sequence::separated_pair(
-                                    character::digit1,
-                                    bytes::tag(","),
-                                    combinator::opt(character::digit1),
-                                ),
-                                |(lower, upper): (Input, Option<_>)| -> BoundResult<_> {
-                                    let lower = lower.parse::<usize>()?;
-                                    let upper =
-                                        upper.map(|upper| upper.parse::<usize>()).transpose()?;
-                                    Ok((lower, upper))
-                                },
-                            ),
-                        ),
-                        error::context(
-                            "converged",
-                            combinator::map_res(character::digit1, |n: Input| -> BoundResult<_> {
-                                let n = n.parse::<usize>()?;
-                                Ok((n, Some(n)))
-                            }),
-                        ),
-                        combinator::success((1, None)),
-                    )),
-                ),
-                combinator::success((0, None)),
-            ))(input)
-        }
-
-        combinator::map(
-            sequence::delimited(
-                bytes::tag("<"),
-                sequence::tuple((
-                    error::context(
-                        "sub-glob",
-                        glob(move |input| {
-                            combinator::peek(branch::alt((bytes::tag(":"), bytes::tag(">"))))(input)
-                        }, ":"),
-                    ),
-                    error::context("bounds", bounds),
-                )),
-                bytes::tag(">"),
-            ),
-            |(tokens, (lower, upper))| {
-                Repetition {
-                    tokens,
-                    lower,
-                    upper,
-                }
-                .into()
+    // Parses the escaped character in a literal. The leading backslash is not
+    // parsed.
+    fn escaped_literal(&mut self) -> Option<char> {
+        match self.peek()? {
+            x @ ('?' | '*' | '$' | '<' | '>' | '(' | ')' | '[' | ']' | '{' | '}' | ',' | ':') => {
+                self.offset += 1;
+                Some(x)
             },
-        )(input)
-    }
-
-    fn class(input: Input) -> ParseResult<TokenKind<Annotation>> {
-        fn archetypes(input: Input) -> ParseResult<Vec<Archetype>> {
-            let escaped_character = |input| {
-                branch::alt((
-                    character::none_of("[]-\\"),
-                    branch::alt((
-                        combinator::value('[', bytes::tag("\\[")),
-                        combinator::value(']', bytes::tag("\\]")),
-                        combinator::value('-', bytes::tag("\\-")),
-                    )),
-                ))(input)
-            };
+            // Separators and back slashes are never literals, even if they are
+            // escaped as code points.
+            _ => self
+                .escaped_code_point()
+                .filter(|x| !matches!(x, '/' | '\\')),
+        }
+    }
 
-            multi::many1(branch::alt((
-                combinator::map(
-                    sequence::separated_pair(escaped_character, bytes::tag("-"), escaped_character),
-                    Archetype::from,
-                ),
-                combinator::map(escaped_character, Archetype::from),
-            )))(input)
+    // Parses escaped control characters and code points, such as `\t` and
+    // `\u{1F600}`. The leading backslash is not parsed.
+    fn escaped_code_point(&mut self) -> Option<char> {
+        if self.eat("t") {
+            Some('\t')
+        }
+        else if self.eat("n") {
+            Some('\n')
         }
+        else if self.eat("x{") || self.eat("u{") {
+            let digits = self.eat_while(|x| x.is_ascii_hexdigit());
+            let x = u32::from_str_radix(digits, 16)
+                .ok()
+                .and_then(char::from_u32)?;
+            self.eat("}").then(|| x)
+        }
+        else {
+            None
+        }
+    }
 
-        combinator::map(
-            sequence::delimited(
-                bytes::tag("["),
-                sequence::tuple((combinator::opt(bytes::tag("!")), archetypes)),
-                bytes::tag("]"),
-            ),
-            |(negation, archetypes)| {
-                Class {
-                    is_negated: negation.is_some(),
-                    archetypes,
+    fn wildcard(
+        &mut self,
+        start: usize,
+        terminator: Terminator,
+    ) -> ParseResult<TokenKind<'t, Annotation>> {
+        if let Some(tree) = self.tree(start, terminator) {
+            return Ok(tree);
+        }
+        let offset = self.offset;
+        let evaluation = match self.eat_char() {
+            Some('/') => return Ok(TokenKind::Separator(Separator)),
+            Some('*') => Evaluation::Eager,
+            _ => Evaluation::Lazy,
+        };
+        // Zero-or-more wildcards must be followed by something other than
+        // another zero-or-more wildcard (ignoring any flags) or the
+        // terminator.
+        let mut next = *self;
+        next.flags();
+        match next.peek() {
+            Some('*' | '$') => {
+                return Err(vec![ErrorEntry::new(
+                    (offset, next.offset + 1 - offset),
+                    ErrorKind::AdjacentZeroOrMore,
+                )]);
+            },
+            None if !self.is_terminated(terminator) => {
+                return Err(next.expected(terminator.expectation()));
+            },
+            _ => {},
+        }
+        Ok(Wildcard::ZeroOrMore {
+            evaluation,
+            is_separator_crossing: self.state.flags.is_separator_crossing,
+        }
+        .into())
+    }
+
+    // Parses a tree wildcard. Tree wildcards begin with a separator or the
+    // beginning of a sub-glob and are followed by a separator or the
+    // terminator. Flags may appear between these and `**`, such as in
+    // `a/(?i)**/b`.
+    fn tree(&mut self, start: usize, terminator: Terminator) -> Option<TokenKind<'t, Annotation>> {
+        let checkpoint = *self;
+        let has_root = self.eat("/");
+        if !has_root && self.offset != start {
+            return None;
+        }
+        self.flags();
+        if self.eat("**") {
+            let postfix = *self;
+            self.flags();
+            if self.eat("/") {
+                return Some(Wildcard::Tree { has_root }.into());
+            }
+            *self = postfix;
+            if self.is_terminated(terminator) {
+                return Some(Wildcard::Tree { has_root }.into());
+            }
+        }
+        *self = checkpoint;
+        None
+    }
+
+    fn repetition(&mut self) -> ParseResult<TokenKind<'t, Annotation>> {
+        let start = self.offset;
+        let within = within("repetition", start);
+        self.offset += 1;
+        let tokens = self.glob(Terminator::Repetition).map_err(&within)?;
+        let (lower, upper) = if self.eat(":") {
+            self.bounds().map_err(&within)?
+        }
+        else {
+            (0, None)
+        };
+        // Repetitions with bounds are lazy if the bounds are followed by `?`,
+        // such as `<*/:1,?>`.
+        let evaluation = if self.eat("?") {
+            Evaluation::Lazy
+        }
+        else {
+            Evaluation::Eager
+        };
+        if !self.eat(">") {
+            return Err(within(self.expected("`>`")));
+        }
+        Ok(Repetition {
+            tokens,
+            lower,
+            upper,
+            evaluation,
+        }
+        .into())
+    }
+
+    // Parses the bounds of a repetition, such as `1,2`. The leading `:` is not
+    // parsed.
+    fn bounds(&mut self) -> ParseResult<(usize, Option<usize>)> {
+        let lower = match self.bound()? {
+            Some(lower) => lower,
+            _ => return Ok((1, None)),
+        };
+        if self.eat(",") {
+            Ok((lower, self.bound()?))
+        }
+        else {
+            Ok((lower, Some(lower)))
+        }
+    }
+
+    fn bound(&mut self) -> ParseResult<Option<usize>> {
+        let start = self.offset;
+        let digits = self.eat_while(|x| x.is_ascii_digit());
+        if digits.is_empty() {
+            Ok(None)
+        }
+        else {
+            digits.parse().map(Some).map_err(|_| {
+                vec![ErrorEntry::new(
+                    (start, digits.len()),
+                    ErrorKind::RepetitionBoundOverflow,
+                )]
+            })
+        }
+    }
+
+    // Optional groups, such as `(-v2)?`, are repetitions with the bounds
+    // `0,1`. Groups cannot begin with `?`, which would be ambiguous with flags.
+    fn optional(&mut self) -> ParseResult<TokenKind<'t, Annotation>> {
+        let start = self.offset;
+        let within = within("optional group", start);
+        self.offset += 1;
+        let floor = mem::replace(&mut self.state.floor, self.state.scopes.depth);
+        let tokens = self.glob(Terminator::Optional).map_err(&within)?;
+        self.state.floor = floor;
+        if !(self.eat(")") && self.eat("?")) {
+            return Err(within(self.expected("`?`")));
+        }
+        Ok(Repetition {
+            tokens,
+            lower: 0,
+            upper: Some(1),
+            evaluation: Evaluation::Eager,
+        }
+        .into())
+    }
+
+    fn alternative(&mut self) -> ParseResult<TokenKind<'t, Annotation>> {
+        let start = self.offset;
+        let within = within("alternative", start);
+        self.offset += 1;
+        // Branches may be empty, such as in `{foo,}`, but at least one branch
+        // must be non-empty.
+        let mut branches = vec![];
+        loop {
+            if Terminator::Alternative.is_terminated(self.rest()) {
+                branches.push(vec![]);
+            }
+            else {
+                branches.push(self.glob(Terminator::Alternative).map_err(&within)?);
+            }
+            if !self.eat(",") {
+                break;
+            }
+        }
+        if !self.eat("}") {
+            return Err(within(self.expected("`}`")));
+        }
+        if branches.iter().all(Vec::is_empty) {
+            return Err(vec![ErrorEntry::new(
+                (start, self.offset - start),
+                ErrorKind::EmptyAlternative,
+            )]);
+        }
+        Ok(Alternative::from(branches).into())
+    }
+
+    fn class(&mut self) -> ParseResult<TokenKind<'t, Annotation>> {
+        let start = self.offset;
+        let within = within("class", start);
+        self.offset += 1;
+        let is_negated = self.eat("!");
+        let mut archetypes = vec![];
+        loop {
+            // Equivalence classes, such as `[=e=]`, are expanded into the
+            // characters that are equivalent to the delimited character.
+            if self.eat("[=") {
+                let x = self
+                    .eat_char()
+                    .ok_or_else(|| within(self.expected("a character")))?;
+                if !self.eat("=]") {
+                    return Err(within(self.expected("`=]`")));
                 }
-                .into()
+                archetypes.extend(collate::equivalents(x).into_iter().map(Archetype::from));
+                continue;
+            }
+            let x = match self.class_character().map_err(&within)? {
+                Some(x) => x,
+                _ => break,
+            };
+            let checkpoint = *self;
+            if self.eat("-") {
+                if let Some(y) = self.class_character().map_err(&within)? {
+                    archetypes.push(Archetype::from((x, y)));
+                    continue;
+                }
+            }
+            *self = checkpoint;
+            archetypes.push(Archetype::from(x));
+        }
+        if archetypes.is_empty() {
+            return Err(within(self.expected("a character")));
+        }
+        if !self.eat("]") {
+            return Err(within(self.expected("`]`")));
+        }
+        Ok(Class {
+            is_negated,
+            archetypes,
+        }
+        .into())
+    }
+
+    // Parses a character in a class. Collating symbols, such as `[.-.]`, are
+    // interchangeable with the character that they delimit. Only single
+    // characters are supported.
+    fn class_character(&mut self) -> ParseResult<Option<char>> {
+        if self.eat("[.") {
+            let x = self.eat_char().ok_or_else(|| self.expected("a character"))?;
+            return if self.eat(".]") {
+                Ok(Some(x))
+            }
+            else {
+                Err(self.expected("`.]`"))
+            };
+        }
+        let escape = self.offset;
+        match self.peek() {
+            Some('\\') => {
+                self.offset += 1;
+                let x = match self.peek() {
+                    Some(x @ ('[' | ']' | '-')) => {
+                        self.offset += 1;
+                        Some(x)
+                    },
+                    _ => self.escaped_code_point(),
+                };
+                x.map(Some).ok_or_else(|| {
+                    let n = 1 + self.expression[escape + 1..]
+                        .chars()
+                        .next()
+                        .map_or(0, char::len_utf8);
+                    vec![ErrorEntry::new((escape, n), ErrorKind::InvalidEscape)]
+                })
             },
-        )(input)
-    }
-
-    fn alternative(input: Input) -> ParseResult<TokenKind<Annotation>> {
-        sequence::delimited(
-            bytes::tag("{"),
-            combinator::map(
-                multi::separated_list1(
-                    bytes::tag(","),
-                    error::context(
-                        "sub-glob",
-                        glob(move |input| {
-                            combinator::peek(branch::alt((bytes::tag(","), bytes::tag("}"))))(input)
-                        }, ","),
-                    ),
-                ),
-                |alternatives: Vec<Vec<_>>| Alternative::from(alternatives).into(),
-            ),
-            bytes::tag("}"),
-        )(input)
-    }
-
-    fn glob<'i>(
-        terminator: impl 'i + Clone + Parser<Input<'i>, Input<'i>, ErrorStack<'i>>,
-        literal_cant_contain: &'i str,
-    ) -> impl Parser<Input<'i>, Vec<Token<'i, Annotation>>, ErrorStack<'i>> {
-        fn annotate<'i, F>(
-            parser: F,
-        ) -> impl FnMut(Input<'i>) -> ParseResult<'i, Token<'i, Annotation>>
-        where
-            F: 'i + Parser<Input<'i>, TokenKind<'i, Annotation>, ErrorStack<'i>>,
-        {
-            combinator::map(pori::span(parser), |(span, kind)| Token::new(kind, span))
-        }
-
-        move |mut input: Input<'i>| {
-            input.state.subexpression = input.location();
-            sequence::terminated(
-                multi::many1(branch::alt((
-                    annotate(error::context(
-                        "literal",
-                        sequence::preceded(flags_with_state, literal(literal_cant_contain)),
-                    )),
-                    annotate(error::context(
-                        "repetition",
-                        sequence::preceded(flags_with_state, repetition),
-                    )),
-                    annotate(error::context(
-                        "alternative",
-                        sequence::preceded(flags_with_state, alternative),
-                    )),
-                    annotate(error::context(
-                        "wildcard",
-                        sequence::preceded(flags_with_state, wildcard(terminator.clone())),
-                    )),
-                    annotate(error::context(
-                        "class",
-                        sequence::preceded(flags_with_state, class),
-                    )),
-                    annotate(error::context(
-                        "separator",
-                        sequence::preceded(flags_with_state, separator),
-                    )),
-                ))),
-                terminator.clone(),
-            )
-            .parse(input)
-        }
-    }
-
-    if expression.is_empty() {
-        Ok(Tokenized {
-            expression: expression.into(),
-            tokens: vec![],
-        })
+            Some('[' | ']' | '-') | None => Ok(None),
+            x => {
+                self.eat_char();
+                Ok(x)
+            },
+        }
+    }
+}
+
+// Gets a function that appends the group that encloses an error as context.
+fn within(
+    context: &'static str,
+    offset: usize,
+) -> impl Fn(Vec<ErrorEntry>) -> Vec<ErrorEntry> {
+    move |mut entries| {
+        entries.push(ErrorEntry::new((offset, 1), ErrorKind::Context(context)));
+        entries
+    }
+}
+
+pub fn parse(expression: &str) -> Result<Tokenized, ParseError> {
+    parse_with_folding(expression, CaseFolding::default())
+}
+
+// Parses an expression with the given folding of case-insensitive literals.
+#[cfg_attr(feature = "tracing", tracing::instrument(level = "trace", err))]
+pub fn parse_with_folding(
+    expression: &str,
+    folding: CaseFolding,
+) -> Result<Tokenized<'_>, ParseError<'_>> {
+    let tokens = if expression.is_empty() {
+        vec![]
     }
     else {
-        let input = Input::new(Expression::from(expression), ParserState::default());
-        let tokens = combinator::all_consuming(glob(combinator::eof, ""))(input)
-            .map(|(_, tokens)| tokens)
-            .map_err(|error| ParseError::new(expression, error))?;
-        Ok(Tokenized {
-            expression: expression.into(),
-            tokens,
-        })
+        Parser::new(expression, folding)
+            .glob(Terminator::End)
+            .map_err(|locations| ParseError::new(expression, locations))?
+    };
+    Ok(Tokenized {
+        expression: expression.into(),
+        tokens,
+        folding,
+    })
+}
+
+// Splits an expression after its first `n` tokens and any flag groups that
+// precede the next token. If `is_unrooted` is `true`, then the root separator
+// of the next token is also split. Returns the offset of the split and flag
+// groups that, when followed by the text after the split, parse into the same
+// flags as the remaining tokens.
+pub fn split(expression: &str, n: usize, is_unrooted: bool) -> Option<(usize, String)> {
+    let mut parser = Parser::new(expression, CaseFolding::default());
+    for _ in 0..n {
+        parser.flags();
+        parser.token(0, Terminator::End).ok()??;
+    }
+    parser.flags();
+    if is_unrooted && !parser.eat(ROOT_SEPARATOR_EXPRESSION) {
+        return None;
     }
+    Some((parser.offset, parser.state.expression()))
 }
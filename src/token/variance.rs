@@ -4,16 +4,31 @@ use std::collections::VecDeque;
 use std::ops::{Add, Mul};
 
 use crate::encode;
-use crate::token::{self, Separator, Token};
+use crate::token::{self, Separator, Token, TokenKind, Wildcard};
 use crate::PATHS_ARE_CASE_INSENSITIVE;
 
+/// Invariant quantity of a pattern, such as its size or text.
+///
+/// Invariants are combined conjunctively via [`Add`], which concatenates them
+/// (for example, the text `a` followed by the text `b`), and are repeated via
+/// [`Mul`], which concatenates them with themselves some number of times.
+///
+/// [`Add`]: std::ops::Add
+/// [`Mul`]: std::ops::Mul
 pub trait Invariance:
     Add<Self, Output = Self> + Eq + Mul<usize, Output = Self> + PartialEq<Self> + Sized
 {
+    /// Gets the empty invariant, which is the identity of [`Add`].
+    ///
+    /// [`Add`]: std::ops::Add
     fn empty() -> Self;
 }
 
+/// Types that have a [`Variance`], such as the units of an analysis.
+///
+/// [`Variance`]: crate::variance::Variance
 pub trait UnitVariance<T> {
+    /// Gets the variance of the unit.
     fn unit_variance(self) -> Variance<T>;
 }
 
@@ -23,11 +38,24 @@ impl<T> UnitVariance<T> for Variance<T> {
     }
 }
 
+/// Conjunctive variance of a sequence of units.
+///
+/// This trait is implemented for all iterators over [`UnitVariance`] types.
+///
+/// [`UnitVariance`]: crate::variance::UnitVariance
 pub trait ConjunctiveVariance<T>: Iterator + Sized
 where
     Self::Item: UnitVariance<T>,
     T: Invariance,
 {
+    /// Gets the variance of the units in sequence, such as the tokens `a`,
+    /// `?`, and `c` in the glob expression `a?c`.
+    ///
+    /// The variances of the units are combined via [`Add`]. The conjunctive
+    /// variance of no units is the [empty][`Invariance::empty`] invariant.
+    ///
+    /// [`Add`]: std::ops::Add
+    /// [`Invariance::empty`]: crate::variance::Invariance::empty
     fn conjunctive_variance(self) -> Variance<T> {
         self.map(UnitVariance::unit_variance)
             .reduce(Add::add)
@@ -43,11 +71,26 @@ where
 {
 }
 
+/// Disjunctive variance of a set of units.
+///
+/// This trait is implemented for all iterators over [`UnitVariance`] types.
+///
+/// [`UnitVariance`]: crate::variance::UnitVariance
 pub trait DisjunctiveVariance<T>: Iterator + Sized
 where
     Self::Item: UnitVariance<T>,
     T: Invariance,
 {
+    /// Gets the variance of any one of the units, such as the branches `a`
+    /// and `b` of the glob expression `{a,b}`.
+    ///
+    /// The disjunctive variance is invariant if all of the variances of the
+    /// units are equal and is variant and [closed][`Boundedness::Closed`]
+    /// otherwise. The disjunctive variance of no units is the
+    /// [empty][`Invariance::empty`] invariant.
+    ///
+    /// [`Boundedness::Closed`]: crate::variance::Boundedness::Closed
+    /// [`Invariance::empty`]: crate::variance::Invariance::empty
     fn disjunctive_variance(self) -> Variance<T> {
         // TODO: This implementation is incomplete. Unbounded variance (and
         //       unbounded depth) are "infectious" when disjunctive. If any unit
@@ -146,9 +189,19 @@ where
     }
 }
 
+/// Conversion of text into [`InvariantText`].
+///
+/// [`InvariantText`]: crate::variance::InvariantText
 pub trait IntoInvariantText<'t> {
+    /// Converts text into nominal invariant text, such as the name of a file.
+    ///
+    /// Nominal text is compared with the case sensitivity of paths on the
+    /// target platform.
     fn into_nominal_text(self) -> InvariantText<'t>;
 
+    /// Converts text into structural invariant text, such as a separator.
+    ///
+    /// Structural text is always compared exactly.
     fn into_structural_text(self) -> InvariantText<'t>;
 }
 
@@ -162,6 +215,16 @@ impl<'t> IntoInvariantText<'t> for Cow<'t, str> {
     }
 }
 
+impl<'t> IntoInvariantText<'t> for &'t str {
+    fn into_nominal_text(self) -> InvariantText<'t> {
+        InvariantFragment::Nominal(self.into()).into()
+    }
+
+    fn into_structural_text(self) -> InvariantText<'t> {
+        InvariantFragment::Structural(self.into()).into()
+    }
+}
+
 impl IntoInvariantText<'static> for String {
     fn into_nominal_text(self) -> InvariantText<'static> {
         InvariantFragment::Nominal(self.into()).into()
@@ -172,10 +235,15 @@ impl IntoInvariantText<'static> for String {
     }
 }
 
+/// Invariant size of a pattern in bytes.
+///
+/// The size of a pattern is invariant if all of the text that it matches has
+/// the same length, such as in the glob expression `a?c`.
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 pub struct InvariantSize(usize);
 
 impl InvariantSize {
+    /// Constructs an `InvariantSize` of `n` bytes.
     pub const fn new(n: usize) -> Self {
         InvariantSize(n)
     }
@@ -207,6 +275,9 @@ impl Invariance for InvariantSize {
     }
 }
 
+/// # Panics
+///
+/// Panics if the product overflows.
 impl Mul<usize> for InvariantSize {
     type Output = Self;
 
@@ -223,52 +294,76 @@ impl Mul<usize> for InvariantSize {
 //       detect contiguous like fragments that are equivalent to an aggregated
 //       fragment. This works, but relies on constructing `InvariantText` by
 //       consistently appending fragments.
+/// Invariant text of a pattern.
+///
+/// The text of a pattern is invariant if it matches exactly one text, such as
+/// in the glob expression `a/[b]/{c,c}`. Invariant text is constructed via
+/// [`IntoInvariantText`] and is composed of nominal and structural fragments,
+/// which are compared differently. Repeated text is represented lazily, so
+/// the repetition of text is independent of the number of repetitions until
+/// the text is [expanded][`InvariantText::to_string`].
+///
+/// [`IntoInvariantText`]: crate::variance::IntoInvariantText
+/// [`InvariantText::to_string`]: crate::variance::InvariantText::to_string
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct InvariantText<'t> {
-    fragments: VecDeque<InvariantFragment<'t>>,
+    segments: VecDeque<InvariantSegment<'t>>,
 }
 
 impl<'t> InvariantText<'t> {
+    /// Constructs empty `InvariantText`.
     pub fn new() -> Self {
         InvariantText {
-            fragments: VecDeque::new(),
+            segments: VecDeque::new(),
         }
     }
 
+    /// Clones any borrowed data into an owning instance.
     pub fn into_owned(self) -> InvariantText<'static> {
-        let InvariantText { fragments } = self;
+        let InvariantText { segments } = self;
         InvariantText {
-            fragments: fragments
+            segments: segments
                 .into_iter()
-                .map(InvariantFragment::into_owned)
+                .map(InvariantSegment::into_owned)
                 .collect(),
         }
     }
 
+    // Repetitions are expanded here, so this may allocate very large strings
+    // for pathological repetitions like `<long:9999999999999>`.
+    /// Expands the invariant text into a string.
+    ///
+    /// Repetitions are expanded, so this may allocate very large strings for
+    /// text that is repeated many times.
     pub fn to_string(&self) -> Cow<'t, str> {
-        self.fragments
+        self.segments
             .iter()
-            .map(|fragment| fragment.as_string().clone())
-            .reduce(|text, fragment| text + fragment)
+            .map(InvariantSegment::to_string)
+            .reduce(|text, segment| text + segment)
             .unwrap_or(Cow::Borrowed(""))
     }
 
+    /// Repeats the text `n` times.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of repetitions of any text overflows.
     pub fn repeat(self, n: usize) -> Self {
-        if n == 0 {
+        if n <= 1 || self.segments.is_empty() {
             self
         }
         else {
-            let InvariantText { fragments } = self;
-            let n = (n - 1)
-                .checked_mul(fragments.len())
-                .expect("overflow determining invariant text");
-            let first = fragments.clone();
-            InvariantText {
-                fragments: first
-                    .into_iter()
-                    .chain(fragments.into_iter().cycle().take(n))
-                    .collect(),
+            let InvariantText { mut segments } = self;
+            if segments.len() == 1 {
+                // Repeat the text of a repetition by multiplying its count.
+                if let Some(InvariantSegment::Repetition(_, ref mut m)) = segments.front_mut() {
+                    *m = m
+                        .checked_mul(n)
+                        .expect("overflow determining invariant text");
+                    return InvariantText { segments };
+                }
             }
+            InvariantSegment::Repetition(Box::new(InvariantText { segments }), n).into()
         }
     }
 }
@@ -277,21 +372,24 @@ impl<'t> Add for InvariantText<'t> {
     type Output = Self;
 
     fn add(self, other: Self) -> Self::Output {
+        let InvariantText { segments: mut left } = self;
         let InvariantText {
-            fragments: mut left,
-        } = self;
-        let InvariantText {
-            fragments: mut right,
+            segments: mut right,
         } = other;
         let end = left.pop_back();
         let start = right.pop_front();
-        let InvariantText { fragments: middle } = match (end, start) {
-            (Some(left), Some(right)) => left + right,
+        let InvariantText { segments: middle } = match (end, start) {
+            (Some(InvariantSegment::Fragment(left)), Some(InvariantSegment::Fragment(right))) => {
+                left + right
+            },
+            (Some(left), Some(right)) => InvariantText {
+                segments: [left, right].into_iter().collect(),
+            },
             (Some(middle), None) | (None, Some(middle)) => middle.into(),
             (None, None) => InvariantText::new(),
         };
         InvariantText {
-            fragments: left
+            segments: left
                 .into_iter()
                 .chain(middle.into_iter())
                 .chain(right.into_iter())
@@ -316,8 +414,14 @@ impl<'t> Default for InvariantText<'t> {
 
 impl<'t> From<InvariantFragment<'t>> for InvariantText<'t> {
     fn from(fragment: InvariantFragment<'t>) -> Self {
+        InvariantSegment::Fragment(fragment).into()
+    }
+}
+
+impl<'t> From<InvariantSegment<'t>> for InvariantText<'t> {
+    fn from(segment: InvariantSegment<'t>) -> Self {
         InvariantText {
-            fragments: [fragment].into_iter().collect(),
+            segments: [segment].into_iter().collect(),
         }
     }
 }
@@ -336,6 +440,36 @@ impl<'t> Mul<usize> for InvariantText<'t> {
     }
 }
 
+// Repeated invariant text is represented lazily by the repeated text and the
+// number of repetitions. This avoids expanding pathological repetitions like
+// `<long:9999999999999>`, so that the analysis of repetitions is independent of
+// the number of repetitions.
+#[derive(Clone, Debug, Eq, PartialEq)]
+enum InvariantSegment<'t> {
+    Fragment(InvariantFragment<'t>),
+    Repetition(Box<InvariantText<'t>>, usize),
+}
+
+impl<'t> InvariantSegment<'t> {
+    pub fn into_owned(self) -> InvariantSegment<'static> {
+        match self {
+            InvariantSegment::Fragment(fragment) => {
+                InvariantSegment::Fragment(fragment.into_owned())
+            },
+            InvariantSegment::Repetition(text, n) => {
+                InvariantSegment::Repetition(Box::new(text.into_owned()), n)
+            },
+        }
+    }
+
+    pub fn to_string(&self) -> Cow<'t, str> {
+        match self {
+            InvariantSegment::Fragment(ref fragment) => fragment.as_string().clone(),
+            InvariantSegment::Repetition(ref text, n) => text.to_string().repeat(*n).into(),
+        }
+    }
+}
+
 #[derive(Clone, Debug, Eq)]
 enum InvariantFragment<'t> {
     Nominal(Cow<'t, str>),
@@ -366,14 +500,12 @@ impl<'t> Add for InvariantFragment<'t> {
         use InvariantFragment::{Nominal, Structural};
 
         match (self, other) {
-            (Nominal(left), Nominal(right)) => InvariantText {
-                fragments: [Nominal(left + right)].into_iter().collect(),
-            },
-            (Structural(left), Structural(right)) => InvariantText {
-                fragments: [Structural(left + right)].into_iter().collect(),
-            },
+            (Nominal(left), Nominal(right)) => Nominal(left + right).into(),
+            (Structural(left), Structural(right)) => Structural(left + right).into(),
             (left, right) => InvariantText {
-                fragments: [left, right].into_iter().collect(),
+                segments: [InvariantSegment::Fragment(left), InvariantSegment::Fragment(right)]
+                    .into_iter()
+                    .collect(),
             },
         }
     }
@@ -406,24 +538,49 @@ impl<'t> PartialEq for InvariantFragment<'t> {
     }
 }
 
+/// Boundedness of a quantity of a pattern, such as its depth or breadth.
+///
+/// See [`Glob::breadth`], [`Glob::depth`], and [`Variance`].
+///
+/// [`Glob::breadth`]: crate::Glob::breadth
+/// [`Glob::depth`]: crate::Glob::depth
+/// [`Variance`]: crate::variance::Variance
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum Boundedness {
+    /// The quantity has an upper bound.
     Closed,
+    /// The quantity has no upper bound.
     Open,
 }
 
 impl Boundedness {
+    /// Returns `true` if closed (bounded).
     pub fn is_closed(&self) -> bool {
         matches!(self, Boundedness::Closed)
     }
 
+    /// Returns `true` if open (unbounded).
     pub fn is_open(&self) -> bool {
         matches!(self, Boundedness::Open)
     }
 }
 
+/// Variance of a quantity of a pattern, such as its size or text.
+///
+/// This type is more detailed than the [`Variance`] of a [`Pattern`]. A
+/// quantity is either invariant and has exactly one value, such as the text of
+/// the glob expression `a/b` or the size of the glob expression `a?c`, or is
+/// variant. Variances are combined conjunctively via [`Add`] (see
+/// [`ConjunctiveVariance`]) and disjunctively via [`DisjunctiveVariance`].
+///
+/// [`Add`]: std::ops::Add
+/// [`ConjunctiveVariance`]: crate::variance::ConjunctiveVariance
+/// [`DisjunctiveVariance`]: crate::variance::DisjunctiveVariance
+/// [`Pattern`]: crate::Pattern
+/// [`Variance`]: crate::Variance
 #[derive(Clone, Debug, Eq)]
 pub enum Variance<T> {
+    /// The quantity is invariant and has exactly one value.
     Invariant(T),
     // NOTE: In this context, _boundedness_ refers to whether or not a variant
     //       token or expression is _constrained_ or _unconstrained_. For
@@ -435,10 +592,20 @@ pub enum Variance<T> {
     //       part of an expression is constrained to a known set of matches. As
     //       such, both the expressions `?` and `*` are variant with open
     //       bounds.
+    /// The quantity is variant.
+    ///
+    /// Variance is [open][`Boundedness::Open`] if it is unconstrained, such
+    /// as in the glob expressions `?` and `**`, and
+    /// [closed][`Boundedness::Closed`] if it is constrained to a set of
+    /// values, such as in the glob expression `{a,b}`.
+    ///
+    /// [`Boundedness::Closed`]: crate::variance::Boundedness::Closed
+    /// [`Boundedness::Open`]: crate::variance::Boundedness::Open
     Variant(Boundedness),
 }
 
 impl<T> Variance<T> {
+    /// Maps the invariant value, if any.
     pub fn map_invariance<U>(self, mut f: impl FnMut(T) -> U) -> Variance<U> {
         match self {
             Variance::Invariant(invariant) => Variance::Invariant(f(invariant)),
@@ -446,6 +613,7 @@ impl<T> Variance<T> {
         }
     }
 
+    /// Gets the invariant value, if any.
     pub fn as_invariance(&self) -> Option<&T> {
         match self {
             Variance::Invariant(ref invariant) => Some(invariant),
@@ -453,6 +621,11 @@ impl<T> Variance<T> {
         }
     }
 
+    /// Gets the boundedness of the variance.
+    ///
+    /// Invariance is always [closed][`Boundedness::Closed`].
+    ///
+    /// [`Boundedness::Closed`]: crate::variance::Boundedness::Closed
     pub fn boundedness(&self) -> Boundedness {
         match self {
             Variance::Variant(ref boundedness) => *boundedness,
@@ -460,15 +633,21 @@ impl<T> Variance<T> {
         }
     }
 
+    /// Returns `true` if invariant.
     pub fn is_invariant(&self) -> bool {
         matches!(self, Variance::Invariant(_))
     }
 
+    /// Returns `true` if variant.
     pub fn is_variant(&self) -> bool {
         matches!(self, Variance::Variant(_))
     }
 }
 
+/// Conjunctive combination of variances.
+///
+/// The sum of invariants is the sum of their values. The sum of open variants
+/// is open and the sum of any other variances is variant and closed.
 impl<T> Add for Variance<T>
 where
     T: Add<T, Output = T>,
@@ -592,13 +771,90 @@ where
     )
 }
 
+/// Gets an upper bound on the number of components in paths matched by the
+/// token tree.
+///
+/// Returns `None` if the depth of the token tree is unbounded, such as when it
+/// contains a tree wildcard. Roots are not components, so `/a/b` has at most
+/// two components.
+pub fn component_upper_bound<'i, 't, A, I>(tokens: I) -> Option<usize>
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    let mut tokens = tokens.into_iter().peekable();
+    let is_rooted = matches!(
+        tokens.peek().map(|token| token.kind()),
+        Some(TokenKind::Separator(_))
+    );
+    separator_upper_bound(tokens).map(|n| if is_rooted { n } else { n + 1 })
+}
+
+// Gets an upper bound on the number of separators in text matched by the
+// tokens. Returns `None` if the number of separators is unbounded.
+fn separator_upper_bound<'i, 't, A, I>(tokens: I) -> Option<usize>
+where
+    't: 'i,
+    A: 't,
+    I: IntoIterator<Item = &'i Token<'t, A>>,
+{
+    tokens.into_iter().try_fold(0usize, |sum, token| {
+        let n = match token.kind() {
+            TokenKind::Separator(_) => 1,
+            TokenKind::Wildcard(
+                Wildcard::Tree { .. }
+                | Wildcard::ZeroOrMore {
+                    is_separator_crossing: true,
+                    ..
+                },
+            ) => {
+                return None;
+            },
+            TokenKind::Alternative(ref alternative) => alternative
+                .branches()
+                .iter()
+                .map(separator_upper_bound)
+                .try_fold(0usize, |max, n| n.map(|n| max.max(n)))?,
+            TokenKind::Repetition(ref repetition) => {
+                match (separator_upper_bound(repetition.tokens())?, repetition.bounds()) {
+                    (0, _) => 0,
+                    (n, (_, Some(upper))) => n.checked_mul(upper)?,
+                    (_, (_, None)) => {
+                        return None;
+                    },
+                }
+            },
+            _ => 0,
+        };
+        sum.checked_add(n)
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use std::path::{Path, PathBuf};
 
-    use crate::token::variance::{self, Boundedness, InvariantSize, Variance};
+    use crate::token::variance::{self, Boundedness, InvariantSize, InvariantText, Variance};
     use crate::token::{self, TokenTree};
 
+    #[test]
+    fn component_upper_bound() {
+        fn bound(expression: &str) -> Option<usize> {
+            variance::component_upper_bound(token::parse(expression).unwrap().tokens())
+        }
+
+        assert_eq!(bound(""), Some(1));
+        assert_eq!(bound("a"), Some(1));
+        assert_eq!(bound("a/*/b"), Some(3));
+        assert_eq!(bound("/a/b"), Some(2));
+        assert_eq!(bound("*/{*.rs,*/*.rs}"), Some(3));
+        assert_eq!(bound("a/<*/:0,2>b"), Some(4));
+        assert_eq!(bound("a/<b*:1,>"), Some(2));
+        assert_eq!(bound("a/**"), None);
+        assert_eq!(bound("a/<*/:1,>b"), None);
+    }
+
     #[test]
     fn invariant_text_prefix() {
         fn invariant_path_prefix(expression: &str) -> PathBuf {
@@ -658,6 +914,20 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn invariant_text_of_large_repetition() {
+        let tokenized = token::parse("a/<b:1000000000000>").unwrap();
+        assert!(tokenized.variance::<InvariantText>().is_invariant());
+
+        let tokenized = token::parse("<<ab:3>:4>").unwrap();
+        match tokenized.variance::<InvariantText>() {
+            Variance::Invariant(text) => {
+                assert_eq!(text.to_string(), "ab".repeat(12));
+            },
+            _ => panic!("repetition is variant"),
+        }
+    }
+
     #[test]
     fn exhaustiveness() {
         assert!(token::is_exhaustive(token::parse("**").unwrap().tokens()));
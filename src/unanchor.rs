@@ -0,0 +1,201 @@
+use regex::Regex;
+use std::sync::Arc;
+
+use crate::encode::{self, CompileError, LazyProgram};
+use crate::token::TokenTree as _;
+use crate::{CandidatePath, Glob, MatchedText, Pattern as _, PATHS_ARE_CASE_INSENSITIVE};
+
+/// [`Glob`] that matches any suffix of the components of a path.
+///
+/// An `Unanchored` glob matches a path if its [`Glob`] matches the path or any
+/// of its suffixes that begin with a whole component, like patterns in ignore
+/// files that match at any level of a directory tree. For example, `build/**`
+/// matches `build/out.o` and `a/b/build/out.o`, but not `a/rebuild/out.o`.
+///
+/// This is similar to prepending `**/` to a glob expression, but the leading
+/// components are matched implicitly and are not captured, so the indices of
+/// [`MatchedText`] are the same as those of the [`Glob`]. If the path matches
+/// at more than one level, then the highest level is matched. [Rooted
+/// globs][`Glob::has_root`] are not unanchored and only match complete paths.
+///
+/// See [`Glob::unanchored`].
+///
+/// # Examples
+///
+/// ```rust
+/// use wax::{CandidatePath, Glob};
+///
+/// let glob = Glob::new("build/{*}.o").unwrap().unanchored().unwrap();
+///
+/// assert!(glob.is_match("build/main.o"));
+/// assert!(glob.is_match("a/b/build/main.o"));
+/// assert!(!glob.is_match("a/rebuild/main.o"));
+///
+/// let path = CandidatePath::from("a/build/main.o");
+/// let matched = glob.matched(&path).unwrap();
+/// assert_eq!(matched.get(1), Some("main"));
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`Glob::has_root`]: crate::Glob::has_root
+/// [`Glob::unanchored`]: crate::Glob::unanchored
+/// [`MatchedText`]: crate::MatchedText
+#[derive(Clone, Debug)]
+pub struct Unanchored<'t> {
+    glob: Glob<'t>,
+    // The program that matches suffixes of paths. This is `None` if the glob is
+    // rooted, in which case paths are matched by the glob.
+    pattern: Option<Regex>,
+    // The program with the opposite default case sensitivity. See `Glob`.
+    recased: LazyProgram<Regex>,
+}
+
+impl<'t> Unanchored<'t> {
+    pub(crate) fn new(glob: Glob<'t>) -> Result<Self, CompileError> {
+        let pattern = if glob.has_root() {
+            None
+        }
+        else {
+            Some(encode::compile_suffix(glob.tree.as_ref().tokens())?)
+        };
+        Ok(Unanchored {
+            glob,
+            pattern,
+            recased: LazyProgram::default(),
+        })
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Unanchored<'static> {
+        let Unanchored {
+            glob,
+            pattern,
+            recased,
+        } = self;
+        Unanchored {
+            glob: glob.into_owned(),
+            pattern,
+            recased,
+        }
+    }
+
+    /// Gets the [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn glob(&self) -> &Glob<'t> {
+        &self.glob
+    }
+
+    /// Returns `true` if a path or any suffix of its components matches the
+    /// [`Glob`].
+    ///
+    /// [`Glob`]: crate::Glob
+    pub fn is_match<'p>(&self, path: impl Into<CandidatePath<'p>>) -> bool {
+        let path = path.into();
+        match (self.recased(&path), self.pattern.as_ref()) {
+            (Some(pattern), _) => pattern.is_match(path.as_ref()),
+            (_, Some(pattern)) => pattern.is_match(path.as_ref()),
+            _ => self.glob.is_match(path),
+        }
+    }
+
+    /// Gets [matched text][`MatchedText`] in a [`CandidatePath`].
+    ///
+    /// The complete text is the entire path, including any leading components
+    /// that are not matched by the [`Glob`]. Returns `None` if no suffix of the
+    /// path matches the [`Glob`].
+    ///
+    /// [`CandidatePath`]: crate::CandidatePath
+    /// [`Glob`]: crate::Glob
+    /// [`MatchedText`]: crate::MatchedText
+    pub fn matched<'p>(&self, path: &'p CandidatePath<'_>) -> Option<MatchedText<'p>> {
+        match (self.recased(path), self.pattern.as_ref()) {
+            (Some(pattern), _) => pattern.captures(path.as_ref()).map(From::from),
+            (_, Some(pattern)) => pattern.captures(path.as_ref()).map(From::from),
+            _ => self.glob.matched(path),
+        }
+    }
+
+    // Gets the program with the opposite default case sensitivity if the glob
+    // is unrooted and the kind of the path differs from the target platform.
+    fn recased(&self, path: &CandidatePath<'_>) -> Option<Arc<Regex>> {
+        (self.pattern.is_some() && path.kind.is_case_insensitive() != PATHS_ARE_CASE_INSENSITIVE)
+            .then(|| {
+                self.recased.get_or_compile(|| {
+                    let tokenized = self
+                        .glob
+                        .tree
+                        .as_ref()
+                        .clone()
+                        .recase(!PATHS_ARE_CASE_INSENSITIVE);
+                    encode::compile_suffix(tokenized.tokens())
+                        .expect("failed to compile glob expression")
+                })
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{CandidatePath, Glob};
+
+    #[test]
+    fn match_unanchored_glob() {
+        let glob = Glob::new("build/**").unwrap().unanchored().unwrap();
+
+        assert!(glob.is_match("build/out.o"));
+        assert!(glob.is_match("a/build/out.o"));
+        assert!(glob.is_match("a/b/build/x/out.o"));
+
+        assert!(!glob.is_match("a/rebuild/out.o"));
+        assert!(!glob.is_match("a/build.o"));
+
+        let glob = Glob::new("*.rs").unwrap().unanchored().unwrap();
+
+        assert!(glob.is_match("lib.rs"));
+        assert!(glob.is_match("src/lib.rs"));
+
+        assert!(!glob.is_match("src/lib.rs/mod.txt"));
+    }
+
+    #[test]
+    fn match_unanchored_glob_with_root() {
+        let glob = Glob::new("/build/**").unwrap().unanchored().unwrap();
+
+        assert!(glob.is_match("/build/out.o"));
+
+        assert!(!glob.is_match("/a/build/out.o"));
+    }
+
+    #[test]
+    fn match_unanchored_glob_with_path_kind() {
+        let glob = Glob::new("build/*.o").unwrap().unanchored().unwrap();
+
+        assert!(glob.is_match(CandidatePath::windows(r"a\Build\OUT.o")));
+        assert!(!glob.is_match(CandidatePath::unix("a/Build/OUT.o")));
+
+        // Flags take precedence over the kind of a path.
+        let glob = Glob::new("(?-i)build/*.o").unwrap().unanchored().unwrap();
+
+        assert!(glob.is_match(CandidatePath::windows(r"a\build\OUT.o")));
+        assert!(!glob.is_match(CandidatePath::windows(r"a\Build\out.o")));
+    }
+
+    #[test]
+    fn matched_unanchored_glob() {
+        let glob = Glob::new("{*}/build/{*}.o").unwrap().unanchored().unwrap();
+
+        let path = "a/b/build/main.o".into();
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!(matched.complete(), "a/b/build/main.o");
+        assert_eq!(matched.get(1), Some("b"));
+        assert_eq!(matched.get(2), Some("main"));
+
+        // The glob matches at the highest level.
+        let glob = Glob::new("build/**").unwrap().unanchored().unwrap();
+
+        let path = "a/build/b/build/c".into();
+        let matched = glob.matched(&path).unwrap();
+        assert_eq!(matched.get(1), Some("b/build/c"));
+    }
+}
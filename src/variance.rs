@@ -0,0 +1,60 @@
+//! Analysis of the variance of patterns.
+//!
+//! A quantity of a pattern, such as the text or size that it matches, is
+//! _invariant_ if it has exactly one value and _variant_ otherwise. This
+//! module exposes the types used to describe and combine variance, so that
+//! tools can perform their own analyses over patterns.
+//!
+//! Variances are combined in two ways. A sequence of units, such as the
+//! tokens in the glob expression `a?c`, is _conjunctive_ and its variance is
+//! the sum of the variances of its units (see [`ConjunctiveVariance`]). A set
+//! of units, such as the branches in the glob expression `{a,b}`, is
+//! _disjunctive_ and its variance is invariant only if the variances of its
+//! units are the same invariant (see [`DisjunctiveVariance`]).
+//!
+//! Note that this [`Variance`] describes a particular quantity and is more
+//! detailed than the [`Variance`][`crate::Variance`] of a [`Pattern`].
+//!
+//! # Examples
+//!
+//! Units implement [`UnitVariance`] and can then be combined via iterators.
+//!
+//! ```rust
+//! use wax::variance::{
+//!     Boundedness, ConjunctiveVariance as _, DisjunctiveVariance as _, InvariantSize,
+//!     UnitVariance, Variance,
+//! };
+//!
+//! enum Unit {
+//!     Char,
+//!     Any,
+//! }
+//!
+//! impl UnitVariance<InvariantSize> for &'_ Unit {
+//!     fn unit_variance(self) -> Variance<InvariantSize> {
+//!         match self {
+//!             Unit::Char => Variance::Invariant(InvariantSize::new(1)),
+//!             Unit::Any => Variance::Variant(Boundedness::Open),
+//!         }
+//!     }
+//! }
+//!
+//! let units = [Unit::Char, Unit::Char];
+//! assert_eq!(
+//!     units.iter().conjunctive_variance(),
+//!     Variance::Invariant(InvariantSize::new(2)),
+//! );
+//! let units = [Unit::Char, Unit::Any];
+//! assert!(units.iter().disjunctive_variance().is_variant());
+//! ```
+//!
+//! [`ConjunctiveVariance`]: crate::variance::ConjunctiveVariance
+//! [`DisjunctiveVariance`]: crate::variance::DisjunctiveVariance
+//! [`Pattern`]: crate::Pattern
+//! [`UnitVariance`]: crate::variance::UnitVariance
+//! [`Variance`]: crate::variance::Variance
+
+pub use crate::token::{
+    Boundedness, ConjunctiveVariance, DisjunctiveVariance, IntoInvariantText, Invariance,
+    InvariantSize, InvariantText, UnitVariance, Variance,
+};
@@ -0,0 +1,46 @@
+/// Strategy for issuing file system queries when reading a directory tree.
+///
+/// By default, a traversal queries the file system one operation at a time as
+/// it reads a directory tree. This is appropriate for local storage, but
+/// leaves throughput on the table when each query has high latency, such as on
+/// network file systems or cold storage. Other backends submit many queries
+/// at once so that their latencies overlap.
+///
+/// A backend is honored by the [`Filesystem`] that reads the directory tree
+/// and is ignored by file systems that do not support it, which then issue
+/// queries sequentially. See [`Filesystem::configure`] and
+/// [`WalkBehavior::backend`].
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`Filesystem::configure`]: crate::Filesystem::configure
+/// [`WalkBehavior::backend`]: crate::WalkBehavior::backend
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum WalkBackend {
+    /// Queries are issued one at a time as the traversal requires them.
+    Sequential,
+    /// Queries are pipelined via `io_uring` on Linux.
+    ///
+    /// When a directory is read, the metadata of all of its entries is queried
+    /// and all of its subdirectories are opened in batches submitted to an
+    /// `io_uring` instance. Directory entries are still read via `getdents64`,
+    /// which `io_uring` does not support. This backend is supported by
+    /// [`LinuxFilesystem`].
+    ///
+    /// If `io_uring` is unavailable, such as on kernels older than 5.6 or in
+    /// sandboxes that forbid it, then queries are issued sequentially.
+    ///
+    /// This backend is **experimental**.
+    ///
+    /// [`LinuxFilesystem`]: crate::LinuxFilesystem
+    #[cfg_attr(docsrs, doc(cfg(feature = "io-uring")))]
+    #[cfg(feature = "io-uring")]
+    IoUring,
+}
+
+impl Default for WalkBackend {
+    fn default() -> Self {
+        WalkBackend::Sequential
+    }
+}
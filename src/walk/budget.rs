@@ -0,0 +1,70 @@
+/// Limit on the number of directory descriptors that a traversal keeps open.
+///
+/// Some [`Filesystem`]s read directory trees by keeping the descriptors of
+/// directories open, such as [`LinuxFilesystem`], which opens subdirectories
+/// relative to their parents. Deep or wide directory trees can then exhaust the
+/// limit on open descriptors for a process (i.e., `ulimit -n`), which causes
+/// queries to fail both in the traversal and elsewhere in a program.
+///
+/// A `DescriptorBudget` caps the number of such descriptors. When the cap is
+/// reached, the descriptors of the shallowest directories are closed first and
+/// these directories are reopened via their paths when they are next read. This
+/// trades some path resolution for a bounded number of descriptors.
+///
+/// Reading a reopened directory resumes at the position at which it stopped.
+/// Entries are neither repeated nor skipped so long as the file system keeps
+/// directory positions stable across opens, as most local file systems do. If
+/// a directory is replaced (e.g., renamed or removed and recreated) while its
+/// descriptor is closed, then reading it fails with an error rather than
+/// resuming in the other directory. As with any directory, entries that are
+/// added or removed while it is being read may or may not be yielded.
+///
+/// Budgets are honored by the [`Filesystem`] that reads a directory tree and
+/// are ignored by file systems that do not keep descriptors open. See
+/// [`WalkBehavior::descriptors`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// #[cfg(target_os = "linux")]
+/// use wax::LinuxFilesystem;
+/// use wax::{DescriptorBudget, Glob};
+///
+/// let glob = Glob::new("**/*.log").unwrap();
+/// #[cfg(target_os = "linux")]
+/// for entry in glob.walk_with_filesystem(
+///     LinuxFilesystem::new(),
+///     "/var/log",
+///     DescriptorBudget::new(64),
+/// ) {
+///     let entry = entry.unwrap();
+///     // ...
+/// }
+/// ```
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`LinuxFilesystem`]: crate::LinuxFilesystem
+/// [`WalkBehavior::descriptors`]: crate::WalkBehavior::descriptors
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct DescriptorBudget {
+    limit: usize,
+}
+
+impl DescriptorBudget {
+    /// Constructs a `DescriptorBudget` that keeps at most the given number of
+    /// descriptors open.
+    ///
+    /// At least one descriptor is needed to read a directory, so a limit of
+    /// zero is interpreted as one.
+    pub fn new(limit: usize) -> Self {
+        DescriptorBudget {
+            limit: limit.max(1),
+        }
+    }
+
+    /// Gets the maximum number of open descriptors.
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
@@ -0,0 +1,138 @@
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+use crate::walk::filesystem::Filesystem;
+use crate::{StrExt as _, PATHS_ARE_CASE_INSENSITIVE};
+
+/// Case sensitivity of the file system in a directory tree.
+///
+/// By default, literals in glob expressions are matched case-sensitively on
+/// Unix and case-insensitively on Windows. This does not describe some file
+/// systems, such as an exFAT or FAT32 volume mounted on Linux or a
+/// case-sensitive volume on macOS. `CaseSensitivity` determines the default
+/// case sensitivity of literals when matching a [`Glob`] against a directory
+/// tree.
+///
+/// Flags in a glob expression always take precedence. For example, literals
+/// following the flag `(?-i)` are matched case-sensitively regardless of
+/// `CaseSensitivity`.
+///
+/// See [`WalkBehavior::case`].
+///
+/// [`Glob`]: crate::Glob
+/// [`WalkBehavior::case`]: crate::WalkBehavior::case
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum CaseSensitivity {
+    /// Literals are matched using the case sensitivity of the target platform.
+    ///
+    /// Literals are case-insensitive on Windows and case-sensitive on other
+    /// platforms.
+    Platform,
+    /// Literals are matched case-sensitively.
+    Sensitive,
+    /// Literals are matched case-insensitively.
+    Insensitive,
+    /// The case sensitivity of the file system is detected when a traversal
+    /// begins.
+    ///
+    /// The directory given to functions like [`Glob::walk_with_behavior`] is
+    /// probed by querying the name of one of its entries with its case
+    /// swapped. If the directory cannot be read or has no entries with names
+    /// that have casing, then the case sensitivity of the target platform is
+    /// used.
+    ///
+    /// Only the given directory is probed, so this does not detect file
+    /// systems that are mounted beneath it.
+    ///
+    /// [`Glob::walk_with_behavior`]: crate::Glob::walk_with_behavior
+    Detect,
+}
+
+impl CaseSensitivity {
+    // Determines if literals are case-insensitive by default when reading the
+    // given directory.
+    pub(in crate::walk) fn is_case_insensitive<S>(self, filesystem: &S, directory: &Path) -> bool
+    where
+        S: Filesystem,
+    {
+        match self {
+            CaseSensitivity::Platform => PATHS_ARE_CASE_INSENSITIVE,
+            CaseSensitivity::Sensitive => false,
+            CaseSensitivity::Insensitive => true,
+            CaseSensitivity::Detect => {
+                detect(filesystem, directory).unwrap_or(PATHS_ARE_CASE_INSENSITIVE)
+            },
+        }
+    }
+}
+
+impl Default for CaseSensitivity {
+    fn default() -> Self {
+        CaseSensitivity::Platform
+    }
+}
+
+// Probes a directory for the case sensitivity of its file system. The case of
+// the name of an entry is swapped and the swapped path is queried. If the
+// query succeeds and the swapped name is not itself an entry in the
+// directory, then the file system is case-insensitive.
+fn detect<S>(filesystem: &S, directory: &Path) -> Option<bool>
+where
+    S: Filesystem,
+{
+    let paths: Vec<PathBuf> = filesystem
+        .read_dir(directory)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|(path, _, _)| path))
+        .collect();
+    for path in paths.iter() {
+        let name = match path.file_name().and_then(|name| name.to_str()) {
+            Some(name) => name,
+            _ => continue,
+        };
+        let swapped = name.swap_case();
+        if swapped == name {
+            continue;
+        }
+        let swapped = path.with_file_name(swapped);
+        if paths.contains(&swapped) {
+            // Both names exist as distinct entries.
+            return Some(false);
+        }
+        match filesystem.symlink_metadata(&swapped) {
+            Ok(_) => {
+                return Some(true);
+            },
+            Err(error) if error.kind() == ErrorKind::NotFound => {
+                return Some(false);
+            },
+            _ => continue,
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::walk::case::CaseSensitivity;
+    use crate::walk::StdFilesystem;
+
+    #[test]
+    fn detect_case_sensitivity() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("Cargo.toml"), b"").unwrap();
+        let is_case_insensitive = root.path().join("cargo.TOML").exists();
+        assert_eq!(
+            CaseSensitivity::Detect.is_case_insensitive(&StdFilesystem, root.path()),
+            is_case_insensitive,
+        );
+
+        // Directories with no entries with casing fall back to the platform.
+        let root = tempfile::tempdir().unwrap();
+        assert_eq!(
+            CaseSensitivity::Detect.is_case_insensitive(&StdFilesystem, root.path()),
+            CaseSensitivity::Platform.is_case_insensitive(&StdFilesystem, root.path()),
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use std::path::{Component, Path, PathBuf};
+
+use crate::walk::filesystem::Filesystem;
+use crate::walk::retry::{retry, RetryPolicy};
+use crate::walk::{WalkError, WalkOperation};
+
+/// Confinement of a traversal to the directory tree in which it begins.
+///
+/// By default, a traversal reads whatever its paths resolve to: symbolic links
+/// may refer to files anywhere and glob expressions with invariant prefixes
+/// like `../` or `/etc` begin outside of the given directory. This is
+/// undesirable when reading untrusted directory trees, such as archives or
+/// uploads that have been extracted by a service.
+///
+/// When confined, no path yielded by a traversal resolves outside of the
+/// directory given to functions like [`Glob::walk_with_behavior`]. Symbolic
+/// links that resolve outside of this directory are neither yielded nor
+/// followed. Instead, an error is yielded for each such link. A glob expression
+/// with an invariant prefix that resolves outside of this directory yields a
+/// single error. See [`WalkBehavior::confinement`].
+///
+/// Paths are resolved when they are read, so confinement cannot prevent a path
+/// from being replaced with a symbolic link **after** it has been yielded.
+///
+/// **Confinement is only robust against concurrent modification of the
+/// directory tree when the [`Filesystem`] is [confining][`is_confining`]**,
+/// such as [`LinuxFilesystem`] when symbolic links are read as files. Other
+/// file systems, including [`StdFilesystem`], verify a directory by its path
+/// and then read it by its path. A directory that is replaced by a symbolic
+/// link between these queries is read, so such traversals can be raced by
+/// another process that can write to the directory tree. Use a confining
+/// file system to read directory trees that may be modified by untrusted
+/// processes while they are read.
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`Glob::walk_with_behavior`]: crate::Glob::walk_with_behavior
+/// [`is_confining`]: crate::Filesystem::is_confining
+/// [`LinuxFilesystem`]: crate::LinuxFilesystem
+/// [`StdFilesystem`]: crate::StdFilesystem
+/// [`WalkBehavior::confinement`]: crate::WalkBehavior::confinement
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Confinement {
+    /// Paths may resolve anywhere.
+    Unconfined,
+    /// Paths resolve beneath the directory in which the traversal begins.
+    ///
+    /// The root of the traversal and any symbolic links are resolved via
+    /// [`Filesystem::canonicalize`] and verified to be beneath the directory.
+    /// Directories are also verified before they are read, unless the
+    /// [`Filesystem`] descends into directories without following symbolic
+    /// links, such as [`LinuxFilesystem`]. Verification and reading are
+    /// separate queries, so this is subject to races with concurrent
+    /// modification. See [`Filesystem::is_confining`].
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    /// [`Filesystem::canonicalize`]: crate::Filesystem::canonicalize
+    /// [`Filesystem::is_confining`]: crate::Filesystem::is_confining
+    /// [`LinuxFilesystem`]: crate::LinuxFilesystem
+    Confined,
+}
+
+impl Confinement {
+    pub(in crate::walk) fn is_confined(&self) -> bool {
+        matches!(self, Confinement::Confined)
+    }
+}
+
+impl Default for Confinement {
+    fn default() -> Self {
+        Confinement::Unconfined
+    }
+}
+
+// Verifies that the paths read by a traversal resolve beneath a directory.
+#[derive(Debug)]
+pub struct Boundary {
+    directory: PathBuf,
+    // The canonical path of the directory is queried when the first path is
+    // verified, which is the root of the traversal.
+    canonical: Option<PathBuf>,
+    is_verifying_directories: bool,
+}
+
+impl Boundary {
+    pub fn new<S>(filesystem: &S, directory: &Path) -> Self
+    where
+        S: Filesystem,
+    {
+        Boundary {
+            directory: directory.to_path_buf(),
+            canonical: None,
+            is_verifying_directories: !filesystem.is_confining(),
+        }
+    }
+
+    // Returns `true` if directories must be verified before they are read,
+    // because the file system may follow symbolic links when reading them.
+    pub fn is_verifying_directories(&self) -> bool {
+        self.is_verifying_directories
+    }
+
+    // Verifies that a path resolves beneath the directory.
+    pub fn verify<S>(
+        &mut self,
+        filesystem: &S,
+        policy: Option<&RetryPolicy>,
+        depth: usize,
+        path: &Path,
+    ) -> Result<(), WalkError>
+    where
+        S: Filesystem,
+    {
+        let canonicalize = |path: &Path| {
+            retry(policy, || filesystem.canonicalize(path))
+                .map(|path| normalize(&path))
+                .map_err(|error| {
+                    WalkError::io(depth, WalkOperation::ResolveLink, Some(path.into()), error)
+                })
+        };
+        let directory = match self.canonical {
+            Some(ref directory) => directory,
+            None => self.canonical.insert(canonicalize(&self.directory)?),
+        };
+        if is_beneath(&canonicalize(path)?, directory) {
+            Ok(())
+        }
+        else {
+            Err(WalkError::escape(
+                depth,
+                self.directory.clone(),
+                path.to_path_buf(),
+            ))
+        }
+    }
+}
+
+// Returns `true` if a normalized path is the same as or beneath a normalized
+// directory. Relative paths may begin with parent directory components, which
+// are not beneath the directory.
+fn is_beneath(path: &Path, directory: &Path) -> bool {
+    path.strip_prefix(directory).map_or(false, |path| {
+        path.components()
+            .all(|component| component != Component::ParentDir)
+    })
+}
+
+// Resolves current and parent directory components in a path. Canonical paths
+// have no such components, but `Filesystem::canonicalize` may return paths as
+// is.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => match normalized.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    normalized.pop();
+                },
+                // The parent of a root is the root itself.
+                Some(Component::Prefix(_) | Component::RootDir) => {},
+                _ => normalized.push(component),
+            },
+            _ => normalized.push(component),
+        }
+    }
+    normalized
+}
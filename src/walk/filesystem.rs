@@ -0,0 +1,251 @@
+use std::fmt::Debug;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use walkdir::{DirEntry, WalkDir};
+
+use crate::walk::WalkBehavior;
+
+/// Type of a file in a [`Filesystem`].
+///
+/// This trait is implemented by [`std::fs::FileType`].
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`std::fs::FileType`]: std::fs::FileType
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub trait FileType: Clone + Debug {
+    /// Returns `true` if the file is a directory.
+    fn is_dir(&self) -> bool;
+
+    /// Returns `true` if the file is a symbolic link.
+    fn is_symlink(&self) -> bool;
+}
+
+impl FileType for fs::FileType {
+    fn is_dir(&self) -> bool {
+        fs::FileType::is_dir(self)
+    }
+
+    fn is_symlink(&self) -> bool {
+        fs::FileType::is_symlink(self)
+    }
+}
+
+/// Metadata of a file in a [`Filesystem`].
+///
+/// This trait is implemented by [`std::fs::Metadata`].
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`std::fs::Metadata`]: std::fs::Metadata
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub trait Metadata: Clone + Debug {
+    type FileType: FileType;
+
+    /// Gets the type of the file.
+    fn file_type(&self) -> Self::FileType;
+
+    /// Gets the size of the file in bytes, if any.
+    ///
+    /// The default implementation returns `None`.
+    fn size(&self) -> Option<u64> {
+        None
+    }
+
+    /// Gets the last modification time of the file, if any.
+    ///
+    /// The default implementation returns `None`.
+    fn modified(&self) -> Option<SystemTime> {
+        None
+    }
+}
+
+impl Metadata for fs::Metadata {
+    type FileType = fs::FileType;
+
+    fn file_type(&self) -> Self::FileType {
+        fs::Metadata::file_type(self)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.len())
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        fs::Metadata::modified(self).ok()
+    }
+}
+
+/// File system that can be traversed by functions like [`Glob::walk`].
+///
+/// `Filesystem` provides the operations used to read directory trees. By
+/// default, directory trees are read from the native file system via
+/// [`StdFilesystem`], but other implementations can be used with functions like
+/// [`Glob::walk_with_filesystem`] to match [`Glob`]s against in-memory trees,
+/// remote listings, test fixtures, etc.
+///
+/// `Filesystem`s are cloned into each [`WalkEntry`], so implementations that
+/// share state should be cheap to clone, such as via [`Arc`].
+///
+/// Paths given to and returned by a `Filesystem` are composed from the
+/// directory given to functions like [`Glob::walk_with_filesystem`] and the
+/// paths of the entries returned by [`read_dir`].
+///
+/// [`Arc`]: std::sync::Arc
+/// [`Glob`]: crate::Glob
+/// [`Glob::walk`]: crate::Glob::walk
+/// [`Glob::walk_with_filesystem`]: crate::Glob::walk_with_filesystem
+/// [`read_dir`]: crate::Filesystem::read_dir
+/// [`StdFilesystem`]: crate::StdFilesystem
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub trait Filesystem: 'static + Clone + Debug {
+    type FileType: FileType;
+    type Metadata: Metadata<FileType = Self::FileType>;
+    /// Iterator over the entries in a directory.
+    ///
+    /// Each entry is the path of a file in the directory, its type, and its
+    /// metadata if the metadata is read along with the directory. The type and
+    /// metadata of a symbolic link describe the link file itself rather than
+    /// its target.
+    ///
+    /// Metadata should only be provided if it requires no additional queries,
+    /// such as when the platform reads metadata along with directory entries.
+    /// Otherwise, metadata is queried if and when it is requested.
+    type ReadDir: Iterator<Item = io::Result<(PathBuf, Self::FileType, Option<Self::Metadata>)>>;
+
+    /// Reads the entries in a directory.
+    ///
+    /// The path of each entry must be the path of the directory joined with
+    /// the name of the file.
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir>;
+
+    /// Gets the metadata of a file, following symbolic links.
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata>;
+
+    /// Gets the metadata of a file without following symbolic links.
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata>;
+
+    /// Gets the canonical path of a file, resolving any symbolic links.
+    ///
+    /// This is used to detect cycles when following symbolic links. The
+    /// default implementation returns the path as is, which is sufficient for
+    /// file systems that do not support symbolic links.
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        Ok(path.to_path_buf())
+    }
+
+    /// Returns `true` if the file system descends into directories without
+    /// following symbolic links.
+    ///
+    /// When a traversal is [confined][`Confinement::Confined`], each directory
+    /// is resolved via [`canonicalize`] and verified to be beneath the root
+    /// before it is read, unless the file system is confining. Confining file
+    /// systems open each directory relative to an ancestor without following
+    /// symbolic links, such as via `openat` and `O_NOFOLLOW`, so that a
+    /// directory that is replaced by a symbolic link cannot be read. Otherwise,
+    /// a directory may be replaced between its verification and reading, so
+    /// confinement cannot be guaranteed if the directory tree is modified
+    /// concurrently. This should reflect the behavior with which the file
+    /// system is [configured][`configure`]. The default implementation returns
+    /// `false`.
+    ///
+    /// [`canonicalize`]: crate::Filesystem::canonicalize
+    /// [`configure`]: crate::Filesystem::configure
+    /// [`Confinement::Confined`]: crate::Confinement::Confined
+    fn is_confining(&self) -> bool {
+        false
+    }
+
+    /// Configures the file system for a traversal with the given behavior.
+    ///
+    /// This is called once when a traversal begins and before any queries
+    /// are made. File systems can use this to honor behavior that concerns
+    /// how queries are issued, such as [`WalkBehavior::backend`]. The default
+    /// implementation does nothing.
+    ///
+    /// [`WalkBehavior::backend`]: crate::WalkBehavior::backend
+    fn configure(&mut self, behavior: &WalkBehavior) {
+        let _ = behavior;
+    }
+}
+
+/// The native file system.
+///
+/// This [`Filesystem`] reads directories via [`walkdir`] and metadata via
+/// [`std::fs`] and is used by functions like [`Glob::walk`].
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`Glob::walk`]: crate::Glob::walk
+/// [`std::fs`]: std::fs
+/// [`walkdir`]: walkdir
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFilesystem;
+
+impl Filesystem for StdFilesystem {
+    type FileType = fs::FileType;
+    type Metadata = fs::Metadata;
+    type ReadDir = StdEntries;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+        let mut entries = WalkDir::new(path)
+            .min_depth(1)
+            .max_depth(1)
+            .follow_links(false)
+            .into_iter();
+        // `walkdir` opens directories when their entries are first read, so
+        // read the first entry to detect errors when opening the directory.
+        let first = match entries.next() {
+            Some(Err(error)) => return Err(error.into()),
+            first => first,
+        };
+        Ok(StdEntries { first, entries })
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        fs::metadata(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        fs::symlink_metadata(path)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// Iterator over the entries in a directory of a [`StdFilesystem`].
+///
+/// [`StdFilesystem`]: crate::StdFilesystem
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+pub struct StdEntries {
+    // The first entry, which is read when the directory is opened.
+    first: Option<walkdir::Result<DirEntry>>,
+    entries: walkdir::IntoIter,
+}
+
+impl Iterator for StdEntries {
+    type Item = io::Result<(PathBuf, fs::FileType, Option<fs::Metadata>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.first.take().or_else(|| self.entries.next())?;
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(error) => return Some(Err(error.into())),
+        };
+        // Metadata is read along with directory entries on Windows, but must
+        // be queried separately on Unix.
+        #[cfg(windows)]
+        let metadata = match entry.metadata() {
+            Ok(metadata) => Some(metadata),
+            Err(error) => return Some(Err(error.into())),
+        };
+        #[cfg(not(windows))]
+        let metadata = None;
+        let file_type = entry.file_type();
+        Some(Ok((entry.into_path(), file_type, metadata)))
+    }
+}
@@ -0,0 +1,701 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::vec;
+
+use crate::walk::{Filesystem, Metadata, StdFilesystem, Walk, WalkEntry, WalkError};
+
+const MAGIC: &[u8; 11] = b"wax-changes";
+const VERSION: u8 = 1;
+
+// Timestamps are coarse, so files and directories that are modified shortly
+// before a traversal begins may be modified again without changing their
+// stamps. Such stamps are never considered unchanged. Some file systems, such
+// as FAT, record modification times with a granularity of two seconds.
+const RACY_INTERVAL: Duration = Duration::from_secs(2);
+
+// Modification time and size of a file or directory.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct Stamp {
+    modified: Option<SystemTime>,
+    size: Option<u64>,
+}
+
+impl Stamp {
+    pub fn from_metadata<M>(metadata: &M) -> Self
+    where
+        M: Metadata,
+    {
+        Stamp {
+            modified: metadata.modified(),
+            size: metadata.size(),
+        }
+    }
+
+    // Returns `true` if the stamp is the same as a stamp recorded by a
+    // traversal that began at the given time. Stamps without a modification
+    // time are never unchanged.
+    fn is_unchanged(&self, recorded: &Stamp, time: SystemTime) -> bool {
+        self == recorded
+            && self.modified.map_or(false, |modified| {
+                modified
+                    .checked_add(RACY_INTERVAL)
+                    .map_or(false, |modified| modified < time)
+            })
+    }
+}
+
+// Identifies the traversal that recorded a cache. A cache can only be used by
+// traversals with the same glob, root, and interpretation of symbolic links.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Key {
+    pattern: String,
+    root: PathBuf,
+    is_following_links: bool,
+}
+
+/// Record of the directories and matched files of a traversal.
+///
+/// A `ChangeCache` is recorded by an [`IncrementalWalk`] and is given to the
+/// next [`IncrementalWalk`] of the same directory tree, which then yields only
+/// the files that have been added, modified, or removed since the cache was
+/// recorded. Caches are written to and read from a user-provided store via
+/// [`ChangeCache::write_to`] and [`ChangeCache::read_from`], so they can
+/// persist between processes.
+///
+/// A cache records the modification time and size of each directory that is
+/// read and each file that is matched. Directories with the same modification
+/// time and size as recorded are not read again. Instead, the directories and
+/// matched files that were recorded beneath them are queried directly. Files
+/// with a different modification time or size are modified.
+///
+/// A cache can only be used by a traversal with the same [`Glob`], root
+/// directory, [`WalkBehavior::case`], and [`WalkBehavior::link`]. Otherwise,
+/// the cache is ignored and all matched files are added.
+///
+/// [`ChangeCache::read_from`]: crate::ChangeCache::read_from
+/// [`ChangeCache::write_to`]: crate::ChangeCache::write_to
+/// [`Glob`]: crate::Glob
+/// [`IncrementalWalk`]: crate::IncrementalWalk
+/// [`WalkBehavior::case`]: crate::WalkBehavior::case
+/// [`WalkBehavior::link`]: crate::WalkBehavior::link
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct ChangeCache {
+    key: Option<Key>,
+    // The time at which the recording traversal began.
+    time: Option<SystemTime>,
+    // Directories that have been yielded by the traversal. Directories that
+    // have been completely read have a stamp.
+    directories: BTreeMap<PathBuf, Option<Stamp>>,
+    matches: BTreeMap<PathBuf, Stamp>,
+}
+
+impl ChangeCache {
+    /// Constructs an empty `ChangeCache` with which all matched files are added.
+    pub fn new() -> Self {
+        ChangeCache::default()
+    }
+
+    /// Reads a cache that has been written by [`ChangeCache::write_to`].
+    ///
+    /// Reads are not buffered, so a buffered reader should be used when
+    /// reading from files and other sources with costly reads.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be read. If the data is not a cache
+    /// written by this version of Wax, then the error is
+    /// [`io::ErrorKind::InvalidData`].
+    ///
+    /// [`ChangeCache::write_to`]: crate::ChangeCache::write_to
+    /// [`io::ErrorKind::InvalidData`]: std::io::ErrorKind::InvalidData
+    pub fn read_from(reader: impl Read) -> io::Result<Self> {
+        let mut reader = reader;
+        let reader = &mut reader;
+        let mut magic = [0u8; MAGIC.len()];
+        reader.read_exact(&mut magic)?;
+        if &magic != MAGIC || read_u8(reader)? != VERSION {
+            return Err(invalid_data("unrecognized change cache"));
+        }
+        let key = if read_bool(reader)? {
+            Some(Key {
+                pattern: String::from_utf8(read_bytes(reader)?)
+                    .map_err(|_| invalid_data("glob pattern is not UTF-8"))?,
+                root: read_path(reader)?,
+                is_following_links: read_bool(reader)?,
+            })
+        }
+        else {
+            None
+        };
+        let time = read_time(reader)?;
+        let mut directories = BTreeMap::new();
+        for _ in 0..read_u64(reader)? {
+            let path = read_path(reader)?;
+            let stamp = if read_bool(reader)? {
+                Some(read_stamp(reader)?)
+            }
+            else {
+                None
+            };
+            directories.insert(path, stamp);
+        }
+        let mut matches = BTreeMap::new();
+        for _ in 0..read_u64(reader)? {
+            matches.insert(read_path(reader)?, read_stamp(reader)?);
+        }
+        Ok(ChangeCache {
+            key,
+            time,
+            directories,
+            matches,
+        })
+    }
+
+    /// Writes the cache so that it can be read by [`ChangeCache::read_from`].
+    ///
+    /// Writes are not buffered, so a buffered writer should be used when
+    /// writing to files and other sinks with costly writes. The encoding of
+    /// paths is specific to the target platform, so caches should not be
+    /// read on other platforms.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the cache cannot be written.
+    ///
+    /// [`ChangeCache::read_from`]: crate::ChangeCache::read_from
+    pub fn write_to(&self, writer: impl Write) -> io::Result<()> {
+        let mut writer = writer;
+        let writer = &mut writer;
+        writer.write_all(MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        write_bool(writer, self.key.is_some())?;
+        if let Some(ref key) = self.key {
+            write_bytes(writer, key.pattern.as_bytes())?;
+            write_path(writer, &key.root)?;
+            write_bool(writer, key.is_following_links)?;
+        }
+        write_time(writer, self.time)?;
+        write_u64(writer, self.directories.len() as u64)?;
+        for (path, stamp) in self.directories.iter() {
+            write_path(writer, path)?;
+            write_bool(writer, stamp.is_some())?;
+            if let Some(stamp) = stamp {
+                write_stamp(writer, stamp)?;
+            }
+        }
+        write_u64(writer, self.matches.len() as u64)?;
+        for (path, stamp) in self.matches.iter() {
+            write_path(writer, path)?;
+            write_stamp(writer, stamp)?;
+        }
+        writer.flush()
+    }
+
+    /// Gets an iterator over the paths of the matched files in the cache.
+    pub fn paths(&self) -> impl '_ + Iterator<Item = &'_ Path> {
+        self.matches.keys().map(PathBuf::as_path)
+    }
+
+    /// Gets the number of matched files in the cache.
+    pub fn len(&self) -> usize {
+        self.matches.len()
+    }
+
+    /// Returns `true` if the cache has no matched files.
+    pub fn is_empty(&self) -> bool {
+        self.matches.is_empty()
+    }
+}
+
+// Change of a matched file, not including removal.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Difference {
+    Added,
+    Modified,
+}
+
+// Changes detected by a traversal. The cache recorded by a previous traversal
+// is compared with the cache that is recorded by the traversal.
+#[derive(Debug)]
+pub struct Changes {
+    previous: ChangeCache,
+    // Paths in the previous cache keyed by the paths of their parent
+    // directories.
+    listings: HashMap<PathBuf, BTreeSet<PathBuf>>,
+    next: ChangeCache,
+    // Directories that could not be read. Matched files beneath these
+    // directories are never removed.
+    failures: Vec<PathBuf>,
+}
+
+impl Changes {
+    pub fn new(previous: ChangeCache, key: Key) -> Self {
+        let previous = if previous.key.as_ref() == Some(&key) {
+            previous
+        }
+        else {
+            ChangeCache::default()
+        };
+        let mut listings: HashMap<_, BTreeSet<_>> = HashMap::new();
+        for path in previous.directories.keys().chain(previous.matches.keys()) {
+            if let Some(parent) = path.parent() {
+                listings
+                    .entry(parent.to_path_buf())
+                    .or_default()
+                    .insert(path.clone());
+            }
+        }
+        Changes {
+            previous,
+            listings,
+            next: ChangeCache {
+                key: Some(key),
+                time: Some(SystemTime::now()),
+                directories: BTreeMap::new(),
+                matches: BTreeMap::new(),
+            },
+            failures: vec![],
+        }
+    }
+
+    // Gets the recorded directories and matched files in a directory if the
+    // directory is unchanged since the previous traversal.
+    pub fn listing(&self, path: &Path, stamp: Option<&Stamp>) -> Option<Vec<PathBuf>> {
+        let time = self.previous.time?;
+        let recorded = self.previous.directories.get(path)?.as_ref()?;
+        if stamp?.is_unchanged(recorded, time) {
+            Some(
+                self.listings
+                    .get(path)
+                    .map(|paths| paths.iter().cloned().collect())
+                    .unwrap_or_default(),
+            )
+        }
+        else {
+            None
+        }
+    }
+
+    pub fn insert_directory(&mut self, path: &Path) {
+        self.next
+            .directories
+            .entry(path.to_path_buf())
+            .or_insert(None);
+    }
+
+    // Records the stamp of a directory that has been completely read.
+    pub fn complete_directory(&mut self, path: &Path, stamp: Stamp) {
+        self.next
+            .directories
+            .insert(path.to_path_buf(), Some(stamp));
+    }
+
+    pub fn fail_directory(&mut self, path: &Path) {
+        self.failures.push(path.to_path_buf());
+    }
+
+    pub fn insert_match(&mut self, path: &Path, stamp: Stamp) -> Option<Difference> {
+        let difference = match (self.previous.matches.get(path), self.previous.time) {
+            (Some(recorded), Some(time)) if stamp.is_unchanged(recorded, time) => None,
+            (Some(_), _) => Some(Difference::Modified),
+            (None, _) => Some(Difference::Added),
+        };
+        self.next.matches.insert(path.to_path_buf(), stamp);
+        difference
+    }
+
+    // Gets the paths of files that were matched by the previous traversal but
+    // have not been matched. Files beneath directories that could not be read
+    // are retained rather than removed.
+    pub fn remove_unmatched(&mut self) -> Vec<PathBuf> {
+        let mut removed = vec![];
+        for (path, stamp) in self.previous.matches.iter() {
+            if self.next.matches.contains_key(path) {
+                continue;
+            }
+            if self
+                .failures
+                .iter()
+                .any(|directory| path.starts_with(directory))
+            {
+                self.next.matches.insert(path.clone(), *stamp);
+            }
+            else {
+                removed.push(path.clone());
+            }
+        }
+        removed
+    }
+
+    // Retains the recorded stamp of a file that was matched by the previous
+    // traversal, so that any change is detected by the next traversal.
+    pub fn retain(&mut self, path: &Path) {
+        if let Some(stamp) = self.previous.matches.get(path) {
+            self.next.matches.insert(path.to_path_buf(), *stamp);
+        }
+    }
+
+    pub fn retain_unmatched(&mut self) {
+        for (path, stamp) in self.previous.matches.iter() {
+            self.next.matches.entry(path.clone()).or_insert(*stamp);
+        }
+    }
+
+    pub fn into_cache(self) -> ChangeCache {
+        self.next
+    }
+}
+
+/// Change to a file that matches a [`Glob`] since a previous traversal.
+///
+/// See [`IncrementalWalk`].
+///
+/// [`Glob`]: crate::Glob
+/// [`IncrementalWalk`]: crate::IncrementalWalk
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum Change<S = StdFilesystem>
+where
+    S: Filesystem,
+{
+    /// A matched file that was not matched by the previous traversal.
+    Added(WalkEntry<'static, S>),
+    /// A matched file with a different modification time or size than when
+    /// it was matched by the previous traversal.
+    Modified(WalkEntry<'static, S>),
+    /// The path of a file that was matched by the previous traversal but is
+    /// no longer matched, such as because it has been removed.
+    Removed(PathBuf),
+}
+
+impl<S> Change<S>
+where
+    S: Filesystem,
+{
+    /// Gets the path of the changed file.
+    pub fn path(&self) -> &Path {
+        match self {
+            Change::Added(entry) | Change::Modified(entry) => entry.path(),
+            Change::Removed(path) => path,
+        }
+    }
+
+    /// Gets the [`WalkEntry`] of the changed file, if any.
+    ///
+    /// Returns `None` if the file has been [removed][`Change::Removed`].
+    ///
+    /// [`Change::Removed`]: crate::Change::Removed
+    /// [`WalkEntry`]: crate::WalkEntry
+    pub fn entry(&self) -> Option<&WalkEntry<'static, S>> {
+        match self {
+            Change::Added(entry) | Change::Modified(entry) => Some(entry),
+            Change::Removed(_) => None,
+        }
+    }
+}
+
+/// Iterator over changes to files matching a [`Glob`] in a directory tree.
+///
+/// `IncrementalWalk` is constructed by [`Walk::incremental`]. It matches a
+/// [`Glob`] against a directory tree like [`Walk`], but yields only the
+/// [changes][`Change`] since the traversal that recorded a [`ChangeCache`].
+/// Added and modified files are yielded as they are matched and removed files
+/// are yielded after the directory tree has been read. The cache for the next
+/// traversal is recorded as the directory tree is read and is retrieved via
+/// [`IncrementalWalk::into_cache`].
+///
+/// Directories that have not changed since the cache was recorded are not
+/// read. Changes are detected via the modification times and sizes of
+/// directories and matched files, so the metadata of each directory and
+/// matched file is queried. A directory is changed when files are added to,
+/// removed from, or renamed within the directory, but not when the contents
+/// of its files change.
+///
+/// If a directory cannot be read, then an error is yielded and the files that
+/// were previously matched beneath it are neither yielded as removed nor
+/// removed from the cache.
+///
+/// [`Change`]: crate::Change
+/// [`ChangeCache`]: crate::ChangeCache
+/// [`Glob`]: crate::Glob
+/// [`IncrementalWalk::into_cache`]: crate::IncrementalWalk::into_cache
+/// [`Walk`]: crate::Walk
+/// [`Walk::incremental`]: crate::Walk::incremental
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+#[must_use]
+pub struct IncrementalWalk<'g, S = StdFilesystem>
+where
+    S: Filesystem,
+{
+    walk: Walk<'g, S>,
+    // Files that have been removed, once the directory tree has been read.
+    removed: Option<vec::IntoIter<PathBuf>>,
+}
+
+impl<'g, S> IncrementalWalk<'g, S>
+where
+    S: Filesystem,
+{
+    pub(in crate::walk) fn new(walk: Walk<'g, S>, cache: ChangeCache) -> Self {
+        let mut walk = walk;
+        let key = Key {
+            pattern: walk.pattern.as_str().to_owned(),
+            root: walk.root.clone(),
+            is_following_links: walk.walk.is_following_links(),
+        };
+        walk.walk.set_changes(Changes::new(cache, key));
+        IncrementalWalk {
+            walk,
+            removed: None,
+        }
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> IncrementalWalk<'static, S> {
+        let IncrementalWalk { walk, removed } = self;
+        IncrementalWalk {
+            walk: walk.into_owned(),
+            removed,
+        }
+    }
+
+    /// Converts into the [`ChangeCache`] recorded by the traversal.
+    ///
+    /// The cache is complete once the iterator has been exhausted. If it is
+    /// converted before then, then the cache retains the records of the
+    /// previous cache for any files that have not been matched, so that their
+    /// changes are yielded by the next traversal.
+    ///
+    /// [`ChangeCache`]: crate::ChangeCache
+    pub fn into_cache(mut self) -> ChangeCache {
+        let mut changes = self
+            .walk
+            .walk
+            .take_changes()
+            .expect("incremental traversal has no changes");
+        match self.removed {
+            Some(removed) => {
+                for path in removed {
+                    changes.retain(&path);
+                }
+            },
+            None => changes.retain_unmatched(),
+        }
+        changes.into_cache()
+    }
+
+    /// Gets the root directory of the traversal.
+    ///
+    /// See [`Walk::root`].
+    ///
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn root(&self) -> &Path {
+        self.walk.root()
+    }
+
+    fn changes(&mut self) -> &mut Changes {
+        self.walk
+            .walk
+            .changes_mut()
+            .expect("incremental traversal has no changes")
+    }
+}
+
+impl<S> Iterator for IncrementalWalk<'_, S>
+where
+    S: Filesystem,
+{
+    type Item = Result<Change<S>, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.removed.is_none() {
+            while let Some(entry) = self.walk.next() {
+                let entry = match entry {
+                    Ok(entry) => entry,
+                    Err(error) => return Some(Err(error)),
+                };
+                // Files with unknown metadata are recorded without a stamp,
+                // so that they are modified in the next traversal.
+                let (stamp, error) = match entry.metadata() {
+                    Ok(metadata) => (Stamp::from_metadata(&metadata), None),
+                    Err(error) => (Stamp::default(), Some(error)),
+                };
+                let difference = self.changes().insert_match(entry.path(), stamp);
+                if let Some(error) = error {
+                    return Some(Err(error));
+                }
+                match difference {
+                    Some(Difference::Added) => return Some(Ok(Change::Added(entry))),
+                    Some(Difference::Modified) => return Some(Ok(Change::Modified(entry))),
+                    None => {},
+                }
+            }
+            self.removed = Some(self.changes().remove_unmatched().into_iter());
+        }
+        self.removed
+            .as_mut()
+            .and_then(Iterator::next)
+            .map(|path| Ok(Change::Removed(path)))
+    }
+}
+
+fn invalid_data(message: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+fn read_u8(reader: &mut impl Read) -> io::Result<u8> {
+    let mut bytes = [0u8; 1];
+    reader.read_exact(&mut bytes)?;
+    Ok(bytes[0])
+}
+
+fn read_bool(reader: &mut impl Read) -> io::Result<bool> {
+    match read_u8(reader)? {
+        0 => Ok(false),
+        1 => Ok(true),
+        _ => Err(invalid_data("invalid boolean")),
+    }
+}
+
+fn read_u64(reader: &mut impl Read) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+fn read_bytes(reader: &mut impl Read) -> io::Result<Vec<u8>> {
+    let len = read_u64(reader)?;
+    // The buffer is not allocated from the length, which may be corrupt.
+    let mut bytes = vec![];
+    reader.take(len).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 == len {
+        Ok(bytes)
+    }
+    else {
+        Err(io::ErrorKind::UnexpectedEof.into())
+    }
+}
+
+#[cfg(unix)]
+fn read_path(reader: &mut impl Read) -> io::Result<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::unix::ffi::OsStringExt as _;
+
+    read_bytes(reader).map(|bytes| OsString::from_vec(bytes).into())
+}
+
+#[cfg(windows)]
+fn read_path(reader: &mut impl Read) -> io::Result<PathBuf> {
+    use std::ffi::OsString;
+    use std::os::windows::ffi::OsStringExt as _;
+
+    let bytes = read_bytes(reader)?;
+    if bytes.len() % 2 != 0 {
+        return Err(invalid_data("invalid wide path"));
+    }
+    let wide: Vec<_> = bytes
+        .chunks_exact(2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .collect();
+    Ok(OsString::from_wide(&wide).into())
+}
+
+#[cfg(not(any(unix, windows)))]
+fn read_path(reader: &mut impl Read) -> io::Result<PathBuf> {
+    String::from_utf8(read_bytes(reader)?)
+        .map(PathBuf::from)
+        .map_err(|_| invalid_data("path is not UTF-8"))
+}
+
+fn read_time(reader: &mut impl Read) -> io::Result<Option<SystemTime>> {
+    if read_bool(reader)? {
+        let seconds = read_u64(reader)?;
+        let nanoseconds = read_u64(reader)?;
+        let nanoseconds =
+            u32::try_from(nanoseconds).map_err(|_| invalid_data("invalid timestamp"))?;
+        // Times that cannot be represented are read as unknown.
+        Ok(UNIX_EPOCH.checked_add(Duration::new(seconds, nanoseconds)))
+    }
+    else {
+        Ok(None)
+    }
+}
+
+fn read_stamp(reader: &mut impl Read) -> io::Result<Stamp> {
+    let modified = read_time(reader)?;
+    let size = if read_bool(reader)? {
+        Some(read_u64(reader)?)
+    }
+    else {
+        None
+    };
+    Ok(Stamp { modified, size })
+}
+
+fn write_bool(writer: &mut impl Write, value: bool) -> io::Result<()> {
+    writer.write_all(&[u8::from(value)])
+}
+
+fn write_u64(writer: &mut impl Write, value: u64) -> io::Result<()> {
+    writer.write_all(&value.to_le_bytes())
+}
+
+fn write_bytes(writer: &mut impl Write, bytes: &[u8]) -> io::Result<()> {
+    write_u64(writer, bytes.len() as u64)?;
+    writer.write_all(bytes)
+}
+
+#[cfg(unix)]
+fn write_path(writer: &mut impl Write, path: &Path) -> io::Result<()> {
+    use std::os::unix::ffi::OsStrExt as _;
+
+    write_bytes(writer, path.as_os_str().as_bytes())
+}
+
+#[cfg(windows)]
+fn write_path(writer: &mut impl Write, path: &Path) -> io::Result<()> {
+    use std::os::windows::ffi::OsStrExt as _;
+
+    let bytes: Vec<_> = path
+        .as_os_str()
+        .encode_wide()
+        .flat_map(u16::to_le_bytes)
+        .collect();
+    write_bytes(writer, &bytes)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn write_path(writer: &mut impl Write, path: &Path) -> io::Result<()> {
+    let path = path
+        .to_str()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path is not UTF-8"))?;
+    write_bytes(writer, path.as_bytes())
+}
+
+fn write_time(writer: &mut impl Write, time: Option<SystemTime>) -> io::Result<()> {
+    // Times before the epoch are written as unknown.
+    match time.and_then(|time| time.duration_since(UNIX_EPOCH).ok()) {
+        Some(duration) => {
+            write_bool(writer, true)?;
+            write_u64(writer, duration.as_secs())?;
+            write_u64(writer, u64::from(duration.subsec_nanos()))
+        },
+        None => write_bool(writer, false),
+    }
+}
+
+fn write_stamp(writer: &mut impl Write, stamp: &Stamp) -> io::Result<()> {
+    write_time(writer, stamp.modified)?;
+    match stamp.size {
+        Some(size) => {
+            write_bool(writer, true)?;
+            write_u64(writer, size)
+        },
+        None => write_bool(writer, false),
+    }
+}
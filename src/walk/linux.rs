@@ -0,0 +1,1209 @@
+#![cfg(target_os = "linux")]
+
+use std::ffi::{CString, OsStr, OsString};
+use std::fmt::{self, Debug, Formatter};
+use std::fs;
+use std::io;
+use std::mem::MaybeUninit;
+use std::os::raw::c_int;
+use std::os::unix::ffi::OsStrExt as _;
+use std::os::unix::fs::FileTypeExt as _;
+use std::os::unix::io::RawFd;
+use std::path::{Component, Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError, Weak};
+use std::time::{Duration, SystemTime};
+#[cfg(feature = "io-uring")]
+use std::vec;
+
+#[cfg(feature = "io-uring")]
+use crate::walk::uring::{Operation, Ring, Statx};
+use crate::walk::{
+    DescriptorBudget, FileType, Filesystem, LinkBehavior, Metadata, WalkBackend, WalkBehavior,
+};
+
+// The size of the buffer into which directory entries are read. Each call to
+// `getdents64` reads as many entries as fit into this buffer.
+const DIRENT_BUFFER_SIZE: usize = 16 * 1024;
+// The maximum number of subdirectories that are opened before they are read
+// when pipelining queries.
+#[cfg(feature = "io-uring")]
+const PREFETCH_LIMIT: usize = 256;
+
+// Open file descriptor of a directory that is closed when dropped.
+#[derive(Debug)]
+struct Descriptor(RawFd);
+
+impl Descriptor {
+    // Opens a directory relative to the given directory descriptor with the
+    // given additional flags.
+    fn open_dir(parent: RawFd, path: &Path, flags: c_int) -> io::Result<Self> {
+        let path = c_path(path)?;
+        // SAFETY: `path` is a nul-terminated string and `parent` is either an
+        //         open descriptor or `AT_FDCWD`.
+        let fd = unsafe {
+            libc::openat(
+                parent,
+                path.as_ptr(),
+                libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC | flags,
+            )
+        };
+        if fd < 0 {
+            Err(io::Error::last_os_error())
+        }
+        else {
+            Ok(Descriptor(fd))
+        }
+    }
+
+    // Gets the device and inode of the directory.
+    fn identity(&self) -> io::Result<Identity> {
+        let mut stat = MaybeUninit::<libc::stat>::uninit();
+        // SAFETY: The descriptor is open and `stat` is initialized if the call
+        //         succeeds.
+        if unsafe { libc::fstat(self.0, stat.as_mut_ptr()) } == 0 {
+            // SAFETY: `fstat` succeeded and initialized `stat`.
+            let stat = unsafe { stat.assume_init() };
+            Ok(Identity {
+                device: stat.st_dev,
+                inode: stat.st_ino,
+            })
+        }
+        else {
+            Err(io::Error::last_os_error())
+        }
+    }
+}
+
+impl Drop for Descriptor {
+    fn drop(&mut self) {
+        // SAFETY: The descriptor is open and owned exclusively by this type.
+        unsafe {
+            libc::close(self.0);
+        }
+    }
+}
+
+// Device and inode that identify a directory.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct Identity {
+    device: libc::dev_t,
+    inode: libc::ino_t,
+}
+
+// Directory that is being read. Its descriptor may be closed to remain within
+// a descriptor budget, in which case the directory is reopened via its path
+// when it is next read.
+#[derive(Debug)]
+struct Handle {
+    path: PathBuf,
+    descriptor: Mutex<Option<Descriptor>>,
+    // The identity of the directory when its descriptor is closed. A reopened
+    // directory must have the same identity, because reading resumes at a
+    // position that is only meaningful in the same directory.
+    identity: Mutex<Option<Identity>>,
+}
+
+impl Handle {
+    fn descriptor(&self) -> MutexGuard<'_, Option<Descriptor>> {
+        self.descriptor
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Closes the descriptor of the directory, if any, and returns `true` if it
+    // was open.
+    fn close(&self) -> bool {
+        match self.descriptor().take() {
+            Some(descriptor) => {
+                *self.identity() = descriptor.identity().ok();
+                true
+            },
+            None => false,
+        }
+    }
+
+    // Verifies that a reopened descriptor refers to the same directory as the
+    // descriptor that was closed.
+    fn verify(&self, descriptor: Descriptor) -> io::Result<Descriptor> {
+        match *self.identity() {
+            Some(identity) if identity != descriptor.identity()? => Err(io::Error::new(
+                io::ErrorKind::Other,
+                "directory was replaced while it was being read",
+            )),
+            _ => Ok(descriptor),
+        }
+    }
+
+    fn identity(&self) -> MutexGuard<'_, Option<Identity>> {
+        self.identity.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn is_open(&self) -> bool {
+        self.descriptor().is_some()
+    }
+}
+
+/// Type of a file in a [`LinuxFilesystem`].
+///
+/// [`LinuxFilesystem`]: crate::LinuxFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", target_os = "linux"))))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LinuxFileType {
+    // The file type bits of the mode of the file (`S_IFMT`).
+    format: u32,
+}
+
+impl LinuxFileType {
+    fn from_mode(mode: u32) -> Self {
+        LinuxFileType {
+            format: mode & libc::S_IFMT,
+        }
+    }
+
+    fn from_dirent_type(kind: u8) -> Option<Self> {
+        let format = match kind {
+            libc::DT_BLK => libc::S_IFBLK,
+            libc::DT_CHR => libc::S_IFCHR,
+            libc::DT_DIR => libc::S_IFDIR,
+            libc::DT_FIFO => libc::S_IFIFO,
+            libc::DT_LNK => libc::S_IFLNK,
+            libc::DT_REG => libc::S_IFREG,
+            libc::DT_SOCK => libc::S_IFSOCK,
+            _ => return None,
+        };
+        Some(LinuxFileType { format })
+    }
+
+    fn from_std(file_type: fs::FileType) -> Self {
+        let format = if file_type.is_symlink() {
+            libc::S_IFLNK
+        }
+        else if file_type.is_dir() {
+            libc::S_IFDIR
+        }
+        else if file_type.is_block_device() {
+            libc::S_IFBLK
+        }
+        else if file_type.is_char_device() {
+            libc::S_IFCHR
+        }
+        else if file_type.is_fifo() {
+            libc::S_IFIFO
+        }
+        else if file_type.is_socket() {
+            libc::S_IFSOCK
+        }
+        else {
+            libc::S_IFREG
+        };
+        LinuxFileType { format }
+    }
+
+    /// Returns `true` if the file is a regular file.
+    pub fn is_file(&self) -> bool {
+        self.format == libc::S_IFREG
+    }
+}
+
+impl FileType for LinuxFileType {
+    fn is_dir(&self) -> bool {
+        self.format == libc::S_IFDIR
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.format == libc::S_IFLNK
+    }
+}
+
+/// Metadata of a file in a [`LinuxFilesystem`].
+///
+/// [`LinuxFilesystem`]: crate::LinuxFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", target_os = "linux"))))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct LinuxMetadata {
+    mode: u32,
+    uid: u32,
+    gid: u32,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl LinuxMetadata {
+    fn new(mode: u32, uid: u32, gid: u32, size: u64, seconds: i64, nanoseconds: u32) -> Self {
+        let nanoseconds = Duration::from_nanos(nanoseconds.into());
+        let modified = if seconds >= 0 {
+            SystemTime::UNIX_EPOCH.checked_add(Duration::from_secs(seconds as u64) + nanoseconds)
+        }
+        else {
+            SystemTime::UNIX_EPOCH
+                .checked_sub(Duration::from_secs(seconds.unsigned_abs()))
+                .and_then(|time| time.checked_add(nanoseconds))
+        };
+        LinuxMetadata {
+            mode,
+            uid,
+            gid,
+            size,
+            modified,
+        }
+    }
+
+    fn from_stat(stat: &libc::stat) -> Self {
+        LinuxMetadata::new(
+            stat.st_mode,
+            stat.st_uid,
+            stat.st_gid,
+            stat.st_size as u64,
+            stat.st_mtime,
+            stat.st_mtime_nsec as u32,
+        )
+    }
+
+    #[cfg(feature = "io-uring")]
+    fn from_statx(statx: &Statx) -> Self {
+        LinuxMetadata::new(
+            statx.stx_mode.into(),
+            statx.stx_uid,
+            statx.stx_gid,
+            statx.stx_size,
+            statx.stx_mtime.tv_sec,
+            statx.stx_mtime.tv_nsec,
+        )
+    }
+
+    /// Gets the mode of the file, including its permissions.
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// Gets the user ID of the owner of the file.
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// Gets the group ID of the owner of the file.
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+}
+
+impl Metadata for LinuxMetadata {
+    type FileType = LinuxFileType;
+
+    fn file_type(&self) -> Self::FileType {
+        LinuxFileType::from_mode(self.mode)
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.size)
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+/// [`Filesystem`] that reads directories via `openat` and `getdents64` on
+/// Linux.
+///
+/// `LinuxFilesystem` opens each directory relative to the open descriptor of
+/// its parent and reads its entries in large batches, so paths are not
+/// resolved again as a traversal descends into a directory tree and the types
+/// of files are read along with the directory. Descriptors are retained only
+/// for the directories that are being read. Metadata is also queried relative
+/// to these descriptors.
+///
+/// If these system calls are unavailable, such as in some sandboxes, then
+/// directories are read via [`std::fs::read_dir`] instead.
+///
+/// The number of open descriptors can be limited via
+/// [`WalkBehavior::descriptors`]. With the `io-uring` feature, queries can be
+/// pipelined via [`WalkBehavior::backend`].
+///
+/// When a traversal is [confined][`WalkBehavior::confinement`] and reads
+/// symbolic links as files, each directory is opened beneath an open ancestor
+/// with `O_NOFOLLOW`, so a directory that is replaced by a symbolic link is
+/// never read. The descriptor of the root is then kept open regardless of the
+/// budget.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::{Glob, LinuxFilesystem, WalkBehavior};
+///
+/// let glob = Glob::new("**/*.{c,h}").unwrap();
+/// let filesystem = LinuxFilesystem::new();
+/// for entry in glob.walk_with_filesystem(filesystem, "/usr/src", WalkBehavior::default()) {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// ```
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`std::fs::read_dir`]: std::fs::read_dir
+/// [`WalkBehavior::backend`]: crate::WalkBehavior::backend
+/// [`WalkBehavior::confinement`]: crate::WalkBehavior::confinement
+/// [`WalkBehavior::descriptors`]: crate::WalkBehavior::descriptors
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", target_os = "linux"))))]
+#[derive(Clone, Default)]
+pub struct LinuxFilesystem {
+    state: Arc<Mutex<State>>,
+    backend: WalkBackend,
+    descriptors: Option<DescriptorBudget>,
+    is_confined: bool,
+}
+
+impl LinuxFilesystem {
+    /// Constructs a `LinuxFilesystem`.
+    pub fn new() -> Self {
+        LinuxFilesystem::default()
+    }
+
+    fn state(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    // Gets the maximum number of open descriptors.
+    fn limit(&self) -> usize {
+        self.descriptors
+            .as_ref()
+            .map_or(usize::MAX, DescriptorBudget::limit)
+    }
+
+    fn stat(&self, path: &Path, flags: c_int) -> io::Result<LinuxMetadata> {
+        self.state()
+            .with_parent(path, |parent, name| stat(parent, name, flags))
+    }
+
+    // Gets the additional flags with which subdirectories are prefetched.
+    #[cfg(feature = "io-uring")]
+    fn flags(&self) -> c_int {
+        if self.is_confined {
+            libc::O_NOFOLLOW
+        }
+        else {
+            0
+        }
+    }
+}
+
+impl Debug for LinuxFilesystem {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("LinuxFilesystem")
+            .field("backend", &self.backend)
+            .field("descriptors", &self.descriptors)
+            .field("is_confined", &self.is_confined)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Filesystem for LinuxFilesystem {
+    type FileType = LinuxFileType;
+    type Metadata = LinuxMetadata;
+    type ReadDir = DentsEntries;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+        let mut state = self.state();
+        let descriptor = match state.open(path, self.is_confined) {
+            Ok(descriptor) => descriptor,
+            // `std::fs::read_dir` follows symbolic links, so confined
+            // traversals do not fall back to it.
+            Err(error) if error.raw_os_error() == Some(libc::ENOSYS) && !self.is_confined => {
+                return DentsEntries::fallback(path);
+            },
+            Err(error) => return Err(error),
+        };
+        state.spill(self.limit(), self.is_confined);
+        let handle = Arc::new(Handle {
+            path: path.to_path_buf(),
+            descriptor: Mutex::new(Some(descriptor)),
+            identity: Mutex::new(None),
+        });
+        state.push(&handle);
+        match self.backend {
+            #[cfg(feature = "io-uring")]
+            WalkBackend::IoUring => Ok(state.read_pipelined(self, handle)),
+            _ => Ok(DentsEntries::dents(self, handle)),
+        }
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        self.stat(path, 0)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        self.stat(path, libc::AT_SYMLINK_NOFOLLOW)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+
+    fn is_confining(&self) -> bool {
+        self.is_confined
+    }
+
+    fn configure(&mut self, behavior: &WalkBehavior) {
+        self.backend = behavior.backend;
+        self.descriptors = behavior.descriptors;
+        // Followed symbolic links must be opened as such, so only traversals
+        // that read links as files are confined by opening directories
+        // without following links. Otherwise, directories are verified by the
+        // traversal.
+        self.is_confined =
+            behavior.confinement.is_confined() && matches!(behavior.link, LinkBehavior::ReadFile);
+    }
+}
+
+// Descriptors shared by clones of a `LinuxFilesystem`.
+//
+// To avoid deadlocks, the lock of a `Handle` may be acquired while this state
+// is locked, but not the other way around.
+#[derive(Default)]
+struct State {
+    // Directories that are being read, ordered by depth. This is the path from
+    // the root to the most recently read directory. Handles are owned by the
+    // iterators that read them and so are closed when reading is complete.
+    handles: Vec<Weak<Handle>>,
+    // Descriptors of subdirectories that have been opened before they are
+    // read.
+    #[cfg(feature = "io-uring")]
+    prefetched: Vec<(PathBuf, Descriptor)>,
+    // The `io_uring` instance is created when it is first used. It is `None`
+    // if it cannot be created.
+    #[cfg(feature = "io-uring")]
+    ring: Option<Option<Ring>>,
+}
+
+impl State {
+    fn handles(&self) -> impl '_ + DoubleEndedIterator<Item = Arc<Handle>> {
+        self.handles.iter().filter_map(Weak::upgrade)
+    }
+
+    // Gets the number of open descriptors.
+    fn count(&self) -> usize {
+        let n = self.handles().filter(|handle| handle.is_open()).count();
+        #[cfg(feature = "io-uring")]
+        let n = n + self.prefetched.len();
+        n
+    }
+
+    // Closes descriptors until fewer than the given number are open, so that
+    // another descriptor can be opened. Prefetched descriptors are closed
+    // first, followed by the descriptors of the shallowest directories. When
+    // confined, the descriptor of the root is never closed, because other
+    // directories are opened beneath it.
+    fn spill(&mut self, limit: usize, is_confined: bool) {
+        let mut n = self.count();
+        #[cfg(feature = "io-uring")]
+        while n >= limit && !self.prefetched.is_empty() {
+            self.prefetched.remove(0);
+            n -= 1;
+        }
+        for handle in self.handles().skip(usize::from(is_confined)) {
+            if n < limit {
+                break;
+            }
+            if handle.close() {
+                #[cfg(feature = "tracing")]
+                tracing::trace!(path = ?handle.path, "closing directory descriptor");
+                n -= 1;
+            }
+        }
+    }
+
+    // Calls a function with the descriptor of the directory that contains the
+    // given path and the name of the file in that directory. If the descriptor
+    // of the parent directory is not open, then the function is called with
+    // `AT_FDCWD` and the entire path.
+    fn with_parent<T>(&self, path: &Path, f: impl FnOnce(RawFd, &Path) -> T) -> T {
+        if let (Some(parent), Some(name)) = (path.parent(), path.file_name()) {
+            if let Some(handle) = self.handles().rev().find(|handle| handle.path == parent) {
+                if let Some(descriptor) = handle.descriptor().as_ref() {
+                    return f(descriptor.0, Path::new(name));
+                }
+            }
+        }
+        f(libc::AT_FDCWD, path)
+    }
+
+    // Opens a directory, using its prefetched descriptor if any.
+    fn open(&mut self, path: &Path, is_confined: bool) -> io::Result<Descriptor> {
+        #[cfg(feature = "io-uring")]
+        if let Some(index) = self.prefetched.iter().position(|(other, _)| other == path) {
+            return Ok(self.prefetched.swap_remove(index).1);
+        }
+        if is_confined {
+            self.open_beneath(path)
+        }
+        else {
+            self.with_parent(path, |parent, path| Descriptor::open_dir(parent, path, 0))
+        }
+    }
+
+    // Opens a directory beneath the nearest ancestor that is open, one
+    // component at a time and without following symbolic links. If there is no
+    // such ancestor, then the directory is opened via its path. This only
+    // occurs for the root, which is never closed when confined.
+    fn open_beneath(&self, path: &Path) -> io::Result<Descriptor> {
+        for handle in self.handles().rev() {
+            let components = match path.strip_prefix(&handle.path) {
+                Ok(components) if components.as_os_str().is_empty() => continue,
+                Ok(components) => components,
+                Err(_) => continue,
+            };
+            let ancestor = handle.descriptor();
+            let mut parent = match ancestor.as_ref() {
+                Some(ancestor) => ancestor.0,
+                None => continue,
+            };
+            let mut descriptor = None;
+            for component in components.components() {
+                let name = match component {
+                    Component::Normal(name) => name,
+                    _ => {
+                        return Err(io::Error::new(
+                            io::ErrorKind::InvalidInput,
+                            "path is not beneath directory",
+                        ));
+                    },
+                };
+                let child = Descriptor::open_dir(parent, Path::new(name), libc::O_NOFOLLOW)?;
+                parent = child.0;
+                descriptor = Some(child);
+            }
+            return Ok(descriptor.expect("no components beneath directory"));
+        }
+        Descriptor::open_dir(libc::AT_FDCWD, path, 0)
+    }
+
+    fn push(&mut self, handle: &Arc<Handle>) {
+        // Directories are read depth-first, so only the ancestors of the
+        // directory remain open.
+        let path = handle.path.as_path();
+        self.handles.retain(|ancestor| {
+            ancestor.upgrade().map_or(false, |ancestor| {
+                path.starts_with(&ancestor.path) && ancestor.path != path
+            })
+        });
+        self.handles.push(Arc::downgrade(handle));
+        // Prefetched subdirectories are discarded once their parent directory
+        // is no longer being read, such as when they are pruned.
+        #[cfg(feature = "io-uring")]
+        {
+            let parents: Vec<_> = self.handles().map(|handle| handle.path.clone()).collect();
+            self.prefetched.retain(|(child, _)| {
+                child
+                    .parent()
+                    .map_or(false, |parent| parents.iter().any(|other| other == parent))
+            });
+        }
+    }
+
+    #[cfg(feature = "io-uring")]
+    fn ring(&mut self) -> Option<&mut Ring> {
+        self.ring.get_or_insert_with(|| Ring::new().ok()).as_mut()
+    }
+
+    // Reads a directory and then queries the metadata of its entries and opens
+    // its subdirectories in batches via `io_uring`.
+    #[cfg(feature = "io-uring")]
+    fn read_pipelined(
+        &mut self,
+        filesystem: &LinuxFilesystem,
+        handle: Arc<Handle>,
+    ) -> DentsEntries {
+        if self.ring().is_none() {
+            return DentsEntries::dents(filesystem, handle);
+        }
+        let path = handle.path.clone();
+        let mut available = PREFETCH_LIMIT
+            .saturating_sub(self.prefetched.len())
+            .min(filesystem.limit().saturating_sub(self.count()));
+        // The descriptor of the directory is held open while its entries are
+        // read and queried, regardless of the budget.
+        let descriptor = handle.descriptor();
+        let parent = match descriptor.as_ref() {
+            Some(descriptor) => descriptor.0,
+            None => {
+                drop(descriptor);
+                return DentsEntries::dents(filesystem, handle);
+            },
+        };
+        let mut buffer = Dents::new();
+        let mut dirents = vec![];
+        let mut errors = vec![];
+        while let Some(dirent) = buffer.next(parent) {
+            match dirent {
+                Ok((name, _)) if name == "." || name == ".." => {},
+                Ok(dirent) => dirents.push(dirent),
+                // `getdents64` is unavailable, so the directory is read
+                // sequentially via `std::fs::read_dir` instead.
+                Err(error) if error.raw_os_error() == Some(libc::ENOSYS) => {
+                    drop(descriptor);
+                    return DentsEntries::dents(filesystem, handle);
+                },
+                Err(error) => errors.push(error),
+            }
+        }
+
+        let mut operations = Vec::with_capacity(dirents.len());
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC | filesystem.flags();
+        for (name, file_type) in dirents.iter() {
+            let name = match CString::new(name.as_bytes()) {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            if file_type.map_or(false, |file_type| file_type.is_dir()) && available > 0 {
+                available -= 1;
+                operations.push(Operation::Open {
+                    parent,
+                    name: name.clone(),
+                    flags,
+                });
+            }
+            operations.push(Operation::Statx {
+                parent,
+                name,
+                flags: libc::AT_SYMLINK_NOFOLLOW,
+                statx: Box::new(MaybeUninit::uninit()),
+            });
+        }
+        let results = self
+            .ring()
+            .and_then(|ring| ring.execute(operations).ok())
+            .unwrap_or_default();
+        let mut metadata = Vec::with_capacity(dirents.len());
+        for (operation, result) in results {
+            match operation {
+                Operation::Open { name, .. } => {
+                    if result >= 0 {
+                        let path = path.join(OsStr::from_bytes(name.as_bytes()));
+                        self.prefetched.push((path, Descriptor(result)));
+                    }
+                },
+                Operation::Statx { name, statx, .. } => {
+                    // SAFETY: `statx` is initialized if the operation succeeds.
+                    let statx = (result == 0)
+                        .then(|| LinuxMetadata::from_statx(unsafe { statx.assume_init_ref() }));
+                    metadata.push((name, statx));
+                },
+            }
+        }
+
+        let mut items: Vec<_> = errors.into_iter().map(Err).collect();
+        let mut metadata = metadata.into_iter().peekable();
+        for (name, file_type) in dirents {
+            let entry = path.join(&name);
+            // Operations are returned in order, so the metadata of an entry,
+            // if any, is next.
+            let metadata = metadata
+                .next_if(|(other, _)| other.as_bytes() == name.as_bytes())
+                .and_then(|(_, metadata)| metadata);
+            items.push(match (file_type, metadata) {
+                (_, Some(metadata)) => Ok((entry, metadata.file_type(), Some(metadata))),
+                (Some(file_type), None) => Ok((entry, file_type, None)),
+                (None, None) => stat(parent, Path::new(&name), libc::AT_SYMLINK_NOFOLLOW)
+                    .map(|metadata| (entry, metadata.file_type(), Some(metadata))),
+            });
+        }
+        drop(descriptor);
+        DentsEntries {
+            path,
+            read: Read::Buffered {
+                handle,
+                entries: items.into_iter(),
+            },
+        }
+    }
+}
+
+// Buffer of directory entries read via `getdents64`.
+#[derive(Debug)]
+struct Dents {
+    buffer: Vec<u8>,
+    // The range of unread entries in the buffer.
+    start: usize,
+    end: usize,
+    // The position in the directory following the buffered entries. This is
+    // used to resume reading if the descriptor of the directory is closed.
+    position: i64,
+    is_terminated: bool,
+}
+
+impl Dents {
+    fn new() -> Self {
+        Dents {
+            buffer: vec![0; DIRENT_BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            position: 0,
+            is_terminated: false,
+        }
+    }
+
+    fn is_drained(&self) -> bool {
+        self.start >= self.end
+    }
+
+    // Reads the next entry from the directory. Entries are the name of a file
+    // and its type, which is `None` if it is not reported by the file system.
+    fn next(&mut self, descriptor: RawFd) -> Option<io::Result<(OsString, Option<LinuxFileType>)>> {
+        // The layout of `linux_dirent64` is an inode number (8 bytes), offset
+        // (8 bytes), record length (2 bytes), type (1 byte), and nul-terminated
+        // name.
+        fn len(record: &[u8]) -> usize {
+            usize::from(u16::from_ne_bytes([record[16], record[17]]))
+        }
+
+        if self.is_drained() {
+            if self.is_terminated {
+                return None;
+            }
+            // SAFETY: The descriptor is open and `buffer` is writable storage
+            //         of the given length. The kernel writes only complete
+            //         records and returns the number of bytes written, so the
+            //         records that are read below are within `..n`.
+            let n = unsafe {
+                libc::syscall(
+                    libc::SYS_getdents64,
+                    descriptor,
+                    self.buffer.as_mut_ptr(),
+                    self.buffer.len(),
+                )
+            };
+            if n <= 0 {
+                self.is_terminated = true;
+                return (n < 0).then(|| Err(io::Error::last_os_error()));
+            }
+            self.start = 0;
+            self.end = n as usize;
+            let mut last = 0;
+            while last + len(&self.buffer[last..]) < self.end {
+                last += len(&self.buffer[last..]);
+            }
+            let mut position = [0; 8];
+            position.copy_from_slice(&self.buffer[last + 8..last + 16]);
+            self.position = i64::from_ne_bytes(position);
+        }
+        let record = &self.buffer[self.start..self.end];
+        let len = len(record);
+        let kind = record[18];
+        let name = &record[19..len];
+        let name = &name[..name
+            .iter()
+            .position(|&byte| byte == 0)
+            .unwrap_or(name.len())];
+        self.start += len;
+        Some(Ok((
+            OsStr::from_bytes(name).to_os_string(),
+            LinuxFileType::from_dirent_type(kind),
+        )))
+    }
+}
+
+#[derive(Debug)]
+enum Read {
+    Dents {
+        filesystem: LinuxFilesystem,
+        handle: Arc<Handle>,
+        dents: Dents,
+    },
+    Std(fs::ReadDir),
+    // The handle of a directory that has been read via `io_uring` is retained,
+    // because its entries are queried relative to it.
+    #[cfg(feature = "io-uring")]
+    Buffered {
+        #[allow(dead_code)]
+        handle: Arc<Handle>,
+        entries: vec::IntoIter<io::Result<(PathBuf, LinuxFileType, Option<LinuxMetadata>)>>,
+    },
+}
+
+/// Iterator over the entries in a directory of a [`LinuxFilesystem`].
+///
+/// [`LinuxFilesystem`]: crate::LinuxFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", target_os = "linux"))))]
+#[derive(Debug)]
+pub struct DentsEntries {
+    path: PathBuf,
+    read: Read,
+}
+
+impl DentsEntries {
+    fn dents(filesystem: &LinuxFilesystem, handle: Arc<Handle>) -> Self {
+        DentsEntries {
+            path: handle.path.clone(),
+            read: Read::Dents {
+                filesystem: filesystem.clone(),
+                handle,
+                dents: Dents::new(),
+            },
+        }
+    }
+
+    fn fallback(path: &Path) -> io::Result<Self> {
+        Ok(DentsEntries {
+            path: path.to_path_buf(),
+            read: Read::Std(fs::read_dir(path)?),
+        })
+    }
+}
+
+impl Iterator for DentsEntries {
+    type Item = io::Result<(PathBuf, LinuxFileType, Option<LinuxMetadata>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let (filesystem, handle, dents) = match self.read {
+                Read::Dents {
+                    ref filesystem,
+                    ref handle,
+                    ref mut dents,
+                } => (filesystem, handle, dents),
+                Read::Std(ref mut entries) => {
+                    return entries.next().map(|entry| {
+                        let entry = entry?;
+                        Ok((
+                            entry.path(),
+                            LinuxFileType::from_std(entry.file_type()?),
+                            None,
+                        ))
+                    });
+                },
+                #[cfg(feature = "io-uring")]
+                Read::Buffered {
+                    ref mut entries, ..
+                } => return entries.next(),
+            };
+            let mut descriptor = handle.descriptor();
+            if dents.is_drained() && !dents.is_terminated && descriptor.is_none() {
+                // The descriptor has been closed to remain within the budget,
+                // so the directory is reopened via its path and reading resumes
+                // at the position following the buffered entries. If the
+                // directory has been replaced, then that position is
+                // meaningless, so reading fails instead. The directory is
+                // reopened before its handle is locked, because the handles of
+                // its ancestors are locked when confined.
+                drop(descriptor);
+                let mut state = filesystem.state();
+                state.spill(filesystem.limit(), filesystem.is_confined);
+                let reopened = state
+                    .open(&self.path, filesystem.is_confined)
+                    .and_then(|descriptor| handle.verify(descriptor))
+                    .and_then(|descriptor| seek(descriptor, dents.position));
+                descriptor = handle.descriptor();
+                match reopened {
+                    Ok(reopened) => {
+                        *descriptor = Some(reopened);
+                    },
+                    Err(error) => {
+                        dents.is_terminated = true;
+                        return Some(Err(error));
+                    },
+                }
+            }
+            let fd = descriptor
+                .as_ref()
+                .map_or(libc::AT_FDCWD, |descriptor| descriptor.0);
+            let (name, file_type) = match dents.next(fd)? {
+                Ok(dirent) => dirent,
+                // `getdents64` may be unavailable, such as in sandboxes that
+                // filter system calls. This is detected by the first read, so
+                // no entries have been yielded yet.
+                Err(error)
+                    if error.raw_os_error() == Some(libc::ENOSYS) && !filesystem.is_confined =>
+                {
+                    drop(descriptor);
+                    match fs::read_dir(&self.path) {
+                        Ok(entries) => {
+                            self.read = Read::Std(entries);
+                            continue;
+                        },
+                        Err(error) => return Some(Err(error)),
+                    }
+                },
+                Err(error) => return Some(Err(error)),
+            };
+            if name == "." || name == ".." {
+                continue;
+            }
+            let path = self.path.join(&name);
+            return Some(match file_type {
+                Some(file_type) => Ok((path, file_type, None)),
+                // Some file systems do not report the types of files in
+                // directory entries, in which case the type is queried
+                // relative to the directory.
+                None => match descriptor.as_ref() {
+                    Some(descriptor) => {
+                        stat(descriptor.0, Path::new(&name), libc::AT_SYMLINK_NOFOLLOW)
+                    },
+                    None => stat(libc::AT_FDCWD, &path, libc::AT_SYMLINK_NOFOLLOW),
+                }
+                .map(|metadata| (path, metadata.file_type(), Some(metadata))),
+            });
+        }
+    }
+}
+
+// Seeks to the given position in a directory.
+fn seek(descriptor: Descriptor, position: i64) -> io::Result<Descriptor> {
+    if position != 0 {
+        // SAFETY: The descriptor is open.
+        if unsafe { libc::lseek(descriptor.0, position, libc::SEEK_SET) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(descriptor)
+}
+
+fn stat(parent: RawFd, path: &Path, flags: c_int) -> io::Result<LinuxMetadata> {
+    let path = c_path(path)?;
+    let mut stat = MaybeUninit::<libc::stat>::uninit();
+    // SAFETY: `path` is a nul-terminated string, `parent` is either an open
+    //         descriptor or `AT_FDCWD`, and `stat` is initialized if the call
+    //         succeeds.
+    let code = unsafe { libc::fstatat(parent, path.as_ptr(), stat.as_mut_ptr(), flags) };
+    if code == 0 {
+        // SAFETY: `fstatat` succeeded and initialized `stat`.
+        Ok(LinuxMetadata::from_stat(unsafe { &stat.assume_init() }))
+    }
+    else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+fn c_path(path: &Path) -> io::Result<CString> {
+    CString::new(path.as_os_str().as_bytes())
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidInput, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::walk::{FileType as _, Filesystem as _, Metadata as _};
+    #[cfg(feature = "io-uring")]
+    use crate::WalkBackend;
+    use crate::{Confinement, DescriptorBudget, Glob, LinuxFilesystem, WalkBehavior};
+
+    #[test]
+    fn walk_linux_filesystem() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src/walk")).unwrap();
+        fs::create_dir(root.path().join("doc")).unwrap();
+        for path in ["src/lib.rs", "src/walk/mod.rs", "doc/guide.md"] {
+            fs::write(root.path().join(path), b"wax").unwrap();
+        }
+        std::os::unix::fs::symlink("lib.rs", root.path().join("src/link.rs")).unwrap();
+
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let entries: Vec<_> = glob
+            .walk_with_filesystem(LinuxFilesystem::new(), root.path(), WalkBehavior::default())
+            .map(Result::unwrap)
+            .collect();
+        for entry in entries.iter() {
+            let metadata = entry.metadata().unwrap();
+            assert!(!metadata.file_type().is_dir());
+            assert_eq!(
+                metadata.file_type().is_symlink(),
+                entry.path().ends_with("link.rs"),
+            );
+            if !metadata.file_type().is_symlink() {
+                assert_eq!(metadata.size(), Some(3));
+            }
+            assert!(metadata.modified().is_some());
+        }
+        let paths: HashSet<_> = entries
+            .into_iter()
+            .map(|entry| {
+                entry
+                    .into_path()
+                    .strip_prefix(root.path())
+                    .unwrap()
+                    .to_path_buf()
+            })
+            .collect();
+        assert_eq!(
+            paths,
+            ["src/lib.rs", "src/link.rs", "src/walk/mod.rs"]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        );
+    }
+
+    #[test]
+    fn walk_linux_filesystem_with_descriptor_budget() {
+        let root = tempfile::tempdir().unwrap();
+        // Create directories with more entries than are read at once, so that
+        // reading resumes after descriptors are closed.
+        let mut directory = root.path().to_path_buf();
+        for name in ["a", "b", "c", "d"] {
+            directory.push(name);
+            fs::create_dir(&directory).unwrap();
+            for n in 0..500 {
+                fs::write(directory.join(format!("file-with-a-long-name-{}", n)), b"").unwrap();
+            }
+        }
+
+        let glob = Glob::new("**").unwrap();
+        let walk = |behavior: WalkBehavior| {
+            let mut paths: Vec<_> = glob
+                .walk_with_filesystem(LinuxFilesystem::new(), root.path(), behavior)
+                .map(|entry| entry.unwrap().into_path())
+                .collect();
+            paths.sort();
+            paths
+        };
+        let unbounded = walk(WalkBehavior::default());
+        assert_eq!(unbounded.len(), 2005);
+        assert_eq!(walk(DescriptorBudget::new(1).into()), unbounded);
+        #[cfg(feature = "io-uring")]
+        assert_eq!(
+            walk(WalkBehavior {
+                backend: WalkBackend::IoUring,
+                descriptors: Some(DescriptorBudget::new(2)),
+                ..Default::default()
+            }),
+            unbounded,
+        );
+    }
+
+    #[test]
+    fn read_replaced_directory_with_descriptor_budget() {
+        let root = tempfile::tempdir().unwrap();
+        for name in ["a", "b"] {
+            let directory = root.path().join(name);
+            fs::create_dir(&directory).unwrap();
+            for n in 0..500 {
+                fs::write(directory.join(format!("file-with-a-long-name-{}", n)), b"").unwrap();
+            }
+        }
+
+        let mut filesystem = LinuxFilesystem::new();
+        filesystem.configure(&DescriptorBudget::new(1).into());
+        let mut entries = filesystem.read_dir(&root.path().join("a")).unwrap();
+        assert!(entries.next().unwrap().is_ok());
+        // Reading another directory closes the descriptor of `a`, which is then
+        // replaced before it is reopened.
+        let _b = filesystem.read_dir(&root.path().join("b")).unwrap();
+        fs::rename(root.path().join("a"), root.path().join("c")).unwrap();
+        fs::create_dir(root.path().join("a")).unwrap();
+        assert!(entries.any(|entry| entry.is_err()));
+    }
+
+    #[test]
+    fn walk_linux_filesystem_with_confinement() {
+        use std::os::unix::fs::symlink;
+
+        let root = tempfile::tempdir().unwrap();
+        let path = root.path().join("tree");
+        fs::create_dir_all(path.join("a/b/c")).unwrap();
+        fs::create_dir(path.join("d")).unwrap();
+        fs::create_dir(root.path().join("outside")).unwrap();
+        fs::write(root.path().join("outside/secret"), b"").unwrap();
+        fs::write(path.join("a/b/c/file"), b"").unwrap();
+        symlink("../../../outside/secret", path.join("a/b/link")).unwrap();
+
+        // Directories are opened beneath the root, which remains open when
+        // other descriptors are closed to remain within the budget.
+        let glob = Glob::new("**").unwrap();
+        let (paths, errors): (Vec<_>, Vec<_>) = glob
+            .walk_with_filesystem(
+                LinuxFilesystem::new(),
+                &path,
+                WalkBehavior {
+                    confinement: Confinement::Confined,
+                    descriptors: Some(DescriptorBudget::new(1)),
+                    ..Default::default()
+                },
+            )
+            .partition(Result::is_ok);
+        let paths: HashSet<_> = paths
+            .into_iter()
+            .map(|entry| entry.unwrap().into_path())
+            .collect();
+        assert_eq!(
+            paths,
+            ["a", "a/b", "a/b/c", "a/b/c/file", "d"]
+                .into_iter()
+                .map(|name| path.join(name))
+                .chain(Some(path.clone()))
+                .collect(),
+        );
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].as_ref().unwrap_err().path(),
+            Some(path.join("a/b/link").as_path()),
+        );
+
+        // Directories that are replaced by symbolic links are not read.
+        let mut errors = vec![];
+        for entry in glob.walk_with_filesystem(
+            LinuxFilesystem::new(),
+            &path,
+            WalkBehavior::from(Confinement::Confined),
+        ) {
+            match entry {
+                Ok(entry) => {
+                    assert!(!entry.path().ends_with("secret"));
+                    if entry.path() == path.join("d") {
+                        fs::remove_dir(path.join("d")).unwrap();
+                        symlink("../outside", path.join("d")).unwrap();
+                    }
+                },
+                Err(error) => errors.push(error),
+            }
+        }
+        assert_eq!(errors.len(), 2);
+        assert!(errors
+            .iter()
+            .any(|error| error.path() == Some(path.join("d").as_path())));
+    }
+
+    #[cfg(feature = "io-uring")]
+    #[test]
+    fn walk_linux_filesystem_with_io_uring() {
+        let root = tempfile::tempdir().unwrap();
+        // Create more entries than are submitted in one batch.
+        for n in 0..300 {
+            let directory = root.path().join(format!("d{}", n));
+            fs::create_dir(&directory).unwrap();
+            fs::write(directory.join("a.txt"), vec![0; n]).unwrap();
+        }
+        std::os::unix::fs::symlink("d0", root.path().join("link")).unwrap();
+
+        let glob = Glob::new("**").unwrap();
+        let walk = |backend| {
+            let mut entries: Vec<_> = glob
+                .walk_with_filesystem(
+                    LinuxFilesystem::new(),
+                    root.path(),
+                    WalkBehavior {
+                        backend,
+                        ..Default::default()
+                    },
+                )
+                .map(|entry| {
+                    let entry = entry.unwrap();
+                    let metadata = entry.metadata().unwrap();
+                    (
+                        entry.into_path(),
+                        metadata.file_type().is_dir(),
+                        metadata.file_type().is_symlink(),
+                        metadata.size(),
+                    )
+                })
+                .collect();
+            entries.sort();
+            entries
+        };
+        let sequential = walk(WalkBackend::Sequential);
+        assert_eq!(sequential.len(), 602);
+        assert_eq!(walk(WalkBackend::IoUring), sequential);
+    }
+}
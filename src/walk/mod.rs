@@ -0,0 +1,2000 @@
+#![cfg(feature = "walk")]
+
+mod backend;
+mod budget;
+mod case;
+mod confine;
+mod filesystem;
+mod incremental;
+mod linux;
+mod progress;
+mod prune;
+mod retry;
+mod sort;
+mod traverse;
+mod unix;
+mod uring;
+mod windows;
+
+use itertools::Itertools as _;
+use regex::Regex;
+use std::borrow::Cow;
+use std::cmp::{self, Ordering};
+use std::fmt::{self, Display, Formatter};
+use std::io;
+use std::path::{self, Component, Path, PathBuf};
+use std::vec;
+use thiserror::Error;
+
+use crate::capture::MatchedText;
+use crate::dispatch::PrefixDispatch;
+use crate::encode::{self, CompileError};
+use crate::token::{self, Token, TokenTree};
+use crate::walk::confine::Boundary;
+use crate::walk::sort::Sort;
+use crate::walk::traverse::{Entry, Traversal};
+use crate::{
+    BuildError, CandidatePath, Compose, Glob, PositionExt as _, PATHS_ARE_CASE_INSENSITIVE,
+};
+
+pub use crate::walk::backend::WalkBackend;
+pub use crate::walk::budget::DescriptorBudget;
+pub use crate::walk::case::CaseSensitivity;
+pub use crate::walk::confine::Confinement;
+pub use crate::walk::filesystem::{FileType, Filesystem, Metadata, StdEntries, StdFilesystem};
+pub use crate::walk::incremental::{Change, ChangeCache, IncrementalWalk};
+#[cfg(target_os = "linux")]
+pub use crate::walk::linux::{DentsEntries, LinuxFileType, LinuxFilesystem, LinuxMetadata};
+pub use crate::walk::progress::{ProgressCallback, WalkProgress};
+pub use crate::walk::prune::{PruneCallback, PruneCause, PrunedTree};
+pub use crate::walk::retry::RetryPolicy;
+pub use crate::walk::sort::{DirectoryOrder, SortCallback, SortEntry};
+#[cfg(unix)]
+pub use crate::walk::unix::{FilterPermissions, UnixFileIterator};
+#[cfg(windows)]
+pub use crate::walk::windows::{FindEntries, WindowsFileType, WindowsFilesystem, WindowsMetadata};
+
+pub type WalkItem<'e, S = StdFilesystem> = Result<WalkEntry<'e, S>, WalkError>;
+
+/// Describes errors that occur when matching a [`Glob`] against a directory
+/// tree.
+///
+/// Errors describe the [operation][`WalkOperation`] that failed and where it
+/// failed, so that they can be handled without inspecting their messages. For
+/// example, a traversal may ignore directories that cannot be read due to
+/// permissions but report any other errors.
+///
+/// `WalkError` implements conversion into [`io::Error`].
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::io;
+/// use wax::{Glob, WalkOperation};
+///
+/// let glob = Glob::new("**/*.log").unwrap();
+/// for entry in glob.walk("/var/log") {
+///     match entry {
+///         Ok(entry) => println!("{:?}", entry.path()),
+///         Err(error)
+///             if error.operation() == WalkOperation::ReadDirectory
+///                 && error.io_error_kind() == Some(io::ErrorKind::PermissionDenied) => {},
+///         Err(error) => eprintln!("{}", error),
+///     }
+/// }
+/// ```
+///
+/// [`Glob`]: crate::Glob
+/// [`io::Error`]: std::io::Error
+/// [`WalkOperation`]: crate::WalkOperation
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug, Error)]
+#[error("failed to match directory tree: {kind}")]
+pub struct WalkError {
+    depth: usize,
+    kind: WalkErrorKind,
+}
+
+impl WalkError {
+    /// Gets the path at which the error occurred.
+    ///
+    /// Returns `None` if there is no path associated with the error.
+    pub fn path(&self) -> Option<&Path> {
+        self.kind.path()
+    }
+
+    /// Gets the depth from [the root][`Walk::root`] at which the error
+    /// occurred.
+    ///
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the operation that failed.
+    ///
+    /// Symbolic link cycles and paths that resolve outside of a [confined
+    /// traversal][`Confinement`] are detected while resolving symbolic links,
+    /// so the operation of these errors is [`WalkOperation::ResolveLink`].
+    ///
+    /// [`Confinement`]: crate::Confinement
+    /// [`WalkOperation::ResolveLink`]: crate::WalkOperation::ResolveLink
+    pub fn operation(&self) -> WalkOperation {
+        match self.kind {
+            WalkErrorKind::Io { operation, .. } => operation,
+            WalkErrorKind::LinkCycle { .. } | WalkErrorKind::Escape { .. } => {
+                WalkOperation::ResolveLink
+            },
+        }
+    }
+
+    /// Gets the [`io::ErrorKind`] of the underlying I/O error.
+    ///
+    /// Returns `None` if the error is not an I/O error, such as a symbolic link
+    /// cycle.
+    ///
+    /// [`io::ErrorKind`]: std::io::ErrorKind
+    pub fn io_error_kind(&self) -> Option<io::ErrorKind> {
+        self.io_error().map(io::Error::kind)
+    }
+
+    /// Gets the underlying I/O error.
+    ///
+    /// Returns `None` if the error is not an I/O error, such as a symbolic link
+    /// cycle.
+    pub fn io_error(&self) -> Option<&io::Error> {
+        match self.kind {
+            WalkErrorKind::Io { ref error, .. } => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if the error is a symbolic link cycle.
+    ///
+    /// Cycles are only detected when [following symbolic
+    /// links][`LinkBehavior::ReadTarget`].
+    ///
+    /// [`LinkBehavior::ReadTarget`]: crate::LinkBehavior::ReadTarget
+    pub fn is_link_cycle(&self) -> bool {
+        matches!(self.kind, WalkErrorKind::LinkCycle { .. })
+    }
+
+    /// Returns `true` if the error is a path that resolves outside of a
+    /// [confined traversal][`Confinement`].
+    ///
+    /// [`Confinement`]: crate::Confinement
+    pub fn is_escape(&self) -> bool {
+        matches!(self.kind, WalkErrorKind::Escape { .. })
+    }
+}
+
+impl WalkError {
+    fn io(depth: usize, operation: WalkOperation, path: Option<PathBuf>, error: io::Error) -> Self {
+        WalkError {
+            depth,
+            kind: WalkErrorKind::Io {
+                path,
+                operation,
+                error,
+            },
+        }
+    }
+
+    fn link_cycle(depth: usize, root: PathBuf, leaf: PathBuf) -> Self {
+        WalkError {
+            depth,
+            kind: WalkErrorKind::LinkCycle { root, leaf },
+        }
+    }
+
+    fn escape(depth: usize, root: PathBuf, path: PathBuf) -> Self {
+        WalkError {
+            depth,
+            kind: WalkErrorKind::Escape { root, path },
+        }
+    }
+}
+
+impl From<WalkError> for io::Error {
+    fn from(error: WalkError) -> Self {
+        let kind = error.io_error_kind().unwrap_or(io::ErrorKind::Other);
+        io::Error::new(kind, error)
+    }
+}
+
+/// Operation on a directory tree that fails with a [`WalkError`].
+///
+/// See [`WalkError::operation`].
+///
+/// [`WalkError`]: crate::WalkError
+/// [`WalkError::operation`]: crate::WalkError::operation
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum WalkOperation {
+    /// Opening a directory or reading its entries.
+    ReadDirectory,
+    /// Querying the metadata of a file.
+    ReadMetadata,
+    /// Resolving a symbolic link, such as reading the metadata of its target
+    /// when following links or canonicalizing paths to detect cycles and
+    /// verify [confinement][`Confinement`].
+    ///
+    /// [`Confinement`]: crate::Confinement
+    ResolveLink,
+}
+
+impl Display for WalkOperation {
+    fn fmt(&self, formatter: &mut Formatter<'_>) -> fmt::Result {
+        formatter.write_str(match self {
+            WalkOperation::ReadDirectory => "read directory",
+            WalkOperation::ReadMetadata => "read metadata",
+            WalkOperation::ResolveLink => "resolve symbolic link",
+        })
+    }
+}
+
+/// Describes all errors that occur when collecting the paths of files matching
+/// a [`Glob`] in a directory tree.
+///
+/// See [`Walk::collect_paths`].
+///
+/// [`Glob`]: crate::Glob
+/// [`Walk::collect_paths`]: crate::Walk::collect_paths
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug, Error)]
+#[error("failed to match directory tree: {} error(s)", .errors.len())]
+pub struct WalkErrors {
+    errors: Vec<WalkError>,
+}
+
+impl WalkErrors {
+    /// Gets the errors in the order in which they occurred.
+    pub fn errors(&self) -> &[WalkError] {
+        &self.errors
+    }
+
+    /// Converts into the errors in the order in which they occurred.
+    pub fn into_errors(self) -> Vec<WalkError> {
+        self.errors
+    }
+}
+
+impl IntoIterator for WalkErrors {
+    type Item = WalkError;
+    type IntoIter = vec::IntoIter<WalkError>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.errors.into_iter()
+    }
+}
+
+#[derive(Debug, Error)]
+#[non_exhaustive]
+enum WalkErrorKind {
+    #[error("failed to {operation} at `{path:?}`: {error}")]
+    Io {
+        path: Option<PathBuf>,
+        operation: WalkOperation,
+        error: io::Error,
+    },
+    #[error("symbolic link cycle detected from `{root}` to `{leaf}`")]
+    LinkCycle { root: PathBuf, leaf: PathBuf },
+    #[error("`{path}` resolves outside of confined directory `{root}`")]
+    Escape { root: PathBuf, path: PathBuf },
+}
+
+impl WalkErrorKind {
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            WalkErrorKind::Io { ref path, .. } => path.as_ref().map(PathBuf::as_ref),
+            WalkErrorKind::LinkCycle { ref leaf, .. } => Some(leaf.as_ref()),
+            WalkErrorKind::Escape { ref path, .. } => Some(path.as_ref()),
+        }
+    }
+}
+
+/// Traverses a directory tree via a `Walk` instance.
+///
+/// This macro emits an interruptable loop that executes a block of code
+/// whenever a `WalkEntry` or error is encountered while traversing a directory
+/// tree. The block may return from its function or otherwise interrupt and
+/// subsequently resume the loop.
+///
+/// Note that if the block attempts to emit a `WalkEntry` across a function
+/// boundary, then the entry must own its contents. A `move` block receives a
+/// `WalkEntry` into which the entry and its path are moved rather than copied.
+macro_rules! walk {
+    ($state:expr => move |$entry:ident| $f:block) => {
+        walk!(@walk move, $state => |$entry| $f)
+    };
+    ($state:expr => |$entry:ident| $f:block) => {
+        walk!(@walk ref, $state => |$entry| $f)
+    };
+    (@entry ref, $entry:ident, $matched:ident) => {
+        WalkEntry {
+            entry: Cow::Borrowed(&$entry),
+            matched: $matched,
+        }
+    };
+    (@entry move, $entry:ident, $matched:ident) => {{
+        // The matched text borrows the path of the entry, so it is copied
+        // before the entry is moved.
+        let matched = $matched.into_owned();
+        WalkEntry {
+            entry: Cow::Owned($entry),
+            matched,
+        }
+    }};
+    (@walk $mode:ident, $state:expr => |$entry:ident| $f:block) => {
+        use itertools::EitherOrBoth::{Both, Left, Right};
+        use itertools::Position::{First, Last, Middle, Only};
+
+        // `while-let` avoids a mutable borrow of `walk`, which would prevent a
+        // subsequent call to `skip_tree_with` within the loop body.
+        #[allow(clippy::while_let_on_iterator)]
+        #[allow(unreachable_code)]
+        'walk: while let Some(entry) = $state.walk.next() {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(error) => {
+                    let $entry = Err(error);
+                    $f
+                    continue; // May be unreachable.
+                }
+            };
+            let path = entry
+                .path()
+                .strip_prefix(&$state.prefix)
+                .expect("path is not in tree");
+            let depth = entry.depth().saturating_sub(1);
+            // Components are skipped after they are filtered, because paths
+            // beneath rooted globs begin with root components that have no
+            // corresponding component pattern.
+            for candidate in path
+                .components()
+                .filter_map(|component| match component {
+                    Component::Normal(component) => Some(CandidatePath::from(component)),
+                    _ => None,
+                })
+                .skip(depth)
+                .zip_longest($state.components.iter().skip(depth))
+                .with_position()
+            {
+                match candidate.as_tuple() {
+                    (First(_) | Middle(_), Both(component, pattern)) => {
+                        if !pattern.is_match(component.as_ref()) {
+                            // Do not descend into directories that do not match
+                            // the corresponding component pattern.
+                            if entry.file_type().is_dir() {
+                                $state.walk.skip_tree_with(PruneCause::Mismatch);
+                            }
+                            continue 'walk;
+                        }
+                    }
+                    (Last(_) | Only(_), Both(component, pattern)) => {
+                        if pattern.is_match(component.as_ref()) {
+                            if $state.is_complete && entry.file_type().is_dir() {
+                                // Do not descend into directories that match
+                                // the final component pattern when there are
+                                // no component boundaries: paths beneath such
+                                // directories have too many components.
+                                $state.walk.skip_tree_with(PruneCause::Mismatch);
+                            }
+                            let path = CandidatePath::from(path);
+                            if let Some(matched) =
+                                $state.pattern.captures(path.as_ref()).map(MatchedText::from)
+                            {
+                                let $entry = Ok(walk!(@entry $mode, entry, matched));
+                                $f
+                            }
+                        }
+                        else {
+                            // Do not descend into directories that do not match
+                            // the corresponding component pattern.
+                            if entry.file_type().is_dir() {
+                                $state.walk.skip_tree_with(PruneCause::Mismatch);
+                            }
+                        }
+                        continue 'walk;
+                    }
+                    (_, Left(_component)) => {
+                        if $state.is_complete {
+                            // The path has more components than the glob and
+                            // cannot match.
+                            if entry.file_type().is_dir() {
+                                $state.walk.skip_tree_with(PruneCause::Mismatch);
+                            }
+                            continue 'walk;
+                        }
+                        // Match the trailing components of the path against
+                        // the trailing component patterns before matching the
+                        // complete pattern. Paths beneath a directory that does
+                        // not match may yet match, so the tree is not skipped.
+                        if !path
+                            .components()
+                            .rev()
+                            .filter_map(|component| match component {
+                                Component::Normal(component) => {
+                                    Some(CandidatePath::from(component))
+                                },
+                                _ => None,
+                            })
+                            .zip($state.suffix.iter().rev())
+                            .all(|(component, pattern)| pattern.is_match(component.as_ref()))
+                        {
+                            continue 'walk;
+                        }
+                        let path = CandidatePath::from(path);
+                        if let Some(matched) =
+                            $state.pattern.captures(path.as_ref()).map(MatchedText::from)
+                        {
+                            let $entry = Ok(walk!(@entry $mode, entry, matched));
+                            $f
+                        }
+                        continue 'walk;
+                    }
+                    (_, Right(_pattern)) => {
+                        continue 'walk;
+                    }
+                }
+            }
+            // If the loop is not entered, check for a match. This may indicate
+            // that the `Glob` is empty and a single invariant path may be
+            // matched.
+            let path = CandidatePath::from(path);
+            if let Some(matched) = $state.pattern.captures(path.as_ref()).map(MatchedText::from) {
+                let $entry = Ok(walk!(@entry $mode, entry, matched));
+                $f
+            }
+        }
+    };
+}
+
+/// An [`Iterator`] over [`WalkEntry`]s that can filter directory trees.
+///
+/// A `FileIterator` is a `TreeIterator` that yields [`WalkEntry`]s. This trait
+/// is implemented by [`Walk`] and adaptors like [`FilterTree`]. A
+/// `TreeIterator` is an iterator that reads its items from a tree and therefore
+/// can meaningfully filter not only items but their corresponding sub-trees to
+/// avoid unnecessary work. To that end, this trait provides the `filter_tree`
+/// function, which allows directory trees to be discarded (not read from the
+/// file system) when matching [`Glob`]s against directory trees.
+///
+/// [`filter_tree`]: crate::FileIterator::filter_tree
+/// [`Glob`]: crate::Glob
+/// [`Iterator`]: std::iter::Iterator
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+pub trait FileIterator<S = StdFilesystem>:
+    Sized + TreeIterator<Item = WalkItem<'static, S>>
+where
+    S: Filesystem,
+{
+    /// Filters [`WalkEntry`]s and controls the traversal of directory trees.
+    ///
+    /// This function creates an adaptor that filters [`WalkEntry`]s and
+    /// furthermore specifies how iteration proceeds to traverse directory
+    /// trees. The adaptor accepts a function that, when discarding a
+    /// [`WalkEntry`], yields a [`FilterTarget`]. **If the entry refers to a
+    /// directory and [`FilterTarget::Tree`] is returned by the function, then
+    /// iteration will not descend into that directory and the tree will not be
+    /// read from the file system.** Therefore, this adaptor should be preferred
+    /// over functions like [`Iterator::filter`] when discarded directories do
+    /// not need to be read.
+    ///
+    /// Errors are not filtered, so if an error occurs reading a file at a path
+    /// that would have been discarded, then that error is still yielded by the
+    /// iterator.
+    ///
+    /// # Examples
+    ///
+    /// The [`FilterTree`] adaptor can be used to apply additional custom
+    /// filtering that avoids unnecessary directory reads. The following example
+    /// filters out hidden files on Unix and Windows. On Unix, hidden files are
+    /// filtered out nominally via [`not`]. On Windows, `filter_tree` is used to
+    /// detect the [hidden attribute][attributes]. In both cases, the adaptor
+    /// does not read conventionally hidden directory trees.
+    ///
+    /// ```rust,no_run
+    /// use wax::Glob;
+    /// #[cfg(windows)]
+    /// use wax::{FileIterator, FilterTarget};
+    ///
+    /// let glob = Glob::new("**/*.(?i){jpg,jpeg}").unwrap();
+    /// let walk = glob.walk("./Pictures");
+    /// // Filter out nominally hidden files on Unix. Like `filter_tree`, `not`
+    /// // does not perform unnecessary reads of directory trees.
+    /// #[cfg(unix)]
+    /// let walk = walk.not(["**/.*/**"]).unwrap();
+    /// // Filter out files with the hidden attribute on Windows.
+    /// #[cfg(windows)]
+    /// let walk = walk.filter_tree(|entry| {
+    ///     use std::os::windows::fs::MetadataExt as _;
+    ///
+    ///     const ATTRIBUTE_HIDDEN: u32 = 0x2;
+    ///
+    ///     let attributes = entry.metadata().unwrap().file_attributes();
+    ///     if (attributes & ATTRIBUTE_HIDDEN) == ATTRIBUTE_HIDDEN {
+    ///         // Do not read hidden directory trees.
+    ///         Some(FilterTarget::Tree)
+    ///     }
+    ///     else {
+    ///         None
+    ///     }
+    /// });
+    /// for entry in walk {
+    ///     let entry = entry.unwrap();
+    ///     println!("JPEG: {:?}", entry.path());
+    /// }
+    /// ```
+    ///
+    /// [`FilterTree`]: crate::FilterTree
+    /// [`Iterator`]: std::iter::Iterator
+    /// [`Iterator::filter`]: std::iter::Iterator::filter
+    /// [`not`]: crate::Walk::not
+    /// [`Walk`]: crate::Walk
+    /// [`WalkEntry`]: crate::WalkEntry
+    ///
+    /// [attributes]: https://docs.microsoft.com/en-us/windows/win32/fileio/file-attribute-constants
+    fn filter_tree<F>(self, f: F) -> FilterTree<Self, F>
+    where
+        F: FnMut(&WalkEntry<'static, S>) -> Option<FilterTarget>;
+
+    /// Stops traversing directory trees after the given number of
+    /// [`WalkEntry`]s have been yielded.
+    ///
+    /// This function creates an adaptor that yields at most `n`
+    /// [`WalkEntry`]s. Unlike [`Iterator::take`], the adaptor discards its
+    /// input as soon as the `n`th [`WalkEntry`] is yielded, which closes any
+    /// directories that are open for reading. Errors are yielded but are not
+    /// counted.
+    ///
+    /// This is useful for existence queries, where the traversal can end as
+    /// soon as a match is found.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::{FileIterator, Glob};
+    ///
+    /// let glob = Glob::new("**/Cargo.toml").unwrap();
+    /// let is_rust = glob.walk(".").take_matches(1).flatten().next().is_some();
+    /// ```
+    ///
+    /// [`Iterator::take`]: std::iter::Iterator::take
+    /// [`WalkEntry`]: crate::WalkEntry
+    fn take_matches(self, n: usize) -> TakeMatches<Self> {
+        TakeMatches {
+            input: if n == 0 { None } else { Some(self) },
+            remaining: n,
+        }
+    }
+}
+
+impl<I, S> FileIterator<S> for I
+where
+    I: TreeIterator<Item = WalkItem<'static, S>> + Sized,
+    S: Filesystem,
+{
+    fn filter_tree<F>(self, f: F) -> FilterTree<Self, F>
+    where
+        F: FnMut(&WalkEntry<'static, S>) -> Option<FilterTarget>,
+    {
+        FilterTree {
+            input: self,
+            f,
+            cause: PruneCause::Filter,
+        }
+    }
+}
+
+pub trait TreeIterator: Iterator {
+    fn skip_tree(&mut self) {
+        self.skip_tree_with(PruneCause::Filter);
+    }
+
+    // Skips the tree of the most recently yielded directory and reports the
+    // cause to any prune callback.
+    fn skip_tree_with(&mut self, cause: PruneCause);
+}
+
+/// Negated combinator that efficiently filters [`WalkEntry`]s.
+///
+/// Determines an appropriate [`FilterTarget`] for a [`WalkEntry`] based on the
+/// [exhaustiveness][`Pattern::is_exhaustive`] of its component [`Pattern`]s.
+/// This can be used with [`FilterTree`] to efficiently filter [`WalkEntry`]s
+/// without reading directory trees from the file system when not necessary.
+///
+/// [`FilterTarget`]: crate::FilterTarget
+/// [`FilterTree`]: crate::FilterTree
+/// [`Pattern`]: crate::Pattern
+/// [`Pattern::is_exhaustive`]: crate::Pattern::is_exhaustive
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Debug)]
+pub struct Negation {
+    exhaustive: Option<PrefixDispatch>,
+    nonexhaustive: Option<PrefixDispatch>,
+}
+
+impl Negation {
+    /// Composes glob expressions into a `Negation`.
+    ///
+    /// This function accepts an [`IntoIterator`] with items that implement the
+    /// [`Compose`] trait such as [`Glob`] and `&str`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the inputs fail to build. If the inputs are a
+    /// compiled [`Pattern`] type such as [`Glob`], then this only occurs if the
+    /// compiled program is too large.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Pattern`]: crate::Pattern
+    /// [`IntoIterator`]: std::iter::IntoIterator
+    pub fn any<'t, I>(patterns: I) -> Result<Self, BuildError>
+    where
+        I: IntoIterator,
+        I::Item: Compose<'t>,
+    {
+        let (exhaustive, nonexhaustive) = patterns
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(Into::into)?
+            .into_iter()
+            .partition::<Vec<_>, _>(|tree| {
+                // Patterns with exclusions are never exhaustive.
+                !tree.as_ref().has_exclusions() && token::is_exhaustive(tree.as_ref().tokens())
+            });
+        // An empty combinator matches empty paths (such as the root of a
+        // walk), so only compose non-empty partitions.
+        let any = |trees: Vec<_>| -> Result<_, BuildError> {
+            Ok(if trees.is_empty() {
+                None
+            }
+            else {
+                Some(crate::any(trees)?.pattern)
+            })
+        };
+        let negation = Negation {
+            exhaustive: any(exhaustive)?,
+            nonexhaustive: any(nonexhaustive)?,
+        };
+        Ok(negation)
+    }
+
+    /// Gets the appropriate [`FilterTarget`] for the given [`WalkEntry`].
+    ///
+    /// This function can be used with [`FileIterator::filter_tree`] to
+    /// effeciently filter [`WalkEntry`]s without reading directory trees from
+    /// the file system when not necessary.
+    ///
+    /// Returns [`FilterTarget::Tree`] if the [`WalkEntry`] matches an
+    /// [exhaustive glob expression][`Pattern::is_exhaustive`], such as
+    /// `secret/**`.
+    ///
+    /// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+    /// [`FilterTarget`]: crate::FilterTarget
+    /// [`FilterTarget::Tree`]: crate::FilterTarget::Tree
+    /// [`Pattern::is_exhaustive`]: crate::Pattern::is_exhaustive
+    /// [`WalkEntry`]: crate::WalkEntry
+    pub fn target<S>(&self, entry: &WalkEntry<S>) -> Option<FilterTarget>
+    where
+        S: Filesystem,
+    {
+        let path = entry.to_candidate_path();
+        let is_match = |pattern: &Option<PrefixDispatch>| {
+            pattern
+                .as_ref()
+                .map_or(false, |pattern| pattern.is_match(path.as_ref()))
+        };
+        if is_match(&self.exhaustive) {
+            // Do not descend into directories that match the exhaustive
+            // negation.
+            Some(FilterTarget::Tree)
+        }
+        else if is_match(&self.nonexhaustive) {
+            Some(FilterTarget::File)
+        }
+        else {
+            None
+        }
+    }
+}
+
+/// Configuration for interpreting symbolic links.
+///
+/// Determines how symbolic links are interpreted when traversing directory
+/// trees using functions like [`Glob::walk`]. **By default, symbolic links are
+/// read as regular files and their targets are ignored.**
+///
+/// [`Glob::walk`]: crate::Glob::walk
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug)]
+pub enum LinkBehavior {
+    /// Read the symbolic link file itself.
+    ///
+    /// This behavior reads the symbolic link as a regular file. The
+    /// corresponding [`WalkEntry`] uses the path of the link file and its
+    /// metadata describes the link file itself. The target is effectively
+    /// ignored and traversal will **not** follow the link.
+    ///
+    /// [`WalkEntry`]: crate::WalkEntry
+    ReadFile,
+    /// Read the target of the symbolic link.
+    ///
+    /// This behavior reads the target of the symbolic link. The corresponding
+    /// [`WalkEntry`] uses the path of the link file and its metadata describes
+    /// the target. If the target is a directory, then traversal will follow the
+    /// link and descend into the target.
+    ///
+    /// If a link is reentrant and forms a cycle, then an error will be emitted
+    /// instead of a [`WalkEntry`] and traversal will not follow the link.
+    ///
+    /// [`WalkEntry`]: crate::WalkEntry
+    ReadTarget,
+}
+
+impl Default for LinkBehavior {
+    fn default() -> Self {
+        LinkBehavior::ReadFile
+    }
+}
+
+/// Configuration for matching [`Glob`]s against directory trees.
+///
+/// Determines the behavior of the traversal within a directory tree when using
+/// functions like [`Glob::walk`]. `WalkBehavior` can be constructed via
+/// conversions from types representing its fields. APIs generally accept `impl
+/// Into<WalkBehavior>`, so these conversion can be used implicitly. When
+/// constructed using such a conversion, `WalkBehavior` will use defaults for
+/// any remaining fields.
+///
+/// `WalkBehavior` may hold callbacks, such as [`WalkBehavior::on_progress`],
+/// and so is [`Clone`] but not [`Copy`]. Callbacks are shared via reference
+/// counting, so cloning a `WalkBehavior` is inexpensive.
+///
+/// # Examples
+///
+/// By default, symbolic links are interpreted as regular files and targets are
+/// ignored. To read linked targets, use [`LinkBehavior::ReadTarget`].
+///
+/// ```rust
+/// use wax::{Glob, LinkBehavior};
+///
+/// for entry in Glob::new("**")
+///     .unwrap()
+///     .walk_with_behavior(".", LinkBehavior::ReadTarget)
+/// {
+///     let entry = entry.unwrap();
+///     // ...
+/// }
+/// ```
+///
+/// [`Clone`]: std::clone::Clone
+/// [`Copy`]: std::marker::Copy
+/// [`Glob`]: crate::Glob
+/// [`Glob::walk`]: crate::Glob::walk
+/// [`WalkBehavior::on_progress`]: crate::WalkBehavior::on_progress
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Debug)]
+pub struct WalkBehavior {
+    // TODO: Consider using a dedicated type for this field. Using primitive
+    //       types does not interact well with conversions used in `walk` APIs.
+    //       For example, if another `usize` field is introduced, then the
+    //       conversions become ambiguous and confusing.
+    /// Maximum depth.
+    ///
+    /// Determines the maximum depth to which a directory tree will be traversed
+    /// relative to [the root][`Walk::root`]. A depth of zero corresponds to the
+    /// root and so using such a depth will yield at most one entry for the
+    /// root.
+    ///
+    /// The default value is [`usize::MAX`].
+    ///
+    /// [`usize::MAX`]: usize::MAX
+    /// [`Walk::root`]: crate::Walk::root
+    pub depth: usize,
+    /// Interpretation of symbolic links.
+    ///
+    /// Determines how symbolic links are interpreted when traversing a
+    /// directory tree. See [`LinkBehavior`].
+    ///
+    /// The default value is [`LinkBehavior::ReadFile`].
+    ///
+    /// [`LinkBehavior`]: crate::LinkBehavior
+    /// [`LinkBehavior::ReadFile`]: crate::LinkBehavior::ReadFile
+    pub link: LinkBehavior,
+    /// Progress callback.
+    ///
+    /// Determines the callback that is invoked with the
+    /// [progress][`WalkProgress`] of the traversal. The callback is invoked
+    /// when a directory is entered and periodically as entries are read from a
+    /// directory tree. See [`WalkBehavior::on_progress`].
+    ///
+    /// The default value is `None`.
+    ///
+    /// [`WalkBehavior::on_progress`]: crate::WalkBehavior::on_progress
+    /// [`WalkProgress`]: crate::WalkProgress
+    pub progress: Option<ProgressCallback>,
+    /// Pruning callback.
+    ///
+    /// Determines the callback that is invoked when the traversal does not
+    /// read a directory tree, such as when the directory cannot contain
+    /// matching files. See [`WalkBehavior::on_prune`].
+    ///
+    /// The default value is `None`.
+    ///
+    /// [`WalkBehavior::on_prune`]: crate::WalkBehavior::on_prune
+    pub prune: Option<PruneCallback>,
+    /// Order of entries in a directory.
+    ///
+    /// Determines the callback that compares the entries in each directory.
+    /// If there is no callback, then entries are yielded in the order in which
+    /// they are read from a directory, which is unspecified and may differ
+    /// between platforms and file systems. See [`WalkBehavior::sort_by`].
+    ///
+    /// The default value is `None`.
+    ///
+    /// [`WalkBehavior::sort_by`]: crate::WalkBehavior::sort_by
+    pub sort: Option<SortCallback>,
+    /// Order of directories relative to other files in a directory.
+    ///
+    /// Determines whether directories are ordered before or after other files
+    /// in each directory. This takes precedence over [`sort`], which orders
+    /// directories and other files among themselves.
+    ///
+    /// The default value is [`DirectoryOrder::Mixed`].
+    ///
+    /// [`sort`]: crate::WalkBehavior::sort
+    /// [`DirectoryOrder::Mixed`]: crate::DirectoryOrder::Mixed
+    pub directories: DirectoryOrder,
+    /// Retry strategy for transient errors.
+    ///
+    /// Determines if and how file system queries that fail are retried before
+    /// an error is yielded. See [`RetryPolicy`].
+    ///
+    /// The default value is `None`.
+    ///
+    /// [`RetryPolicy`]: crate::RetryPolicy
+    pub retry: Option<RetryPolicy>,
+    /// Case sensitivity of the file system.
+    ///
+    /// Determines the default case sensitivity of literals in a glob when
+    /// matching it against a directory tree. Flags in a glob expression take
+    /// precedence. See [`CaseSensitivity`].
+    ///
+    /// The default value is [`CaseSensitivity::Platform`].
+    ///
+    /// [`CaseSensitivity`]: crate::CaseSensitivity
+    /// [`CaseSensitivity::Platform`]: crate::CaseSensitivity::Platform
+    pub case: CaseSensitivity,
+    /// Strategy for issuing file system queries.
+    ///
+    /// Determines how queries are issued when reading a directory tree, such
+    /// as whether they are pipelined. Backends are only honored by
+    /// [`Filesystem`]s that support them. See [`WalkBackend`].
+    ///
+    /// The default value is [`WalkBackend::Sequential`].
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    /// [`WalkBackend`]: crate::WalkBackend
+    /// [`WalkBackend::Sequential`]: crate::WalkBackend::Sequential
+    pub backend: WalkBackend,
+    /// Limit on open directory descriptors.
+    ///
+    /// Determines the maximum number of directory descriptors that are kept
+    /// open when reading a directory tree. Budgets are only honored by
+    /// [`Filesystem`]s that keep descriptors open. See [`DescriptorBudget`].
+    ///
+    /// The default value is `None`.
+    ///
+    /// [`DescriptorBudget`]: crate::DescriptorBudget
+    /// [`Filesystem`]: crate::Filesystem
+    pub descriptors: Option<DescriptorBudget>,
+    /// Confinement of the traversal to the directory in which it begins.
+    ///
+    /// Determines whether paths may resolve outside of the directory given to
+    /// functions like [`Glob::walk_with_behavior`], such as via symbolic
+    /// links. See [`Confinement`].
+    ///
+    /// The default value is [`Confinement::Unconfined`].
+    ///
+    /// [`Confinement`]: crate::Confinement
+    /// [`Confinement::Unconfined`]: crate::Confinement::Unconfined
+    /// [`Glob::walk_with_behavior`]: crate::Glob::walk_with_behavior
+    pub confinement: Confinement,
+}
+
+impl WalkBehavior {
+    /// Sets the callback that is invoked with the [progress][`WalkProgress`] of
+    /// the traversal.
+    ///
+    /// Progress callbacks can be used to drive progress indicators or
+    /// watchdogs for long-running traversals without wrapping iterators.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::{Glob, WalkBehavior};
+    ///
+    /// let glob = Glob::new("**/*.{log,txt}").unwrap();
+    /// let behavior = WalkBehavior::default().on_progress(|progress| {
+    ///     eprintln!(
+    ///         "read {} entries: {}",
+    ///         progress.entries(),
+    ///         progress.directory().display(),
+    ///     );
+    /// });
+    /// for entry in glob.walk_with_behavior(".", behavior) {
+    ///     let entry = entry.unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// [`WalkProgress`]: crate::WalkProgress
+    pub fn on_progress<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Fn(&WalkProgress<'_>) + Send + Sync,
+    {
+        self.progress = Some(f.into());
+        self
+    }
+
+    /// Sets the callback that is invoked when the traversal does not read a
+    /// directory tree.
+    ///
+    /// The callback receives the directory and the [cause][`PruneCause`] for
+    /// each directory tree that is not read, which can help explain why a walk
+    /// does not match a file. Directories are pruned when they cannot contain
+    /// matching files, are at the maximum depth, or are discarded by adaptors
+    /// like [`Walk::not`] and [`FileIterator::filter_tree`].
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::{Glob, WalkBehavior};
+    ///
+    /// let glob = Glob::new("src/**/*.rs").unwrap();
+    /// let behavior = WalkBehavior::default().on_prune(|pruned| {
+    ///     eprintln!("pruned {:?}: {:?}", pruned.path(), pruned.cause());
+    /// });
+    /// for entry in glob.walk_with_behavior(".", behavior).not(["**/target/**"]).unwrap() {
+    ///     let entry = entry.unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+    /// [`PruneCause`]: crate::PruneCause
+    /// [`Walk::not`]: crate::Walk::not
+    pub fn on_prune<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Fn(&PrunedTree<'_>) + Send + Sync,
+    {
+        self.prune = Some(f.into());
+        self
+    }
+
+    /// Sets the callback that compares the entries in each directory.
+    ///
+    /// Entries in a directory are read and sorted before any of them are
+    /// yielded and the contents of a directory are yielded immediately after
+    /// the directory. Sorting is stable, so entries that compare equal are
+    /// yielded in the order in which they are read.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::{DirectoryOrder, Glob, WalkBehavior};
+    ///
+    /// let glob = Glob::new("**/*.md").unwrap();
+    /// let behavior = WalkBehavior {
+    ///     directories: DirectoryOrder::Last,
+    ///     ..Default::default()
+    /// }
+    /// .sort_by(|left, right| left.file_name().cmp(&right.file_name()));
+    /// for entry in glob.walk_with_behavior(".", behavior) {
+    ///     let entry = entry.unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    pub fn sort_by<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Fn(&SortEntry<'_>, &SortEntry<'_>) -> Ordering + Send + Sync,
+    {
+        self.sort = Some(f.into());
+        self
+    }
+
+    /// Sets a key function that orders the entries in each directory.
+    ///
+    /// This is like [`WalkBehavior::sort_by`], but compares the keys of
+    /// entries. Keys are computed each time entries are compared.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::cmp::Reverse;
+    /// use wax::{Glob, WalkBehavior};
+    ///
+    /// // Yield the most recently modified files first.
+    /// let glob = Glob::new("**/*.log").unwrap();
+    /// let behavior = WalkBehavior::default().sort_by_key(|entry| Reverse(entry.modified()));
+    /// for entry in glob.walk_with_behavior(".", behavior) {
+    ///     let entry = entry.unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// [`WalkBehavior::sort_by`]: crate::WalkBehavior::sort_by
+    pub fn sort_by_key<K, F>(self, f: F) -> Self
+    where
+        K: Ord,
+        F: 'static + Fn(&SortEntry<'_>) -> K + Send + Sync,
+    {
+        self.sort_by(move |left, right| f(left).cmp(&f(right)))
+    }
+}
+
+/// Constructs a `WalkBehavior` using the following defaults:
+///
+/// | Field           | Description                          | Value                         |
+/// |-----------------|--------------------------------------|-------------------------------|
+/// | [`depth`]       | Maximum depth.                       | [`usize::MAX`]                |
+/// | [`link`]        | Interpretation of symbolic links.    | [`LinkBehavior::ReadFile`]    |
+/// | [`progress`]    | Progress callback.                   | `None`                        |
+/// | [`prune`]       | Pruning callback.                    | `None`                        |
+/// | [`sort`]        | Order of entries in a directory.     | `None`                        |
+/// | [`directories`] | Order of directories.                | [`DirectoryOrder::Mixed`]     |
+/// | [`retry`]       | Retry strategy for transient errors. | `None`                        |
+/// | [`case`]        | Case sensitivity of the file system. | [`CaseSensitivity::Platform`] |
+/// | [`backend`]     | Strategy for issuing queries.        | [`WalkBackend::Sequential`]   |
+/// | [`descriptors`] | Limit on open directory descriptors. | `None`                        |
+/// | [`confinement`] | Confinement to the directory.        | [`Confinement::Unconfined`]   |
+///
+/// [`backend`]: crate::WalkBehavior::backend
+/// [`case`]: crate::WalkBehavior::case
+/// [`confinement`]: crate::WalkBehavior::confinement
+/// [`depth`]: crate::WalkBehavior::depth
+/// [`descriptors`]: crate::WalkBehavior::descriptors
+/// [`directories`]: crate::WalkBehavior::directories
+/// [`link`]: crate::WalkBehavior::link
+/// [`progress`]: crate::WalkBehavior::progress
+/// [`prune`]: crate::WalkBehavior::prune
+/// [`retry`]: crate::WalkBehavior::retry
+/// [`sort`]: crate::WalkBehavior::sort
+/// [`CaseSensitivity::Platform`]: crate::CaseSensitivity::Platform
+/// [`Confinement::Unconfined`]: crate::Confinement::Unconfined
+/// [`DirectoryOrder::Mixed`]: crate::DirectoryOrder::Mixed
+/// [`LinkBehavior::ReadFile`]: crate::LinkBehavior::ReadFile
+/// [`WalkBackend::Sequential`]: crate::WalkBackend::Sequential
+/// [`usize::MAX`]: usize::MAX
+impl Default for WalkBehavior {
+    fn default() -> Self {
+        WalkBehavior {
+            depth: usize::MAX,
+            link: LinkBehavior::default(),
+            progress: None,
+            prune: None,
+            sort: None,
+            directories: DirectoryOrder::default(),
+            retry: None,
+            case: CaseSensitivity::default(),
+            backend: WalkBackend::default(),
+            descriptors: None,
+            confinement: Confinement::default(),
+        }
+    }
+}
+
+impl From<CaseSensitivity> for WalkBehavior {
+    fn from(case: CaseSensitivity) -> Self {
+        WalkBehavior {
+            case,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Confinement> for WalkBehavior {
+    fn from(confinement: Confinement) -> Self {
+        WalkBehavior {
+            confinement,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<DescriptorBudget> for WalkBehavior {
+    fn from(descriptors: DescriptorBudget) -> Self {
+        WalkBehavior {
+            descriptors: Some(descriptors),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<WalkBackend> for WalkBehavior {
+    fn from(backend: WalkBackend) -> Self {
+        WalkBehavior {
+            backend,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<()> for WalkBehavior {
+    fn from(_: ()) -> Self {
+        Default::default()
+    }
+}
+
+impl From<LinkBehavior> for WalkBehavior {
+    fn from(link: LinkBehavior) -> Self {
+        WalkBehavior {
+            link,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<RetryPolicy> for WalkBehavior {
+    fn from(retry: RetryPolicy) -> Self {
+        WalkBehavior {
+            retry: Some(retry),
+            ..Default::default()
+        }
+    }
+}
+
+impl From<usize> for WalkBehavior {
+    fn from(depth: usize) -> Self {
+        WalkBehavior {
+            depth,
+            ..Default::default()
+        }
+    }
+}
+
+/// Iterator over files matching a [`Glob`] in a directory tree.
+///
+/// `Walk` is a `TreeIterator` and supports [`FileIterator::filter_tree`].
+///
+/// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+/// [`Glob`]: crate::Glob
+#[derive(Debug)]
+// This type is principally an iterator and is therefore lazy.
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[must_use]
+pub struct Walk<'g, S = StdFilesystem>
+where
+    S: Filesystem,
+{
+    pattern: Cow<'g, Regex>,
+    components: Vec<Regex>,
+    // Whether or not `components` matches every component of the glob, in
+    // which case paths with more components cannot match.
+    is_complete: bool,
+    // Programs for the trailing components of the glob that follow its last
+    // component boundary. This is empty if the glob is complete.
+    suffix: Vec<Regex>,
+    root: PathBuf,
+    prefix: PathBuf,
+    walk: Traversal<S>,
+}
+
+impl<'g, S> Walk<'g, S>
+where
+    S: Filesystem,
+{
+    // Compiles a program for each leading component of a glob and determines
+    // if these programs match every component of the glob.
+    //
+    // Alternatives with component boundaries are expanded, so that their
+    // branches are matched component-wise too. The program for a component
+    // matches the corresponding component of any branch, so directories are
+    // only pruned if no branch can match paths beneath them.
+    fn compile<'t, I>(tokens: I) -> Result<(Vec<Regex>, bool), CompileError>
+    where
+        I: IntoIterator<Item = &'t Token<'t>>,
+        I::IntoIter: Clone,
+    {
+        // Limits the number of branches that are expanded. Component programs
+        // are alternatives of the branches, so more branches are less
+        // selective and more expensive.
+        const BRANCH_LIMIT: usize = 64;
+
+        let tokens = tokens.into_iter();
+        let sequences = token::expand_alternatives(tokens.clone(), BRANCH_LIMIT)
+            .unwrap_or_else(|| vec![tokens.collect()]);
+        let branches: Vec<_> = sequences
+            .iter()
+            .map(|sequence| {
+                let mut components = Vec::new();
+                for component in token::components(sequence.iter().copied()) {
+                    if component
+                        .tokens()
+                        .iter()
+                        .any(|token| token.has_component_boundary())
+                    {
+                        // Stop at component boundaries, such as tree wildcards
+                        // or any boundary within a group token.
+                        return (components, false);
+                    }
+                    components.push(component);
+                }
+                (components, true)
+            })
+            .collect();
+        let n = branches
+            .iter()
+            .map(|(components, _)| components.len())
+            .min()
+            .unwrap_or(0);
+        // Paths with more components cannot match only if every branch is
+        // complete and has the same number of components.
+        let is_complete = branches
+            .iter()
+            .all(|(components, is_complete)| *is_complete && components.len() == n);
+        let regexes = (0..n)
+            .map(|i| match branches.as_slice() {
+                [(components, _)] => Glob::compile(components[i].tokens().iter().copied()),
+                _ => encode::compile([token::any(branches.iter().map(|(components, _)| {
+                    components[i].tokens().iter().map(|token| (*token).clone())
+                }))]),
+            })
+            .collect::<Result<_, _>>()?;
+        Ok((regexes, is_complete))
+    }
+
+    // Compiles a program for each trailing component of a glob that follows its
+    // last component boundary, such as `*.rs` in `src/**/*.rs`.
+    fn compile_suffix<'t, I>(tokens: I) -> Result<Vec<Regex>, CompileError>
+    where
+        I: IntoIterator<Item = &'t Token<'t>>,
+    {
+        let components: Vec<_> = token::components(tokens).collect();
+        let mut regexes = components
+            .iter()
+            .rev()
+            .take_while(|component| {
+                !component
+                    .tokens()
+                    .iter()
+                    .any(|token| token.has_component_boundary())
+            })
+            .map(|component| Glob::compile(component.tokens().iter().copied()))
+            .collect::<Result<Vec<_>, _>>()?;
+        regexes.reverse();
+        Ok(regexes)
+    }
+
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> Walk<'static, S> {
+        let Walk {
+            pattern,
+            components,
+            is_complete,
+            suffix,
+            root,
+            prefix,
+            walk,
+        } = self;
+        Walk {
+            pattern: Cow::Owned(pattern.into_owned()),
+            components,
+            is_complete,
+            suffix,
+            root,
+            prefix,
+            walk,
+        }
+    }
+
+    /// Calls a closure on each matched file or error.
+    ///
+    /// This function is similar to [`for_each`], but does not clone paths and
+    /// [matched text][`MatchedText`] and so may be somewhat more efficient.
+    /// Note that the closure receives borrowing [`WalkEntry`]s rather than
+    /// `'static` items.
+    ///
+    /// [`for_each`]: std::iter::Iterator::for_each
+    /// [`WalkEntry`]: crate::WalkEntry
+    pub fn for_each_ref(mut self, mut f: impl FnMut(WalkItem<S>)) {
+        walk!(self => |entry| {
+            f(entry);
+        });
+    }
+
+    /// Collects the paths of matched files.
+    ///
+    /// The entire directory tree is read, even if errors occur. If no errors
+    /// occur, then the paths of all matched files are returned in the order in
+    /// which they are matched. Otherwise, all errors are returned and paths
+    /// are discarded. To retain paths when errors occur, see
+    /// [`collect_paths_lossy`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any errors occur while reading the directory tree.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("**/*.{md,txt}").unwrap();
+    /// match glob.walk("doc").collect_paths() {
+    ///     Ok(paths) => println!("{} files", paths.len()),
+    ///     Err(errors) => {
+    ///         for error in errors.errors() {
+    ///             eprintln!("{}", error);
+    ///         }
+    ///     },
+    /// }
+    /// ```
+    ///
+    /// [`collect_paths_lossy`]: crate::Walk::collect_paths_lossy
+    pub fn collect_paths(self) -> Result<Vec<PathBuf>, WalkErrors> {
+        let (paths, errors) = self.collect_paths_lossy();
+        if errors.is_empty() {
+            Ok(paths)
+        }
+        else {
+            Err(WalkErrors { errors })
+        }
+    }
+
+    /// Collects the paths of matched files and any errors.
+    ///
+    /// This function is like [`collect_paths`], but returns both the paths of
+    /// matched files and errors that occurred while reading the directory
+    /// tree.
+    ///
+    /// [`collect_paths`]: crate::Walk::collect_paths
+    pub fn collect_paths_lossy(self) -> (Vec<PathBuf>, Vec<WalkError>) {
+        let mut paths = Vec::new();
+        let mut errors = Vec::new();
+        self.for_each_ref(|entry| match entry {
+            Ok(entry) => paths.push(entry.into_path()),
+            Err(error) => errors.push(error),
+        });
+        (paths, errors)
+    }
+
+    /// Filters [`WalkEntry`]s against negated glob expressions.
+    ///
+    /// This function creates an adaptor that discards [`WalkEntry`]s that match
+    /// any of the given glob expressions. This allows for broad negations while
+    /// matching a [`Glob`] against a directory tree that cannot be achieved
+    /// using a single glob expression alone.
+    ///
+    /// The adaptor is constructed via [`FilterTree`] and [`Negation`] and
+    /// therefore does not read directory trees from the file system when a
+    /// directory matches an [exhaustive glob
+    /// expression][`Pattern::is_exhaustive`] such as `**/private/**` or
+    /// `hidden/<<?>/>*`. **This function should be preferred when filtering
+    /// [`WalkEntry`]s against [`Glob`]s, since this avoids potentially large
+    /// and unnecessary reads**.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if any of the inputs fail to build. If the inputs are a
+    /// compiled [`Pattern`] type such as [`Glob`], then this only occurs if the
+    /// compiled program is too large.
+    ///
+    /// # Examples
+    ///
+    /// Because glob expressions do not support general negations, it is
+    /// sometimes impossible to express patterns that deny particular text. In
+    /// such cases, `not` can be used to apply additional patterns as a filter.
+    ///
+    /// ```rust,no_run
+    /// use wax::Glob;
+    ///
+    /// // Find image files, but not if they are beneath a directory with a name that
+    /// // suggests that they are private.
+    /// let glob = Glob::new("**/*.(?i){jpg,jpeg,png}").unwrap();
+    /// for entry in glob.walk(".").not(["**/(?i)<.:0,1>private/**"]).unwrap() {
+    ///     let entry = entry.unwrap();
+    ///     // ...
+    /// }
+    /// ```
+    ///
+    /// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+    /// [`Glob`]: crate::Glob
+    /// [`Iterator::filter`]: std::iter::Iterator::filter
+    /// [`Negation`]: crate::Negation
+    /// [`Pattern`]: crate::Pattern
+    /// [`Pattern::is_exhaustive`]: crate::Pattern::is_exhaustive
+    /// [`WalkEntry`]: crate::WalkEntry
+    pub fn not<'t, I>(self, patterns: I) -> Result<impl 'g + FileIterator<S>, BuildError>
+    where
+        I: IntoIterator,
+        I::Item: Compose<'t>,
+    {
+        Negation::any(patterns).map(|negation| FilterTree {
+            input: self,
+            f: move |entry: &WalkEntry<'static, S>| negation.target(entry),
+            cause: PruneCause::Negation,
+        })
+    }
+
+    /// Yields only the changes to matched files since a previous traversal.
+    ///
+    /// This function creates an [`IncrementalWalk`] that compares the
+    /// directory tree with a [`ChangeCache`] recorded by a previous traversal
+    /// and yields the [changes][`Change`] to matched files: files that have
+    /// been added, modified, or removed. Directories that are unchanged since
+    /// the cache was recorded are not read. The cache for the next traversal
+    /// is retrieved via [`IncrementalWalk::into_cache`]. An empty cache, such as
+    /// from [`ChangeCache::new`], yields all matched files as added.
+    ///
+    /// This function should be called before the `Walk` is iterated.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use std::fs::File;
+    /// use std::io::{BufReader, BufWriter};
+    /// use wax::{Change, ChangeCache, Glob};
+    ///
+    /// let cache = File::open("target/changes")
+    ///     .and_then(|file| ChangeCache::read_from(BufReader::new(file)))
+    ///     .unwrap_or_default();
+    ///
+    /// let glob = Glob::new("src/**/*.rs").unwrap();
+    /// let mut changes = glob.walk(".").incremental(cache);
+    /// for change in &mut changes {
+    ///     match change.unwrap() {
+    ///         Change::Added(entry) | Change::Modified(entry) => {
+    ///             println!("build {:?}", entry.path());
+    ///         },
+    ///         Change::Removed(path) => println!("clean {:?}", path),
+    ///         _ => {},
+    ///     }
+    /// }
+    ///
+    /// let file = File::create("target/changes").unwrap();
+    /// changes.into_cache().write_to(BufWriter::new(file)).unwrap();
+    /// ```
+    ///
+    /// [`Change`]: crate::Change
+    /// [`ChangeCache`]: crate::ChangeCache
+    /// [`ChangeCache::new`]: crate::ChangeCache::new
+    /// [`IncrementalWalk`]: crate::IncrementalWalk
+    /// [`IncrementalWalk::into_cache`]: crate::IncrementalWalk::into_cache
+    pub fn incremental(self, cache: ChangeCache) -> IncrementalWalk<'g, S> {
+        IncrementalWalk::new(self, cache)
+    }
+
+    /// Gets the root directory of the traversal.
+    ///
+    /// The root directory is determined by joining the directory path in
+    /// functions like [`Glob::walk`] with any [invariant
+    /// prefix](`Glob::partition`) of the [`Glob`]. When a [`Glob`] is rooted,
+    /// the root directory is the same as the invariant prefix.
+    ///
+    /// The depth specified via [`WalkBehavior`] is relative to this path.
+    ///
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::partition`]: crate::Glob::partition
+    /// [`Glob::walk`]: crate::Glob::walk
+    /// [`WalkBehavior`]: crate::WalkBehavior
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+}
+
+impl<S> Iterator for Walk<'_, S>
+where
+    S: Filesystem,
+{
+    type Item = WalkItem<'static, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        walk!(self => move |entry| {
+            return Some(entry);
+        });
+        None
+    }
+}
+
+impl<S> TreeIterator for Walk<'_, S>
+where
+    S: Filesystem,
+{
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        self.walk.skip_tree_with(cause);
+    }
+}
+
+/// Describes how files are read and discarded by [`FilterTree`].
+///
+/// [`FilterTree`]: crate::FilterTree
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum FilterTarget {
+    /// Discard the file.
+    ///
+    /// The [`WalkEntry`] for the given file is discarded by the [`FilterTree`]
+    /// adaptor. Only this particular file is ignored and if the entry
+    /// represents a directory, then its tree is still read from the file
+    /// system.
+    ///
+    /// [`FilterTree`]: crate::FilterTree
+    /// [`WalkEntry`]: crate::WalkEntry
+    File,
+    /// Discard the file and its directory tree, if any.
+    ///
+    /// The [`WalkEntry`] for the given file is discarded by the [`FilterTree`]
+    /// adaptor. If the entry represents a directory, then its entire tree is
+    /// ignored and is not read from the file system.
+    ///
+    /// When the [`WalkEntry`] represents a normal file (not a directory), then
+    /// this is the same as [`FilterTarget::File`].
+    ///
+    /// [`FilterTarget::File`]: crate::FilterTarget::File
+    /// [`FilterTree`]: crate::FilterTree
+    /// [`WalkEntry`]: crate::WalkEntry
+    Tree,
+}
+
+/// Iterator adaptor that filters [`WalkEntry`]s and controls the traversal of
+/// directory trees.
+///
+/// This adaptor is returned by [`FileIterator::filter_tree`] and in addition to
+/// filtering [`WalkEntry`]s also determines how `TreeIterator`s traverse
+/// directory trees. If discarded directories do not need to be read from the
+/// file system, then **this adaptor should be preferred over functions like
+/// [`Iterator::filter`], because it can avoid potentially large and unnecessary
+/// reads.**
+///
+/// `FilterTree` is a `TreeIterator` and supports [`FileIterator::filter_tree`]
+/// so `filter_tree` may be chained.
+///
+/// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Debug)]
+pub struct FilterTree<I, F> {
+    input: I,
+    f: F,
+    cause: PruneCause,
+}
+
+impl<I, F, S> Iterator for FilterTree<I, F>
+where
+    I: TreeIterator<Item = WalkItem<'static, S>>,
+    F: FnMut(&WalkEntry<'static, S>) -> Option<FilterTarget>,
+    S: Filesystem,
+{
+    type Item = WalkItem<'static, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(result) = self.input.next() {
+                if let Ok(entry) = result.as_ref() {
+                    match (self.f)(entry) {
+                        None => {
+                            return Some(result);
+                        },
+                        Some(FilterTarget::File) => {
+                            continue;
+                        },
+                        Some(FilterTarget::Tree) => {
+                            if entry.file_type().is_dir() {
+                                self.input.skip_tree_with(self.cause);
+                            }
+                            continue;
+                        },
+                    }
+                }
+                return Some(result);
+            }
+            return None;
+        }
+    }
+}
+
+impl<I, F> TreeIterator for FilterTree<I, F>
+where
+    Self: Iterator,
+    I: TreeIterator,
+{
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        self.input.skip_tree_with(cause);
+    }
+}
+
+/// Iterator adaptor that stops traversing directory trees after a number of
+/// [`WalkEntry`]s have been yielded.
+///
+/// This adaptor is returned by [`FileIterator::take_matches`]. `TakeMatches` is
+/// a `TreeIterator` and supports [`FileIterator::filter_tree`].
+///
+/// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+/// [`FileIterator::take_matches`]: crate::FileIterator::take_matches
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Debug)]
+pub struct TakeMatches<I> {
+    // The input is discarded when no more entries may be yielded, which ends
+    // the traversal.
+    input: Option<I>,
+    remaining: usize,
+}
+
+impl<I, S> Iterator for TakeMatches<I>
+where
+    I: TreeIterator<Item = WalkItem<'static, S>>,
+    S: Filesystem,
+{
+    type Item = WalkItem<'static, S>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.input.as_mut()?.next();
+        match result {
+            Some(Ok(_)) => {
+                self.remaining -= 1;
+                if self.remaining == 0 {
+                    self.input = None;
+                }
+            },
+            Some(Err(_)) => {},
+            None => {
+                self.input = None;
+            },
+        }
+        result
+    }
+}
+
+impl<I> TreeIterator for TakeMatches<I>
+where
+    Self: Iterator,
+    I: TreeIterator,
+{
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        if let Some(input) = self.input.as_mut() {
+            input.skip_tree_with(cause);
+        }
+    }
+}
+
+/// Describes a file matching a [`Glob`] in a directory tree.
+///
+/// The [`path`] of a `WalkEntry` is the native path of the file, which begins
+/// with the directory given to functions like [`Glob::walk`] and so may be
+/// absolute. The [`Glob`] is matched against the path **relative to this
+/// directory**, which includes any [invariant prefix][`Glob::partition`] of
+/// the [`Glob`]. The [matched text][`matched`] and its captures are therefore
+/// the same as those of [`Pattern::matched`] for this relative path and
+/// capture indices agree with the [`Glob`]. The [depth][`depth`] is instead
+/// relative to [the root][`Walk::root`], which excludes the invariant prefix.
+/// To locate captures in the native path, see [`matched_in_path`].
+///
+/// If the [`Glob`] is rooted, then the relative and native paths are the same.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::Glob;
+///
+/// let glob = Glob::new("src/**/{*}.rs").unwrap();
+/// for entry in glob.walk("/home/user/project") {
+///     let entry = entry.unwrap();
+///     // For example, `src/token/parse.rs` and `parse`.
+///     let matched = entry.matched();
+///     println!("{} {}", matched.complete(), matched.get(2).unwrap());
+///     // For example, `/home/user/project/src/token/parse.rs` and `parse`.
+///     let matched = entry.matched_in_path().unwrap();
+///     println!("{} {}", matched.complete(), matched.get(2).unwrap());
+/// }
+/// ```
+///
+/// [`depth`]: crate::WalkEntry::depth
+/// [`Glob`]: crate::Glob
+/// [`Glob::partition`]: crate::Glob::partition
+/// [`Glob::walk`]: crate::Glob::walk
+/// [`matched`]: crate::WalkEntry::matched
+/// [`matched_in_path`]: crate::WalkEntry::matched_in_path
+/// [`path`]: crate::WalkEntry::path
+/// [`Pattern::matched`]: crate::Pattern::matched
+/// [`Walk::root`]: crate::Walk::root
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Debug)]
+pub struct WalkEntry<'e, S = StdFilesystem>
+where
+    S: Filesystem,
+{
+    entry: Cow<'e, Entry<S>>,
+    matched: MatchedText<'e>,
+}
+
+impl<'e, S> WalkEntry<'e, S>
+where
+    S: Filesystem,
+{
+    /// Clones any borrowed data into an owning instance.
+    pub fn into_owned(self) -> WalkEntry<'static, S> {
+        let WalkEntry { entry, matched } = self;
+        WalkEntry {
+            entry: Cow::Owned(entry.into_owned()),
+            matched: matched.into_owned(),
+        }
+    }
+
+    /// Converts the entry into the path of the matched file.
+    ///
+    /// Entries yielded by a [`Walk`] own their paths, so this hands over the
+    /// path that was built during traversal and does not allocate. Entries
+    /// given to [`Walk::for_each_ref`] borrow their paths, which are copied.
+    ///
+    /// [`Walk`]: crate::Walk
+    /// [`Walk::for_each_ref`]: crate::Walk::for_each_ref
+    pub fn into_path(self) -> PathBuf {
+        match self.entry {
+            Cow::Borrowed(entry) => entry.path().to_path_buf(),
+            Cow::Owned(entry) => entry.into_path(),
+        }
+    }
+
+    /// Gets the path of the matched file.
+    pub fn path(&self) -> &Path {
+        self.entry.path()
+    }
+
+    /// Converts the entry to the relative [`CandidatePath`].
+    ///
+    /// **This differs from [`path`] and [`into_path`], which are natively
+    /// encoded and may be absolute.** The [`CandidatePath`] is always relative
+    /// to the directory given to functions like [`Glob::walk`] and is the path
+    /// against which the [`Glob`] is matched. It borrows the [matched
+    /// text][`matched`], so this does not allocate.
+    ///
+    /// [`CandidatePath`]: crate::CandidatePath
+    /// [`Glob`]: crate::Glob
+    /// [`Glob::walk`]: crate::Glob::walk
+    /// [`into_path`]: crate::WalkEntry::into_path
+    /// [`matched`]: crate::WalkEntry::matched
+    /// [`path`]: crate::WalkEntry::path
+    pub fn to_candidate_path(&self) -> CandidatePath<'_> {
+        self.matched.to_candidate_path()
+    }
+
+    /// Gets the type of the file.
+    ///
+    /// The type is read during traversal, so this never queries the
+    /// [`Filesystem`].
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    pub fn file_type(&self) -> S::FileType {
+        self.entry.file_type()
+    }
+
+    /// Gets the metadata of the file.
+    ///
+    /// Metadata is queried at most once and is not queried at all if it was
+    /// read during traversal, such as when reading directories on Windows or
+    /// following symbolic links. Prefer this function over querying the
+    /// metadata of [`path`], which always queries the [`Filesystem`].
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the metadata has not been read and querying it
+    /// fails. Errors are not cached, so the query is repeated in subsequent
+    /// calls.
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    /// [`path`]: crate::WalkEntry::path
+    pub fn metadata(&self) -> Result<S::Metadata, WalkError> {
+        self.entry.metadata()
+    }
+
+    /// Gets the metadata of the file if it has already been read.
+    ///
+    /// This never queries the [`Filesystem`]. Metadata is available if it was
+    /// read during traversal or by a previous call to [`metadata`].
+    ///
+    /// [`Filesystem`]: crate::Filesystem
+    /// [`metadata`]: crate::WalkEntry::metadata
+    pub fn cached_metadata(&self) -> Option<S::Metadata> {
+        self.entry.cached_metadata()
+    }
+
+    /// Gets the depth of the file from [the root][`Walk::root`] of the
+    /// directory tree.
+    ///
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn depth(&self) -> usize {
+        self.entry.depth()
+    }
+
+    /// Gets the matched text in the path of the file.
+    ///
+    /// The text is matched against the path relative to the directory given to
+    /// functions like [`Glob::walk`], so the complete text is the same as the
+    /// text of [`to_candidate_path`]. See [`WalkEntry`].
+    ///
+    /// [`Glob::walk`]: crate::Glob::walk
+    /// [`to_candidate_path`]: crate::WalkEntry::to_candidate_path
+    /// [`WalkEntry`]: crate::WalkEntry
+    pub fn matched(&self) -> &MatchedText<'e> {
+        &self.matched
+    }
+
+    /// Gets the matched text in the native path of the file.
+    ///
+    /// This function is similar to [`matched`], but the complete text is the
+    /// [native path][`path`] of the file and the text and [`Span`]s of
+    /// captures are located in this path. This can be used to modify the
+    /// captured text of the native path, such as when renaming files. Captured
+    /// text may contain native separators.
+    ///
+    /// Returns `None` if the path is not valid UTF-8, in which case captures
+    /// cannot be located in the path.
+    ///
+    /// # Examples
+    ///
+    /// ```rust,no_run
+    /// use wax::Glob;
+    ///
+    /// let glob = Glob::new("*.jpeg").unwrap();
+    /// for entry in glob.walk("photos") {
+    ///     let entry = entry.unwrap();
+    ///     let matched = entry.matched_in_path().unwrap();
+    ///     let (start, n) = matched.span(1).unwrap();
+    ///     let path = matched.complete();
+    ///     // For example, `photos/beach.jpg`.
+    ///     println!("{}.jpg", &path[..start + n]);
+    /// }
+    /// ```
+    ///
+    /// [`matched`]: crate::WalkEntry::matched
+    /// [`path`]: crate::WalkEntry::path
+    /// [`Span`]: crate::Span
+    pub fn matched_in_path(&self) -> Option<MatchedText<'static>> {
+        let path = self.path().to_str()?;
+        let relative = self.matched.complete();
+        // The relative path is a suffix of the native path, but its text may
+        // differ in separators on some platforms.
+        let offset = path.len().checked_sub(relative.len())?;
+        let suffix = path.get(offset..)?;
+        suffix
+            .chars()
+            .zip(relative.chars())
+            .all(|(native, relative)| {
+                native == relative || (path::is_separator(native) && path::is_separator(relative))
+            })
+            .then(|| self.matched.to_translated(path, offset))
+    }
+}
+
+pub fn walk<'g, S>(
+    glob: &'g Glob<'_>,
+    filesystem: S,
+    directory: impl AsRef<Path>,
+    behavior: impl Into<WalkBehavior>,
+) -> Walk<'g, S>
+where
+    S: Filesystem,
+{
+    let directory = directory.as_ref();
+    let behavior = behavior.into();
+    let mut filesystem = filesystem;
+    filesystem.configure(&behavior);
+    let WalkBehavior {
+        depth,
+        link,
+        progress,
+        prune,
+        sort,
+        directories,
+        retry,
+        case,
+        confinement,
+        ..
+    } = behavior;
+    // Literals are recased and compiled again if the default case sensitivity
+    // of the file system differs from that of the target platform. Literals
+    // with case sensitivity that is set by flags are not recased.
+    let is_case_insensitive = case.is_case_insensitive(&filesystem, directory);
+    let recased = (is_case_insensitive != PATHS_ARE_CASE_INSENSITIVE)
+        .then(|| glob.tree.as_ref().clone().recase(is_case_insensitive));
+    let tokens = recased.as_ref().map_or_else(
+        || glob.tree.as_ref().tokens(),
+        |tokenized| tokenized.tokens(),
+    );
+    // The directory tree is traversed from `root`, which may include an
+    // invariant prefix from the glob pattern. `Walk` patterns are only applied
+    // to path components following this prefix in `root`.
+    let (root, prefix) = invariant_path_prefix(tokens).map_or_else(
+        || {
+            let root = Cow::from(directory);
+            (root.clone(), root)
+        },
+        |prefix| {
+            let root = directory.join(&prefix).into();
+            if prefix.is_absolute() {
+                // Absolute paths replace paths with which they are joined,
+                // in which case there is no prefix.
+                (root, PathBuf::new().into())
+            }
+            else {
+                (root, directory.into())
+            }
+        },
+    );
+    let (components, is_complete) =
+        Walk::<S>::compile(tokens).expect("failed to compile glob sub-expressions");
+    let suffix = if is_complete {
+        vec![]
+    }
+    else {
+        Walk::<S>::compile_suffix(tokens).expect("failed to compile glob sub-expressions")
+    };
+    // Do not read directories beneath the greatest depth that the glob can
+    // match, if any. This depth is relative to `root` and so excludes the
+    // components of the invariant prefix.
+    let depth = token::component_upper_bound(tokens).map_or(depth, |bound| {
+        let n = invariant_path_prefix(tokens).map_or(0, |prefix| {
+            prefix
+                .components()
+                .filter(|component| {
+                    matches!(component, Component::Normal(_) | Component::ParentDir)
+                })
+                .count()
+        });
+        cmp::min(depth, bound.saturating_sub(n))
+    });
+    // Confined traversals verify that paths resolve beneath the given
+    // directory rather than `root`, which may include an invariant prefix that
+    // escapes the directory.
+    let boundary = confinement
+        .is_confined()
+        .then(|| Boundary::new(&filesystem, directory));
+    #[cfg(feature = "tracing")]
+    tracing::debug!(root = ?root, expression = %glob, "walking directory tree");
+    Walk {
+        pattern: match recased {
+            Some(ref tokenized) => Cow::Owned(
+                Glob::compile(tokenized.tokens()).expect("failed to compile glob expression"),
+            ),
+            _ => Cow::Borrowed(&glob.pattern),
+        },
+        components,
+        is_complete,
+        suffix,
+        root: root.clone().into_owned(),
+        prefix: prefix.into_owned(),
+        walk: Traversal::new(
+            filesystem,
+            root.into_owned(),
+            link,
+            depth,
+            progress,
+            prune,
+            Sort::new(sort, directories),
+            retry,
+            boundary,
+        ),
+    }
+}
+
+fn invariant_path_prefix<'t, A, I>(tokens: I) -> Option<PathBuf>
+where
+    A: 't,
+    I: IntoIterator<Item = &'t Token<'t, A>>,
+{
+    let prefix = token::invariant_text_prefix(tokens);
+    if prefix.is_empty() {
+        None
+    }
+    else {
+        Some(prefix.into())
+    }
+}
@@ -0,0 +1,114 @@
+use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+
+// The number of entries read between periodic progress reports.
+const INTERVAL: usize = 1024;
+
+/// Progress of a traversal of a directory tree.
+///
+/// `WalkProgress` is given to [`ProgressCallback`]s when a directory is
+/// entered and periodically as entries are read from a directory tree. Counts
+/// include all entries read from the tree, regardless of whether or not they
+/// match a [`Glob`].
+///
+/// [`Glob`]: crate::Glob
+/// [`ProgressCallback`]: crate::ProgressCallback
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug)]
+pub struct WalkProgress<'p> {
+    entries: usize,
+    directories: usize,
+    errors: usize,
+    directory: &'p Path,
+}
+
+impl<'p> WalkProgress<'p> {
+    /// Gets the number of entries read so far.
+    pub fn entries(&self) -> usize {
+        self.entries
+    }
+
+    /// Gets the number of directories entered so far.
+    pub fn directories(&self) -> usize {
+        self.directories
+    }
+
+    /// Gets the number of errors encountered so far.
+    pub fn errors(&self) -> usize {
+        self.errors
+    }
+
+    /// Gets the path of the directory currently being read.
+    pub fn directory(&self) -> &'p Path {
+        self.directory
+    }
+}
+
+/// Callback that is invoked with the progress of a traversal.
+///
+/// See [`WalkBehavior::on_progress`].
+///
+/// [`WalkBehavior::on_progress`]: crate::WalkBehavior::on_progress
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone)]
+pub struct ProgressCallback(Arc<dyn Fn(&WalkProgress<'_>) + Send + Sync>);
+
+impl Debug for ProgressCallback {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.debug_tuple("ProgressCallback").finish()
+    }
+}
+
+impl<F> From<F> for ProgressCallback
+where
+    F: 'static + Fn(&WalkProgress<'_>) + Send + Sync,
+{
+    fn from(f: F) -> Self {
+        ProgressCallback(Arc::new(f))
+    }
+}
+
+#[derive(Debug)]
+pub struct Progress {
+    callback: ProgressCallback,
+    entries: usize,
+    directories: usize,
+    errors: usize,
+}
+
+impl Progress {
+    pub fn new(callback: ProgressCallback) -> Self {
+        Progress {
+            callback,
+            entries: 0,
+            directories: 0,
+            errors: 0,
+        }
+    }
+
+    pub fn enter(&mut self, directory: &Path) {
+        self.directories += 1;
+        self.report(directory);
+    }
+
+    pub fn entry(&mut self, directory: &Path) {
+        self.entries += 1;
+        if self.entries % INTERVAL == 0 {
+            self.report(directory);
+        }
+    }
+
+    pub fn error(&mut self) {
+        self.errors += 1;
+    }
+
+    fn report(&self, directory: &Path) {
+        (self.callback.0)(&WalkProgress {
+            entries: self.entries,
+            directories: self.directories,
+            errors: self.errors,
+            directory,
+        });
+    }
+}
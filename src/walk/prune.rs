@@ -0,0 +1,105 @@
+use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+
+/// Reason that a directory tree is not read.
+///
+/// See [`PrunedTree`].
+///
+/// [`PrunedTree`]: crate::PrunedTree
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum PruneCause {
+    /// The directory tree cannot contain files that match the glob.
+    ///
+    /// The path of the directory does not match the corresponding components
+    /// of the glob or the glob cannot match paths with more components.
+    Mismatch,
+    /// The directory is at the [maximum depth][`WalkBehavior::depth`] of the
+    /// traversal.
+    ///
+    /// The maximum depth is also limited by the number of components that the
+    /// glob can match, if any.
+    ///
+    /// [`WalkBehavior::depth`]: crate::WalkBehavior::depth
+    Depth,
+    /// The directory matches an [exhaustive][`Pattern::is_exhaustive`] negated
+    /// glob, such as those given to [`Walk::not`] or [`Filter::new`].
+    ///
+    /// [`Filter::new`]: crate::Filter::new
+    /// [`Pattern::is_exhaustive`]: crate::Pattern::is_exhaustive
+    /// [`Walk::not`]: crate::Walk::not
+    Negation,
+    /// The directory is discarded by a [`FileIterator::filter_tree`] callback
+    /// that returns [`FilterTarget::Tree`].
+    ///
+    /// [`FileIterator::filter_tree`]: crate::FileIterator::filter_tree
+    /// [`FilterTarget::Tree`]: crate::FilterTarget::Tree
+    Filter,
+}
+
+/// Directory tree that is not read by a traversal.
+///
+/// `PrunedTree` is given to [`PruneCallback`]s when a traversal skips the
+/// contents of a directory. The directory itself has already been read, but
+/// none of the files beneath it are read from the file system.
+///
+/// [`PruneCallback`]: crate::PruneCallback
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug)]
+pub struct PrunedTree<'p> {
+    path: &'p Path,
+    depth: usize,
+    cause: PruneCause,
+}
+
+impl<'p> PrunedTree<'p> {
+    /// Gets the path of the directory.
+    pub fn path(&self) -> &'p Path {
+        self.path
+    }
+
+    /// Gets the depth of the directory from [the root][`Walk::root`] of the
+    /// traversal.
+    ///
+    /// [`Walk::root`]: crate::Walk::root
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Gets the reason that the directory tree is not read.
+    pub fn cause(&self) -> PruneCause {
+        self.cause
+    }
+}
+
+/// Callback that is invoked when a traversal does not read a directory tree.
+///
+/// See [`WalkBehavior::on_prune`].
+///
+/// [`WalkBehavior::on_prune`]: crate::WalkBehavior::on_prune
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone)]
+pub struct PruneCallback(Arc<dyn Fn(&PrunedTree<'_>) + Send + Sync>);
+
+impl PruneCallback {
+    pub(in crate::walk) fn report(&self, path: &Path, depth: usize, cause: PruneCause) {
+        (self.0)(&PrunedTree { path, depth, cause });
+    }
+}
+
+impl Debug for PruneCallback {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.debug_tuple("PruneCallback").finish()
+    }
+}
+
+impl<F> From<F> for PruneCallback
+where
+    F: 'static + Fn(&PrunedTree<'_>) + Send + Sync,
+{
+    fn from(f: F) -> Self {
+        PruneCallback(Arc::new(f))
+    }
+}
@@ -0,0 +1,202 @@
+use std::fmt::{self, Debug, Formatter};
+use std::io::{self, ErrorKind};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+type ErrorFilter = dyn Fn(&io::Error) -> bool + Send + Sync;
+
+/// Strategy for retrying file system queries that fail with transient errors.
+///
+/// By default, an error that occurs when reading a directory tree is yielded
+/// immediately and the affected subtree is not read. A `RetryPolicy` instead
+/// repeats a failed query up to a number of times, waiting between each
+/// attempt. This is useful when reading network or other remote file systems,
+/// where queries may fail spuriously.
+///
+/// The delay between attempts begins with a [backoff][`RetryPolicy::backoff`]
+/// and doubles after each attempt. By default, only errors that are typically
+/// transient are retried, such as [`ErrorKind::Interrupted`],
+/// [`ErrorKind::WouldBlock`], and [`ErrorKind::TimedOut`]. See
+/// [`RetryPolicy::retry_if`].
+///
+/// Retries apply to queries made by the traversal, such as reading
+/// directories, querying the metadata of roots and followed symbolic links,
+/// and canonicalizing paths. Queries made by [`WalkEntry::metadata`] are not
+/// retried.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use std::time::Duration;
+/// use wax::{Glob, RetryPolicy};
+///
+/// let retry = RetryPolicy::new(3).backoff(Duration::from_millis(50));
+/// for entry in Glob::new("**/*.dat")
+///     .unwrap()
+///     .walk_with_behavior("/mnt/archive", retry)
+/// {
+///     let entry = entry.unwrap();
+///     // ...
+/// }
+/// ```
+///
+/// [`ErrorKind::Interrupted`]: std::io::ErrorKind::Interrupted
+/// [`ErrorKind::TimedOut`]: std::io::ErrorKind::TimedOut
+/// [`ErrorKind::WouldBlock`]: std::io::ErrorKind::WouldBlock
+/// [`RetryPolicy::backoff`]: crate::RetryPolicy::backoff
+/// [`RetryPolicy::retry_if`]: crate::RetryPolicy::retry_if
+/// [`WalkEntry::metadata`]: crate::WalkEntry::metadata
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone)]
+pub struct RetryPolicy {
+    retries: usize,
+    backoff: Duration,
+    filter: Option<Arc<ErrorFilter>>,
+}
+
+impl RetryPolicy {
+    /// Constructs a `RetryPolicy` that retries a failed query at most the
+    /// given number of times.
+    ///
+    /// The initial backoff is zero, so queries are retried immediately.
+    pub fn new(retries: usize) -> Self {
+        RetryPolicy {
+            retries,
+            backoff: Duration::ZERO,
+            filter: None,
+        }
+    }
+
+    /// Sets the delay before the first retry.
+    ///
+    /// The delay doubles after each retry.
+    pub fn backoff(mut self, backoff: Duration) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Sets the function that determines if an error is retried.
+    ///
+    /// This replaces the default classification of transient errors.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use std::io::ErrorKind;
+    /// use wax::RetryPolicy;
+    ///
+    /// // Also retry errors from an unreliable FUSE mount.
+    /// let retry = RetryPolicy::new(5).retry_if(|error| {
+    ///     RetryPolicy::is_transient(error) || error.kind() == ErrorKind::NotConnected
+    /// });
+    /// ```
+    pub fn retry_if<F>(mut self, f: F) -> Self
+    where
+        F: 'static + Fn(&io::Error) -> bool + Send + Sync,
+    {
+        self.filter = Some(Arc::new(f));
+        self
+    }
+
+    /// Gets the maximum number of retries.
+    pub fn retries(&self) -> usize {
+        self.retries
+    }
+
+    /// Returns `true` if an error is typically transient.
+    ///
+    /// This is the default classification of errors that are retried.
+    pub fn is_transient(error: &io::Error) -> bool {
+        matches!(
+            error.kind(),
+            ErrorKind::Interrupted | ErrorKind::WouldBlock | ErrorKind::TimedOut
+        )
+    }
+
+    fn is_retried(&self, error: &io::Error) -> bool {
+        match self.filter.as_ref() {
+            Some(filter) => filter(error),
+            None => RetryPolicy::is_transient(error),
+        }
+    }
+
+    fn run<T>(&self, mut f: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+        let mut backoff = self.backoff;
+        let mut retries = 0;
+        loop {
+            match f() {
+                Err(error) if retries < self.retries && self.is_retried(&error) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::debug!(error = %error, retries, "retrying file system query");
+                    if !backoff.is_zero() {
+                        thread::sleep(backoff);
+                        backoff = backoff.saturating_mul(2);
+                    }
+                    retries += 1;
+                },
+                result => return result,
+            }
+        }
+    }
+}
+
+impl Debug for RetryPolicy {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("RetryPolicy")
+            .field("retries", &self.retries)
+            .field("backoff", &self.backoff)
+            .finish_non_exhaustive()
+    }
+}
+
+// Runs a file system query with an optional `RetryPolicy`.
+pub fn retry<T>(
+    policy: Option<&RetryPolicy>,
+    mut f: impl FnMut() -> io::Result<T>,
+) -> io::Result<T> {
+    match policy {
+        Some(policy) => policy.run(f),
+        None => f(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, ErrorKind};
+
+    use crate::walk::retry;
+    use crate::RetryPolicy;
+
+    fn query(failures: usize, kind: ErrorKind) -> impl FnMut() -> io::Result<usize> {
+        let mut attempts = 0;
+        move || {
+            attempts += 1;
+            if attempts > failures {
+                Ok(attempts)
+            }
+            else {
+                Err(kind.into())
+            }
+        }
+    }
+
+    #[test]
+    fn retry_transient_errors() {
+        let policy = RetryPolicy::new(2);
+        assert_eq!(
+            retry::retry(Some(&policy), query(2, ErrorKind::Interrupted)).unwrap(),
+            3,
+        );
+        assert!(retry::retry(Some(&policy), query(3, ErrorKind::Interrupted)).is_err());
+        assert!(retry::retry(Some(&policy), query(1, ErrorKind::NotFound)).is_err());
+        assert!(retry::retry(None, query(1, ErrorKind::Interrupted)).is_err());
+
+        let policy = RetryPolicy::new(1).retry_if(|error| error.kind() == ErrorKind::NotFound);
+        assert_eq!(
+            retry::retry(Some(&policy), query(1, ErrorKind::NotFound)).unwrap(),
+            2,
+        );
+    }
+}
@@ -0,0 +1,173 @@
+use std::cmp::Ordering;
+use std::ffi::OsStr;
+use std::fmt::{self, Debug, Formatter};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::SystemTime;
+
+/// Order of directories relative to other files among the entries of a
+/// directory.
+///
+/// See [`WalkBehavior::directories`].
+///
+/// [`WalkBehavior::directories`]: crate::WalkBehavior::directories
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DirectoryOrder {
+    /// Directories are ordered like any other file.
+    Mixed,
+    /// Directories are ordered before other files.
+    First,
+    /// Directories are ordered after other files.
+    Last,
+}
+
+impl Default for DirectoryOrder {
+    fn default() -> Self {
+        DirectoryOrder::Mixed
+    }
+}
+
+// Type and lazily queried metadata of an entry in a directory that is being
+// sorted.
+pub trait Sibling {
+    fn path(&self) -> &Path;
+
+    fn is_dir(&self) -> bool;
+
+    fn is_symlink(&self) -> bool;
+
+    fn size(&self) -> Option<u64>;
+
+    fn modified(&self) -> Option<SystemTime>;
+}
+
+/// Entry in a directory that is compared by a [`SortCallback`].
+///
+/// The type and metadata of a symbolic link describe the link file itself
+/// rather than its target. Metadata is queried at most once per entry and only
+/// if it is requested and was not read along with the directory. Queried
+/// metadata is reused by the corresponding [`WalkEntry`].
+///
+/// [`SortCallback`]: crate::SortCallback
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone, Copy)]
+pub struct SortEntry<'e> {
+    sibling: &'e dyn Sibling,
+}
+
+impl<'e> SortEntry<'e> {
+    pub(in crate::walk) fn new(sibling: &'e dyn Sibling) -> Self {
+        SortEntry { sibling }
+    }
+
+    /// Gets the path of the file.
+    pub fn path(&self) -> &'e Path {
+        self.sibling.path()
+    }
+
+    /// Gets the name of the file, if any.
+    pub fn file_name(&self) -> Option<&'e OsStr> {
+        self.sibling.path().file_name()
+    }
+
+    /// Returns `true` if the file is a directory.
+    pub fn is_dir(&self) -> bool {
+        self.sibling.is_dir()
+    }
+
+    /// Returns `true` if the file is a symbolic link.
+    pub fn is_symlink(&self) -> bool {
+        self.sibling.is_symlink()
+    }
+
+    /// Gets the size of the file in bytes.
+    ///
+    /// Returns `None` if the metadata of the file cannot be queried or does
+    /// not provide a size. See [`Metadata::size`].
+    ///
+    /// [`Metadata::size`]: crate::Metadata::size
+    pub fn size(&self) -> Option<u64> {
+        self.sibling.size()
+    }
+
+    /// Gets the last modification time of the file.
+    ///
+    /// Returns `None` if the metadata of the file cannot be queried or does
+    /// not provide a modification time. See [`Metadata::modified`].
+    ///
+    /// [`Metadata::modified`]: crate::Metadata::modified
+    pub fn modified(&self) -> Option<SystemTime> {
+        self.sibling.modified()
+    }
+}
+
+impl<'e> Debug for SortEntry<'e> {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("SortEntry")
+            .field("path", &self.path())
+            .field("is_dir", &self.is_dir())
+            .finish_non_exhaustive()
+    }
+}
+
+type Compare = dyn Fn(&SortEntry<'_>, &SortEntry<'_>) -> Ordering + Send + Sync;
+
+/// Callback that compares entries in a directory.
+///
+/// See [`WalkBehavior::sort_by`].
+///
+/// [`WalkBehavior::sort_by`]: crate::WalkBehavior::sort_by
+#[cfg_attr(docsrs, doc(cfg(feature = "walk")))]
+#[derive(Clone)]
+pub struct SortCallback(Arc<Compare>);
+
+impl Debug for SortCallback {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter.debug_tuple("SortCallback").finish()
+    }
+}
+
+impl<F> From<F> for SortCallback
+where
+    F: 'static + Fn(&SortEntry<'_>, &SortEntry<'_>) -> Ordering + Send + Sync,
+{
+    fn from(f: F) -> Self {
+        SortCallback(Arc::new(f))
+    }
+}
+
+#[derive(Debug)]
+pub struct Sort {
+    callback: Option<SortCallback>,
+    directories: DirectoryOrder,
+}
+
+impl Sort {
+    // Gets a `Sort` if the given configuration orders entries. Otherwise,
+    // entries are yielded in the order in which they are read.
+    pub fn new(callback: Option<SortCallback>, directories: DirectoryOrder) -> Option<Self> {
+        match (callback, directories) {
+            (None, DirectoryOrder::Mixed) => None,
+            (callback, directories) => Some(Sort {
+                callback,
+                directories,
+            }),
+        }
+    }
+
+    pub fn compare(&self, left: &SortEntry<'_>, right: &SortEntry<'_>) -> Ordering {
+        let ordering = match self.directories {
+            DirectoryOrder::Mixed => Ordering::Equal,
+            DirectoryOrder::First => right.is_dir().cmp(&left.is_dir()),
+            DirectoryOrder::Last => left.is_dir().cmp(&right.is_dir()),
+        };
+        ordering.then_with(|| {
+            self.callback
+                .as_ref()
+                .map_or(Ordering::Equal, |callback| (callback.0)(left, right))
+        })
+    }
+}
@@ -0,0 +1,642 @@
+use std::fmt::{self, Debug, Formatter};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, PoisonError};
+use std::time::SystemTime;
+use std::vec;
+
+use crate::walk::confine::Boundary;
+use crate::walk::incremental::{Changes, Stamp};
+use crate::walk::progress::{Progress, ProgressCallback};
+use crate::walk::prune::{PruneCallback, PruneCause};
+use crate::walk::retry::{retry, RetryPolicy};
+use crate::walk::sort::{self, Sort, SortEntry};
+use crate::walk::{
+    FileType as _, Filesystem, LinkBehavior, Metadata as _, WalkError, WalkOperation,
+};
+
+/// File read from a directory tree.
+#[derive(Clone, Debug)]
+pub struct Entry<S>
+where
+    S: Filesystem,
+{
+    filesystem: S,
+    path: PathBuf,
+    file_type: S::FileType,
+    metadata: MetadataCache<S::Metadata>,
+    depth: usize,
+    is_followed: bool,
+}
+
+impl<S> Entry<S>
+where
+    S: Filesystem,
+{
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn file_type(&self) -> S::FileType {
+        self.file_type.clone()
+    }
+
+    pub fn metadata(&self) -> Result<S::Metadata, WalkError> {
+        self.metadata
+            .get_or_query(|| {
+                if self.is_followed {
+                    self.filesystem.metadata(&self.path)
+                }
+                else {
+                    self.filesystem.symlink_metadata(&self.path)
+                }
+            })
+            .map_err(|error| {
+                WalkError::io(
+                    self.depth,
+                    WalkOperation::ReadMetadata,
+                    Some(self.path.clone()),
+                    error,
+                )
+            })
+    }
+
+    pub fn cached_metadata(&self) -> Option<S::Metadata> {
+        self.metadata.get()
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+}
+
+// Metadata of an `Entry` that is queried at most once. Metadata may be read
+// during traversal, in which case it is never queried.
+#[derive(Debug)]
+struct MetadataCache<M>(Mutex<Option<M>>);
+
+impl<M> MetadataCache<M>
+where
+    M: Clone,
+{
+    fn new(metadata: Option<M>) -> Self {
+        MetadataCache(Mutex::new(metadata))
+    }
+
+    fn into_inner(self) -> Option<M> {
+        self.0.into_inner().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn get(&self) -> Option<M> {
+        self.0
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone()
+    }
+
+    // Queries metadata if it has not yet been read. Errors are not cached.
+    fn get_or_query(&self, f: impl FnOnce() -> io::Result<M>) -> io::Result<M> {
+        let mut metadata = self.0.lock().unwrap_or_else(PoisonError::into_inner);
+        match metadata.as_ref() {
+            Some(metadata) => Ok(metadata.clone()),
+            None => {
+                let queried = f()?;
+                *metadata = Some(queried.clone());
+                Ok(queried)
+            },
+        }
+    }
+}
+
+impl<M> Clone for MetadataCache<M>
+where
+    M: Clone,
+{
+    fn clone(&self) -> Self {
+        MetadataCache::new(self.get())
+    }
+}
+
+// Entry in a directory that is being sorted.
+struct Sibling<S>
+where
+    S: Filesystem,
+{
+    path: PathBuf,
+    file_type: S::FileType,
+    metadata: MetadataCache<S::Metadata>,
+}
+
+// View of a `Sibling` that queries metadata from its file system.
+struct SiblingView<'s, S>
+where
+    S: Filesystem,
+{
+    filesystem: &'s S,
+    sibling: &'s Sibling<S>,
+}
+
+impl<'s, S> SiblingView<'s, S>
+where
+    S: Filesystem,
+{
+    fn metadata(&self) -> Option<S::Metadata> {
+        // Like the entries read from a directory, the metadata of a sibling
+        // describes symbolic links rather than their targets.
+        self.sibling
+            .metadata
+            .get_or_query(|| self.filesystem.symlink_metadata(&self.sibling.path))
+            .ok()
+    }
+}
+
+impl<'s, S> sort::Sibling for SiblingView<'s, S>
+where
+    S: Filesystem,
+{
+    fn path(&self) -> &Path {
+        &self.sibling.path
+    }
+
+    fn is_dir(&self) -> bool {
+        self.sibling.file_type.is_dir()
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.sibling.file_type.is_symlink()
+    }
+
+    fn size(&self) -> Option<u64> {
+        self.metadata().and_then(|metadata| metadata.size())
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.metadata().and_then(|metadata| metadata.modified())
+    }
+}
+
+type DirectoryEntry<S> = (
+    PathBuf,
+    <S as Filesystem>::FileType,
+    Option<<S as Filesystem>::Metadata>,
+);
+
+// Entries in a directory in the order in which they are read or sorted.
+enum Entries<S>
+where
+    S: Filesystem,
+{
+    Read(S::ReadDir),
+    // Paths recorded by a previous traversal of an unchanged directory. The
+    // file type and metadata of each path are queried as it is read.
+    Recorded(S, vec::IntoIter<PathBuf>),
+    Sorted(vec::IntoIter<io::Result<DirectoryEntry<S>>>),
+}
+
+impl<S> Entries<S>
+where
+    S: Filesystem,
+{
+    // Reads and sorts all entries in a directory. Errors are yielded before any
+    // entries.
+    fn sorted(
+        filesystem: &S,
+        entries: impl Iterator<Item = io::Result<DirectoryEntry<S>>>,
+        sort: &Sort,
+    ) -> Self {
+        let mut errors = vec![];
+        let mut siblings = vec![];
+        for entry in entries {
+            match entry {
+                Ok((path, file_type, metadata)) => siblings.push(Sibling::<S> {
+                    path,
+                    file_type,
+                    metadata: MetadataCache::new(metadata),
+                }),
+                Err(error) => errors.push(Err(error)),
+            }
+        }
+        siblings.sort_by(|left, right| {
+            let left = SiblingView {
+                filesystem,
+                sibling: left,
+            };
+            let right = SiblingView {
+                filesystem,
+                sibling: right,
+            };
+            sort.compare(&SortEntry::new(&left), &SortEntry::new(&right))
+        });
+        errors.extend(siblings.into_iter().map(|sibling| {
+            Ok((
+                sibling.path,
+                sibling.file_type,
+                sibling.metadata.into_inner(),
+            ))
+        }));
+        Entries::Sorted(errors.into_iter())
+    }
+}
+
+impl<S> Iterator for Entries<S>
+where
+    S: Filesystem,
+{
+    type Item = io::Result<DirectoryEntry<S>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            Entries::Read(entries) => entries.next(),
+            Entries::Recorded(filesystem, paths) => loop {
+                let path = paths.next()?;
+                match filesystem.symlink_metadata(&path) {
+                    Ok(metadata) => return Some(Ok((path, metadata.file_type(), Some(metadata)))),
+                    // The file has been removed since the directory was
+                    // queried, which is detected by the next traversal.
+                    Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                    Err(error) => return Some(Err(error)),
+                }
+            },
+            Entries::Sorted(entries) => entries.next(),
+        }
+    }
+}
+
+struct Directory<S>
+where
+    S: Filesystem,
+{
+    path: PathBuf,
+    // The canonical path is only queried to detect cycles when following
+    // symbolic links.
+    canonical: Option<PathBuf>,
+    entries: Entries<S>,
+    // The stamp of the directory when it is read. This is only queried when
+    // recording changes and is discarded if an error occurs.
+    stamp: Option<Stamp>,
+}
+
+/// Depth-first traversal of a directory tree in a [`Filesystem`].
+///
+/// The root of the tree is yielded first. Directories are read after they are
+/// yielded unless `skip_tree_with` is called before the next item is requested.
+pub struct Traversal<S>
+where
+    S: Filesystem,
+{
+    filesystem: S,
+    root: Option<PathBuf>,
+    link: LinkBehavior,
+    depth: usize,
+    directories: Vec<Directory<S>>,
+    pending: Option<PathBuf>,
+    progress: Option<Progress>,
+    prune: Option<PruneCallback>,
+    sort: Option<Sort>,
+    retry: Option<RetryPolicy>,
+    boundary: Option<Boundary>,
+    changes: Option<Changes>,
+}
+
+impl<S> Traversal<S>
+where
+    S: Filesystem,
+{
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        filesystem: S,
+        root: PathBuf,
+        link: LinkBehavior,
+        depth: usize,
+        progress: Option<ProgressCallback>,
+        prune: Option<PruneCallback>,
+        sort: Option<Sort>,
+        retry: Option<RetryPolicy>,
+        boundary: Option<Boundary>,
+    ) -> Self {
+        Traversal {
+            filesystem,
+            root: Some(root),
+            link,
+            depth,
+            directories: vec![],
+            pending: None,
+            progress: progress.map(Progress::new),
+            prune,
+            sort,
+            retry,
+            boundary,
+            changes: None,
+        }
+    }
+
+    // Records changes to the directory tree. Directories that are unchanged
+    // since the previous traversal are not read.
+    pub fn set_changes(&mut self, changes: Changes) {
+        self.changes = Some(changes);
+    }
+
+    pub fn changes_mut(&mut self) -> Option<&mut Changes> {
+        self.changes.as_mut()
+    }
+
+    pub fn take_changes(&mut self) -> Option<Changes> {
+        self.changes.take()
+    }
+
+    pub fn skip_tree_with(&mut self, cause: PruneCause) {
+        if let Some(path) = self.pending.take() {
+            #[cfg(feature = "tracing")]
+            tracing::trace!(path = ?path, cause = ?cause, "skipping directory");
+            if let Some(prune) = self.prune.as_ref() {
+                // Pending directories are read from the most recently entered
+                // directory, so their depth is the number of open directories.
+                prune.report(&path, self.directories.len(), cause);
+            }
+        }
+    }
+
+    pub fn is_following_links(&self) -> bool {
+        matches!(self.link, LinkBehavior::ReadTarget)
+    }
+
+    fn entry(
+        &mut self,
+        path: PathBuf,
+        file_type: S::FileType,
+        metadata: Option<S::Metadata>,
+        depth: usize,
+    ) -> Entry<S> {
+        if file_type.is_dir() {
+            if let Some(changes) = self.changes.as_mut() {
+                changes.insert_directory(&path);
+            }
+            if depth < self.depth {
+                self.pending = Some(path.clone());
+            }
+            else if let Some(prune) = self.prune.as_ref() {
+                prune.report(&path, depth, PruneCause::Depth);
+            }
+        }
+        Entry {
+            filesystem: self.filesystem.clone(),
+            path,
+            file_type,
+            metadata: MetadataCache::new(metadata),
+            depth,
+            is_followed: self.is_following_links(),
+        }
+    }
+
+    // Verifies that a path resolves beneath the boundary of a confined
+    // traversal, if any.
+    fn verify(&mut self, depth: usize, path: &Path) -> Result<(), WalkError> {
+        match self.boundary.as_mut() {
+            Some(boundary) => boundary.verify(&self.filesystem, self.retry.as_ref(), depth, path),
+            None => Ok(()),
+        }
+    }
+
+    // Gets the ancestor directory that is the same as the given directory, if
+    // any. Such an ancestor forms a cycle.
+    fn cycle(&mut self, path: &Path) -> Result<Option<PathBuf>, WalkError> {
+        let depth = self.directories.len();
+        let canonical =
+            retry(self.retry.as_ref(), || self.filesystem.canonicalize(path)).map_err(|error| {
+                WalkError::io(depth, WalkOperation::ResolveLink, Some(path.into()), error)
+            })?;
+        for directory in self.directories.iter_mut() {
+            if directory.canonical.is_none() {
+                directory.canonical = Some(
+                    retry(self.retry.as_ref(), || {
+                        self.filesystem.canonicalize(&directory.path)
+                    })
+                    .map_err(|error| {
+                        // Report the ancestor that failed to resolve rather
+                        // than the link being checked.
+                        WalkError::io(
+                            depth,
+                            WalkOperation::ResolveLink,
+                            Some(directory.path.clone()),
+                            error,
+                        )
+                    })?,
+                );
+            }
+            if directory.canonical.as_ref() == Some(&canonical) {
+                return Ok(Some(directory.path.clone()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl<S> Debug for Traversal<S>
+where
+    S: Filesystem,
+{
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        formatter
+            .debug_struct("Traversal")
+            .field("filesystem", &self.filesystem)
+            .field("link", &self.link)
+            .field("depth", &self.depth)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<S> Iterator for Traversal<S>
+where
+    S: Filesystem,
+{
+    type Item = Result<Entry<S>, WalkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let item = self.read();
+        #[cfg(feature = "tracing")]
+        if let Some(Err(error)) = item.as_ref() {
+            tracing::debug!(error = %error, "failed to read directory tree");
+        }
+        if let Some(progress) = self.progress.as_mut() {
+            match item.as_ref() {
+                Some(Ok(entry)) => progress.entry(
+                    self.directories
+                        .last()
+                        .map_or_else(|| entry.path(), |directory| directory.path.as_path()),
+                ),
+                Some(Err(_)) => progress.error(),
+                None => {},
+            }
+        }
+        item
+    }
+}
+
+impl<S> Traversal<S>
+where
+    S: Filesystem,
+{
+    fn read(&mut self) -> Option<Result<Entry<S>, WalkError>> {
+        if let Some(root) = self.root.take() {
+            if let Err(error) = self.verify(0, &root) {
+                return Some(Err(error));
+            }
+            // The root is always followed if it is a symbolic link. Its
+            // metadata is only retained if the entry is also followed.
+            return Some(
+                match retry(self.retry.as_ref(), || self.filesystem.metadata(&root)) {
+                    Ok(metadata) => {
+                        let file_type = metadata.file_type();
+                        let metadata = Some(metadata).filter(|_| self.is_following_links());
+                        Ok(self.entry(root, file_type, metadata, 0))
+                    },
+                    Err(error) => Err(WalkError::io(
+                        0,
+                        WalkOperation::ReadMetadata,
+                        Some(root),
+                        error,
+                    )),
+                },
+            );
+        }
+        if let Some(path) = self.pending.take() {
+            // Directories are verified before they are read, because they may
+            // have been replaced by symbolic links since they were yielded.
+            // Note that a directory may still be replaced between this
+            // verification and reading it. Only confining file systems, which
+            // skip this verification, prevent that.
+            if self
+                .boundary
+                .as_ref()
+                .map_or(false, Boundary::is_verifying_directories)
+            {
+                let depth = self.directories.len();
+                if let Err(error) = self.verify(depth, &path) {
+                    return Some(Err(error));
+                }
+            }
+            // When recording changes, the directory is queried before it is
+            // read, so that any changes while it is read are detected by the
+            // next traversal.
+            let stamp = self.changes.as_ref().and_then(|_| {
+                retry(self.retry.as_ref(), || self.filesystem.metadata(&path))
+                    .ok()
+                    .map(|metadata| Stamp::from_metadata(&metadata))
+            });
+            let listing = self
+                .changes
+                .as_ref()
+                .and_then(|changes| changes.listing(&path, stamp.as_ref()));
+            let entries = match listing {
+                Some(paths) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(path = ?path, "reading unchanged directory");
+                    Ok(Entries::Recorded(
+                        self.filesystem.clone(),
+                        paths.into_iter(),
+                    ))
+                },
+                None => retry(self.retry.as_ref(), || self.filesystem.read_dir(&path))
+                    .map(Entries::Read),
+            };
+            match entries {
+                Ok(entries) => {
+                    #[cfg(feature = "tracing")]
+                    tracing::trace!(
+                        path = ?path,
+                        depth = self.directories.len(),
+                        "entering directory",
+                    );
+                    if let Some(progress) = self.progress.as_mut() {
+                        progress.enter(&path);
+                    }
+                    let entries = match self.sort.as_ref() {
+                        Some(sort) => Entries::sorted(&self.filesystem, entries, sort),
+                        None => entries,
+                    };
+                    self.directories.push(Directory {
+                        path,
+                        canonical: None,
+                        entries,
+                        stamp,
+                    });
+                },
+                Err(error) => {
+                    if let Some(changes) = self.changes.as_mut() {
+                        changes.fail_directory(&path);
+                    }
+                    let depth = self.directories.len();
+                    return Some(Err(WalkError::io(
+                        depth,
+                        WalkOperation::ReadDirectory,
+                        Some(path),
+                        error,
+                    )));
+                },
+            }
+        }
+        loop {
+            let depth = self.directories.len();
+            let directory = self.directories.last_mut()?;
+            let (path, file_type, metadata) = match directory.entries.next() {
+                Some(Ok(entry)) => entry,
+                Some(Err(error)) => {
+                    if let Some(changes) = self.changes.as_mut() {
+                        changes.fail_directory(&directory.path);
+                    }
+                    directory.stamp = None;
+                    return Some(Err(WalkError::io(
+                        depth - 1,
+                        WalkOperation::ReadDirectory,
+                        Some(directory.path.clone()),
+                        error,
+                    )));
+                },
+                None => {
+                    let directory = self.directories.pop().expect("no directory in traversal");
+                    if let (Some(changes), Some(stamp)) = (self.changes.as_mut(), directory.stamp) {
+                        changes.complete_directory(&directory.path, stamp);
+                    }
+                    continue;
+                },
+            };
+            if file_type.is_symlink() {
+                if let Err(error) = self.verify(depth, &path) {
+                    return Some(Err(error));
+                }
+            }
+            if !(self.is_following_links() && file_type.is_symlink()) {
+                return Some(Ok(self.entry(path, file_type, metadata, depth)));
+            }
+            // The metadata of a followed symbolic link describes its target.
+            let metadata = match retry(self.retry.as_ref(), || self.filesystem.metadata(&path)) {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    return Some(Err(WalkError::io(
+                        depth,
+                        WalkOperation::ResolveLink,
+                        Some(path),
+                        error,
+                    )));
+                },
+            };
+            let file_type = metadata.file_type();
+            if file_type.is_dir() {
+                match self.cycle(&path) {
+                    Ok(Some(root)) => {
+                        return Some(Err(WalkError::link_cycle(depth, root, path)));
+                    },
+                    Ok(None) => {},
+                    Err(error) => {
+                        return Some(Err(error));
+                    },
+                }
+            }
+            return Some(Ok(self.entry(path, file_type, Some(metadata), depth)));
+        }
+    }
+}
@@ -0,0 +1,192 @@
+#![cfg(unix)]
+
+use std::fs;
+use std::os::unix::fs::MetadataExt as _;
+
+use crate::walk::{FileIterator, PruneCause, StdFilesystem, TreeIterator, WalkItem};
+
+// Effective user and groups of the current process.
+#[derive(Clone, Debug)]
+struct Credentials {
+    uid: u32,
+    gids: Vec<u32>,
+}
+
+impl Credentials {
+    fn effective() -> Self {
+        // SAFETY: These functions are always successful and `getgroups` is
+        //         given a buffer with capacity for the number of groups that
+        //         it reports. Groups may change between these calls, in which
+        //         case `getgroups` fails and only the effective group is used.
+        unsafe {
+            let uid = libc::geteuid();
+            let mut gids = vec![libc::getegid()];
+            let n = libc::getgroups(0, std::ptr::null_mut());
+            if n > 0 {
+                let mut groups: Vec<libc::gid_t> = vec![0; n as usize];
+                let n = libc::getgroups(n, groups.as_mut_ptr());
+                if n > 0 {
+                    groups.truncate(n as usize);
+                    gids.extend(groups);
+                }
+            }
+            Credentials { uid, gids }
+        }
+    }
+
+    fn is_readable(&self, metadata: &fs::Metadata) -> bool {
+        let mode = metadata.mode();
+        if self.uid == 0 {
+            // The superuser can read any file.
+            true
+        }
+        else if metadata.uid() == self.uid {
+            mode & 0o400 != 0
+        }
+        else if self.gids.contains(&metadata.gid()) {
+            mode & 0o040 != 0
+        }
+        else {
+            mode & 0o004 != 0
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+enum Permission {
+    Readable(Credentials),
+    OwnedBy(u32),
+    ModeMask(u32),
+}
+
+impl Permission {
+    fn is_permitted(&self, metadata: &fs::Metadata) -> bool {
+        match self {
+            Permission::Readable(ref credentials) => credentials.is_readable(metadata),
+            Permission::OwnedBy(uid) => metadata.uid() == *uid,
+            Permission::ModeMask(mask) => metadata.mode() & mask != 0,
+        }
+    }
+}
+
+/// [`FileIterator`] extension that filters [`WalkEntry`]s by Unix permissions
+/// and ownership.
+///
+/// Filters are evaluated from the [metadata][`WalkEntry::metadata`] of
+/// entries, which describes the targets of symbolic links only if links are
+/// [followed][`LinkBehavior::ReadTarget`]. If the metadata of an entry cannot
+/// be queried, then an error is yielded in its place. Filters only discard
+/// files: directories that do not pass a filter are still read.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::{Glob, UnixFileIterator as _};
+///
+/// // Find executable scripts.
+/// let glob = Glob::new("bin/**/*.sh").unwrap();
+/// for entry in glob.walk(".").mode_mask(0o111) {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// ```
+///
+/// [`FileIterator`]: crate::FileIterator
+/// [`LinkBehavior::ReadTarget`]: crate::LinkBehavior::ReadTarget
+/// [`WalkEntry`]: crate::WalkEntry
+/// [`WalkEntry::metadata`]: crate::WalkEntry::metadata
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", unix))))]
+pub trait UnixFileIterator: FileIterator<StdFilesystem> {
+    /// Filters [`WalkEntry`]s that are readable by the effective user and
+    /// groups of the current process.
+    ///
+    /// Readability is determined by the mode of a file and the identity of
+    /// the process when this function is called. Access control lists and
+    /// other security mechanisms are not considered.
+    ///
+    /// [`WalkEntry`]: crate::WalkEntry
+    fn readable(self) -> FilterPermissions<Self> {
+        FilterPermissions {
+            input: self,
+            permission: Permission::Readable(Credentials::effective()),
+        }
+    }
+
+    /// Filters [`WalkEntry`]s that are owned by the given user ID.
+    ///
+    /// [`WalkEntry`]: crate::WalkEntry
+    fn owned_by(self, uid: u32) -> FilterPermissions<Self> {
+        FilterPermissions {
+            input: self,
+            permission: Permission::OwnedBy(uid),
+        }
+    }
+
+    /// Filters [`WalkEntry`]s with a mode that has **any** of the bits in the
+    /// given mask.
+    ///
+    /// For example, the mask `0o111` filters files that are executable by
+    /// their owner, group, or others.
+    ///
+    /// [`WalkEntry`]: crate::WalkEntry
+    fn mode_mask(self, mask: u32) -> FilterPermissions<Self> {
+        FilterPermissions {
+            input: self,
+            permission: Permission::ModeMask(mask),
+        }
+    }
+}
+
+impl<I> UnixFileIterator for I where I: FileIterator<StdFilesystem> {}
+
+/// Iterator adaptor that filters [`WalkEntry`]s by Unix permissions and
+/// ownership.
+///
+/// See [`UnixFileIterator`].
+///
+/// [`UnixFileIterator`]: crate::UnixFileIterator
+/// [`WalkEntry`]: crate::WalkEntry
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", unix))))]
+#[derive(Clone, Debug)]
+pub struct FilterPermissions<I> {
+    input: I,
+    permission: Permission,
+}
+
+impl<I> Iterator for FilterPermissions<I>
+where
+    I: TreeIterator<Item = WalkItem<'static>>,
+{
+    type Item = WalkItem<'static>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let entry = match self.input.next()? {
+                Ok(entry) => entry,
+                error => {
+                    return Some(error);
+                },
+            };
+            match entry.metadata() {
+                Ok(metadata) => {
+                    if self.permission.is_permitted(&metadata) {
+                        return Some(Ok(entry));
+                    }
+                },
+                Err(error) => {
+                    return Some(Err(error));
+                },
+            }
+        }
+    }
+}
+
+impl<I> TreeIterator for FilterPermissions<I>
+where
+    Self: Iterator,
+    I: TreeIterator,
+{
+    fn skip_tree_with(&mut self, cause: PruneCause) {
+        self.input.skip_tree_with(cause);
+    }
+}
@@ -0,0 +1,679 @@
+#![cfg(all(feature = "io-uring", target_os = "linux"))]
+
+use std::ffi::CString;
+use std::io;
+use std::mem::{self, MaybeUninit};
+use std::os::unix::io::RawFd;
+use std::ptr;
+use std::sync::atomic::{AtomicU32, Ordering};
+
+// The number of submission queue entries. Operations are submitted in batches
+// of at most this many entries.
+const QUEUE_DEPTH: u32 = 256;
+
+const IORING_ENTER_GETEVENTS: u32 = 1;
+const IORING_FEAT_SINGLE_MMAP: u32 = 1;
+const IORING_OFF_SQ_RING: libc::off_t = 0;
+const IORING_OFF_CQ_RING: libc::off_t = 0x800_0000;
+const IORING_OFF_SQES: libc::off_t = 0x1000_0000;
+const IORING_OP_OPENAT: u8 = 18;
+const IORING_OP_STATX: u8 = 21;
+const STATX_BASIC_STATS: u32 = 0x7ff;
+
+#[repr(C)]
+#[derive(Default)]
+struct SubmissionOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    flags: u32,
+    dropped: u32,
+    array: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+#[repr(C)]
+#[derive(Default)]
+struct CompletionOffsets {
+    head: u32,
+    tail: u32,
+    ring_mask: u32,
+    ring_entries: u32,
+    overflow: u32,
+    cqes: u32,
+    flags: u32,
+    resv1: u32,
+    user_addr: u64,
+}
+
+// `io_uring_params`.
+#[repr(C)]
+#[derive(Default)]
+struct Parameters {
+    sq_entries: u32,
+    cq_entries: u32,
+    flags: u32,
+    sq_thread_cpu: u32,
+    sq_thread_idle: u32,
+    features: u32,
+    wq_fd: u32,
+    resv: [u32; 3],
+    sq_off: SubmissionOffsets,
+    cq_off: CompletionOffsets,
+}
+
+// `io_uring_sqe`.
+#[repr(C)]
+#[derive(Default)]
+struct SubmissionEntry {
+    opcode: u8,
+    flags: u8,
+    ioprio: u16,
+    fd: i32,
+    off: u64,
+    addr: u64,
+    len: u32,
+    op_flags: u32,
+    user_data: u64,
+    buf_index: u16,
+    personality: u16,
+    splice_fd_in: i32,
+    addr3: u64,
+    pad: u64,
+}
+
+// `io_uring_cqe`.
+#[repr(C)]
+struct CompletionEntry {
+    user_data: u64,
+    res: i32,
+    flags: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct StatxTimestamp {
+    pub tv_sec: i64,
+    pub tv_nsec: u32,
+    reserved: i32,
+}
+
+// `statx`. Fields that are not used are reserved.
+#[repr(C)]
+#[derive(Clone, Copy)]
+pub struct Statx {
+    pub stx_mask: u32,
+    pub stx_blksize: u32,
+    pub stx_attributes: u64,
+    pub stx_nlink: u32,
+    pub stx_uid: u32,
+    pub stx_gid: u32,
+    pub stx_mode: u16,
+    reserved0: u16,
+    pub stx_ino: u64,
+    pub stx_size: u64,
+    pub stx_blocks: u64,
+    pub stx_attributes_mask: u64,
+    pub stx_atime: StatxTimestamp,
+    pub stx_btime: StatxTimestamp,
+    pub stx_ctime: StatxTimestamp,
+    pub stx_mtime: StatxTimestamp,
+    reserved1: [u64; 16],
+}
+
+/// Operation that is submitted to a [`Ring`].
+///
+/// Operations own the memory that is read and written by the kernel.
+pub enum Operation {
+    /// Queries the metadata of a file relative to a directory.
+    Statx {
+        parent: RawFd,
+        name: CString,
+        flags: i32,
+        statx: Box<MaybeUninit<Statx>>,
+    },
+    /// Opens a file relative to a directory.
+    Open {
+        parent: RawFd,
+        name: CString,
+        flags: i32,
+    },
+}
+
+impl Operation {
+    fn entry(&mut self, user_data: u64) -> SubmissionEntry {
+        match self {
+            Operation::Statx {
+                parent,
+                name,
+                flags,
+                statx,
+            } => SubmissionEntry {
+                opcode: IORING_OP_STATX,
+                fd: *parent,
+                off: statx.as_mut_ptr() as u64,
+                addr: name.as_ptr() as u64,
+                len: STATX_BASIC_STATS,
+                op_flags: *flags as u32,
+                user_data,
+                ..Default::default()
+            },
+            Operation::Open {
+                parent,
+                name,
+                flags,
+            } => SubmissionEntry {
+                opcode: IORING_OP_OPENAT,
+                fd: *parent,
+                addr: name.as_ptr() as u64,
+                op_flags: *flags as u32,
+                user_data,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+// Memory mapped by `io_uring_setup`, which is unmapped when dropped.
+struct Mapping {
+    address: *mut libc::c_void,
+    len: usize,
+}
+
+impl Mapping {
+    fn new(fd: RawFd, len: usize, offset: libc::off_t) -> io::Result<Self> {
+        // SAFETY: The address hint is null, so no existing mapping is
+        //         replaced. The mapping is shared with the kernel and is not
+        //         aliased by any other Rust allocation.
+        let address = unsafe {
+            libc::mmap(
+                ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_POPULATE,
+                fd,
+                offset,
+            )
+        };
+        if address == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        }
+        else {
+            Ok(Mapping { address, len })
+        }
+    }
+
+    // Gets a pointer to data at the given offset into the mapping.
+    fn at<T>(&self, offset: u32) -> *mut T {
+        // SAFETY: Offsets are given by the kernel and are within the mapping.
+        unsafe { self.address.cast::<u8>().add(offset as usize).cast() }
+    }
+}
+
+impl Drop for Mapping {
+    fn drop(&mut self) {
+        // SAFETY: The mapping was created by `mmap` with this length.
+        unsafe {
+            libc::munmap(self.address, self.len);
+        }
+    }
+}
+
+/// An `io_uring` instance that executes batches of [`Operation`]s.
+pub struct Ring {
+    fd: RawFd,
+    submissions: Mapping,
+    completions: Option<Mapping>,
+    entries: Mapping,
+    sq_off: SubmissionOffsets,
+    cq_off: CompletionOffsets,
+    sq_entries: u32,
+    // The ring cannot be used if an error occurs while operations are in
+    // flight, because their memory must not be reused.
+    is_poisoned: bool,
+}
+
+// SAFETY: The mappings are owned by the ring and are only accessed via
+//         `&mut self`.
+unsafe impl Send for Ring {}
+
+impl Ring {
+    pub fn new() -> io::Result<Self> {
+        let mut parameters = Parameters::default();
+        // SAFETY: `parameters` is a valid `io_uring_params`.
+        let fd = unsafe {
+            libc::syscall(
+                libc::SYS_io_uring_setup,
+                QUEUE_DEPTH,
+                &mut parameters as *mut Parameters,
+            )
+        };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let fd = fd as RawFd;
+        let close = |error| {
+            // SAFETY: The descriptor was opened above and is not used again.
+            unsafe {
+                libc::close(fd);
+            }
+            error
+        };
+        let sq_len = parameters.sq_off.array as usize + parameters.sq_entries as usize * 4;
+        let cq_len = parameters.cq_off.cqes as usize
+            + parameters.cq_entries as usize * mem::size_of::<CompletionEntry>();
+        let (submissions, completions) =
+            if parameters.features & IORING_FEAT_SINGLE_MMAP == IORING_FEAT_SINGLE_MMAP {
+                let len = sq_len.max(cq_len);
+                (
+                    Mapping::new(fd, len, IORING_OFF_SQ_RING).map_err(close)?,
+                    None,
+                )
+            }
+            else {
+                (
+                    Mapping::new(fd, sq_len, IORING_OFF_SQ_RING).map_err(close)?,
+                    Some(Mapping::new(fd, cq_len, IORING_OFF_CQ_RING).map_err(close)?),
+                )
+            };
+        let entries = Mapping::new(
+            fd,
+            parameters.sq_entries as usize * mem::size_of::<SubmissionEntry>(),
+            IORING_OFF_SQES,
+        )
+        .map_err(close)?;
+        Ok(Ring {
+            fd,
+            submissions,
+            completions,
+            entries,
+            sq_off: parameters.sq_off,
+            cq_off: parameters.cq_off,
+            sq_entries: parameters.sq_entries,
+            is_poisoned: false,
+        })
+    }
+
+    fn completions(&self) -> &Mapping {
+        self.completions.as_ref().unwrap_or(&self.submissions)
+    }
+
+    /// Executes operations and returns their results.
+    ///
+    /// The result of each operation is the (non-negative) return value or
+    /// (negated) error code of the corresponding system call. Operations are
+    /// returned in the order in which they are given.
+    pub fn execute(&mut self, mut operations: Vec<Operation>) -> io::Result<Vec<(Operation, i32)>> {
+        if self.is_poisoned {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                "io_uring instance is unusable",
+            ));
+        }
+        let mut results = vec![0; operations.len()];
+        let mut start = 0;
+        while start < operations.len() {
+            let end = operations.len().min(start + self.sq_entries as usize);
+            if let Err(error) = self.submit(&mut operations[start..end], start, &mut results) {
+                // Operations may still be in flight, so their memory is leaked
+                // rather than freed while the kernel may write to it.
+                self.is_poisoned = true;
+                mem::forget(operations);
+                return Err(error);
+            }
+            start = end;
+        }
+        Ok(operations.into_iter().zip(results).collect())
+    }
+
+    // Submits a batch of operations and waits for all of them to complete.
+    //
+    // The batch must fit in the submission ring, which is empty when this
+    // function is called and when it returns successfully.
+    fn submit(
+        &mut self,
+        operations: &mut [Operation],
+        offset: usize,
+        results: &mut [i32],
+    ) -> io::Result<()> {
+        debug_assert!(operations.len() <= self.sq_entries as usize);
+        let sq_mask = self.load(self.sq_off.ring_mask);
+        // SAFETY: The tail is an aligned `u32` in the submission mapping, which
+        //         outlives the reference. It is only written by this process,
+        //         but it is read by the kernel and so must be atomic.
+        let sq_tail = unsafe { &*self.submissions.at::<AtomicU32>(self.sq_off.tail) };
+        // SAFETY: The submission ring and entries have `sq_mask + 1` slots. All
+        //         previously submitted entries have been consumed by the kernel
+        //         and the batch fits in the ring, so no slot that is written
+        //         has yet to be read. The operations own the memory that their
+        //         entries refer to and are not dropped until they complete.
+        unsafe {
+            push(
+                sq_tail,
+                sq_mask,
+                self.entries.at::<SubmissionEntry>(0),
+                self.submissions.at::<u32>(self.sq_off.array),
+                operations,
+                offset,
+            );
+        }
+
+        let cq_mask = self.load_completion(self.cq_off.ring_mask);
+        // SAFETY: The head and tail are aligned `u32`s in the completion
+        //         mapping, which outlives the references. The head is written
+        //         by this process and the tail by the kernel, so both must be
+        //         atomic.
+        let (cq_head, cq_tail) = unsafe {
+            (
+                &*self.completions().at::<AtomicU32>(self.cq_off.head),
+                &*self.completions().at::<AtomicU32>(self.cq_off.tail),
+            )
+        };
+        let cqes = self.completions().at::<CompletionEntry>(self.cq_off.cqes);
+        let mut unsubmitted = operations.len() as u32;
+        let mut incomplete = operations.len();
+        while incomplete > 0 {
+            // SAFETY: The ring descriptor is open and no signal mask is given.
+            //         This call only reads entries that have been pushed above.
+            let n = unsafe {
+                libc::syscall(
+                    libc::SYS_io_uring_enter,
+                    self.fd,
+                    unsubmitted,
+                    1u32,
+                    IORING_ENTER_GETEVENTS,
+                    ptr::null::<libc::sigset_t>(),
+                    0usize,
+                )
+            };
+            if n < 0 {
+                let error = io::Error::last_os_error();
+                match error.raw_os_error() {
+                    Some(libc::EINTR) | Some(libc::EAGAIN) | Some(libc::EBUSY) => {},
+                    _ => return Err(error),
+                }
+            }
+            else {
+                // The kernel may consume fewer entries than are given, in
+                // which case the remaining entries are submitted again.
+                unsubmitted -= n as u32;
+            }
+            // The kernel may post fewer completions than are in flight, in
+            // which case the remaining completions are awaited again.
+            //
+            // SAFETY: The completion ring has `cq_mask + 1` slots. It is never
+            //         overrun, because it has at least as many slots as the
+            //         submission ring, and the kernel initializes the entries
+            //         between the head and tail before advancing the tail.
+            incomplete -= unsafe { reap(cq_head, cq_tail, cq_mask, cqes, results) };
+        }
+        Ok(())
+    }
+
+    fn load(&self, offset: u32) -> u32 {
+        // SAFETY: The offset is given by the kernel and is within the mapping.
+        unsafe { *self.submissions.at::<u32>(offset) }
+    }
+
+    fn load_completion(&self, offset: u32) -> u32 {
+        // SAFETY: The offset is given by the kernel and is within the mapping.
+        unsafe { *self.completions().at::<u32>(offset) }
+    }
+}
+
+impl Drop for Ring {
+    fn drop(&mut self) {
+        // SAFETY: The descriptor is open and owned by the ring. The mappings
+        //         remain valid after it is closed and are unmapped when the
+        //         fields are dropped.
+        unsafe {
+            libc::close(self.fd);
+        }
+    }
+}
+
+// Writes entries for operations to a submission ring and advances its tail.
+//
+// The `user_data` of each entry is the index of its operation plus `offset`.
+//
+// # Safety
+//
+// `entries` and `array` must be valid for writes of `mask + 1` elements and
+// `mask + 1` must be a power of two. The slots that follow the tail must not be
+// read until the tail is advanced and there must be a free slot for each
+// operation.
+unsafe fn push(
+    tail: &AtomicU32,
+    mask: u32,
+    entries: *mut SubmissionEntry,
+    array: *mut u32,
+    operations: &mut [Operation],
+    offset: usize,
+) {
+    let mut next = tail.load(Ordering::Acquire);
+    for (index, operation) in operations.iter_mut().enumerate() {
+        let slot = next & mask;
+        // SAFETY: `slot` is masked to the length of `entries` and `array` and
+        //         the slot is free per the contract of this function.
+        unsafe {
+            entries
+                .add(slot as usize)
+                .write(operation.entry((offset + index) as u64));
+            array.add(slot as usize).write(slot);
+        }
+        next = next.wrapping_add(1);
+    }
+    // Entries must be written before the tail is advanced past them.
+    tail.store(next, Ordering::Release);
+}
+
+// Reads the results of completions between the head and tail of a completion
+// ring and advances its head. Returns the number of completions that are read.
+//
+// The result of each completion is written to `results` at the index given by
+// its `user_data`.
+//
+// # Safety
+//
+// `entries` must be valid for reads of `mask + 1` elements and `mask + 1` must
+// be a power of two. The entries between the head and tail must be initialized
+// and must not be overrun before the head is advanced past them.
+unsafe fn reap(
+    head: &AtomicU32,
+    tail: &AtomicU32,
+    mask: u32,
+    entries: *const CompletionEntry,
+    results: &mut [i32],
+) -> usize {
+    let start = head.load(Ordering::Acquire);
+    // Entries must be read after the tail is advanced past them.
+    let end = tail.load(Ordering::Acquire);
+    let mut next = start;
+    while next != end {
+        // SAFETY: The index is masked to the length of `entries` and the entry
+        //         is initialized per the contract of this function.
+        let completion = unsafe { &*entries.add((next & mask) as usize) };
+        results[completion.user_data as usize] = completion.res;
+        next = next.wrapping_add(1);
+    }
+    // Entries must be read before the head is advanced past them, because the
+    // kernel may then overwrite them.
+    head.store(next, Ordering::Release);
+    end.wrapping_sub(start) as usize
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+    use std::fs::{self, File};
+    use std::mem::MaybeUninit;
+    use std::os::unix::io::AsRawFd as _;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    use crate::walk::uring::{
+        self, CompletionEntry, Operation, Ring, SubmissionEntry, QUEUE_DEPTH,
+    };
+
+    // The mask of the rings in these tests, which have eight slots.
+    const MASK: u32 = 7;
+
+    fn operations(n: usize) -> Vec<Operation> {
+        (0..n)
+            .map(|n| Operation::Open {
+                parent: libc::AT_FDCWD,
+                name: CString::new(n.to_string()).unwrap(),
+                flags: 0,
+            })
+            .collect()
+    }
+
+    fn completion(user_data: u64, res: i32) -> CompletionEntry {
+        CompletionEntry {
+            user_data,
+            res,
+            flags: 0,
+        }
+    }
+
+    #[test]
+    fn push_fills_and_wraps_submission_ring() {
+        let mut entries: Vec<SubmissionEntry> = (0..=MASK).map(|_| Default::default()).collect();
+        let mut array = vec![u32::MAX; entries.len()];
+        fn push(
+            tail: &AtomicU32,
+            entries: &mut [SubmissionEntry],
+            array: &mut [u32],
+            offset: usize,
+        ) {
+            // SAFETY: `entries` and `array` have `MASK + 1` elements and are
+            //         not read until this function returns.
+            unsafe {
+                uring::push(
+                    tail,
+                    MASK,
+                    entries.as_mut_ptr(),
+                    array.as_mut_ptr(),
+                    &mut operations(entries.len()),
+                    offset,
+                );
+            }
+        }
+
+        // Fill the ring from a slot other than the first, so that the slots
+        // wrap around to the start of the ring.
+        let tail = AtomicU32::new(5);
+        push(&tail, &mut entries, &mut array, 0);
+        assert_eq!(tail.load(Ordering::Relaxed), 13);
+        for n in 0..=MASK {
+            let slot = ((5 + n) & MASK) as usize;
+            assert_eq!(entries[slot].user_data, u64::from(n));
+            assert_eq!(array[slot], slot as u32);
+        }
+
+        // Fill the ring such that the tail wraps around.
+        let tail = AtomicU32::new(u32::MAX - 2);
+        push(&tail, &mut entries, &mut array, 16);
+        assert_eq!(tail.load(Ordering::Relaxed), 5);
+        for n in 0..=MASK {
+            let slot = ((u32::MAX - 2).wrapping_add(n) & MASK) as usize;
+            assert_eq!(entries[slot].user_data, 16 + u64::from(n));
+            assert_eq!(array[slot], slot as u32);
+        }
+    }
+
+    #[test]
+    fn reap_short_and_wrapped_completions() {
+        let mut entries: Vec<_> = (0..=MASK).map(|_| completion(0, 0)).collect();
+        let mut results = vec![i32::MIN; entries.len()];
+        fn reap(
+            head: &AtomicU32,
+            tail: &AtomicU32,
+            entries: &[CompletionEntry],
+            results: &mut [i32],
+        ) -> usize {
+            // SAFETY: `entries` has `MASK + 1` initialized elements.
+            unsafe { uring::reap(head, tail, MASK, entries.as_ptr(), results) }
+        }
+
+        // The head and tail wrap around while completions are read.
+        let head = AtomicU32::new(u32::MAX - 1);
+        let tail = AtomicU32::new(u32::MAX - 1);
+        assert_eq!(reap(&head, &tail, &entries, &mut results), 0);
+
+        // Post fewer completions than are in flight, out of order.
+        for (n, (user_data, res)) in [(2, -libc::ENOENT), (0, 3), (5, 4)].into_iter().enumerate() {
+            let slot = (u32::MAX - 1).wrapping_add(n as u32) & MASK;
+            entries[slot as usize] = completion(user_data, res);
+        }
+        tail.store(1, Ordering::Release);
+        assert_eq!(reap(&head, &tail, &entries, &mut results), 3);
+        assert_eq!(head.load(Ordering::Relaxed), 1);
+        assert_eq!(results[..3], [3, i32::MIN, -libc::ENOENT]);
+        assert_eq!(results[5], 4);
+        assert_eq!(reap(&head, &tail, &entries, &mut results), 0);
+
+        // Post the remaining completions.
+        for (n, user_data) in [1, 3, 4, 6, 7].into_iter().enumerate() {
+            entries[1 + n] = completion(user_data, user_data as i32 * 10);
+        }
+        tail.store(6, Ordering::Release);
+        assert_eq!(reap(&head, &tail, &entries, &mut results), 5);
+        assert_eq!(head.load(Ordering::Relaxed), 6);
+        assert_eq!(results, [3, 10, -libc::ENOENT, 30, 40, 4, 60, 70]);
+    }
+
+    #[test]
+    fn execute_operations_across_batches() {
+        let mut ring = match Ring::new() {
+            Ok(ring) => ring,
+            // `io_uring` may be unavailable or disabled, such as in some
+            // containers.
+            Err(_) => return,
+        };
+        let root = tempfile::tempdir().unwrap();
+        // Create more files than fit in the ring, such that batches wrap
+        // around the ring. Every fifth file is missing.
+        let n = QUEUE_DEPTH as usize * 2 + 7;
+        for n in (0..n).filter(|n| n % 5 != 0) {
+            fs::write(root.path().join(n.to_string()), vec![0; n]).unwrap();
+        }
+        let directory = File::open(root.path()).unwrap();
+        let statx = |names: &mut dyn Iterator<Item = usize>| -> Vec<_> {
+            names
+                .map(|n| Operation::Statx {
+                    parent: directory.as_raw_fd(),
+                    name: CString::new(n.to_string()).unwrap(),
+                    flags: libc::AT_SYMLINK_NOFOLLOW,
+                    statx: Box::new(MaybeUninit::uninit()),
+                })
+                .collect()
+        };
+
+        // Execute twice, so that the second execution begins with a partially
+        // used ring.
+        for operations in [statx(&mut (0..n)), statx(&mut (0..n).rev())] {
+            let results = ring.execute(operations).unwrap();
+            assert_eq!(results.len(), n);
+            for (operation, result) in results {
+                match operation {
+                    Operation::Statx { name, statx, .. } => {
+                        let n: usize = name.to_str().unwrap().parse().unwrap();
+                        if n % 5 == 0 {
+                            assert_eq!(result, -libc::ENOENT);
+                        }
+                        else {
+                            assert_eq!(result, 0);
+                            // SAFETY: `statx` is initialized if the operation
+                            //         succeeds.
+                            let statx = unsafe { statx.assume_init_ref() };
+                            assert_eq!(statx.stx_size, n as u64);
+                        }
+                    },
+                    _ => unreachable!(),
+                }
+            }
+        }
+    }
+}
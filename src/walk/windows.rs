@@ -0,0 +1,391 @@
+#![cfg(windows)]
+
+use std::ffi::{c_void, OsString};
+use std::fs;
+use std::io;
+use std::iter;
+use std::mem::MaybeUninit;
+use std::os::windows::ffi::{OsStrExt as _, OsStringExt as _};
+use std::os::windows::fs::MetadataExt as _;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::walk::{FileType, Filesystem, Metadata};
+
+const ERROR_FILE_NOT_FOUND: i32 = 2;
+const ERROR_NO_MORE_FILES: i32 = 18;
+const FILE_ATTRIBUTE_DIRECTORY: u32 = 0x10;
+const FILE_ATTRIBUTE_REPARSE_POINT: u32 = 0x400;
+const FIND_EX_INFO_BASIC: i32 = 1;
+const FIND_EX_SEARCH_NAME_MATCH: i32 = 0;
+const FIND_FIRST_EX_LARGE_FETCH: u32 = 2;
+const INVALID_HANDLE_VALUE: isize = -1;
+// Reparse tags with this bit set are name surrogates, such as symbolic links
+// and junctions. This is consistent with `std::fs::FileType::is_symlink`.
+const IO_REPARSE_TAG_NAME_SURROGATE: u32 = 0x2000_0000;
+// The number of 100ns intervals between 1601-01-01 and the Unix epoch.
+const UNIX_EPOCH_INTERVALS: u64 = 116_444_736_000_000_000;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FileTime {
+    low: u32,
+    high: u32,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct FindData {
+    file_attributes: u32,
+    creation_time: FileTime,
+    last_access_time: FileTime,
+    last_write_time: FileTime,
+    file_size_high: u32,
+    file_size_low: u32,
+    reserved0: u32,
+    reserved1: u32,
+    file_name: [u16; 260],
+    alternate_file_name: [u16; 14],
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn FindFirstFileExW(
+        file_name: *const u16,
+        info_level: i32,
+        find_data: *mut c_void,
+        search_op: i32,
+        search_filter: *mut c_void,
+        additional_flags: u32,
+    ) -> isize;
+    fn FindNextFileW(find_file: isize, find_data: *mut FindData) -> i32;
+    fn FindClose(find_file: isize) -> i32;
+}
+
+/// Type of a file in a [`WindowsFilesystem`].
+///
+/// [`WindowsFilesystem`]: crate::WindowsFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", windows))))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct WindowsFileType {
+    attributes: u32,
+    reparse_tag: u32,
+}
+
+impl WindowsFileType {
+    fn is_reparse_surrogate(&self) -> bool {
+        self.attributes & FILE_ATTRIBUTE_REPARSE_POINT != 0
+            && self.reparse_tag & IO_REPARSE_TAG_NAME_SURROGATE != 0
+    }
+}
+
+impl FileType for WindowsFileType {
+    fn is_dir(&self) -> bool {
+        !self.is_reparse_surrogate() && self.attributes & FILE_ATTRIBUTE_DIRECTORY != 0
+    }
+
+    fn is_symlink(&self) -> bool {
+        self.is_reparse_surrogate()
+    }
+}
+
+/// Metadata of a file in a [`WindowsFilesystem`].
+///
+/// [`WindowsFilesystem`]: crate::WindowsFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", windows))))]
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct WindowsMetadata {
+    file_type: WindowsFileType,
+    size: u64,
+    modified: Option<SystemTime>,
+}
+
+impl WindowsMetadata {
+    fn from_find_data(data: &FindData) -> Self {
+        WindowsMetadata {
+            file_type: WindowsFileType {
+                attributes: data.file_attributes,
+                reparse_tag: data.reserved0,
+            },
+            size: (u64::from(data.file_size_high) << 32) | u64::from(data.file_size_low),
+            modified: system_time(
+                (u64::from(data.last_write_time.high) << 32)
+                    | u64::from(data.last_write_time.low),
+            ),
+        }
+    }
+
+    fn from_std(metadata: fs::Metadata) -> Self {
+        // The reparse tag is not exposed by `std`, so it is derived from the
+        // file type.
+        let reparse_tag = if metadata.file_type().is_symlink() {
+            IO_REPARSE_TAG_NAME_SURROGATE
+        }
+        else {
+            0
+        };
+        WindowsMetadata {
+            file_type: WindowsFileType {
+                attributes: metadata.file_attributes(),
+                reparse_tag,
+            },
+            size: metadata.file_size(),
+            modified: system_time(metadata.last_write_time()),
+        }
+    }
+
+    /// Gets the attributes of the file, such as `FILE_ATTRIBUTE_HIDDEN`.
+    pub fn attributes(&self) -> u32 {
+        self.file_type.attributes
+    }
+}
+
+impl Metadata for WindowsMetadata {
+    type FileType = WindowsFileType;
+
+    fn file_type(&self) -> Self::FileType {
+        self.file_type
+    }
+
+    fn size(&self) -> Option<u64> {
+        Some(self.size)
+    }
+
+    fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+}
+
+/// [`Filesystem`] that reads directories via `FindFirstFileExW` on Windows.
+///
+/// `WindowsFilesystem` reads directories with a large buffer and without
+/// querying the short (8.3) names of files, which reduces the overhead of each
+/// entry when reading large directory trees. The attributes, size, and
+/// modification time of each entry are read along with the directory, so
+/// [metadata][`WalkEntry::metadata`] and file type filters never query files
+/// that have been read from a directory.
+///
+/// # Examples
+///
+/// ```rust,no_run
+/// use wax::{Glob, WalkBehavior, WindowsFilesystem};
+///
+/// let glob = Glob::new("**/*.{dll,exe}").unwrap();
+/// for entry in glob.walk_with_filesystem(WindowsFilesystem, "C:/Tools", WalkBehavior::default()) {
+///     let entry = entry.unwrap();
+///     println!("{:?}", entry.path());
+/// }
+/// ```
+///
+/// [`Filesystem`]: crate::Filesystem
+/// [`WalkEntry::metadata`]: crate::WalkEntry::metadata
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", windows))))]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct WindowsFilesystem;
+
+impl Filesystem for WindowsFilesystem {
+    type FileType = WindowsFileType;
+    type Metadata = WindowsMetadata;
+    type ReadDir = FindEntries;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+        FindEntries::new(path)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        fs::metadata(path).map(WindowsMetadata::from_std)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        fs::symlink_metadata(path).map(WindowsMetadata::from_std)
+    }
+
+    fn canonicalize(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::canonicalize(path)
+    }
+}
+
+/// Iterator over the entries in a directory of a [`WindowsFilesystem`].
+///
+/// [`WindowsFilesystem`]: crate::WindowsFilesystem
+#[cfg_attr(docsrs, doc(cfg(all(feature = "walk", windows))))]
+pub struct FindEntries {
+    path: PathBuf,
+    handle: isize,
+    data: Box<FindData>,
+    // Whether or not `data` has been read but not yet yielded. This is the
+    // case for the first entry, which is read when the directory is opened.
+    is_pending: bool,
+}
+
+impl FindEntries {
+    fn new(path: &Path) -> io::Result<Self> {
+        let pattern: Vec<u16> = path
+            .join("*")
+            .as_os_str()
+            .encode_wide()
+            .chain(iter::once(0))
+            .collect();
+        let mut data = Box::new(MaybeUninit::<FindData>::uninit());
+        // SAFETY: `pattern` is a nul-terminated wide string and `data` is
+        //         correctly sized storage for `WIN32_FIND_DATAW`, which is
+        //         initialized if the returned handle is valid.
+        let handle = unsafe {
+            FindFirstFileExW(
+                pattern.as_ptr(),
+                FIND_EX_INFO_BASIC,
+                data.as_mut_ptr().cast(),
+                FIND_EX_SEARCH_NAME_MATCH,
+                std::ptr::null_mut(),
+                FIND_FIRST_EX_LARGE_FETCH,
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            let error = io::Error::last_os_error();
+            return if error.raw_os_error() == Some(ERROR_FILE_NOT_FOUND) && path.is_dir() {
+                // The directory has no entries at all.
+                Ok(FindEntries {
+                    path: path.to_path_buf(),
+                    handle,
+                    // SAFETY: `FindData` consists only of integers, so zeroed
+                    //         memory is a valid instance.
+                    data: Box::new(unsafe { MaybeUninit::zeroed().assume_init() }),
+                    is_pending: false,
+                })
+            }
+            else {
+                Err(error)
+            };
+        }
+        Ok(FindEntries {
+            path: path.to_path_buf(),
+            handle,
+            // SAFETY: The handle is valid, so `data` has been initialized.
+            data: unsafe { Box::from_raw(Box::into_raw(data).cast::<FindData>()) },
+            is_pending: true,
+        })
+    }
+
+    fn close(&mut self) {
+        if self.handle != INVALID_HANDLE_VALUE {
+            // SAFETY: The handle is valid and is never used after it is
+            //         closed.
+            unsafe {
+                FindClose(self.handle);
+            }
+            self.handle = INVALID_HANDLE_VALUE;
+        }
+    }
+}
+
+impl std::fmt::Debug for FindEntries {
+    fn fmt(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter
+            .debug_struct("FindEntries")
+            .field("path", &self.path)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for FindEntries {
+    fn drop(&mut self) {
+        self.close();
+    }
+}
+
+impl Iterator for FindEntries {
+    type Item = io::Result<(PathBuf, WindowsFileType, Option<WindowsMetadata>)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.handle == INVALID_HANDLE_VALUE {
+                return None;
+            }
+            if !self.is_pending {
+                // SAFETY: The handle is valid and `data` is initialized
+                //         storage for `WIN32_FIND_DATAW`.
+                if unsafe { FindNextFileW(self.handle, &mut *self.data) } == 0 {
+                    let error = io::Error::last_os_error();
+                    self.close();
+                    return if error.raw_os_error() == Some(ERROR_NO_MORE_FILES) {
+                        None
+                    }
+                    else {
+                        Some(Err(error))
+                    };
+                }
+            }
+            self.is_pending = false;
+            let name = &self.data.file_name;
+            let name = &name[..name.iter().position(|&x| x == 0).unwrap_or(name.len())];
+            // Skip the current and parent directories.
+            if name == [0x2E] || name == [0x2E, 0x2E] {
+                continue;
+            }
+            let metadata = WindowsMetadata::from_find_data(&self.data);
+            let path = self.path.join(OsString::from_wide(name));
+            return Some(Ok((path, metadata.file_type(), Some(metadata))));
+        }
+    }
+}
+
+// Converts a `FILETIME` into a `SystemTime`.
+fn system_time(intervals: u64) -> Option<SystemTime> {
+    if intervals >= UNIX_EPOCH_INTERVALS {
+        let n = intervals - UNIX_EPOCH_INTERVALS;
+        SystemTime::UNIX_EPOCH.checked_add(Duration::new(
+            n / 10_000_000,
+            ((n % 10_000_000) * 100) as u32,
+        ))
+    }
+    else {
+        let n = UNIX_EPOCH_INTERVALS - intervals;
+        SystemTime::UNIX_EPOCH.checked_sub(Duration::new(
+            n / 10_000_000,
+            ((n % 10_000_000) * 100) as u32,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::path::PathBuf;
+
+    use crate::walk::{FileType as _, Metadata as _};
+    use crate::{Glob, WalkBehavior, WindowsFilesystem};
+
+    #[test]
+    fn walk_windows_filesystem() {
+        let root = tempfile::tempdir().unwrap();
+        fs::create_dir_all(root.path().join("src/walk")).unwrap();
+        fs::create_dir(root.path().join("doc")).unwrap();
+        for path in ["src/lib.rs", "src/walk/mod.rs", "doc/guide.md"] {
+            fs::write(root.path().join(path), b"wax").unwrap();
+        }
+
+        let glob = Glob::new("src/**/*.rs").unwrap();
+        let entries: Vec<_> = glob
+            .walk_with_filesystem(WindowsFilesystem, root.path(), WalkBehavior::default())
+            .map(Result::unwrap)
+            .collect();
+        for entry in entries.iter() {
+            let metadata = entry.metadata().unwrap();
+            assert!(!metadata.file_type().is_dir());
+            assert_eq!(metadata.size(), Some(3));
+            assert!(metadata.modified().is_some());
+        }
+        let paths: HashSet<_> = entries
+            .into_iter()
+            .map(|entry| entry.into_path().strip_prefix(root.path()).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(
+            paths,
+            ["src/lib.rs", "src/walk/mod.rs"]
+                .into_iter()
+                .map(PathBuf::from)
+                .collect(),
+        );
+    }
+}
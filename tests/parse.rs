@@ -1,7 +1,11 @@
-use std::path::PathBuf;
+#[cfg(not(feature = "walk"))]
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use build_fs_tree::{FileSystemTree, dir};
 
+#[cfg(not(feature = "walk"))]
+use wax::Pattern as _;
 use wax::{Glob};
 
 use build_fs_tree::{file, Build};
@@ -87,13 +91,43 @@ fn test(expression: &str, expected: &[&str], path: &PathBuf){
     println!("Captures: {:?}", glob.captures().collect::<Vec<_>>());
     println!("Glob: {:#?}", glob);
 
-    let names: Vec<String> = glob.walk(path).filter_map(|e| {
-        Some(e.unwrap().path().file_name().unwrap().to_string_lossy().to_string())
-    }).collect();
+    let names = matched_file_names(&glob, path);
 
     assert_eq!(expected, names);
 }
 
+/// Gets the file names of the paths in a directory tree that match a glob.
+#[cfg(feature = "walk")]
+fn matched_file_names(glob: &Glob, path: &Path) -> Vec<String> {
+    glob.walk(path).filter_map(|e| {
+        Some(e.unwrap().path().file_name().unwrap().to_string_lossy().to_string())
+    }).collect()
+}
+
+/// Gets the file names of the paths in a directory tree that match a glob.
+///
+/// Without the `walk` feature, the directory tree is read depth-first in the
+/// same order as `Glob::walk` and each path relative to the root is matched
+/// against the glob.
+#[cfg(not(feature = "walk"))]
+fn matched_file_names(glob: &Glob, path: &Path) -> Vec<String> {
+    fn read(glob: &Glob, root: &Path, directory: &Path, names: &mut Vec<String>) {
+        for entry in fs::read_dir(directory).unwrap() {
+            let path = entry.unwrap().path();
+            if glob.is_match(path.strip_prefix(root).unwrap()) {
+                names.push(path.file_name().unwrap().to_string_lossy().to_string());
+            }
+            if path.is_dir() {
+                read(glob, root, &path, names);
+            }
+        }
+    }
+
+    let mut names = vec![];
+    read(glob, path, path, &mut names);
+    names
+}
+
 // TODO: Rust's testing framework does not provide a mechanism for maintaining
 //       shared state. This means that tests that write to the file system must
 //       do so individually rather than writing before and after all tests have
@@ -1,11 +1,18 @@
 #![cfg(feature = "walk")]
 
 use build_fs_tree::{dir, file, Build, FileSystemTree};
-use std::collections::HashSet;
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::vec;
 use tempfile::{self, TempDir};
 
-use wax::{Glob, LinkBehavior, WalkBehavior};
+use wax::{
+    CaseSensitivity, Confinement, DirectoryOrder, FileIterator, FileType, Filesystem, Filter,
+    FilterTarget, Glob, LinkBehavior, Metadata, PruneCause, RetryPolicy, WalkBehavior,
+    WalkOperation,
+};
 
 // TODO: Rust's testing framework does not provide a mechanism for maintaining
 //       shared state. This means that tests that write to the file system must
@@ -56,6 +63,88 @@ fn temptree_with_cyclic_link() -> (TempDir, PathBuf) {
     (root, path)
 }
 
+/// In-memory file system that maps directories to the names of their files.
+#[derive(Clone, Debug)]
+struct MemoryFilesystem(Arc<HashMap<PathBuf, Vec<&'static str>>>);
+
+impl MemoryFilesystem {
+    fn new(directories: impl IntoIterator<Item = (&'static str, Vec<&'static str>)>) -> Self {
+        MemoryFilesystem(Arc::new(
+            directories
+                .into_iter()
+                .map(|(path, names)| (PathBuf::from(path), names))
+                .collect(),
+        ))
+    }
+
+    fn file_type(&self, path: &Path) -> io::Result<MemoryFileType> {
+        if self.0.contains_key(path) {
+            Ok(MemoryFileType::Directory)
+        }
+        else if self
+            .0
+            .iter()
+            .any(|(directory, names)| names.iter().any(|name| directory.join(name) == path))
+        {
+            Ok(MemoryFileType::File)
+        }
+        else {
+            Err(io::ErrorKind::NotFound.into())
+        }
+    }
+}
+
+impl Filesystem for MemoryFilesystem {
+    type FileType = MemoryFileType;
+    type Metadata = MemoryFileType;
+    type ReadDir = vec::IntoIter<io::Result<(PathBuf, MemoryFileType, Option<MemoryFileType>)>>;
+
+    fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+        let names = self.0.get(path).ok_or(io::ErrorKind::NotFound)?;
+        Ok(names
+            .iter()
+            .map(|name| {
+                let path = path.join(name);
+                self.file_type(&path)
+                    .map(|file_type| (path, file_type, Some(file_type)))
+            })
+            .collect::<Vec<_>>()
+            .into_iter())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        self.file_type(path)
+    }
+
+    fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+        self.file_type(path)
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum MemoryFileType {
+    Directory,
+    File,
+}
+
+impl FileType for MemoryFileType {
+    fn is_dir(&self) -> bool {
+        matches!(self, MemoryFileType::Directory)
+    }
+
+    fn is_symlink(&self) -> bool {
+        false
+    }
+}
+
+impl Metadata for MemoryFileType {
+    type FileType = Self;
+
+    fn file_type(&self) -> Self::FileType {
+        *self
+    }
+}
+
 #[test]
 fn walk_with_tree() {
     let (_root, path) = temptree();
@@ -168,6 +257,121 @@ fn walk_with_not() {
     );
 }
 
+#[test]
+fn walk_with_filter() {
+    let (_root, path) = temptree();
+
+    let filter = Filter::new(
+        [
+            Glob::new("**/*.rs").unwrap(),
+            Glob::new("src/**").unwrap(),
+        ],
+        [Glob::new("tests/**").unwrap(), Glob::new("**/glob.rs").unwrap()],
+    )
+    .unwrap();
+    // Collect paths into a `Vec` so that duplicates can be detected.
+    let mut paths: Vec<_> = filter
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    paths.sort_unstable();
+    assert_eq!(paths, vec![path.join("src"), path.join("src/lib.rs")]);
+}
+
+#[test]
+fn walk_with_not_filter() {
+    let (_root, path) = temptree();
+
+    // Only `src/glob.rs` is negated: the exclusion of the `Filter` overrides its
+    // inclusion of `src/lib.rs`.
+    let filter = Filter::new(
+        [Glob::new("src/*.rs").unwrap()],
+        [Glob::new("**/lib.rs").unwrap()],
+    )
+    .unwrap();
+    let paths: HashSet<_> = Glob::new("**/*.rs")
+        .unwrap()
+        .walk(&path)
+        .not([&filter])
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("src/lib.rs"), path.join("tests/walk.rs")]
+            .into_iter()
+            .collect(),
+    );
+}
+
+#[test]
+fn walk_with_exclusive_filter() {
+    let (_root, path) = temptree();
+
+    let filter = Filter::new([], [Glob::new("{src,tests}/**").unwrap()]).unwrap();
+    let paths: HashSet<_> = filter
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            #[allow(clippy::redundant_clone)]
+            path.to_path_buf(),
+            path.join("doc"),
+            path.join("doc/guide.md"),
+            path.join("README.md"),
+        ]
+        .into_iter()
+        .collect(),
+    );
+}
+
+#[test]
+fn walk_entry_into_path() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("src/*.rs").unwrap();
+    for entry in glob.walk(&path) {
+        let entry = entry.unwrap();
+        // Depth is relative to the root `src`, which is the invariant prefix.
+        assert_eq!(entry.depth(), 1);
+        assert!(entry.to_candidate_path().as_ref().starts_with("src/"));
+        // The path is moved out of the entry rather than copied.
+        let text = entry.path().as_os_str().to_str().unwrap().as_ptr();
+        let into = entry.into_path();
+        assert_eq!(into.as_os_str().to_str().unwrap().as_ptr(), text);
+    }
+}
+
+#[test]
+fn walk_entry_matched_in_path() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("src/{*}.rs").unwrap();
+    let mut stems = vec![];
+    for entry in glob.walk(&path) {
+        let entry = entry.unwrap();
+        let relative = entry.matched();
+        let native = entry.matched_in_path().unwrap();
+        assert_eq!(native.complete(), entry.path().to_str().unwrap());
+        assert_eq!(native.get(1), relative.get(1));
+
+        let (start, n) = native.span(1).unwrap();
+        let (relative_start, _) = relative.span(1).unwrap();
+        assert_eq!(
+            native.complete().len() - start,
+            relative.complete().len() - relative_start,
+        );
+        stems.push(native.complete()[start..][..n].to_owned());
+    }
+    stems.sort();
+    assert_eq!(stems, ["glob", "lib"]);
+}
+
 #[test]
 fn walk_with_depth() {
     let (_root, path) = temptree();
@@ -199,6 +403,182 @@ fn walk_with_depth() {
     );
 }
 
+#[test]
+fn walk_with_progress() {
+    let (_root, path) = temptree();
+
+    let reports = Arc::new(Mutex::new(vec![]));
+    let behavior = WalkBehavior::default().on_progress({
+        let reports = reports.clone();
+        move |progress| {
+            reports
+                .lock()
+                .unwrap()
+                .push((progress.directories(), progress.directory().to_path_buf()));
+        }
+    });
+    let glob = Glob::new("**/*.md").unwrap();
+    assert_eq!(glob.walk_with_behavior(&path, behavior).flatten().count(), 2);
+    let (counts, directories): (Vec<_>, HashSet<_>) =
+        reports.lock().unwrap().iter().cloned().unzip();
+    assert_eq!(counts, [1, 2, 3, 4]);
+    assert_eq!(
+        directories,
+        [
+            path.clone(),
+            path.join("doc"),
+            path.join("src"),
+            path.join("tests"),
+        ]
+        .into_iter()
+        .collect(),
+    );
+}
+
+#[test]
+fn walk_with_trailing_components() {
+    let (_root, path) = temptree();
+
+    // Trailing components follow a tree wildcard, which may match zero or more
+    // components.
+    let glob = Glob::new("**/src/*.rs").unwrap();
+    let paths: HashSet<_> = glob
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("src/glob.rs"), path.join("src/lib.rs")]
+            .into_iter()
+            .collect(),
+    );
+
+    let glob = Glob::new("src/**/lib.rs").unwrap();
+    let paths: HashSet<_> = glob
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(paths, [path.join("src/lib.rs")].into_iter().collect());
+}
+
+#[test]
+fn walk_without_component_boundaries() {
+    let (_root, path) = temptree();
+
+    let directories = Arc::new(Mutex::new(vec![]));
+    let behavior = WalkBehavior::default().on_progress({
+        let directories = directories.clone();
+        move |progress| {
+            directories
+                .lock()
+                .unwrap()
+                .push(progress.directory().to_path_buf());
+        }
+    });
+    // Directories that match the final component are not read, because paths
+    // beneath them cannot match the glob.
+    let glob = Glob::new("*").unwrap();
+    let paths: HashSet<_> = glob
+        .walk_with_behavior(&path, behavior)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            path.join("doc"),
+            path.join("README.md"),
+            path.join("src"),
+            path.join("tests"),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    assert_eq!(*directories.lock().unwrap(), [path]);
+}
+
+#[test]
+fn walk_with_separator_crossing_wildcard() {
+    let (_root, path) = temptree();
+
+    // Zero-or-more wildcards that cross separators match any number of
+    // components, so directories are not pruned by component.
+    let glob = Glob::new("(?s)*.rs").unwrap();
+    let paths: HashSet<_> = glob
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("src/glob.rs"), path.join("src/lib.rs"), path.join("tests/walk.rs")]
+            .into_iter()
+            .collect(),
+    );
+}
+
+#[test]
+fn walk_with_empty_alternative_branch() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("{src/,}*.{rs,md}").unwrap();
+    let paths: HashSet<_> = glob
+        .walk(&path)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("README.md"), path.join("src/glob.rs"), path.join("src/lib.rs")]
+            .into_iter()
+            .collect(),
+    );
+}
+
+#[test]
+fn walk_with_bounded_depth() {
+    let filesystem = MemoryFilesystem::new([
+        ("project", vec!["src"]),
+        ("project/src", vec!["lib.rs", "token"]),
+        ("project/src/token", vec!["mod.rs", "parse"]),
+        ("project/src/token/parse", vec!["mod.rs"]),
+    ]);
+
+    let directories = Arc::new(Mutex::new(HashSet::new()));
+    let behavior = WalkBehavior::default().on_progress({
+        let directories = directories.clone();
+        move |progress| {
+            directories
+                .lock()
+                .unwrap()
+                .insert(progress.directory().to_path_buf());
+        }
+    });
+    // Directories beneath the greatest depth of the glob are not read.
+    let glob = Glob::new("*/{*.rs,*/*.rs}").unwrap();
+    let paths: HashSet<_> = glob
+        .walk_with_filesystem(filesystem, "project", behavior)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        ["project/src/lib.rs", "project/src/token/mod.rs"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+    );
+    assert_eq!(
+        *directories.lock().unwrap(),
+        ["project", "project/src", "project/src/token"]
+            .into_iter()
+            .map(PathBuf::from)
+            .collect(),
+    );
+}
+
 #[test]
 #[cfg(any(unix, windows))]
 fn walk_with_cyclic_link_file() {
@@ -261,3 +641,813 @@ fn walk_with_cyclic_link_target() {
     paths.sort_unstable();
     assert_eq!(paths, expected);
 }
+
+#[test]
+fn walk_with_filesystem() {
+    let filesystem = MemoryFilesystem::new([
+        ("project", vec!["README.md", "doc", "src"]),
+        ("project/doc", vec!["guide.md"]),
+        ("project/src", vec!["lib.rs", "glob.rs"]),
+    ]);
+
+    let glob = Glob::new("**/*.{md,rs}").unwrap();
+    let paths: HashSet<_> = glob
+        .walk_with_filesystem(filesystem.clone(), "project", WalkBehavior::default())
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            "project/README.md",
+            "project/doc/guide.md",
+            "project/src/glob.rs",
+            "project/src/lib.rs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect(),
+    );
+
+    let glob = Glob::new("src/*.rs").unwrap();
+    let entries: Vec<_> = glob
+        .walk_with_filesystem(filesystem, "project", WalkBehavior::default())
+        .flatten()
+        .collect();
+    assert_eq!(entries.len(), 2);
+    assert!(entries.iter().all(|entry| !entry.file_type().is_dir()));
+    assert!(entries.iter().all(|entry| entry.metadata().is_ok()));
+}
+
+#[test]
+fn walk_with_cached_metadata() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("doc/**/*.md").unwrap();
+    for entry in glob.walk(&path).flatten() {
+        let metadata = entry.metadata().unwrap();
+        assert!(metadata.is_file());
+        assert_eq!(entry.cached_metadata().unwrap().len(), metadata.len());
+    }
+
+    // Metadata read along with directories is retained.
+    let filesystem = MemoryFilesystem::new([("project", vec!["lib.rs"])]);
+    let glob = Glob::new("*.rs").unwrap();
+    let entries: Vec<_> = glob
+        .walk_with_filesystem(filesystem, "project", WalkBehavior::default())
+        .flatten()
+        .collect();
+    assert_eq!(entries.len(), 1);
+    assert!(entries[0].cached_metadata().is_some());
+}
+
+#[test]
+fn walk_with_sort() {
+    let filesystem = MemoryFilesystem::new([
+        ("project", vec!["b.md", "src", "a.md", "doc"]),
+        ("project/doc", vec!["guide.md"]),
+        ("project/src", vec!["lib.rs", "glob.rs"]),
+    ]);
+    let walk = |behavior: WalkBehavior| -> Vec<_> {
+        Glob::new("**")
+            .unwrap()
+            .walk_with_filesystem(filesystem.clone(), "project", behavior)
+            .flatten()
+            .map(|entry| entry.into_path())
+            .collect()
+    };
+
+    let behavior = WalkBehavior::default().sort_by_key(|entry| entry.path().to_path_buf());
+    assert_eq!(
+        walk(behavior.clone()),
+        [
+            "project",
+            "project/a.md",
+            "project/b.md",
+            "project/doc",
+            "project/doc/guide.md",
+            "project/src",
+            "project/src/glob.rs",
+            "project/src/lib.rs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>(),
+    );
+    assert_eq!(
+        walk(WalkBehavior {
+            directories: DirectoryOrder::First,
+            ..behavior
+        }),
+        [
+            "project",
+            "project/doc",
+            "project/doc/guide.md",
+            "project/src",
+            "project/src/glob.rs",
+            "project/src/lib.rs",
+            "project/a.md",
+            "project/b.md",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>(),
+    );
+    // Without a comparator, directories are ordered stably.
+    let paths = walk(WalkBehavior {
+        directories: DirectoryOrder::Last,
+        ..Default::default()
+    });
+    assert_eq!(
+        paths[1..5],
+        [
+            "project/b.md",
+            "project/a.md",
+            "project/src",
+            "project/src/lib.rs",
+        ]
+        .into_iter()
+        .map(PathBuf::from)
+        .collect::<Vec<_>>(),
+    );
+}
+
+#[test]
+fn walk_with_retry() {
+    /// File system that fails to read each directory once.
+    #[derive(Clone, Debug)]
+    struct FlakyFilesystem(MemoryFilesystem, Arc<Mutex<HashSet<PathBuf>>>);
+
+    impl Filesystem for FlakyFilesystem {
+        type FileType = MemoryFileType;
+        type Metadata = MemoryFileType;
+        type ReadDir = <MemoryFilesystem as Filesystem>::ReadDir;
+
+        fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+            if self.1.lock().unwrap().insert(path.to_path_buf()) {
+                Err(io::ErrorKind::Interrupted.into())
+            }
+            else {
+                self.0.read_dir(path)
+            }
+        }
+
+        fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+            self.0.metadata(path)
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+            self.0.symlink_metadata(path)
+        }
+    }
+
+    let filesystem = || {
+        FlakyFilesystem(
+            MemoryFilesystem::new([("project", vec!["src"]), ("project/src", vec!["lib.rs"])]),
+            Default::default(),
+        )
+    };
+    let glob = Glob::new("**/*.rs").unwrap();
+    let items: Vec<_> = glob
+        .walk_with_filesystem(filesystem(), "project", WalkBehavior::default())
+        .collect();
+    assert_eq!(items.len(), 1);
+    let error = items[0].as_ref().unwrap_err();
+    assert_eq!(error.operation(), WalkOperation::ReadDirectory);
+    assert_eq!(error.io_error_kind(), Some(io::ErrorKind::Interrupted));
+    assert_eq!(error.path(), Some(Path::new("project")));
+    assert_eq!(error.depth(), 0);
+
+    let items: Vec<_> = glob
+        .walk_with_filesystem(filesystem(), "project", RetryPolicy::new(1))
+        .collect();
+    assert_eq!(items.len(), 1);
+    assert_eq!(
+        items[0].as_ref().unwrap().path(),
+        Path::new("project/src/lib.rs"),
+    );
+}
+
+#[cfg(unix)]
+#[test]
+fn walk_with_permission_filters() {
+    use std::fs::{self, File};
+    use std::os::unix::fs::{MetadataExt as _, PermissionsExt as _};
+    use wax::{UnixFileIterator as _, WalkEntry};
+
+    let root = tempfile::tempdir().unwrap();
+    for (name, mode) in [("run.sh", 0o755), ("data.sh", 0o644)] {
+        let path = root.path().join(name);
+        File::create(&path).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+    }
+    let glob = Glob::new("*.sh").unwrap();
+    let names = |entries: Vec<WalkEntry>| -> Vec<_> {
+        let mut names: Vec<_> = entries
+            .into_iter()
+            .map(|entry| entry.path().file_name().unwrap().to_owned())
+            .collect();
+        names.sort();
+        names
+    };
+
+    let entries = glob.walk(root.path()).mode_mask(0o111).flatten().collect();
+    assert_eq!(names(entries), ["run.sh"]);
+
+    let entries = glob.walk(root.path()).readable().flatten().collect();
+    assert_eq!(names(entries), ["data.sh", "run.sh"]);
+
+    let uid = fs::metadata(root.path()).unwrap().uid();
+    let entries = glob
+        .walk(root.path())
+        .owned_by(uid)
+        .mode_mask(0o111)
+        .flatten()
+        .collect();
+    assert_eq!(names(entries), ["run.sh"]);
+    assert_eq!(glob.walk(root.path()).owned_by(uid + 1).count(), 0);
+}
+
+#[test]
+fn walk_with_take_matches() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("**/*.rs").unwrap();
+    let mut walk = glob.walk(&path).take_matches(2);
+    assert!(walk.next().unwrap().is_ok());
+    assert!(walk.next().unwrap().is_ok());
+    assert!(walk.next().is_none());
+    assert!(walk.next().is_none());
+
+    assert_eq!(glob.walk(&path).take_matches(0).count(), 0);
+    assert_eq!(glob.walk(&path).take_matches(8).flatten().count(), 3);
+}
+
+#[test]
+fn walk_with_case_sensitivity() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("{readme.md,SRC/LIB.rs}").unwrap();
+    let paths: HashSet<_> = glob
+        .walk_with_behavior(&path, CaseSensitivity::Insensitive)
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("README.md"), path.join("src/lib.rs")].into()
+    );
+    assert_eq!(
+        glob.walk_with_behavior(&path, CaseSensitivity::Sensitive)
+            .flatten()
+            .count(),
+        0,
+    );
+
+    // Flags take precedence over the case sensitivity of the file system.
+    let glob = Glob::new("(?-i)readme.md").unwrap();
+    assert_eq!(
+        glob.walk_with_behavior(&path, CaseSensitivity::Insensitive)
+            .flatten()
+            .count(),
+        0,
+    );
+    let (prefix, glob) = Glob::new("(?-i:src/)L*.RS").unwrap().partition();
+    assert_eq!(
+        glob.walk_with_behavior(path.join(prefix), CaseSensitivity::Insensitive)
+            .flatten()
+            .count(),
+        1,
+    );
+    let (prefix, glob) = Glob::new("src/(?-i:L*.RS)").unwrap().partition();
+    assert_eq!(
+        glob.walk_with_behavior(path.join(prefix), CaseSensitivity::Insensitive)
+            .flatten()
+            .count(),
+        0,
+    );
+}
+
+#[test]
+fn walk_expression() {
+    let (_root, path) = temptree();
+
+    let paths: HashSet<_> = wax::walk_in(&path, "src/*.rs")
+        .unwrap()
+        .flatten()
+        .map(|entry| entry.into_path())
+        .collect();
+    assert_eq!(
+        paths,
+        [path.join("src/glob.rs"), path.join("src/lib.rs")].into()
+    );
+    assert!(wax::walk_in(&path, "src/**/{a,b").is_err());
+
+    // Paths begin with the invariant prefix of the expression.
+    assert!(wax::walk("src/walk/*.rs")
+        .unwrap()
+        .flatten()
+        .any(|entry| entry.path() == Path::new("src/walk/mod.rs")));
+}
+
+#[test]
+fn walk_and_collect_paths() {
+    let (_root, path) = temptree();
+
+    let glob = Glob::new("**/*.rs").unwrap();
+    let paths: HashSet<_> = glob
+        .walk(&path)
+        .collect_paths()
+        .unwrap()
+        .into_iter()
+        .collect();
+    assert_eq!(
+        paths,
+        [
+            path.join("src/glob.rs"),
+            path.join("src/lib.rs"),
+            path.join("tests/walk.rs"),
+        ]
+        .into(),
+    );
+
+    let errors = glob.walk(path.join("missing")).collect_paths().unwrap_err();
+    assert_eq!(errors.errors().len(), 1);
+    assert_eq!(
+        errors.errors()[0].path(),
+        Some(path.join("missing").as_path())
+    );
+    assert_eq!(errors.errors()[0].operation(), WalkOperation::ReadMetadata);
+    assert_eq!(
+        errors.errors()[0].io_error_kind(),
+        Some(io::ErrorKind::NotFound),
+    );
+
+    let (paths, errors) = glob.walk(path.join("missing")).collect_paths_lossy();
+    assert!(paths.is_empty());
+    assert_eq!(errors.len(), 1);
+}
+
+#[test]
+fn walk_with_prune() {
+    let (_root, path) = temptree();
+
+    let pruned = Arc::new(Mutex::new(HashSet::new()));
+    let behavior = |depth| {
+        let path = path.clone();
+        let pruned = pruned.clone();
+        WalkBehavior {
+            depth,
+            ..Default::default()
+        }
+        .on_prune(move |tree| {
+            assert_eq!(tree.depth(), 1);
+            pruned.lock().unwrap().insert((
+                tree.path().strip_prefix(&path).unwrap().to_path_buf(),
+                tree.cause(),
+            ));
+        })
+    };
+    let take = || -> HashSet<_> { pruned.lock().unwrap().drain().collect() };
+    let set = |trees: &[(&str, PruneCause)]| -> HashSet<_> {
+        trees
+            .iter()
+            .map(|(path, cause)| (PathBuf::from(path), *cause))
+            .collect()
+    };
+
+    let glob = Glob::new("{doc,src}/*").unwrap();
+    assert_eq!(
+        glob.walk_with_behavior(&path, behavior(usize::MAX)).count(),
+        3,
+    );
+    assert_eq!(take(), set(&[("tests", PruneCause::Mismatch)]));
+
+    // Alternatives with component boundaries are expanded into their branches,
+    // so directories that cannot match any branch are pruned.
+    let glob = Glob::new("{doc/*.md,src/**/*.rs}").unwrap();
+    assert_eq!(
+        glob.walk_with_behavior(&path, behavior(usize::MAX)).count(),
+        3,
+    );
+    assert_eq!(take(), set(&[("tests", PruneCause::Mismatch)]));
+
+    let glob = Glob::new("**/*.rs").unwrap();
+    assert_eq!(glob.walk_with_behavior(&path, behavior(1)).count(), 0);
+    assert_eq!(
+        take(),
+        set(&[
+            ("doc", PruneCause::Depth),
+            ("src", PruneCause::Depth),
+            ("tests", PruneCause::Depth),
+        ]),
+    );
+
+    // Adaptors only receive matching entries, so directories must match the
+    // glob to be pruned by adaptors.
+    let glob = Glob::new("**").unwrap();
+    assert_eq!(
+        glob.walk_with_behavior(&path, behavior(usize::MAX))
+            .not(["src/**"])
+            .unwrap()
+            .filter_tree(|entry| {
+                (entry.path().file_name() == Some("tests".as_ref())).then(|| FilterTarget::Tree)
+            })
+            .count(),
+        4,
+    );
+    assert_eq!(
+        take(),
+        set(&[("src", PruneCause::Negation), ("tests", PruneCause::Filter)]),
+    );
+}
+
+#[test]
+fn walk_anchored_glob() {
+    let (_root, path) = temptree();
+    let paths = |glob: &str, directory: &Path| -> HashSet<PathBuf> {
+        Glob::new(glob)
+            .unwrap()
+            .anchored_at(directory)
+            .walk()
+            .flatten()
+            .map(|entry| entry.into_path())
+            .collect()
+    };
+
+    assert_eq!(
+        paths("**/*.rs", &path.join("src")),
+        [path.join("src/glob.rs"), path.join("src/lib.rs")].into(),
+    );
+    // Traversals that begin outside of the directory read no files.
+    assert!(paths("../**/*.md", &path.join("src")).is_empty());
+
+    // Rooted globs are matched against complete paths.
+    let glob = format!("{}/**/*.md", wax::escape(path.to_str().unwrap()));
+    assert_eq!(
+        paths(&glob, &path.join("doc")),
+        [path.join("doc/guide.md")].into(),
+    );
+    assert_eq!(
+        paths(&glob, &path),
+        [path.join("doc/guide.md"), path.join("README.md")].into(),
+    );
+}
+
+/// Writes a testing directory tree with symbolic links that resolve inside and
+/// outside of the tree to a temporary location on the file system.
+#[cfg(unix)]
+fn temptree_with_escaping_links() -> (TempDir, PathBuf) {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    let (root, path) = temptree();
+    fs::create_dir(root.path().join("outside")).unwrap();
+    fs::write(root.path().join("outside/secret.txt"), b"").unwrap();
+    symlink("lib.rs", path.join("src/link.rs")).unwrap();
+    symlink("../../outside/secret.txt", path.join("src/secret.rs")).unwrap();
+    symlink("../outside", path.join("outside")).unwrap();
+    (root, path)
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_with_confinement() {
+    let (_root, path) = temptree_with_escaping_links();
+
+    let walk = |glob: &str, link: LinkBehavior| {
+        let (entries, errors): (Vec<_>, Vec<_>) = Glob::new(glob)
+            .unwrap()
+            .walk_with_behavior(
+                &path,
+                WalkBehavior {
+                    link,
+                    confinement: Confinement::Confined,
+                    ..Default::default()
+                },
+            )
+            .partition(Result::is_ok);
+        let paths: HashSet<_> = entries
+            .into_iter()
+            .map(|entry| entry.unwrap().into_path())
+            .collect();
+        let errors: HashSet<_> = errors
+            .into_iter()
+            .map(Result::unwrap_err)
+            .inspect(|error| {
+                assert!(error.is_escape());
+                assert_eq!(error.operation(), WalkOperation::ResolveLink);
+                assert!(error.io_error_kind().is_none());
+            })
+            .map(|error| error.path().unwrap().to_path_buf())
+            .collect();
+        (paths, errors)
+    };
+
+    for link in [LinkBehavior::ReadFile, LinkBehavior::ReadTarget] {
+        let (paths, errors) = walk("**/*.{rs,txt}", link);
+        assert_eq!(
+            paths,
+            [
+                path.join("src/glob.rs"),
+                path.join("src/lib.rs"),
+                path.join("src/link.rs"),
+                path.join("tests/walk.rs"),
+            ]
+            .into(),
+        );
+        assert_eq!(
+            errors,
+            [path.join("outside"), path.join("src/secret.rs")].into(),
+        );
+    }
+    // Traversals that begin outside of the directory yield a single error.
+    let (paths, errors) = walk("../outside/*.txt", LinkBehavior::ReadFile);
+    assert!(paths.is_empty());
+    assert_eq!(errors, [path.join("../outside")].into());
+    let (paths, errors) = walk("outside/*.txt", LinkBehavior::ReadTarget);
+    assert!(paths.is_empty());
+    assert_eq!(errors, [path.join("outside")].into());
+
+    // Unconfined traversals follow links outside of the directory.
+    assert_eq!(
+        Glob::new("outside/*.txt")
+            .unwrap()
+            .walk(&path)
+            .flatten()
+            .map(|entry| entry.into_path())
+            .collect::<Vec<_>>(),
+        [path.join("outside/secret.txt")],
+    );
+}
+
+#[test]
+#[cfg(unix)]
+fn walk_with_confinement_and_replaced_directory() {
+    use std::fs;
+    use std::os::unix::fs::symlink;
+
+    let (_root, path) = temptree_with_escaping_links();
+
+    // Replace a directory with a symbolic link after it is yielded but before
+    // it is read.
+    let glob = Glob::new("**").unwrap();
+    let mut paths = vec![];
+    let mut errors = vec![];
+    for entry in glob.walk_with_behavior(&path, Confinement::Confined) {
+        match entry {
+            Ok(entry) => {
+                if entry.path() == path.join("doc") {
+                    fs::rename(path.join("doc"), path.join("doc.bak")).unwrap();
+                    symlink("../outside", path.join("doc")).unwrap();
+                }
+                paths.push(entry.into_path());
+            },
+            Err(error) => errors.push(error.path().unwrap().to_path_buf()),
+        }
+    }
+    assert!(paths.iter().all(|path| !path.ends_with("secret.txt")));
+    assert!(errors.contains(&path.join("doc")));
+}
+
+#[test]
+fn walk_incremental() {
+    use std::collections::BTreeMap;
+    use std::time::{Duration, SystemTime};
+    use wax::{Change, ChangeCache, IncrementalWalk};
+
+    #[derive(Clone, Copy, Debug)]
+    struct StampedMetadata {
+        is_dir: bool,
+        modified: SystemTime,
+        size: u64,
+    }
+
+    impl FileType for StampedMetadata {
+        fn is_dir(&self) -> bool {
+            self.is_dir
+        }
+
+        fn is_symlink(&self) -> bool {
+            false
+        }
+    }
+
+    impl Metadata for StampedMetadata {
+        type FileType = Self;
+
+        fn file_type(&self) -> Self::FileType {
+            *self
+        }
+
+        fn size(&self) -> Option<u64> {
+            Some(self.size)
+        }
+
+        fn modified(&self) -> Option<SystemTime> {
+            Some(self.modified)
+        }
+    }
+
+    /// In-memory file system with modification times that records the
+    /// directories that it reads.
+    #[derive(Clone, Debug, Default)]
+    struct StampedFilesystem {
+        files: Arc<Mutex<BTreeMap<PathBuf, StampedMetadata>>>,
+        reads: Arc<Mutex<Vec<PathBuf>>>,
+        clock: Arc<Mutex<u64>>,
+    }
+
+    impl StampedFilesystem {
+        // Modification times are long before any traversal, so they are never
+        // too recent to compare.
+        fn tick(&self) -> SystemTime {
+            let mut clock = self.clock.lock().unwrap();
+            *clock += 1;
+            SystemTime::UNIX_EPOCH + Duration::from_secs(*clock)
+        }
+
+        fn touch(&self, files: &mut BTreeMap<PathBuf, StampedMetadata>, path: &Path) {
+            let modified = self.tick();
+            files.insert(
+                path.to_path_buf(),
+                StampedMetadata {
+                    is_dir: true,
+                    modified,
+                    size: 0,
+                },
+            );
+        }
+
+        fn write(&self, path: &str, size: u64) {
+            let path = Path::new(path);
+            let mut files = self.files.lock().unwrap();
+            for ancestor in path.ancestors().skip(1) {
+                if !ancestor.as_os_str().is_empty() && !files.contains_key(ancestor) {
+                    self.touch(&mut files, ancestor);
+                    if let Some(parent) = ancestor.parent() {
+                        self.touch(&mut files, parent);
+                    }
+                }
+            }
+            let is_new = !files.contains_key(path);
+            let modified = self.tick();
+            files.insert(
+                path.to_path_buf(),
+                StampedMetadata {
+                    is_dir: false,
+                    modified,
+                    size,
+                },
+            );
+            if is_new {
+                self.touch(&mut files, path.parent().unwrap());
+            }
+        }
+
+        fn remove(&self, path: &str) {
+            let path = Path::new(path);
+            let mut files = self.files.lock().unwrap();
+            files.remove(path).unwrap();
+            self.touch(&mut files, path.parent().unwrap());
+        }
+
+        fn take_reads(&self) -> Vec<PathBuf> {
+            self.reads.lock().unwrap().drain(..).collect()
+        }
+    }
+
+    impl Filesystem for StampedFilesystem {
+        type FileType = StampedMetadata;
+        type Metadata = StampedMetadata;
+        type ReadDir =
+            vec::IntoIter<io::Result<(PathBuf, StampedMetadata, Option<StampedMetadata>)>>;
+
+        fn read_dir(&self, path: &Path) -> io::Result<Self::ReadDir> {
+            self.reads.lock().unwrap().push(path.to_path_buf());
+            let files = self.files.lock().unwrap();
+            Ok(files
+                .iter()
+                .filter(|(file, _)| file.parent() == Some(path))
+                .map(|(file, metadata)| Ok((file.clone(), *metadata, Some(*metadata))))
+                .collect::<Vec<_>>()
+                .into_iter())
+        }
+
+        fn metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+            let files = self.files.lock().unwrap();
+            files.get(path).copied().ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+
+        fn symlink_metadata(&self, path: &Path) -> io::Result<Self::Metadata> {
+            self.metadata(path)
+        }
+    }
+
+    fn changes<I>(changes: I) -> Vec<(&'static str, PathBuf)>
+    where
+        I: Iterator<Item = Result<Change<StampedFilesystem>, wax::WalkError>>,
+    {
+        changes
+            .map(|change| {
+                let change = change.unwrap();
+                let kind = match change {
+                    Change::Added(_) => "added",
+                    Change::Modified(_) => "modified",
+                    Change::Removed(_) => "removed",
+                    _ => unreachable!(),
+                };
+                (kind, change.path().to_path_buf())
+            })
+            .collect()
+    }
+
+    let filesystem = StampedFilesystem::default();
+    filesystem.write("project/README.md", 1);
+    filesystem.write("project/doc/guide.md", 1);
+    filesystem.write("project/src/glob.rs", 1);
+    filesystem.write("project/src/lib.rs", 1);
+
+    let glob = Glob::new("**/*.rs").unwrap();
+    let walk = |cache| -> IncrementalWalk<'_, StampedFilesystem> {
+        glob.walk_with_filesystem(filesystem.clone(), "project", ())
+            .incremental(cache)
+    };
+
+    // All matched files are added to an empty cache.
+    let mut incremental = walk(ChangeCache::new());
+    assert_eq!(
+        changes(&mut incremental),
+        [
+            ("added", PathBuf::from("project/src/glob.rs")),
+            ("added", PathBuf::from("project/src/lib.rs")),
+        ],
+    );
+    let cache = incremental.into_cache();
+    assert_eq!(cache.len(), 2);
+
+    // Caches can be written and read.
+    let mut bytes = vec![];
+    cache.write_to(&mut bytes).unwrap();
+    assert_eq!(ChangeCache::read_from(bytes.as_slice()).unwrap(), cache);
+    assert_eq!(
+        ChangeCache::read_from(&b"not a change cache"[..])
+            .unwrap_err()
+            .kind(),
+        io::ErrorKind::InvalidData,
+    );
+
+    // Unchanged directories are not read.
+    filesystem.take_reads();
+    let mut incremental = walk(cache);
+    assert!(changes(&mut incremental).is_empty());
+    assert!(filesystem.take_reads().is_empty());
+    let cache = incremental.into_cache();
+
+    filesystem.write("project/src/lib.rs", 2);
+    filesystem.write("project/src/token/parse.rs", 1);
+    filesystem.remove("project/src/glob.rs");
+
+    // Changes that are not yielded are retained in the cache.
+    let mut incremental = walk(cache);
+    assert_eq!(
+        incremental.next().unwrap().unwrap().path(),
+        Path::new("project/src/lib.rs"),
+    );
+    let cache = incremental.into_cache();
+
+    let mut incremental = walk(cache);
+    assert_eq!(
+        changes(&mut incremental),
+        [
+            ("added", PathBuf::from("project/src/token/parse.rs")),
+            ("removed", PathBuf::from("project/src/glob.rs")),
+        ],
+    );
+    // Directories that are not completely read before the cache is retrieved
+    // are read again.
+    assert_eq!(
+        filesystem.take_reads(),
+        [
+            PathBuf::from("project/src"),
+            PathBuf::from("project"),
+            PathBuf::from("project/src"),
+            PathBuf::from("project/src/token"),
+        ],
+    );
+    let cache = incremental.into_cache();
+    assert_eq!(
+        cache.paths().collect::<Vec<_>>(),
+        [
+            Path::new("project/src/lib.rs"),
+            Path::new("project/src/token/parse.rs"),
+        ],
+    );
+
+    // Caches recorded by other globs are ignored.
+    let glob = Glob::new("**/*.md").unwrap();
+    let mut incremental = glob
+        .walk_with_filesystem(filesystem.clone(), "project", ())
+        .incremental(cache);
+    assert_eq!(changes(&mut incremental).len(), 2);
+}